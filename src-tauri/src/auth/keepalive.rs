@@ -0,0 +1,120 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::netgrab;
+use crate::session;
+use crate::settings::Settings;
+
+/// Bumped whenever the keep-alive task is started or stopped; a running loop exits as soon
+/// as it sees a generation other than its own, so at most one task is ever active.
+static KEEPALIVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a heartbeat response body means SEQTA no longer accepts the session.
+fn is_expired_response(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("status").and_then(|s| s.as_str()).map(|s| s.to_string()))
+        .map_or(false, |status| status == "401" || status == "failed")
+}
+
+async fn send_heartbeat() -> Result<String, netgrab::NetError> {
+    let headers = HashMap::from([(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    )]);
+    netgrab::request_api_data(
+        "/seqta/student/heartbeat",
+        netgrab::RequestMethod::POST,
+        Some(headers),
+        Some(json!({ "heartbeat": true })),
+        None,
+        false,
+        false,
+        None,
+    )
+    .await
+}
+
+/// Start (or restart) the keep-alive loop using the interval from settings.
+pub fn start(app: AppHandle) {
+    let generation = KEEPALIVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let interval_mins = Settings::load().session_keepalive_interval_mins.max(1);
+
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_secs(interval_mins * 60);
+        loop {
+            tokio::time::sleep(interval).await;
+            if KEEPALIVE_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if !session::Session::exists() {
+                println!("[KEEPALIVE] No session, stopping");
+                return;
+            }
+
+            match send_heartbeat().await {
+                Ok(body) if is_expired_response(&body) => {
+                    println!("[KEEPALIVE] Heartbeat rejected, session expired");
+                    // Only report expiry if nobody restarted/stopped us mid-request
+                    if KEEPALIVE_GENERATION.load(Ordering::SeqCst) == generation {
+                        let _ = app.emit("session-expired", ());
+                    }
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) if e.is_unauthorized() => {
+                    println!("[KEEPALIVE] Session rejected: {}", e);
+                    if KEEPALIVE_GENERATION.load(Ordering::SeqCst) == generation {
+                        let _ = app.emit("session-expired", ());
+                    }
+                    return;
+                }
+                // Network trouble isn't expiry; try again next interval
+                Err(e) => println!("[KEEPALIVE] Heartbeat failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Stop the keep-alive loop, if one is running.
+pub fn stop() {
+    KEEPALIVE_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Start the loop at launch if it's enabled and there's a session to keep alive.
+pub fn start_if_enabled(app: AppHandle) {
+    if Settings::load().session_keepalive_enabled && session::Session::exists() {
+        start(app);
+    }
+}
+
+/// Turn the session keep-alive on or off and persist the choice.
+#[tauri::command]
+pub fn set_keepalive_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = Settings::load();
+    settings.session_keepalive_enabled = enabled;
+    settings.save().map_err(|e| e.to_string())?;
+
+    if enabled {
+        start(app);
+    } else {
+        stop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_expired_response() {
+        assert!(is_expired_response(r#"{"status":"401"}"#));
+        assert!(is_expired_response(r#"{"status":"failed"}"#));
+        assert!(!is_expired_response(r#"{"status":"200","payload":{}}"#));
+        assert!(!is_expired_response("not json"));
+    }
+}
@@ -6,10 +6,13 @@ use tauri::{Emitter, Manager};
 use time::OffsetDateTime;
 use url::Url;
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use reqwest::cookie::Jar;
 
+use crate::keepalive;
 use crate::netgrab;
 use crate::session;
 use crate::profiles;
@@ -48,9 +51,33 @@ struct SeqtaJWT {
     exp: i64, // Expiration timestamp
 }
 
+/// Upper bound on the subjects check so a stalled SEQTA server can't hang a login flow.
+const SESSION_VALIDATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Repeat `desqta://auth` links carrying the same cookie inside this window are ignored.
+const AUTH_DEEPLINK_DEDUP_WINDOW: Duration = Duration::from_secs(5);
+
+/// Last `desqta://auth` cookie processed and when, for de-duplicating rapid repeat links.
+static LAST_AUTH_DEEPLINK: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/// Set by `cancel_login` to tell the browser login poller to stop.
+static LOGIN_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Window id of the browser login window currently being polled.
+static ACTIVE_LOGIN_WINDOW: Mutex<Option<String>> = Mutex::new(None);
+
+/// How often `login-waiting` is re-emitted while the login window is polled.
+const LOGIN_WAITING_EMIT_INTERVAL_SECS: u64 = 10;
+
 #[tauri::command]
 pub fn force_reload(app: tauri::AppHandle) {
+    // Reloads follow a fresh login, so (re)start the keep-alive and inbox checks for the new
+    // session, look the student up again and warm the caches the first pages need
+    keepalive::start_if_enabled(app.clone());
+    crate::message_poller::start_if_enabled(app.clone());
+    crate::student_info::clear_cache();
     app.emit("reload", "hi".to_string()).unwrap();
+    crate::caches::prewarm_in_background();
 }
 
 /// True if a saved login session exists.
@@ -59,22 +86,166 @@ pub fn check_session_exists() -> bool {
     session::Session::exists()
 }
 
-/// Persist the SEQTA `base_url` and `JSESSIONID`.
+/// Persist the SEQTA `base_url` and `JSESSIONID`, with the path SEQTA is served under when
+/// it isn't `/seqta`.
 #[tauri::command]
-pub fn save_session(base_url: String, jsessionid: String) -> Result<(), String> {
+pub fn save_session(
+    base_url: String,
+    jsessionid: String,
+    seqta_prefix: Option<String>,
+) -> Result<(), String> {
+    let custom_headers = session::Session::custom_headers_for(&base_url);
     session::Session {
         base_url,
         jsessionid,
         additional_cookies: Vec::new(),
         stored_username: None,
         stored_password: None,
+        custom_headers,
+        seqta_prefix,
     }
     .save()
     .map_err(|e| e.to_string())
 }
 
+/// Work out which path SEQTA is served under at `base_url` by probing the login endpoint,
+/// which answers with JSON even without a session. Returns `None` for the default `/seqta`,
+/// and also when nothing answers so the login itself reports the problem.
+pub async fn detect_seqta_prefix(base_url: &str) -> Option<String> {
+    let client = netgrab::create_client_builder()
+        .default_headers(netgrab::custom_header_map(base_url))
+        .timeout(SESSION_VALIDATION_TIMEOUT)
+        .build()
+        .ok()?;
+
+    for prefix in [session::DEFAULT_SEQTA_PREFIX, ""] {
+        let probe_url = session::seqta_url(base_url, Some(prefix), "/seqta/student/login");
+        let Ok(response) = client
+            .post(&probe_url)
+            .header("Content-Type", "application/json; charset=utf-8")
+            .json(&json!({}))
+            .send()
+            .await
+        else {
+            continue;
+        };
+        let is_seqta = response
+            .json::<serde_json::Value>()
+            .await
+            .is_ok_and(|body| body.is_object());
+        if is_seqta {
+            println!("[AUTH] SEQTA found at {}", probe_url);
+            return (prefix != session::DEFAULT_SEQTA_PREFIX).then(|| prefix.to_string());
+        }
+    }
+    None
+}
+
+/// Check that a JSESSIONID is live by loading the student's subjects.
+/// SEQTA can answer 200 with `{"status": "failed"}` for a dead session, so the body is checked too.
+pub async fn validate_session(
+    base_url: &str,
+    seqta_prefix: Option<&str>,
+    jsessionid: &str,
+) -> bool {
+    let client = match netgrab::create_client_builder()
+        .default_headers(netgrab::custom_header_map(base_url))
+        .cookie_store(true)
+        .timeout(SESSION_VALIDATION_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+
+    let subjects_url = session::seqta_url(base_url, seqta_prefix, "/seqta/student/load/subjects");
+    let check_res = client
+        .post(&subjects_url)
+        .header("Cookie", format!("JSESSIONID={}", jsessionid))
+        .header("Content-Type", "application/json; charset=utf-8")
+        .json(&json!({}))
+        .send()
+        .await;
+
+    match check_res {
+        Ok(res) if res.status().is_success() => match res.json::<serde_json::Value>().await {
+            Ok(json) => {
+                let status_str = json.get("status").and_then(|s| s.as_str());
+                status_str != Some("failed") && status_str != Some("401")
+            }
+            Err(_) => false, // Failed to parse JSON
+        },
+        _ => false,
+    }
+}
+
+/// True if the same cookie was already handled by a `desqta://auth` link moments ago.
+/// Records `cookie` as the latest one either way.
+fn is_duplicate_auth_deeplink(cookie: &str) -> bool {
+    let mut last = match LAST_AUTH_DEEPLINK.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let now = Instant::now();
+    let duplicate = matches!(
+        last.as_ref(),
+        Some((prev, at)) if prev == cookie && now.duration_since(*at) < AUTH_DEEPLINK_DEDUP_WINDOW
+    );
+    *last = Some((cookie.to_string(), now));
+    duplicate
+}
+
+/// Validate and persist a session received through a `desqta://auth?cookie=&url=` deep link.
+/// Emits `login-error` with a user-facing message if the session is dead or expired.
+pub async fn handle_auth_deeplink(app: tauri::AppHandle, base_url: String, cookie: String) {
+    if is_duplicate_auth_deeplink(&cookie) {
+        println!("[AUTH] Ignoring repeated auth deep link");
+        return;
+    }
+
+    let seqta_prefix = detect_seqta_prefix(&base_url).await;
+    if !validate_session(&base_url, seqta_prefix.as_deref(), &cookie).await {
+        eprintln!("[AUTH] Deep link session failed validation");
+        let _ = app.emit(
+            "login-error",
+            "This login link has expired or the session is no longer valid. Please sign in again.",
+        );
+        return;
+    }
+
+    match save_session(base_url, cookie, seqta_prefix) {
+        Ok(_) => {
+            println!("[AUTH] Saved validated session from deep link");
+            force_reload(app);
+        }
+        Err(e) => {
+            eprintln!("[AUTH] Failed to save session from deep link: {}", e);
+            let _ = app.emit("login-error", format!("Failed to save session: {}", e));
+        }
+    }
+}
+
+/// Stop waiting for a browser login and close its window.
+#[tauri::command]
+pub fn cancel_login(app: tauri::AppHandle) {
+    LOGIN_CANCELLED.store(true, Ordering::SeqCst);
+
+    let window_id = match ACTIVE_LOGIN_WINDOW.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    };
+    if let Some(window_id) = window_id {
+        if let Some(window) = app.get_webview_window(&window_id) {
+            let _ = window.destroy();
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn logout(app: tauri::AppHandle) -> bool {
+    keepalive::stop();
+    crate::message_poller::stop();
+
     // Clear webview data first (cache, cookies, etc.)
     if let Err(e) = clear_webview_data(app).await {
         println!(
@@ -267,16 +438,22 @@ async fn perform_desqta_connect_auth(
 ) -> Result<session::Session, String> {
     let base_url = payload.u;
     let jsessionid = payload.s;
+    let seqta_prefix = detect_seqta_prefix(&base_url).await;
 
     use crate::netgrab;
 
     let client = netgrab::create_client_builder()
+        .default_headers(netgrab::custom_header_map(&base_url))
         .cookie_store(true)
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
     // Validate session with heartbeat
-    let heartbeat_url = format!("{}/seqta/student/heartbeat", base_url);
+    let heartbeat_url = session::seqta_url(
+        &base_url,
+        seqta_prefix.as_deref(),
+        "/seqta/student/heartbeat",
+    );
     let heartbeat_body = json!({ "heartbeat": true });
 
     let heartbeat_response = client
@@ -292,12 +469,15 @@ async fn perform_desqta_connect_auth(
         return Err("This DesQTA connect link has expired or the session is no longer valid. Please generate a new QR code from BetterSEQTA+ (Settings → Connect Mobile App) and try again.".to_string());
     }
 
+    let custom_headers = session::Session::custom_headers_for(&base_url);
     let session = session::Session {
         base_url,
         jsessionid,
         additional_cookies: Vec::new(),
         stored_username: None,
         stored_password: None,
+        custom_headers,
+        seqta_prefix,
     };
 
     Ok(session)
@@ -337,11 +517,16 @@ fn decode_jwt(token: &str) -> Result<SeqtaJWT, String> {
 }
 
 /// Fetch user info from SEQTA API
-async fn fetch_user_info(base_url: &str, jsessionid: &str) -> Result<UserInfoPayload, String> {
+async fn fetch_user_info(
+    base_url: &str,
+    seqta_prefix: Option<&str>,
+    jsessionid: &str,
+) -> Result<UserInfoPayload, String> {
     use crate::netgrab;
     
-    let login_url = format!("{}/seqta/student/login", base_url);
+    let login_url = session::seqta_url(base_url, seqta_prefix, "/seqta/student/login");
     let client = netgrab::create_client_builder()
+        .default_headers(netgrab::custom_header_map(base_url))
         .cookie_store(true)
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
@@ -386,6 +571,7 @@ fn validate_token(token: &str) -> Result<bool, String> {
 async fn perform_qr_auth(sso_payload: SeqtaSSOPayload) -> Result<session::Session, String> {
     let base_url = sso_payload.u;
     let token = sso_payload.t;
+    let seqta_prefix = detect_seqta_prefix(&base_url).await;
 
     let jar = Arc::new(Jar::default());
     jar.add_cookie_str(
@@ -412,7 +598,8 @@ async fn perform_qr_auth(sso_payload: SeqtaSSOPayload) -> Result<session::Sessio
     );
 
     use crate::netgrab;
-    
+
+    headers.extend(netgrab::custom_header_map(&base_url));
     let client = netgrab::create_client_builder()
         .cookie_provider(jar.clone())
         .cookie_store(true)
@@ -421,7 +608,8 @@ async fn perform_qr_auth(sso_payload: SeqtaSSOPayload) -> Result<session::Sessio
         .unwrap();
 
     // Step 1: First login request (empty body)
-    let first_login_url = format!("{}/seqta/student/login", base_url);
+    let first_login_url =
+        session::seqta_url(&base_url, seqta_prefix.as_deref(), "/seqta/student/login");
 
     let first_login_body = json!({
         "token": &token
@@ -474,7 +662,11 @@ async fn perform_qr_auth(sso_payload: SeqtaSSOPayload) -> Result<session::Sessio
         });
 
     // Step 4: Send a heartbeat - Defib. Check if the JSESSIONID/JWT is valid
-    let heartbeat_url = format!("{}/seqta/student/heartbeat", base_url);
+    let heartbeat_url = session::seqta_url(
+        &base_url,
+        seqta_prefix.as_deref(),
+        "/seqta/student/heartbeat",
+    );
 
     let heartbeat_body = json!({
         "heartbeat": true
@@ -492,12 +684,15 @@ async fn perform_qr_auth(sso_payload: SeqtaSSOPayload) -> Result<session::Sessio
     }
 
     // Create session with the newly obtained JSESSIONID as the token
+    let custom_headers = session::Session::custom_headers_for(&base_url);
     let session = session::Session {
         base_url,
         jsessionid: jsessionid.ok_or("Could not get JSESSIONID from response headers")?,
         additional_cookies: vec![], // No additional cookies given by QR auth (same as SSO and normal login now)
         stored_username: None, // QR auth doesn't store credentials
         stored_password: None,
+        custom_headers,
+        seqta_prefix,
     };
 
     Ok(session)
@@ -518,7 +713,11 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
         let session = perform_qr_auth(sso_payload).await?;
 
         // Fetch user info to create/get profile
-        let user_info = fetch_user_info(&session.base_url, &session.jsessionid).await?;
+        let user_info = fetch_user_info(
+            &session.base_url,
+            session.seqta_prefix.as_deref(),
+            &session.jsessionid,
+        ).await?;
         
         // Create or get profile
         let profile = profiles::ProfileManager::get_or_create_profile(
@@ -547,7 +746,11 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
         let session = perform_desqta_connect_auth(payload).await?;
 
         // Fetch user info to create/get profile
-        let user_info = fetch_user_info(&session.base_url, &session.jsessionid).await?;
+        let user_info = fetch_user_info(
+            &session.base_url,
+            session.seqta_prefix.as_deref(),
+            &session.jsessionid,
+        ).await?;
 
         // Create or get profile
         let profile = profiles::ProfileManager::get_or_create_profile(
@@ -577,7 +780,7 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
     // For regular URL-based login, handle differently for desktop vs mobile
     #[cfg(desktop)]
     {
-        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::atomic::AtomicU64;
         use tauri::{WebviewUrl, WebviewWindowBuilder};
         use tokio::time::{sleep, Duration};
 
@@ -627,6 +830,13 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
             .navigate(url_clone)
             .map_err(|e| format!("Failed to navigate: {}", e))?;
 
+        // Register this window as the one `cancel_login` should stop
+        LOGIN_CANCELLED.store(false, Ordering::SeqCst);
+        if let Ok(mut active) = ACTIVE_LOGIN_WINDOW.lock() {
+            *active = Some(window_id.clone());
+        }
+        let timeout_secs = crate::settings::Settings::load().login_timeout_secs.max(1);
+
         // Clone handles for async block
         let app_handle_clone = app.clone();
         let window_id_clone = window_id.clone();
@@ -639,13 +849,38 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
                 if let Some(window) = app_handle_clone.get_webview_window(&window_id_clone) {
                     let _ = window.destroy(); // Use destroy() instead of close() for complete cleanup
                 }
+                if let Ok(mut active) = ACTIVE_LOGIN_WINDOW.lock() {
+                    if active.as_deref() == Some(window_id_clone.as_str()) {
+                        *active = None;
+                    }
+                }
             };
 
-            for _ in 0..1920 {
-                // Poll for 1920 seconds max
+            let seqta_prefix = detect_seqta_prefix(&http_url).await;
+
+            // Measured from now rather than counted in polls, since validating a session can
+            // take a while
+            let started = Instant::now();
+            let deadline = started + Duration::from_secs(timeout_secs);
+            let mut next_waiting_emit = started;
+            while Instant::now() < deadline {
+                if Instant::now() >= next_waiting_emit {
+                    let elapsed = started.elapsed().as_secs();
+                    let _ = app_handle_clone.emit(
+                        "login-waiting",
+                        json!({ "elapsed_secs": elapsed, "timeout_secs": timeout_secs }),
+                    );
+                    next_waiting_emit += Duration::from_secs(LOGIN_WAITING_EMIT_INTERVAL_SECS);
+                }
+
                 // Wait 1 second between polls
                 sleep(Duration::from_secs(1)).await;
 
+                if LOGIN_CANCELLED.load(Ordering::SeqCst) {
+                    destroy_login_window();
+                    return;
+                }
+
                 // Try to get cookies from the login window
                 if let Some(webview) = app_handle_clone.get_webview_window(&window_id_clone) {
                     if counter > 5 {
@@ -665,47 +900,13 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
 
                                                 // Validate the session with a subjects request before accepting it
                                                 // This prevents capturing invalid/pre-login sessions
-                                                use crate::netgrab;
-                                                
-                                                let subjects_url = format!("{}/seqta/student/load/subjects", base_url);
-                                                let client = netgrab::create_client_builder()
-                                                    .cookie_store(true)
-                                                    .build()
-                                                    .unwrap_or_default();
-
-                                                // Manually construct the cookie header since we're not using a jar here for this quick check
-                                                let cookie_header = format!("JSESSIONID={}", value);
-                                                
-                                                let check_res = client
-                                                    .post(&subjects_url)
-                                                    .header("Cookie", cookie_header)
-                                                    .header("Content-Type", "application/json; charset=utf-8")
-                                                    .json(&serde_json::json!({}))
-                                                    .send()
-                                                    .await;
-
-                                                // Check for both HTTP success and API payload success
-                                                let is_valid_session = match check_res {
-                                                    Ok(res) => {
-                                                        if res.status().is_success() {
-                                                            // Check the body for application-level errors (like status: "failed")
-                                                            match res.json::<serde_json::Value>().await {
-                                                                Ok(json) => {
-                                                                    // SEQTA APIs might return 200 OK but with { "status": "failed" } or similar
-                                                                    // Only accept if payload exists or status is not explicitly failed/401
-                                                                    let status_str = json.get("status").and_then(|s| s.as_str());
-                                                                    status_str != Some("failed") && status_str != Some("401")
-                                                                },
-                                                                Err(_) => false // Failed to parse JSON
-                                                            }
-                                                        } else {
-                                                            false
-                                                        }
-                                                    },
-                                                    Err(_) => false,
-                                                };
-
-                                                if !is_valid_session {
+                                                if !validate_session(
+                                                    &base_url,
+                                                    seqta_prefix.as_deref(),
+                                                    &value,
+                                                )
+                                                .await
+                                                {
                                                     // Session exists but is not valid (e.g. pre-login or expired)
                                                     // Continue polling...
                                                     continue;
@@ -746,10 +947,18 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
                                                     additional_cookies,
                                                     stored_username: None, // Browser login doesn't store credentials
                                                     stored_password: None,
+                                                    custom_headers: session::Session::custom_headers_for(&base_url),
+                                                    seqta_prefix: seqta_prefix.clone(),
                                                 };
 
                                                 // Fetch user info to create/get profile
-                                                match fetch_user_info(&base_url, &value).await {
+                                                match fetch_user_info(
+                                                    &base_url,
+                                                    seqta_prefix.as_deref(),
+                                                    &value,
+                                                )
+                                                .await
+                                                {
                                                     Ok(user_info) => {
                                                         // Create or get profile
                                                         if let Ok(profile) = profiles::ProfileManager::get_or_create_profile(
@@ -771,6 +980,7 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
 
                                                 // Properly destroy the window to ensure complete cleanup
                                                 destroy_login_window();
+                                                let _ = app_handle_clone.emit("login-success", ());
 
                                                 // Small delay to ensure window is fully destroyed before reload
                                                 sleep(Duration::from_millis(100)).await;
@@ -789,6 +999,7 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
                     }
                 } else {
                     // Window was closed by user, exit polling
+                    destroy_login_window();
                     return;
                 }
                 counter += 1; // increment the counter at the end of the loop
@@ -796,6 +1007,7 @@ pub async fn create_login_window(app: tauri::AppHandle, url: String) -> Result<(
 
             // Timeout reached - destroy window if it still exists
             destroy_login_window();
+            let _ = app_handle_clone.emit("login-timeout", json!({ "timeout_secs": timeout_secs }));
         });
     }
 
@@ -853,10 +1065,12 @@ pub async fn direct_login(
 
     use crate::netgrab;
     
-    let login_url = format!("{}/seqta/student/login", http_url);
+    let seqta_prefix = detect_seqta_prefix(&http_url).await;
+    let login_url = session::seqta_url(&http_url, seqta_prefix.as_deref(), "/seqta/student/login");
 
     // Create HTTP client with cookie store enabled and school network-friendly config
     let client = netgrab::create_client_builder()
+        .default_headers(netgrab::custom_header_map(&http_url))
         .cookie_store(true)
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
@@ -906,7 +1120,11 @@ pub async fn direct_login(
         .ok_or("Could not get JSESSIONID from response headers")?;
 
     // Validate session with a heartbeat request
-    let heartbeat_url = format!("{}/seqta/student/heartbeat", http_url);
+    let heartbeat_url = session::seqta_url(
+        &http_url,
+        seqta_prefix.as_deref(),
+        "/seqta/student/heartbeat",
+    );
     let heartbeat_body = json!({ "heartbeat": true });
 
     let heartbeat_response = client
@@ -923,7 +1141,7 @@ pub async fn direct_login(
     }
 
     // Fetch user info to create/get profile
-    let user_info = fetch_user_info(&http_url, &jsessionid).await?;
+    let user_info = fetch_user_info(&http_url, seqta_prefix.as_deref(), &jsessionid).await?;
 
     // Create or get profile
     let profile = profiles::ProfileManager::get_or_create_profile(
@@ -947,6 +1165,8 @@ pub async fn direct_login(
         additional_cookies: Vec::new(),
         stored_username: Some(username.clone()),
         stored_password: Some(password.clone()),
+        custom_headers: session::Session::custom_headers_for(&http_url),
+        seqta_prefix,
     };
 
     session
@@ -0,0 +1,299 @@
+//! Moving a SEQTA session between machines, for support and testing without a new QR login.
+
+use base64::{engine::general_purpose, Engine as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use zeroize::Zeroize;
+
+use crate::caches;
+use crate::logger;
+use crate::login;
+use crate::session::{self, Cookie};
+
+/// Prefix of an unencrypted export (base64 JSON).
+const PLAIN_PREFIX: &str = "desqta-session:1:";
+/// Prefix of a passphrase-encrypted export (base64 salt, nonce and ciphertext).
+const ENCRYPTED_PREFIX: &str = "desqta-session:1e:";
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// What an export carries. Stored credentials are deliberately left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SessionExport {
+    base_url: String,
+    jsessionid: String,
+    #[serde(default)]
+    additional_cookies: Vec<Cookie>,
+    #[serde(default)]
+    seqta_prefix: Option<String>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    key
+}
+
+fn aead_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey, String> {
+    let mut key_bytes = derive_key(passphrase, salt);
+    let key = UnboundKey::new(&AES_256_GCM, &key_bytes)
+        .map_err(|_| "Failed to create encryption key".to_string());
+    key_bytes.zeroize();
+    Ok(LessSafeKey::new(key?))
+}
+
+fn encode_export(export: &SessionExport, passphrase: Option<&str>) -> Result<String, String> {
+    let mut json =
+        serde_json::to_vec(export).map_err(|e| format!("Failed to serialize session: {}", e))?;
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let Some(passphrase) = passphrase else {
+        let blob = format!(
+            "{}{}",
+            PLAIN_PREFIX,
+            general_purpose::STANDARD.encode(&json)
+        );
+        json.zeroize();
+        return Ok(blob);
+    };
+
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| "Failed to generate random bytes".to_string())?;
+
+    let key = aead_key(passphrase, &salt)?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut json)
+        .map_err(|_| "Failed to encrypt session".to_string())?;
+
+    let mut payload = Vec::with_capacity(SALT_LEN + NONCE_LEN + json.len());
+    payload.extend_from_slice(&salt);
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&json);
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_PREFIX,
+        general_purpose::STANDARD.encode(&payload)
+    ))
+}
+
+/// Read an export, checking it's well-formed before anything touches the saved session.
+fn decode_export(blob: &str, passphrase: Option<&str>) -> Result<SessionExport, String> {
+    let blob: String = blob.chars().filter(|c| !c.is_whitespace()).collect();
+    let decode = |data: &str| {
+        general_purpose::STANDARD
+            .decode(data)
+            .map_err(|_| "The session data is corrupted (invalid base64)".to_string())
+    };
+
+    let mut json = if let Some(data) = blob.strip_prefix(ENCRYPTED_PREFIX) {
+        let passphrase = passphrase
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| "This session export is encrypted. Enter its passphrase.".to_string())?;
+        let mut payload = decode(data)?;
+        if payload.len() <= SALT_LEN + NONCE_LEN {
+            return Err("The session data is corrupted (too short)".to_string());
+        }
+        let (salt, rest) = payload.split_at_mut(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| "The session data is corrupted (bad nonce)".to_string())?;
+        let key = aead_key(passphrase, salt)?;
+        let plain = key
+            .open_in_place(nonce, Aad::empty(), ciphertext)
+            .map_err(|_| "Wrong passphrase, or the session data is corrupted".to_string())?;
+        plain.to_vec()
+    } else if let Some(data) = blob.strip_prefix(PLAIN_PREFIX) {
+        decode(data)?
+    } else {
+        return Err("This doesn't look like a DesQTA session export".to_string());
+    };
+
+    let export = serde_json::from_slice::<SessionExport>(&json)
+        .map_err(|e| format!("The session data is incomplete: {}", e));
+    json.zeroize();
+    let export = export?;
+
+    let base_url = url::Url::parse(&export.base_url)
+        .map_err(|_| "The session has an invalid SEQTA address".to_string())?;
+    if !matches!(base_url.scheme(), "http" | "https") || base_url.host_str().is_none() {
+        return Err("The session has an invalid SEQTA address".to_string());
+    }
+    if export.jsessionid.trim().is_empty() {
+        return Err("The session has no session cookie".to_string());
+    }
+    if export
+        .additional_cookies
+        .iter()
+        .any(|cookie| cookie.name.trim().is_empty())
+    {
+        return Err("The session contains a cookie without a name".to_string());
+    }
+    Ok(export)
+}
+
+/// Export the current session (SEQTA address, JSESSIONID and extra cookies, never the saved
+/// password) as a text blob. Anyone holding it can act as the student until the session
+/// expires, so `confirmed` must be true. With a `passphrase` the blob is AES-GCM encrypted,
+/// otherwise it's only base64.
+#[tauri::command]
+pub fn export_session(confirmed: bool, passphrase: Option<String>) -> Result<String, String> {
+    if !confirmed {
+        return Err(
+            "Exporting a session lets anyone with the export use your account. Confirm to continue."
+                .to_string(),
+        );
+    }
+    let current = session::Session::load();
+    if current.base_url.is_empty() || current.jsessionid.is_empty() {
+        return Err("No active session to export. Please log in first.".to_string());
+    }
+
+    let export = SessionExport {
+        base_url: current.base_url,
+        jsessionid: current.jsessionid,
+        additional_cookies: current.additional_cookies,
+        seqta_prefix: current.seqta_prefix,
+    };
+    let blob = encode_export(&export, passphrase.as_deref())?;
+
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::WARN,
+            "session_transfer",
+            "export_session",
+            "Session exported",
+            serde_json::json!({ "encrypted": blob.starts_with(ENCRYPTED_PREFIX) }),
+        );
+    }
+    Ok(blob)
+}
+
+/// Import a session from `export_session` into the active profile. The session is checked
+/// against SEQTA first and only saved if it's still live; caches from the previous session
+/// are dropped and the app reloads.
+#[tauri::command]
+pub async fn import_session(
+    app: tauri::AppHandle,
+    blob: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let export = decode_export(&blob, passphrase.as_deref())?;
+    let base_url = export.base_url.trim_end_matches('/').to_string();
+
+    if !login::validate_session(
+        &base_url,
+        export.seqta_prefix.as_deref(),
+        &export.jsessionid,
+    )
+    .await
+    {
+        return Err(
+            "This session has expired or SEQTA rejected it. Export a fresh one and try again."
+                .to_string(),
+        );
+    }
+
+    session::Session {
+        custom_headers: session::Session::custom_headers_for(&base_url),
+        base_url,
+        jsessionid: export.jsessionid,
+        additional_cookies: export.additional_cookies,
+        stored_username: None,
+        stored_password: None,
+        seqta_prefix: export.seqta_prefix,
+    }
+    .save()
+    .map_err(|e| format!("Failed to save session: {}", e))?;
+
+    let cleared = caches::clear_memory_caches();
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "session_transfer",
+            "import_session",
+            "Session imported",
+            serde_json::json!({ "cleared_cache_entries": cleared }),
+        );
+    }
+    login::force_reload(app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SessionExport {
+        SessionExport {
+            base_url: "https://learn.example.edu.au".to_string(),
+            jsessionid: "ABC123".to_string(),
+            additional_cookies: vec![Cookie {
+                name: "JSESSIONID".to_string(),
+                value: "XYZ".to_string(),
+                domain: None,
+                path: Some("/".to_string()),
+            }],
+            seqta_prefix: None,
+        }
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let plain = encode_export(&sample(), None).unwrap();
+        assert!(plain.starts_with(PLAIN_PREFIX));
+        assert_eq!(decode_export(&plain, None).unwrap(), sample());
+        // Line breaks added by chat apps or email are ignored
+        let wrapped = format!("{}\n{}", &plain[..30], &plain[30..]);
+        assert_eq!(decode_export(&wrapped, None).unwrap(), sample());
+
+        let encrypted = encode_export(&sample(), Some("correct horse")).unwrap();
+        assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+        assert!(!encrypted.contains(&general_purpose::STANDARD.encode("ABC123")));
+        assert_eq!(
+            decode_export(&encrypted, Some("correct horse")).unwrap(),
+            sample()
+        );
+        assert!(decode_export(&encrypted, Some("wrong")).is_err());
+        assert!(decode_export(&encrypted, None).is_err());
+    }
+
+    #[test]
+    fn test_decode_export_rejects_malformed() {
+        let encode =
+            |json: &str| format!("{}{}", PLAIN_PREFIX, general_purpose::STANDARD.encode(json));
+        assert!(decode_export("hello", None).is_err());
+        assert!(decode_export(&format!("{}!!!", PLAIN_PREFIX), None).is_err());
+        assert!(decode_export(&format!("{}AAAA", ENCRYPTED_PREFIX), Some("x")).is_err());
+        assert!(decode_export(&encode("{\"base_url\": 1}"), None).is_err());
+        assert!(decode_export(
+            &encode(r#"{"base_url": "file:///etc", "jsessionid": "a"}"#),
+            None
+        )
+        .is_err());
+        assert!(decode_export(
+            &encode(r#"{"base_url": "https://learn.example.edu.au", "jsessionid": " "}"#),
+            None
+        )
+        .is_err());
+
+        let minimal = decode_export(
+            &encode(r#"{"base_url": "https://learn.example.edu.au", "jsessionid": "a"}"#),
+            None,
+        )
+        .unwrap();
+        assert!(minimal.additional_cookies.is_empty());
+    }
+}
@@ -1,9 +1,14 @@
+use crate::notifications::{self, NotificationCategory, NotifyOutcome};
 use crate::sanitization;
+use crate::seqta_mentions;
+use crate::settings::Settings;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use tauri::command;
-use tauri::Emitter;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Webview};
 
 #[cfg(not(target_os = "android"))]
 use dirs_next;
@@ -42,9 +47,63 @@ impl Default for SearchStats {
     }
 }
 
+/// One search the user ran, newest first in `GlobalSearchData::search_history`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "SearchHistoryRecord")]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    /// RFC 3339; empty for entries saved before history had timestamps
+    pub searched_at: String,
+    /// ID of the result the user opened, if any
+    pub chosen_id: Option<String>,
+}
+
+/// History used to be plain query strings, which still load (without a timestamp).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchHistoryRecord {
+    Entry {
+        query: String,
+        #[serde(default)]
+        searched_at: String,
+        #[serde(default)]
+        chosen_id: Option<String>,
+    },
+    Legacy(String),
+}
+
+impl From<SearchHistoryRecord> for SearchHistoryEntry {
+    fn from(record: SearchHistoryRecord) -> Self {
+        match record {
+            SearchHistoryRecord::Entry {
+                query,
+                searched_at,
+                chosen_id,
+            } => Self {
+                query,
+                searched_at,
+                chosen_id,
+            },
+            SearchHistoryRecord::Legacy(query) => Self {
+                query,
+                searched_at: String::new(),
+                chosen_id: None,
+            },
+        }
+    }
+}
+
+/// A past query and how often it was searched, for suggestions.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FrequentSearch {
+    pub query: String,
+    pub count: usize,
+    pub last_searched: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GlobalSearchData {
-    pub search_history: Vec<String>,
+    pub search_history: Vec<SearchHistoryEntry>,
     pub favorite_items: Vec<String>,
     pub recent_items: Vec<SearchItem>,
     pub search_stats: SearchStats,
@@ -105,31 +164,39 @@ fn get_search_data_path() -> PathBuf {
     dir
 }
 
-#[command]
-pub fn get_global_search_data() -> Result<GlobalSearchData, String> {
-    let path = get_search_data_path();
+/// Serializes every read-modify-write of `global_search.json` within the process so
+/// concurrent commands can't drop each other's updates.
+static SEARCH_DATA_LOCK: Mutex<()> = Mutex::new(());
 
+fn lock_search_data() -> MutexGuard<'static, ()> {
+    // The guarded data lives on disk, so a panic mid-update leaves nothing inconsistent here
+    SEARCH_DATA_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn read_search_data(path: &Path) -> Result<GlobalSearchData, String> {
     if !path.exists() {
         return Ok(GlobalSearchData::default());
     }
 
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
     let data: GlobalSearchData =
         serde_json::from_str(&content).unwrap_or_else(|_| GlobalSearchData::default());
 
     Ok(data)
 }
 
-#[command]
-pub fn save_global_search_data(mut data: GlobalSearchData) -> Result<(), String> {
-    let path = get_search_data_path();
-
+fn write_search_data(path: &Path, mut data: GlobalSearchData) -> Result<(), String> {
     // Sanitize search history
     data.search_history = data
         .search_history
         .into_iter()
-        .map(|query| sanitization::sanitize_search_query(&query))
-        .filter(|query| !query.is_empty())
+        .map(|mut entry| {
+            entry.query = sanitization::sanitize_search_query(&entry.query);
+            entry
+        })
+        .filter(|entry| !entry.query.is_empty())
         .collect();
 
     // Sanitize recent items
@@ -148,56 +215,154 @@ pub fn save_global_search_data(mut data: GlobalSearchData) -> Result<(), String>
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
+    // Write to a sibling temp file and rename over the original so a crash mid-write
+    // never leaves a truncated file behind
     let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
+/// Load, modify and save the search data at `path` while holding the lock.
+fn update_search_data_at<T>(
+    path: &Path,
+    update: impl FnOnce(&mut GlobalSearchData) -> T,
+) -> Result<T, String> {
+    let _guard = lock_search_data();
+    let mut data = read_search_data(path)?;
+    let result = update(&mut data);
+    write_search_data(path, data)?;
+    Ok(result)
+}
+
+fn update_global_search_data<T>(
+    update: impl FnOnce(&mut GlobalSearchData) -> T,
+) -> Result<T, String> {
+    update_search_data_at(&get_search_data_path(), update)
+}
+
+#[command]
+pub fn get_global_search_data() -> Result<GlobalSearchData, String> {
+    let _guard = lock_search_data();
+    read_search_data(&get_search_data_path())
+}
+
+#[command]
+pub fn save_global_search_data(data: GlobalSearchData) -> Result<(), String> {
+    let _guard = lock_search_data();
+    write_search_data(&get_search_data_path(), data)
+}
+
 #[command]
 pub fn clear_search_history() -> Result<(), String> {
-    let mut data = get_global_search_data()?;
-    data.search_history.clear();
-    save_global_search_data(data)?;
-    Ok(())
+    update_global_search_data(|data| data.search_history.clear())
+}
+
+/// Add a search to the front of the history, dropping the oldest past `max_history_items`.
+/// Does nothing when the `auto_save` preference is off. Returns whether it was recorded.
+fn apply_record_search(
+    data: &mut GlobalSearchData,
+    query: &str,
+    chosen_id: Option<String>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    let query = sanitization::sanitize_search_query(query);
+    if !data.search_preferences.auto_save || query.is_empty() {
+        return false;
+    }
+    data.search_history.insert(
+        0,
+        SearchHistoryEntry {
+            query,
+            searched_at: now.to_rfc3339(),
+            chosen_id: chosen_id.filter(|id| !id.is_empty()),
+        },
+    );
+    let max_items = data.search_preferences.max_history_items.max(0) as usize;
+    data.search_history.truncate(max_items);
+    true
+}
+
+/// Most searched queries (case-insensitive), ties broken by the most recent.
+fn frequent_searches(history: &[SearchHistoryEntry], limit: usize) -> Vec<FrequentSearch> {
+    let mut by_query: Vec<FrequentSearch> = Vec::new();
+    // History is newest first, so the first time a query is seen is its latest use
+    for entry in history {
+        match by_query
+            .iter_mut()
+            .find(|f| f.query.eq_ignore_ascii_case(&entry.query))
+        {
+            Some(frequent) => frequent.count += 1,
+            None => by_query.push(FrequentSearch {
+                query: entry.query.clone(),
+                count: 1,
+                last_searched: entry.searched_at.clone(),
+            }),
+        }
+    }
+    // Stable sort keeps recency order among equal counts
+    by_query.sort_by(|a, b| b.count.cmp(&a.count));
+    by_query.truncate(limit);
+    by_query
 }
 
+/// Record a search (and the result chosen from it) in the history. This is the only place
+/// history entries are added; nothing is saved when the `auto_save` preference is off.
 #[command]
-pub fn clear_recent_items() -> Result<(), String> {
-    let mut data = get_global_search_data()?;
-    data.recent_items.clear();
-    save_global_search_data(data)?;
-    Ok(())
+pub fn record_search(query: String, chosen_id: Option<String>) -> Result<bool, String> {
+    update_global_search_data(|data| {
+        apply_record_search(data, &query, chosen_id, chrono::Utc::now())
+    })
 }
 
+/// The user's most frequent searches, for suggestions.
 #[command]
-pub fn add_custom_shortcut(shortcut: SearchItem) -> Result<(), String> {
-    let mut data = get_global_search_data()?;
+pub fn get_frequent_searches(limit: Option<usize>) -> Result<Vec<FrequentSearch>, String> {
+    let data = get_global_search_data()?;
+    Ok(frequent_searches(&data.search_history, limit.unwrap_or(5)))
+}
 
-    // Remove existing shortcut with same id if it exists
-    data.custom_shortcuts.retain(|s| s.id != shortcut.id);
+/// Forget searches older than `days`, including old entries with no timestamp. Returns how
+/// many were removed.
+#[command]
+pub fn clear_search_history_older_than(days: u32) -> Result<usize, String> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    update_global_search_data(|data| {
+        let before = data.search_history.len();
+        data.search_history.retain(|entry| {
+            chrono::DateTime::parse_from_rfc3339(&entry.searched_at)
+                .map_or(false, |searched_at| searched_at >= cutoff)
+        });
+        before - data.search_history.len()
+    })
+}
 
-    // Add new shortcut
-    data.custom_shortcuts.push(shortcut);
+#[command]
+pub fn clear_recent_items() -> Result<(), String> {
+    update_global_search_data(|data| data.recent_items.clear())
+}
 
-    save_global_search_data(data)?;
-    Ok(())
+#[command]
+pub fn add_custom_shortcut(shortcut: SearchItem) -> Result<(), String> {
+    update_global_search_data(|data| {
+        // Remove existing shortcut with same id if it exists
+        data.custom_shortcuts.retain(|s| s.id != shortcut.id);
+
+        // Add new shortcut
+        data.custom_shortcuts.push(shortcut);
+    })
 }
 
 #[command]
 pub fn remove_custom_shortcut(shortcut_id: String) -> Result<(), String> {
-    let mut data = get_global_search_data()?;
-    data.custom_shortcuts.retain(|s| s.id != shortcut_id);
-    save_global_search_data(data)?;
-    Ok(())
+    update_global_search_data(|data| data.custom_shortcuts.retain(|s| s.id != shortcut_id))
 }
 
 #[command]
 pub fn update_search_preferences(preferences: SearchPreferences) -> Result<(), String> {
-    let mut data = get_global_search_data()?;
-    data.search_preferences = preferences;
-    save_global_search_data(data)?;
-    Ok(())
+    update_global_search_data(|data| data.search_preferences = preferences)
 }
 
 #[command]
@@ -206,10 +371,7 @@ pub fn get_search_analytics() -> Result<SearchStats, String> {
     Ok(data.search_stats)
 }
 
-#[command]
-pub fn increment_search_usage(item_id: String, category: String) -> Result<(), String> {
-    let mut data = get_global_search_data()?;
-
+fn apply_search_usage(data: &mut GlobalSearchData, item_id: &str, category: String) {
     // Update search stats
     data.search_stats.total_searches += 1;
     data.search_stats.last_search_time = Some(chrono::Utc::now().to_rfc3339());
@@ -224,9 +386,11 @@ pub fn increment_search_usage(item_id: String, category: String) -> Result<(), S
         item.use_count = Some(item.use_count.unwrap_or(0) + 1);
         item.last_used = Some(chrono::Utc::now().to_rfc3339());
     }
+}
 
-    save_global_search_data(data)?;
-    Ok(())
+#[command]
+pub fn increment_search_usage(item_id: String, category: String) -> Result<(), String> {
+    update_global_search_data(|data| apply_search_usage(data, &item_id, category))
 }
 
 #[command]
@@ -251,6 +415,187 @@ pub fn reset_search_data() -> Result<(), String> {
     Ok(())
 }
 
+/// Most live SEQTA results mixed into a palette query.
+const MAX_SEQTA_RESULTS: usize = 8;
+
+/// Queries shorter than this only search local items.
+const MIN_SEQTA_QUERY_LEN: usize = 2;
+
+/// Bumped by every palette query so a superseded one can bail after its debounce.
+static QUERY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Where a palette result came from, so the UI can section them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchResultSource {
+    Local,
+    Seqta,
+}
+
+/// One ranked command-palette result.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GlobalSearchResult {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Local category, or `seqta:<mention type>` for live results
+    pub category: String,
+    pub source: SearchResultSource,
+    pub path: Option<String>,
+    pub score: i32,
+    /// Mention payload for SEQTA results
+    pub data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GlobalSearchResponse {
+    pub query: String,
+    pub results: Vec<GlobalSearchResult>,
+    /// True when a newer query arrived during the debounce; `results` is empty
+    pub superseded: bool,
+}
+
+/// Text relevance of `query` against a title plus extra searchable fields; 0 means no match.
+fn match_score(query: &str, name: &str, extra: &[&str]) -> i32 {
+    let name = name.to_lowercase();
+    if name == query {
+        100
+    } else if name.starts_with(query) {
+        75
+    } else if name.contains(query) {
+        50
+    } else if extra.iter().any(|e| e.to_lowercase().contains(query)) {
+        25
+    } else {
+        0
+    }
+}
+
+fn score_local_item(query: &str, item: &SearchItem) -> i32 {
+    let mut extra: Vec<&str> = item.keywords.iter().flatten().map(|k| k.as_str()).collect();
+    if let Some(desc) = &item.description {
+        extra.push(desc);
+    }
+    let base = match_score(query, &item.name, &extra);
+    if base == 0 {
+        return 0;
+    }
+    base + item.priority.unwrap_or(0) + item.use_count.unwrap_or(0).min(20)
+}
+
+/// Unified command-palette search over local pages/shortcuts and live SEQTA content.
+/// `local_items` are the frontend's static pages; stored shortcuts and recent items are added here.
+#[command]
+pub async fn global_search_query(
+    query: String,
+    local_items: Option<Vec<SearchItem>>,
+) -> Result<GlobalSearchResponse, String> {
+    let generation = QUERY_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let data = get_global_search_data()?;
+    let query = sanitization::sanitize_search_query(&query);
+
+    let delay_ms = data.search_preferences.search_delay_ms.max(0) as u64;
+    if delay_ms > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    }
+    if QUERY_GENERATION.load(Ordering::SeqCst) != generation {
+        return Ok(GlobalSearchResponse {
+            query,
+            results: Vec::new(),
+            superseded: true,
+        });
+    }
+
+    let query_lower = query.to_lowercase();
+    let mut results: Vec<GlobalSearchResult> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    let locals = local_items
+        .unwrap_or_default()
+        .into_iter()
+        .chain(data.custom_shortcuts.into_iter())
+        .chain(data.recent_items.into_iter());
+    for item in locals {
+        if data.disabled_categories.contains(&item.category) || !seen.insert(item.id.clone()) {
+            continue;
+        }
+        let score = if query_lower.is_empty() {
+            item.priority.unwrap_or(0) + item.use_count.unwrap_or(0)
+        } else {
+            score_local_item(&query_lower, &item)
+        };
+        if score == 0 && !query_lower.is_empty() {
+            continue;
+        }
+        results.push(GlobalSearchResult {
+            id: item.id,
+            name: item.name,
+            description: item.description,
+            category: item.category,
+            source: SearchResultSource::Local,
+            path: Some(item.path),
+            score,
+            data: None,
+        });
+    }
+
+    let seqta_enabled = !data.disabled_categories.iter().any(|c| c == "seqta");
+    if seqta_enabled && query.chars().count() >= MIN_SEQTA_QUERY_LEN {
+        // search_mentions keeps its own short-lived cache, so repeat keystrokes stay cheap
+        match seqta_mentions::search_mentions(query.clone(), None, None).await {
+            Ok(mentions) => {
+                for mention in mentions.items.into_iter().take(MAX_SEQTA_RESULTS) {
+                    let type_name = serde_json::to_value(&mention.mention_type)
+                        .ok()
+                        .and_then(|v| v.as_str().map(|s| s.to_string()))
+                        .unwrap_or_default();
+                    // Local navigation wins ties over live content
+                    let score =
+                        match_score(&query_lower, &mention.title, &[mention.subtitle.as_str()]) - 1;
+                    results.push(GlobalSearchResult {
+                        id: format!("seqta:{}", mention.id),
+                        name: mention.title,
+                        description: Some(mention.subtitle),
+                        category: format!("seqta:{}", type_name),
+                        source: SearchResultSource::Seqta,
+                        path: None,
+                        score: score.max(1),
+                        data: Some(mention.data),
+                    });
+                }
+            }
+            Err(e) => {
+                println!("[GlobalSearch] SEQTA search failed, showing local results only: {}", e);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(GlobalSearchResponse {
+        query,
+        results,
+        superseded: false,
+    })
+}
+
+/// Record that the user opened a palette result: keep it in recent items and bump its usage.
+#[command]
+pub fn record_global_search_selection(item: SearchItem) -> Result<(), String> {
+    update_global_search_data(|data| {
+        let max_recent = data.search_preferences.max_recent_items.max(0) as usize;
+
+        let existing = data.recent_items.iter().position(|i| i.id == item.id);
+        let entry = match existing {
+            Some(index) => data.recent_items.remove(index),
+            None => item,
+        };
+        data.recent_items.insert(0, entry.clone());
+        data.recent_items.truncate(max_recent);
+        apply_search_usage(data, &entry.id, entry.category);
+    })
+}
+
 // Window Management Commands - Desktop only
 #[command]
 pub async fn toggle_fullscreen(window: tauri::Window) -> Result<(), String> {
@@ -348,23 +693,72 @@ pub async fn close_devtools(_window: tauri::Window) -> Result<(), String> {
     }
 }
 
-// Zoom Commands - Emit event to frontend; zoom is applied via CSS (document.documentElement.style.zoom)
+// Zoom Commands - the level is saved as `zoom_level` and applied as the native zoom of every
+// webview, so windows opened later pick it up too (see `apply_saved_zoom`). Mobile webviews
+// don't support native zoom, so there the frontend applies the level as CSS zoom.
+const ZOOM_MIN: f64 = 0.5;
+const ZOOM_MAX: f64 = 3.0;
+const ZOOM_STEP: f64 = 0.1;
+
+/// `level` within `ZOOM_MIN..=ZOOM_MAX`, rounded to a whole percent. Anything that isn't a
+/// number resets to 1.0.
+fn clamp_zoom(level: f64) -> f64 {
+    if !level.is_finite() {
+        return 1.0;
+    }
+    (level.clamp(ZOOM_MIN, ZOOM_MAX) * 100.0).round() / 100.0
+}
+
+fn saved_zoom() -> f64 {
+    Settings::load().zoom_level.map(clamp_zoom).unwrap_or(1.0)
+}
+
+/// Apply the saved zoom level to `webview`. Called whenever a page finishes loading.
+pub fn apply_saved_zoom<R: Runtime>(webview: &Webview<R>) {
+    if cfg!(desktop) {
+        let _ = webview.set_zoom(saved_zoom());
+    }
+}
+
+/// Save `level` (clamped), apply it to every open window and emit `zoom-changed`.
+fn update_zoom(app: &AppHandle, level: f64) -> Result<f64, String> {
+    let level = clamp_zoom(level);
+    let mut settings = Settings::load();
+    settings.zoom_level = Some(level);
+    settings.save().map_err(|e| e.to_string())?;
+    if cfg!(desktop) {
+        for window in app.webview_windows().values() {
+            let _ = window.set_zoom(level);
+        }
+    }
+    let _ = app.emit("zoom-changed", level);
+    Ok(level)
+}
+
 #[command]
-pub async fn zoom_in(window: tauri::Window) -> Result<(), String> {
-    let _ = window.emit("zoom", "in");
-    Ok(())
+pub fn get_zoom() -> f64 {
+    saved_zoom()
 }
 
+/// Set the zoom level, clamped to 0.5-3.0. Returns the level that was applied.
 #[command]
-pub async fn zoom_out(window: tauri::Window) -> Result<(), String> {
-    let _ = window.emit("zoom", "out");
-    Ok(())
+pub fn set_zoom(app: AppHandle, level: f64) -> Result<f64, String> {
+    update_zoom(&app, level)
 }
 
 #[command]
-pub async fn zoom_reset(window: tauri::Window) -> Result<(), String> {
-    let _ = window.emit("zoom", "reset");
-    Ok(())
+pub async fn zoom_in(app: AppHandle) -> Result<f64, String> {
+    update_zoom(&app, saved_zoom() + ZOOM_STEP)
+}
+
+#[command]
+pub async fn zoom_out(app: AppHandle) -> Result<f64, String> {
+    update_zoom(&app, saved_zoom() - ZOOM_STEP)
+}
+
+#[command]
+pub async fn zoom_reset(app: AppHandle) -> Result<f64, String> {
+    update_zoom(&app, 1.0)
 }
 
 // Cache Management Commands
@@ -457,15 +851,23 @@ pub async fn hide_window(window: tauri::Window) -> Result<(), String> {
 }
 
 // Notification Commands
+/// Show a notification through `notifications::notify`, so category preferences and quiet
+/// hours apply. Uncategorised notifications count as reminders.
 #[command]
-pub async fn show_notification(app: tauri::AppHandle, title: String, body: String) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-    app.notification()
-        .builder()
-        .title(title)
-        .body(body)
-        .show()
-        .map_err(|e| e.to_string())
+pub async fn show_notification(
+    app: tauri::AppHandle,
+    title: String,
+    body: String,
+    category: Option<NotificationCategory>,
+    sound: Option<String>,
+) -> Result<NotifyOutcome, String> {
+    notifications::notify_with_sound(
+        &app,
+        category.unwrap_or(NotificationCategory::Reminders),
+        &title,
+        &body,
+        sound.as_deref(),
+    )
 }
 
 // File System Commands
@@ -518,3 +920,102 @@ pub async fn get_app_data_dir() -> Result<String, String> {
     let parent = path.parent().unwrap_or(&path);
     Ok(parent.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_usage_increments_are_not_lost() {
+        let path = std::env::temp_dir()
+            .join(format!("desqta-global-search-{}", uuid::Uuid::new_v4()))
+            .join("global_search.json");
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        update_search_data_at(&path, |data| {
+                            apply_search_usage(data, &format!("item-{}-{}", t, i), "pages".into())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let data = read_search_data(&path).unwrap();
+        assert_eq!(data.search_stats.total_searches, 200);
+        assert_eq!(data.search_stats.most_used_categories, vec!["pages"]);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_search_history_respects_cap() {
+        let mut data = GlobalSearchData::default();
+        data.search_preferences.max_history_items = 20;
+        let now = chrono::Utc::now();
+        for i in 0..500 {
+            let query = if i % 3 == 0 { "Maths" } else { "timetable" };
+            assert!(apply_record_search(&mut data, query, None, now));
+        }
+        assert_eq!(data.search_history.len(), 20);
+
+        assert!(apply_record_search(
+            &mut data,
+            "essay",
+            Some("assessment-7".into()),
+            now
+        ));
+        assert_eq!(data.search_history.len(), 20);
+        assert_eq!(data.search_history[0].query, "essay");
+        assert_eq!(
+            data.search_history[0].chosen_id.as_deref(),
+            Some("assessment-7")
+        );
+
+        let frequent = frequent_searches(&data.search_history, 2);
+        assert_eq!(frequent[0].query, "timetable");
+        assert_eq!(frequent.len(), 2);
+
+        data.search_preferences.auto_save = false;
+        assert!(!apply_record_search(&mut data, "private", None, now));
+        assert_eq!(data.search_history[0].query, "essay");
+
+        let legacy: GlobalSearchData = serde_json::from_value(serde_json::json!({
+            "search_history": ["old query"],
+            "favorite_items": [],
+            "recent_items": [],
+            "search_stats": SearchStats::default(),
+            "custom_shortcuts": [],
+            "disabled_categories": [],
+            "search_preferences": SearchPreferences::default(),
+        }))
+        .unwrap();
+        assert_eq!(legacy.search_history[0].query, "old query");
+        assert!(legacy.search_history[0].searched_at.is_empty());
+    }
+
+    #[test]
+    fn test_zoom_clamping() {
+        assert_eq!(clamp_zoom(1.0), 1.0);
+        assert_eq!(clamp_zoom(0.1), 0.5);
+        assert_eq!(clamp_zoom(5.0), 3.0);
+        assert_eq!(clamp_zoom(f64::NAN), 1.0);
+        assert_eq!(clamp_zoom(f64::INFINITY), 1.0);
+        // Repeated steps don't drift away from whole percents
+        let mut level = 1.0;
+        for _ in 0..3 {
+            level = clamp_zoom(level + ZOOM_STEP);
+        }
+        assert_eq!(level, 1.3);
+        for _ in 0..30 {
+            level = clamp_zoom(level + ZOOM_STEP);
+        }
+        assert_eq!(level, ZOOM_MAX);
+    }
+}
@@ -1,25 +1,73 @@
+#[path = "auth/keepalive.rs"]
+mod keepalive;
 #[path = "auth/login.rs"]
 mod login;
+#[path = "auth/session_transfer.rs"]
+mod session_transfer;
 
+#[path = "utils/ai.rs"]
+mod ai;
 #[path = "utils/analytics.rs"]
 mod analytics;
 #[path = "utils/assessments.rs"]
 mod assessments;
+#[path = "utils/assessment_reminders.rs"]
+mod assessment_reminders;
+#[path = "utils/app_archive.rs"]
+mod app_archive;
+#[path = "utils/bell_times.rs"]
+mod bell_times;
+#[path = "utils/caches.rs"]
+mod caches;
+#[path = "utils/color_scheme.rs"]
+mod color_scheme;
 #[path = "utils/courses.rs"]
 mod courses;
+#[path = "utils/data_dir.rs"]
+mod data_dir;
 #[path = "utils/database.rs"]
 mod database;
+#[path = "utils/diagnostics.rs"]
+mod diagnostics;
+#[path = "utils/disk_space.rs"]
+mod disk_space;
+#[path = "utils/global_shortcuts.rs"]
+mod global_shortcuts;
+#[path = "utils/grade_analyser.rs"]
+mod grade_analyser;
 mod global_search;
+#[path = "utils/lesson_summary.rs"]
+mod lesson_summary;
 #[path = "utils/logger.rs"]
 mod logger;
+#[path = "utils/message_poller.rs"]
+mod message_poller;
 #[path = "utils/messages.rs"]
 mod messages;
 #[path = "utils/netgrab.rs"]
 mod netgrab;
 #[path = "utils/news.rs"]
 mod news;
+#[path = "utils/note_templates.rs"]
+mod note_templates;
+#[path = "utils/notes_assets.rs"]
+mod notes_assets;
+#[path = "utils/notes_images.rs"]
+mod notes_images;
+#[path = "utils/notes_autosave.rs"]
+mod notes_autosave;
+#[path = "utils/notes_backup.rs"]
+mod notes_backup;
+#[path = "utils/notes_encryption.rs"]
+mod notes_encryption;
 #[path = "utils/notes_filesystem.rs"]
 mod notes_filesystem;
+#[path = "utils/notes_import.rs"]
+mod notes_import;
+#[path = "utils/notes_pdf.rs"]
+mod notes_pdf;
+#[path = "utils/notifications.rs"]
+mod notifications;
 #[path = "utils/performance_testing.rs"]
 mod performance_testing;
 #[path = "utils/system_monitor.rs"]
@@ -44,14 +92,32 @@ mod settings;
 mod profiles;
 #[path = "utils/migration.rs"]
 mod migration;
+#[path = "utils/redaction.rs"]
+mod redaction;
+#[path = "utils/student_info.rs"]
+mod student_info;
+#[path = "utils/subject_colors.rs"]
+mod subject_colors;
+#[path = "utils/text_search.rs"]
+mod text_search;
 #[path = "utils/theme_manager.rs"]
 mod theme_manager;
+#[path = "utils/timetable_ics.rs"]
+mod timetable_ics;
+#[path = "utils/timezone.rs"]
+mod timezone;
 #[path = "utils/todolist.rs"]
 mod todolist;
+#[path = "utils/upcoming_lessons.rs"]
+mod upcoming_lessons;
 #[path = "utils/pdf_extract.rs"]
 mod pdf_extract;
 #[path = "utils/forum_photos.rs"]
 mod forum_photos;
+#[path = "utils/window_state.rs"]
+mod window_state;
+#[path = "utils/weather.rs"]
+mod weather;
 
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use serde_json;
@@ -61,6 +127,7 @@ use std::fs;
 use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
 #[cfg(desktop)]
 use tauri::tray::TrayIconBuilder;
+use tauri::webview::PageLoadEvent;
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use tauri::Listener;
 use tauri::{AppHandle, Window, WindowEvent};
@@ -90,6 +157,22 @@ fn quit(app: AppHandle) {
     app.exit(0);
 }
 
+/// Passed by the login item so startup can tell it was launched at login.
+#[cfg(desktop)]
+const AUTOSTART_ARG: &str = "--autostart";
+/// Login items registered before autostart options existed pass this instead.
+#[cfg(desktop)]
+const LEGACY_AUTOSTART_ARG: &str = "--minimize";
+#[cfg(desktop)]
+const MAX_AUTOSTART_DELAY_SECS: u32 = 600;
+
+#[cfg(desktop)]
+fn launched_at_login() -> bool {
+    std::env::args()
+        .skip(1)
+        .any(|arg| arg == AUTOSTART_ARG || arg == LEGACY_AUTOSTART_ARG)
+}
+
 #[tauri::command]
 fn enable_autostart(window: Window) -> Result<(), String> {
     #[cfg(desktop)]
@@ -129,6 +212,37 @@ fn is_autostart_enabled(window: Window) -> Result<bool, String> {
     }
 }
 
+/// Choose whether a launch at login stays in the tray and how long it waits before showing
+/// the window. The delay is applied by the app itself, so it works with every launcher.
+#[tauri::command]
+fn set_autostart_options(window: Window, minimized: bool, delay_secs: u32) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        if delay_secs > MAX_AUTOSTART_DELAY_SECS {
+            return Err(format!(
+                "Startup delay must be at most {} seconds",
+                MAX_AUTOSTART_DELAY_SECS
+            ));
+        }
+        let mut settings = settings::Settings::load();
+        settings.autostart_minimized = minimized;
+        settings.autostart_delay_secs = delay_secs;
+        settings.save().map_err(|e| e.to_string())?;
+
+        // Re-register so a login item from an older version gets the current launch arguments
+        let manager = window.app_handle().autolaunch();
+        if manager.is_enabled().map_err(|e| e.to_string())? {
+            manager.disable().map_err(|e| e.to_string())?;
+            manager.enable().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+    #[cfg(not(desktop))]
+    {
+        Err("Autostart not supported on this platform".to_string())
+    }
+}
+
 /// Get the SEQTA base URL from the current session
 #[tauri::command]
 fn get_seqta_base_url() -> String {
@@ -136,16 +250,15 @@ fn get_seqta_base_url() -> String {
     session.base_url
 }
 
+/// Full URL of a SEQTA endpoint (`/seqta/student/...`) for the current session, with the
+/// session's SEQTA path prefix applied
+#[tauri::command]
+fn get_seqta_url(path: String) -> String {
+    session::Session::load().seqta_url(&path)
+}
+
 fn get_version_app_data_dir() -> std::path::PathBuf {
-    if cfg!(target_os = "android") {
-        let mut dir = std::path::PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir
-    } else {
-        let mut dir = dirs_next::data_dir().expect("Unable to determine data dir");
-        dir.push("DesQTA");
-        dir
-    }
+    data_dir::app_data_dir().expect("Unable to determine data dir")
 }
 
 /// Path for the next-lesson widget data file (Android widget reads from same location).
@@ -201,6 +314,9 @@ fn get_app_version(app: tauri::AppHandle) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Before anything opens files in the data directory
+    data_dir::apply_pending_migration();
+
     let mut builder = tauri::Builder::default()
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_opener::init())
@@ -213,9 +329,18 @@ pub fn run() {
     {
         builder = builder
             .plugin(tauri_plugin_updater::Builder::new().build())
+            .plugin(
+                tauri_plugin_global_shortcut::Builder::new()
+                    .with_handler(|app, shortcut, event| {
+                        if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                            global_shortcuts::handle_shortcut(app, shortcut);
+                        }
+                    })
+                    .build(),
+            )
             .plugin(tauri_plugin_autostart::init(
                 tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-                Some(vec!["--minimize"]),
+                Some(vec![AUTOSTART_ARG]),
             ));
     }
 
@@ -371,15 +496,11 @@ pub fn run() {
                     // Check if we have both required parameters
                     if let (Some(cookie), Some(base_url)) = (cookie, base_url) {
                         println!("[Desqta] Using base_url: {}", base_url);
-                        match login::save_session(base_url, cookie) {
-                            Ok(_) => {
-                                println!("[Desqta] Successfully saved session from deep link. Check session.json for update.");
-                                login::force_reload(app.app_handle().clone());
-                            },
-                            Err(e) => {
-                                eprintln!("[Desqta] Failed to save session from deep link: {}", e);
-                            }
-                        }
+                        // Validate against SEQTA before saving so a stale link can't replace a working session
+                        let app_handle = app.app_handle().clone();
+                        tauri::async_runtime::spawn(async move {
+                            login::handle_auth_deeplink(app_handle, base_url, cookie).await;
+                        });
                     } else {
                         eprintln!("[Desqta] Missing required parameters. Need both cookie and URL.");
                     }
@@ -395,32 +516,49 @@ pub fn run() {
             enable_autostart,
             disable_autostart,
             is_autostart_enabled,
+            set_autostart_options,
+            global_shortcuts::register_global_shortcut,
+            global_shortcuts::unregister_global_shortcut,
             netgrab::get_api_data,
+            netgrab::seqta_request,
             netgrab::open_url,
             netgrab::get_rss_feed,
             netgrab::post_api_data,
             netgrab::fetch_api_data,
             netgrab::proxy_request,
             netgrab::get_seqta_file,
+            netgrab::get_seqta_file_base64,
+            netgrab::download_seqta_file,
+            netgrab::cancel_seqta_download,
             netgrab::upload_seqta_file,
             netgrab::upload_and_link_assessment_file,
+            netgrab::get_custom_headers,
+            netgrab::set_custom_headers,
             login::check_session_exists,
             login::save_session,
             login::create_login_window,
             login::logout,
             login::force_reload,
             login::cleanup_login_windows,
+            login::cancel_login,
+            keepalive::set_keepalive_enabled,
+            message_poller::set_message_polling,
             login::has_login_windows,
             login::clear_webview_data,
             login::direct_login,
             login::reauthenticate,
+            session_transfer::export_session,
+            session_transfer::import_session,
             get_seqta_base_url,
+            get_seqta_url,
             set_next_lesson_for_widget,
             get_version_update_info,
             clear_version_update_info,
             get_app_version,
             profiles::get_current_profile,
             profiles::list_profiles,
+            profiles::create_profile,
+            profiles::rename_profile,
             profiles::switch_profile,
             profiles::delete_profile,
             settings::get_settings,
@@ -428,6 +566,9 @@ pub fn run() {
             settings::get_settings_json,
             settings::save_settings_from_json,
             settings::get_settings_subset,
+            ai::ai_generate,
+            ai::set_gemini_api_key,
+            ai::has_gemini_api_key,
             settings::save_settings_merge,
             settings::save_cloud_token,
             settings::get_cloud_user,
@@ -445,24 +586,37 @@ pub fn run() {
             settings::get_cloud_base_url,
             settings::set_cloud_base_url,
             settings::upload_settings_to_cloud,
+            settings::set_cloud_settings_visibility,
             settings::download_settings_from_cloud,
+            settings::get_cloud_sync_state,
             settings::check_cloud_settings,
             analytics::save_analytics,
             analytics::load_analytics,
             analytics::delete_analytics,
             analytics::sync_analytics_data,
+            analytics::get_last_analytics_sync,
+            analytics::export_analytics_csv,
+            analytics::export_analytics_json,
+            grade_analyser::analyse_grades,
             seqta_config::load_seqta_config,
             seqta_config::save_seqta_config,
             seqta_config::is_seqta_config_different,
+            seqta_config::check_seqta_config_changes,
+            seqta_config::acknowledge_seqta_config_changes,
             global_search::get_global_search_data,
             global_search::save_global_search_data,
             global_search::clear_search_history,
+            global_search::record_search,
+            global_search::get_frequent_searches,
+            global_search::clear_search_history_older_than,
             global_search::clear_recent_items,
             global_search::add_custom_shortcut,
             global_search::remove_custom_shortcut,
             global_search::update_search_preferences,
             global_search::get_search_analytics,
             global_search::increment_search_usage,
+            global_search::global_search_query,
+            global_search::record_global_search_selection,
             global_search::export_search_data,
             global_search::import_search_data,
             global_search::reset_search_data,
@@ -476,14 +630,19 @@ pub fn run() {
             global_search::zoom_in,
             global_search::zoom_out,
             global_search::zoom_reset,
+            global_search::get_zoom,
+            global_search::set_zoom,
             global_search::clear_cache,
             global_search::get_system_info,
             global_search::restart_app,
             global_search::show_window,
             global_search::hide_window,
             global_search::show_notification,
+            notifications::get_notification_prefs,
+            notifications::set_notification_prefs,
             global_search::open_file_explorer,
             global_search::get_app_data_dir,
+            data_dir::set_data_directory,
             logger::get_log_file_path_command,
             logger::get_logs_for_troubleshooting,
             logger::clear_logs,
@@ -493,11 +652,16 @@ pub fn run() {
             theme_manager::get_available_themes,
             theme_manager::get_custom_themes,
             theme_manager::load_theme_manifest,
+            theme_manager::render_theme_preview,
             theme_manager::save_custom_theme,
+            theme_manager::regenerate_theme_css,
             theme_manager::delete_custom_theme,
             theme_manager::import_theme_from_file,
+            theme_manager::import_theme_from_url,
             theme_manager::get_themes_directory_path,
             theme_manager::export_theme_to_file,
+            color_scheme::refresh_color_scheme_schedule,
+            color_scheme::set_color_scheme_override,
             theme_manager::read_theme_css,
             theme_manager::download_and_install_theme,
             theme_manager::download_theme_to_temp,
@@ -522,8 +686,16 @@ pub fn run() {
             news::get_news_australia,
             todolist::load_todos,
             todolist::save_todos,
+            todolist::create_todo_from_note_selection,
+            todolist::get_todos_for_note,
+            todolist::complete_todo,
             notes_filesystem::load_notes_filesystem,
             notes_filesystem::save_note_filesystem,
+            notes_filesystem::quick_capture_note,
+            note_templates::list_note_templates,
+            note_templates::save_note_template,
+            note_templates::delete_note_template,
+            note_templates::create_note_from_template,
             notes_filesystem::delete_note_filesystem,
             notes_filesystem::get_note_filesystem,
             notes_filesystem::search_notes_filesystem,
@@ -532,9 +704,23 @@ pub fn run() {
             notes_filesystem::create_folder_filesystem,
             notes_filesystem::delete_folder_filesystem,
             notes_filesystem::rename_folder_filesystem,
+            notes_filesystem::set_folder_metadata,
+            notes_filesystem::move_folder_filesystem,
             notes_filesystem::move_note_filesystem,
             notes_filesystem::get_notes_stats_filesystem,
+            notes_filesystem::get_notes_insights,
+            notes_filesystem::touch_note_accessed,
+            notes_filesystem::get_recent_notes,
+            notes_filesystem::recompute_all_notes_metadata,
             notes_filesystem::backup_notes_filesystem,
+            notes_backup::set_backup_schedule,
+            notes_autosave::autosave_note,
+            notes_autosave::flush_pending_autosaves,
+            notes_assets::link_external_file,
+            notes_assets::resolve_note_asset,
+            notes_assets::find_broken_asset_links,
+            notes_images::reencode_existing_note_images,
+            notes_pdf::export_note_pdf,
             notes_filesystem::restore_notes_from_backup_filesystem,
             notes_filesystem::save_image_from_base64_filesystem,
             notes_filesystem::get_image_path_filesystem,
@@ -542,12 +728,32 @@ pub fn run() {
             notes_filesystem::delete_note_images_filesystem,
             notes_filesystem::cleanup_unused_images_filesystem,
             notes_filesystem::get_file_tree,
+            notes_filesystem::get_folder_children,
+            notes_filesystem::list_all_tags,
+            notes_filesystem::rename_tag,
+            notes_filesystem::delete_tag,
+            notes_filesystem::scan_note_integrity,
+            notes_filesystem::repair_note_from_history,
+            notes_filesystem::verify_notes_integrity,
+            notes_filesystem::repair_notes_integrity,
+            notes_encryption::get_notes_encryption_status,
+            notes_encryption::set_notes_encryption,
+            notes_encryption::unlock_notes,
+            notes_encryption::lock_notes,
+            notes_encryption::change_notes_passphrase,
+            notes_encryption::disable_notes_encryption,
+            notes_import::import_notes_from_markdown,
+            weather::get_weather,
+            weather::reverse_geocode,
             profile_picture::save_profile_picture,
             profile_picture::save_profile_picture_from_url,
             profile_picture::get_profile_picture_path_cmd,
             profile_picture::delete_profile_picture,
             profile_picture::has_custom_profile_picture,
             profile_picture::get_profile_picture_data_url,
+            student_info::get_current_student_info,
+            subject_colors::get_subject_colors,
+            subject_colors::set_subject_color,
             forum_photos::save_forum_photo,
             forum_photos::get_forum_photo_path,
             forum_photos::get_forum_photo_data_url,
@@ -558,6 +764,8 @@ pub fn run() {
             performance_testing::delete_performance_test_result,
             performance_testing::get_performance_tests_directory,
             performance_testing::clear_all_performance_tests,
+            performance_testing::compare_performance_results,
+            performance_testing::detect_performance_regression,
             system_monitor::get_system_metrics,
             system_monitor::get_detailed_system_info,
             system_monitor::start_system_monitoring,
@@ -566,7 +774,17 @@ pub fn run() {
             database::db_cache_set,
             database::db_cache_delete,
             database::db_cache_clear,
+            caches::clear_all_caches,
+            caches::get_cache_stats,
+            caches::prewarm_caches,
+            caches::cancel_cache_prewarm,
+            app_archive::export_app_data,
+            app_archive::import_app_data,
+            diagnostics::get_diagnostics,
+            disk_space::check_free_space,
             database::db_cache_cleanup_expired,
+            database::db_stats,
+            database::db_vacuum,
             database::db_queue_add,
             database::db_queue_all,
             database::db_queue_delete,
@@ -590,43 +808,58 @@ pub fn run() {
             database::db_notification_get_by_assessment,
             database::db_notification_cleanup_old,
             database::db_notification_delete_by_assessment,
+            assessment_reminders::schedule_assessment_reminders,
             database::db_widget_layout_save,
             database::db_widget_layout_load,
             assessments::get_processed_assessments,
             assessments::get_assessment_detail,
+            assessments::get_assessment_by_id,
+            assessments::get_upcoming_assessments,
+            assessments::get_past_assessments,
             courses::get_courses_subjects,
+            courses::get_subjects,
             courses::get_course_content,
+            courses::search_course_content,
+            courses::export_course_materials,
             messages::fetch_messages,
             messages::fetch_message_content,
             messages::star_messages,
             messages::delete_messages,
             messages::restore_messages,
+            messages::mark_messages_read,
+            messages::move_messages,
+            messages::get_unread_message_count,
+            messages::search_messages,
+            messages::send_message,
             seqta_mentions::search_seqta_mentions,
             seqta_mentions::search_seqta_mentions_with_context,
+            seqta_mentions::cancel_mention_search,
             seqta_mentions::update_seqta_mention_data,
+            seqta_mentions::update_mentions_batch,
             seqta_mentions::get_weekly_schedule_for_class_cmd,
             seqta_mentions::fetch_lesson_content_cmd,
+            timetable_ics::export_timetable_ics,
+            timezone::get_timezone_info,
+            bell_times::get_bell_times,
+            bell_times::get_current_period,
+            upcoming_lessons::get_upcoming_lessons,
+            timezone::list_timezones,
+            timezone::set_timezone,
+            lesson_summary::summarise_lesson,
             html_parser::sanitize_html_command,
             html_parser::parse_html_command,
             html_parser::extract_iframe_src_command,
             html_parser::extract_text_content_command,
             pdf_extract::get_assessment_weighting
         ])
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                global_search::apply_saved_zoom(webview);
+            }
+        })
         .setup(|app| {
             // --- START: Auto-Clear Cache on Update ---
-            use dirs_next;
-            use std::path::PathBuf;
-
-            // Get the app data directory (consistent with rest of codebase: AppData/Roaming/DesQTA)
-            let app_data_dir = if cfg!(target_os = "android") {
-                let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-                dir.push("DesQTA");
-                dir
-            } else {
-                let mut dir = dirs_next::data_dir().expect("Unable to determine data dir");
-                dir.push("DesQTA");
-                dir
-            };
+            let app_data_dir = get_version_app_data_dir();
 
             let version_file = app_data_dir.join("last_run_version");
             let current_version = app.package_info().version.to_string();
@@ -683,6 +916,25 @@ pub fn run() {
                 eprintln!("Failed to initialize database: {}", e);
             }
 
+            // Look for school-side SEQTA config changes once the app has settled
+            if session::Session::exists() {
+                let app_handle = app.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                    if let Err(e) = seqta_config::check_seqta_config_changes(app_handle).await {
+                        eprintln!("[DesQTA] SEQTA config check failed: {}", e);
+                    }
+                });
+            }
+
+            keepalive::start_if_enabled(app.app_handle().clone());
+            message_poller::start_if_enabled(app.app_handle().clone());
+            assessment_reminders::reschedule_on_launch();
+            notes_backup::start_if_enabled(app.app_handle().clone());
+            color_scheme::start(app.app_handle().clone());
+            #[cfg(desktop)]
+            global_shortcuts::register_saved(app.app_handle());
+
             // On desktop: check if app was launched via deep link (first launch, before single-instance)
             #[cfg(desktop)]
             {
@@ -826,9 +1078,12 @@ pub fn run() {
                 if let Some(window) = app.webview_windows().get("main") {
                     let _ = window.set_title("DesQTA");
                     let _ = window.set_min_size(Some(tauri::Size::Logical(tauri::LogicalSize::new(900.0, 700.0))));
-                    let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(900.0, 700.0)));
                     let _ = window.set_decorations(false);
-                    let _ = window.center();
+                    // Reopen where the user left it; fall back to the default size, centered
+                    if !window_state::restore(window) {
+                        let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(900.0, 700.0)));
+                        let _ = window.center();
+                    }
                     
                     let window_clone = window.clone();
                     let current_fullscreen = Cell::new(window.is_fullscreen().unwrap_or(false));
@@ -863,10 +1118,12 @@ pub fn run() {
                         }
                     };
                     
+                    let window_for_state = window_clone.clone();
                     window.on_window_event(move |event| {
                         match event {
                             WindowEvent::Resized(_) | WindowEvent::Moved(_) => {
                                 check_and_emit_state();
+                                window_state::schedule_save(&window_for_state);
                             }
                             _ => {}
                         }
@@ -907,11 +1164,27 @@ pub fn run() {
 
             // Bring main window to front on startup (fixes installer/updater launch where window
             // opens behind other windows). Short delay helps when launched by another process.
+            // At login, honour the autostart options: stay in the tray, or show after a delay.
             #[cfg(desktop)]
             {
                 let app_handle = app.app_handle().clone();
+                let at_login = launched_at_login();
+                let startup = settings::Settings::load();
+                let stay_hidden = at_login && startup.autostart_minimized;
+                let delay_secs = if at_login { startup.autostart_delay_secs } else { 0 };
+                if stay_hidden || delay_secs > 0 {
+                    if let Some(window) = app.webview_windows().get("main") {
+                        let _ = window.hide();
+                    }
+                }
                 std::thread::spawn(move || {
-                    std::thread::sleep(std::time::Duration::from_millis(300));
+                    if stay_hidden {
+                        return;
+                    }
+                    std::thread::sleep(
+                        std::time::Duration::from_millis(300)
+                            + std::time::Duration::from_secs(delay_secs as u64),
+                    );
                     if let Some(window) = app_handle.webview_windows().get("main") {
                         let _ = window.show();
                         let _ = window.set_focus();
@@ -942,6 +1215,15 @@ pub fn run() {
                 }
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                color_scheme::stop();
+                if let Err(e) = notes_autosave::flush_pending_autosaves(app_handle.clone()) {
+                    eprintln!("[AUTOSAVE] Flushing autosaves on exit failed: {}", e);
+                }
+                notes_backup::backup_on_exit(app_handle);
+            }
+        });
 }
@@ -1,10 +1,20 @@
+use crate::assessments;
+use crate::caches::CacheCounters;
+use crate::courses;
 use crate::netgrab;
 use crate::netgrab::RequestMethod;
+use crate::settings;
+use crate::student_info;
+use crate::timezone;
 use anyhow::{anyhow, Result};
 use chrono::Datelike;
+use futures::future::{AbortHandle, Abortable};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -21,10 +31,126 @@ struct CacheEntry {
 /// In-memory cache for mention search results
 static MENTION_CACHE: OnceLock<Mutex<HashMap<String, CacheEntry>>> = OnceLock::new();
 const CACHE_DURATION_MS: u64 = 5 * 60 * 1000; // 5 minutes
+pub static MENTION_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+/// Maximum mention refreshes in flight at once for `update_mentions_batch`
+const MENTION_BATCH_CONCURRENCY: usize = 4;
 
 /// Teacher cache (key: programme-metaclass-code)
 static TEACHER_CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
 
+/// Timetable lessons per fetched date range (key: student-from-until), with fetch time
+static TIMETABLE_CACHE: OnceLock<Mutex<HashMap<String, (u64, Vec<Value>)>>> = OnceLock::new();
+const TIMETABLE_CACHE_DURATION_MS: u64 = 15 * 60 * 1000; // 15 minutes
+pub static TIMETABLE_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+/// Lesson content (`payload.w`) per class (key: programme-metaclass), with fetch time
+static COURSE_CACHE: OnceLock<Mutex<HashMap<String, (u64, Option<Value>)>>> = OnceLock::new();
+const COURSE_CACHE_DURATION_MS: u64 = 15 * 60 * 1000; // 15 minutes
+pub static COURSE_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+/// SEQTA rejects or truncates long timetable ranges, so longer ones are fetched in pieces
+const TIMETABLE_CHUNK_DAYS: i64 = 28;
+/// Longest range `fetch_timetable_range` accepts
+const MAX_TIMETABLE_RANGE_DAYS: i64 = 366;
+/// Timetable chunks requested at once
+const TIMETABLE_FETCH_CONCURRENCY: usize = 3;
+/// Days ahead searched for timetable slot mentions unless the caller asks for a wider window
+const DEFAULT_SLOT_WINDOW_DAYS: i64 = 14;
+/// Timetable fetched per step while looking for a class's weekly schedule
+const WEEKLY_SCHEDULE_WINDOW_DAYS: i64 = 12 * 7;
+/// How far back to look for a class that hasn't met recently
+const WEEKLY_SCHEDULE_LOOKBACK_DAYS: i64 = 365;
+
+/// Sequence numbers handed out to mention searches, across all channels
+static MENTION_SEARCH_SEQ: AtomicU64 = AtomicU64::new(0);
+/// Newest search of each channel that has searched
+static MENTION_SEARCH_CHANNELS: Mutex<Vec<MentionSearchChannel>> = Mutex::new(Vec::new());
+/// Channel of callers that don't name one, i.e. the editor's mention picker
+const DEFAULT_MENTION_SEARCH_CHANNEL: &str = "editor";
+
+/// Mention searches only supersede others on the same channel, so e.g. a detail view
+/// loading related items doesn't cancel the picker's search.
+struct MentionSearchChannel {
+    channel: String,
+    /// Sequence number of the newest search; anything older is stale
+    latest_seq: u64,
+    /// Abort handle of the newest search while it's running
+    in_flight: Option<AbortHandle>,
+}
+
+/// Lookup from timetable lesson fields to a human subject title
+#[derive(Clone, Default)]
+struct SubjectTitles {
+    /// Keyed by "programme-metaclass"
+    by_class: HashMap<String, String>,
+    /// Keyed by lowercase subject code
+    by_code: HashMap<String, String>,
+}
+
+impl SubjectTitles {
+    /// Built from the subject list, which puts current subjects first so a code shared with an
+    /// archived class resolves to the current one.
+    fn from_subjects(subjects: &[courses::SubjectListing]) -> Self {
+        let mut titles = Self::default();
+        for subject in subjects {
+            let title = subject.title.trim();
+            if title.is_empty() {
+                continue;
+            }
+            titles.by_class.insert(
+                format!("{}-{}", subject.programme, subject.metaclass),
+                title.to_string(),
+            );
+            titles
+                .by_code
+                .entry(subject.code.trim().to_lowercase())
+                .or_insert_with(|| title.to_string());
+        }
+        titles
+    }
+
+    /// Title for a lesson, preferring the exact class over the (possibly shared) code.
+    fn resolve(&self, code: &str, programme: Option<i64>, metaclass: Option<i64>) -> Option<String> {
+        programme
+            .zip(metaclass)
+            .and_then(|(p, m)| self.by_class.get(&format!("{}-{}", p, m)))
+            .or_else(|| self.by_code.get(&code.trim().to_lowercase()))
+            .cloned()
+    }
+
+    /// Subject title for a timetable lesson, falling back to its code.
+    fn lesson_title(&self, lesson: &Value) -> String {
+        let code = lesson["code"].as_str().unwrap_or("");
+        self.resolve(code, lesson["programmeID"].as_i64(), lesson["metaID"].as_i64())
+            .unwrap_or_else(|| code.to_string())
+    }
+}
+
+/// Subject titles from the cached subject list (`courses::get_subjects`). Failures yield an
+/// empty lookup, so callers fall back to codes.
+async fn subject_titles() -> SubjectTitles {
+    match courses::get_subjects(None).await {
+        Ok(subjects) => SubjectTitles::from_subjects(&subjects),
+        Err(e) => {
+            println!("[SeqtaMentions] Failed to load subject titles: {}", e);
+            SubjectTitles::default()
+        }
+    }
+}
+
+/// Load the subject list into its cache ahead of the first title lookup. Returns how many
+/// subjects there are.
+pub async fn warm_subject_titles() -> Result<usize, String> {
+    Ok(courses::get_subjects(None).await?.len())
+}
+
+/// Subject title of each timetable lesson, in order, falling back to the lesson's code.
+pub async fn lesson_subject_titles(lessons: &[Value]) -> Vec<String> {
+    let subjects = subject_titles().await;
+    lessons.iter().map(|l| subjects.lesson_title(l)).collect()
+}
+
 /// Mention type enum matching TypeScript
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -56,10 +182,34 @@ pub struct SeqtaMentionItem {
     pub last_updated: Option<String>,
 }
 
+/// Results of a mention search. Sources that failed are named in `failed_sources` (e.g.
+/// "notices") and left out of `items`, so one flaky endpoint doesn't hide everything else.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MentionSearchResults {
+    pub items: Vec<SeqtaMentionItem>,
+    pub failed_sources: Vec<String>,
+    /// A newer search on the same channel replaced this one; `items` is empty and should be
+    /// ignored rather than shown.
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+impl MentionSearchResults {
+    fn cancelled() -> Self {
+        Self {
+            cancelled: true,
+            ..Default::default()
+        }
+    }
+}
+
 /// Initialize caches
 fn init_caches() {
     MENTION_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
     TEACHER_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    TIMETABLE_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    COURSE_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
 }
 
 /// Get current timestamp in milliseconds
@@ -78,12 +228,16 @@ fn is_cache_valid(entry: &CacheEntry) -> bool {
 /// Get cached data if valid
 fn get_cached(key: &str) -> Option<Vec<SeqtaMentionItem>> {
     init_caches();
-    let cache = MENTION_CACHE.get().unwrap().lock().unwrap();
+    let mut cache = MENTION_CACHE.get().unwrap().lock().unwrap();
     if let Some(entry) = cache.get(key) {
         if is_cache_valid(entry) {
+            MENTION_CACHE_STATS.hit();
             return Some(entry.data.clone());
         }
+        cache.remove(key);
+        MENTION_CACHE_STATS.evicted(1);
     }
+    MENTION_CACHE_STATS.miss();
     None
 }
 
@@ -100,10 +254,395 @@ fn set_cache(key: String, data: Vec<SeqtaMentionItem>) {
     );
 }
 
-/// Format date for subtitle
-fn format_date(date_str: &str) -> String {
-    // Simple date formatting - can be enhanced later
-    date_str.to_string()
+/// Number of cached mention searches, teacher lookups, timetable ranges and courses.
+pub fn cache_len() -> usize {
+    init_caches();
+    let mentions = MENTION_CACHE.get().unwrap().lock().map_or(0, |c| c.len());
+    let teachers = TEACHER_CACHE.get().unwrap().lock().map_or(0, |c| c.len());
+    let timetables = TIMETABLE_CACHE.get().unwrap().lock().map_or(0, |c| c.len());
+    let courses = COURSE_CACHE.get().unwrap().lock().map_or(0, |c| c.len());
+    mentions + teachers + timetables + courses
+}
+
+/// Drop every cached mention search, teacher, timetable range and course.
+/// Returns the number of entries removed.
+pub fn clear_caches() -> usize {
+    init_caches();
+    let mut cleared = 0;
+    if let Ok(mut cache) = MENTION_CACHE.get().unwrap().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    if let Ok(mut cache) = TEACHER_CACHE.get().unwrap().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    if let Ok(mut cache) = TIMETABLE_CACHE.get().unwrap().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    if let Ok(mut cache) = COURSE_CACHE.get().unwrap().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    cleared
+}
+
+/// Parse a SEQTA JSON response body. SEQTA answers an expired session with its HTML login
+/// page (still HTTP 200), so an HTML body that looks like a login page is reported as
+/// `Unauthorized` for the session-expired handling; any other non-JSON body is a `Parse` error
+/// with a readable message rather than serde's.
+pub(crate) fn parse_seqta_payload(response: &str) -> Result<Value, netgrab::NetError> {
+    let body = response.trim_start_matches('\u{feff}').trim();
+    if body.is_empty() {
+        return Err(netgrab::NetError::Parse(
+            "SEQTA returned an empty response".to_string(),
+        ));
+    }
+    if body.starts_with('<') {
+        let head = body.chars().take(4096).collect::<String>().to_lowercase();
+        if ["login", "saml", "password"].iter().any(|m| head.contains(m)) {
+            return Err(netgrab::NetError::Unauthorized(
+                "SEQTA returned its login page. Please log in again.".to_string(),
+            ));
+        }
+        return Err(netgrab::NetError::Parse(
+            "SEQTA returned an HTML page instead of data".to_string(),
+        ));
+    }
+    serde_json::from_str(body).map_err(|e| {
+        netgrab::NetError::Parse(format!("SEQTA returned an unexpected response: {}", e))
+    })
+}
+
+/// `parse_seqta_payload` for the staff list. A body that isn't JSON at all (some schools don't
+/// offer the list) means no staff rather than an error; a login page still reports the
+/// expired session.
+fn parse_staff_payload(response: &str) -> Result<Value, netgrab::NetError> {
+    match parse_seqta_payload(response) {
+        Err(netgrab::NetError::Parse(_)) if !response.trim_start().starts_with('{') => {
+            Ok(json!({}))
+        }
+        result => result,
+    }
+}
+
+/// Split an inclusive date range into consecutive inclusive pieces of at most
+/// `TIMETABLE_CHUNK_DAYS` days. Empty if `until` is before `from`.
+fn timetable_chunks(
+    from: chrono::NaiveDate,
+    until: chrono::NaiveDate,
+) -> Vec<(chrono::NaiveDate, chrono::NaiveDate)> {
+    let mut chunks = Vec::new();
+    let mut start = from;
+    while start <= until {
+        let end = (start + chrono::Duration::days(TIMETABLE_CHUNK_DAYS - 1)).min(until);
+        chunks.push((start, end));
+        start = end + chrono::Duration::days(1);
+    }
+    chunks
+}
+
+/// Fetch one chunk of the timetable, from the cache when it's fresh.
+async fn fetch_timetable_chunk(
+    student_id: i64,
+    from: chrono::NaiveDate,
+    until: chrono::NaiveDate,
+) -> Result<Vec<Value>> {
+    init_caches();
+    let key = format!("{}-{}-{}", student_id, from, until);
+    let cached = TIMETABLE_CACHE
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .get(&key)
+        .cloned();
+    if let Some((fetched, lessons)) = cached {
+        if current_timestamp_ms() - fetched < TIMETABLE_CACHE_DURATION_MS {
+            TIMETABLE_CACHE_STATS.hit();
+            return Ok(lessons);
+        }
+    }
+    TIMETABLE_CACHE_STATS.miss();
+
+    let body = json!({
+        "from": from.format("%Y-%m-%d").to_string(),
+        "until": until.format("%Y-%m-%d").to_string(),
+        "student": student_id
+    });
+    let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+
+    let response = netgrab::fetch_api_data(
+        "/seqta/student/load/timetable?",
+        RequestMethod::POST,
+        Some(headers),
+        Some(body),
+        None,
+        false,
+        false,
+        None,
+    )
+    .await
+    .map_err(|e| anyhow!("Failed to fetch timetable: {}", e))?;
+
+    let json_response = parse_seqta_payload(&response)?;
+    let lessons = json_response["payload"]["items"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    TIMETABLE_CACHE
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .insert(key, (current_timestamp_ms(), lessons.clone()));
+    Ok(lessons)
+}
+
+/// Every timetable lesson between `from` and `until` (inclusive) for the current student,
+/// fetched in chunks SEQTA accepts and cached per chunk. Ranges over a year are rejected.
+pub async fn fetch_timetable_range(
+    from: chrono::NaiveDate,
+    until: chrono::NaiveDate,
+) -> Result<Vec<Value>> {
+    if until < from {
+        return Err(anyhow!("Timetable range ends before it starts"));
+    }
+    if (until - from).num_days() >= MAX_TIMETABLE_RANGE_DAYS {
+        return Err(anyhow!(
+            "Timetable range is too long (at most {} days)",
+            MAX_TIMETABLE_RANGE_DAYS
+        ));
+    }
+    let student_id = student_info::current_student_id()
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let chunks: Vec<Vec<Value>> = stream::iter(timetable_chunks(from, until))
+        .map(|(start, end)| fetch_timetable_chunk(student_id, start, end))
+        .buffered(TIMETABLE_FETCH_CONCURRENCY)
+        .try_collect()
+        .await?;
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+/// Default window for timetable slot mentions: today and the next two weeks.
+fn default_slot_window() -> (chrono::NaiveDate, chrono::NaiveDate) {
+    let today = timezone::today();
+    let until = today + chrono::Duration::days(DEFAULT_SLOT_WINDOW_DAYS);
+    (today, until)
+}
+
+/// Parse an optional `YYYY-MM-DD` window from the frontend. Both ends or neither are needed.
+pub(crate) fn parse_timetable_window(
+    from: Option<&str>,
+    until: Option<&str>,
+) -> Result<Option<(chrono::NaiveDate, chrono::NaiveDate)>> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow!("Invalid timetable date '{}', expected YYYY-MM-DD", date))
+    };
+    match (from, until) {
+        (None, None) => Ok(None),
+        (Some(from), Some(until)) => Ok(Some((parse(from)?, parse(until)?))),
+        _ => Err(anyhow!("A timetable window needs both dates")),
+    }
+}
+
+/// How a language writes a short date.
+enum DateStyle {
+    /// Weekday, day and month names, e.g. "Wed 1 May" or "Mi., 1. Mai"
+    Named {
+        weekdays: [&'static str; 7],
+        months: [&'static str; 12],
+        /// Placeholders: {weekday}, {day}, {month}
+        pattern: &'static str,
+        twelve_hour: bool,
+    },
+    /// Numeric month and day with unit suffixes, e.g. "5月1日(水)"
+    Numeric {
+        weekdays: [&'static str; 7],
+        /// Placeholders: {year}, {month}, {day}, {weekday}
+        pattern: &'static str,
+        year_pattern: &'static str,
+    },
+}
+
+/// Date style for a `language` setting such as "de" or "en-pirate". Languages without their
+/// own style use English.
+fn date_style(language: &str) -> DateStyle {
+    let base = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    let named =
+        |weekdays: [&'static str; 7], months: [&'static str; 12], pattern| DateStyle::Named {
+            weekdays,
+            months,
+            pattern,
+            twelve_hour: false,
+        };
+    match base.as_str() {
+        "de" => named(
+            ["Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa.", "So."],
+            [
+                "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.",
+                "Nov.", "Dez.",
+            ],
+            "{weekday}, {day}. {month}",
+        ),
+        "fr" => named(
+            ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+            [
+                "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+                "nov.", "déc.",
+            ],
+            "{weekday} {day} {month}",
+        ),
+        "es" => named(
+            ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+            [
+                "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+            ],
+            "{weekday}, {day} {month}",
+        ),
+        "it" => named(
+            ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+            [
+                "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+            ],
+            "{weekday} {day} {month}",
+        ),
+        "nl" => named(
+            ["ma", "di", "wo", "do", "vr", "za", "zo"],
+            [
+                "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+            ],
+            "{weekday} {day} {month}",
+        ),
+        "pt" => named(
+            ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+            [
+                "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+            ],
+            "{weekday}, {day} {month}",
+        ),
+        "ja" => DateStyle::Numeric {
+            weekdays: ["月", "火", "水", "木", "金", "土", "日"],
+            pattern: "{month}月{day}日({weekday})",
+            year_pattern: "{year}年",
+        },
+        "zh" => DateStyle::Numeric {
+            weekdays: ["周一", "周二", "周三", "周四", "周五", "周六", "周日"],
+            pattern: "{month}月{day}日 {weekday}",
+            year_pattern: "{year}年",
+        },
+        "ko" => DateStyle::Numeric {
+            weekdays: ["월", "화", "수", "목", "금", "토", "일"],
+            pattern: "{month}월 {day}일 ({weekday})",
+            year_pattern: "{year}년 ",
+        },
+        _ => DateStyle::Named {
+            weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            months: [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ],
+            pattern: "{weekday} {day} {month}",
+            twelve_hour: true,
+        },
+    }
+}
+
+/// Parse the date formats SEQTA uses for due dates. The time is kept as written: SEQTA sends
+/// plain dates as midnight UTC, and shifting those into the local zone would show a due time
+/// nobody set.
+fn parse_seqta_date(date_str: &str) -> Option<(chrono::NaiveDate, Option<chrono::NaiveTime>)> {
+    let date_str = date_str.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+        return Some((date, None));
+    }
+    let datetime = chrono::DateTime::parse_from_rfc3339(date_str)
+        .map(|dt| dt.naive_local())
+        .ok()
+        .or_else(|| {
+            [
+                "%Y-%m-%dT%H:%M:%S%.f",
+                "%Y-%m-%d %H:%M:%S%.f",
+                "%Y-%m-%d %H:%M",
+            ]
+            .iter()
+            .find_map(|format| chrono::NaiveDateTime::parse_from_str(date_str, format).ok())
+        })?;
+    let time = datetime.time();
+    Some((
+        datetime.date(),
+        (time != chrono::NaiveTime::MIN).then_some(time),
+    ))
+}
+
+/// Format a SEQTA date for a subtitle in the style of `language`, e.g. "Wed 1 May". The year
+/// is only shown when it isn't the current one, and a time only when it isn't midnight.
+/// Anything that doesn't parse is returned unchanged.
+fn format_date_for(date_str: &str, language: &str, today: chrono::NaiveDate) -> String {
+    let Some((date, time)) = parse_seqta_date(date_str) else {
+        return date_str.to_string();
+    };
+    let weekday = date.weekday().num_days_from_monday() as usize;
+    let show_year = date.year() != today.year();
+
+    match date_style(language) {
+        DateStyle::Named {
+            weekdays,
+            months,
+            pattern,
+            twelve_hour,
+        } => {
+            let mut formatted = pattern
+                .replace("{weekday}", weekdays[weekday])
+                .replace("{day}", &date.day().to_string())
+                .replace("{month}", months[date.month0() as usize]);
+            if show_year {
+                formatted.push_str(&format!(" {}", date.year()));
+            }
+            if let Some(time) = time {
+                let time = if twelve_hour {
+                    time.format("%-I:%M%P").to_string()
+                } else {
+                    time.format("%H:%M").to_string()
+                };
+                formatted.push_str(&format!(" {}", time));
+            }
+            formatted
+        }
+        DateStyle::Numeric {
+            weekdays,
+            pattern,
+            year_pattern,
+        } => {
+            let mut formatted = String::new();
+            if show_year {
+                formatted.push_str(&year_pattern.replace("{year}", &date.year().to_string()));
+            }
+            formatted.push_str(
+                &pattern
+                    .replace("{month}", &date.month().to_string())
+                    .replace("{day}", &date.day().to_string())
+                    .replace("{weekday}", weekdays[weekday]),
+            );
+            if let Some(time) = time {
+                formatted.push_str(&format!(" {}", time.format("%H:%M")));
+            }
+            formatted
+        }
+    }
+}
+
+/// Format a due date for a subtitle using the `language` setting.
+fn format_date(date_str: &str, language: &str) -> String {
+    format_date_for(date_str, language, timezone::today())
 }
 
 /// Fetch assignments from SEQTA
@@ -111,7 +650,9 @@ async fn fetch_assignments(
     query: &str,
     category_filter: Option<&str>,
 ) -> Result<Vec<SeqtaMentionItem>> {
-    let student_id = 69; // TODO: Get from session
+    let student_id = student_info::current_student_id()
+        .await
+        .map_err(|e| anyhow!(e))?;
 
     let body = json!({
         "student": student_id
@@ -135,8 +676,7 @@ async fn fetch_assignments(
     .await
     .map_err(|e| anyhow!("Failed to fetch assignments: {}", e))?;
 
-    let json_response: Value = serde_json::from_str(&response)
-        .map_err(|e| anyhow!("Failed to parse assignments response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let assignments = json_response["payload"]
         .as_array()
@@ -150,6 +690,7 @@ async fn fetch_assignments(
     };
 
     let query_lower = query.to_lowercase();
+    let language = settings::Settings::load().language;
     let filtered: Vec<SeqtaMentionItem> = assignments
         .iter()
         .filter(|a| {
@@ -181,7 +722,7 @@ async fn fetch_assignments(
                 id: format!("assignment-{}", id_val),
                 mention_type: MentionType::Assignment,
                 title: title.clone(),
-                subtitle: format!("{} • Due: {}", subject, format_date(due)),
+                subtitle: format!("{} • Due: {}", subject, format_date(due, &language)),
                 data: json!({
                     "id": id_val,
                     "title": title,
@@ -223,8 +764,7 @@ async fn fetch_classes(
     .await
     .map_err(|e| anyhow!("Failed to fetch classes: {}", e))?;
 
-    let json_response: Value = serde_json::from_str(&response)
-        .map_err(|e| anyhow!("Failed to parse classes response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let folders = json_response["payload"]
         .as_array()
@@ -322,46 +862,14 @@ async fn fetch_subjects(
         .collect())
 }
 
-/// Fetch timetable slots
+/// Fetch timetable slots between the dates in `window`, or over the next two weeks
 async fn fetch_timetable_slots(
     query: &str,
     category_filter: Option<&str>,
+    window: Option<(chrono::NaiveDate, chrono::NaiveDate)>,
 ) -> Result<Vec<SeqtaMentionItem>> {
-    let student_id = 69; // TODO: Get from session
-
-    let start = chrono::Utc::now();
-    let end = start + chrono::Duration::days(14);
-    let from = start.format("%Y-%m-%d").to_string();
-    let until = end.format("%Y-%m-%d").to_string();
-
-    let body = json!({
-        "from": from,
-        "until": until,
-        "student": student_id
-    });
-
-    let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
-
-    let response = netgrab::fetch_api_data(
-        "/seqta/student/load/timetable?",
-        RequestMethod::POST,
-        Some(headers),
-        Some(body),
-        None,
-        false,
-        false,
-        None,
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to fetch timetable: {}", e))?;
-
-    let json_response: Value = serde_json::from_str(&response)
-        .map_err(|e| anyhow!("Failed to parse timetable response: {}", e))?;
-
-    let items = json_response["payload"]["items"]
-        .as_array()
-        .map(|v| v.as_slice())
-        .unwrap_or(EMPTY_ARRAY);
+    let (from, until) = window.unwrap_or_else(default_slot_window);
+    let items = fetch_timetable_range(from, until).await?;
 
     let limit = if category_filter == Some("timetable_slot") {
         100
@@ -369,6 +877,7 @@ async fn fetch_timetable_slots(
         20
     };
     let query_lower = query.to_lowercase();
+    let subjects = subject_titles().await;
 
     let results: Vec<SeqtaMentionItem> = items
         .iter()
@@ -379,9 +888,11 @@ async fn fetch_timetable_slots(
             let code = lesson["code"].as_str().unwrap_or("").to_lowercase();
             let title = lesson["title"].as_str().unwrap_or("").to_lowercase();
             let desc = lesson["description"].as_str().unwrap_or("").to_lowercase();
+            let subject = subjects.lesson_title(lesson).to_lowercase();
             code.contains(&query_lower)
                 || title.contains(&query_lower)
                 || desc.contains(&query_lower)
+                || subject.contains(&query_lower)
         })
         .take(limit)
         .map(|lesson| {
@@ -426,6 +937,7 @@ async fn fetch_timetable_slots(
                 .as_str()
                 .or_else(|| lesson["description"].as_str())
                 .unwrap_or("Lesson");
+            let subject_name = subjects.lesson_title(lesson);
             let room = lesson["room"].as_str().unwrap_or("TBA");
             let id_val = lesson["id"].as_i64().or_else(|| Some(0)).unwrap_or(0);
 
@@ -439,7 +951,7 @@ async fn fetch_timetable_slots(
                     }
                 ),
                 mention_type: MentionType::TimetableSlot,
-                title: format!("{} {}-{}", code, from_time, until_time),
+                title: format!("{} {}-{}", subject_name, from_time, until_time),
                 subtitle: format!("{} {} • Room {}", day_name, date, room),
                 data: json!({
                     "id": id_val,
@@ -447,6 +959,7 @@ async fn fetch_timetable_slots(
                     "from": from_time,
                     "until": until_time,
                     "code": code,
+                    "subject": subject_name,
                     "title": title,
                     "room": room,
                     "teacher": lesson["staff"].as_str()
@@ -468,7 +981,7 @@ async fn fetch_notices(
     query: &str,
     category_filter: Option<&str>,
 ) -> Result<Vec<SeqtaMentionItem>> {
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let today = timezone::today().format("%Y-%m-%d").to_string();
 
     let body = json!({
         "date": today
@@ -489,8 +1002,7 @@ async fn fetch_notices(
     .await
     .map_err(|e| anyhow!("Failed to fetch notices: {}", e))?;
 
-    let json_response: Value = serde_json::from_str(&response)
-        .map_err(|e| anyhow!("Failed to parse notices response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let notices = json_response["payload"]
         .as_array()
@@ -573,8 +1085,7 @@ async fn fetch_homework(
     .await
     .map_err(|e| anyhow!("Failed to fetch homework: {}", e))?;
 
-    let json_response: Value = serde_json::from_str(&response)
-        .map_err(|e| anyhow!("Failed to parse homework response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let homework_items = json_response["payload"]
         .as_array()
@@ -664,12 +1175,7 @@ async fn fetch_staff(query: &str, category_filter: Option<&str>) -> Result<Vec<S
     .await
     .map_err(|e| anyhow!("Failed to fetch staff: {}", e))?;
 
-    let json_response: Value = if response.starts_with('{') {
-        serde_json::from_str(&response)
-            .map_err(|e| anyhow!("Failed to parse staff response: {}", e))?
-    } else {
-        json!({})
-    };
+    let json_response = parse_staff_payload(&response)?;
 
     let staff = json_response["payload"]
         .as_array()
@@ -727,6 +1233,114 @@ async fn fetch_timetables(
     Ok(vec![])
 }
 
+/// A SEQTA source searched for mentions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MentionSource {
+    Assignments,
+    Classes,
+    Subjects,
+    Timetables,
+    TimetableSlots,
+    Notices,
+    Homework,
+    Staff,
+}
+
+const ALL_MENTION_SOURCES: [MentionSource; 8] = [
+    MentionSource::Assignments,
+    MentionSource::Classes,
+    MentionSource::Subjects,
+    MentionSource::Timetables,
+    MentionSource::TimetableSlots,
+    MentionSource::Notices,
+    MentionSource::Homework,
+    MentionSource::Staff,
+];
+
+impl MentionSource {
+    fn name(self) -> &'static str {
+        match self {
+            MentionSource::Assignments => "assignments",
+            MentionSource::Classes => "classes",
+            MentionSource::Subjects => "subjects",
+            MentionSource::Timetables => "timetables",
+            MentionSource::TimetableSlots => "timetable_slots",
+            MentionSource::Notices => "notices",
+            MentionSource::Homework => "homework",
+            MentionSource::Staff => "staff",
+        }
+    }
+}
+
+/// Sources worth querying for `category_filter`. A filter for one mention type only needs the
+/// endpoint behind it ("file" has none); no filter, or one we don't recognise, searches all.
+fn mention_sources(category_filter: Option<&str>) -> Vec<MentionSource> {
+    let source = match category_filter {
+        Some("assignment") | Some("assessment") => MentionSource::Assignments,
+        Some("class") => MentionSource::Classes,
+        Some("subject") => MentionSource::Subjects,
+        Some("timetable") => MentionSource::Timetables,
+        Some("timetable_slot") => MentionSource::TimetableSlots,
+        Some("notice") => MentionSource::Notices,
+        Some("homework") => MentionSource::Homework,
+        Some("teacher") => MentionSource::Staff,
+        Some("file") => return Vec::new(),
+        _ => return ALL_MENTION_SOURCES.to_vec(),
+    };
+    vec![source]
+}
+
+async fn fetch_mention_source(
+    source: MentionSource,
+    query: &str,
+    category_filter: Option<&str>,
+    timetable_window: Option<(chrono::NaiveDate, chrono::NaiveDate)>,
+) -> Result<Vec<SeqtaMentionItem>> {
+    match source {
+        MentionSource::Assignments => fetch_assignments(query, category_filter).await,
+        MentionSource::Classes => fetch_classes(query, category_filter).await,
+        MentionSource::Subjects => fetch_subjects(query, category_filter).await,
+        MentionSource::Timetables => fetch_timetables(query, category_filter).await,
+        MentionSource::TimetableSlots => {
+            fetch_timetable_slots(query, category_filter, timetable_window).await
+        }
+        MentionSource::Notices => fetch_notices(query, category_filter).await,
+        MentionSource::Homework => fetch_homework(query, category_filter).await,
+        MentionSource::Staff => fetch_staff(query, category_filter).await,
+    }
+}
+
+/// Fetch `sources` in parallel and combine the items of those that succeeded, in source
+/// order. Failed sources are logged and returned by name; the search only fails as a whole
+/// when every source did.
+async fn fetch_mention_sources<F, Fut>(
+    sources: Vec<MentionSource>,
+    fetch: F,
+) -> Result<(Vec<SeqtaMentionItem>, Vec<String>)>
+where
+    F: Fn(MentionSource) -> Fut,
+    Fut: Future<Output = Result<Vec<SeqtaMentionItem>>>,
+{
+    let results = futures::future::join_all(sources.iter().map(|source| fetch(*source))).await;
+    let mut items = Vec::new();
+    let mut failed_sources = Vec::new();
+    let mut last_error = None;
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(found) => items.extend(found),
+            Err(e) => {
+                println!("[SeqtaMentions] Failed to search {}: {}", source.name(), e);
+                failed_sources.push(source.name().to_string());
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if failed_sources.len() == sources.len() => Err(e),
+        _ => Ok((items, failed_sources)),
+    }
+}
+
 /// Sort items by relevance
 fn sort_by_relevance(items: &mut [SeqtaMentionItem], query: &str) {
     if query.is_empty() {
@@ -776,44 +1390,38 @@ fn sort_by_relevance(items: &mut [SeqtaMentionItem], query: &str) {
     });
 }
 
-/// Main search function
+/// Main search function. Timetable slots are searched within `timetable_window` (inclusive
+/// dates), or over the next two weeks when it's `None`. Results missing a failed source
+/// aren't cached, so the next search tries it again.
 pub async fn search_mentions(
     query: String,
     category_filter: Option<String>,
-) -> Result<Vec<SeqtaMentionItem>> {
+    timetable_window: Option<(chrono::NaiveDate, chrono::NaiveDate)>,
+) -> Result<MentionSearchResults> {
+    let window_key = timetable_window
+        .map(|(from, until)| format!("_{}_{}", from, until))
+        .unwrap_or_default();
     let cache_key = format!(
-        "search_{}_{}",
+        "search_{}_{}{}",
         query,
-        category_filter.as_deref().unwrap_or("all")
+        category_filter.as_deref().unwrap_or("all"),
+        window_key
     );
 
     // Check cache
     if let Some(cached) = get_cached(&cache_key) {
-        return Ok(cached);
+        return Ok(MentionSearchResults {
+            items: cached,
+            failed_sources: Vec::new(),
+            cancelled: false,
+        });
     }
 
-    // Fetch from all sources in parallel
-    let (assignments, classes, subjects, timetables, timetable_slots, notices, homework, staff) = tokio::try_join!(
-        fetch_assignments(&query, category_filter.as_deref()),
-        fetch_classes(&query, category_filter.as_deref()),
-        fetch_subjects(&query, category_filter.as_deref()),
-        fetch_timetables(&query, category_filter.as_deref()),
-        fetch_timetable_slots(&query, category_filter.as_deref()),
-        fetch_notices(&query, category_filter.as_deref()),
-        fetch_homework(&query, category_filter.as_deref()),
-        fetch_staff(&query, category_filter.as_deref()),
-    )?;
-
-    // Combine all items
-    let mut all_items = Vec::new();
-    all_items.extend(assignments);
-    all_items.extend(classes);
-    all_items.extend(subjects);
-    all_items.extend(timetables);
-    all_items.extend(timetable_slots);
-    all_items.extend(notices);
-    all_items.extend(homework);
-    all_items.extend(staff);
+    let sources = mention_sources(category_filter.as_deref());
+    let (mut all_items, failed_sources) = fetch_mention_sources(sources, |source| {
+        fetch_mention_source(source, &query, category_filter.as_deref(), timetable_window)
+    })
+    .await?;
 
     // Filter by query if provided
     if !query.trim().is_empty() {
@@ -834,10 +1442,16 @@ pub async fn search_mentions(
     let limit = if category_filter.is_some() { 100 } else { 50 };
     all_items.truncate(limit);
 
-    // Cache results
-    set_cache(cache_key, all_items.clone());
+    // Cache complete results only
+    if failed_sources.is_empty() {
+        set_cache(cache_key, all_items.clone());
+    }
 
-    Ok(all_items)
+    Ok(MentionSearchResults {
+        items: all_items,
+        failed_sources,
+        cancelled: false,
+    })
 }
 
 /// Search with context (simplified - just calls regular search for now)
@@ -845,10 +1459,10 @@ pub async fn search_mentions_with_context(
     query: String,
     _note_content: String,
     category_filter: Option<String>,
-) -> Result<Vec<SeqtaMentionItem>> {
+) -> Result<MentionSearchResults> {
     // Context-aware search can be enhanced later
     // For now, just use regular search
-    search_mentions(query, category_filter).await
+    search_mentions(query, category_filter, None).await
 }
 
 /// Fetch assignment/assessment by ID
@@ -856,281 +1470,68 @@ async fn fetch_assignment_by_id(
     id: String,
     meta: Option<Value>,
 ) -> Result<Option<SeqtaMentionItem>> {
-    let student_id = 69; // TODO: Get from session
     let clean_id = id.replace("assessment-", "").replace("assignment-", "");
+    let Ok(numeric_id) = clean_id.parse::<i64>() else {
+        return Ok(None);
+    };
 
     // Try to get programme/metaclass from meta
-    let programme = meta
-        .as_ref()
-        .and_then(|m| m.get("data").and_then(|d| d.get("programme")))
-        .or_else(|| {
-            meta.as_ref()
-                .and_then(|m| m.get("lookup").and_then(|l| l.get("programme")))
-        })
-        .and_then(|v| v.as_i64());
-    let metaclass = meta
-        .as_ref()
-        .and_then(|m| m.get("data").and_then(|d| d.get("metaclass")))
-        .or_else(|| {
-            meta.as_ref()
-                .and_then(|m| m.get("lookup").and_then(|l| l.get("metaclass")))
-        })
-        .and_then(|v| v.as_i64());
-
-    // Check upcoming first
-    let body = json!({ "student": student_id });
-    let headers = HashMap::from([(
-        "Content-Type".to_string(),
-        "application/json; charset=utf-8".to_string(),
-    )]);
-
-    let response = netgrab::fetch_api_data(
-        "/seqta/student/assessment/list/upcoming?",
-        netgrab::RequestMethod::POST,
-        Some(headers.clone()),
-        Some(body.clone()),
-        None,
-        false,
-        false,
-        None,
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to fetch upcoming assessments: {}", e))?;
-
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
-
-    let upcoming = json_response["payload"]
-        .as_array()
-        .map(|v| v.as_slice())
-        .unwrap_or(EMPTY_ARRAY);
-
-    let found = upcoming.iter().find(|a| {
-        let a_id = a["id"].as_i64().map(|i| i.to_string());
-        a_id.as_deref() == Some(&clean_id)
-            || a_id.as_deref() == Some(&id.replace("assessment-", "").replace("assignment-", ""))
-    });
-
-    // If not found, try past assessments
-    if found.is_none() && programme.is_some() && metaclass.is_some() {
-        let past_body = json!({
-            "programme": programme.unwrap(),
-            "metaclass": metaclass.unwrap(),
-            "student": student_id
-        });
+    let meta_field = |key: &str| {
+        meta.as_ref()
+            .and_then(|m| m.get("data").and_then(|d| d.get(key)))
+            .or_else(|| {
+                meta.as_ref()
+                    .and_then(|m| m.get("lookup").and_then(|l| l.get(key)))
+            })
+            .and_then(|v| v.as_i64())
+    };
+    let programme = meta_field("programme");
+    let metaclass = meta_field("metaclass");
 
-        if let Ok(past_response) = netgrab::fetch_api_data(
-            "/seqta/student/assessment/list/past?",
-            netgrab::RequestMethod::POST,
-            Some(headers.clone()),
-            Some(past_body),
-            None,
-            false,
-            false,
-            None,
-        )
+    let found = assessments::lookup_assessment(numeric_id, programme, metaclass)
         .await
-        {
-            if let Ok(past_json) = serde_json::from_str::<Value>(&past_response) {
-                if let Some(tasks) = past_json["payload"]["tasks"].as_array() {
-                    for task in tasks {
-                        let task_id = task["id"].as_i64().map(|i| i.to_string());
-                        if task_id.as_deref() == Some(&clean_id) {
-                            // Create a SeqtaMentionItem from this task
-                            let due = task["due"]
-                                .as_str()
-                                .or_else(|| task["dueDate"].as_str())
-                                .unwrap_or("");
-                            let subject = task["subject"]
-                                .as_str()
-                                .or_else(|| task["code"].as_str())
-                                .unwrap_or("");
-
-                            let status = if !due.is_empty() {
-                                if let Ok(due_dt) = chrono::DateTime::parse_from_rfc3339(due) {
-                                    if due_dt.with_timezone(&chrono::Utc) > chrono::Utc::now() {
-                                        "pending"
-                                    } else {
-                                        "overdue"
-                                    }
-                                } else {
-                                    "unknown"
-                                }
-                            } else {
-                                task["status"].as_str().unwrap_or("unknown")
-                            };
-
-                            return Ok(Some(SeqtaMentionItem {
-                                id: format!("assessment-{}", task["id"].as_i64().unwrap_or(0)),
-                                mention_type: MentionType::Assessment,
-                                title: task["title"].as_str().unwrap_or("Assessment").to_string(),
-                                subtitle: format!("{} • {}", subject, format_date(due)),
-                                data: json!({
-                                    "id": task["id"],
-                                    "title": task["title"],
-                                    "subject": subject,
-                                    "code": task["code"],
-                                    "due": due,
-                                    "dueDate": due,
-                                    "status": status,
-                                    "programme": task.get("programme").or_else(|| task.get("programmeID")).cloned(),
-                                    "metaclass": task.get("metaclass").or_else(|| task.get("metaID")).cloned(),
-                                }),
-                                last_updated: Some(chrono::Utc::now().to_rfc3339()),
-                            }));
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // If still not found and we have metaclass, try detail endpoint
-    if found.is_none() && metaclass.is_some() {
-        if let Ok(clean_id_num) = clean_id.parse::<i64>() {
-            let detail_body = json!({
-                "assessment": clean_id_num,
-                "student": student_id,
-                "metaclass": metaclass.unwrap()
-            });
+        .map_err(|e| anyhow!("Failed to fetch assessment: {}", e))?;
+    let Some((assignment, _)) = found else {
+        return Ok(None);
+    };
 
-            if let Ok(detail_response) = netgrab::fetch_api_data(
-                "/seqta/student/assessment/get?",
-                netgrab::RequestMethod::POST,
-                Some(headers),
-                Some(detail_body),
-                None,
-                false,
-                false,
-                None,
-            )
-            .await
-            {
-                if let Ok(detail_json) = serde_json::from_str::<Value>(&detail_response) {
-                    if detail_json["payload"]["id"].is_number() {
-                        let payload = detail_json["payload"].clone();
-                        let due = payload["due"]
-                            .as_str()
-                            .or_else(|| payload["dueDate"].as_str())
-                            .unwrap_or("");
-                        let subject = payload["subject"]
-                            .as_str()
-                            .or_else(|| payload["code"].as_str())
-                            .unwrap_or("");
-
-                        let status = if !due.is_empty() {
-                            if let Ok(due_dt) = chrono::DateTime::parse_from_rfc3339(due) {
-                                if due_dt.with_timezone(&chrono::Utc) > chrono::Utc::now() {
-                                    "pending"
-                                } else {
-                                    "overdue"
-                                }
-                            } else {
-                                "unknown"
-                            }
-                        } else {
-                            payload["status"].as_str().unwrap_or("unknown")
-                        };
-
-                        return Ok(Some(SeqtaMentionItem {
-                            id: format!("assessment-{}", payload["id"].as_i64().unwrap_or(0)),
-                            mention_type: MentionType::Assessment,
-                            title: payload["title"]
-                                .as_str()
-                                .unwrap_or("Assessment")
-                                .to_string(),
-                            subtitle: format!("{} • {}", subject, format_date(due)),
-                            data: json!({
-                                "id": payload["id"],
-                                "title": payload["title"],
-                                "subject": subject,
-                                "code": payload["code"],
-                                "due": due,
-                                "dueDate": due,
-                                "status": status,
-                                "programme": payload.get("programme").or_else(|| payload.get("programmeID")).cloned(),
-                                "metaclass": payload.get("metaclass").or_else(|| payload.get("metaID")).cloned(),
-                            }),
-                            last_updated: Some(chrono::Utc::now().to_rfc3339()),
-                        }));
-                    }
-                }
-            }
-        }
-    }
+    let due = assignment["due"]
+        .as_str()
+        .or_else(|| assignment["dueDate"].as_str())
+        .unwrap_or("");
+    let subject = assignment["subject"]
+        .as_str()
+        .or_else(|| assignment["code"].as_str())
+        .unwrap_or("");
+    let status = assessments::assessment_status(&assignment);
+    let submission = assessments::AssessmentSubmissionInfo::from_value(&assignment);
 
-    if let Some(assignment) = found {
-        let due = assignment["due"]
-            .as_str()
-            .or_else(|| assignment["dueDate"].as_str())
-            .unwrap_or("");
-        let subject = assignment["subject"]
+    Ok(Some(SeqtaMentionItem {
+        id: format!("assessment-{}", assignment["id"].as_i64().unwrap_or(0)),
+        mention_type: MentionType::Assessment,
+        title: assignment["title"]
             .as_str()
-            .or_else(|| assignment["code"].as_str())
-            .unwrap_or("");
-
-        let due_date = if !due.is_empty() {
-            chrono::DateTime::parse_from_rfc3339(due)
-                .ok()
-                .map(|dt| dt.with_timezone(&chrono::Utc))
-                .or_else(|| {
-                    chrono::NaiveDateTime::parse_from_str(due, "%Y-%m-%dT%H:%M:%S")
-                        .ok()
-                        .map(|dt| {
-                            chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
-                                dt,
-                                chrono::Utc,
-                            )
-                        })
-                })
-                .or_else(|| {
-                    chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d")
-                        .ok()
-                        .map(|d| {
-                            chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
-                                d.and_hms_opt(0, 0, 0).unwrap(),
-                                chrono::Utc,
-                            )
-                        })
-                })
-        } else {
-            None
-        };
-
-        let status = if let Some(due_dt) = due_date {
-            if due_dt > chrono::Utc::now() {
-                "pending"
-            } else {
-                "overdue"
-            }
-        } else {
-            assignment["status"].as_str().unwrap_or("unknown")
-        };
-
-        return Ok(Some(SeqtaMentionItem {
-            id: format!("assessment-{}", assignment["id"].as_i64().unwrap_or(0)),
-            mention_type: MentionType::Assessment,
-            title: assignment["title"]
-                .as_str()
-                .unwrap_or("Assessment")
-                .to_string(),
-            subtitle: format!("{} • {}", subject, format_date(due)),
-            data: json!({
-                "id": assignment["id"],
-                "title": assignment["title"],
-                "subject": subject,
-                "code": assignment["code"],
-                "due": due,
-                "dueDate": due,
-                "status": status,
-                "programme": assignment.get("programme").or_else(|| assignment.get("programmeID")).cloned(),
-                "metaclass": assignment.get("metaclass").or_else(|| assignment.get("metaID")).cloned(),
-            }),
-            last_updated: Some(chrono::Utc::now().to_rfc3339()),
-        }));
-    }
-
-    Ok(None)
+            .unwrap_or("Assessment")
+            .to_string(),
+        subtitle: format!(
+            "{} • {}",
+            subject,
+            format_date(due, &settings::Settings::load().language)
+        ),
+        data: json!({
+            "id": assignment["id"],
+            "title": assignment["title"],
+            "subject": subject,
+            "code": assignment["code"],
+            "due": due,
+            "dueDate": due,
+            "status": status,
+            "submission": submission,
+            "programme": assignment.get("programme").or_else(|| assignment.get("programmeID")).cloned(),
+            "metaclass": assignment.get("metaclass").or_else(|| assignment.get("metaID")).cloned(),
+        }),
+        last_updated: Some(chrono::Utc::now().to_rfc3339()),
+    }))
 }
 
 /// Fetch class by ID (programme-metaclass format)
@@ -1154,8 +1555,7 @@ async fn fetch_class_by_id(id: String) -> Result<Option<SeqtaMentionItem>> {
     .await
     .map_err(|e| anyhow!("Failed to fetch classes: {}", e))?;
 
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let folders = json_response["payload"]
         .as_array()
@@ -1187,94 +1587,66 @@ async fn fetch_class_by_id(id: String) -> Result<Option<SeqtaMentionItem>> {
         let programme = subject["programme"].as_i64();
         let metaclass = subject["metaclass"].as_i64();
 
-        // Fetch timetable for next 14 days
-        let start = chrono::Utc::now();
-        let end = start + chrono::Duration::days(14);
-        let from = start.format("%Y-%m-%d").to_string();
-        let until = end.format("%Y-%m-%d").to_string();
-
-        let tt_body = json!({
-            "from": from,
-            "until": until,
-            "student": 69
-        });
-
+        // Timetable for the next two weeks
+        let (from, until) = default_slot_window();
         let mut lessons = Vec::new();
-        if let Ok(tt_response) = netgrab::fetch_api_data(
-            "/seqta/student/load/timetable?",
-            netgrab::RequestMethod::POST,
-            Some(HashMap::from([(
-                "Content-Type".to_string(),
-                "application/json".to_string(),
-            )])),
-            Some(tt_body),
-            None,
-            false,
-            false,
-            None,
-        )
-        .await
-        {
-            if let Ok(tt_json) = serde_json::from_str::<Value>(&tt_response) {
-                if let Some(items) = tt_json["payload"]["items"].as_array() {
-                    for item in items {
-                        let meta_ok = metaclass
-                            .map(|m| item["metaID"].as_i64().map(|mi| mi == m).unwrap_or(false))
-                            .unwrap_or(false);
-                        let prog_ok = programme
-                            .map(|p| {
-                                item["programmeID"]
-                                    .as_i64()
-                                    .map(|pi| pi == p)
-                                    .unwrap_or(false)
-                            })
-                            .unwrap_or(false);
-                        let code_ok = item["code"]
-                            .as_str()
-                            .map(|c| c.to_lowercase() == code.to_lowercase())
-                            .unwrap_or(false);
-
-                        if (meta_ok && prog_ok) || code_ok {
-                            let date = item["date"]
-                                .as_str()
-                                .or_else(|| item["from"].as_str().and_then(|s| s.split('T').next()))
-                                .unwrap_or("");
-                            let from_time = item["from"]
-                                .as_str()
-                                .and_then(|s| {
-                                    if s.len() >= 5 {
-                                        Some(s[..5].to_string())
-                                    } else if s.len() >= 16 {
-                                        Some(s[11..16].to_string())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .unwrap_or_else(|| "".to_string());
-                            let until_time = item["until"]
-                                .as_str()
-                                .and_then(|s| {
-                                    if s.len() >= 5 {
-                                        Some(s[..5].to_string())
-                                    } else if s.len() >= 16 {
-                                        Some(s[11..16].to_string())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .unwrap_or_else(|| "".to_string());
-
-                            lessons.push(json!({
-                                "date": date,
-                                "from": from_time,
-                                "until": until_time,
-                                "room": item["room"].as_str().unwrap_or("TBA"),
-                                "teacher": item["staff"].as_str()
-                                    .or_else(|| item["teacher"].as_str())
-                                    .unwrap_or("")
-                            }));
-                        }
-                    }
+        if let Ok(items) = fetch_timetable_range(from, until).await {
+            for item in &items {
+                let meta_ok = metaclass
+                    .map(|m| item["metaID"].as_i64().map(|mi| mi == m).unwrap_or(false))
+                    .unwrap_or(false);
+                let prog_ok = programme
+                    .map(|p| {
+                        item["programmeID"]
+                            .as_i64()
+                            .map(|pi| pi == p)
+                            .unwrap_or(false)
+                    })
+                    .unwrap_or(false);
+                let code_ok = item["code"]
+                    .as_str()
+                    .map(|c| c.to_lowercase() == code.to_lowercase())
+                    .unwrap_or(false);
+
+                if (meta_ok && prog_ok) || code_ok {
+                    let date = item["date"]
+                        .as_str()
+                        .or_else(|| item["from"].as_str().and_then(|s| s.split('T').next()))
+                        .unwrap_or("");
+                    let from_time = item["from"]
+                        .as_str()
+                        .and_then(|s| {
+                            if s.len() >= 5 {
+                                Some(s[..5].to_string())
+                            } else if s.len() >= 16 {
+                                Some(s[11..16].to_string())
+                            } else {
+                                None
+                            }
+                        })
+                        .unwrap_or_else(|| "".to_string());
+                    let until_time = item["until"]
+                        .as_str()
+                        .and_then(|s| {
+                            if s.len() >= 5 {
+                                Some(s[..5].to_string())
+                            } else if s.len() >= 16 {
+                                Some(s[11..16].to_string())
+                            } else {
+                                None
+                            }
+                        })
+                        .unwrap_or_else(|| "".to_string());
+
+                    lessons.push(json!({
+                        "date": date,
+                        "from": from_time,
+                        "until": until_time,
+                        "room": item["room"].as_str().unwrap_or("TBA"),
+                        "teacher": item["staff"].as_str()
+                            .or_else(|| item["teacher"].as_str())
+                            .unwrap_or("")
+                    }));
                 }
             }
         }
@@ -1327,8 +1699,7 @@ async fn fetch_subject_by_id(id: String) -> Result<Option<SeqtaMentionItem>> {
     .await
     .map_err(|e| anyhow!("Failed to fetch subjects: {}", e))?;
 
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let folders = json_response["payload"]
         .as_array()
@@ -1407,43 +1778,11 @@ async fn fetch_timetable_slot_by_id(
         .and_then(|d| d.get("id"))
         .and_then(|v| v.as_i64());
 
-    let start_date = date
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
-    let end_date = date.map(|s| s.to_string()).unwrap_or_else(|| {
-        (chrono::Utc::now() + chrono::Duration::days(14))
-            .format("%Y-%m-%d")
-            .to_string()
-    });
-
-    let body = json!({
-        "from": start_date,
-        "until": end_date,
-        "student": 69
-    });
-
-    let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
-
-    let response = netgrab::fetch_api_data(
-        "/seqta/student/load/timetable?",
-        netgrab::RequestMethod::POST,
-        Some(headers),
-        Some(body),
-        None,
-        false,
-        false,
-        None,
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to fetch timetable: {}", e))?;
-
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
-
-    let items = json_response["payload"]["items"]
-        .as_array()
-        .map(|v| v.as_slice())
-        .unwrap_or(EMPTY_ARRAY);
+    let (from, until) = date
+        .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .map(|d| (d, d))
+        .unwrap_or_else(default_slot_window);
+    let items = fetch_timetable_range(from, until).await?;
 
     let lesson = items.iter().find(|l| {
         if let Some(lid) = lesson_id {
@@ -1506,7 +1845,7 @@ async fn fetch_timetable_slot_by_id(
             .unwrap_or_else(|| "".to_string());
 
         let code = lesson_val["code"].as_str().unwrap_or("");
-        let subject_name = code.to_string(); // Could fetch from subjects API but keeping simple
+        let subject_name = subject_titles().await.lesson_title(lesson_val);
 
         return Ok(Some(SeqtaMentionItem {
             id: format!("timetable-slot-{}", lesson_val["id"].as_i64().unwrap_or(0)),
@@ -1523,6 +1862,7 @@ async fn fetch_timetable_slot_by_id(
                 "from": from_time_str,
                 "until": until_time_str,
                 "code": code,
+                "subject": subject_name,
                 "title": lesson_val.get("title").or_else(|| lesson_val.get("description")).cloned(),
                 "room": lesson_val["room"].clone(),
                 "teacher": lesson_val.get("staff").or_else(|| lesson_val.get("teacher")).cloned(),
@@ -1545,7 +1885,7 @@ async fn fetch_notice_by_id(id: String, meta: Option<Value>) -> Result<Option<Se
         .and_then(|d| d.get("date"))
         .and_then(|v| v.as_str())
         .map(|s| s.to_string())
-        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+        .unwrap_or_else(|| timezone::today().format("%Y-%m-%d").to_string());
     let date = date_str.as_str();
 
     let body = json!({ "date": date });
@@ -1564,8 +1904,7 @@ async fn fetch_notice_by_id(id: String, meta: Option<Value>) -> Result<Option<Se
     .await
     .map_err(|e| anyhow!("Failed to fetch notices: {}", e))?;
 
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let notices = json_response["payload"]
         .as_array()
@@ -1634,8 +1973,7 @@ async fn fetch_homework_by_id(
     .await
     .map_err(|e| anyhow!("Failed to fetch homework: {}", e))?;
 
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
 
     let homework_items = json_response["payload"]
         .as_array()
@@ -1708,11 +2046,7 @@ async fn fetch_teacher_by_id(id: String, _meta: Option<Value>) -> Result<Option<
     .await
     .map_err(|e| anyhow!("Failed to fetch staff: {}", e))?;
 
-    let json_response: Value = if response.starts_with('{') {
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?
-    } else {
-        json!({})
-    };
+    let json_response = parse_staff_payload(&response)?;
 
     let staff = json_response["payload"]
         .as_array()
@@ -1771,48 +2105,29 @@ async fn fetch_timetable_by_id(
             .and_then(|d| d.get("date"))
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
-            .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| timezone::today().format("%Y-%m-%d").to_string())
     } else {
         date_str
     };
 
-    let body = json!({
-        "from": date.as_str(),
-        "until": date.as_str(),
-        "student": 69
-    });
-
-    let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
-
-    let response = netgrab::fetch_api_data(
-        "/seqta/student/load/timetable?",
-        netgrab::RequestMethod::POST,
-        Some(headers),
-        Some(body),
-        None,
-        false,
-        false,
-        None,
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to fetch timetable: {}", e))?;
-
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| anyhow!("Failed to parse response: {}", e))?;
-
-    let items = json_response["payload"]["items"]
-        .as_array()
-        .map(|v| v.as_slice())
-        .unwrap_or(EMPTY_ARRAY);
+    let day = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid timetable date: {}", date))?;
+    let items = fetch_timetable_range(day, day).await?;
 
+    let subjects = subject_titles().await;
     let classes: Vec<Value> = items
         .iter()
         .map(|lesson| {
-            let subject = lesson
-                .get("title")
-                .or_else(|| lesson.get("code"))
-                .cloned()
-                .unwrap_or(json!(""));
+            let code = lesson["code"].as_str().unwrap_or("");
+            let subject = subjects
+                .resolve(code, lesson["programmeID"].as_i64(), lesson["metaID"].as_i64())
+                .or_else(|| {
+                    lesson["title"]
+                        .as_str()
+                        .filter(|t| !t.trim().is_empty())
+                        .map(|t| t.to_string())
+                })
+                .unwrap_or_else(|| code.to_string());
             let teacher = lesson
                 .get("staff")
                 .or_else(|| lesson.get("teacher"))
@@ -1820,6 +2135,7 @@ async fn fetch_timetable_by_id(
                 .unwrap_or(json!(""));
             json!({
                 "subject": subject,
+                "code": code,
                 "time": format!("{} - {}",
                     lesson["from"].as_str()
                         .and_then(|s| if s.len() >= 5 { Some(&s[..5]) } else { None })
@@ -1905,27 +2221,109 @@ pub async fn update_mention_data(
     }
 }
 
-/// Tauri command: Search mentions
+fn mention_search_channels() -> std::sync::MutexGuard<'static, Vec<MentionSearchChannel>> {
+    MENTION_SEARCH_CHANNELS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Mark every earlier search on `channel` stale and abort the one in flight, replacing it
+/// with `next`. Returns the new sequence number and whether a search was aborted.
+fn supersede_mention_search(channel: &str, next: Option<AbortHandle>) -> (u64, bool) {
+    let mut channels = mention_search_channels();
+    let seq = MENTION_SEARCH_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    let index = match channels.iter().position(|c| c.channel == channel) {
+        Some(index) => index,
+        None => {
+            channels.push(MentionSearchChannel {
+                channel: channel.to_string(),
+                latest_seq: 0,
+                in_flight: None,
+            });
+            channels.len() - 1
+        }
+    };
+    let entry = &mut channels[index];
+    entry.latest_seq = seq;
+    let previous = std::mem::replace(&mut entry.in_flight, next);
+    if let Some(handle) = &previous {
+        handle.abort();
+    }
+    (seq, previous.is_some())
+}
+
+/// Run `search` as the newest mention search on `channel`. The previous one there is
+/// aborted, dropping its SEQTA requests, and a search that's superseded (even one that
+/// already finished) returns `None` so results never arrive out of order.
+async fn run_latest_mention_search<T>(
+    channel: &str,
+    search: impl Future<Output = Result<T>>,
+) -> std::result::Result<Option<T>, String> {
+    let (handle, registration) = AbortHandle::new_pair();
+    let (seq, _) = supersede_mention_search(channel, Some(handle));
+
+    let outcome = Abortable::new(search, registration).await;
+
+    let mut channels = mention_search_channels();
+    let latest = match channels.iter_mut().find(|c| c.channel == channel) {
+        Some(entry) if entry.latest_seq == seq => {
+            entry.in_flight = None;
+            true
+        }
+        _ => false,
+    };
+    drop(channels);
+
+    match outcome {
+        Ok(result) if latest => result.map(Some).map_err(|e| e.to_string()),
+        _ => Ok(None),
+    }
+}
+
+/// Tauri command: Cancel the mention search in flight on `channel` (the editor's by
+/// default), e.g. when the picker closes. Returns whether a search was running.
+#[tauri::command]
+pub fn cancel_mention_search(channel: Option<String>) -> bool {
+    let channel = channel.as_deref().unwrap_or(DEFAULT_MENTION_SEARCH_CHANNEL);
+    supersede_mention_search(channel, None).1
+}
+
+/// Tauri command: Search mentions. `timetable_from`/`timetable_until` (YYYY-MM-DD, both or
+/// neither) widen the window searched for timetable slots, e.g. to the whole term. Starting
+/// a search cancels the previous one on the same `channel` (the editor's by default), which
+/// then returns results marked `cancelled`.
 #[tauri::command]
 pub async fn search_seqta_mentions(
     query: String,
     category_filter: Option<String>,
-) -> Result<Vec<SeqtaMentionItem>, String> {
-    search_mentions(query, category_filter)
-        .await
-        .map_err(|e| e.to_string())
+    timetable_from: Option<String>,
+    timetable_until: Option<String>,
+    channel: Option<String>,
+) -> Result<MentionSearchResults, String> {
+    let window = parse_timetable_window(timetable_from.as_deref(), timetable_until.as_deref())
+        .map_err(|e| e.to_string())?;
+    let channel = channel.as_deref().unwrap_or(DEFAULT_MENTION_SEARCH_CHANNEL);
+    let results =
+        run_latest_mention_search(channel, search_mentions(query, category_filter, window)).await?;
+    Ok(results.unwrap_or_else(MentionSearchResults::cancelled))
 }
 
-/// Tauri command: Search mentions with context
+/// Tauri command: Search mentions with context. Supersedes other searches on its channel
+/// like `search_seqta_mentions`.
 #[tauri::command]
 pub async fn search_seqta_mentions_with_context(
     query: String,
     note_content: String,
     category_filter: Option<String>,
-) -> Result<Vec<SeqtaMentionItem>, String> {
-    search_mentions_with_context(query, note_content, category_filter)
-        .await
-        .map_err(|e| e.to_string())
+    channel: Option<String>,
+) -> Result<MentionSearchResults, String> {
+    let channel = channel.as_deref().unwrap_or(DEFAULT_MENTION_SEARCH_CHANNEL);
+    let results = run_latest_mention_search(
+        channel,
+        search_mentions_with_context(query, note_content, category_filter),
+    )
+    .await?;
+    Ok(results.unwrap_or_else(MentionSearchResults::cancelled))
 }
 
 /// Tauri command: Update mention data
@@ -1940,115 +2338,151 @@ pub async fn update_seqta_mention_data(
         .map_err(|e| e.to_string())
 }
 
+/// One mention to refresh in `update_mentions_batch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MentionUpdateRequest {
+    #[serde(alias = "mentionId")]
+    pub mention_id: String,
+    #[serde(alias = "mentionType")]
+    pub mention_type: String,
+    #[serde(default)]
+    pub meta: Option<Value>,
+}
+
+/// Outcome of refreshing one mention. `item` is None with no error when SEQTA no longer has it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MentionUpdateResult {
+    pub item: Option<SeqtaMentionItem>,
+    pub error: Option<String>,
+}
+
+/// Drop repeated requests (same id, type and meta), keeping the first occurrence.
+fn dedupe_mention_requests(requests: Vec<MentionUpdateRequest>) -> Vec<MentionUpdateRequest> {
+    let mut seen = std::collections::HashSet::new();
+    requests
+        .into_iter()
+        .filter(|r| {
+            let meta = r.meta.as_ref().map(|m| m.to_string()).unwrap_or_default();
+            seen.insert((r.mention_id.clone(), r.mention_type.clone(), meta))
+        })
+        .collect()
+}
+
+/// Refresh many mentions concurrently (at most `MENTION_BATCH_CONCURRENCY` at a time).
+/// Results are keyed by mention id; one failing mention doesn't affect the others.
+pub async fn update_mentions(
+    requests: Vec<MentionUpdateRequest>,
+) -> HashMap<String, MentionUpdateResult> {
+    stream::iter(dedupe_mention_requests(requests).into_iter().map(|request| async move {
+        let result = match update_mention_data(
+            request.mention_id.clone(),
+            request.mention_type,
+            request.meta,
+        )
+        .await
+        {
+            Ok(item) => MentionUpdateResult { item, error: None },
+            Err(e) => MentionUpdateResult {
+                item: None,
+                error: Some(e.to_string()),
+            },
+        };
+        (request.mention_id, result)
+    }))
+    .buffer_unordered(MENTION_BATCH_CONCURRENCY)
+    .collect()
+    .await
+}
+
+/// Tauri command: Refresh several mentions in one call
+#[tauri::command]
+pub async fn update_mentions_batch(
+    requests: Vec<MentionUpdateRequest>,
+) -> Result<HashMap<String, MentionUpdateResult>, String> {
+    Ok(update_mentions(requests).await)
+}
+
 /// Get weekly schedule for a class
 pub async fn get_weekly_schedule_for_class(
     programme: Option<i64>,
     metaclass: Option<i64>,
     code: Option<String>,
 ) -> Result<Vec<serde_json::Map<String, Value>>, String> {
-    let student_id = 69; // TODO: Get from session
     let mut collected: Vec<serde_json::Map<String, Value>> = Vec::new();
 
-    // Go back 6 steps (~2 months each, up to ~1 year)
-    for i in 0..6 {
-        let anchor = chrono::Utc::now() - chrono::Duration::days(i * 60);
-        let day = anchor.weekday().num_days_from_sunday();
-
-        // Find Monday of the anchor week
-        let delta_to_monday = if day == 0 { -6 } else { 1 - day as i64 };
-        let monday = anchor + chrono::Duration::days(delta_to_monday);
-        let friday = monday + chrono::Duration::days(4);
-
-        let from = monday.format("%Y-%m-%d").to_string();
-        let until = friday.format("%Y-%m-%d").to_string();
-
-        let body = json!({
-            "from": from,
-            "until": until,
-            "student": student_id
-        });
+    // Start with the most recent weeks and only reach further back (up to a year) for a class
+    // that hasn't met lately
+    let today = timezone::today();
+    let earliest = today - chrono::Duration::days(WEEKLY_SCHEDULE_LOOKBACK_DAYS);
+    let mut until = today + chrono::Duration::days(DEFAULT_SLOT_WINDOW_DAYS);
+    while collected.is_empty() && until >= earliest {
+        let from = (until - chrono::Duration::days(WEEKLY_SCHEDULE_WINDOW_DAYS - 1)).max(earliest);
+        let items = fetch_timetable_range(from, until)
+            .await
+            .map_err(|e| e.to_string())?;
+        for item in &items {
+            let meta_ok = metaclass
+                .map(|m| item["metaID"].as_i64().map(|mi| mi == m).unwrap_or(false))
+                .unwrap_or(false);
+            let prog_ok = programme
+                .map(|p| {
+                    item["programmeID"]
+                        .as_i64()
+                        .map(|pi| pi == p)
+                        .unwrap_or(false)
+                })
+                .unwrap_or(false);
+            let code_ok = code
+                .as_ref()
+                .map(|c| {
+                    item["code"]
+                        .as_str()
+                        .map(|ic| ic.to_lowercase() == c.to_lowercase())
+                        .unwrap_or(false)
+                })
+                .unwrap_or(false);
 
-        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
-
-        if let Ok(response) = netgrab::fetch_api_data(
-            "/seqta/student/load/timetable?",
-            netgrab::RequestMethod::POST,
-            Some(headers),
-            Some(body),
-            None,
-            false,
-            false,
-            None,
-        )
-        .await
-        {
-            if let Ok(json_response) = serde_json::from_str::<Value>(&response) {
-                if let Some(items) = json_response["payload"]["items"].as_array() {
-                    for item in items {
-                        let meta_ok = metaclass
-                            .map(|m| item["metaID"].as_i64().map(|mi| mi == m).unwrap_or(false))
-                            .unwrap_or(false);
-                        let prog_ok = programme
-                            .map(|p| {
-                                item["programmeID"]
-                                    .as_i64()
-                                    .map(|pi| pi == p)
-                                    .unwrap_or(false)
-                            })
-                            .unwrap_or(false);
-                        let code_ok = code
-                            .as_ref()
-                            .map(|c| {
-                                item["code"]
-                                    .as_str()
-                                    .map(|ic| ic.to_lowercase() == c.to_lowercase())
-                                    .unwrap_or(false)
-                            })
-                            .unwrap_or(false);
-
-                        if (meta_ok && prog_ok) || code_ok {
-                            let date = item["date"]
-                                .as_str()
-                                .or_else(|| item["from"].as_str().and_then(|s| s.split('T').next()))
-                                .unwrap_or("");
-                            let from_time = item["from"]
-                                .as_str()
-                                .and_then(|s| {
-                                    if s.len() >= 5 {
-                                        Some(s[..5].to_string())
-                                    } else if s.len() >= 16 {
-                                        Some(s[11..16].to_string())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .unwrap_or_else(|| "".to_string());
-                            let until_time = item["until"]
-                                .as_str()
-                                .and_then(|s| {
-                                    if s.len() >= 5 {
-                                        Some(s[..5].to_string())
-                                    } else if s.len() >= 16 {
-                                        Some(s[11..16].to_string())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .unwrap_or_else(|| "".to_string());
-
-                            let mut entry = serde_json::Map::new();
-                            entry.insert("date".to_string(), json!(date));
-                            entry.insert("from".to_string(), json!(from_time));
-                            entry.insert("until".to_string(), json!(until_time));
-                            if let Some(room) = item["room"].as_str() {
-                                entry.insert("room".to_string(), json!(room));
-                            }
-                            collected.push(entry);
+            if (meta_ok && prog_ok) || code_ok {
+                let date = item["date"]
+                    .as_str()
+                    .or_else(|| item["from"].as_str().and_then(|s| s.split('T').next()))
+                    .unwrap_or("");
+                let from_time = item["from"]
+                    .as_str()
+                    .and_then(|s| {
+                        if s.len() >= 5 {
+                            Some(s[..5].to_string())
+                        } else if s.len() >= 16 {
+                            Some(s[11..16].to_string())
+                        } else {
+                            None
                         }
-                    }
+                    })
+                    .unwrap_or_else(|| "".to_string());
+                let until_time = item["until"]
+                    .as_str()
+                    .and_then(|s| {
+                        if s.len() >= 5 {
+                            Some(s[..5].to_string())
+                        } else if s.len() >= 16 {
+                            Some(s[11..16].to_string())
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or_else(|| "".to_string());
+
+                let mut entry = serde_json::Map::new();
+                entry.insert("date".to_string(), json!(date));
+                entry.insert("from".to_string(), json!(from_time));
+                entry.insert("until".to_string(), json!(until_time));
+                if let Some(room) = item["room"].as_str() {
+                    entry.insert("room".to_string(), json!(room));
                 }
+                collected.push(entry);
             }
         }
+        until = from - chrono::Duration::days(1);
     }
 
     // Deduplicate by weekday and time range
@@ -2091,13 +2525,26 @@ pub async fn get_weekly_schedule_for_class(
     Ok(deduped)
 }
 
-/// Fetch lesson content for a class
-pub async fn fetch_lesson_content(
-    programme: i64,
-    metaclass: i64,
-    lesson_index: Option<usize>,
-    term_index: Option<usize>,
-) -> Result<Option<Value>, String> {
+/// Every lesson of a class (`payload.w`, lessons grouped by term), from the cache when it's
+/// fresh. `None` when SEQTA returned no lesson content.
+pub async fn fetch_course_lessons(programme: i64, metaclass: i64) -> Result<Option<Value>, String> {
+    init_caches();
+    let key = format!("{}-{}", programme, metaclass);
+    let cached = COURSE_CACHE
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .get(&key)
+        .cloned();
+    if let Some((fetched, lessons)) = cached {
+        if current_timestamp_ms() - fetched < COURSE_CACHE_DURATION_MS {
+            COURSE_CACHE_STATS.hit();
+            return Ok(lessons);
+        }
+    }
+    COURSE_CACHE_STATS.miss();
+
     let body = json!({
         "programme": programme.to_string(),
         "metaclass": metaclass.to_string(),
@@ -2121,11 +2568,29 @@ pub async fn fetch_lesson_content(
     .await
     .map_err(|e| format!("Failed to fetch lesson content: {}", e))?;
 
-    let json_response: Value =
-        serde_json::from_str(&response).map_err(|e| format!("Failed to parse response: {}", e))?;
+    let json_response = parse_seqta_payload(&response)?;
+    let lessons = json_response
+        .get("payload")
+        .and_then(|p| p.get("w"))
+        .cloned();
 
-    let course_payload = json_response.get("payload");
-    if let Some(w) = course_payload.and_then(|p| p.get("w")) {
+    COURSE_CACHE
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .insert(key, (current_timestamp_ms(), lessons.clone()));
+    Ok(lessons)
+}
+
+/// Fetch lesson content for a class
+pub async fn fetch_lesson_content(
+    programme: i64,
+    metaclass: i64,
+    lesson_index: Option<usize>,
+    term_index: Option<usize>,
+) -> Result<Option<Value>, String> {
+    if let Some(w) = fetch_course_lessons(programme, metaclass).await? {
         // If lessonIndex and termIndex provided, return specific lesson
         if let (Some(li), Some(ti)) = (lesson_index, term_index) {
             if let Some(term) = w.as_array().and_then(|terms| terms.get(ti)) {
@@ -2137,7 +2602,7 @@ pub async fn fetch_lesson_content(
         }
 
         // Otherwise return all lessons
-        return Ok(Some(w.clone()));
+        return Ok(Some(w));
     }
 
     Ok(None)
@@ -2163,3 +2628,191 @@ pub async fn fetch_lesson_content_cmd(
 ) -> Result<Option<Value>, String> {
     fetch_lesson_content(programme, metaclass, lesson_index, term_index).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let format = |date: &str, language: &str| format_date_for(date, language, today);
+
+        assert_eq!(format("2024-05-01", "en"), "Wed 1 May");
+        assert_eq!(format("2024-05-01T00:00:00Z", "en"), "Wed 1 May");
+        assert_eq!(format("2024-05-01 15:30:00", "en"), "Wed 1 May 3:30pm");
+        assert_eq!(
+            format("2023-11-20T09:05:00+10:00", "en"),
+            "Mon 20 Nov 2023 9:05am"
+        );
+        assert_eq!(format("2024-05-01T00:00:00Z", "en-pirate"), "Wed 1 May");
+
+        assert_eq!(format("2024-05-01", "de"), "Mi., 1. Mai");
+        assert_eq!(format("2024-05-01 15:30:00", "de"), "Mi., 1. Mai 15:30");
+        assert_eq!(format("2024-05-01", "fr"), "mer. 1 mai");
+        assert_eq!(format("2024-05-01", "ja"), "5月1日(水)");
+        assert_eq!(format("2025-05-01", "ja"), "2025年5月1日(木)");
+
+        // Unparseable dates are shown as SEQTA sent them
+        assert_eq!(format("", "en"), "");
+        assert_eq!(format("next Tuesday", "de"), "next Tuesday");
+        assert_eq!(format("2024-13-45", "en"), "2024-13-45");
+    }
+
+    #[test]
+    fn test_parse_seqta_payload_login_page_is_unauthorized() {
+        let login_page = r#"<!DOCTYPE html>
+<html><head><title>SEQTA Learn</title></head>
+<body><form action="/login" method="post">
+<input name="username"><input type="password" name="password">
+</form></body></html>"#;
+        let err = parse_seqta_payload(login_page).unwrap_err();
+        assert!(err.is_unauthorized());
+        assert_eq!(
+            err.to_string(),
+            "SEQTA returned its login page. Please log in again."
+        );
+    }
+
+    #[test]
+    fn test_parse_seqta_payload_unexpected_bodies() {
+        assert_eq!(
+            parse_seqta_payload("  "),
+            Err(netgrab::NetError::Parse(
+                "SEQTA returned an empty response".to_string()
+            ))
+        );
+        let err = parse_seqta_payload("<html><body>502 Bad Gateway</body></html>").unwrap_err();
+        assert!(matches!(err, netgrab::NetError::Parse(_)));
+        assert!(!err.is_unauthorized());
+        assert!(matches!(
+            parse_seqta_payload("Service Unavailable"),
+            Err(netgrab::NetError::Parse(_))
+        ));
+
+        // The staff list falls back to no staff, except for a login page or broken JSON
+        assert_eq!(parse_staff_payload("Service Unavailable"), Ok(json!({})));
+        assert_eq!(parse_staff_payload(""), Ok(json!({})));
+        assert!(parse_staff_payload("<html><form>Login</form></html>")
+            .unwrap_err()
+            .is_unauthorized());
+        assert!(parse_staff_payload("{\"payload\": [").is_err());
+    }
+
+    #[test]
+    fn test_timetable_chunks_cover_term() {
+        let date = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let (from, until) = (date("2025-04-28"), date("2025-07-04"));
+        let chunks = timetable_chunks(from, until);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.first().unwrap().0, from);
+        assert_eq!(chunks.last().unwrap().1, until);
+        for (start, end) in &chunks {
+            assert!(start <= end);
+            assert!((*end - *start).num_days() < TIMETABLE_CHUNK_DAYS);
+        }
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].1 + chrono::Duration::days(1), pair[1].0);
+        }
+
+        let day = date("2025-05-01");
+        assert_eq!(timetable_chunks(day, day), vec![(day, day)]);
+        assert!(timetable_chunks(until, from).is_empty());
+    }
+
+    #[test]
+    fn test_parse_timetable_window() {
+        assert!(parse_timetable_window(None, None).unwrap().is_none());
+        let (from, until) = parse_timetable_window(Some("2025-04-28"), Some(" 2025-07-04 "))
+            .unwrap()
+            .unwrap();
+        assert_eq!(from.to_string(), "2025-04-28");
+        assert_eq!(until.to_string(), "2025-07-04");
+        assert!(parse_timetable_window(Some("2025-04-28"), None).is_err());
+        assert!(parse_timetable_window(Some("28/04/2025"), Some("2025-07-04")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_newer_mention_search_cancels_older() {
+        let slow = tokio::spawn(run_latest_mention_search("test-picker", async {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            Ok(1)
+        }));
+        let other = tokio::spawn(run_latest_mention_search("test-detail", async {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            Ok(3)
+        }));
+        // Let the slow searches register before superseding one
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(
+            run_latest_mention_search("test-picker", async { Ok(2) }).await,
+            Ok(Some(2))
+        );
+
+        let stale = tokio::time::timeout(std::time::Duration::from_secs(5), slow)
+            .await
+            .expect("superseded search should stop straight away")
+            .unwrap();
+        assert_eq!(stale, Ok(None));
+        // A search on another channel isn't affected
+        assert_eq!(other.await.unwrap(), Ok(Some(3)));
+        assert!(!cancel_mention_search(Some("test-picker".to_string())));
+    }
+
+    #[test]
+    fn test_category_filter_limits_mention_sources() {
+        assert_eq!(
+            mention_sources(Some("notice")),
+            vec![MentionSource::Notices]
+        );
+        assert_eq!(mention_sources(Some("teacher")), vec![MentionSource::Staff]);
+        assert_eq!(
+            mention_sources(Some("assessment")),
+            vec![MentionSource::Assignments]
+        );
+        assert!(mention_sources(Some("file")).is_empty());
+        assert_eq!(mention_sources(None), ALL_MENTION_SOURCES.to_vec());
+        assert_eq!(mention_sources(Some("unknown")).len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_failed_mention_source_returns_partial_results() {
+        let item = |id: &str| SeqtaMentionItem {
+            id: id.to_string(),
+            mention_type: MentionType::Class,
+            title: id.to_string(),
+            subtitle: String::new(),
+            data: Value::Null,
+            last_updated: None,
+        };
+        let sources = vec![
+            MentionSource::Assignments,
+            MentionSource::Notices,
+            MentionSource::Classes,
+        ];
+        let (items, failed) = fetch_mention_sources(sources.clone(), |source| async move {
+            match source {
+                MentionSource::Notices => Err(anyhow!("notices endpoint timed out")),
+                other => Ok(vec![item(other.name())]),
+            }
+        })
+        .await
+        .unwrap();
+        let ids: Vec<&str> = items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["assignments", "classes"]);
+        assert_eq!(failed, vec!["notices".to_string()]);
+
+        // Nothing to show at all is still an error
+        let err = fetch_mention_sources(sources, |_| async { Err(anyhow!("offline")) })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "offline");
+    }
+
+    #[test]
+    fn test_parse_seqta_payload_json() {
+        let value = parse_seqta_payload("\u{feff}{\"status\":\"200\",\"payload\":[1]}").unwrap();
+        assert_eq!(value["payload"][0], 1);
+    }
+}
@@ -0,0 +1,570 @@
+//! Gemini requests made from Rust, so the API key stays out of the webview.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::logger;
+use crate::netgrab;
+use crate::settings;
+
+const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+/// Same model the frontend analysers use.
+const DEFAULT_GEMINI_MODEL: &str = "gemini-2.5-flash-lite";
+const KEYRING_SERVICE: &str = "DesQTA";
+const GEMINI_KEYRING_USER: &str = "gemini_api_key";
+const AI_REQUEST_TIMEOUT_SECS: u64 = 60;
+/// Upper bound for `timeout_secs`, long enough for a streamed answer on slow school WiFi.
+const MAX_AI_REQUEST_TIMEOUT_SECS: u64 = 300;
+
+/// Why an AI request failed. Sent to the frontend as `{ kind, message }` so it can show a
+/// specific hint (add a key, wait for the quota, rephrase) instead of a generic error.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum AiError {
+    /// `ai_integrations_enabled` is off
+    Disabled,
+    /// The AI feature itself (e.g. the grade analyser) is off
+    FeatureDisabled(String),
+    MissingKey,
+    /// The key was rejected
+    Auth(String),
+    /// Rate limit or quota exhausted
+    Quota(String),
+    /// The prompt or the answer was blocked by Gemini's safety filters
+    SafetyBlocked(String),
+    Timeout(String),
+    Network(String),
+    /// Any other error reported by the API
+    Api(String),
+    InvalidResponse(String),
+}
+
+impl std::fmt::Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AiError::Disabled => write!(f, "AI integrations are turned off in Settings"),
+            AiError::MissingKey => write!(f, "No Gemini API key set. Please add one in Settings."),
+            AiError::FeatureDisabled(m)
+            | AiError::Auth(m)
+            | AiError::Quota(m)
+            | AiError::SafetyBlocked(m)
+            | AiError::Timeout(m)
+            | AiError::Network(m)
+            | AiError::Api(m)
+            | AiError::InvalidResponse(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            AiError::Timeout("The AI request timed out".to_string())
+        } else {
+            AiError::Network(e.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AiGenerateOptions {
+    /// Gemini model id; defaults to `DEFAULT_GEMINI_MODEL`
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub system_instruction: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+    /// Ask Gemini to answer with JSON only
+    #[serde(default)]
+    pub json_response: bool,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Emit `ai-generate-chunk` events as the answer arrives
+    #[serde(default)]
+    pub stream: bool,
+    /// Echoed in stream events so the caller can tell concurrent requests apart
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+/// Payload of the `ai-generate-chunk` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiGenerateChunk {
+    pub request_id: Option<String>,
+    pub text: String,
+    pub done: bool,
+}
+
+fn gemini_keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, GEMINI_KEYRING_USER)
+        .map_err(|e| format!("Failed to access keyring: {}", e))
+}
+
+/// Move a Gemini key kept in `settings` (saved before keys moved to the keychain, or sent in a
+/// settings patch) into the keychain and blank the settings copy. A blank key is just dropped.
+/// The copy stays put if the keychain can't be written. Returns whether `settings` changed.
+pub fn migrate_legacy_gemini_key(settings: &mut settings::Settings) -> bool {
+    let Some(key) = settings.gemini_api_key.take() else {
+        return false;
+    };
+    if !key.trim().is_empty() {
+        let stored = gemini_keyring_entry().and_then(|entry| {
+            entry
+                .set_password(key.trim())
+                .map_err(|e| format!("Failed to store API key: {}", e))
+        });
+        if let Err(e) = stored {
+            println!("[AI] Keeping Gemini key in settings: {}", e);
+            settings.gemini_api_key = Some(key);
+            return false;
+        }
+    }
+    true
+}
+
+/// The Gemini key from the OS keychain. A key still kept in settings is moved there first,
+/// and used from settings when the keychain isn't available.
+fn gemini_api_key() -> Option<String> {
+    let mut settings = settings::Settings::load();
+    if migrate_legacy_gemini_key(&mut settings) {
+        if let Err(e) = settings.save() {
+            println!("[AI] Failed to clear migrated Gemini key: {}", e);
+        }
+    }
+    gemini_keyring_entry()
+        .ok()
+        .and_then(|entry| entry.get_password().ok())
+        .or(settings.gemini_api_key)
+        .filter(|key| !key.trim().is_empty())
+}
+
+/// `model` if it's a plain Gemini model id, so it can't change the request URL.
+fn validate_model(model: &str) -> Result<&str, AiError> {
+    if !model.is_empty()
+        && model
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        Ok(model)
+    } else {
+        Err(AiError::Api(format!("Invalid Gemini model \"{}\"", model)))
+    }
+}
+
+/// Store the Gemini key in the OS keychain (or remove it when `key` is empty) and drop any
+/// copy kept in the settings file.
+#[tauri::command]
+pub fn set_gemini_api_key(key: Option<String>) -> Result<(), String> {
+    let entry = gemini_keyring_entry()?;
+    match key.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        Some(key) => entry
+            .set_password(key)
+            .map_err(|e| format!("Failed to store API key: {}", e))?,
+        None => {
+            let _ = entry.delete_password();
+        }
+    }
+
+    let mut settings = settings::Settings::load();
+    if settings.gemini_api_key.is_some() {
+        settings.gemini_api_key = None;
+        settings.save().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Whether a Gemini key is available, without revealing it.
+#[tauri::command]
+pub fn has_gemini_api_key() -> bool {
+    gemini_api_key().is_some()
+}
+
+fn request_body(prompt: &str, options: &AiGenerateOptions) -> Value {
+    let mut body = json!({
+        "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+    });
+    if let Some(instruction) = &options.system_instruction {
+        body["systemInstruction"] = json!({ "parts": [{ "text": instruction }] });
+    }
+    let mut config = serde_json::Map::new();
+    if let Some(temperature) = options.temperature {
+        config.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(max_tokens) = options.max_output_tokens {
+        config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+    }
+    if options.json_response {
+        config.insert("responseMimeType".to_string(), json!("application/json"));
+    }
+    if !config.is_empty() {
+        body["generationConfig"] = Value::Object(config);
+    }
+    body
+}
+
+/// Map a failed Gemini response (`{"error": {"code", "message", "status"}}`) to an error kind.
+fn classify_error(status: u16, body: &str) -> AiError {
+    let error = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").cloned())
+        .unwrap_or(Value::Null);
+    let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Gemini request failed with HTTP status {}", status));
+    let api_status = error.get("status").and_then(|s| s.as_str()).unwrap_or("");
+
+    if status == 429 || api_status == "RESOURCE_EXHAUSTED" {
+        AiError::Quota(message)
+    } else if status == 401
+        || status == 403
+        || matches!(api_status, "UNAUTHENTICATED" | "PERMISSION_DENIED")
+        || message.contains("API key")
+    {
+        AiError::Auth(message)
+    } else {
+        AiError::Api(message)
+    }
+}
+
+/// Text of one `generateContent` response (or streamed chunk). Blocked prompts and answers
+/// cut off for safety become `SafetyBlocked`.
+fn response_text(response: &Value) -> Result<String, AiError> {
+    if let Some(reason) = response
+        .pointer("/promptFeedback/blockReason")
+        .and_then(|r| r.as_str())
+    {
+        return Err(AiError::SafetyBlocked(format!(
+            "The prompt was blocked ({})",
+            reason
+        )));
+    }
+
+    let Some(candidate) = response.pointer("/candidates/0") else {
+        return Err(AiError::InvalidResponse(
+            "Gemini returned no answer".to_string(),
+        ));
+    };
+    let text: String = candidate
+        .pointer("/content/parts")
+        .and_then(|parts| parts.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let finish_reason = candidate
+        .get("finishReason")
+        .and_then(|r| r.as_str())
+        .unwrap_or("");
+    if text.is_empty()
+        && matches!(
+            finish_reason,
+            "SAFETY" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" | "RECITATION"
+        )
+    {
+        return Err(AiError::SafetyBlocked(format!(
+            "The answer was blocked ({})",
+            finish_reason
+        )));
+    }
+    Ok(text)
+}
+
+/// Complete server-sent events in `buffer`, removed from it. Returns each event's `data:`.
+/// Bytes are only decoded once their event is complete, so a character split across network
+/// chunks stays whole.
+fn take_sse_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = Vec::new();
+    while let Some(end) = find_event_end(buffer) {
+        let event: Vec<u8> = buffer.drain(..end).collect();
+        let event = String::from_utf8_lossy(&event).replace("\r\n", "\n");
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim)
+            .collect();
+        if !data.is_empty() {
+            events.push(data.join("\n"));
+        }
+    }
+    events
+}
+
+/// Length of the first complete event in `buffer`, including its blank-line terminator.
+fn find_event_end(buffer: &[u8]) -> Option<usize> {
+    let lf = buffer.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    let crlf = buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4);
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Stream the answer as `ai-generate-chunk` events. The final `done` event is sent whether or
+/// not the stream finished cleanly.
+async fn read_stream(
+    app: &AppHandle,
+    resp: reqwest::Response,
+    request_id: &Option<String>,
+) -> Result<String, AiError> {
+    let result = stream_chunks(app, resp, request_id).await;
+    let _ = app.emit(
+        "ai-generate-chunk",
+        AiGenerateChunk {
+            request_id: request_id.clone(),
+            text: String::new(),
+            done: true,
+        },
+    );
+    result
+}
+
+async fn stream_chunks(
+    app: &AppHandle,
+    mut resp: reqwest::Response,
+    request_id: &Option<String>,
+) -> Result<String, AiError> {
+    let mut buffer = Vec::new();
+    let mut answer = String::new();
+    while let Some(chunk) = resp.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        for data in take_sse_events(&mut buffer) {
+            let value: Value = serde_json::from_str(&data)
+                .map_err(|e| AiError::InvalidResponse(format!("Bad stream chunk: {}", e)))?;
+            // Usage-only chunks carry no candidates
+            if value.get("candidates").is_none() && value.get("promptFeedback").is_none() {
+                continue;
+            }
+            let text = response_text(&value)?;
+            if !text.is_empty() {
+                answer.push_str(&text);
+                let _ = app.emit(
+                    "ai-generate-chunk",
+                    AiGenerateChunk {
+                        request_id: request_id.clone(),
+                        text,
+                        done: false,
+                    },
+                );
+            }
+        }
+    }
+    Ok(answer)
+}
+
+/// Cache key for an AI answer: the SHA-256 of the prompt it was asked with.
+pub(crate) fn prompt_hash(prompt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prompt.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// The JSON object in a model answer, ignoring a Markdown code fence or text around it.
+pub fn json_answer(text: &str) -> Option<Value> {
+    let start = text.find('{')?;
+    let end = text.rfind('}').filter(|end| *end > start)?;
+    serde_json::from_str(&text[start..=end]).ok()
+}
+
+/// Non-blank strings in the array under `key`, trimmed.
+pub fn answer_string_list(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Send `prompt` to Gemini with the stored API key and return the answer text. Shared by
+/// `ai_generate` and the backend features built on it. Requires `ai_integrations_enabled`.
+pub async fn generate(
+    app: &AppHandle,
+    prompt: &str,
+    options: AiGenerateOptions,
+) -> Result<String, AiError> {
+    if !settings::Settings::load()
+        .ai_integrations_enabled
+        .unwrap_or(false)
+    {
+        return Err(AiError::Disabled);
+    }
+    netgrab::ensure_online().map_err(|e| AiError::Network(e.to_string()))?;
+    let api_key = gemini_api_key().ok_or(AiError::MissingKey)?;
+
+    let model = validate_model(options.model.as_deref().unwrap_or(DEFAULT_GEMINI_MODEL))?;
+    let url = if options.stream {
+        format!(
+            "{}/{}:streamGenerateContent?alt=sse",
+            GEMINI_API_BASE, model
+        )
+    } else {
+        format!("{}/{}:generateContent", GEMINI_API_BASE, model)
+    };
+    let timeout = options
+        .timeout_secs
+        .unwrap_or(AI_REQUEST_TIMEOUT_SECS)
+        .clamp(1, MAX_AI_REQUEST_TIMEOUT_SECS);
+    let client = netgrab::create_client_builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+        .map_err(|e| AiError::Network(format!("Failed to build HTTP client: {}", e)))?;
+
+    let resp = client
+        .post(&url)
+        .header("x-goog-api-key", api_key)
+        .json(&request_body(prompt, &options))
+        .send()
+        .await?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        let error = classify_error(status.as_u16(), &body);
+        if let Some(logger) = logger::get_logger() {
+            let _ = logger.log(
+                logger::LogLevel::WARN,
+                "ai",
+                "ai_generate",
+                &format!("Gemini request failed: {}", error),
+                json!({ "model": model, "status": status.as_u16() }),
+            );
+        }
+        return Err(error);
+    }
+
+    if options.stream {
+        return read_stream(app, resp, &options.request_id).await;
+    }
+    let value: Value = resp
+        .json()
+        .await
+        .map_err(|e| AiError::InvalidResponse(format!("Failed to parse Gemini response: {}", e)))?;
+    response_text(&value)
+}
+
+/// Send `prompt` to Gemini with the stored API key and return the answer text. The key never
+/// leaves the backend. With `options.stream`, partial text is also emitted as
+/// `ai-generate-chunk` events. Requires `ai_integrations_enabled`.
+#[tauri::command]
+pub async fn ai_generate(
+    app: AppHandle,
+    prompt: String,
+    options: Option<AiGenerateOptions>,
+) -> Result<String, AiError> {
+    generate(&app, &prompt, options.unwrap_or_default()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_error() {
+        let quota =
+            r#"{"error":{"code":429,"message":"Quota exceeded","status":"RESOURCE_EXHAUSTED"}}"#;
+        assert_eq!(
+            classify_error(429, quota),
+            AiError::Quota("Quota exceeded".to_string())
+        );
+        let bad_key =
+            r#"{"error":{"code":400,"message":"API key not valid.","status":"INVALID_ARGUMENT"}}"#;
+        assert!(matches!(classify_error(400, bad_key), AiError::Auth(_)));
+        assert_eq!(
+            classify_error(500, "<html>"),
+            AiError::Api("Gemini request failed with HTTP status 500".to_string())
+        );
+    }
+
+    #[test]
+    fn test_response_text() {
+        let ok = json!({
+            "candidates": [{
+                "content": { "parts": [{ "text": "Hello" }, { "text": " world" }] },
+                "finishReason": "STOP"
+            }]
+        });
+        assert_eq!(response_text(&ok).unwrap(), "Hello world");
+
+        let blocked_prompt = json!({ "promptFeedback": { "blockReason": "SAFETY" } });
+        assert!(matches!(
+            response_text(&blocked_prompt),
+            Err(AiError::SafetyBlocked(_))
+        ));
+        let blocked_answer = json!({ "candidates": [{ "finishReason": "SAFETY" }] });
+        assert!(matches!(
+            response_text(&blocked_answer),
+            Err(AiError::SafetyBlocked(_))
+        ));
+        assert!(matches!(
+            response_text(&json!({})),
+            Err(AiError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_take_sse_events_keeps_partial_event() {
+        let mut buffer = b"data: {\"a\":1}\n\ndata: {\"b\"".to_vec();
+        assert_eq!(take_sse_events(&mut buffer), vec!["{\"a\":1}"]);
+        assert_eq!(buffer, b"data: {\"b\"");
+        buffer.extend_from_slice(b":2}\r\n\r\n");
+        assert_eq!(take_sse_events(&mut buffer), vec!["{\"b\":2}"]);
+        assert!(buffer.is_empty());
+
+        // A character split across network chunks
+        let event = "data: {\"t\":\"é\"}\n\n".as_bytes();
+        let split = event.iter().position(|&b| b >= 0x80).unwrap() + 1;
+        buffer.extend_from_slice(&event[..split]);
+        assert!(take_sse_events(&mut buffer).is_empty());
+        buffer.extend_from_slice(&event[split..]);
+        assert_eq!(take_sse_events(&mut buffer), vec!["{\"t\":\"é\"}"]);
+
+        assert!(validate_model("gemini-2.5-flash-lite").is_ok());
+        assert!(validate_model("../files?x=1").is_err());
+        assert!(validate_model("").is_err());
+    }
+
+    #[test]
+    fn test_request_body_options() {
+        let options = AiGenerateOptions {
+            system_instruction: Some("Be brief".to_string()),
+            max_output_tokens: Some(256),
+            ..Default::default()
+        };
+        let body = request_body("Hi", &options);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "Hi");
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief");
+        assert_eq!(body["generationConfig"], json!({ "maxOutputTokens": 256 }));
+
+        let options = AiGenerateOptions {
+            json_response: true,
+            ..Default::default()
+        };
+        let body = request_body("Hi", &options);
+        assert_eq!(
+            body["generationConfig"],
+            json!({ "responseMimeType": "application/json" })
+        );
+    }
+}
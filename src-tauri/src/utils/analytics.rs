@@ -143,6 +143,7 @@ pub fn load_analytics() -> Result<String, String> {
 
 #[tauri::command]
 pub fn delete_analytics() -> Result<(), String> {
+    let _ = fs::remove_file(analytics_sync_file());
     let path = analytics_file();
     if path.exists() {
         std::fs::remove_file(path).map_err(|e| e.to_string())
@@ -151,6 +152,167 @@ pub fn delete_analytics() -> Result<(), String> {
     }
 }
 
+/// Read the stored analytics as a list of assessments; empty if missing or unreadable.
+fn load_stored_assessments() -> Vec<Value> {
+    let content = match fs::read_to_string(analytics_file()) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    if let Ok(parsed) = serde_json::from_str::<Vec<Value>>(&content) {
+        return parsed;
+    }
+    // Handle case where it's an object instead of array
+    match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(obj)) => obj.values().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+const CSV_HEADER: &str = "subject,title,due_date,grade,weight,percentage";
+
+/// Quote a CSV field if it contains a delimiter, quote or line break (RFC 4180).
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_field_to_string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Flatten assessments into CSV rows, optionally keeping only those due in `year`.
+fn analytics_to_csv(assessments: &[Value], year: Option<i32>) -> (String, usize) {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    let mut rows = 0;
+
+    for assessment in assessments {
+        let due = assessment.get("due").and_then(|d| d.as_str()).unwrap_or("");
+        if let Some(year) = year {
+            if !due.starts_with(&year.to_string()) {
+                continue;
+            }
+        }
+
+        let subject = assessment
+            .get("subject")
+            .and_then(|s| s.as_str())
+            .or_else(|| assessment.get("code").and_then(|c| c.as_str()))
+            .unwrap_or("");
+        let weight = json_field_to_string(
+            assessment
+                .get("weighting")
+                .or_else(|| assessment.get("weight")),
+        );
+        let grade = extract_letter_grade(assessment)
+            .unwrap_or_else(|| json_field_to_string(assessment.get("letterGrade")));
+        let percentage = extract_final_grade(assessment)
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| json_field_to_string(assessment.get("finalGrade")));
+
+        let fields = [
+            subject.to_string(),
+            json_field_to_string(assessment.get("title")),
+            due.to_string(),
+            grade,
+            weight,
+            percentage,
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+        rows += 1;
+    }
+
+    (csv, rows)
+}
+
+/// Stored assessments, or the mock set while `dev_sensitive_info_hider` is on.
+fn visible_assessments() -> Vec<Value> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        serde_json::from_str::<Vec<Value>>(&mock_analytics_json()).unwrap_or_default()
+    } else {
+        load_stored_assessments()
+    }
+}
+
+/// One assessment with released marks, as used by the grade analyser.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GradedAssessment {
+    pub subject: String,
+    pub title: String,
+    pub due: String,
+    /// Percentage, or an approximation derived from the letter grade
+    pub percentage: Option<f32>,
+    pub letter_grade: Option<String>,
+}
+
+fn graded_assessments_from(assessments: &[Value], year: Option<i32>) -> Vec<GradedAssessment> {
+    assessments
+        .iter()
+        .filter_map(|assessment| {
+            let due = assessment.get("due").and_then(|d| d.as_str()).unwrap_or("");
+            if let Some(year) = year {
+                if !due.starts_with(&year.to_string()) {
+                    return None;
+                }
+            }
+            let percentage = extract_final_grade(assessment);
+            let letter_grade = extract_letter_grade(assessment);
+            if percentage.is_none() && letter_grade.is_none() {
+                return None;
+            }
+            let subject = assessment
+                .get("subject")
+                .and_then(|s| s.as_str())
+                .or_else(|| assessment.get("code").and_then(|c| c.as_str()))
+                .unwrap_or("");
+            Some(GradedAssessment {
+                subject: subject.trim().to_string(),
+                title: json_field_to_string(assessment.get("title")),
+                due: due.to_string(),
+                percentage,
+                letter_grade,
+            })
+        })
+        .collect()
+}
+
+/// Assessments with released marks from the stored analytics, optionally only those due in
+/// `year`. Uses the mock data while `dev_sensitive_info_hider` is on.
+pub fn graded_assessments(year: Option<i32>) -> Vec<GradedAssessment> {
+    graded_assessments_from(&visible_assessments(), year)
+}
+
+/// Export stored grades as a spreadsheet-friendly CSV. Writes a header-only file when no
+/// analytics exist. Returns the number of assessment rows written.
+#[tauri::command]
+pub fn export_analytics_csv(dest_path: String, year: Option<i32>) -> Result<usize, String> {
+    let assessments = visible_assessments();
+    let (csv, rows) = analytics_to_csv(&assessments, year);
+    fs::write(&dest_path, csv).map_err(|e| format!("Failed to write CSV: {}", e))?;
+    Ok(rows)
+}
+
+/// Export the raw analytics JSON (an empty array when none exist).
+#[tauri::command]
+pub fn export_analytics_json(dest_path: String) -> Result<(), String> {
+    let data = if crate::settings::Settings::load().dev_sensitive_info_hider {
+        mock_analytics_json()
+    } else {
+        let assessments = load_stored_assessments();
+        serde_json::to_string_pretty(&assessments).map_err(|e| e.to_string())?
+    };
+    fs::write(&dest_path, data).map_err(|e| format!("Failed to write JSON: {}", e))
+}
+
 /// Fetch subjects from SEQTA API
 async fn fetch_subjects() -> Result<Vec<Folder>, String> {
     let body = json!({});
@@ -395,9 +557,160 @@ fn extract_letter_grade(assessment: &Value) -> Option<String> {
     None
 }
 
-/// Sync analytics data - fetches new assessments and merges with existing
+fn analytics_sync_file() -> PathBuf {
+    let mut path = analytics_file();
+    path.set_file_name("analytics_sync.json");
+    path
+}
+
+/// Bookkeeping for incremental analytics syncs, stored next to `analytics.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalyticsSyncState {
+    /// When the last sync finished (RFC 3339)
+    pub last_sync: Option<String>,
+    /// Newest due date among the stored assessments after the last sync
+    #[serde(default)]
+    pub high_water_mark: Option<String>,
+    /// `programme-metaclass` of inactive subjects whose whole history is already stored and
+    /// old enough that it won't be regraded
+    #[serde(default)]
+    pub synced_subjects: Vec<String>,
+}
+
+impl AnalyticsSyncState {
+    fn load() -> Self {
+        fs::read_to_string(analytics_sync_file())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize analytics sync state: {}", e))?;
+        fs::write(analytics_sync_file(), json)
+            .map_err(|e| format!("Failed to write analytics sync state: {}", e))
+    }
+}
+
+/// What a sync changed in the stored analytics.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AnalyticsSyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    /// Of `updated`, assessments whose grade changed (regraded by the teacher)
+    pub regraded: usize,
+    /// Assessments stored after the sync
+    pub total: usize,
+    /// False for a full sync (the first one, or when requested)
+    pub incremental: bool,
+    /// Inactive subjects not fetched because their history was already stored
+    pub subjects_skipped: usize,
+    pub last_sync: Option<String>,
+    pub high_water_mark: Option<String>,
+}
+
+/// Inactive subjects are fetched again until their newest assessment is this far behind the
+/// high water mark, since teachers can still regrade for a while after a subject ends.
+const REGRADE_WINDOW_DAYS: i64 = 90;
+
+fn due_date(due: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Whether an inactive subject whose fetched `assessments` are all due more than
+/// `REGRADE_WINDOW_DAYS` before `high_water_mark` can be skipped from now on.
+fn subject_settled(assessments: &[Value], high_water_mark: Option<&str>) -> bool {
+    let newest = assessments
+        .iter()
+        .filter_map(|a| a.get("due").and_then(|d| d.as_str()).and_then(due_date))
+        .max();
+    let Some(newest) = newest else {
+        return true;
+    };
+    high_water_mark
+        .and_then(due_date)
+        .is_some_and(|mark| newest < mark - chrono::Duration::days(REGRADE_WINDOW_DAYS))
+}
+
+fn grade_fields(assessment: &Value) -> (Option<&Value>, Option<&Value>) {
+    (assessment.get("finalGrade"), assessment.get("letterGrade"))
+}
+
+/// Merge fetched assessments into the stored ones by id, filling in `finalGrade` and
+/// `letterGrade`. A stored grade is never replaced by a fetch without one. Returns the
+/// merged list, newest due date first, and counts for the summary.
+fn merge_assessments(
+    existing: Vec<Value>,
+    fetched: Vec<Value>,
+) -> (Vec<Value>, AnalyticsSyncSummary) {
+    let mut summary = AnalyticsSyncSummary::default();
+    let mut merged: HashMap<i64, Value> = existing
+        .into_iter()
+        .filter_map(|assessment| Some((assessment.get("id")?.as_i64()?, assessment)))
+        .collect();
+
+    for mut assessment in fetched {
+        let Some(id) = assessment.get("id").and_then(|v| v.as_i64()) else {
+            continue;
+        };
+        if let Some(final_grade) = extract_final_grade(&assessment) {
+            assessment["finalGrade"] = json!(final_grade);
+        }
+        if let Some(letter_grade) = extract_letter_grade(&assessment) {
+            assessment["letterGrade"] = json!(letter_grade);
+        }
+
+        match merged.get(&id) {
+            None => summary.added += 1,
+            Some(stored) if *stored == assessment => continue,
+            Some(stored) => {
+                let stored_grade = grade_fields(stored);
+                let new_grade = grade_fields(&assessment);
+                if stored_grade.0.is_some() && new_grade.0.is_none() {
+                    continue;
+                }
+                summary.updated += 1;
+                if (stored_grade.0.is_some() || stored_grade.1.is_some())
+                    && stored_grade != new_grade
+                {
+                    summary.regraded += 1;
+                }
+            }
+        }
+        merged.insert(id, assessment);
+    }
+
+    let mut merged: Vec<Value> = merged.into_values().collect();
+    merged.sort_by(|a, b| {
+        let due_a = a.get("due").and_then(|d| d.as_str()).unwrap_or("");
+        let due_b = b.get("due").and_then(|d| d.as_str()).unwrap_or("");
+        due_b.cmp(due_a) // Descending (newest first)
+    });
+    summary.total = merged.len();
+    summary.high_water_mark = merged
+        .iter()
+        .filter_map(|a| a.get("due").and_then(|d| d.as_str()))
+        .find(|due| !due.is_empty())
+        .map(str::to_string);
+    (merged, summary)
+}
+
+/// When the analytics were last synced, how far the stored history reaches and which
+/// subjects' history is complete, or `None` before the first sync.
+#[tauri::command]
+pub fn get_last_analytics_sync() -> Option<AnalyticsSyncState> {
+    let state = AnalyticsSyncState::load();
+    state.last_sync.is_some().then_some(state)
+}
+
+/// Sync analytics data: fetch assessments and merge them into the stored ones by id, updating
+/// regraded ones. SEQTA can't filter by date, so after the first sync only subjects that are
+/// still active, or inactive ones with assessments inside the regrade window of the high water
+/// mark, are fetched again; older inactive subjects already stored are skipped. `full`
+/// refetches everything.
 #[tauri::command]
-pub async fn sync_analytics_data() -> Result<String, String> {
+pub async fn sync_analytics_data(full: Option<bool>) -> Result<AnalyticsSyncSummary, String> {
     if crate::settings::Settings::load().dev_sensitive_info_hider {
         let mock = mock_analytics_json();
         let path = analytics_file();
@@ -405,7 +718,8 @@ pub async fn sync_analytics_data() -> Result<String, String> {
             let _ = fs::create_dir_all(parent);
         }
         fs::write(&path, &mock).map_err(|e| e.to_string())?;
-        return Ok(mock);
+        let mock: Vec<Value> = serde_json::from_str(&mock).unwrap_or_default();
+        return Ok(merge_assessments(Vec::new(), mock).1);
     }
 
     // Check if we have a valid session before proceeding
@@ -424,52 +738,44 @@ pub async fn sync_analytics_data() -> Result<String, String> {
         );
     }
 
-    // Load existing analytics data
+    // Load existing analytics data. Without any, the sync is a full one whatever the state says
     let path = analytics_file();
-    let mut existing_assessments: Vec<Value> = Vec::new();
-    if path.exists() {
-        if let Ok(content) = fs::read_to_string(&path) {
-            if let Ok(parsed) = serde_json::from_str::<Vec<Value>>(&content) {
-                existing_assessments = parsed;
-            } else if let Ok(parsed_obj) = serde_json::from_str::<Value>(&content) {
-                // Handle case where it's an object instead of array
-                if let Some(obj) = parsed_obj.as_object() {
-                    existing_assessments = obj.values().cloned().collect();
-                }
-            }
-        }
-    }
-
-    // Create a map of existing assessments by ID
-    let mut existing_map: HashMap<i32, Value> = HashMap::new();
-    for assessment in existing_assessments {
-        if let Some(id) = assessment.get("id").and_then(|v| v.as_i64()) {
-            existing_map.insert(id as i32, assessment);
-        }
+    let existing_assessments = load_stored_assessments();
+    let mut sync_state = AnalyticsSyncState::load();
+    let incremental = !full.unwrap_or(false) && !existing_assessments.is_empty();
+    if !incremental {
+        sync_state.synced_subjects.clear();
     }
 
     // Fetch subjects
     let folders = fetch_subjects().await?;
 
-    // Extract all unique subjects
-    let mut unique_subjects_map: HashMap<String, Subject> = HashMap::new();
+    // Extract all unique subjects, noting whether any folder holding them is active
+    let mut unique_subjects_map: HashMap<String, (Subject, bool)> = HashMap::new();
     for folder in &folders {
         for subject in &folder.subjects {
             let key = format!("{}-{}", subject.programme, subject.metaclass);
-            if !unique_subjects_map.contains_key(&key) {
-                unique_subjects_map.insert(key, subject.clone());
-            }
+            let entry = unique_subjects_map
+                .entry(key)
+                .or_insert_with(|| (subject.clone(), false));
+            entry.1 |= folder.active;
         }
     }
+    let subjects_before = unique_subjects_map.len();
+    unique_subjects_map
+        .retain(|key, (_, active)| *active || !sync_state.synced_subjects.contains(key));
+    let subjects_skipped = subjects_before - unique_subjects_map.len();
 
     // Fetch upcoming assessments
     let upcoming_assessments = fetch_upcoming_assessments().await?;
 
     // Fetch past assessments for all subjects with rate limiting (10 per second)
     let mut past_assessments = Vec::new();
-    let subjects: Vec<(i32, i32)> = unique_subjects_map
+    // Fetched inactive subjects, kept until the high water mark is known
+    let mut fetched_inactive: Vec<(String, Vec<Value>)> = Vec::new();
+    let subjects: Vec<(i32, i32, bool)> = unique_subjects_map
         .values()
-        .map(|s| (s.programme, s.metaclass))
+        .map(|(s, active)| (s.programme, s.metaclass, *active))
         .collect();
     
     let total_subjects = subjects.len();
@@ -486,7 +792,7 @@ pub async fn sync_analytics_data() -> Result<String, String> {
     }
 
     // Process requests at rate of 10 per second (100ms delay between requests)
-    for (index, (programme, metaclass)) in subjects.iter().enumerate() {
+    for (index, (programme, metaclass, active)) in subjects.iter().enumerate() {
         // Check if session is still valid before each request
         let current_session = crate::session::Session::load();
         if current_session.jsessionid.is_empty() || current_session.base_url.is_empty() {
@@ -510,6 +816,10 @@ pub async fn sync_analytics_data() -> Result<String, String> {
         // Fetch the assessment data
         match fetch_past_assessments(*programme, *metaclass).await {
             Ok(assessments) => {
+                if !active {
+                    fetched_inactive
+                        .push((format!("{}-{}", programme, metaclass), assessments.clone()));
+                }
                 past_assessments.extend(assessments);
                 
                 // Log progress every 10 subjects
@@ -577,53 +887,26 @@ pub async fn sync_analytics_data() -> Result<String, String> {
     all_assessments.extend(upcoming_assessments);
     all_assessments.extend(past_assessments);
 
-    // Process and merge assessments
-    for mut assessment in all_assessments {
-        // Skip assessments without IDs
-        let id = match assessment.get("id").and_then(|v| v.as_i64()) {
-            Some(id) => id as i32,
-            None => continue, // Skip assessments without IDs
-        };
-
-        // Extract finalGrade
-        if let Some(final_grade) = extract_final_grade(&assessment) {
-            assessment["finalGrade"] = json!(final_grade);
-        }
-
-        // Extract letterGrade
-        if let Some(letter_grade) = extract_letter_grade(&assessment) {
-            assessment["letterGrade"] = json!(letter_grade);
-        }
-
-        // Merge with existing: keep existing if it has finalGrade and new doesn't, or update if new has more complete data
-        if let Some(existing) = existing_map.get(&id) {
-            let existing_has_grade = existing.get("finalGrade").is_some();
-            let new_has_grade = assessment.get("finalGrade").is_some();
-
-            // If existing has grade and new doesn't, keep existing
-            if existing_has_grade && !new_has_grade {
-                continue; // Skip this assessment, keep existing
-            }
+    let (final_assessments, mut summary) = merge_assessments(existing_assessments, all_assessments);
+    summary.incremental = incremental;
+    summary.subjects_skipped = subjects_skipped;
+    for (key, assessments) in &fetched_inactive {
+        if subject_settled(assessments, summary.high_water_mark.as_deref()) {
+            sync_state.synced_subjects.push(key.clone());
         }
-
-        // Update or insert the assessment
-        existing_map.insert(id, assessment);
     }
 
-    // Convert back to Vec and sort by due date
-    let mut final_assessments: Vec<Value> = existing_map.into_values().collect();
-    final_assessments.sort_by(|a, b| {
-        let due_a = a.get("due").and_then(|d| d.as_str()).unwrap_or("");
-        let due_b = b.get("due").and_then(|d| d.as_str()).unwrap_or("");
-        due_b.cmp(due_a) // Descending (newest first)
-    });
-
     // Save to file
     let json_data = serde_json::to_string_pretty(&final_assessments)
         .map_err(|e| format!("Failed to serialize analytics data: {}", e))?;
 
     fs::write(&path, json_data).map_err(|e| format!("Failed to write analytics file: {}", e))?;
 
+    sync_state.last_sync = Some(chrono::Utc::now().to_rfc3339());
+    sync_state.high_water_mark = summary.high_water_mark.clone();
+    sync_state.save()?;
+    summary.last_sync = sync_state.last_sync.clone();
+
     if let Some(logger) = logger::get_logger() {
         let _ = logger.log(
             logger::LogLevel::INFO,
@@ -631,10 +914,127 @@ pub async fn sync_analytics_data() -> Result<String, String> {
             "sync_analytics_data",
             "Completed analytics data sync",
             json!({
-                "total_assessments": final_assessments.len()
+                "total_assessments": summary.total,
+                "added": summary.added,
+                "updated": summary.updated,
+                "regraded": summary.regraded,
+                "incremental": summary.incremental,
+                "subjects_skipped": summary.subjects_skipped
             }),
         );
     }
 
-    Ok("Analytics data synced successfully".to_string())
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("Maths"), "Maths");
+        assert_eq!(csv_escape("English, Literature"), "\"English, Literature\"");
+        assert_eq!(csv_escape("The \"Big\" Test"), "\"The \"\"Big\"\" Test\"");
+        assert_eq!(csv_escape("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_analytics_to_csv_escapes_subject_with_comma() {
+        let assessments = vec![json!({
+            "id": 1,
+            "title": "Essay",
+            "subject": "History, Modern",
+            "status": "MARKS_RELEASED",
+            "due": "2025-05-01",
+            "weighting": 20,
+            "criteria": [{ "results": { "grade": "B+", "percentage": 76 } }]
+        })];
+        let (csv, rows) = analytics_to_csv(&assessments, None);
+        assert_eq!(rows, 1);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "\"History, Modern\",Essay,2025-05-01,B+,20,76");
+    }
+
+    #[test]
+    fn test_analytics_to_csv_year_filter_and_empty() {
+        let assessments = vec![json!({ "title": "Old", "subject": "SCI", "due": "2024-03-01" })];
+        let (csv, rows) = analytics_to_csv(&assessments, Some(2025));
+        assert_eq!(rows, 0);
+        assert_eq!(csv, format!("{}\n", CSV_HEADER));
+    }
+
+    #[test]
+    fn test_graded_assessments_skips_unreleased() {
+        let assessments = vec![
+            json!({
+                "title": "Test 1",
+                "code": "MATH",
+                "status": "MARKS_RELEASED",
+                "due": "2025-03-01",
+                "letterGrade": "B"
+            }),
+            json!({
+                "title": "Test 2",
+                "subject": "MATH",
+                "status": "PENDING",
+                "due": "2025-04-01"
+            }),
+            json!({
+                "title": "Old",
+                "subject": "SCI",
+                "status": "MARKS_RELEASED",
+                "due": "2024-03-01",
+                "finalGrade": 70
+            }),
+        ];
+        let graded = graded_assessments_from(&assessments, Some(2025));
+        assert_eq!(graded.len(), 1);
+        assert_eq!(graded[0].subject, "MATH");
+        assert_eq!(graded[0].letter_grade.as_deref(), Some("B"));
+        assert_eq!(graded[0].percentage, Some(68.0));
+        assert_eq!(graded_assessments_from(&assessments, None).len(), 2);
+    }
+
+    #[test]
+    fn test_merge_assessments_detects_regrade() {
+        let stored = vec![
+            json!({ "id": 1, "title": "Essay", "due": "2025-03-01", "finalGrade": 70.0, "letterGrade": "B-" }),
+            json!({ "id": 2, "title": "Quiz", "due": "2025-04-01", "finalGrade": 90.0 }),
+            json!({ "id": 3, "title": "Project", "due": "2025-05-01" }),
+        ];
+        let fetched = vec![
+            // Regraded by the teacher
+            json!({ "id": 1, "title": "Essay", "due": "2025-03-01", "finalGrade": 78.0, "letterGrade": "B+" }),
+            // A fetch without marks doesn't wipe the stored grade
+            json!({ "id": 2, "title": "Quiz", "due": "2025-04-01" }),
+            json!({ "id": 3, "title": "Project", "due": "2025-05-01" }),
+            json!({ "id": 4, "title": "Exam", "due": "2025-06-01" }),
+            json!({ "title": "No id" }),
+        ];
+
+        let (merged, summary) = merge_assessments(stored, fetched);
+        assert_eq!((summary.added, summary.updated, summary.regraded), (1, 1, 1));
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.high_water_mark.as_deref(), Some("2025-06-01"));
+        let ids: Vec<i64> = merged.iter().filter_map(|a| a["id"].as_i64()).collect();
+        assert_eq!(ids, vec![4, 3, 2, 1]);
+        assert_eq!(merged[3]["finalGrade"], json!(78.0));
+        assert_eq!(merged[3]["letterGrade"], json!("B+"));
+        assert_eq!(merged[2]["finalGrade"], json!(90.0));
+    }
+
+    #[test]
+    fn test_subject_settled_after_regrade_window() {
+        let ended = vec![
+            json!({ "id": 1, "due": "2025-02-01" }),
+            json!({ "id": 2, "due": "2025-03-01T00:00:00" }),
+        ];
+        assert!(subject_settled(&ended, Some("2025-06-01")));
+        // Still inside the window, so it's fetched again in case of a regrade
+        assert!(!subject_settled(&ended, Some("2025-05-01")));
+        assert!(!subject_settled(&ended, None));
+        assert!(subject_settled(&[], Some("2025-06-01")));
+    }
 }
@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{caches, disk_space, logger, netgrab, profiles, settings::Settings};
+
+/// Bumped when the archive layout changes in a way older builds can't read.
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Files and folders exported from the profile directory, relative to it.
+const PROFILE_ENTRIES: &[&str] = &[
+    "settings.json",
+    "notes",
+    "note_contents",
+    "global_search.json",
+    "analytics.json",
+    "themes",
+];
+/// Only exported/imported with `include_secrets`.
+const SECRET_ENTRIES: &[&str] = &["cloud_token.json"];
+/// Files exported from the shared data directory (not per-profile).
+const SHARED_ENTRIES: &[&str] = &["todolist.json"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub profile_id: String,
+    pub include_secrets: bool,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntry {
+    /// Path inside the archive
+    pub path: String,
+    /// Whether restoring replaces an existing file
+    pub overwrites: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub manifest: ArchiveManifest,
+    pub entries: Vec<ImportEntry>,
+    /// Archive entries ignored: secrets without `include_secrets`, unknown or unsafe paths
+    pub skipped: Vec<String>,
+    /// False for a dry run
+    pub applied: bool,
+}
+
+/// Where archive entries are restored to. `profile/...` maps under `profile`, `shared/...`
+/// under `shared`.
+struct ArchiveRoots {
+    profile: PathBuf,
+    shared: PathBuf,
+}
+
+impl ArchiveRoots {
+    /// A fresh directory inside each root that an import is extracted into before anything
+    /// is replaced. Being on the same filesystem, staged files move into place with a rename.
+    fn staging(&self) -> Self {
+        let name = format!(".import-{}", uuid::Uuid::new_v4());
+        Self {
+            profile: self.profile.join(&name),
+            shared: self.shared.join(&name),
+        }
+    }
+
+    fn current() -> Result<(String, Self), String> {
+        let profile = profiles::ProfileManager::get_current_profile()
+            .ok_or_else(|| "No active profile. Please log in first.".to_string())?;
+        Ok((
+            profile.id.clone(),
+            Self {
+                profile: profiles::get_profile_dir(&profile.id),
+                shared: profiles::get_base_data_dir(),
+            },
+        ))
+    }
+}
+
+/// Relative path of every file to export, prefixed with `profile/` or `shared/`.
+fn collect_export_files(roots: &ArchiveRoots, include_secrets: bool) -> Vec<(String, PathBuf)> {
+    let mut profile_entries = PROFILE_ENTRIES.to_vec();
+    if include_secrets {
+        profile_entries.extend_from_slice(SECRET_ENTRIES);
+    }
+
+    let mut files = Vec::new();
+    let sources = profile_entries
+        .iter()
+        .map(|entry| ("profile", &roots.profile, *entry))
+        .chain(SHARED_ENTRIES.iter().map(|entry| ("shared", &roots.shared, *entry)));
+    for (prefix, root, entry) in sources {
+        let path = root.join(entry);
+        for item in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
+            if !item.file_type().is_file() {
+                continue;
+            }
+            let Ok(relative) = item.path().strip_prefix(root) else {
+                continue;
+            };
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((format!("{}/{}", prefix, name), item.path().to_path_buf()));
+        }
+    }
+    files.sort();
+    files
+}
+
+fn write_archive(
+    dest: &Path,
+    files: &[(String, PathBuf)],
+    manifest: &ArchiveManifest,
+) -> Result<u64, String> {
+    let file = fs::File::create(dest).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest_json = serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_NAME, options)
+        .and_then(|_| Ok(zip.write_all(&manifest_json)?))
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    let mut bytes = 0;
+    for (name, path) in files {
+        let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        bytes += data.len() as u64;
+        zip.start_file(name.as_str(), options)
+            .and_then(|_| Ok(zip.write_all(&data)?))
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(bytes)
+}
+
+/// Map an archive entry to its restore location. Returns None for anything that isn't one of
+/// the known entries or that would escape its root (absolute paths, `..`: zip-slip).
+fn restore_target(name: &str, roots: &ArchiveRoots, include_secrets: bool) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+
+    let mut components = path.components();
+    let prefix = components.next()?.as_os_str().to_str()?;
+    let relative: PathBuf = components.collect();
+    let top = relative.components().next()?.as_os_str().to_str()?;
+
+    let (root, allowed) = match prefix {
+        "profile" if SECRET_ENTRIES.contains(&top) => (&roots.profile, include_secrets),
+        "profile" => (&roots.profile, PROFILE_ENTRIES.contains(&top)),
+        "shared" => (&roots.shared, SHARED_ENTRIES.contains(&top)),
+        _ => (&roots.profile, false),
+    };
+    allowed.then(|| root.join(relative))
+}
+
+fn read_manifest<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Result<ArchiveManifest, String> {
+    let mut file = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "Not a DesQTA data archive (manifest missing)".to_string())?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest: ArchiveManifest =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid manifest: {}", e))?;
+    if manifest.format_version == 0 || manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported archive version {} (this build supports up to {})",
+            manifest.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Extract the archive entries at `targets` to their staged paths.
+fn extract_staged<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    targets: &[(usize, PathBuf, PathBuf)],
+) -> Result<(), String> {
+    for (index, staged, _) in targets {
+        let mut file = archive
+            .by_index(*index)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", index, e))?;
+        if let Some(parent) = staged.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .map_err(|e| format!("Failed to extract {}: {}", file.name(), e))?;
+        fs::write(staged, data)
+            .map_err(|e| format!("Failed to write {}: {}", staged.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Move every staged file over its target.
+fn move_staged(targets: &[(usize, PathBuf, PathBuf)]) -> Result<(), String> {
+    for (_, staged, target) in targets {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::rename(staged, target)
+            .map_err(|e| format!("Failed to restore {}: {}", target.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Read `zip_path` and, unless `dry_run`, restore it under `roots`. Everything is extracted
+/// to a staging directory first, so a damaged archive or a full disk leaves the existing
+/// data untouched.
+fn import_archive(
+    zip_path: &Path,
+    roots: &ArchiveRoots,
+    include_secrets: bool,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    let file = fs::File::open(zip_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+    let manifest = read_manifest(&mut archive)?;
+
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    let mut targets = Vec::new();
+    let staging = roots.staging();
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        let name = file.name().to_string();
+        if name == MANIFEST_NAME || file.is_dir() {
+            continue;
+        }
+        match (
+            restore_target(&name, roots, include_secrets),
+            restore_target(&name, &staging, include_secrets),
+        ) {
+            (Some(target), Some(staged)) => {
+                entries.push(ImportEntry {
+                    path: name,
+                    overwrites: target.exists(),
+                });
+                targets.push((i, staged, target));
+            }
+            _ => skipped.push(name),
+        }
+    }
+
+    if !dry_run {
+        let result = extract_staged(&mut archive, &targets).and_then(|_| move_staged(&targets));
+        let _ = fs::remove_dir_all(&staging.profile);
+        let _ = fs::remove_dir_all(&staging.shared);
+        result?;
+    }
+
+    Ok(ImportReport {
+        manifest,
+        entries,
+        skipped,
+        applied: !dry_run,
+    })
+}
+
+/// Bundle the current profile's settings, notes (with images), todos, search data, analytics
+/// and custom themes into one zip at `dest_path`. The cloud token is only included when
+/// `include_secrets` is set.
+#[tauri::command]
+pub fn export_app_data(
+    dest_path: String,
+    include_secrets: Option<bool>,
+) -> Result<ExportSummary, String> {
+    let include_secrets = include_secrets.unwrap_or(false);
+    let (profile_id, roots) = ArchiveRoots::current()?;
+    let files = collect_export_files(&roots, include_secrets);
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        profile_id,
+        include_secrets,
+        files: files.iter().map(|(name, _)| name.clone()).collect(),
+    };
+    // Uncompressed size, so the estimate only errs on the high side
+    let payload: u64 = files
+        .iter()
+        .filter_map(|(_, path)| fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+    disk_space::ensure_free_space(Path::new(&dest_path), payload, "export")?;
+    let bytes = write_archive(Path::new(&dest_path), &files, &manifest)?;
+
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "app_archive",
+            "export_app_data",
+            "Exported app data",
+            serde_json::json!({"files": files.len(), "bytes": bytes, "include_secrets": include_secrets}),
+        );
+    }
+
+    Ok(ExportSummary {
+        path: dest_path,
+        files: files.len(),
+        bytes,
+    })
+}
+
+/// Restore an archive made by `export_app_data` into the current profile. With `dry_run`
+/// nothing is written and the report lists what would be restored and what it overwrites.
+/// Secrets in the archive are ignored unless `include_secrets` is set.
+#[tauri::command]
+pub fn import_app_data(
+    zip_path: String,
+    dry_run: Option<bool>,
+    include_secrets: Option<bool>,
+) -> Result<ImportReport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let (_, roots) = ArchiveRoots::current()?;
+    let report = import_archive(
+        Path::new(&zip_path),
+        &roots,
+        include_secrets.unwrap_or(false),
+        dry_run,
+    )?;
+
+    if report.applied {
+        // Restored files replace what the in-memory state was built from
+        caches::clear_memory_caches();
+        netgrab::set_offline_mode(Settings::load().dev_force_offline_mode);
+
+        if let Some(logger) = logger::get_logger() {
+            let _ = logger.log(
+                logger::LogLevel::INFO,
+                "app_archive",
+                "import_app_data",
+                "Imported app data",
+                serde_json::json!({"files": report.entries.len(), "skipped": report.skipped.len()}),
+            );
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_roots() -> (PathBuf, ArchiveRoots) {
+        let base = std::env::temp_dir().join(format!("desqta-archive-{}", uuid::Uuid::new_v4()));
+        let roots = ArchiveRoots {
+            profile: base.join("profile"),
+            shared: base.join("shared"),
+        };
+        fs::create_dir_all(&roots.profile).unwrap();
+        fs::create_dir_all(&roots.shared).unwrap();
+        (base, roots)
+    }
+
+    fn manifest(files: &[(String, PathBuf)]) -> ArchiveManifest {
+        ArchiveManifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            app_version: "test".to_string(),
+            exported_at: String::new(),
+            profile_id: "p".to_string(),
+            include_secrets: false,
+            files: files.iter().map(|(name, _)| name.clone()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_restore_target_rejects_unsafe_paths() {
+        let (base, roots) = temp_roots();
+        assert!(restore_target("profile/notes/a.json", &roots, false).is_some());
+        assert!(restore_target("shared/todolist.json", &roots, false).is_some());
+        assert!(restore_target("profile/../../etc/passwd", &roots, false).is_none());
+        assert!(restore_target("/profile/notes/a.json", &roots, false).is_none());
+        assert!(restore_target("profile/session.json", &roots, false).is_none());
+        assert!(restore_target("profile/cloud_token.json", &roots, false).is_none());
+        assert!(restore_target("profile/cloud_token.json", &roots, true).is_some());
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_export_then_import_round_trip() {
+        let (base, roots) = temp_roots();
+        fs::create_dir_all(roots.profile.join("notes")).unwrap();
+        fs::write(roots.profile.join("notes/n1.json"), "{}").unwrap();
+        fs::write(roots.profile.join("settings.json"), "{\"a\":1}").unwrap();
+        fs::write(roots.profile.join("cloud_token.json"), "secret").unwrap();
+        fs::write(roots.shared.join("todolist.json"), "[]").unwrap();
+
+        let files = collect_export_files(&roots, false);
+        let names: Vec<&str> = files.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["profile/notes/n1.json", "profile/settings.json", "shared/todolist.json"]
+        );
+
+        let zip_path = base.join("export.zip");
+        write_archive(&zip_path, &files, &manifest(&files)).unwrap();
+
+        let (target_base, target) = temp_roots();
+        fs::write(target.profile.join("settings.json"), "old").unwrap();
+
+        let preview = import_archive(&zip_path, &target, false, true).unwrap();
+        assert!(!preview.applied);
+        assert_eq!(preview.entries.len(), 3);
+        assert!(preview
+            .entries
+            .iter()
+            .any(|e| e.path == "profile/settings.json" && e.overwrites));
+        assert_eq!(
+            fs::read_to_string(target.profile.join("settings.json")).unwrap(),
+            "old"
+        );
+
+        import_archive(&zip_path, &target, false, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.profile.join("settings.json")).unwrap(),
+            "{\"a\":1}"
+        );
+        assert!(target.profile.join("notes/n1.json").exists());
+        assert!(!target.profile.join("cloud_token.json").exists());
+        // The staging directories are cleaned up
+        for root in [&target.profile, &target.shared] {
+            let leftover = fs::read_dir(root)
+                .unwrap()
+                .filter_map(|e| e.ok())
+                .any(|e| e.file_name().to_string_lossy().starts_with(".import-"));
+            assert!(!leftover);
+        }
+
+        fs::remove_dir_all(base).unwrap();
+        fs::remove_dir_all(target_base).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::assessments;
+use crate::database::{self, Notification};
+use crate::session;
+use crate::settings::Settings;
+
+/// Notification types written here start with this, so the frontend's own reminder types
+/// (`reminder_3days`, `due_date`, ...) in the same table are never touched. The frontend
+/// words `lead_*` reminders from the lead time and the assessment.
+const LEAD_TYPE_PREFIX: &str = "lead_";
+
+/// One notification type per lead time, e.g. `lead_3600s`. Together with the assessment ID
+/// this is the table's unique key, so an assessment can't be scheduled twice for a lead time.
+fn lead_notification_type(lead_secs: u64) -> String {
+    format!("{}{}s", LEAD_TYPE_PREFIX, lead_secs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PlannedReminder {
+    assessment_id: i64,
+    notification_type: String,
+    scheduled_for: i64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ReminderChanges {
+    schedule: Vec<PlannedReminder>,
+    cancel: Vec<i64>,
+    unchanged: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ReminderSummary {
+    pub scheduled: usize,
+    pub unchanged: usize,
+    pub cancelled: usize,
+}
+
+/// One reminder per assessment and lead time whose fire time is still ahead of `now`.
+/// Assessments are deduplicated by ID and lead times by whole seconds.
+fn plan_reminders(
+    assessments: &[Value],
+    lead_times: &[Duration],
+    now: DateTime<Utc>,
+) -> Vec<PlannedReminder> {
+    let mut leads: Vec<u64> = lead_times.iter().map(|d| d.as_secs()).collect();
+    leads.sort_unstable();
+    leads.dedup();
+
+    let mut seen = HashSet::new();
+    let mut planned = Vec::new();
+    for assessment in assessments {
+        let Some(id) = assessment["id"].as_i64() else {
+            continue;
+        };
+        if !seen.insert(id) {
+            continue;
+        }
+        let Some(due) = assessment["due"]
+            .as_str()
+            .and_then(assessments::parse_assessment_due)
+        else {
+            continue;
+        };
+        for &lead in &leads {
+            let scheduled_for = due.timestamp().saturating_sub(lead as i64);
+            if scheduled_for > now.timestamp() {
+                planned.push(PlannedReminder {
+                    assessment_id: id,
+                    notification_type: lead_notification_type(lead),
+                    scheduled_for,
+                });
+            }
+        }
+    }
+    planned
+}
+
+/// Compare planned reminders with what's stored. Reminders already stored for the same time
+/// are left alone (even if sent); moved due dates are rescheduled; pending reminders that are
+/// no longer planned are cancelled.
+fn diff_reminders(planned: Vec<PlannedReminder>, existing: &[Notification]) -> ReminderChanges {
+    let existing_by_key: HashMap<(i64, &str), &Notification> = existing
+        .iter()
+        .map(|n| ((n.assessment_id, n.notification_type.as_str()), n))
+        .collect();
+    let planned_keys: HashSet<(i64, String)> = planned
+        .iter()
+        .map(|p| (p.assessment_id, p.notification_type.clone()))
+        .collect();
+
+    let mut changes = ReminderChanges::default();
+    for reminder in planned {
+        match existing_by_key.get(&(reminder.assessment_id, reminder.notification_type.as_str())) {
+            Some(stored) if stored.scheduled_for == reminder.scheduled_for => {
+                changes.unchanged += 1
+            }
+            _ => changes.schedule.push(reminder),
+        }
+    }
+    changes.cancel = existing
+        .iter()
+        .filter(|n| n.sent_at.is_none())
+        .filter(|n| !planned_keys.contains(&(n.assessment_id, n.notification_type.clone())))
+        .map(|n| n.id)
+        .collect();
+    changes
+}
+
+/// Assessments from the local database, or from SEQTA when nothing has been stored yet
+async fn load_assessments() -> Result<Vec<Value>, String> {
+    let stored = database::db_get_assessments_by_year(None)?;
+    if !stored.is_empty() || !session::Session::exists() {
+        return Ok(stored);
+    }
+    let processed = assessments::get_processed_assessments().await?;
+    Ok(processed
+        .assessments
+        .iter()
+        .map(|a| json!({ "id": a.id, "due": a.due }))
+        .collect())
+}
+
+/// Bring the stored reminders in line with the current assessments. With reminders turned
+/// off this just cancels every pending reminder.
+async fn reschedule(lead_times: &[Duration]) -> Result<ReminderSummary, String> {
+    let planned = if Settings::load().reminders_enabled {
+        plan_reminders(&load_assessments().await?, lead_times, Utc::now())
+    } else {
+        Vec::new()
+    };
+    let existing = database::notifications_with_type_prefix(LEAD_TYPE_PREFIX)?;
+    let changes = diff_reminders(planned, &existing);
+
+    for id in &changes.cancel {
+        database::delete_notification(*id)?;
+    }
+    for reminder in &changes.schedule {
+        database::db_notification_schedule(
+            reminder.assessment_id,
+            reminder.notification_type.clone(),
+            reminder.scheduled_for,
+        )?;
+    }
+
+    Ok(ReminderSummary {
+        scheduled: changes.schedule.len(),
+        unchanged: changes.unchanged,
+        cancelled: changes.cancel.len(),
+    })
+}
+
+/// Save the reminder lead times and schedule reminders for every upcoming assessment.
+/// Delivery is left to the notification poller, which sends rows from `db_notification_get_due`.
+#[tauri::command]
+pub async fn schedule_assessment_reminders(
+    lead_times: Vec<Duration>,
+) -> Result<ReminderSummary, String> {
+    let mut settings = Settings::load();
+    settings.reminder_lead_times_secs = lead_times.iter().map(|d| d.as_secs()).collect();
+    settings.save().map_err(|e| e.to_string())?;
+
+    reschedule(&lead_times).await
+}
+
+/// Recompute reminders at launch with the saved lead times, so due-date changes made while
+/// the app was closed are picked up.
+pub fn reschedule_on_launch() {
+    let lead_times: Vec<Duration> = Settings::load()
+        .reminder_lead_times_secs
+        .into_iter()
+        .map(Duration::from_secs)
+        .collect();
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(15)).await;
+        match reschedule(&lead_times).await {
+            Ok(summary) => println!(
+                "[REMINDERS] {} scheduled, {} unchanged, {} cancelled",
+                summary.scheduled, summary.unchanged, summary.cancelled
+            ),
+            Err(e) => eprintln!("[REMINDERS] Failed to reschedule: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        assessments::parse_assessment_due("2025-03-10T09:00:00Z").unwrap()
+    }
+
+    fn stored(id: i64, assessment_id: i64, lead_secs: u64, scheduled_for: i64) -> Notification {
+        Notification {
+            id,
+            assessment_id,
+            notification_type: lead_notification_type(lead_secs),
+            scheduled_for,
+            sent_at: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_plan_reminders_lead_times() {
+        let due = now().timestamp() + 2 * 24 * 60 * 60;
+        let assessments = vec![json!({ "id": 1, "due": "2025-03-12T09:00:00Z" })];
+        let leads = [
+            Duration::from_secs(24 * 60 * 60),
+            Duration::from_secs(60 * 60),
+        ];
+
+        let planned = plan_reminders(&assessments, &leads, now());
+        assert_eq!(planned.len(), 2);
+        assert_eq!(planned[0].scheduled_for, due - 60 * 60);
+        assert_eq!(planned[0].notification_type, "lead_3600s");
+        assert_eq!(planned[1].scheduled_for, due - 24 * 60 * 60);
+        assert_eq!(planned[1].notification_type, "lead_86400s");
+    }
+
+    #[test]
+    fn test_plan_reminders_skips_past_and_unparseable() {
+        let assessments = vec![
+            json!({ "id": 1, "due": "2025-03-10T12:00:00Z" }),
+            json!({ "id": 2, "due": "2025-03-01T09:00:00Z" }),
+            json!({ "id": 3, "due": "" }),
+            json!({ "due": "2025-03-20T09:00:00Z" }),
+        ];
+        let leads = [
+            Duration::from_secs(24 * 60 * 60),
+            Duration::from_secs(60 * 60),
+        ];
+
+        let planned = plan_reminders(&assessments, &leads, now());
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].assessment_id, 1);
+        assert_eq!(planned[0].notification_type, "lead_3600s");
+    }
+
+    #[test]
+    fn test_plan_reminders_dedupes_assessments_and_leads() {
+        let assessments = vec![
+            json!({ "id": 1, "due": "2025-03-12T09:00:00Z" }),
+            json!({ "id": 1, "due": "2025-03-12T09:00:00Z" }),
+        ];
+        let leads = [
+            Duration::from_secs(60 * 60),
+            Duration::from_millis(3_600_500),
+        ];
+
+        assert_eq!(plan_reminders(&assessments, &leads, now()).len(), 1);
+    }
+
+    #[test]
+    fn test_diff_reminders() {
+        let planned = vec![
+            PlannedReminder {
+                assessment_id: 1,
+                notification_type: lead_notification_type(3600),
+                scheduled_for: 1000,
+            },
+            PlannedReminder {
+                assessment_id: 2,
+                notification_type: lead_notification_type(3600),
+                scheduled_for: 2000,
+            },
+            PlannedReminder {
+                assessment_id: 3,
+                notification_type: lead_notification_type(3600),
+                scheduled_for: 3000,
+            },
+        ];
+        let mut sent = stored(13, 4, 3600, 500);
+        sent.sent_at = Some(500);
+        let existing = vec![
+            stored(10, 1, 3600, 1000),
+            stored(11, 2, 3600, 1500),
+            stored(12, 5, 3600, 4000),
+            sent,
+        ];
+
+        let changes = diff_reminders(planned, &existing);
+        assert_eq!(changes.unchanged, 1);
+        assert_eq!(
+            changes
+                .schedule
+                .iter()
+                .map(|p| p.assessment_id)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(changes.cancel, vec![12]);
+    }
+
+    #[test]
+    fn test_diff_reminders_keeps_sent_reminder_for_same_time() {
+        let planned = vec![PlannedReminder {
+            assessment_id: 1,
+            notification_type: lead_notification_type(60),
+            scheduled_for: 1000,
+        }];
+        let mut existing = stored(10, 1, 60, 1000);
+        existing.sent_at = Some(1000);
+
+        let changes = diff_reminders(planned, &[existing]);
+        assert_eq!(changes.unchanged, 1);
+        assert!(changes.schedule.is_empty());
+        assert!(changes.cancel.is_empty());
+    }
+}
@@ -1,16 +1,22 @@
+use super::database;
 use super::netgrab;
 use super::netgrab::RequestMethod;
+use super::timezone;
 use crate::logger;
 use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 
 const STUDENT_ID: i32 = 69;
 
 /// Concurrent past-assessment requests (matches study page; avoids SEQTA overload vs join_all).
 const PAST_ASSESSMENT_FETCH_CONCURRENCY: usize = 6;
 
+/// How long `get_upcoming_assessments` / `get_past_assessments` results stay in the db cache
+const ASSESSMENT_LIST_CACHE_TTL_MINUTES: i64 = 10;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Subject {
     pub code: String,
@@ -91,63 +97,55 @@ pub struct ProcessedAssessmentsResponse {
 }
 
 /// Fetch lesson colours from SEQTA API
-async fn fetch_lesson_colours() -> Result<Vec<Value>, String> {
+pub(crate) async fn fetch_lesson_colours() -> Result<Vec<Value>, String> {
     let body = json!({
         "request": "userPrefs",
         "asArray": true,
         "user": STUDENT_ID
     });
 
-    let response = netgrab::fetch_api_data(
+    let data = netgrab::request_seqta_json(
         "/seqta/student/load/prefs?",
         RequestMethod::POST,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert(
-                "Content-Type".to_string(),
-                "application/json; charset=utf-8".to_string(),
-            );
-            headers
-        }),
         Some(body),
-        None,
-        false,
-        false,
-        None,
     )
-    .await?;
-
-    let data: Value = serde_json::from_str(&response)
-        .map_err(|e| format!("Failed to parse lesson colours: {}", e))?;
+    .await
+    .map_err(|e| format!("Failed to fetch lesson colours: {}", e))?;
 
     Ok(data["payload"].as_array().cloned().unwrap_or_default())
 }
 
+/// Subject colours (`timetable.subject.colour.<code>`) in a `fetch_lesson_colours` result,
+/// keyed by subject code. The first entry for a code wins.
+pub(crate) fn colours_by_code(colours: &[Value]) -> HashMap<String, String> {
+    let mut colour_by_code: HashMap<String, String> = HashMap::new();
+    for c in colours {
+        let Some(name) = c.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let Some(value) = c.get("value").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if let Some(code) = name.strip_prefix("timetable.subject.colour.") {
+            colour_by_code
+                .entry(code.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+    colour_by_code
+}
+
 /// Fetch subjects from SEQTA API
 async fn fetch_subjects() -> Result<Vec<Folder>, String> {
     let body = json!({});
 
-    let response = netgrab::fetch_api_data(
+    let data = netgrab::request_seqta_json(
         "/seqta/student/load/subjects?",
         RequestMethod::POST,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert(
-                "Content-Type".to_string(),
-                "application/json; charset=utf-8".to_string(),
-            );
-            headers
-        }),
         Some(body),
-        None,
-        false,
-        false,
-        None,
     )
-    .await?;
-
-    let data: Value =
-        serde_json::from_str(&response).map_err(|e| format!("Failed to parse subjects: {}", e))?;
+    .await
+    .map_err(|e| format!("Failed to fetch subjects: {}", e))?;
 
     let folders: Vec<Folder> = serde_json::from_value(data["payload"].clone())
         .map_err(|e| format!("Failed to deserialize folders: {}", e))?;
@@ -161,29 +159,15 @@ async fn fetch_upcoming_assessments() -> Result<Vec<Value>, String> {
         "student": STUDENT_ID
     });
 
-    let response = netgrab::fetch_api_data(
+    let data = netgrab::request_seqta_json(
         "/seqta/student/assessment/list/upcoming?",
         RequestMethod::POST,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert(
-                "Content-Type".to_string(),
-                "application/json; charset=utf-8".to_string(),
-            );
-            headers
-        }),
         Some(body),
-        None,
-        false,
-        false,
-        None,
     )
-    .await?;
+    .await
+    .map_err(|e| format!("Failed to fetch upcoming assessments: {}", e))?;
 
-    let data: Value = serde_json::from_str(&response)
-        .map_err(|e| format!("Failed to parse upcoming assessments: {}", e))?;
-
-    Ok(data["payload"].as_array().cloned().unwrap_or_default())
+    Ok(assessment_list_entries(&data))
 }
 
 /// Fetch past assessments for a specific subject
@@ -194,41 +178,30 @@ async fn fetch_past_assessments(programme: i32, metaclass: i32) -> Result<Vec<Va
         "student": STUDENT_ID
     });
 
-    let response = netgrab::fetch_api_data(
+    let data = netgrab::request_seqta_json(
         "/seqta/student/assessment/list/past?",
         RequestMethod::POST,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert(
-                "Content-Type".to_string(),
-                "application/json; charset=utf-8".to_string(),
-            );
-            headers
-        }),
         Some(body),
-        None,
-        false,
-        false,
-        None,
     )
-    .await?;
-
-    let data: Value = serde_json::from_str(&response)
-        .map_err(|e| format!("Failed to parse past assessments: {}", e))?;
+    .await
+    .map_err(|e| format!("Failed to fetch past assessments: {}", e))?;
 
-    // The API returns assessments in payload.pending (for pending/past assessments)
-    // and payload.tasks (for completed tasks). Check both.
-    let mut result = Vec::new();
-
-    if let Some(pending) = data["payload"]["pending"].as_array() {
-        result.extend(pending.iter().cloned());
-    }
+    Ok(assessment_list_entries(&data))
+}
 
-    if let Some(tasks) = data["payload"]["tasks"].as_array() {
-        result.extend(tasks.iter().cloned());
+/// Assessments in a list response. The upcoming endpoint returns a `payload` array; the past
+/// one puts them in `payload.pending` (pending/past assessments) and `payload.tasks`
+/// (completed tasks).
+fn assessment_list_entries(data: &Value) -> Vec<Value> {
+    match &data["payload"] {
+        Value::Array(entries) => entries.clone(),
+        payload => ["pending", "tasks"]
+            .iter()
+            .filter_map(|key| payload[*key].as_array())
+            .flatten()
+            .cloned()
+            .collect(),
     }
-
-    Ok(result)
 }
 
 /// Return mock assessments when dev_sensitive_info_hider is enabled
@@ -322,21 +295,15 @@ fn mock_assessment_detail(assessment_id: i32) -> String {
     serde_json::json!({ "payload": payload }).to_string()
 }
 
-/// Fetch assessment detail - returns JSON string with { payload: {...} }
-/// Uses mock data when dev_sensitive_info_hider is enabled
-#[tauri::command]
-pub async fn get_assessment_detail(assessment: i32, _student: i32, metaclass: i32) -> Result<String, String> {
-    if crate::settings::Settings::load().dev_sensitive_info_hider {
-        return Ok(mock_assessment_detail(assessment));
-    }
-
+/// Raw `/seqta/student/assessment/get` response for one assessment
+async fn fetch_assessment_detail_raw(assessment: i64, metaclass: i64) -> Result<String, String> {
     let body = json!({
         "assessment": assessment,
         "student": STUDENT_ID,
         "metaclass": metaclass
     });
 
-    let response = netgrab::fetch_api_data(
+    netgrab::fetch_api_data(
         "/seqta/student/assessment/get?",
         RequestMethod::POST,
         Some({
@@ -353,9 +320,429 @@ pub async fn get_assessment_detail(assessment: i32, _student: i32, metaclass: i3
         false,
         None,
     )
-    .await?;
+    .await
+}
+
+/// Fetch assessment detail - returns JSON string with { payload: {...} }
+/// Uses mock data when dev_sensitive_info_hider is enabled
+#[tauri::command]
+pub async fn get_assessment_detail(assessment: i32, _student: i32, metaclass: i32) -> Result<String, String> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        return Ok(mock_assessment_detail(assessment));
+    }
+
+    fetch_assessment_detail_raw(assessment as i64, metaclass as i64).await
+}
+
+/// Which SEQTA endpoint an assessment was found on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssessmentSource {
+    Upcoming,
+    Past,
+    Detail,
+}
+
+/// Find an assessment by ID. SEQTA has no single lookup, so this tries the upcoming list,
+/// then the past list for the subject, then the detail endpoint. The last two need the
+/// subject's programme/metaclass. Returns the raw assessment object.
+pub(crate) async fn lookup_assessment(
+    id: i64,
+    programme: Option<i64>,
+    metaclass: Option<i64>,
+) -> Result<Option<(Value, AssessmentSource)>, String> {
+    let matches_id = |a: &&Value| a["id"].as_i64() == Some(id);
+
+    let upcoming = fetch_upcoming_assessments().await?;
+    if let Some(found) = upcoming.iter().find(matches_id) {
+        return Ok(Some((found.clone(), AssessmentSource::Upcoming)));
+    }
+
+    if let (Some(programme), Some(metaclass)) = (programme, metaclass) {
+        if let Ok(past) = fetch_past_assessments(programme as i32, metaclass as i32).await {
+            if let Some(found) = past.iter().find(matches_id) {
+                return Ok(Some((found.clone(), AssessmentSource::Past)));
+            }
+        }
+    }
+
+    if let Some(metaclass) = metaclass {
+        if let Ok(response) = fetch_assessment_detail_raw(id, metaclass).await {
+            if let Ok(data) = serde_json::from_str::<Value>(&response) {
+                if data["payload"]["id"].is_number() {
+                    return Ok(Some((data["payload"].clone(), AssessmentSource::Detail)));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parse the due formats SEQTA uses (RFC 3339, naive datetime, plain date). Dates and times
+/// without an offset are in the school's timezone.
+pub(crate) fn parse_assessment_due(due: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    parse_assessment_due_in(due, &timezone::school_timezone())
+}
+
+fn parse_assessment_due_in(due: &str, tz: &chrono_tz::Tz) -> Option<chrono::DateTime<chrono::Utc>> {
+    if due.is_empty() {
+        return None;
+    }
+    chrono::DateTime::parse_from_rfc3339(due)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(due, "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .and_then(|dt| timezone::local_to_utc(tz, dt))
+        })
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .and_then(|dt| timezone::local_to_utc(tz, dt))
+        })
+}
+
+/// How far the student's work has got, from SEQTA's submission and marking fields.
+/// Ordered, so merging two sources keeps the furthest along.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionStatus {
+    Submitted,
+    Marked,
+    /// Marks have been released to the student
+    Returned,
+}
+
+impl SubmissionStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            SubmissionStatus::Submitted => "submitted",
+            SubmissionStatus::Marked => "marked",
+            SubmissionStatus::Returned => "returned",
+        }
+    }
+
+    /// `None` when SEQTA sent nothing that says the work was submitted or marked
+    fn from_value(value: &Value) -> Option<Self> {
+        if value["status"].as_str() == Some("MARKS_RELEASED") {
+            return Some(SubmissionStatus::Returned);
+        }
+        if value_as_bool(&value["marked"]).unwrap_or(false)
+            || value_as_bool(&value["graded"]).unwrap_or(false)
+        {
+            return Some(SubmissionStatus::Marked);
+        }
+        if value_as_bool(&value["submitted"])
+            .or_else(|| value_as_bool(&value["hasSubmission"]))
+            .unwrap_or(false)
+        {
+            return Some(SubmissionStatus::Submitted);
+        }
+        None
+    }
+}
+
+/// Awarded grade and percentage. SEQTA puts them under `results` on list entries and under
+/// the first criterion's `results` on the detail payload.
+fn assessment_results(value: &Value) -> (Option<String>, Option<f64>) {
+    let results = [&value["results"], &value["criteria"][0]["results"]];
+    let grade = results
+        .iter()
+        .find_map(|r| match &r["grade"] {
+            Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+        .or_else(|| value["letterGrade"].as_str().map(|s| s.to_string()));
+    let percentage = results
+        .iter()
+        .find_map(|r| {
+            r["percentage"]
+                .as_f64()
+                .or_else(|| r["percentage"].as_str().and_then(|s| s.parse().ok()))
+        })
+        .or_else(|| value["finalGrade"].as_f64());
+    (grade, percentage)
+}
+
+/// Submission progress when SEQTA reports it, otherwise "pending" or "overdue" from the due
+/// date, falling back to SEQTA's own status field
+pub(crate) fn assessment_status(assessment: &Value) -> String {
+    if let Some(status) = SubmissionStatus::from_value(assessment) {
+        return status.as_str().to_string();
+    }
+    let due = assessment["due"]
+        .as_str()
+        .or_else(|| assessment["dueDate"].as_str())
+        .unwrap_or("");
+    match parse_assessment_due(due) {
+        Some(due_dt) if due_dt > chrono::Utc::now() => "pending".to_string(),
+        Some(_) => "overdue".to_string(),
+        None => assessment["status"].as_str().unwrap_or("unknown").to_string(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AssessmentSubmissionInfo {
+    pub file_submission_enabled: bool,
+    pub submitted: bool,
+    pub marked: bool,
+    /// `None` when SEQTA sent no submission data
+    pub status: Option<SubmissionStatus>,
+    pub grade: Option<String>,
+    pub percentage: Option<f64>,
+    pub has_feedback: bool,
+}
+
+impl AssessmentSubmissionInfo {
+    pub(crate) fn from_value(value: &Value) -> Self {
+        let status = SubmissionStatus::from_value(value);
+        let (grade, percentage) = assessment_results(value);
+        let has_feedback = value_as_bool(&value["hasFeedback"]).unwrap_or(false)
+            || value["feedback"]
+                .as_str()
+                .map_or(false, |f| !f.trim().is_empty());
+        Self {
+            file_submission_enabled: value_as_bool(
+                &value["submissionSettings"]["fileSubmissionEnabled"],
+            )
+            .unwrap_or(false),
+            submitted: status.is_some(),
+            marked: status.map_or(false, |s| s >= SubmissionStatus::Marked),
+            status,
+            grade,
+            percentage,
+            has_feedback,
+        }
+    }
+
+    fn merge(&mut self, other: AssessmentSubmissionInfo) {
+        self.file_submission_enabled |= other.file_submission_enabled;
+        self.submitted |= other.submitted;
+        self.marked |= other.marked;
+        self.status = self.status.max(other.status);
+        self.grade = self.grade.take().or(other.grade);
+        self.percentage = self.percentage.or(other.percentage);
+        self.has_feedback |= other.has_feedback;
+    }
+}
+
+/// One assessment with the fields the detail page needs, whichever endpoint it came from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssessmentDetail {
+    pub id: i64,
+    pub title: String,
+    pub subject: String,
+    pub code: Option<String>,
+    pub due: Option<String>,
+    pub status: String,
+    pub description: Option<String>,
+    pub programme: Option<i64>,
+    pub metaclass: Option<i64>,
+    pub submission: AssessmentSubmissionInfo,
+    pub source: AssessmentSource,
+}
+
+fn value_as_bool(value: &Value) -> Option<bool> {
+    value
+        .as_bool()
+        .or_else(|| value.as_i64().map(|i| i != 0))
+}
+
+impl AssessmentDetail {
+    fn from_value(value: &Value, source: AssessmentSource) -> Self {
+        let code = value["code"].as_str().map(|s| s.to_string());
+        Self {
+            id: value["id"].as_i64().unwrap_or(0),
+            title: value["title"].as_str().unwrap_or("Assessment").to_string(),
+            subject: value["subject"]
+                .as_str()
+                .or(code.as_deref())
+                .unwrap_or("")
+                .to_string(),
+            code,
+            due: value["due"]
+                .as_str()
+                .or_else(|| value["dueDate"].as_str())
+                .map(|s| s.to_string()),
+            status: assessment_status(value),
+            description: value["description"].as_str().map(|s| s.to_string()),
+            programme: value
+                .get("programme")
+                .or_else(|| value.get("programmeID"))
+                .and_then(|v| v.as_i64()),
+            metaclass: value
+                .get("metaclass")
+                .or_else(|| value.get("metaID"))
+                .and_then(|v| v.as_i64()),
+            submission: AssessmentSubmissionInfo::from_value(value),
+            source,
+        }
+    }
+
+    /// Fill in fields only the detail endpoint returns
+    fn merge_detail(&mut self, detail: &Value) {
+        if self.description.is_none() {
+            self.description = detail["description"].as_str().map(|s| s.to_string());
+        }
+        self.submission.merge(AssessmentSubmissionInfo::from_value(detail));
+        if let Some(status) = self.submission.status {
+            self.status = status.as_str().to_string();
+        }
+    }
+}
+
+/// Typed lookup of a single assessment by ID, checking upcoming, past and detail endpoints.
+/// Unlike `get_assessment_detail` (raw payload incl. criteria/resources) this always returns
+/// the same shape. Uses mock data when dev_sensitive_info_hider is enabled.
+#[tauri::command]
+pub async fn get_assessment_by_id(
+    id: i64,
+    programme: Option<i64>,
+    metaclass: Option<i64>,
+) -> Result<AssessmentDetail, String> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        let mock: Value = serde_json::from_str(&mock_assessment_detail(id as i32))
+            .map_err(|e| e.to_string())?;
+        return Ok(AssessmentDetail::from_value(
+            &mock["payload"],
+            AssessmentSource::Detail,
+        ));
+    }
+
+    let (value, source) = lookup_assessment(id, programme, metaclass)
+        .await?
+        .ok_or_else(|| format!("Assessment {} not found", id))?;
+    let mut detail = AssessmentDetail::from_value(&value, source);
+
+    // List entries don't carry the description/submission settings
+    if source != AssessmentSource::Detail {
+        if let Some(metaclass) = detail.metaclass.or(metaclass) {
+            if let Ok(response) = fetch_assessment_detail_raw(id, metaclass).await {
+                if let Ok(data) = serde_json::from_str::<Value>(&response) {
+                    detail.merge_detail(&data["payload"]);
+                }
+            }
+        }
+    }
+    if detail.programme.is_none() {
+        detail.programme = programme;
+    }
+    if detail.metaclass.is_none() {
+        detail.metaclass = metaclass;
+    }
+
+    Ok(detail)
+}
+
+/// Typed assessments from a list endpoint, without duplicates, ordered by due date (soonest
+/// first for upcoming, most recent first for past)
+fn typed_assessment_list(entries: &[Value], source: AssessmentSource) -> Vec<AssessmentDetail> {
+    let mut seen = HashSet::new();
+    let mut list: Vec<AssessmentDetail> = entries
+        .iter()
+        .filter(|entry| entry["id"].as_i64().map_or(false, |id| seen.insert(id)))
+        .map(|entry| AssessmentDetail::from_value(entry, source))
+        .collect();
+    list.sort_by_key(|a| a.due.as_deref().and_then(parse_assessment_due));
+    if source == AssessmentSource::Past {
+        list.reverse();
+    }
+    list
+}
+
+/// Serve an assessment list from the db cache, fetching and caching it when missing or when
+/// `refresh` is set
+async fn cached_assessment_list(
+    cache_key: String,
+    refresh: bool,
+    source: AssessmentSource,
+    fetch: impl Future<Output = Result<Vec<Value>, String>>,
+) -> Result<Vec<AssessmentDetail>, String> {
+    if !refresh {
+        let cached = database::db_cache_get(cache_key.clone())
+            .ok()
+            .flatten()
+            .and_then(|value| serde_json::from_value::<Vec<AssessmentDetail>>(value).ok());
+        if let Some(list) = cached {
+            return Ok(list);
+        }
+    }
+
+    let list = typed_assessment_list(&fetch.await?, source);
+    if let Ok(value) = serde_json::to_value(&list) {
+        let _ = database::db_cache_set(cache_key, value, Some(ASSESSMENT_LIST_CACHE_TTL_MINUTES));
+    }
+    Ok(list)
+}
+
+/// Mock assessments as list entries, for dev_sensitive_info_hider
+fn mock_assessment_entries() -> Vec<Value> {
+    mock_processed_assessments_response()
+        .assessments
+        .iter()
+        .filter_map(|assessment| serde_json::to_value(assessment).ok())
+        .collect()
+}
+
+/// Upcoming assessments across all subjects, with normalised status and grade fields. Cached
+/// for a few minutes unless `refresh` is set.
+#[tauri::command]
+pub async fn get_upcoming_assessments(
+    refresh: Option<bool>,
+) -> Result<Vec<AssessmentDetail>, String> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        let now = chrono::Utc::now();
+        let upcoming: Vec<Value> = mock_assessment_entries()
+            .into_iter()
+            .filter(|a| parse_assessment_due(a["due"].as_str().unwrap_or("")) > Some(now))
+            .collect();
+        return Ok(typed_assessment_list(&upcoming, AssessmentSource::Upcoming));
+    }
+
+    cached_assessment_list(
+        "assessments:upcoming".to_string(),
+        refresh.unwrap_or(false),
+        AssessmentSource::Upcoming,
+        fetch_upcoming_assessments(),
+    )
+    .await
+}
 
-    Ok(response)
+/// Past assessments for one subject, with normalised status and grade fields. Cached for a
+/// few minutes unless `refresh` is set.
+#[tauri::command]
+pub async fn get_past_assessments(
+    programme: i32,
+    metaclass: i32,
+    refresh: Option<bool>,
+) -> Result<Vec<AssessmentDetail>, String> {
+    let mut list = if crate::settings::Settings::load().dev_sensitive_info_hider {
+        let now = chrono::Utc::now();
+        let past: Vec<Value> = mock_assessment_entries()
+            .into_iter()
+            .filter(|a| a["metaclass"].as_i64() == Some(metaclass as i64))
+            .filter(|a| parse_assessment_due(a["due"].as_str().unwrap_or("")) <= Some(now))
+            .collect();
+        typed_assessment_list(&past, AssessmentSource::Past)
+    } else {
+        cached_assessment_list(
+            format!("assessments:past:{}-{}", programme, metaclass),
+            refresh.unwrap_or(false),
+            AssessmentSource::Past,
+            fetch_past_assessments(programme, metaclass),
+        )
+        .await?
+    };
+
+    for assessment in &mut list {
+        assessment.programme = assessment.programme.or(Some(programme as i64));
+        assessment.metaclass = assessment.metaclass.or(Some(metaclass as i64));
+    }
+    Ok(list)
 }
 
 /// Process and merge all assessments data
@@ -375,11 +762,8 @@ pub async fn get_processed_assessments() -> Result<ProcessedAssessmentsResponse,
         );
     }
 
-    // Step 1: Fetch subjects and lesson colours in parallel
-    let (folders_result, colours_result) = tokio::join!(fetch_subjects(), fetch_lesson_colours());
-
-    let folders = folders_result?;
-    let colours = colours_result?;
+    // Step 1: Fetch subjects
+    let folders = fetch_subjects().await?;
 
     // Step 2: Process subjects
     let all_subjects: Vec<Subject> = folders.iter().flat_map(|f| f.subjects.clone()).collect();
@@ -409,21 +793,7 @@ pub async fn get_processed_assessments() -> Result<ProcessedAssessmentsResponse,
         filters.insert(subject.code.clone(), is_active);
     }
 
-    // Maps for Step 6: avoid O(n²) scans of colours/subjects per assessment
-    let mut colour_by_code: HashMap<String, String> = HashMap::new();
-    for c in &colours {
-        let Some(name) = c.get("name").and_then(|v| v.as_str()) else {
-            continue;
-        };
-        let Some(value) = c.get("value").and_then(|v| v.as_str()) else {
-            continue;
-        };
-        if let Some(code) = name.strip_prefix("timetable.subject.colour.") {
-            colour_by_code
-                .entry(code.to_string())
-                .or_insert_with(|| value.to_string());
-        }
-    }
+    // Map for Step 6: avoid O(n²) scans of subjects per assessment
     let mut metaclass_by_code: HashMap<String, i32> = HashMap::new();
     for s in &all_subjects {
         metaclass_by_code
@@ -472,6 +842,17 @@ pub async fn get_processed_assessments() -> Result<ProcessedAssessmentsResponse,
     // Performance notes:
     // - Move JSON object into `extra_map` (no cloning of every value).
     // - Precompute the due-date "YYYY-MM-DD" key once so sorting doesn't repeatedly split.
+    let codes: Vec<&str> = all_subjects
+        .iter()
+        .map(|s| s.code.as_str())
+        .chain(
+            unique_assessments_map
+                .values()
+                .filter_map(|a| a.get("code").and_then(|v| v.as_str())),
+        )
+        .collect();
+    let colour_by_code = crate::subject_colors::colors_for(codes).await;
+
     let mut processed_with_due_key: Vec<(String, Assessment)> = Vec::new();
     for (_, assessment_value) in unique_assessments_map {
         let extra_map: HashMap<String, Value> = match assessment_value {
@@ -579,3 +960,121 @@ pub async fn get_processed_assessments() -> Result<ProcessedAssessmentsResponse,
         years,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_submission_info_from_seqta_fields() {
+        let released = json!({
+            "status": "MARKS_RELEASED",
+            "hasFeedback": true,
+            "results": { "grade": "B+", "percentage": 78.5 }
+        });
+        let info = AssessmentSubmissionInfo::from_value(&released);
+        assert_eq!(info.status, Some(SubmissionStatus::Returned));
+        assert!(info.submitted && info.marked && info.has_feedback);
+        assert_eq!(info.grade.as_deref(), Some("B+"));
+        assert_eq!(info.percentage, Some(78.5));
+        assert_eq!(assessment_status(&released), "returned");
+
+        let detail = json!({
+            "marked": 1,
+            "criteria": [{ "results": { "grade": "A", "percentage": "91" } }]
+        });
+        let info = AssessmentSubmissionInfo::from_value(&detail);
+        assert_eq!(info.status, Some(SubmissionStatus::Marked));
+        assert_eq!(info.percentage, Some(91.0));
+
+        let submitted = json!({ "hasSubmission": true, "due": "2000-01-01" });
+        assert_eq!(assessment_status(&submitted), "submitted");
+    }
+
+    #[test]
+    fn test_due_date_in_school_timezone() {
+        // 8pm in New York is already midnight UTC
+        let due = "2024-05-01T20:00:00";
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T22:00:00Z").unwrap();
+        let as_utc = parse_assessment_due_in(due, &chrono_tz::Tz::UTC).unwrap();
+        let as_local = parse_assessment_due_in(due, &chrono_tz::America::New_York).unwrap();
+        assert!(as_utc < now, "overdue when read as UTC");
+        assert!(as_local > now, "still pending at the school");
+        assert_eq!(as_local.to_rfc3339(), "2024-05-02T00:00:00+00:00");
+
+        // Plain dates start at the school's midnight; explicit offsets are kept
+        let perth = chrono_tz::Australia::Perth;
+        assert_eq!(
+            parse_assessment_due_in("2024-05-01", &perth)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-04-30T16:00:00+00:00"
+        );
+        assert_eq!(
+            parse_assessment_due_in("2024-05-01T09:00:00Z", &perth)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-01T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_status_falls_back_to_due_date() {
+        let overdue = json!({ "due": "2000-01-01", "status": "PENDING" });
+        assert_eq!(AssessmentSubmissionInfo::from_value(&overdue).status, None);
+        assert_eq!(assessment_status(&overdue), "overdue");
+        assert_eq!(
+            assessment_status(&json!({ "due": "2999-01-01" })),
+            "pending"
+        );
+
+        let mut info = AssessmentSubmissionInfo::from_value(&json!({ "submitted": true }));
+        info.merge(AssessmentSubmissionInfo::from_value(
+            &json!({ "status": "MARKS_RELEASED" }),
+        ));
+        assert_eq!(info.status, Some(SubmissionStatus::Returned));
+        assert!(info.marked);
+    }
+
+    #[test]
+    fn test_assessment_list_shapes() {
+        let upcoming = json!({
+            "status": "200",
+            "payload": [
+                { "id": 2, "title": "Essay", "code": "ENG", "due": "2999-03-01" },
+                { "id": 1, "title": "Test", "code": "MATH", "due": "2999-02-01" },
+                { "id": 1, "title": "Test", "code": "MATH", "due": "2999-02-01" }
+            ]
+        });
+        let list = typed_assessment_list(
+            &assessment_list_entries(&upcoming),
+            AssessmentSource::Upcoming,
+        );
+        assert_eq!(list.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(list[0].status, "pending");
+        assert_eq!(list[0].source, AssessmentSource::Upcoming);
+
+        let past = json!({
+            "status": "200",
+            "payload": {
+                "pending": [{ "id": 3, "title": "Lab", "due": "2000-02-01" }],
+                "tasks": [{
+                    "id": 4,
+                    "title": "Report",
+                    "due": "2000-03-01",
+                    "status": "MARKS_RELEASED",
+                    "results": { "grade": "A", "percentage": 92 }
+                }]
+            }
+        });
+        let list = typed_assessment_list(&assessment_list_entries(&past), AssessmentSource::Past);
+        assert_eq!(list.iter().map(|a| a.id).collect::<Vec<_>>(), vec![4, 3]);
+        assert_eq!(list[0].status, "returned");
+        assert_eq!(list[0].submission.grade.as_deref(), Some("A"));
+        assert_eq!(list[0].submission.percentage, Some(92.0));
+        assert_eq!(list[1].status, "overdue");
+
+        assert!(assessment_list_entries(&json!({ "payload": {} })).is_empty());
+        assert!(assessment_list_entries(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,354 @@
+//! The school's bell times: when each period starts and ends on each day. SEQTA's settings
+//! config doesn't include period definitions, so they're worked out from the timetable,
+//! collecting every period taught on each weekday over the last few weeks. Schools on a
+//! two-week (A/B) cycle get a separate schedule for each week.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::database;
+use crate::seqta_mentions;
+use crate::timetable_ics::parse_lesson_time;
+use crate::timezone;
+
+/// Weeks of timetable, ending with the current one, that bell times are worked out from.
+/// Several weeks so holidays and free periods don't leave gaps.
+const SAMPLE_WEEKS: i64 = 6;
+const BELL_TIMES_CACHE_KEY: &str = "bell_times";
+const BELL_TIMES_CACHE_TTL_MINUTES: i64 = 7 * 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BellPeriod {
+    pub name: String,
+    /// `HH:MM`, school time
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaySchedule {
+    /// 1 (Monday) to 7 (Sunday)
+    pub weekday: u32,
+    /// "A" or "B" on a two-week cycle, `None` when every week is the same
+    pub week: Option<String>,
+    pub periods: Vec<BellPeriod>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BellTimes {
+    /// 1, or 2 for schools on an A/B cycle
+    pub cycle_weeks: u32,
+    pub days: Vec<DaySchedule>,
+}
+
+/// Returned by `get_current_period`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CurrentPeriod {
+    /// The period under way, if any
+    pub current: Option<BellPeriod>,
+    /// The next period starting later today
+    pub next: Option<BellPeriod>,
+    /// This week of the cycle, when the school has one
+    pub week: Option<String>,
+}
+
+/// "A" or "B": the week of a two-week cycle `date` falls in, counted from Monday 5 January
+/// 1970 so it doesn't jump at the end of a year.
+fn cycle_week(date: NaiveDate) -> &'static str {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 5).unwrap();
+    if (date - epoch).num_days().div_euclid(7) % 2 == 0 {
+        "A"
+    } else {
+        "B"
+    }
+}
+
+/// Periods per (weekday, cycle week) in timetable `items`, keyed by start and end time. The
+/// name is SEQTA's `period` label, or "Period n" by position when lessons don't have one.
+fn periods_by_day(
+    items: &[Value],
+) -> BTreeMap<(u32, &'static str), BTreeMap<(NaiveTime, NaiveTime), String>> {
+    let mut by_day: BTreeMap<_, BTreeMap<_, Option<String>>> = BTreeMap::new();
+    for item in items {
+        let Some(date) = item["date"]
+            .as_str()
+            .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        let times = item["from"]
+            .as_str()
+            .and_then(parse_lesson_time)
+            .zip(item["until"].as_str().and_then(parse_lesson_time))
+            .filter(|(start, end)| end > start);
+        let Some(times) = times else {
+            continue;
+        };
+        let label = match &item["period"] {
+            Value::String(p) => Some(p.trim().to_string()).filter(|p| !p.is_empty()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        let periods = by_day
+            .entry((date.weekday().number_from_monday(), cycle_week(date)))
+            .or_default();
+        let name = periods.entry(times).or_default();
+        if name.is_none() {
+            *name = label;
+        }
+    }
+
+    by_day
+        .into_iter()
+        .map(|(day, periods)| {
+            let periods = periods
+                .into_iter()
+                .enumerate()
+                .map(|(i, (times, label))| {
+                    let name = match label {
+                        // Bare numbers read better with "Period" in front
+                        Some(label) if label.chars().all(|c| c.is_ascii_digit()) => {
+                            format!("Period {}", label)
+                        }
+                        Some(label) => label,
+                        None => format!("Period {}", i + 1),
+                    };
+                    (times, name)
+                })
+                .collect();
+            (day, periods)
+        })
+        .collect()
+}
+
+fn day_schedule(
+    weekday: u32,
+    week: Option<&str>,
+    periods: BTreeMap<(NaiveTime, NaiveTime), String>,
+) -> DaySchedule {
+    DaySchedule {
+        weekday,
+        week: week.map(str::to_string),
+        periods: periods
+            .into_iter()
+            .map(|((start, end), name)| BellPeriod {
+                name,
+                start: start.format("%H:%M").to_string(),
+                end: end.format("%H:%M").to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Bell times from timetable `items`. The school is taken to be on an A/B cycle when some
+/// weekday taught in both weeks has different periods in each.
+fn derive_bell_times(items: &[Value]) -> BellTimes {
+    let by_day = periods_by_day(items);
+    let two_week = by_day.iter().any(|((weekday, week), periods)| {
+        *week == "A"
+            && by_day
+                .get(&(*weekday, "B"))
+                .is_some_and(|other| other.keys().ne(periods.keys()))
+    });
+
+    let days = if two_week {
+        by_day
+            .into_iter()
+            .map(|((weekday, week), periods)| day_schedule(weekday, Some(week), periods))
+            .collect()
+    } else {
+        let mut merged: BTreeMap<u32, BTreeMap<(NaiveTime, NaiveTime), String>> = BTreeMap::new();
+        for ((weekday, _), periods) in by_day {
+            let day = merged.entry(weekday).or_default();
+            for (times, name) in periods {
+                day.entry(times).or_insert(name);
+            }
+        }
+        merged
+            .into_iter()
+            .map(|(weekday, periods)| day_schedule(weekday, None, periods))
+            .collect()
+    };
+    BellTimes {
+        cycle_weeks: if two_week { 2 } else { 1 },
+        days,
+    }
+}
+
+/// The period under way and the next one at school time `now`.
+fn current_period(bells: &BellTimes, now: NaiveDateTime) -> CurrentPeriod {
+    let date = now.date();
+    let time = now.time();
+    let week = (bells.cycle_weeks == 2).then(|| cycle_week(date).to_string());
+    let periods = bells
+        .days
+        .iter()
+        .find(|day| day.weekday == date.weekday().number_from_monday() && day.week == week)
+        .map(|day| day.periods.as_slice())
+        .unwrap_or_default();
+    let times =
+        |period: &BellPeriod| parse_lesson_time(&period.start).zip(parse_lesson_time(&period.end));
+
+    let current = periods
+        .iter()
+        .find(|p| times(p).is_some_and(|(start, end)| time >= start && time < end));
+    let next = periods
+        .iter()
+        .find(|p| times(p).is_some_and(|(start, _)| start > time));
+    CurrentPeriod {
+        current: current.cloned(),
+        next: next.cloned(),
+        week,
+    }
+}
+
+/// The school's periods for each weekday (and cycle week), worked out from the last few weeks
+/// of timetable. Cached for a week unless `refresh` is set.
+#[tauri::command]
+pub async fn get_bell_times(refresh: Option<bool>) -> Result<BellTimes, String> {
+    if !refresh.unwrap_or(false) {
+        let cached = database::db_cache_get(BELL_TIMES_CACHE_KEY.to_string())
+            .ok()
+            .flatten()
+            .and_then(|value| serde_json::from_value::<BellTimes>(value).ok());
+        if let Some(bells) = cached {
+            return Ok(bells);
+        }
+    }
+
+    let today = timezone::today();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let from = monday - Duration::weeks(SAMPLE_WEEKS - 1);
+    let until = monday + Duration::days(6);
+    let items = seqta_mentions::fetch_timetable_range(from, until)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let bells = derive_bell_times(&items);
+    // An empty timetable (e.g. the holidays) isn't worth keeping for a week
+    if !bells.days.is_empty() {
+        if let Ok(value) = serde_json::to_value(&bells) {
+            let _ = database::db_cache_set(
+                BELL_TIMES_CACHE_KEY.to_string(),
+                value,
+                Some(BELL_TIMES_CACHE_TTL_MINUTES),
+            );
+        }
+    }
+    Ok(bells)
+}
+
+/// The period under way right now and the next one today, in the school's timezone.
+#[tauri::command]
+pub async fn get_current_period() -> Result<CurrentPeriod, String> {
+    let bells = get_bell_times(None).await?;
+    let now = Utc::now()
+        .with_timezone(&timezone::school_timezone())
+        .naive_local();
+    Ok(current_period(&bells, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lesson(date: &str, period: &str, from: &str, until: &str) -> Value {
+        json!({ "date": date, "period": period, "from": from, "until": until })
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_time(parse_lesson_time(time).unwrap())
+    }
+
+    fn names(period: &Option<BellPeriod>) -> Option<&str> {
+        period.as_ref().map(|p| p.name.as_str())
+    }
+
+    #[test]
+    fn test_current_period() {
+        // 6 and 13 May 2024 are Mondays in different weeks of the cycle
+        assert_ne!(
+            cycle_week(NaiveDate::from_ymd_opt(2024, 5, 6).unwrap()),
+            cycle_week(NaiveDate::from_ymd_opt(2024, 5, 13).unwrap())
+        );
+        let items = vec![
+            lesson("2024-05-06", "1", "08:50:00", "09:45:00"),
+            lesson("2024-05-06", "3", "11:05:00", "12:00:00"),
+            // Period 2 only shows up a fortnight later, when the student had a class in it
+            lesson("2024-05-20", "2", "09:45:00", "10:40:00"),
+            lesson("2024-05-06", "1", "08:50:00", "09:45:00"),
+            // Wednesdays run to a different bell
+            lesson("2024-05-08", "", "08:50", "10:20"),
+            lesson("2024-05-08", "", "10:40", "12:10"),
+            lesson("2024-05-08", "", "not a time", "12:10"),
+        ];
+        let bells = derive_bell_times(&items);
+        assert_eq!(bells.cycle_weeks, 1);
+        assert_eq!(bells.days.len(), 2);
+        let monday = &bells.days[0];
+        assert_eq!(monday.weekday, 1);
+        assert_eq!(monday.week, None);
+        let monday_names: Vec<&str> = monday.periods.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(monday_names, vec!["Period 1", "Period 2", "Period 3"]);
+        assert_eq!(bells.days[1].periods[1].name, "Period 2");
+        assert_eq!(bells.days[1].periods[1].start, "10:40");
+
+        let now = current_period(&bells, at("2024-06-03", "07:30"));
+        assert_eq!(
+            (names(&now.current), names(&now.next)),
+            (None, Some("Period 1"))
+        );
+        let now = current_period(&bells, at("2024-06-03", "09:45"));
+        assert_eq!(
+            (names(&now.current), names(&now.next)),
+            (Some("Period 2"), Some("Period 3"))
+        );
+        // Recess
+        let now = current_period(&bells, at("2024-06-03", "10:50"));
+        assert_eq!(
+            (names(&now.current), names(&now.next)),
+            (None, Some("Period 3"))
+        );
+        let now = current_period(&bells, at("2024-06-05", "10:30"));
+        assert_eq!(
+            (names(&now.current), names(&now.next)),
+            (None, Some("Period 2"))
+        );
+        let now = current_period(&bells, at("2024-06-03", "15:30"));
+        assert_eq!((names(&now.current), names(&now.next)), (None, None));
+        // Saturday
+        let now = current_period(&bells, at("2024-06-08", "10:00"));
+        assert_eq!((names(&now.current), names(&now.next)), (None, None));
+    }
+
+    #[test]
+    fn test_current_period_on_two_week_cycle() {
+        // Week A Mondays start with a long period, week B Mondays with two short ones
+        let items = vec![
+            lesson("2024-05-06", "1", "08:50", "10:40"),
+            lesson("2024-05-13", "1", "08:50", "09:45"),
+            lesson("2024-05-13", "2", "09:45", "10:40"),
+            lesson("2024-05-20", "1", "08:50", "10:40"),
+        ];
+        let bells = derive_bell_times(&items);
+        assert_eq!(bells.cycle_weeks, 2);
+        assert_eq!(bells.days.len(), 2);
+
+        let week_of = |date: &str| cycle_week(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap());
+        let long = current_period(&bells, at("2024-06-03", "10:00"));
+        assert_eq!(long.week.as_deref(), Some(week_of("2024-06-03")));
+        assert_eq!(long.current.as_ref().unwrap().end, "10:40");
+        assert_eq!(long.next, None);
+
+        let short = current_period(&bells, at("2024-05-27", "09:00"));
+        assert_eq!(short.week.as_deref(), Some(week_of("2024-05-27")));
+        assert_eq!(names(&short.current), Some("Period 1"));
+        assert_eq!(short.current.as_ref().unwrap().end, "09:45");
+        assert_eq!(names(&short.next), Some("Period 2"));
+    }
+}
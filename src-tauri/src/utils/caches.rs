@@ -0,0 +1,444 @@
+use chrono::Datelike;
+use futures::future::{AbortHandle, AbortRegistration, Abortable, BoxFuture, FutureExt};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::{
+    assessments, database, forum_photos, grade_analyser, lesson_summary, logger, messages, news,
+    seqta_mentions, session, student_info, theme_manager, timezone, weather,
+};
+
+/// Prewarm fetches in flight at once, so a fresh login doesn't hit SEQTA with a burst.
+const PREWARM_CONCURRENCY: usize = 2;
+/// A session warmed this recently isn't warmed again; matches the shortest cache TTL involved.
+const PREWARM_FRESH_FOR: Duration = Duration::from_secs(5 * 60);
+
+static PREWARM_STATE: Mutex<PrewarmState> = Mutex::new(PrewarmState {
+    generation: 0,
+    running: None,
+    last_warmed: None,
+});
+
+/// Entries removed from one cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClearedCache {
+    pub name: String,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClearCachesSummary {
+    pub caches: Vec<ClearedCache>,
+    pub total_entries: usize,
+    /// Caches that couldn't be cleared, with the reason
+    pub errors: Vec<String>,
+}
+
+/// Hit, miss and eviction counters for one cache. Relaxed atomics, so counting never
+/// takes the cache's own lock.
+pub struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheCounters {
+    pub const fn new() -> Self {
+        Self {
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record entries dropped because they expired.
+    pub fn evicted(&self, entries: usize) {
+        self.evictions.fetch_add(entries as u64, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.evictions.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, name: &str) -> CacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        CacheStats {
+            name: name.to_string(),
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: (lookups > 0).then(|| hits as f64 / lookups as f64),
+        }
+    }
+}
+
+/// Counters for one cache since launch or the last `clear_all_caches`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Hits as a fraction of lookups; `None` until the cache has been queried
+    pub hit_rate: Option<f64>,
+}
+
+/// Counters for every cache that tracks them.
+pub fn cache_stats() -> Vec<CacheStats> {
+    vec![
+        seqta_mentions::MENTION_CACHE_STATS.stats("mentions"),
+        seqta_mentions::TIMETABLE_CACHE_STATS.stats("timetable"),
+        seqta_mentions::COURSE_CACHE_STATS.stats("courses"),
+        database::DB_CACHE_STATS.stats("database"),
+        messages::MESSAGE_CACHE_STATS.stats("messages"),
+        student_info::STUDENT_INFO_CACHE_STATS.stats("student_info"),
+        grade_analyser::GRADE_ANALYSIS_CACHE_STATS.stats("grade_analysis"),
+        lesson_summary::LESSON_SUMMARY_CACHE_STATS.stats("lesson_summaries"),
+    ]
+}
+
+fn reset_cache_stats() {
+    seqta_mentions::MENTION_CACHE_STATS.reset();
+    seqta_mentions::TIMETABLE_CACHE_STATS.reset();
+    seqta_mentions::COURSE_CACHE_STATS.reset();
+    database::DB_CACHE_STATS.reset();
+    messages::MESSAGE_CACHE_STATS.reset();
+    student_info::STUDENT_INFO_CACHE_STATS.reset();
+    grade_analyser::GRADE_ANALYSIS_CACHE_STATS.reset();
+    lesson_summary::LESSON_SUMMARY_CACHE_STATS.reset();
+}
+
+/// Hit, miss and eviction counts per cache, for tuning TTLs and checking stale-data reports.
+#[tauri::command]
+pub fn get_cache_stats() -> Vec<CacheStats> {
+    cache_stats()
+}
+
+/// Drop the in-memory caches only. Used when switching profile, where the on-disk caches
+/// already belong to the old profile's directory.
+pub fn clear_memory_caches() -> usize {
+    prewarm_state().last_warmed = None;
+    seqta_mentions::clear_caches()
+        + news::clear_cache()
+        + weather::clear_cache()
+        + messages::clear_cache()
+        + student_info::clear_cache()
+        + grade_analyser::clear_cache()
+        + lesson_summary::clear_cache()
+}
+
+/// Flush every in-memory and on-disk cache the app owns: mention and timetable lookups,
+/// messages, the student profile, grade analyses, lesson summaries, news, weather, the database
+/// cache table, forum photos and theme store images, and reset the hit/miss counters. Notes,
+/// settings and the session are untouched.
+/// A failure in one cache doesn't stop the others from being cleared.
+#[tauri::command]
+pub fn clear_all_caches(app: AppHandle) -> Result<ClearCachesSummary, String> {
+    let results: Vec<(&str, Result<usize, String>)> = vec![
+        ("mentions", Ok(seqta_mentions::clear_caches())),
+        ("messages", Ok(messages::clear_cache())),
+        ("student_info", Ok(student_info::clear_cache())),
+        ("grade_analysis", Ok(grade_analyser::clear_cache())),
+        ("lesson_summaries", Ok(lesson_summary::clear_cache())),
+        ("news", Ok(news::clear_cache())),
+        ("weather", Ok(weather::clear_cache())),
+        ("database", database::clear_cache_entries()),
+        ("forum_photos", forum_photos::clear_photo_cache()),
+        ("theme_images", theme_manager::clear_theme_image_cache(app)),
+    ];
+    reset_cache_stats();
+    prewarm_state().last_warmed = None;
+
+    let mut summary = ClearCachesSummary {
+        caches: Vec::new(),
+        total_entries: 0,
+        errors: Vec::new(),
+    };
+    for (name, result) in results {
+        match result {
+            Ok(entries) => {
+                summary.total_entries += entries;
+                summary.caches.push(ClearedCache {
+                    name: name.to_string(),
+                    entries,
+                });
+            }
+            Err(e) => summary.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    println!(
+        "[DesQTA] Cleared {} cache entries ({} errors)",
+        summary.total_entries,
+        summary.errors.len()
+    );
+    Ok(summary)
+}
+
+/// Which session is being warmed or was warmed last, so repeat calls don't refetch.
+struct PrewarmState {
+    generation: u64,
+    /// (generation, session) of the prewarm in flight
+    running: Option<(u64, String, AbortHandle)>,
+    last_warmed: Option<(String, Instant)>,
+}
+
+impl PrewarmState {
+    /// Claim a prewarm for `session_key`. Returns `None` when that session is already being
+    /// warmed or was warmed within `PREWARM_FRESH_FOR`; a prewarm for another session is
+    /// aborted, since its results would belong to a stale login.
+    fn begin(&mut self, session_key: &str, now: Instant) -> Option<(u64, AbortRegistration)> {
+        if matches!(&self.running, Some((_, key, _)) if key == session_key) {
+            return None;
+        }
+        if matches!(&self.last_warmed, Some((key, at))
+            if key == session_key && now.duration_since(*at) < PREWARM_FRESH_FOR)
+        {
+            return None;
+        }
+        let (handle, registration) = AbortHandle::new_pair();
+        self.generation += 1;
+        let previous = self
+            .running
+            .replace((self.generation, session_key.to_string(), handle));
+        if let Some((_, _, previous)) = previous {
+            previous.abort();
+        }
+        Some((self.generation, registration))
+    }
+
+    /// Release the claim from `begin`, remembering the session as warm if the prewarm ran to
+    /// the end.
+    fn finish(&mut self, generation: u64, completed: bool, now: Instant) {
+        if !matches!(&self.running, Some((running, _, _)) if *running == generation) {
+            return;
+        }
+        let Some((_, key, _)) = self.running.take() else {
+            return;
+        };
+        if completed {
+            self.last_warmed = Some((key, now));
+        }
+    }
+
+    fn cancel(&mut self) -> bool {
+        match self.running.take() {
+            Some((_, _, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn prewarm_state() -> std::sync::MutexGuard<'static, PrewarmState> {
+    PREWARM_STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Entries loaded into one cache by a prewarm.
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmedCache {
+    pub name: String,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmSummary {
+    /// Caches filled, with how many entries each got
+    pub warmed: Vec<WarmedCache>,
+    /// Caches that couldn't be filled, with the reason
+    pub errors: Vec<String>,
+    /// True when nothing was fetched because this session is already warm or warming
+    pub skipped: bool,
+    pub duration_ms: u64,
+}
+
+/// The student profile first (the timetable and notices need the student id), then the rest
+/// with at most `PREWARM_CONCURRENCY` requests at a time.
+async fn warm_all() -> Vec<(&'static str, Result<usize, String>)> {
+    let mut results = vec![(
+        "student_info",
+        student_info::load_student_info().await.map(|_| 1),
+    )];
+
+    let today = timezone::today();
+    let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    let tasks: Vec<BoxFuture<'static, (&'static str, Result<usize, String>)>> = vec![
+        async { ("subjects", seqta_mentions::warm_subject_titles().await) }.boxed(),
+        async {
+            let assessments = assessments::get_upcoming_assessments(None).await;
+            ("upcoming_assessments", assessments.map(|list| list.len()))
+        }
+        .boxed(),
+        async move {
+            let notices =
+                seqta_mentions::search_mentions(String::new(), Some("notice".to_string()), None)
+                    .await;
+            (
+                "notices",
+                notices
+                    .map(|results| results.items.len())
+                    .map_err(|e| e.to_string()),
+            )
+        }
+        .boxed(),
+        async move {
+            let lessons =
+                seqta_mentions::fetch_timetable_range(monday, monday + chrono::Duration::days(6))
+                    .await;
+            (
+                "timetable",
+                lessons.map(|l| l.len()).map_err(|e| e.to_string()),
+            )
+        }
+        .boxed(),
+    ];
+    results.extend(
+        stream::iter(tasks)
+            .buffer_unordered(PREWARM_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await,
+    );
+    results
+}
+
+/// Fetch the data most pages open with (student profile, subjects, upcoming assessments,
+/// today's notices and this week's timetable) so the first page loads after a login hit warm
+/// caches. Does nothing if the current session is already warm or being warmed. Fails with
+/// "Cancelled" if `cancel_cache_prewarm` stops it.
+#[tauri::command]
+pub async fn prewarm_caches() -> Result<PrewarmSummary, String> {
+    let current = session::Session::load();
+    if current.base_url.is_empty() || current.jsessionid.is_empty() {
+        return Err("No active session. Please log in first.".to_string());
+    }
+
+    let started = Instant::now();
+    let Some((generation, registration)) = prewarm_state().begin(&current.jsessionid, started)
+    else {
+        return Ok(PrewarmSummary {
+            warmed: Vec::new(),
+            errors: Vec::new(),
+            skipped: true,
+            duration_ms: 0,
+        });
+    };
+
+    let results = Abortable::new(warm_all(), registration).await;
+    prewarm_state().finish(generation, results.is_ok(), Instant::now());
+    let results = results.map_err(|_| "Cancelled".to_string())?;
+
+    let mut summary = PrewarmSummary {
+        warmed: Vec::new(),
+        errors: Vec::new(),
+        skipped: false,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    for (name, result) in results {
+        match result {
+            Ok(entries) => summary.warmed.push(WarmedCache {
+                name: name.to_string(),
+                entries,
+            }),
+            Err(e) => summary.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "caches",
+            "prewarm_caches",
+            "Prewarmed caches",
+            serde_json::json!({
+                "warmed": summary.warmed,
+                "errors": summary.errors,
+                "duration_ms": summary.duration_ms
+            }),
+        );
+    }
+    Ok(summary)
+}
+
+/// Start `prewarm_caches` without waiting for it, e.g. straight after a login.
+pub fn prewarm_in_background() {
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = prewarm_caches().await {
+            println!("[DesQTA] Cache prewarm stopped: {}", e);
+        }
+    });
+}
+
+/// Stop a running cache prewarm. Returns false if none was running.
+#[tauri::command]
+pub fn cancel_cache_prewarm() -> bool {
+    prewarm_state().cancel()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prewarm_runs_once_per_session() {
+        let mut state = PrewarmState {
+            generation: 0,
+            running: None,
+            last_warmed: None,
+        };
+        let now = Instant::now();
+
+        let (first, _) = state.begin("session-a", now).unwrap();
+        assert!(state.begin("session-a", now).is_none());
+        state.finish(first, true, now);
+        assert!(state.begin("session-a", now).is_none());
+        assert!(state.begin("session-a", now + PREWARM_FRESH_FOR).is_some());
+
+        // A new login supersedes the prewarm of the old one
+        let (_, old_registration) = state.begin("session-b", now).unwrap();
+        let old = Abortable::new(async {}, old_registration);
+        let (newer, _) = state.begin("session-c", now).unwrap();
+        assert!(old.is_aborted());
+        // The superseded prewarm finishing doesn't release the newer one
+        state.finish(newer - 1, true, now);
+        assert!(state.begin("session-c", now).is_none());
+        assert!(state.cancel());
+        assert!(!state.cancel());
+    }
+
+    #[test]
+    fn test_cache_counters_hit_rate_and_reset() {
+        let counters = CacheCounters::new();
+        assert_eq!(counters.stats("test").hit_rate, None);
+
+        counters.hit();
+        counters.hit();
+        counters.hit();
+        counters.miss();
+        counters.evicted(2);
+        let stats = counters.stats("test");
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (3, 1, 2));
+        assert_eq!(stats.hit_rate, Some(0.75));
+
+        counters.reset();
+        let stats = counters.stats("test");
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (0, 0, 0));
+        assert_eq!(stats.hit_rate, None);
+    }
+}
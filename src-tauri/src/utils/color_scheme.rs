@@ -0,0 +1,261 @@
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::logger;
+use crate::settings::Settings;
+use crate::theme_manager::{AutoSwitchTime, ThemeManager};
+
+/// Bumped whenever the scheduler is restarted; an older loop exits when it notices.
+static SCHEDULER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "light" => Some(Self::Light),
+            "dark" => Some(Self::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of the `color-scheme-changed` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorSchemeChange {
+    pub scheme: ColorScheme,
+    /// "override" when pinned by the user, "schedule" when driven by the theme's switch times
+    pub source: String,
+    /// Local time of the next scheduled switch, if any
+    pub next_switch: Option<String>,
+}
+
+/// Parse a strict 24-hour `HH:MM` time.
+pub(crate) fn parse_switch_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    let (hours, minutes) = value.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    NaiveTime::from_hms_opt(hours.parse().ok()?, minutes.parse().ok()?, 0)
+}
+
+/// Validated light/dark switch times. Returns None (and logs) if either is malformed or both
+/// are the same, since no sensible schedule can be built from them.
+fn parse_schedule(times: &AutoSwitchTime) -> Option<(NaiveTime, NaiveTime)> {
+    match (parse_switch_time(&times.light), parse_switch_time(&times.dark)) {
+        (Some(light), Some(dark)) if light != dark => Some((light, dark)),
+        _ => {
+            if let Some(logger) = logger::get_logger() {
+                let _ = logger.log(
+                    logger::LogLevel::WARN,
+                    "color_scheme",
+                    "parse_schedule",
+                    "Ignoring invalid autoSwitchTime, expected distinct HH:MM values",
+                    serde_json::json!({ "light": times.light, "dark": times.dark }),
+                );
+            }
+            None
+        }
+    }
+}
+
+/// The scheme that should be active at `now`, plus the scheme and local time of the next switch.
+fn compute_schedule(
+    now: NaiveDateTime,
+    light: NaiveTime,
+    dark: NaiveTime,
+) -> (ColorScheme, ColorScheme, NaiveDateTime) {
+    let time = now.time();
+    let in_light = if light < dark {
+        time >= light && time < dark
+    } else {
+        // Light period wraps past midnight
+        time >= light || time < dark
+    };
+
+    let (current, next, at) = if in_light {
+        (ColorScheme::Light, ColorScheme::Dark, dark)
+    } else {
+        (ColorScheme::Dark, ColorScheme::Light, light)
+    };
+
+    let mut next_at = now.date().and_time(at);
+    if next_at <= now {
+        next_at += ChronoDuration::days(1);
+    }
+    (current, next, next_at)
+}
+
+/// Auto-switch times of the active theme, if it defines valid ones.
+fn active_schedule(app: &AppHandle) -> Option<(NaiveTime, NaiveTime)> {
+    let theme_name = Settings::load().current_theme?;
+    let manifest = ThemeManager::new(app.clone())
+        .load_theme_manifest(&theme_name)
+        .ok()?;
+    parse_schedule(manifest.settings.auto_switch_time.as_ref()?)
+}
+
+fn emit_change(app: &AppHandle, change: &ColorSchemeChange) {
+    let _ = app.emit("color-scheme-changed", change);
+}
+
+/// Start (or restart) the scheduler for the active theme. Emits the current scheme right away,
+/// then again at each switch time. The next switch is recomputed at least once per day so
+/// DST changes and clock adjustments are picked up.
+pub fn start(app: AppHandle) {
+    let generation = SCHEDULER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if let Some(scheme) = Settings::load()
+        .color_scheme_override
+        .as_deref()
+        .and_then(ColorScheme::parse)
+    {
+        emit_change(
+            &app,
+            &ColorSchemeChange {
+                scheme,
+                source: "override".to_string(),
+                next_switch: None,
+            },
+        );
+        return;
+    }
+
+    let Some((light, dark)) = active_schedule(&app) else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_emitted = None;
+        loop {
+            if SCHEDULER_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            let now = Local::now().naive_local();
+            let (current, _, next_at) = compute_schedule(now, light, dark);
+            if last_emitted != Some(current) {
+                emit_change(
+                    &app,
+                    &ColorSchemeChange {
+                        scheme: current,
+                        source: "schedule".to_string(),
+                        next_switch: Some(next_at.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                    },
+                );
+                last_emitted = Some(current);
+            }
+
+            // Wake at the switch or just after midnight, whichever comes first
+            let midnight = (now.date() + ChronoDuration::days(1)).and_time(NaiveTime::MIN);
+            let wake_at = next_at.min(midnight);
+            let wait = (wake_at - now)
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+                .max(Duration::from_secs(1));
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+/// Stop the scheduler, if one is running.
+pub fn stop() {
+    SCHEDULER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Re-read the active theme and override setting, e.g. after the user switches theme.
+#[tauri::command]
+pub fn refresh_color_scheme_schedule(app: AppHandle) -> Result<(), String> {
+    start(app);
+    Ok(())
+}
+
+/// Pin the color scheme to "light" or "dark" regardless of the theme's schedule.
+/// Pass None to go back to following the schedule.
+#[tauri::command]
+pub fn set_color_scheme_override(app: AppHandle, scheme: Option<String>) -> Result<(), String> {
+    let scheme = match scheme.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(value) => match ColorScheme::parse(value) {
+            Some(ColorScheme::Light) => Some("light".to_string()),
+            Some(ColorScheme::Dark) => Some("dark".to_string()),
+            None => return Err(format!("Invalid color scheme: {}", value)),
+        },
+        None => None,
+    };
+
+    let mut settings = Settings::load();
+    settings.color_scheme_override = scheme;
+    settings.save().map_err(|e| e.to_string())?;
+
+    start(app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, 1)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap()
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        parse_switch_time(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_switch_time() {
+        assert_eq!(parse_switch_time("07:30"), NaiveTime::from_hms_opt(7, 30, 0));
+        assert_eq!(parse_switch_time("7:30"), None);
+        assert_eq!(parse_switch_time("24:00"), None);
+        assert_eq!(parse_switch_time("12:60"), None);
+        assert_eq!(parse_switch_time("noon"), None);
+    }
+
+    #[test]
+    fn test_next_switch_around_boundary() {
+        let (light, dark) = (time("07:00"), time("19:00"));
+
+        // One second before the light switch: still dark, switching in a second
+        let (current, next, next_at) = compute_schedule(at(6, 59, 59), light, dark);
+        assert_eq!((current, next), (ColorScheme::Dark, ColorScheme::Light));
+        assert_eq!(next_at, at(7, 0, 0));
+
+        // Exactly at the switch: light is active and the next switch is dark tonight
+        let (current, next, next_at) = compute_schedule(at(7, 0, 0), light, dark);
+        assert_eq!((current, next), (ColorScheme::Light, ColorScheme::Dark));
+        assert_eq!(next_at, at(19, 0, 0));
+
+        // After dark: next light switch is tomorrow morning
+        let (current, _, next_at) = compute_schedule(at(19, 0, 0), light, dark);
+        assert_eq!(current, ColorScheme::Dark);
+        assert_eq!(next_at, at(7, 0, 0) + ChronoDuration::days(1));
+    }
+
+    #[test]
+    fn test_next_switch_when_light_wraps_midnight() {
+        // Night-shift style schedule: light from 22:00 until 06:00
+        let (light, dark) = (time("22:00"), time("06:00"));
+        let (current, next, next_at) = compute_schedule(at(23, 30, 0), light, dark);
+        assert_eq!((current, next), (ColorScheme::Light, ColorScheme::Dark));
+        assert_eq!(next_at, at(6, 0, 0) + ChronoDuration::days(1));
+
+        let (current, _, next_at) = compute_schedule(at(12, 0, 0), light, dark);
+        assert_eq!(current, ColorScheme::Dark);
+        assert_eq!(next_at, at(22, 0, 0));
+    }
+}
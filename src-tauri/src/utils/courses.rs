@@ -1,9 +1,16 @@
+use super::database;
 use super::netgrab;
 use super::netgrab::RequestMethod;
+use super::text_search::{create_snippet, highlight_match, SearchMatch};
+use crate::lesson_summary;
 use crate::logger;
+use crate::sanitization;
+use crate::seqta_mentions;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 // --- Struct Definitions ---
 
@@ -217,6 +224,36 @@ pub async fn get_courses_subjects() -> Result<Vec<Folder>, String> {
         );
     }
 
+    let payload = fetch_subjects_payload().await?;
+
+    let folders: Vec<Folder> = serde_json::from_value(payload)
+        .map_err(|e| format!("Failed to deserialize folders: {}", e))?;
+
+    Ok(folders)
+}
+
+/// A subject as shown in pickers and on the subjects page, flattened out of its folder.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubjectListing {
+    pub title: String,
+    pub code: String,
+    pub programme: i32,
+    pub metaclass: i32,
+    /// Only some SEQTA instances include the teacher in the subject list
+    pub teacher: Option<String>,
+    /// Academic year, when the folder is named after one (e.g. "2024" or "2024 Semester 1")
+    pub year: Option<i32>,
+    /// Name of the SEQTA folder the subject is filed under
+    pub folder: String,
+    /// False for subjects in archived folders (past years and semesters)
+    pub active: bool,
+}
+
+const SUBJECT_LIST_CACHE_KEY: &str = "subjects:list";
+const SUBJECT_LIST_CACHE_TTL_MINUTES: i64 = 60;
+
+/// Fetch the folders payload of `/seqta/student/load/subjects`.
+async fn fetch_subjects_payload() -> Result<Value, String> {
     let body = json!({});
 
     let response = netgrab::fetch_api_data(
@@ -250,12 +287,100 @@ pub async fn get_courses_subjects() -> Result<Vec<Folder>, String> {
         return Err("Failed to parse response JSON".to_string());
     };
 
-    let payload = data_value.get("payload").ok_or("No payload in response")?;
+    data_value
+        .get("payload")
+        .cloned()
+        .ok_or_else(|| "No payload in response".to_string())
+}
 
-    let folders: Vec<Folder> = serde_json::from_value(payload.clone())
-        .map_err(|e| format!("Failed to deserialize folders: {}", e))?;
+/// Leading four-digit year of a folder name such as "2024" or "2024 Semester 1".
+fn folder_year(name: &str) -> Option<i32> {
+    let digits: String = name
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    (digits.len() == 4).then(|| digits.parse().ok()).flatten()
+}
 
-    Ok(folders)
+/// Flatten SEQTA's folders -> subjects payload, active subjects first then by title.
+/// Subjects missing a programme or metaclass can't be opened and are left out.
+fn parse_subject_listing(payload: &Value) -> Vec<SubjectListing> {
+    let text = |value: &Value| {
+        value
+            .as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let mut subjects = Vec::new();
+    for folder in payload.as_array().into_iter().flatten() {
+        let active = match &folder["active"] {
+            Value::Bool(b) => *b,
+            Value::Number(n) => n.as_i64().is_some_and(|v| v != 0),
+            _ => false,
+        };
+        let folder_name = text(&folder["description"])
+            .or_else(|| text(&folder["code"]))
+            .unwrap_or_default();
+        let year = text(&folder["code"])
+            .and_then(|code| folder_year(&code))
+            .or_else(|| folder_year(&folder_name));
+        for subject in folder["subjects"].as_array().into_iter().flatten() {
+            let (Some(programme), Some(metaclass)) =
+                (subject["programme"].as_i64(), subject["metaclass"].as_i64())
+            else {
+                continue;
+            };
+            let code = text(&subject["code"]).unwrap_or_default();
+            subjects.push(SubjectListing {
+                title: text(&subject["title"]).unwrap_or_else(|| code.clone()),
+                code,
+                programme: programme as i32,
+                metaclass: metaclass as i32,
+                teacher: text(&subject["teacher"]),
+                year,
+                folder: folder_name.clone(),
+                active,
+            });
+        }
+    }
+    subjects.sort_by(|a, b| {
+        b.active
+            .cmp(&a.active)
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+    });
+    subjects
+}
+
+/// Every subject the student has, current and archived, with title, code and class IDs.
+/// Cached for an hour unless `refresh` is set.
+#[tauri::command]
+pub async fn get_subjects(refresh: Option<bool>) -> Result<Vec<SubjectListing>, String> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        let folders = serde_json::to_value(mock_courses_folders()).map_err(|e| e.to_string())?;
+        return Ok(parse_subject_listing(&folders));
+    }
+
+    if !refresh.unwrap_or(false) {
+        let cached = database::db_cache_get(SUBJECT_LIST_CACHE_KEY.to_string())
+            .ok()
+            .flatten()
+            .and_then(|value| serde_json::from_value::<Vec<SubjectListing>>(value).ok());
+        if let Some(subjects) = cached {
+            return Ok(subjects);
+        }
+    }
+
+    let subjects = parse_subject_listing(&fetch_subjects_payload().await?);
+    if let Ok(value) = serde_json::to_value(&subjects) {
+        let _ = database::db_cache_set(
+            SUBJECT_LIST_CACHE_KEY.to_string(),
+            value,
+            Some(SUBJECT_LIST_CACHE_TTL_MINUTES),
+        );
+    }
+    Ok(subjects)
 }
 
 /// Return mock course content when dev_sensitive_info_hider is enabled
@@ -397,3 +522,588 @@ pub async fn get_course_content(programme: i32, metaclass: i32) -> Result<Course
 
     Ok(course_payload)
 }
+
+// --- Course Content Search ---
+
+/// A lesson that matched `search_course_content`, found at `payload.w[term_index][lesson_index]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseContentMatch {
+    pub term_index: usize,
+    pub lesson_index: usize,
+    pub topic: Option<String>,
+    pub score: f32,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Score every lesson in `weeks` (`payload.w`, lessons grouped by term) the way notes are
+/// scored: the topic like a title, the lesson text like a body. Best match first, ties in
+/// course order.
+fn search_lessons(weeks: &Value, query_lower: &str) -> Vec<CourseContentMatch> {
+    let mut results = Vec::new();
+    for (term_index, term) in weeks.as_array().into_iter().flatten().enumerate() {
+        for (lesson_index, lesson) in term.as_array().into_iter().flatten().enumerate() {
+            let topic = lesson
+                .get("t")
+                .and_then(|t| t.as_str())
+                .map(str::trim)
+                .filter(|t| !t.is_empty());
+            let topic_lower = topic.unwrap_or("").to_lowercase();
+            let text = lesson_summary::lesson_text(lesson);
+            let text_lower = text.to_lowercase();
+
+            let mut score = 0.0f32;
+            let mut matches = Vec::new();
+            for word in query_lower.split_whitespace() {
+                if let Some(pos) = topic_lower.find(word) {
+                    score += 10.0;
+                    matches.push(SearchMatch {
+                        field: "topic".to_string(),
+                        snippet: highlight_match(topic.unwrap_or(""), word, pos),
+                        position: pos,
+                    });
+                }
+                if let Some(pos) = text_lower.find(word) {
+                    score += 2.0;
+                    matches.push(SearchMatch {
+                        field: "content".to_string(),
+                        snippet: create_snippet(&text, word, pos),
+                        position: pos,
+                    });
+                }
+            }
+            if score == 0.0 {
+                continue;
+            }
+            if topic_lower == query_lower {
+                score += 20.0;
+            }
+
+            results.push(CourseContentMatch {
+                term_index,
+                lesson_index,
+                topic: topic.map(str::to_string),
+                score,
+                matches,
+            });
+        }
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results
+}
+
+/// Find the lessons of a class that mention `query`, with snippets and the term and lesson
+/// indices to open them with `fetch_lesson_content`. The course is cached, so searching
+/// again doesn't refetch it.
+#[tauri::command]
+pub async fn search_course_content(
+    programme: i32,
+    metaclass: i32,
+    query: String,
+) -> Result<Vec<CourseContentMatch>, String> {
+    let query_lower = query.trim().to_lowercase();
+    if query_lower.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let weeks = if crate::settings::Settings::load().dev_sensitive_info_hider {
+        serde_json::to_value(mock_course_content(programme, metaclass).w)
+            .map_err(|e| format!("Failed to serialize mock course: {}", e))?
+    } else {
+        match seqta_mentions::fetch_course_lessons(programme as i64, metaclass as i64).await? {
+            Some(weeks) => weeks,
+            None => return Ok(vec![]),
+        }
+    };
+    Ok(search_lessons(&weeks, &query_lower))
+}
+
+// --- Course Materials Export ---
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedCourseFile {
+    /// Path inside the zip
+    pub path: String,
+    pub original_name: String,
+    pub uuid: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedLesson {
+    pub folder: String,
+    pub topic: Option<String>,
+    pub files: Vec<ExportedCourseFile>,
+    pub links: Vec<String>,
+}
+
+/// Written to the zip as manifest.json and returned to the caller
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CourseExportManifest {
+    pub code: String,
+    pub title: String,
+    pub programme: i32,
+    pub metaclass: i32,
+    pub exported_at: String,
+    pub lessons: Vec<ExportedLesson>,
+    pub downloaded: usize,
+    pub failed: usize,
+}
+
+/// Return `name`, or `name (2).ext`, `name (3).ext`... if it's already used in this folder.
+fn dedupe_name(name: &str, used: &mut std::collections::HashSet<String>) -> String {
+    if used.insert(name.to_lowercase()) {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({}){}", stem, n, ext);
+        if used.insert(candidate.to_lowercase()) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+fn link_regex() -> &'static regex::Regex {
+    static LINK: OnceLock<regex::Regex> = OnceLock::new();
+    LINK.get_or_init(|| regex::Regex::new(r#"https?://[^\s"'<>\\]+"#).unwrap())
+}
+
+/// Pull http(s) links out of lesson HTML/JSON content.
+fn extract_links(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for m in link_regex().find_iter(text) {
+        let link = m
+            .as_str()
+            .trim_end_matches(|c| c == ')' || c == '.' || c == ',');
+        if !links.iter().any(|l| l == link) {
+            links.push(link.to_string());
+        }
+    }
+    links
+}
+
+/// The course files or one lesson, as a folder of the export.
+struct ExportSection {
+    folder: String,
+    topic: Option<String>,
+    /// (file name, uuid)
+    files: Vec<(String, String)>,
+    links: Vec<String>,
+}
+
+/// The parts of `course` with files or links to export, each given its own folder name.
+fn export_sections(course: &CoursePayload) -> Vec<ExportSection> {
+    let mut used_folders = std::collections::HashSet::new();
+    let mut sections = Vec::new();
+    let mut push = |folder: &str, topic, files, links| {
+        sections.push(ExportSection {
+            folder: dedupe_name(
+                &sanitization::sanitize_path_component(folder),
+                &mut used_folders,
+            ),
+            topic,
+            files,
+            links,
+        });
+    };
+
+    if !course.cf.is_empty() {
+        push(
+            "Course files",
+            None,
+            course
+                .cf
+                .iter()
+                .map(|f| (f.filename.clone(), f.uuid.clone()))
+                .collect(),
+            extract_links(course.document.as_deref().unwrap_or_default()),
+        );
+    }
+    let mut lesson_number = 0;
+    for (week_index, week) in course.w.iter().enumerate() {
+        for lesson in week {
+            lesson_number += 1;
+            let files: Vec<(String, String)> = lesson
+                .r
+                .iter()
+                .flatten()
+                .map(|f| (f.filename.clone(), f.uuid.clone()))
+                .collect();
+            let mut text = lesson
+                .document
+                .as_ref()
+                .map(|d| d.contents.clone())
+                .unwrap_or_default();
+            if let Some(h) = &lesson.h {
+                text.push_str(h);
+            }
+            let links = extract_links(&text);
+            if files.is_empty() && links.is_empty() {
+                continue;
+            }
+            let topic = lesson.t.clone().filter(|t| !t.trim().is_empty());
+            let folder = match &topic {
+                Some(t) => format!("Week {} - Lesson {} - {}", week_index + 1, lesson_number, t),
+                None => format!("Week {} - Lesson {}", week_index + 1, lesson_number),
+            };
+            push(&folder, topic, files, links);
+        }
+    }
+    sections
+}
+
+/// A zip written beside its destination and only moved into place by `finish`, so a failed
+/// export leaves no partial zip. Dropping it unfinished removes the partial file.
+struct ExportZip {
+    dest_path: PathBuf,
+    partial: PathBuf,
+    zip: Option<zip::ZipWriter<std::fs::File>>,
+}
+
+impl ExportZip {
+    fn create(dest_path: &Path) -> Result<Self, String> {
+        let mut partial = dest_path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        let file = std::fs::File::create(&partial)
+            .map_err(|e| format!("Failed to create zip file: {}", e))?;
+        Ok(Self {
+            dest_path: dest_path.to_path_buf(),
+            partial,
+            zip: Some(zip::ZipWriter::new(file)),
+        })
+    }
+
+    /// Add a file at `path` inside the zip.
+    fn add(&mut self, path: &str, bytes: &[u8]) -> Result<(), String> {
+        use std::io::Write;
+
+        let zip = self.zip.as_mut().expect("zip already finished");
+        zip.start_file(path, zip::write::FileOptions::default())
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        zip.write_all(bytes)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        let mut zip = self.zip.take().expect("zip already finished");
+        // The file is closed before it's renamed
+        let result = zip
+            .finish()
+            .map(drop)
+            .map_err(|e| format!("Failed to finish zip: {}", e))
+            .and_then(|_| {
+                drop(zip);
+                std::fs::rename(&self.partial, &self.dest_path)
+                    .map_err(|e| format!("Failed to save zip file: {}", e))
+            });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&self.partial);
+        }
+        result
+    }
+}
+
+impl Drop for ExportZip {
+    fn drop(&mut self) {
+        if let Some(zip) = self.zip.take() {
+            drop(zip);
+            let _ = std::fs::remove_file(&self.partial);
+        }
+    }
+}
+
+/// Download a course resource through the authenticated SEQTA client.
+async fn download_course_file(uuid: &str) -> Result<Vec<u8>, String> {
+    let mut params = HashMap::new();
+    params.insert("type".to_string(), "resource".to_string());
+    params.insert("file".to_string(), uuid.to_string());
+    // is_image returns the raw body base64-encoded
+    let encoded = netgrab::fetch_api_data(
+        "/seqta/student/load/file",
+        RequestMethod::GET,
+        None,
+        None,
+        Some(params),
+        true,
+        false,
+        None,
+    )
+    .await?;
+    use base64::Engine as _;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Failed to decode file: {}", e))
+}
+
+/// Download every file attached to a course and its lessons into a zip at `dest_path`,
+/// one folder per lesson plus a manifest.json. Files that fail to download are recorded
+/// in the manifest instead of aborting the export.
+#[tauri::command]
+pub async fn export_course_materials(
+    programme: i32,
+    metaclass: i32,
+    dest_path: String,
+) -> Result<CourseExportManifest, String> {
+    let course = get_course_content(programme, metaclass).await?;
+
+    // Each file goes into the zip as soon as it's downloaded, so only one is held in memory
+    let mut zip = ExportZip::create(Path::new(&dest_path))?;
+    let mut lessons = Vec::new();
+    let (mut downloaded, mut failed) = (0, 0);
+
+    for section in export_sections(&course) {
+        let mut used_names = std::collections::HashSet::new();
+        let mut exported_files = Vec::new();
+
+        for (original_name, uuid) in section.files {
+            let name = dedupe_name(
+                &sanitization::sanitize_filename(&original_name),
+                &mut used_names,
+            );
+            let path = format!("{}/{}", section.folder, name);
+            let error = match download_course_file(&uuid).await {
+                Ok(bytes) => {
+                    downloaded += 1;
+                    zip.add(&path, &bytes)?;
+                    None
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("[courses] Failed to export {}: {}", original_name, e);
+                    Some(e)
+                }
+            };
+            exported_files.push(ExportedCourseFile {
+                path,
+                original_name,
+                uuid,
+                success: error.is_none(),
+                error,
+            });
+        }
+
+        if !section.links.is_empty() {
+            let name = dedupe_name("links.txt", &mut used_names);
+            zip.add(
+                &format!("{}/{}", section.folder, name),
+                section.links.join("\n").as_bytes(),
+            )?;
+        }
+
+        lessons.push(ExportedLesson {
+            folder: section.folder,
+            topic: section.topic,
+            files: exported_files,
+            links: section.links,
+        });
+    }
+
+    let manifest = CourseExportManifest {
+        code: course.c,
+        title: course.t,
+        programme,
+        metaclass,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        lessons,
+        downloaded,
+        failed,
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.add("manifest.json", manifest_json.as_bytes())?;
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subject_listing() {
+        let payload = json!([
+            {
+                "code": "2023",
+                "description": "2023",
+                "active": 0,
+                "id": 7,
+                "subjects": [
+                    { "code": "9ENG", "title": "English", "programme": 10, "metaclass": 90 }
+                ]
+            },
+            {
+                "code": "2024",
+                "description": "2024 Semester 1",
+                "active": 1,
+                "id": 8,
+                "subjects": [
+                    {
+                        "code": "10SCI",
+                        "title": "Science",
+                        "programme": 12,
+                        "metaclass": 120,
+                        "classunit": 301,
+                        "marksbookType": "A",
+                        "teacher": "Mr Nguyen"
+                    },
+                    { "code": "10ART", "title": " ", "programme": 11, "metaclass": 110 },
+                    { "code": "HOMEROOM", "title": "Homeroom" }
+                ]
+            }
+        ]);
+
+        let subjects = parse_subject_listing(&payload);
+        let titles: Vec<&str> = subjects.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["10ART", "Science", "English"]);
+        assert_eq!(
+            subjects[1],
+            SubjectListing {
+                title: "Science".to_string(),
+                code: "10SCI".to_string(),
+                programme: 12,
+                metaclass: 120,
+                teacher: Some("Mr Nguyen".to_string()),
+                year: Some(2024),
+                folder: "2024 Semester 1".to_string(),
+                active: true,
+            }
+        );
+        assert!(!subjects[2].active);
+        assert_eq!(subjects[2].year, Some(2023));
+        assert_eq!(subjects[2].teacher, None);
+
+        // Folders serialized from the typed structs (mock data) use a bool flag
+        let mock = serde_json::to_value(mock_courses_folders()).unwrap();
+        assert!(parse_subject_listing(&mock).iter().any(|s| s.active));
+        assert!(parse_subject_listing(&json!({})).is_empty());
+        assert_eq!(folder_year("Year 10"), None);
+    }
+
+    #[test]
+    fn test_search_course_content() {
+        let document = json!({
+            "document": {
+                "modules": [
+                    { "type": "title", "content": { "value": "Plant cells" } },
+                    { "type": "textblock", "content": { "content": { "blocks": [
+                        { "text": "Chloroplasts are where photosynthesis happens." }
+                    ] } } }
+                ]
+            }
+        });
+        let weeks = json!([
+            [
+                { "t": "Introduction", "document": { "contents": "<p>Course overview &amp; expectations</p>" } },
+                { "t": "Cells", "document": { "contents": document.to_string() }, "h": "Read chapter 2" }
+            ],
+            [
+                { "t": "Photosynthesis", "l": "<p>Light and dark reactions</p>" },
+                { "t": null, "document": null },
+                { "t": "Respiration", "h": "<p>Compare with <b>photosynthesis</b></p>" }
+            ]
+        ]);
+
+        let results = search_lessons(&weeks, "photosynthesis");
+        let found: Vec<(usize, usize)> = results
+            .iter()
+            .map(|r| (r.term_index, r.lesson_index))
+            .collect();
+        // Exact topic first, then the two lessons that only mention it, in course order
+        assert_eq!(found, vec![(1, 0), (0, 1), (1, 2)]);
+        assert_eq!(results[0].topic.as_deref(), Some("Photosynthesis"));
+        assert_eq!(results[0].matches[0].field, "topic");
+        let cells = &results[1].matches[0];
+        assert_eq!(cells.field, "content");
+        assert!(cells.snippet.contains("where photosynthesis happens"));
+        assert!(!results[2].matches[0].snippet.contains("<b>"));
+
+        let overview = search_lessons(&weeks, "overview & expectations");
+        assert_eq!(overview.len(), 1);
+        assert_eq!((overview[0].term_index, overview[0].lesson_index), (0, 0));
+        assert!(search_lessons(&weeks, "mitochondria").is_empty());
+        assert!(search_lessons(&json!(null), "cells").is_empty());
+    }
+
+    #[test]
+    fn test_export_course_materials() {
+        use std::io::Read;
+
+        let file = |name: &str, uuid: &str| -> UserFile {
+            serde_json::from_value(json!({
+                "filename": name,
+                "t": "",
+                "mimetype": "application/pdf",
+                "created_date": "",
+                "uuid": uuid,
+            }))
+            .unwrap()
+        };
+        let mut course = mock_course_content(1, 1);
+        let mut lesson = course.w[0][0].clone();
+        lesson.r = Some(vec![file("Notes.pdf", "u1"), file("notes.pdf", "u2")]);
+        lesson.h = Some("See https://example.com/reading. Then https://example.com/reading".into());
+        course.w = vec![vec![lesson.clone()], vec![lesson.clone()]];
+        // Nothing to export in this one
+        lesson.r = None;
+        lesson.h = None;
+        lesson.document = None;
+        course.w[1].push(lesson);
+
+        let sections = export_sections(&course);
+        let folders: Vec<&str> = sections.iter().map(|s| s.folder.as_str()).collect();
+        assert_eq!(
+            folders,
+            vec![
+                "Week 1 - Lesson 1 - Introduction",
+                "Week 2 - Lesson 2 - Introduction"
+            ]
+        );
+        assert_eq!(sections[0].files.len(), 2);
+        assert_eq!(sections[0].links, vec!["https://example.com/reading"]);
+
+        let mut used = std::collections::HashSet::new();
+        assert_eq!(dedupe_name("Notes.pdf", &mut used), "Notes.pdf");
+        assert_eq!(dedupe_name("notes.pdf", &mut used), "notes (2).pdf");
+
+        let dir =
+            std::env::temp_dir().join(format!("desqta-course-export-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("course.zip");
+        let mut zip = ExportZip::create(&dest).unwrap();
+        zip.add("Lesson/Notes.pdf", b"%PDF").unwrap();
+        zip.add("manifest.json", b"{}").unwrap();
+        zip.finish().unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["Lesson/Notes.pdf", "manifest.json"]);
+        let mut contents = String::new();
+        archive
+            .by_name("manifest.json")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "{}");
+        // Only the finished zip is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let missing = dir.join("missing").join("course.zip");
+        assert!(ExportZip::create(&missing).is_err());
+        assert!(!missing.exists());
+        // An export abandoned part way leaves nothing behind
+        let abandoned = dir.join("abandoned.zip");
+        let mut zip = ExportZip::create(&abandoned).unwrap();
+        zip.add("Lesson/Notes.pdf", b"%PDF").unwrap();
+        drop(zip);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Bootstrap config, kept next to the executable so a portable install carries its data
+/// location with it. Read before anything else touches the data directory.
+const BOOTSTRAP_FILE: &str = "desqta-data.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BootstrapConfig {
+    /// Data directory override. Relative paths are resolved against the executable's folder,
+    /// so a USB install keeps working when the drive letter changes.
+    #[serde(default)]
+    data_dir: Option<String>,
+    /// Previous data directory, copied into the new one on the next launch.
+    #[serde(default)]
+    migrate_from: Option<String>,
+}
+
+static DATA_DIR: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+fn executable_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .parent()
+        .map(Path::to_path_buf)
+}
+
+fn bootstrap_file() -> Option<PathBuf> {
+    executable_dir().map(|dir| dir.join(BOOTSTRAP_FILE))
+}
+
+fn load_bootstrap() -> BootstrapConfig {
+    bootstrap_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_bootstrap(config: &BootstrapConfig) -> Result<(), String> {
+    let path = bootstrap_file().ok_or_else(|| "Unable to locate the app folder".to_string())?;
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize data directory config: {}", e))?;
+    fs::write(&path, content).map_err(|e| {
+        format!(
+            "Failed to save data directory config next to the app: {}",
+            e
+        )
+    })
+}
+
+/// `<OS data dir>/DesQTA`, or the app's files directory on Android.
+pub fn default_data_dir() -> Result<PathBuf, String> {
+    #[cfg(target_os = "android")]
+    {
+        Ok(PathBuf::from("/data/data/com.desqta.app/files").join("DesQTA"))
+    }
+    #[cfg(not(target_os = "android"))]
+    {
+        dirs_next::data_dir()
+            .map(|dir| dir.join("DesQTA"))
+            .ok_or_else(|| "Unable to determine data dir".to_string())
+    }
+}
+
+/// An override as written in the bootstrap config. Blank means no override.
+fn resolve_override(path: &str, exe_dir: Option<&Path>) -> Option<PathBuf> {
+    let path = Path::new(path.trim());
+    if path.as_os_str().is_empty() {
+        None
+    } else if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        exe_dir.map(|dir| dir.join(path))
+    }
+}
+
+fn configured_data_dir(config: &BootstrapConfig) -> Result<PathBuf, String> {
+    if cfg!(target_os = "android") {
+        return default_data_dir();
+    }
+    match config
+        .data_dir
+        .as_deref()
+        .and_then(|path| resolve_override(path, executable_dir().as_deref()))
+    {
+        Some(dir) => Ok(dir),
+        None => default_data_dir(),
+    }
+}
+
+/// The DesQTA data directory every module stores its files under. Resolved once per run, so
+/// a new override takes effect on the next launch. The directory isn't created here.
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    DATA_DIR
+        .get_or_init(|| configured_data_dir(&load_bootstrap()))
+        .clone()
+}
+
+/// Check `dir` can be created and written to.
+fn check_writable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create {:?}: {}", dir, e))?;
+    let probe = dir.join(".desqta-write-test");
+    fs::write(&probe, b"ok").map_err(|e| format!("{:?} is not writable: {}", dir, e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Copy everything in `from` into `to`. Files that already exist in `to` are kept, so an
+/// interrupted migration can simply run again. Returns the number of files copied.
+fn copy_data_dir(from: &Path, to: &Path) -> Result<usize, String> {
+    let mut copied = 0;
+    for entry in walkdir::WalkDir::new(from)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let relative = entry
+            .path()
+            .strip_prefix(from)
+            .map_err(|e| format!("Failed to get relative path: {}", e))?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+        } else if entry.file_type().is_file() && !target.exists() {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {:?}: {}", entry.path(), e))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Copy the previous data directory into the new one if `set_data_directory` asked for it.
+/// Must run before anything opens files in the data directory. The old copy is left in place.
+pub fn apply_pending_migration() {
+    let mut config = load_bootstrap();
+    let Some(from) = config.migrate_from.clone() else {
+        return;
+    };
+    let to = match configured_data_dir(&config) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("[DataDir] Skipping migration: {}", e);
+            return;
+        }
+    };
+
+    match copy_data_dir(Path::new(&from), &to) {
+        Ok(copied) => {
+            println!(
+                "[DataDir] Copied {} files from {} to {:?}",
+                copied, from, to
+            );
+            config.migrate_from = None;
+            if let Err(e) = save_bootstrap(&config) {
+                eprintln!("[DataDir] {}", e);
+            }
+        }
+        // Left pending so the next launch retries
+        Err(e) => eprintln!("[DataDir] Failed to migrate data: {}", e),
+    }
+}
+
+/// Store DesQTA's data in `path` from the next launch on. A relative path is kept relative to
+/// the app's folder (portable mode); no path goes back to the default location. With
+/// `migrate`, the current data is copied over on the next launch. Returns the new directory.
+#[tauri::command]
+pub fn set_data_directory(path: Option<String>, migrate: bool) -> Result<String, String> {
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        return Err("Changing the data directory isn't supported on this platform".to_string());
+    }
+
+    let path = path.filter(|p| !p.trim().is_empty());
+    let config = BootstrapConfig {
+        data_dir: path.clone(),
+        migrate_from: None,
+    };
+    let target = configured_data_dir(&config)?;
+    let current = app_data_dir()?;
+
+    if target != current && (target.starts_with(&current) || current.starts_with(&target)) {
+        return Err(
+            "The data directory can't be inside the current one, or contain it".to_string(),
+        );
+    }
+    check_writable(&target)?;
+
+    let config = BootstrapConfig {
+        migrate_from: (migrate && target != current && current.exists())
+            .then(|| current.to_string_lossy().to_string()),
+        ..config
+    };
+    save_bootstrap(&config)?;
+
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_override() {
+        let exe_dir = std::env::temp_dir().join("desqta-portable");
+        assert_eq!(resolve_override("  ", Some(&exe_dir)), None);
+        assert_eq!(
+            resolve_override("data", Some(&exe_dir)),
+            Some(exe_dir.join("data"))
+        );
+        assert_eq!(
+            resolve_override(exe_dir.to_str().unwrap(), None),
+            Some(exe_dir.clone())
+        );
+        assert_eq!(resolve_override("data", None), None);
+    }
+
+    #[test]
+    fn test_copy_data_dir_keeps_existing_files() {
+        let root = std::env::temp_dir().join(format!("desqta-data-dir-{}", uuid::Uuid::new_v4()));
+        let (from, to) = (root.join("old"), root.join("new"));
+        fs::create_dir_all(from.join("profiles/default")).unwrap();
+        fs::write(from.join("profiles.json"), "old").unwrap();
+        fs::write(from.join("profiles/default/settings.json"), "old").unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(to.join("profiles.json"), "new").unwrap();
+
+        assert_eq!(copy_data_dir(&from, &to).unwrap(), 1);
+        assert_eq!(fs::read_to_string(to.join("profiles.json")).unwrap(), "new");
+        assert_eq!(
+            fs::read_to_string(to.join("profiles/default/settings.json")).unwrap(),
+            "old"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
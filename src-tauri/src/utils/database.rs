@@ -5,6 +5,7 @@ use serde_json::Value;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::AppHandle;
+use crate::caches::CacheCounters;
 use crate::profiles;
 use crate::logger;
 use crate::settings::Settings;
@@ -12,6 +13,7 @@ use crate::settings::Settings;
 // Global database connection (allows reinitialization for profile switching)
 static DB: Mutex<Option<Connection>> = Mutex::new(None);
 static DB_INITIALIZED: AtomicBool = AtomicBool::new(false);
+pub static DB_CACHE_STATS: CacheCounters = CacheCounters::new();
 
 /// Initialize the database connection
 pub fn init_database(_app: &AppHandle) -> Result<()> {
@@ -50,16 +52,19 @@ pub fn init_database(_app: &AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// Close the database connection, e.g. before its profile directory is deleted
+pub fn close_database() {
+    let mut db_guard = DB.lock().unwrap();
+    if let Some(conn) = db_guard.take() {
+        drop(conn);
+    }
+    DB_INITIALIZED.store(false, Ordering::Release);
+}
+
 /// Reinitialize database connection (for profile switching)
 pub fn reinit_database(app: &AppHandle) -> Result<()> {
     // Close current connection
-    {
-        let mut db_guard = DB.lock().unwrap();
-        if let Some(conn) = db_guard.take() {
-            drop(conn);
-        }
-        DB_INITIALIZED.store(false, Ordering::Release);
-    }
+    close_database();
     
     // Reinitialize with new profile
     init_database(app)
@@ -84,6 +89,12 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
+    // HTTP validators for cached responses (migration for existing databases)
+    conn.execute("ALTER TABLE cache ADD COLUMN etag TEXT", [])
+        .ok();
+    conn.execute("ALTER TABLE cache ADD COLUMN last_modified TEXT", [])
+        .ok();
+
     // Sync queue table: for offline operations
     conn.execute(
         "CREATE TABLE IF NOT EXISTS sync_queue (
@@ -368,13 +379,19 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
     Ok(())
 }
 
-/// Clean up expired cache entries
+/// How long an expired response with an ETag or Last-Modified is kept for revalidation.
+const VALIDATOR_GRACE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Clean up expired cache entries. Responses with HTTP validators stay a while longer, so an
+/// unchanged response can be revalidated instead of downloaded again.
 fn cleanup_expired_cache(conn: &Connection) -> SqlResult<()> {
     let now = Utc::now().timestamp();
-    conn.execute(
-        "DELETE FROM cache WHERE expires_at IS NOT NULL AND expires_at < ?",
-        params![now],
+    let removed = conn.execute(
+        "DELETE FROM cache WHERE expires_at IS NOT NULL AND expires_at < ?1
+            AND ((etag IS NULL AND last_modified IS NULL) OR expires_at < ?2)",
+        params![now, now - VALIDATOR_GRACE_SECS],
     )?;
+    DB_CACHE_STATS.evicted(removed);
     Ok(())
 }
 
@@ -409,11 +426,15 @@ pub fn db_cache_get(key: String) -> Result<Option<Value>, String> {
 
         match result {
             Ok(value_str) => {
+                DB_CACHE_STATS.hit();
                 let value: Value = serde_json::from_str(&value_str)
                     .map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))?;
                 Ok(Some(value))
             }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                DB_CACHE_STATS.miss();
+                Ok(None)
+            }
             Err(e) => Err(anyhow::anyhow!("Query error: {}", e)),
         }
     }).map_err(|e| e.to_string())
@@ -435,11 +456,13 @@ pub fn db_cache_get_many(keys: Vec<String>) -> Result<Value, String> {
 
             match result {
                 Ok(value_str) => {
+                    DB_CACHE_STATS.hit();
                     let value: Value = serde_json::from_str(&value_str)
                         .map_err(|e| anyhow::anyhow!("Failed to parse JSON for key {}: {}", key, e))?;
                     results.insert(key, value);
                 }
                 Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    DB_CACHE_STATS.miss();
                     results.insert(key, Value::Null);
                 }
                 Err(e) => return Err(anyhow::anyhow!("Query error: {}", e)),
@@ -468,6 +491,98 @@ pub fn db_cache_set(key: String, value: Value, ttl_minutes: Option<i64>) -> Resu
     }).map_err(|e| e.to_string())
 }
 
+/// A cached HTTP response and the validators the server sent with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Past its TTL: revalidate before use
+    pub expired: bool,
+}
+
+/// Cached response stored under `key` by `cache_set_response`, expired or not.
+pub fn cache_get_response(key: &str) -> Result<Option<CachedResponse>, String> {
+    let now = Utc::now().timestamp();
+    with_conn(|conn| {
+        let result = conn.query_row(
+            "SELECT value, etag, last_modified, expires_at FROM cache WHERE key = ?",
+            params![key],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            },
+        );
+        match result {
+            Ok((value, etag, last_modified, expires_at)) => {
+                // Stored as a JSON string, like every other cache value
+                let body = match serde_json::from_str::<Value>(&value) {
+                    Ok(Value::String(body)) => body,
+                    _ => return Ok(None),
+                };
+                Ok(Some(CachedResponse {
+                    body,
+                    etag,
+                    last_modified,
+                    expired: expires_at.map_or(false, |expires_at| expires_at <= now),
+                }))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Query error: {}", e)),
+        }
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Store a response body with its validators. The body is readable through `db_cache_get`
+/// as a JSON string.
+pub fn cache_set_response(
+    key: &str,
+    response: &CachedResponse,
+    ttl_minutes: Option<i64>,
+) -> Result<(), String> {
+    let value_str = serde_json::to_string(&response.body)
+        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    let now = Utc::now().timestamp();
+    let expires_at = ttl_minutes.map(|ttl| now + (ttl * 60));
+
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO cache (key, value, created_at, expires_at, etag, last_modified)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                key,
+                value_str,
+                now,
+                expires_at,
+                response.etag,
+                response.last_modified
+            ],
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))?;
+        Ok(())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Give an entry a fresh TTL after the server confirmed it's unchanged (HTTP 304).
+pub fn cache_refresh_ttl(key: &str, ttl_minutes: Option<i64>) -> Result<(), String> {
+    let expires_at = ttl_minutes.map(|ttl| Utc::now().timestamp() + (ttl * 60));
+    with_conn(|conn| {
+        conn.execute(
+            "UPDATE cache SET expires_at = ?1 WHERE key = ?2",
+            params![expires_at, key],
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))?;
+        Ok(())
+    })
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn db_cache_delete(key: String) -> Result<(), String> {
     with_conn(|conn| {
@@ -479,10 +594,23 @@ pub fn db_cache_delete(key: String) -> Result<(), String> {
 
 #[tauri::command]
 pub fn db_cache_clear() -> Result<(), String> {
+    clear_cache_entries().map(|_| ())
+}
+
+/// Number of rows in the key-value cache table, expired ones included.
+pub fn cache_entry_count() -> Result<usize, String> {
+    with_conn(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM cache", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))
+    }).map_err(|e| e.to_string())
+}
+
+/// Delete every row of the key-value cache table. Returns the number of rows removed.
+pub fn clear_cache_entries() -> Result<usize, String> {
     with_conn(|conn| {
         conn.execute("DELETE FROM cache", [])
-            .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))?;
-        Ok(())
+            .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))
     }).map_err(|e| e.to_string())
 }
 
@@ -914,7 +1042,7 @@ pub fn db_get_assessments_by_year(year: Option<i32>) -> Result<Vec<Value>, Strin
 
 // ========== Notification Operations ==========
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Notification {
     pub id: i64,
     pub assessment_id: i64,
@@ -1054,6 +1182,52 @@ pub fn db_notification_delete_by_assessment(assessment_id: i64) -> Result<(), St
     }).map_err(|e| e.to_string())
 }
 
+/// Every stored notification whose type starts with `prefix`, sent or not.
+pub fn notifications_with_type_prefix(prefix: &str) -> Result<Vec<Notification>, String> {
+    with_conn(|conn| {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, assessment_id, notification_type, scheduled_for, sent_at, created_at 
+                 FROM notifications 
+                 WHERE substr(notification_type, 1, length(?1)) = ?1 
+                 ORDER BY scheduled_for ASC"
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![prefix], |row| {
+                Ok(Notification {
+                    id: row.get(0)?,
+                    assessment_id: row.get(1)?,
+                    notification_type: row.get(2)?,
+                    scheduled_for: row.get(3)?,
+                    sent_at: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })
+            .map_err(|e| anyhow::anyhow!("Query error: {}", e))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| anyhow::anyhow!("Row error: {}", e))?);
+        }
+
+        Ok(results)
+    }).map_err(|e| e.to_string())
+}
+
+/// Delete a single notification by row ID.
+pub fn delete_notification(notification_id: i64) -> Result<(), String> {
+    with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM notifications WHERE id = ?",
+            params![notification_id],
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to execute: {}", e))?;
+        Ok(())
+    }).map_err(|e| e.to_string())
+}
+
 // ========== Widget Layout Operations ==========
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -1130,3 +1304,188 @@ pub fn db_widget_layout_load() -> Result<Option<WidgetLayout>, String> {
 
     Ok(Some(layout))
 }
+
+// ========== Maintenance ==========
+
+/// Row counts, size and health of the database, as returned by `db_stats`.
+#[derive(Debug, serde::Serialize)]
+pub struct DbStats {
+    /// Size of the database file in bytes
+    pub file_size_bytes: u64,
+    /// Bytes sitting in free pages that `db_vacuum` would give back
+    pub free_bytes: u64,
+    /// Row count per table
+    pub tables: std::collections::BTreeMap<String, i64>,
+    pub integrity_ok: bool,
+    /// Problems reported by SQLite's integrity check (empty when healthy)
+    pub integrity_errors: Vec<String>,
+}
+
+/// Outcome of `db_vacuum`.
+#[derive(Debug, serde::Serialize)]
+pub struct VacuumResult {
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub reclaimed_bytes: u64,
+}
+
+/// Total and free bytes of the database, from its page counts.
+fn database_size(conn: &Connection) -> SqlResult<(u64, u64)> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let freelist_count: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    Ok((
+        (page_size * page_count) as u64,
+        (page_size * freelist_count) as u64,
+    ))
+}
+
+/// Run SQLite's quick integrity check. Returns the problems found, or nothing if healthy.
+fn integrity_problems(conn: &Connection) -> SqlResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let messages = rows.collect::<SqlResult<Vec<String>>>()?;
+    Ok(messages.into_iter().filter(|m| m != "ok").collect())
+}
+
+fn table_row_counts(conn: &Connection) -> SqlResult<std::collections::BTreeMap<String, i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqlResult<Vec<String>>>()?;
+
+    let mut counts = std::collections::BTreeMap::new();
+    for name in names {
+        let count = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")),
+            [],
+            |row| row.get::<_, i64>(0),
+        )?;
+        counts.insert(name, count);
+    }
+    Ok(counts)
+}
+
+fn database_stats(conn: &Connection) -> SqlResult<DbStats> {
+    let (file_size_bytes, free_bytes) = database_size(conn)?;
+    let integrity_errors = integrity_problems(conn)?;
+    Ok(DbStats {
+        file_size_bytes,
+        free_bytes,
+        tables: table_row_counts(conn)?,
+        integrity_ok: integrity_errors.is_empty(),
+        integrity_errors,
+    })
+}
+
+/// Drop expired cache rows, then rebuild the file without its free pages. Refuses to touch a
+/// database that fails the integrity check, since rebuilding could lose what's left of it.
+fn vacuum_database(conn: &Connection) -> Result<VacuumResult> {
+    let problems = integrity_problems(conn).context("Integrity check failed")?;
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Database is corrupted, not compacting it: {}",
+            problems.join("; ")
+        ));
+    }
+
+    let (size_before_bytes, _) = database_size(conn)?;
+    cleanup_expired_cache(conn).context("Failed to cleanup")?;
+    conn.execute_batch("VACUUM").context("Failed to vacuum")?;
+    let (size_after_bytes, _) = database_size(conn)?;
+
+    Ok(VacuumResult {
+        size_before_bytes,
+        size_after_bytes,
+        reclaimed_bytes: size_before_bytes.saturating_sub(size_after_bytes),
+    })
+}
+
+/// Report table row counts, file size and free space, and run a quick integrity check.
+/// Corruption is logged and returned in `integrity_errors` rather than as an error.
+#[tauri::command]
+pub fn db_stats() -> Result<DbStats, String> {
+    let stats = with_conn(|conn| {
+        database_stats(conn).map_err(|e| anyhow::anyhow!("Failed to read stats: {}", e))
+    })
+    .map_err(|e| e.to_string())?;
+
+    if !stats.integrity_ok {
+        if let Some(logger) = logger::get_logger() {
+            let _ = logger.log(
+                logger::LogLevel::ERROR,
+                "database",
+                "db_stats",
+                "Database integrity check failed",
+                serde_json::json!({ "errors": stats.integrity_errors }),
+            );
+        }
+    }
+    Ok(stats)
+}
+
+/// Compact the database (VACUUM), returning how much space was reclaimed. Holds the database
+/// lock throughout, so no writes can happen while it runs.
+#[tauri::command]
+pub fn db_vacuum() -> Result<VacuumResult, String> {
+    let result = with_conn(|conn| vacuum_database(conn));
+    if let Some(logger) = logger::get_logger() {
+        let _ = match &result {
+            Ok(result) => logger.log(
+                logger::LogLevel::INFO,
+                "database",
+                "db_vacuum",
+                "Database compacted",
+                serde_json::json!({
+                    "size_before": result.size_before_bytes,
+                    "size_after": result.size_after_bytes,
+                    "reclaimed": result.reclaimed_bytes
+                }),
+            ),
+            Err(e) => logger.log(
+                logger::LogLevel::ERROR,
+                "database",
+                "db_vacuum",
+                "Database compaction failed",
+                serde_json::json!({ "error": e.to_string() }),
+            ),
+        };
+    }
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_and_vacuum() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let big = "x".repeat(4096);
+        for i in 0..200 {
+            conn.execute(
+                "INSERT INTO cache (key, value, created_at) VALUES (?1, ?2, 0)",
+                params![format!("key{}", i), big],
+            )
+            .unwrap();
+        }
+        conn.execute("DELETE FROM cache WHERE key != 'key0'", [])
+            .unwrap();
+
+        let stats = database_stats(&conn).unwrap();
+        assert!(stats.integrity_ok);
+        assert!(stats.integrity_errors.is_empty());
+        assert_eq!(stats.tables.get("cache"), Some(&1));
+        assert_eq!(stats.tables.get("sync_queue"), Some(&0));
+        assert!(stats.free_bytes > 0);
+
+        let result = vacuum_database(&conn).unwrap();
+        assert_eq!(result.size_before_bytes, stats.file_size_bytes);
+        assert!(result.reclaimed_bytes > 0);
+        assert_eq!(database_stats(&conn).unwrap().free_bytes, 0);
+        assert_eq!(table_row_counts(&conn).unwrap().get("cache"), Some(&1));
+    }
+}
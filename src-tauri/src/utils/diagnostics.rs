@@ -0,0 +1,209 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::{
+    caches, database, disk_space, logger, netgrab, news, notes_filesystem, profiles, redaction,
+    seqta_mentions, session, settings, weather,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileDiagnostics {
+    pub id: String,
+    pub display_name: Option<String>,
+    /// Host of the SEQTA instance, without path or credentials
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiagnostics {
+    pub present: bool,
+    /// Base URL parses and a session cookie is stored. Checked locally, SEQTA is not contacted.
+    pub well_formed: bool,
+    pub host: Option<String>,
+    pub additional_cookies: usize,
+    pub has_stored_credentials: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathDiagnostics {
+    pub name: String,
+    pub path: String,
+    pub exists: bool,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheDiagnostics {
+    pub name: String,
+    pub entries: usize,
+}
+
+/// Snapshot of the app's state for support requests. Contains no tokens, cookies or
+/// passwords, only whether they are present. Sections that fail are left empty and the
+/// reason is added to `errors`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub generated_at: String,
+    pub app_version: String,
+    pub platform: String,
+    pub arch: String,
+    pub profile: Option<ProfileDiagnostics>,
+    pub session: SessionDiagnostics,
+    pub paths: Vec<PathDiagnostics>,
+    /// Free space on the disk holding the data directory, `None` if it couldn't be read
+    pub available_space_bytes: Option<u64>,
+    pub caches: Vec<CacheDiagnostics>,
+    pub cache_stats: Vec<caches::CacheStats>,
+    pub notes_count: Option<usize>,
+    pub log_level: Option<String>,
+    pub cloud_configured: bool,
+    pub offline_mode: bool,
+    pub errors: Vec<String>,
+}
+
+fn url_host(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+}
+
+/// Total size of the files under `path` (or of `path` itself if it's a file).
+fn disk_usage(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn path_diagnostics(name: &str, path: PathBuf) -> PathDiagnostics {
+    PathDiagnostics {
+        name: name.to_string(),
+        exists: path.exists(),
+        size_bytes: disk_usage(&path),
+        path: path.to_string_lossy().to_string(),
+    }
+}
+
+fn session_diagnostics() -> SessionDiagnostics {
+    let session = session::Session::load();
+    let present = !session.base_url.is_empty() && !session.jsessionid.is_empty();
+    let host = url_host(&session.base_url);
+    SessionDiagnostics {
+        present,
+        well_formed: present && host.is_some(),
+        host,
+        additional_cookies: session.additional_cookies.len(),
+        has_stored_credentials: session.stored_username.is_some()
+            && session.stored_password.is_some(),
+    }
+}
+
+/// Collect a diagnostics report. Never fails as a whole: each check that errors is recorded
+/// in `errors` and the rest of the report is still filled in. With `dev_sensitive_info_hider`
+/// on, names, emails and the home directory are masked (see `redaction`).
+#[tauri::command]
+pub fn get_diagnostics(app: AppHandle) -> serde_json::Value {
+    let mut report = serde_json::to_value(collect_diagnostics(app)).unwrap_or_default();
+    if redaction::is_enabled() {
+        redaction::redact_sensitive(&mut report);
+    }
+    report
+}
+
+fn collect_diagnostics(app: AppHandle) -> Diagnostics {
+    let mut errors = Vec::new();
+
+    let profile = profiles::ProfileManager::get_current_profile().map(|p| ProfileDiagnostics {
+        host: url_host(&p.base_url),
+        id: p.id,
+        display_name: p.display_name,
+    });
+
+    let data_dir = profiles::get_base_data_dir();
+    let available_space_bytes = disk_space::available_space(&data_dir);
+    if available_space_bytes.is_none() {
+        errors.push("disk space: could not determine free space".to_string());
+    }
+    let mut paths = vec![path_diagnostics("data", data_dir)];
+    if let Some(profile) = &profile {
+        let profile_dir = profiles::get_profile_dir(&profile.id);
+        paths.push(path_diagnostics("notes", profile_dir.join("notes")));
+        paths.push(path_diagnostics("note_contents", profile_dir.join("note_contents")));
+        paths.push(path_diagnostics("themes", profile_dir.join("themes")));
+        paths.push(path_diagnostics("profile", profile_dir));
+    } else {
+        errors.push("profile: no active profile".to_string());
+    }
+    match logger::get_log_file_path() {
+        Ok(log_file) => {
+            if let Some(dir) = log_file.parent() {
+                paths.push(path_diagnostics("logs", dir.to_path_buf()));
+            }
+        }
+        Err(e) => errors.push(format!("logs: {}", e)),
+    }
+
+    let mut caches = vec![
+        CacheDiagnostics {
+            name: "mentions".to_string(),
+            entries: seqta_mentions::cache_len(),
+        },
+        CacheDiagnostics {
+            name: "news".to_string(),
+            entries: news::cache_len(),
+        },
+        CacheDiagnostics {
+            name: "weather".to_string(),
+            entries: weather::cache_len(),
+        },
+    ];
+    match database::cache_entry_count() {
+        Ok(entries) => caches.push(CacheDiagnostics {
+            name: "database".to_string(),
+            entries,
+        }),
+        Err(e) => errors.push(format!("database cache: {}", e)),
+    }
+
+    let notes_count = if profile.is_some() {
+        match notes_filesystem::load_notes_filesystem(app) {
+            Ok(notes) => Some(notes.len()),
+            Err(e) => {
+                errors.push(format!("notes: {}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let log_level = logger::get_logger().map(|l| l.log_level().to_string());
+    if log_level.is_none() {
+        errors.push("logger: not initialised".to_string());
+    }
+
+    let cloud = settings::CloudToken::load();
+
+    Diagnostics {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        session: session_diagnostics(),
+        profile,
+        paths,
+        available_space_bytes,
+        caches,
+        cache_stats: caches::cache_stats(),
+        notes_count,
+        log_level,
+        cloud_configured: cloud.token.is_some(),
+        offline_mode: netgrab::is_offline_mode(),
+        errors,
+    }
+}
@@ -0,0 +1,109 @@
+//! Free space checks run before large writes (backups, exports, downloads), so a full disk
+//! fails the operation up front instead of leaving a half-written file behind.
+
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+use super::data_dir;
+
+/// Bytes available on the disk holding `path`, or `None` when it can't be determined.
+/// `path` doesn't have to exist yet; its nearest existing ancestor is used.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let existing = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| existing.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Space to ask for when writing `payload_bytes`: half again on top, to cover temporary
+/// `.part` files, archive overhead and estimates that come in low.
+pub fn required_with_margin(payload_bytes: u64) -> u64 {
+    payload_bytes.saturating_add(payload_bytes / 2)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Fail when `available` can't hold `payload_bytes` plus the margin. Unknown free space is
+/// let through, since refusing every write on a platform we can't query would be worse.
+fn check_space(available: Option<u64>, payload_bytes: u64, what: &str) -> Result<(), String> {
+    let required = required_with_margin(payload_bytes);
+    match available {
+        Some(available) if available < required => Err(format!(
+            "Not enough disk space for the {}: {} needed, {} available. Free up some space and try again.",
+            what,
+            format_bytes(required),
+            format_bytes(available)
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn ensure_free_space_with(
+    path: &Path,
+    payload_bytes: u64,
+    what: &str,
+    available: impl FnOnce(&Path) -> Option<u64>,
+) -> Result<(), String> {
+    check_space(available(path), payload_bytes, what)
+}
+
+/// Error out before writing `payload_bytes` to `path` if its disk is too full. `what` names
+/// the operation in the message shown to the user, e.g. "backup".
+pub fn ensure_free_space(path: &Path, payload_bytes: u64, what: &str) -> Result<(), String> {
+    ensure_free_space_with(path, payload_bytes, what, available_space)
+}
+
+/// Whether `required_bytes` (plus the safety margin) fit on the disk holding `path`, or the
+/// app data directory when no path is given.
+#[tauri::command]
+pub fn check_free_space(required_bytes: u64, path: Option<String>) -> bool {
+    let path = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => match data_dir::app_data_dir() {
+            Ok(dir) => dir,
+            Err(_) => return true,
+        },
+    };
+    ensure_free_space(&path, required_bytes, "write").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insufficient_space_fails_early() {
+        let dest = Path::new("/backups/notes.json");
+        let tight = |_: &Path| Some(1_400);
+
+        // 1000 bytes need 1500 with the margin
+        let err = ensure_free_space_with(dest, 1_000, "backup", tight).unwrap_err();
+        assert!(err.contains("Not enough disk space for the backup"));
+        assert!(err.contains("1.4 KB available"));
+        assert!(ensure_free_space_with(dest, 900, "backup", tight).is_ok());
+
+        // Unknown free space doesn't block the write
+        assert!(ensure_free_space_with(dest, u64::MAX, "backup", |_| None).is_ok());
+        assert_eq!(required_with_margin(u64::MAX), u64::MAX);
+    }
+}
@@ -226,6 +226,29 @@ fn remove_cached_photo_path(uuid: &str) -> Result<(), String> {
     .map_err(|e| e.to_string())
 }
 
+/// Delete all downloaded forum photos and their database entries. Returns the number of
+/// photos removed; they are fetched again the next time they're shown.
+pub fn clear_photo_cache() -> Result<usize, String> {
+    use crate::database;
+
+    let removed = database::with_conn(|conn| {
+        conn.execute("DELETE FROM forum_photos", [])
+            .map_err(|e| anyhow::anyhow!("Failed to clear forum photos: {}", e))
+    })
+    .map_err(|e| e.to_string())?;
+
+    let photos_dir = get_photos_directory()?;
+    if let Ok(entries) = fs::read_dir(&photos_dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_file() {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
 /// Initialize forum_photos table in database schema
 pub fn init_forum_photos_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
     conn.execute(
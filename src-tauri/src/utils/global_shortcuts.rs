@@ -0,0 +1,257 @@
+//! System-wide hotkeys bound to quick actions. Bindings live in `Settings::global_shortcuts`
+//! and are registered with the OS on startup; when one fires the main window is brought up
+//! and a `global-shortcut` event carrying the action ID is emitted for the frontend.
+
+use tauri::AppHandle;
+
+#[cfg(desktop)]
+use crate::logger;
+#[cfg(desktop)]
+use crate::settings::{GlobalShortcutBinding, Settings};
+#[cfg(desktop)]
+use serde::Serialize;
+#[cfg(desktop)]
+use tauri::{Emitter, Manager};
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Actions a global shortcut can trigger.
+pub const ACTIONS: &[&str] = &["open_global_search", "quick_capture_note", "show_window"];
+
+#[cfg(not(desktop))]
+const UNSUPPORTED: &str = "Global shortcuts are only available on desktop";
+
+/// Payload of the `global-shortcut` event.
+#[cfg(desktop)]
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalShortcutFired {
+    pub action_id: String,
+}
+
+fn validate_action(action_id: &str) -> Result<(), String> {
+    if ACTIONS.contains(&action_id) {
+        Ok(())
+    } else {
+        Err(format!("Unknown shortcut action: {}", action_id))
+    }
+}
+
+/// Parse an accelerator like `CmdOrCtrl+Shift+K`. A modifier is required so a global
+/// shortcut can never swallow plain typing in other apps.
+#[cfg(desktop)]
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    let shortcut: Shortcut = accelerator
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid shortcut \"{}\": {}", accelerator, e))?;
+    if shortcut.mods.is_empty() {
+        return Err(format!(
+            "Shortcut \"{}\" needs at least one modifier (Ctrl, Alt, Shift or Cmd)",
+            accelerator
+        ));
+    }
+    Ok(shortcut)
+}
+
+/// The action other than `action_id` that is already bound to `shortcut`, if any.
+#[cfg(desktop)]
+fn find_conflict<'a>(
+    bindings: &'a [GlobalShortcutBinding],
+    action_id: &str,
+    shortcut: &Shortcut,
+) -> Option<&'a str> {
+    bindings
+        .iter()
+        .filter(|b| b.action_id != action_id)
+        .find(|b| parse_accelerator(&b.accelerator).ok().as_ref() == Some(shortcut))
+        .map(|b| b.action_id.as_str())
+}
+
+#[cfg(desktop)]
+fn log_error(function: &str, message: &str, binding: &GlobalShortcutBinding) {
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::ERROR,
+            "global_shortcuts",
+            function,
+            message,
+            serde_json::json!({ "action_id": binding.action_id, "accelerator": binding.accelerator }),
+        );
+    }
+}
+
+/// Register every saved binding with the OS, replacing whatever was registered before (e.g.
+/// the previous profile's bindings). Failures (e.g. another app holding the same hotkey) are
+/// logged and the rest still get registered.
+#[cfg(desktop)]
+pub fn register_saved(app: &AppHandle) {
+    let _ = app.global_shortcut().unregister_all();
+    for binding in Settings::load().global_shortcuts {
+        let result = parse_accelerator(&binding.accelerator).and_then(|shortcut| {
+            app.global_shortcut()
+                .register(shortcut)
+                .map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            log_error(
+                "register_saved",
+                &format!("Failed to register shortcut: {}", e),
+                &binding,
+            );
+        }
+    }
+}
+
+/// Called by the global shortcut plugin when a registered hotkey is pressed.
+#[cfg(desktop)]
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut) {
+    let Some(binding) = Settings::load()
+        .global_shortcuts
+        .into_iter()
+        .find(|b| parse_accelerator(&b.accelerator).ok().as_ref() == Some(shortcut))
+    else {
+        return;
+    };
+    if let Some(window) = app.webview_windows().get("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit(
+        "global-shortcut",
+        GlobalShortcutFired {
+            action_id: binding.action_id,
+        },
+    );
+}
+
+/// Bind `accelerator` to one of `ACTIONS`, replacing the action's previous shortcut. Fails
+/// when the accelerator is malformed, already bound to another action, or taken by another
+/// application.
+#[tauri::command]
+pub fn register_global_shortcut(
+    app: AppHandle,
+    accelerator: String,
+    action_id: String,
+) -> Result<(), String> {
+    validate_action(&action_id)?;
+    #[cfg(desktop)]
+    {
+        let shortcut = parse_accelerator(&accelerator)?;
+        let mut settings = Settings::load();
+        if let Some(other) = find_conflict(&settings.global_shortcuts, &action_id, &shortcut) {
+            return Err(format!(
+                "\"{}\" is already used for {}",
+                accelerator.trim(),
+                other
+            ));
+        }
+
+        let manager = app.global_shortcut();
+        let previous = settings
+            .global_shortcuts
+            .iter()
+            .find(|b| b.action_id == action_id)
+            .and_then(|b| parse_accelerator(&b.accelerator).ok());
+        let previous = previous.filter(|p| p != &shortcut);
+        if let Some(previous) = previous {
+            let _ = manager.unregister(previous);
+        }
+        if !manager.is_registered(shortcut) {
+            if let Err(e) = manager.register(shortcut) {
+                // Keep the action on its old shortcut rather than leaving it unbound
+                if let Some(previous) = previous {
+                    let _ = manager.register(previous);
+                }
+                return Err(format!(
+                    "Couldn't register \"{}\", it may be in use by another application: {}",
+                    accelerator.trim(),
+                    e
+                ));
+            }
+        }
+
+        settings
+            .global_shortcuts
+            .retain(|b| b.action_id != action_id);
+        settings.global_shortcuts.push(GlobalShortcutBinding {
+            action_id,
+            accelerator: accelerator.trim().to_string(),
+        });
+        settings.save().map_err(|e| e.to_string())
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = (app, accelerator);
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+/// Remove the shortcut bound to `action_id`. Returns false if the action had none.
+#[tauri::command]
+pub fn unregister_global_shortcut(app: AppHandle, action_id: String) -> Result<bool, String> {
+    #[cfg(desktop)]
+    {
+        let mut settings = Settings::load();
+        let Some(index) = settings
+            .global_shortcuts
+            .iter()
+            .position(|b| b.action_id == action_id)
+        else {
+            return Ok(false);
+        };
+        let binding = settings.global_shortcuts.remove(index);
+        if let Ok(shortcut) = parse_accelerator(&binding.accelerator) {
+            if let Err(e) = app.global_shortcut().unregister(shortcut) {
+                log_error("unregister_global_shortcut", &e.to_string(), &binding);
+            }
+        }
+        settings.save().map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = (app, action_id);
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(all(test, desktop))]
+mod tests {
+    use super::*;
+
+    fn binding(action_id: &str, accelerator: &str) -> GlobalShortcutBinding {
+        GlobalShortcutBinding {
+            action_id: action_id.to_string(),
+            accelerator: accelerator.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_accelerator_validation_and_conflicts() {
+        assert!(parse_accelerator("CmdOrCtrl+Shift+K").is_ok());
+        assert!(parse_accelerator("K").is_err());
+        assert!(parse_accelerator("Ctrl+Shift+").is_err());
+        assert!(parse_accelerator("Ctrl+Banana").is_err());
+        assert!(validate_action("quick_capture_note").is_ok());
+        assert!(validate_action("format_disk").is_err());
+
+        let bindings = vec![
+            binding("open_global_search", "Control+Shift+K"),
+            binding("show_window", "Alt+D"),
+        ];
+        // Same keys written differently still conflict
+        let shortcut = parse_accelerator("Shift+Ctrl+K").unwrap();
+        assert_eq!(
+            find_conflict(&bindings, "quick_capture_note", &shortcut),
+            Some("open_global_search")
+        );
+        // Re-binding an action to its own shortcut isn't a conflict
+        assert_eq!(
+            find_conflict(&bindings, "open_global_search", &shortcut),
+            None
+        );
+        let free = parse_accelerator("Ctrl+Alt+N").unwrap();
+        assert_eq!(find_conflict(&bindings, "quick_capture_note", &free), None);
+    }
+}
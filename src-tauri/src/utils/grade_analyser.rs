@@ -0,0 +1,274 @@
+//! Grade analyser: summarises released marks with Gemini through the `ai` proxy.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+use crate::ai::{self, AiError, AiGenerateOptions};
+use crate::analytics::{self, GradedAssessment};
+use crate::caches::CacheCounters;
+use crate::logger;
+use crate::settings;
+
+/// Shown instead of calling Gemini when there is nothing to analyse.
+const NO_GRADES_MESSAGE: &str =
+    "No released grades yet. Sync your analytics once some marks are out and try again.";
+
+const GRADE_ANALYSER_INSTRUCTION: &str = "You are a supportive study coach for a high school \
+student. Base every statement only on the grades provided. Be specific, encouraging and brief.";
+
+/// Strengths, weaknesses and what to work on next, as returned by `analyse_grades`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct GradeAnalysis {
+    pub summary: String,
+    #[serde(default)]
+    pub strengths: Vec<String>,
+    #[serde(default)]
+    pub weaknesses: Vec<String>,
+    /// Subjects the student should spend extra time on, most important first
+    #[serde(default)]
+    pub focus_subjects: Vec<String>,
+    /// Graded assessments the analysis is based on
+    #[serde(default)]
+    pub assessment_count: usize,
+    /// Whether this came from the cache rather than a new request
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// Analyses keyed by a hash of the prompt, which is built only from the grade data.
+static ANALYSIS_CACHE: OnceLock<Mutex<HashMap<String, GradeAnalysis>>> = OnceLock::new();
+pub static GRADE_ANALYSIS_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+fn analysis_cache() -> &'static Mutex<HashMap<String, GradeAnalysis>> {
+    ANALYSIS_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Forget cached analyses. Returns entries removed.
+pub fn clear_cache() -> usize {
+    analysis_cache()
+        .lock()
+        .map(|mut cache| cache.drain().count())
+        .unwrap_or(0)
+}
+
+fn format_grade(assessment: &GradedAssessment) -> String {
+    match (&assessment.letter_grade, assessment.percentage) {
+        (Some(letter), Some(percentage)) => format!("{} ({:.0}%)", letter, percentage),
+        (Some(letter), None) => letter.clone(),
+        (None, Some(percentage)) => format!("{:.0}%", percentage),
+        (None, None) => "ungraded".to_string(),
+    }
+}
+
+/// Build the analysis prompt: one block per subject with its average and every assessment in
+/// due order. Deterministic, so the same grades always give the same prompt (and cache key).
+fn build_grade_prompt(assessments: &[GradedAssessment], year: Option<i32>) -> String {
+    let mut by_subject: BTreeMap<&str, Vec<&GradedAssessment>> = BTreeMap::new();
+    for assessment in assessments {
+        let subject = if assessment.subject.is_empty() {
+            "Other"
+        } else {
+            assessment.subject.as_str()
+        };
+        by_subject.entry(subject).or_default().push(assessment);
+    }
+
+    let mut prompt = match year {
+        Some(year) => format!("Here are my graded assessments for {}.\n", year),
+        None => "Here are my graded assessments.\n".to_string(),
+    };
+    for (subject, mut items) in by_subject {
+        items.sort_by(|a, b| a.due.cmp(&b.due).then_with(|| a.title.cmp(&b.title)));
+        let percentages: Vec<f32> = items.iter().filter_map(|a| a.percentage).collect();
+        prompt.push_str(&format!("\nSubject: {}", subject));
+        if !percentages.is_empty() {
+            let average = percentages.iter().sum::<f32>() / percentages.len() as f32;
+            prompt.push_str(&format!(" (average {:.0}%)", average));
+        }
+        prompt.push('\n');
+        for item in items {
+            let due = if item.due.is_empty() {
+                "no due date"
+            } else {
+                item.due.get(..10).unwrap_or(&item.due)
+            };
+            prompt.push_str(&format!(
+                "- {} [{}]: {}\n",
+                item.title,
+                due,
+                format_grade(item)
+            ));
+        }
+    }
+    prompt.push_str(
+        "\nAnalyse my results. Reply with JSON only, in this shape:\n\
+         {\"summary\": \"two or three sentences\", \
+         \"strengths\": [\"...\"], \
+         \"weaknesses\": [\"...\"], \
+         \"focus_subjects\": [\"subject names from above, most important first\"]}\n",
+    );
+    prompt
+}
+
+/// Read the model's answer. Tolerates a Markdown code fence or text around the JSON object.
+fn parse_grade_analysis(text: &str) -> Result<GradeAnalysis, AiError> {
+    let invalid = || AiError::InvalidResponse("The grade analysis wasn't valid JSON".to_string());
+    let value = ai::json_answer(text).ok_or_else(invalid)?;
+
+    let analysis = GradeAnalysis {
+        summary: value
+            .get("summary")
+            .and_then(|s| s.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string(),
+        strengths: ai::answer_string_list(&value, "strengths"),
+        weaknesses: ai::answer_string_list(&value, "weaknesses"),
+        focus_subjects: ai::answer_string_list(&value, "focus_subjects"),
+        ..Default::default()
+    };
+    if analysis.summary.is_empty()
+        && analysis.strengths.is_empty()
+        && analysis.weaknesses.is_empty()
+    {
+        return Err(invalid());
+    }
+    Ok(analysis)
+}
+
+/// Ask Gemini for strengths, weaknesses and subjects to focus on, based on the released grades
+/// in the stored analytics (only those due in `year`, if given). Results are cached per grade
+/// set, so re-running without new marks costs nothing. Requires `ai_integrations_enabled` and
+/// `grade_analyser_enabled`.
+#[tauri::command]
+pub async fn analyse_grades(app: AppHandle, year: Option<i32>) -> Result<GradeAnalysis, AiError> {
+    let settings = settings::Settings::load();
+    if !settings.ai_integrations_enabled.unwrap_or(false) {
+        return Err(AiError::Disabled);
+    }
+    if !settings.grade_analyser_enabled.unwrap_or(true) {
+        return Err(AiError::FeatureDisabled(
+            "The grade analyser is turned off in Settings".to_string(),
+        ));
+    }
+
+    let assessments = analytics::graded_assessments(year);
+    if assessments.is_empty() {
+        return Ok(GradeAnalysis {
+            summary: NO_GRADES_MESSAGE.to_string(),
+            ..Default::default()
+        });
+    }
+
+    let prompt = build_grade_prompt(&assessments, year);
+    let key = ai::prompt_hash(&prompt);
+    if let Some(cached) = analysis_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+    {
+        GRADE_ANALYSIS_CACHE_STATS.hit();
+        return Ok(GradeAnalysis {
+            cached: true,
+            ..cached
+        });
+    }
+    GRADE_ANALYSIS_CACHE_STATS.miss();
+
+    let options = AiGenerateOptions {
+        system_instruction: Some(GRADE_ANALYSER_INSTRUCTION.to_string()),
+        temperature: Some(0.4),
+        json_response: true,
+        ..Default::default()
+    };
+    let text = ai::generate(&app, &prompt, options).await?;
+    let analysis = GradeAnalysis {
+        assessment_count: assessments.len(),
+        ..parse_grade_analysis(&text)?
+    };
+
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "grade_analyser",
+            "analyse_grades",
+            "Analysed grades",
+            json!({
+                "assessments": assessments.len(),
+                "year": year,
+                "focus_subjects": analysis.focus_subjects.len()
+            }),
+        );
+    }
+
+    if let Ok(mut cache) = analysis_cache().lock() {
+        cache.insert(key, analysis.clone());
+    }
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graded(subject: &str, title: &str, due: &str, percentage: f32) -> GradedAssessment {
+        GradedAssessment {
+            subject: subject.to_string(),
+            title: title.to_string(),
+            due: due.to_string(),
+            percentage: Some(percentage),
+            letter_grade: None,
+        }
+    }
+
+    #[test]
+    fn test_build_grade_prompt() {
+        let assessments = vec![
+            graded("SCI", "Lab Report", "2025-06-01", 60.0),
+            graded("MATH", "Algebra Test", "2025-05-10T00:00:00", 90.0),
+            graded("MATH", "Calculus Test", "2025-03-02", 80.0),
+            GradedAssessment {
+                letter_grade: Some("B".to_string()),
+                percentage: None,
+                ..graded("", "Essay", "", 0.0)
+            },
+        ];
+        let prompt = build_grade_prompt(&assessments, Some(2025));
+        assert!(prompt.starts_with("Here are my graded assessments for 2025.\n"));
+        assert!(prompt.contains(
+            "Subject: MATH (average 85%)\n\
+             - Calculus Test [2025-03-02]: 80%\n\
+             - Algebra Test [2025-05-10]: 90%\n"
+        ));
+        assert!(prompt.contains("Subject: Other\n- Essay [no due date]: B\n"));
+        assert!(prompt.find("MATH").unwrap() < prompt.find("SCI").unwrap());
+
+        let mut shuffled = assessments.clone();
+        shuffled.reverse();
+        assert_eq!(
+            ai::prompt_hash(&prompt),
+            ai::prompt_hash(&build_grade_prompt(&shuffled, Some(2025)))
+        );
+    }
+
+    #[test]
+    fn test_parse_grade_analysis() {
+        let text = "```json\n{\"summary\": \"Solid term.\", \"strengths\": [\"Maths\", \" \"], \
+                    \"weaknesses\": [\"Science reports\"], \"focus_subjects\": [\"SCI\"]}\n```";
+        let analysis = parse_grade_analysis(text).unwrap();
+        assert_eq!(analysis.summary, "Solid term.");
+        assert_eq!(analysis.strengths, vec!["Maths"]);
+        assert_eq!(analysis.weaknesses, vec!["Science reports"]);
+        assert_eq!(analysis.focus_subjects, vec!["SCI"]);
+        assert!(!analysis.cached);
+
+        assert!(matches!(
+            parse_grade_analysis("Sorry, I can't help with that."),
+            Err(AiError::InvalidResponse(_))
+        ));
+        assert!(parse_grade_analysis("{}").is_err());
+    }
+}
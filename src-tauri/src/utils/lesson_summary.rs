@@ -0,0 +1,370 @@
+//! Lesson summariser: condenses a SEQTA lesson's content with Gemini through the `ai` proxy.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+use crate::ai::{self, AiError, AiGenerateOptions};
+use crate::caches::CacheCounters;
+use crate::logger;
+use crate::seqta_mentions;
+use crate::settings;
+use crate::text_search::html_to_text;
+
+/// Lessons with less text than this (after the topic) aren't worth a request.
+const MIN_LESSON_TEXT_CHARS: usize = 40;
+/// Keeps very long lessons (pasted readings, big tables) within a reasonable prompt size.
+const MAX_LESSON_TEXT_CHARS: usize = 30_000;
+
+const NO_CONTENT_MESSAGE: &str =
+    "This lesson doesn't have enough written content to summarise yet.";
+
+const LESSON_SUMMARY_INSTRUCTION: &str = "You summarise high school lesson content for the \
+student who attended it. Use only the lesson content provided. Be clear and concise.";
+
+/// A term from the lesson with a short definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub definition: String,
+}
+
+/// Returned by `summarise_lesson`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LessonSummary {
+    pub summary: String,
+    #[serde(default)]
+    pub key_points: Vec<String>,
+    #[serde(default)]
+    pub glossary: Vec<GlossaryTerm>,
+    /// False when the lesson had too little content and nothing was sent to Gemini
+    #[serde(default)]
+    pub has_content: bool,
+    /// Whether this came from the cache rather than a new request
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// Summaries keyed by a hash of the extracted lesson text.
+static SUMMARY_CACHE: OnceLock<Mutex<HashMap<String, LessonSummary>>> = OnceLock::new();
+pub static LESSON_SUMMARY_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+fn summary_cache() -> &'static Mutex<HashMap<String, LessonSummary>> {
+    SUMMARY_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Forget cached summaries. Returns entries removed.
+pub fn clear_cache() -> usize {
+    summary_cache()
+        .lock()
+        .map(|mut cache| cache.drain().count())
+        .unwrap_or(0)
+}
+
+fn push_paragraph(text: &mut String, paragraph: &str) {
+    let paragraph = paragraph.trim();
+    if !paragraph.is_empty() {
+        text.push_str(paragraph);
+        text.push('\n');
+    }
+}
+
+/// Text of one lesson module. SEQTA has used several editors over the years, so this accepts
+/// title values, DraftJS blocks, Lexical HTML and legacy HTML strings.
+fn module_text(module: &Value, text: &mut String) {
+    let Some(content) = module.get("content") else {
+        return;
+    };
+    if let Some(value) = content.get("value").and_then(|v| v.as_str()) {
+        push_paragraph(text, &html_to_text(value));
+    }
+    if let Some(html) = content.get("html").and_then(|h| h.as_str()) {
+        push_paragraph(text, &html_to_text(html));
+    }
+    match content.get("content") {
+        Some(Value::String(html)) => push_paragraph(text, &html_to_text(html)),
+        Some(inner) => {
+            for block in inner
+                .get("blocks")
+                .and_then(|b| b.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(block_text) = block.get("text").and_then(|t| t.as_str()) {
+                    push_paragraph(text, block_text);
+                }
+            }
+        }
+        None => {}
+    }
+}
+
+/// Text of a lesson's `document.contents`: a JSON module document, or plain HTML in older
+/// lessons.
+fn document_text(contents: &str, text: &mut String) {
+    let Ok(document) = serde_json::from_str::<Value>(contents) else {
+        push_paragraph(text, &html_to_text(contents));
+        return;
+    };
+    let modules = document
+        .get("document")
+        .and_then(|d| d.get("modules"))
+        .or_else(|| document.get("modules"))
+        .and_then(|m| m.as_array());
+    for module in modules.into_iter().flatten() {
+        module_text(module, text);
+    }
+}
+
+/// Plain text of a lesson from `/seqta/student/load/courses`: the document, lesson notes and
+/// homework, without the topic (which says little on its own).
+pub(crate) fn lesson_text(lesson: &Value) -> String {
+    let mut text = String::new();
+    if let Some(contents) = lesson
+        .get("document")
+        .and_then(|d| d.get("contents"))
+        .and_then(|c| c.as_str())
+    {
+        document_text(contents, &mut text);
+    }
+    if let Some(notes) = lesson.get("l").and_then(|l| l.as_str()) {
+        push_paragraph(&mut text, &html_to_text(notes));
+    }
+    if let Some(homework) = lesson.get("h").and_then(|h| h.as_str()) {
+        let homework = html_to_text(homework);
+        if !homework.is_empty() {
+            push_paragraph(&mut text, &format!("Homework: {}", homework));
+        }
+    }
+    match text.char_indices().nth(MAX_LESSON_TEXT_CHARS) {
+        Some((cut, _)) => text[..cut].to_string(),
+        None => text.trim_end().to_string(),
+    }
+}
+
+fn build_lesson_prompt(topic: Option<&str>, text: &str) -> String {
+    let mut prompt = String::new();
+    if let Some(topic) = topic.map(str::trim).filter(|t| !t.is_empty()) {
+        prompt.push_str(&format!("Lesson topic: {}\n", topic));
+    }
+    prompt.push_str(&format!("Lesson content:\n{}\n", text));
+    prompt.push_str(
+        "\nSummarise this lesson. Reply with JSON only, in this shape:\n\
+         {\"summary\": \"two or three sentences\", \
+         \"key_points\": [\"...\"], \
+         \"glossary\": [{\"term\": \"...\", \"definition\": \"one sentence\"}]}\n",
+    );
+    prompt
+}
+
+fn parse_lesson_summary(text: &str) -> Result<LessonSummary, AiError> {
+    let invalid = || AiError::InvalidResponse("The lesson summary wasn't valid JSON".to_string());
+    let value = ai::json_answer(text).ok_or_else(invalid)?;
+
+    let glossary = value
+        .get("glossary")
+        .and_then(|g| g.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let term = entry.get("term")?.as_str()?.trim();
+            let definition = entry.get("definition")?.as_str()?.trim();
+            (!term.is_empty() && !definition.is_empty()).then(|| GlossaryTerm {
+                term: term.to_string(),
+                definition: definition.to_string(),
+            })
+        })
+        .collect();
+    let summary = LessonSummary {
+        summary: value
+            .get("summary")
+            .and_then(|s| s.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string(),
+        key_points: ai::answer_string_list(&value, "key_points"),
+        glossary,
+        has_content: true,
+        cached: false,
+    };
+    if summary.summary.is_empty() && summary.key_points.is_empty() {
+        return Err(invalid());
+    }
+    Ok(summary)
+}
+
+/// Summarise one lesson: a short overview, key points and glossary terms, generated from the
+/// lesson's document, notes and homework. Lessons with little or no written content get a
+/// message instead of a request. Summaries are cached per lesson content. Requires
+/// `ai_integrations_enabled` and `lesson_summary_analyser_enabled`.
+#[tauri::command]
+pub async fn summarise_lesson(
+    app: AppHandle,
+    programme: i64,
+    metaclass: i64,
+    lesson_index: usize,
+    term_index: usize,
+) -> Result<LessonSummary, AiError> {
+    let settings = settings::Settings::load();
+    if !settings.ai_integrations_enabled.unwrap_or(false) {
+        return Err(AiError::Disabled);
+    }
+    if !settings.lesson_summary_analyser_enabled.unwrap_or(true) {
+        return Err(AiError::FeatureDisabled(
+            "The lesson summary analyser is turned off in Settings".to_string(),
+        ));
+    }
+
+    let lesson = seqta_mentions::fetch_lesson_content(
+        programme,
+        metaclass,
+        Some(lesson_index),
+        Some(term_index),
+    )
+    .await
+    .map_err(AiError::Network)?
+    .ok_or_else(|| AiError::Api("That lesson couldn't be found in SEQTA".to_string()))?;
+
+    let text = lesson_text(&lesson);
+    if text.chars().count() < MIN_LESSON_TEXT_CHARS {
+        return Ok(LessonSummary {
+            summary: NO_CONTENT_MESSAGE.to_string(),
+            ..Default::default()
+        });
+    }
+
+    let topic = lesson.get("t").and_then(|t| t.as_str());
+    let prompt = build_lesson_prompt(topic, &text);
+    let key = ai::prompt_hash(&prompt);
+    if let Some(cached) = summary_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+    {
+        LESSON_SUMMARY_CACHE_STATS.hit();
+        return Ok(LessonSummary {
+            cached: true,
+            ..cached
+        });
+    }
+    LESSON_SUMMARY_CACHE_STATS.miss();
+
+    let options = AiGenerateOptions {
+        system_instruction: Some(LESSON_SUMMARY_INSTRUCTION.to_string()),
+        temperature: Some(0.3),
+        json_response: true,
+        ..Default::default()
+    };
+    let summary = parse_lesson_summary(&ai::generate(&app, &prompt, options).await?)?;
+
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "lesson_summary",
+            "summarise_lesson",
+            "Summarised lesson",
+            json!({
+                "programme": programme,
+                "metaclass": metaclass,
+                "content_chars": text.chars().count(),
+                "key_points": summary.key_points.len()
+            }),
+        );
+    }
+
+    if let Ok(mut cache) = summary_cache().lock() {
+        cache.insert(key, summary.clone());
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lesson_text_from_mixed_modules() {
+        let document = json!({
+            "document": {
+                "modules": [
+                    { "type": "title", "content": { "value": "Photosynthesis" } },
+                    {
+                        "type": "textblock",
+                        "content": {
+                            "content": {
+                                "blocks": [
+                                    { "text": "Plants convert light into energy." },
+                                    { "text": "" }
+                                ],
+                                "entityMap": {}
+                            }
+                        }
+                    },
+                    {
+                        "content": {
+                            "editor": "lexical",
+                            "html": "<p>Chlorophyll&nbsp;absorbs <b>red</b> light.</p>"
+                        }
+                    },
+                    { "content": { "content": "<table><tr><td>CO2</td><td>O2</td></tr></table>" } },
+                    { "content": { "url": "https://example.com/video" } },
+                    { "type": "divider" }
+                ]
+            }
+        });
+        let lesson = json!({
+            "t": "Week 3",
+            "document": { "contents": document.to_string() },
+            "h": "<p>Read chapter 4</p>"
+        });
+        assert_eq!(
+            lesson_text(&lesson),
+            "Photosynthesis\n\
+             Plants convert light into energy.\n\
+             Chlorophyll absorbs red light.\n\
+             CO2 O2\n\
+             Homework: Read chapter 4"
+        );
+    }
+
+    #[test]
+    fn test_lesson_text_html_and_empty() {
+        let lesson = json!({
+            "document": { "contents": "<p>Mock lesson content for demonstration.</p>" },
+            "h": null
+        });
+        assert_eq!(
+            lesson_text(&lesson),
+            "Mock lesson content for demonstration."
+        );
+        assert_eq!(
+            lesson_text(&json!({ "t": "Introduction", "document": null })),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_build_lesson_prompt_and_parse() {
+        let prompt = build_lesson_prompt(Some(" Cells "), "Cells are the unit of life.");
+        assert!(prompt.starts_with("Lesson topic: Cells\nLesson content:\nCells are the unit"));
+        assert!(!build_lesson_prompt(None, "text").contains("Lesson topic"));
+
+        let answer = "```json\n{\"summary\": \"Cells basics.\", \"key_points\": [\"Cells\"], \
+                      \"glossary\": [{\"term\": \"Cell\", \"definition\": \"Unit of life.\"}, \
+                      {\"term\": \"\", \"definition\": \"dropped\"}]}\n```";
+        let summary = parse_lesson_summary(answer).unwrap();
+        assert_eq!(summary.summary, "Cells basics.");
+        assert_eq!(summary.key_points, vec!["Cells"]);
+        assert_eq!(
+            summary.glossary,
+            vec![GlossaryTerm {
+                term: "Cell".to_string(),
+                definition: "Unit of life.".to_string()
+            }]
+        );
+        assert!(summary.has_content);
+        assert!(parse_lesson_summary("{\"glossary\": []}").is_err());
+    }
+}
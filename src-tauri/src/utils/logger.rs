@@ -188,6 +188,10 @@ impl Logger {
         }
     }
 
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level.clone()
+    }
+
     #[allow(dead_code)]
     pub fn set_log_level(&mut self, level: LogLevel) {
         self.log_level = level;
@@ -366,23 +370,11 @@ macro_rules! log_function_exit {
 }
 
 // Utility functions
-fn get_log_file_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir.push("logs");
-        dir.push("latest.log");
-        Ok(dir)
-    }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir = dirs_next::data_dir().ok_or("Unable to determine data dir")?;
-        dir.push("DesQTA");
-        dir.push("logs");
-        dir.push("latest.log");
-        Ok(dir)
-    }
+pub fn get_log_file_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut dir = crate::data_dir::app_data_dir()?;
+    dir.push("logs");
+    dir.push("latest.log");
+    Ok(dir)
 }
 
 fn generate_session_id() -> String {
@@ -505,6 +497,17 @@ pub async fn export_logs_for_support() -> Result<String, String> {
         return Err("No log file found".to_string());
     }
 
+    let log_content = std::fs::read_to_string(&log_path).map_err(|e| e.to_string())?;
+    let redactor = crate::redaction::is_enabled().then(crate::redaction::Redactor::from_settings);
+    Ok(build_support_export(&log_content, redactor.as_ref()))
+}
+
+/// Support package text: a system info header followed by the log, redacted when a
+/// `redactor` is given.
+fn build_support_export(
+    log_content: &str,
+    redactor: Option<&crate::redaction::Redactor>,
+) -> String {
     // Create a comprehensive support package
     let mut support_data = String::new();
 
@@ -517,12 +520,42 @@ pub async fn export_logs_for_support() -> Result<String, String> {
     support_data.push_str(&format!("App Version: {}\n", env!("CARGO_PKG_VERSION")));
     support_data.push_str(&format!("OS: {}\n", std::env::consts::OS));
     support_data.push_str(&format!("Arch: {}\n", std::env::consts::ARCH));
+    if redactor.is_some() {
+        support_data.push_str("Personal details redacted\n");
+    }
     support_data.push_str("=====================================\n\n");
 
     // Add recent logs
-    let log_content = std::fs::read_to_string(&log_path).map_err(|e| e.to_string())?;
     support_data.push_str("=== APPLICATION LOGS ===\n");
-    support_data.push_str(&log_content);
+    match redactor {
+        Some(redactor) => support_data.push_str(&redactor.redact_log(log_content)),
+        None => support_data.push_str(log_content),
+    }
 
-    Ok(support_data)
+    support_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::{Redactor, DEFAULT_REDACTED_FIELDS};
+
+    #[test]
+    fn test_redacted_support_export_has_no_emails() {
+        let log = "[2025-01-01 09:00:00.000] [INFO] [auth::login] [login.rs:10] [ThreadId(1)] \
+                   Signed in as jane.smith@school.edu.au | {\"user\":{\"email\":\"js@x.org\"}}\n\
+                   [2025-01-01 09:00:01.000] [WARN] [messages::fetch] [:0] [ThreadId(2)] \
+                   Reply to teacher@school.edu.au failed | not json";
+        let fields: Vec<String> = DEFAULT_REDACTED_FIELDS
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        let redactor = Redactor::new(&fields, vec![]);
+
+        let export = build_support_export(log, Some(&redactor));
+        assert!(!export.contains('@'), "{}", export);
+        assert!(export.contains("Signed in as [email]"));
+
+        assert!(build_support_export(log, None).contains("teacher@school.edu.au"));
+    }
 }
@@ -0,0 +1,263 @@
+//! Background inbox check. While enabled, the newest page of the inbox is fetched every
+//! `message_polling_interval_mins` and a notification is sent (through `notifications`) for
+//! each unread message that hasn't been seen before. Seen message IDs are kept per profile so
+//! restarting the app doesn't notify about the same messages again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::messages::{self, Message};
+use crate::netgrab;
+use crate::notifications::{self, NotificationCategory};
+use crate::profiles;
+use crate::session;
+use crate::settings::Settings;
+
+/// Bumped whenever the poller is started or stopped; a running loop exits as soon as it sees
+/// a generation other than its own, so at most one poller is ever active.
+static POLLER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Seen IDs kept, newest first. Far more than one inbox page, so older messages can't
+/// reappear as new.
+const MAX_SEEN_IDS: usize = 1000;
+/// More new messages than this at once get a single summary notification.
+const MAX_INDIVIDUAL_NOTIFICATIONS: usize = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PollState {
+    /// Message IDs already listed in an earlier check, newest first
+    seen_ids: Vec<i64>,
+}
+
+/// Location: `$DATA_DIR/DesQTA/profiles/{profile_id}/message_poll_state.json`
+fn state_file() -> PathBuf {
+    let mut dir = profiles::get_profile_dir(
+        &profiles::ProfileManager::get_current_profile()
+            .map(|p| p.id)
+            .unwrap_or_else(|| "default".to_string()),
+    );
+    dir.push("message_poll_state.json");
+    dir
+}
+
+/// Saved state, or `None` before the first check.
+fn load_state() -> Option<PollState> {
+    fs::read_to_string(state_file())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+fn save_state(state: &PollState) -> Result<(), String> {
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    fs::write(state_file(), json).map_err(|e| format!("Failed to save message poll state: {}", e))
+}
+
+/// Unread messages in `inbox` that no earlier check listed, plus the seen IDs to keep.
+/// Without earlier state nothing is new: the first check only records what's already there.
+fn diff_messages<'a>(
+    inbox: &'a [Message],
+    previous: Option<&PollState>,
+) -> (Vec<&'a Message>, PollState) {
+    let new = match previous {
+        Some(state) => {
+            let seen: HashSet<i64> = state.seen_ids.iter().copied().collect();
+            inbox
+                .iter()
+                .filter(|m| m.unread && !seen.contains(&m.id))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let mut listed = HashSet::new();
+    let mut seen_ids: Vec<i64> = inbox
+        .iter()
+        .map(|m| m.id)
+        .filter(|id| listed.insert(*id))
+        .collect();
+    if let Some(state) = previous {
+        seen_ids.extend(state.seen_ids.iter().filter(|id| !listed.contains(id)));
+    }
+    seen_ids.truncate(MAX_SEEN_IDS);
+    (new, PollState { seen_ids })
+}
+
+fn notify_new_messages(app: &AppHandle, new: &[&Message]) {
+    let result = if new.len() > MAX_INDIVIDUAL_NOTIFICATIONS {
+        notifications::notify(
+            app,
+            NotificationCategory::Messages,
+            &format!("{} new messages", new.len()),
+            &format!("Latest from {}: {}", new[0].sender, new[0].subject),
+        )
+        .map(|_| ())
+    } else {
+        new.iter().try_for_each(|message| {
+            notifications::notify(
+                app,
+                NotificationCategory::Messages,
+                &format!("New message from {}", message.sender),
+                &message.subject,
+            )
+            .map(|_| ())
+        })
+    };
+    if let Err(e) = result {
+        println!("[MESSAGE POLLER] Failed to notify: {}", e);
+    }
+}
+
+/// Start (or restart) the poller using the interval from settings.
+pub fn start(app: AppHandle) {
+    let generation = POLLER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let interval_mins = Settings::load().message_polling_interval_mins.max(1);
+
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_secs(interval_mins * 60);
+        loop {
+            tokio::time::sleep(interval).await;
+            if POLLER_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if !session::Session::exists() {
+                println!("[MESSAGE POLLER] No session, stopping");
+                return;
+            }
+            // Offline mode may be turned off again, so just skip this check
+            if netgrab::is_offline_mode() {
+                continue;
+            }
+
+            match messages::fetch_latest_inbox().await {
+                Ok(inbox) => {
+                    if POLLER_GENERATION.load(Ordering::SeqCst) != generation {
+                        return;
+                    }
+                    let previous = load_state();
+                    let (new, state) = diff_messages(&inbox, previous.as_ref());
+                    if !new.is_empty() {
+                        notify_new_messages(&app, &new);
+                    }
+                    if let Err(e) = save_state(&state) {
+                        println!("[MESSAGE POLLER] {}", e);
+                    }
+                }
+                Err(e) if e.is_unauthorized() => {
+                    println!("[MESSAGE POLLER] Session rejected: {}", e);
+                    if POLLER_GENERATION.load(Ordering::SeqCst) == generation {
+                        let _ = app.emit("session-expired", ());
+                    }
+                    return;
+                }
+                // Network trouble isn't expiry; try again next interval
+                Err(e) => println!("[MESSAGE POLLER] Inbox check failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Stop the poller, if one is running.
+pub fn stop() {
+    POLLER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Start the poller at launch if it's enabled and there's a session to poll with.
+pub fn start_if_enabled(app: AppHandle) {
+    if Settings::load().message_polling_enabled && session::Session::exists() {
+        start(app);
+    }
+}
+
+/// Turn background message checks on or off and persist the choice. `interval_minutes`
+/// (at least 1) replaces the saved interval when given.
+#[tauri::command]
+pub fn set_message_polling(
+    app: AppHandle,
+    enabled: bool,
+    interval_minutes: Option<u64>,
+) -> Result<(), String> {
+    let mut settings = Settings::load();
+    settings.message_polling_enabled = enabled;
+    if let Some(minutes) = interval_minutes {
+        settings.message_polling_interval_mins = minutes.max(1);
+    }
+    settings.save().map_err(|e| e.to_string())?;
+
+    if enabled {
+        start(app);
+    } else {
+        stop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64, unread: bool) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "folder": "inbox",
+            "sender": "Ms Smith",
+            "senderPhoto": null,
+            "to": "",
+            "subject": format!("Message {}", id),
+            "preview": "",
+            "body": "",
+            "date": "2024-05-01 09:00:00",
+            "unread": unread,
+            "starred": false,
+            "files": null,
+        }))
+        .unwrap()
+    }
+
+    fn ids(messages: &[&Message]) -> Vec<i64> {
+        messages.iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn test_new_message_diffing() {
+        // First check only records what's there
+        let inbox = vec![message(3, true), message(2, false), message(1, true)];
+        let (new, state) = diff_messages(&inbox, None);
+        assert!(new.is_empty());
+        assert_eq!(state.seen_ids, vec![3, 2, 1]);
+
+        // Only unread messages that weren't listed before are new
+        let inbox = vec![
+            message(5, true),
+            message(4, false),
+            message(3, true),
+            message(2, false),
+        ];
+        let (new, state) = diff_messages(&inbox, Some(&state));
+        assert_eq!(ids(&new), vec![5]);
+        // Message 1 dropped off the page but is still remembered
+        assert_eq!(state.seen_ids, vec![5, 4, 3, 2, 1]);
+
+        // Nothing changed, nothing new
+        let (new, state) = diff_messages(&inbox, Some(&state));
+        assert!(new.is_empty());
+
+        // A remembered message marked unread again isn't new
+        let inbox = vec![message(6, true), message(1, true)];
+        let (new, state) = diff_messages(&inbox, Some(&state));
+        assert_eq!(ids(&new), vec![6]);
+        assert_eq!(state.seen_ids, vec![6, 1, 5, 4, 3, 2]);
+
+        let many: Vec<Message> = (0..MAX_SEEN_IDS as i64 + 50)
+            .rev()
+            .map(|id| message(id, false))
+            .collect();
+        let (_, state) = diff_messages(&many, Some(&state));
+        assert_eq!(state.seen_ids.len(), MAX_SEEN_IDS);
+        assert_eq!(state.seen_ids[0], MAX_SEEN_IDS as i64 + 49);
+    }
+}
@@ -1,9 +1,17 @@
+use super::html_parser;
 use super::netgrab;
-use super::netgrab::RequestMethod;
+use super::netgrab::{NetError, RequestMethod};
+use super::text_search::{create_snippet, highlight_match, html_to_text, SearchMatch};
+use crate::caches::CacheCounters;
+use crate::database::{self, QueueItem};
 use crate::logger;
+use crate::seqta_mentions;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MessageFile {
@@ -20,11 +28,30 @@ pub struct MessageFile {
     pub created_by: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SenderKind {
+    Staff,
+    Student,
+    #[default]
+    Unknown,
+}
+
+/// Who sent a message, normalised from the shapes SEQTA uses.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct MessageSender {
+    pub name: String,
+    pub kind: SenderKind,
+    pub id: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Message {
     pub id: i64,
     pub folder: String,
     pub sender: String,
+    #[serde(rename = "senderInfo", default)]
+    pub sender_info: MessageSender,
     #[serde(rename = "senderPhoto")]
     pub sender_photo: Option<String>,
     pub to: String,
@@ -37,6 +64,40 @@ pub struct Message {
     pub files: Option<Vec<MessageFile>>,
 }
 
+/// Sender details from a message list entry. SEQTA sends either a plain `sender` name with
+/// `sender_id`/`sender_type` beside it, or a `sender` object.
+fn parse_sender(msg: &Value) -> MessageSender {
+    let sender = msg.get("sender");
+    let field = |keys: &[&str]| {
+        keys.iter().find_map(|key| {
+            sender
+                .and_then(|s| s.get(key))
+                .or_else(|| msg.get(format!("sender_{}", key)))
+                .filter(|v| !v.is_null())
+        })
+    };
+
+    let name = sender
+        .and_then(|v| v.as_str())
+        .or_else(|| field(&["name"]).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Unknown")
+        .to_string();
+    let id = field(&["id"]).and_then(|v| v.as_i64());
+    let kind = match field(&["type", "staff"]) {
+        Some(Value::Bool(true)) => SenderKind::Staff,
+        Some(Value::Bool(false)) => SenderKind::Student,
+        Some(Value::String(kind)) => match kind.to_lowercase().as_str() {
+            "staff" | "teacher" => SenderKind::Staff,
+            "student" => SenderKind::Student,
+            _ => SenderKind::Unknown,
+        },
+        _ => SenderKind::Unknown,
+    };
+    MessageSender { name, kind, id }
+}
+
 fn parse_message_json(msg: &Value, folder_label: &str) -> Option<Message> {
     let id = msg.get("id")?.as_i64()?;
     let subject = msg
@@ -44,11 +105,8 @@ fn parse_message_json(msg: &Value, folder_label: &str) -> Option<Message> {
         .and_then(|v| v.as_str())
         .unwrap_or("(No Subject)")
         .to_string();
-    let sender = msg
-        .get("sender")
-        .and_then(|v| v.as_str())
-        .unwrap_or("Unknown")
-        .to_string();
+    let sender_info = parse_sender(msg);
+    let sender = sender_info.name.clone();
     let sender_photo = msg
         .get("sender_photo")
         .and_then(|v| v.as_str())
@@ -127,6 +185,7 @@ fn parse_message_json(msg: &Value, folder_label: &str) -> Option<Message> {
         id,
         folder: folder_display,
         sender,
+        sender_info,
         sender_photo,
         to,
         subject,
@@ -155,6 +214,11 @@ fn mock_messages(folder: &str) -> Vec<Message> {
             id: 1001,
             folder: folder_display.clone(),
             sender: "Mr. Johnson".to_string(),
+            sender_info: MessageSender {
+                name: "Mr. Johnson".to_string(),
+                kind: SenderKind::Staff,
+                id: None,
+            },
             sender_photo: None,
             to: "Student Portal".to_string(),
             subject: "Welcome to DesQTA".to_string(),
@@ -169,6 +233,11 @@ fn mock_messages(folder: &str) -> Vec<Message> {
             id: 1002,
             folder: folder_display.clone(),
             sender: "Ms. Smith".to_string(),
+            sender_info: MessageSender {
+                name: "Ms. Smith".to_string(),
+                kind: SenderKind::Staff,
+                id: None,
+            },
             sender_photo: None,
             to: "Class Group".to_string(),
             subject: "Assignment Reminder".to_string(),
@@ -183,6 +252,11 @@ fn mock_messages(folder: &str) -> Vec<Message> {
             id: 1003,
             folder: folder_display,
             sender: "Dr. Williams".to_string(),
+            sender_info: MessageSender {
+                name: "Dr. Williams".to_string(),
+                kind: SenderKind::Staff,
+                id: None,
+            },
             sender_photo: None,
             to: "All Students".to_string(),
             subject: "Important Notice".to_string(),
@@ -196,45 +270,91 @@ fn mock_messages(folder: &str) -> Vec<Message> {
     ]
 }
 
+/// One page of a folder listing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub offset: usize,
+    /// Messages in the folder. Only counts the pages fetched so far while `has_more` is set.
+    pub total: usize,
+    /// Unread messages among `total`, not just this page
+    pub unread: usize,
+    pub has_more: bool,
+}
+
+/// Cut `limit` messages (all when `None`) starting at `offset` out of a folder listing.
+/// `complete` is false when SEQTA has older messages that haven't been fetched yet.
+fn message_page(
+    messages: Vec<Message>,
+    offset: usize,
+    limit: Option<usize>,
+    complete: bool,
+) -> MessagePage {
+    let total = messages.len();
+    let unread = messages.iter().filter(|m| m.unread).count();
+    let page: Vec<Message> = messages
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    MessagePage {
+        has_more: offset + page.len() < total || !complete,
+        messages: page,
+        offset,
+        total,
+        unread,
+    }
+}
+
+/// A page of `folder` (or an RSS feed folder), with the folder's total and unread counts.
+/// Without `offset`/`limit` the whole folder is returned. SEQTA is only asked for the pages
+/// needed to reach `offset + limit`, and the listing is cached briefly so later pages carry
+/// on from it; the first page is always fetched fresh unless `refresh` is false.
 #[tauri::command]
 pub async fn fetch_messages(
     folder: String,
     rss_url: Option<String>,
-) -> Result<Vec<Message>, String> {
+    offset: Option<usize>,
+    limit: Option<usize>,
+    refresh: Option<bool>,
+) -> Result<MessagePage, String> {
+    let offset = offset.unwrap_or(0);
+    let refresh = refresh.unwrap_or(offset == 0);
+    let wanted = limit.map_or(MAX_LISTED_MESSAGES, |limit| offset.saturating_add(limit));
+    let listing = cached_folder_listing(&folder, rss_url, refresh, wanted).await?;
+    // Star changes SEQTA hasn't confirmed yet already show as made
+    let messages = overlay_pending_stars(listing.messages(), &folder, &load_pending_stars());
+    Ok(message_page(messages, offset, limit, listing.is_complete()))
+}
+
+/// A folder's listing with nothing fetched from SEQTA yet. RSS feeds are read in full.
+async fn load_folder_listing(
+    folder: &str,
+    rss_url: Option<String>,
+) -> Result<FolderListing, String> {
     if crate::settings::Settings::load().dev_sensitive_info_hider {
-        return Ok(mock_messages(&folder));
+        return Ok(FolderListing::complete(mock_messages(folder)));
     }
 
     if let Some(logger) = logger::get_logger() {
         let _ = logger.log(
             logger::LogLevel::INFO,
             "messages",
-            "fetch_messages",
+            "load_folder_listing",
             &format!("Fetching messages for folder: {}", folder),
             json!({ "folder": folder, "rss": rss_url }),
         );
     }
 
     if folder == "sent" {
-        // Fetch both sent and outbox in parallel
         // Force "Sent" as the folder name for UI consistency, merging sent/outbox
-        let sent_future = fetch_seqta_messages("sent", Some("Sent"));
-        let outbox_future = fetch_seqta_messages("outbox", Some("Sent"));
-
-        let (sent_res, outbox_res) = tokio::join!(sent_future, outbox_future);
-
-        let mut messages = Vec::new();
-        if let Ok(sent) = sent_res {
-            messages.extend(sent);
-        }
-        if let Ok(outbox) = outbox_res {
-            messages.extend(outbox);
-        }
-
-        // Sort by date descending
-        messages.sort_by(|a, b| b.date.cmp(&a.date));
-
-        Ok(messages)
+        Ok(FolderListing {
+            labels: vec![
+                LabelListing::new("sent", Some("Sent")),
+                LabelListing::new("outbox", Some("Sent")),
+            ],
+        })
     } else if folder.starts_with("rss-") {
         // Handle RSS feeds
         // For now, if rss_url is provided or embedded in folder name, fetch it
@@ -294,6 +414,10 @@ pub async fn fetch_messages(
                             id: (i as i64) + 1000000, // Fake ID
                             folder: rss_url.clone().unwrap_or_else(|| channel_title.to_string()),
                             sender: channel_title.to_string(),
+                            sender_info: MessageSender {
+                                name: channel_title.to_string(),
+                                ..MessageSender::default()
+                            },
                             sender_photo: None,
                             to: "".to_string(),
                             subject: title.to_string(),
@@ -308,33 +432,145 @@ pub async fn fetch_messages(
                 }
                 // Sort by date descending
                 messages.sort_by(|a, b| b.date.cmp(&a.date));
-                Ok(messages)
+                Ok(FolderListing::complete(messages))
             }
             Err(e) => Err(format!("Failed to fetch RSS feed: {}", e)),
         }
     } else {
         // Regular folder
-        let msgs = fetch_seqta_messages(&folder, None).await?;
-        Ok(msgs)
+        Ok(FolderListing {
+            labels: vec![LabelListing::new(folder, None)],
+        })
+    }
+}
+
+/// Messages SEQTA is asked for per request when listing a folder.
+const SEQTA_MESSAGE_PAGE_SIZE: usize = 100;
+/// Most messages listed from one folder; older ones are left out.
+const MAX_LISTED_MESSAGES: usize = 1000;
+
+/// The messages of one SEQTA label fetched so far, newest first.
+#[derive(Debug, Clone)]
+struct LabelListing {
+    label: String,
+    /// Folder name given to the messages instead of the label
+    folder_override: Option<&'static str>,
+    messages: Vec<Message>,
+    /// Offset of the next page to request, `None` once there's nothing more to fetch
+    next_offset: Option<usize>,
+}
+
+impl LabelListing {
+    fn new(label: &str, folder_override: Option<&'static str>) -> Self {
+        Self {
+            label: label.to_string(),
+            folder_override,
+            messages: Vec::new(),
+            next_offset: Some(0),
+        }
+    }
+
+    /// Request pages from SEQTA until `wanted` messages are listed or the label runs out.
+    async fn fill(&mut self, wanted: usize) -> Result<(), String> {
+        let wanted = wanted.min(MAX_LISTED_MESSAGES);
+        while let Some(offset) = self.next_offset {
+            if self.messages.len() >= wanted {
+                break;
+            }
+            let (page, listed) =
+                fetch_seqta_message_page(&self.label, self.folder_override, offset).await?;
+            self.messages.extend(page);
+            let next = offset + listed;
+            self.next_offset =
+                (listed == SEQTA_MESSAGE_PAGE_SIZE && next < MAX_LISTED_MESSAGES).then_some(next);
+        }
+        Ok(())
     }
 }
 
-async fn fetch_seqta_messages(
+/// A folder's messages, made up of one or more labels (the sent folder merges "sent" and
+/// "outbox").
+#[derive(Debug, Clone)]
+struct FolderListing {
+    labels: Vec<LabelListing>,
+}
+
+impl FolderListing {
+    /// A listing that's already fully loaded, like an RSS feed.
+    fn complete(messages: Vec<Message>) -> Self {
+        Self {
+            labels: vec![LabelListing {
+                label: String::new(),
+                folder_override: None,
+                messages,
+                next_offset: None,
+            }],
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.labels.iter().all(|l| l.next_offset.is_none())
+    }
+
+    /// Messages fetched so far, newest first.
+    fn messages(&self) -> Vec<Message> {
+        let mut messages: Vec<Message> = self
+            .labels
+            .iter()
+            .flat_map(|l| l.messages.iter().cloned())
+            .collect();
+        if self.labels.len() > 1 {
+            messages.sort_by(|a, b| b.date.cmp(&a.date));
+        }
+        messages
+    }
+
+    /// Fetch until each label has `wanted` messages or runs out, so the newest `wanted` of the
+    /// merged listing are right. A label failing is only an error when every label fails.
+    async fn fill(&mut self, wanted: usize) -> Result<(), String> {
+        let results =
+            futures::future::join_all(self.labels.iter_mut().map(|l| l.fill(wanted))).await;
+        let failed = results.iter().filter(|r| r.is_err()).count();
+        match results.into_iter().find_map(Result::err) {
+            Some(e) if failed == self.labels.len() => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parse a `/seqta/student/load/message` list response. Entries without an id are skipped.
+fn parse_message_list(data: &Value, folder_name: &str) -> (Vec<Message>, usize) {
+    let entries = data
+        .get("payload")
+        .and_then(|p| p.get("messages"))
+        .and_then(|m| m.as_array())
+        .map(|m| m.as_slice())
+        .unwrap_or_default();
+    let messages = entries
+        .iter()
+        .filter_map(|msg| parse_message_json(msg, folder_name))
+        .collect();
+    (messages, entries.len())
+}
+
+/// One page of `label` starting at `offset`, plus how many entries SEQTA listed in it.
+async fn fetch_seqta_message_page(
     label: &str,
     folder_override: Option<&str>,
-) -> Result<Vec<Message>, String> {
+    offset: usize,
+) -> Result<(Vec<Message>, usize), NetError> {
     let body = json!({
         "searchValue": "",
         "sortBy": "date",
         "sortOrder": "desc",
         "action": "list",
         "label": label,
-        "offset": 0,
-        "limit": 100,
+        "offset": offset,
+        "limit": SEQTA_MESSAGE_PAGE_SIZE,
         "datetimeUntil": null,
     });
 
-    let response = netgrab::fetch_api_data(
+    let response = netgrab::request_api_data(
         "/seqta/student/load/message?",
         RequestMethod::POST,
         Some({
@@ -353,25 +589,24 @@ async fn fetch_seqta_messages(
     )
     .await?;
 
-    let data: Value =
-        serde_json::from_str(&response).map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    let mut messages = Vec::new();
-    let folder_name = folder_override.unwrap_or(label);
-
-    if let Some(msgs_array) = data
-        .get("payload")
-        .and_then(|p| p.get("messages"))
-        .and_then(|m| m.as_array())
-    {
-        for msg_val in msgs_array {
-            if let Some(msg) = parse_message_json(msg_val, folder_name) {
-                messages.push(msg);
-            }
-        }
+    let data = seqta_mentions::parse_seqta_payload(&response)?;
+    if data.get("status").and_then(|s| s.as_str()) == Some("401") {
+        return Err(NetError::Unauthorized(
+            "SEQTA session expired. Please log in again.".to_string(),
+        ));
     }
+    Ok(parse_message_list(&data, folder_override.unwrap_or(label)))
+}
 
-    Ok(messages)
+/// The newest page of the inbox, for the new-message poller. Errors keep their `NetError` kind
+/// so an expired session can be told apart from network trouble.
+pub(crate) async fn fetch_latest_inbox() -> Result<Vec<Message>, NetError> {
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        return Ok(mock_messages("inbox"));
+    }
+    fetch_seqta_message_page("inbox", None, 0)
+        .await
+        .map(|(messages, _)| messages)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -438,18 +673,291 @@ pub async fn fetch_message_content(id: i64) -> Result<MessageContentResponse, St
                 .collect()
         });
 
+    cache_message_body(id, &content);
     Ok(MessageContentResponse { content, files })
 }
 
+// Search
+
+/// How long a folder listing is reused by `search_messages` and for later pages
+const MESSAGE_LIST_CACHE_DURATION_MS: i64 = 60 * 1000;
+/// Message bodies downloaded at once when a search needs ones that aren't cached
+const MESSAGE_BODY_CONCURRENCY: usize = 4;
+
+struct MessageListEntry {
+    listing: FolderListing,
+    timestamp: i64,
+}
+
+/// Folder listings by folder name, so searching on every keystroke doesn't refetch them
+static MESSAGE_LIST_CACHE: OnceLock<Mutex<HashMap<String, MessageListEntry>>> = OnceLock::new();
+/// Plain-text message bodies by message ID. Messages can't be edited once sent, so these
+/// never expire.
+static MESSAGE_BODY_CACHE: OnceLock<Mutex<HashMap<i64, String>>> = OnceLock::new();
+pub static MESSAGE_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+fn message_list_cache() -> &'static Mutex<HashMap<String, MessageListEntry>> {
+    MESSAGE_LIST_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn message_body_cache() -> &'static Mutex<HashMap<i64, String>> {
+    MESSAGE_BODY_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_message_body(id: i64, html: &str) {
+    if let Ok(mut cache) = message_body_cache().lock() {
+        cache.insert(id, html_to_text(html));
+    }
+}
+
+/// Drop cached folder listings and message bodies. Returns the number of entries removed.
+pub fn clear_cache() -> usize {
+    let mut cleared = 0;
+    if let Ok(mut cache) = message_list_cache().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    if let Ok(mut cache) = message_body_cache().lock() {
+        cleared += cache.len();
+        cache.clear();
+    }
+    cleared
+}
+
+/// `folder`'s listing with at least `wanted` messages fetched (or all of them), carrying on
+/// from the cached listing while it's fresh unless `refresh` is set.
+async fn cached_folder_listing(
+    folder: &str,
+    rss_url: Option<String>,
+    refresh: bool,
+    wanted: usize,
+) -> Result<FolderListing, String> {
+    // RSS folders are labelled with the feed name, so listings for different names differ
+    let key = match &rss_url {
+        Some(name) => format!("{}\n{}", folder, name),
+        None => folder.to_string(),
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut cached = None;
+    if let Ok(mut cache) = message_list_cache().lock() {
+        if let Some(entry) = cache.get(&key) {
+            if !refresh && now - entry.timestamp < MESSAGE_LIST_CACHE_DURATION_MS {
+                cached = Some((entry.listing.clone(), entry.timestamp));
+            } else {
+                cache.remove(&key);
+                MESSAGE_CACHE_STATS.evicted(1);
+            }
+        }
+    }
+    let (mut listing, timestamp) = match cached {
+        Some(cached) => {
+            MESSAGE_CACHE_STATS.hit();
+            cached
+        }
+        None => {
+            MESSAGE_CACHE_STATS.miss();
+            (load_folder_listing(folder, rss_url).await?, now)
+        }
+    };
+
+    listing.fill(wanted).await?;
+    // Pages added later keep the listing's original timestamp, so it still expires on time
+    if let Ok(mut cache) = message_list_cache().lock() {
+        cache.insert(
+            key,
+            MessageListEntry {
+                listing: listing.clone(),
+                timestamp,
+            },
+        );
+    }
+    Ok(listing)
+}
+
+/// Body text for each message, downloading the ones that aren't cached. A body that fails
+/// to download is left out, so the message is still matched on its subject and sender.
+async fn message_bodies(messages: &[Message]) -> HashMap<i64, String> {
+    let mut bodies = HashMap::new();
+    let mut missing = Vec::new();
+    {
+        let cache = message_body_cache()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for message in messages {
+            if !message.body.is_empty() {
+                bodies.insert(message.id, html_to_text(&message.body));
+            } else if let Some(text) = cache.get(&message.id) {
+                bodies.insert(message.id, text.clone());
+            } else {
+                missing.push(message.id);
+            }
+        }
+    }
+
+    let fetched: Vec<(i64, Result<MessageContentResponse, String>)> = stream::iter(missing)
+        .map(|id| async move { (id, fetch_message_content(id).await) })
+        .buffer_unordered(MESSAGE_BODY_CONCURRENCY)
+        .collect()
+        .await;
+    for (id, result) in fetched {
+        if let Ok(response) = result {
+            bodies.insert(id, html_to_text(&response.content));
+        }
+    }
+    bodies
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MessageSearchFilters {
+    /// Case-insensitive part of the sender's name
+    pub sender: Option<String>,
+    /// Inclusive bounds as `YYYY-MM-DD`
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+}
+
+impl MessageSearchFilters {
+    fn matches(&self, message: &Message) -> bool {
+        if let Some(sender) = &self.sender {
+            if !message
+                .sender
+                .to_lowercase()
+                .contains(&sender.trim().to_lowercase())
+            {
+                return false;
+            }
+        }
+        // `Message::date` is "YYYY-MM-DD HH:MM", so the day compares as a string
+        let day = message.date.get(..10).unwrap_or(&message.date);
+        if let Some(from) = &self.date_from {
+            if day < from.get(..10).unwrap_or(from) {
+                return false;
+            }
+        }
+        if let Some(to) = &self.date_to {
+            if day > to.get(..10).unwrap_or(to) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSearchResult {
+    pub message: Message,
+    pub score: f32,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Score a message the way the notes advanced search scores notes: subject like a title,
+/// sender like tags, body like content. `None` when nothing matched.
+fn score_message(message: &Message, body: &str, query_lower: &str) -> Option<MessageSearchResult> {
+    let mut score = 0.0f32;
+    let mut matches = Vec::new();
+
+    let subject_lower = message.subject.to_lowercase();
+    let sender_lower = message.sender.to_lowercase();
+    let body_lower = body.to_lowercase();
+    for term in query_lower.split_whitespace() {
+        if let Some(pos) = subject_lower.find(term) {
+            score += 10.0;
+            matches.push(SearchMatch {
+                field: "subject".to_string(),
+                snippet: highlight_match(&message.subject, term, pos),
+                position: pos,
+            });
+        }
+        if sender_lower.contains(term) {
+            score += 5.0;
+            matches.push(SearchMatch {
+                field: "sender".to_string(),
+                snippet: message.sender.clone(),
+                position: 0,
+            });
+        }
+        if let Some(pos) = body_lower.find(term) {
+            score += 2.0;
+            matches.push(SearchMatch {
+                field: "body".to_string(),
+                snippet: create_snippet(body, term, pos),
+                position: pos,
+            });
+        }
+    }
+
+    if score == 0.0 {
+        return None;
+    }
+    if subject_lower == query_lower {
+        score += 20.0;
+    }
+    if subject_lower.starts_with(query_lower) {
+        score += 5.0;
+    }
+
+    Some(MessageSearchResult {
+        message: message.clone(),
+        score,
+        matches,
+    })
+}
+
+/// Full-text search over a folder's messages (the inbox by default), ranked best match
+/// first. Listings are cached briefly and bodies indefinitely, so repeated searches stay
+/// local.
 #[tauri::command]
-pub async fn star_messages(items: Vec<i64>, star: bool) -> Result<(), String> {
-    let body = json!({
-        "mode": "x-star",
-        "starred": star,
-        "items": items,
+pub async fn search_messages(
+    query: String,
+    folder: Option<String>,
+    filters: Option<MessageSearchFilters>,
+) -> Result<Vec<MessageSearchResult>, String> {
+    let query_lower = query.trim().to_lowercase();
+    if query_lower.is_empty() {
+        return Ok(vec![]);
+    }
+    let folder = folder.unwrap_or_else(|| "inbox".to_string());
+    let filters = filters.unwrap_or_default();
+
+    let messages: Vec<Message> = cached_folder_listing(&folder, None, false, MAX_LISTED_MESSAGES)
+        .await?
+        .messages()
+        .into_iter()
+        .filter(|message| filters.matches(message))
+        .collect();
+    let bodies = message_bodies(&messages).await;
+
+    let mut results: Vec<MessageSearchResult> = messages
+        .iter()
+        .filter_map(|message| {
+            let body = bodies.get(&message.id).map(String::as_str).unwrap_or("");
+            score_message(message, body, &query_lower)
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.message.date.cmp(&a.message.date))
     });
 
-    let _ = netgrab::fetch_api_data(
+    Ok(results)
+}
+
+/// Outcome of a bulk message action for a single message ID.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageOperationResult {
+    pub id: i64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Largest number of IDs sent to SEQTA in one save request.
+const MESSAGE_BATCH_SIZE: usize = 50;
+
+/// POST a `/seqta/student/save/message` action and check SEQTA accepted it.
+async fn save_message_action(body: Value) -> Result<(), String> {
+    let response = netgrab::fetch_api_data(
         "/seqta/student/save/message?",
         RequestMethod::POST,
         Some({
@@ -468,49 +976,463 @@ pub async fn star_messages(items: Vec<i64>, star: bool) -> Result<(), String> {
     )
     .await?;
 
+    // SEQTA reports failures in the body with a non-200 status rather than the HTTP code
+    if let Ok(data) = serde_json::from_str::<Value>(&response) {
+        if let Some(status) = data.get("status").and_then(|s| s.as_str()) {
+            if status != "200" {
+                return Err(format!("SEQTA rejected message action (status {})", status));
+            }
+        }
+    }
+
     Ok(())
 }
 
-#[tauri::command]
-pub async fn delete_messages(items: Vec<i64>) -> Result<(), String> {
-    let body = json!({
-        "mode": "x-label",
-        "label": "trash",
-        "items": items,
-    });
+/// Run a save action over `items` in batches. When a batch fails each of its IDs is retried
+/// on its own so one bad message doesn't fail the rest.
+async fn apply_bulk_message_action<F>(
+    items: Vec<i64>,
+    build_body: F,
+) -> Vec<MessageOperationResult>
+where
+    F: Fn(&[i64]) -> Value,
+{
+    let mut results = Vec::with_capacity(items.len());
 
-    let _ = netgrab::fetch_api_data(
-        "/seqta/student/save/message?",
-        RequestMethod::POST,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert(
-                "Content-Type".to_string(),
-                "application/json; charset=utf-8".to_string(),
+    for batch in items.chunks(MESSAGE_BATCH_SIZE) {
+        let batch_error = match save_message_action(build_body(batch)).await {
+            Ok(()) => {
+                results.extend(batch.iter().map(|&id| MessageOperationResult {
+                    id,
+                    success: true,
+                    error: None,
+                }));
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        if batch.len() == 1 {
+            results.push(MessageOperationResult {
+                id: batch[0],
+                success: false,
+                error: Some(batch_error),
+            });
+            continue;
+        }
+
+        for &id in batch {
+            let result = save_message_action(build_body(&[id])).await;
+            results.push(MessageOperationResult {
+                id,
+                success: result.is_ok(),
+                error: result.err(),
+            });
+        }
+    }
+
+    // Cached listings no longer show the right read, starred or folder state
+    if results.iter().any(|r| r.success) {
+        if let Ok(mut cache) = message_list_cache().lock() {
+            cache.clear();
+        }
+    }
+
+    let failed: Vec<i64> = results.iter().filter(|r| !r.success).map(|r| r.id).collect();
+    if !failed.is_empty() {
+        if let Some(logger) = logger::get_logger() {
+            let _ = logger.log(
+                logger::LogLevel::WARN,
+                "messages",
+                "apply_bulk_message_action",
+                &format!("{} of {} message actions failed", failed.len(), results.len()),
+                json!({ "failed": failed }),
             );
-            headers
-        }),
-        Some(body),
-        None,
-        false,
-        false,
-        None,
-    )
-    .await?;
+        }
+    }
 
-    Ok(())
+    results
+}
+
+/// Keeps the old `Result` contract for callers that only check for an error: fails when no
+/// message succeeded, otherwise returns the per-ID results.
+fn bulk_results(
+    results: Vec<MessageOperationResult>,
+) -> Result<Vec<MessageOperationResult>, String> {
+    if !results.is_empty() && results.iter().all(|r| !r.success) {
+        return Err(results[0]
+            .error
+            .clone()
+            .unwrap_or_else(|| "Message action failed".to_string()));
+    }
+    Ok(results)
+}
+
+/// Sync queue type for a star change SEQTA hasn't confirmed yet.
+const PENDING_STAR_QUEUE_TYPE: &str = "message_star";
+/// A pending star change older than this belongs to a request that never finished (the app
+/// quit mid-request, say) and is dropped rather than shown.
+const PENDING_STAR_MAX_AGE_SECS: i64 = 5 * 60;
+
+/// A star change shown in listings before SEQTA has confirmed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PendingStar {
+    /// Sync queue row recording the change
+    queue_id: i64,
+    starred: bool,
+}
+
+/// Payload of the `message-star-rollback` event, sent when SEQTA rejects a star change that
+/// was already shown. The message is back to what SEQTA has, so listings should be reloaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct StarRollback {
+    pub id: i64,
+    /// The change that was undone
+    pub starred: bool,
+    pub error: Option<String>,
 }
 
+/// The latest pending star change for each message in sync queue `items`, leaving out ones
+/// older than `PENDING_STAR_MAX_AGE_SECS` at `now` (seconds).
+fn pending_stars_from_queue(items: &[QueueItem], now: i64) -> HashMap<i64, PendingStar> {
+    let mut pending: Vec<(i64, i64, PendingStar)> = items
+        .iter()
+        .filter(|item| item.item_type == PENDING_STAR_QUEUE_TYPE)
+        .filter(|item| now - item.created_at <= PENDING_STAR_MAX_AGE_SECS)
+        .filter_map(|item| {
+            let change = PendingStar {
+                queue_id: item.id?,
+                starred: item.payload["starred"].as_bool()?,
+            };
+            Some((item.payload["id"].as_i64()?, item.created_at, change))
+        })
+        .collect();
+    // Later rows replace earlier ones
+    pending.sort_by_key(|(_, created_at, change)| (*created_at, change.queue_id));
+    pending
+        .into_iter()
+        .map(|(id, _, change)| (id, change))
+        .collect()
+}
+
+/// Pending star changes, deleting stale ones from the sync queue.
+fn load_pending_stars() -> HashMap<i64, PendingStar> {
+    let items = match database::db_queue_all() {
+        Ok(items) => items,
+        Err(e) => {
+            println!("[Messages] Failed to read pending star changes: {}", e);
+            return HashMap::new();
+        }
+    };
+    let now = chrono::Utc::now().timestamp();
+    for item in &items {
+        if item.item_type == PENDING_STAR_QUEUE_TYPE
+            && now - item.created_at > PENDING_STAR_MAX_AGE_SECS
+        {
+            if let Some(id) = item.id {
+                let _ = database::db_queue_delete(id);
+            }
+        }
+    }
+    pending_stars_from_queue(&items, now)
+}
+
+/// Record `starred` as pending for each of `ids`, replacing any change still pending for
+/// them so toggling a message repeatedly leaves only the last one.
+fn record_pending_stars(ids: &[i64], starred: bool) -> HashMap<i64, PendingStar> {
+    let existing = load_pending_stars();
+    ids.iter()
+        .filter_map(|&id| {
+            if let Some(previous) = existing.get(&id) {
+                let _ = database::db_queue_delete(previous.queue_id);
+            }
+            // Without the queue the change just isn't shown until SEQTA confirms it
+            match database::db_queue_add(
+                PENDING_STAR_QUEUE_TYPE.to_string(),
+                json!({ "id": id, "starred": starred }),
+            ) {
+                Ok(queue_id) => Some((id, PendingStar { queue_id, starred })),
+                Err(e) => {
+                    println!("[Messages] Failed to record pending star change: {}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// `messages` from `folder` with pending star changes applied. Messages being unstarred are
+/// left out of the starred folder.
+fn overlay_pending_stars(
+    mut messages: Vec<Message>,
+    folder: &str,
+    pending: &HashMap<i64, PendingStar>,
+) -> Vec<Message> {
+    if pending.is_empty() {
+        return messages;
+    }
+    for message in &mut messages {
+        if let Some(change) = pending.get(&message.id) {
+            message.starred = change.starred;
+        }
+    }
+    if folder.eq_ignore_ascii_case("starred") {
+        messages.retain(|m| m.starred);
+    }
+    messages
+}
+
+/// Settle the changes `recorded` by one `star_messages` call once SEQTA has answered with
+/// `results`. Returns the sync queue rows to delete (confirmed changes are now SEQTA's own
+/// state, failed ones are rolled back) and the rollbacks to report. A change replaced in
+/// `current` by a later toggle is left for that request to settle.
+fn settle_pending_stars(
+    recorded: &HashMap<i64, PendingStar>,
+    current: &HashMap<i64, PendingStar>,
+    results: &[MessageOperationResult],
+) -> (Vec<i64>, Vec<StarRollback>) {
+    let mut settled = Vec::new();
+    let mut rollbacks = Vec::new();
+    for result in results {
+        let Some(change) = recorded.get(&result.id) else {
+            continue;
+        };
+        if current.get(&result.id) != Some(change) {
+            continue;
+        }
+        settled.push(change.queue_id);
+        if !result.success {
+            rollbacks.push(StarRollback {
+                id: result.id,
+                starred: change.starred,
+                error: result.error.clone(),
+            });
+        }
+    }
+    (settled, rollbacks)
+}
+
+/// Star or unstar `items`. The change shows in `fetch_messages` straight away and is kept
+/// until SEQTA answers; any message SEQTA rejects goes back to its old state and a
+/// `message-star-rollback` event is sent for it. Repeated IDs are only sent once.
 #[tauri::command]
-pub async fn restore_messages(items: Vec<i64>) -> Result<(), String> {
-    let body = json!({
-        "mode": "x-label",
-        "label": "inbox",
-        "items": items,
-    });
+pub async fn star_messages(
+    app: AppHandle,
+    items: Vec<i64>,
+    star: bool,
+) -> Result<Vec<MessageOperationResult>, String> {
+    let mut seen = HashSet::new();
+    let items: Vec<i64> = items.into_iter().filter(|id| seen.insert(*id)).collect();
+    let recorded = record_pending_stars(&items, star);
 
-    let _ = netgrab::fetch_api_data(
-        "/seqta/student/save/message?",
+    let results = apply_bulk_message_action(items, |batch| {
+        json!({
+            "mode": "x-star",
+            "starred": star,
+            "items": batch,
+        })
+    })
+    .await;
+
+    let (settled, rollbacks) = settle_pending_stars(&recorded, &load_pending_stars(), &results);
+    for queue_id in settled {
+        let _ = database::db_queue_delete(queue_id);
+    }
+    for rollback in &rollbacks {
+        let _ = app.emit("message-star-rollback", rollback);
+    }
+    bulk_results(results)
+}
+
+#[tauri::command]
+pub async fn delete_messages(items: Vec<i64>) -> Result<Vec<MessageOperationResult>, String> {
+    let results = apply_bulk_message_action(items, |batch| {
+        json!({
+            "mode": "x-label",
+            "label": "trash",
+            "items": batch,
+        })
+    })
+    .await;
+    bulk_results(results)
+}
+
+#[tauri::command]
+pub async fn restore_messages(items: Vec<i64>) -> Result<Vec<MessageOperationResult>, String> {
+    let results = apply_bulk_message_action(items, |batch| {
+        json!({
+            "mode": "x-label",
+            "label": "inbox",
+            "items": batch,
+        })
+    })
+    .await;
+    bulk_results(results)
+}
+
+/// Folders a message can be moved into with `move_messages`.
+const MOVABLE_FOLDERS: [&str; 3] = ["inbox", "archive", "trash"];
+
+#[tauri::command]
+pub async fn mark_messages_read(
+    items: Vec<i64>,
+    read: bool,
+) -> Result<Vec<MessageOperationResult>, String> {
+    let results = apply_bulk_message_action(items, |batch| {
+        json!({
+            "mode": "x-read",
+            "read": read,
+            "items": batch,
+        })
+    })
+    .await;
+    bulk_results(results)
+}
+
+#[tauri::command]
+pub async fn move_messages(
+    items: Vec<i64>,
+    folder: String,
+) -> Result<Vec<MessageOperationResult>, String> {
+    let label = folder.trim().to_lowercase();
+    if !MOVABLE_FOLDERS.contains(&label.as_str()) {
+        return Err(format!("Cannot move messages to folder: {}", folder));
+    }
+
+    let results = apply_bulk_message_action(items, |batch| {
+        json!({
+            "mode": "x-label",
+            "label": label,
+            "items": batch,
+        })
+    })
+    .await;
+    bulk_results(results)
+}
+
+/// Number of unread messages in `folder` (defaults to the inbox). Counted from the cached
+/// listing, which is only fetched (its newest page) when there isn't a fresh one.
+#[tauri::command]
+pub async fn get_unread_message_count(folder: Option<String>) -> Result<usize, String> {
+    let folder = folder.unwrap_or_else(|| "inbox".to_string());
+    let listing = cached_folder_listing(&folder, None, false, SEQTA_MESSAGE_PAGE_SIZE).await?;
+    Ok(listing.messages().iter().filter(|m| m.unread).count())
+}
+
+/// Someone to send a message to, as picked from `/seqta/student/load/message/people`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recipient {
+    pub id: i64,
+    /// Staff member rather than student
+    #[serde(default)]
+    pub staff: bool,
+}
+
+/// A local file to upload and attach to a new message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageAttachment {
+    pub file_name: String,
+    pub file_path: String,
+}
+
+/// Why `send_message` failed. Nothing is kept on failure, so the compose form still holds the
+/// draft and can retry; `Network` is the one worth queueing until the app is back online.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum SendMessageError {
+    /// Missing subject, body or recipients
+    Invalid(String),
+    /// An attachment couldn't be uploaded, so nothing was sent
+    Attachment(String),
+    /// SEQTA couldn't be reached (or offline mode is on)
+    Network(String),
+    /// SEQTA refused the message
+    Rejected(String),
+}
+
+impl From<netgrab::NetError> for SendMessageError {
+    fn from(e: netgrab::NetError) -> Self {
+        match e {
+            netgrab::NetError::Network(_)
+            | netgrab::NetError::Timeout(_)
+            | netgrab::NetError::Offline => SendMessageError::Network(e.to_string()),
+            _ => SendMessageError::Rejected(e.to_string()),
+        }
+    }
+}
+
+/// Body of a `/seqta/student/save/message` request that sends a new message.
+fn send_message_body(
+    recipients: &[Recipient],
+    subject: &str,
+    contents: &str,
+    blind: bool,
+    files: &[i64],
+) -> Value {
+    let participants: Vec<Value> = recipients
+        .iter()
+        .map(|r| {
+            if r.staff {
+                json!({ "staff": true, "id": r.id })
+            } else {
+                json!({ "student": true, "id": r.id })
+            }
+        })
+        .collect();
+    json!({
+        "subject": subject,
+        "contents": contents,
+        "participants": participants,
+        "blind": blind,
+        "files": files,
+    })
+}
+
+/// Send a new direqt message to staff and/or students. `body_html` is sanitized like note
+/// content, attachments are uploaded first, and `blind` hides recipients from each other.
+/// Returns the new message's id, or `None` if SEQTA accepted it without reporting one.
+#[tauri::command]
+pub async fn send_message(
+    recipients: Vec<Recipient>,
+    subject: String,
+    body_html: String,
+    attachments: Option<Vec<MessageAttachment>>,
+    blind: Option<bool>,
+) -> Result<Option<i64>, SendMessageError> {
+    let subject = subject.trim();
+    if subject.is_empty() {
+        return Err(SendMessageError::Invalid("Add a subject".to_string()));
+    }
+    if recipients.is_empty() {
+        return Err(SendMessageError::Invalid(
+            "Add at least one recipient".to_string(),
+        ));
+    }
+    let contents =
+        html_parser::sanitize_html(&body_html, None).map_err(SendMessageError::Invalid)?;
+    if html_to_text(&contents).trim().is_empty() {
+        return Err(SendMessageError::Invalid(
+            "The message is empty".to_string(),
+        ));
+    }
+
+    let mut files = Vec::new();
+    for attachment in attachments.unwrap_or_default() {
+        let file_id =
+            netgrab::upload_seqta_file(attachment.file_name.clone(), attachment.file_path.clone())
+                .await
+                .and_then(|response| netgrab::uploaded_file_id(&response))
+                .map_err(|e| {
+                    SendMessageError::Attachment(format!(
+                        "Failed to upload \"{}\": {}",
+                        attachment.file_name, e
+                    ))
+                })?;
+        files.push(file_id);
+    }
+
+    let response = netgrab::request_api_data(
+        "/seqta/student/save/message",
         RequestMethod::POST,
         Some({
             let mut headers = HashMap::new();
@@ -520,7 +1442,13 @@ pub async fn restore_messages(items: Vec<i64>) -> Result<(), String> {
             );
             headers
         }),
-        Some(body),
+        Some(send_message_body(
+            &recipients,
+            subject,
+            &contents,
+            blind.unwrap_or(false),
+            &files,
+        )),
         None,
         false,
         false,
@@ -528,5 +1456,281 @@ pub async fn restore_messages(items: Vec<i64>) -> Result<(), String> {
     )
     .await?;
 
-    Ok(())
+    let data: Value = serde_json::from_str(&response).map_err(|_| {
+        SendMessageError::Rejected("SEQTA returned an unexpected response".to_string())
+    })?;
+    let status = data
+        .get("status")
+        .and_then(|s| s.as_str().map(String::from).or_else(|| Some(s.to_string())));
+    if status.as_deref() != Some("200") {
+        return Err(SendMessageError::Rejected(format!(
+            "SEQTA rejected the message (status {})",
+            status.unwrap_or_else(|| "unknown".to_string())
+        )));
+    }
+
+    // The sent folder changed
+    clear_cache();
+    let id = data.get("payload").and_then(netgrab::extract_seqta_id);
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "messages",
+            "send_message",
+            "Message sent",
+            json!({ "id": id, "recipients": recipients.len(), "attachments": files.len() }),
+        );
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64, subject: &str, sender: &str, date: &str) -> Message {
+        Message {
+            id,
+            folder: "Inbox".to_string(),
+            sender: sender.to_string(),
+            sender_info: MessageSender {
+                name: sender.to_string(),
+                ..MessageSender::default()
+            },
+            sender_photo: None,
+            to: String::new(),
+            subject: subject.to_string(),
+            preview: subject.to_string(),
+            body: String::new(),
+            date: date.to_string(),
+            unread: false,
+            starred: false,
+            files: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_message_list_into_pages() {
+        let fixture = json!({
+            "status": "200",
+            "payload": {
+                "messages": [
+                    {
+                        "id": 31,
+                        "subject": "Excursion forms",
+                        "sender": "Ms. Smith",
+                        "sender_id": 7,
+                        "sender_type": "staff",
+                        "date": "2025-03-10T14:30:00",
+                        "read": false,
+                        "participants": [{ "name": "Year 10" }],
+                        "attachments": [{ "id": 1 }]
+                    },
+                    {
+                        "id": 30,
+                        "subject": "Group project",
+                        "sender": { "name": "Alex Lee", "id": 1234, "staff": false },
+                        "date": "2025-03-09T08:00:00",
+                        "read": true
+                    },
+                    {
+                        "id": 29,
+                        "sender": "  ",
+                        "date": "2025-03-08T08:00:00",
+                        "read": false
+                    },
+                    { "subject": "No id" }
+                ]
+            }
+        });
+
+        let (messages, listed) = parse_message_list(&fixture, "inbox");
+        assert_eq!((messages.len(), listed), (3, 4));
+        assert_eq!(
+            messages[0].sender_info,
+            MessageSender {
+                name: "Ms. Smith".to_string(),
+                kind: SenderKind::Staff,
+                id: Some(7),
+            }
+        );
+        assert_eq!(messages[0].folder, "Inbox");
+        assert_eq!(messages[0].date, "2025-03-10 14:30");
+        assert_eq!(messages[0].preview, "Excursion forms (Attachment)");
+        assert_eq!(messages[1].sender, "Alex Lee");
+        assert_eq!(messages[1].sender_info.kind, SenderKind::Student);
+        assert_eq!(messages[1].sender_info.id, Some(1234));
+        assert_eq!(messages[2].sender_info.name, "Unknown");
+        assert_eq!(messages[2].sender_info.kind, SenderKind::Unknown);
+        assert_eq!(messages[2].subject, "(No Subject)");
+
+        let page = message_page(messages.clone(), 1, Some(1), true);
+        assert_eq!(page.messages.len(), 1);
+        assert_eq!(page.messages[0].id, 30);
+        assert_eq!((page.offset, page.total, page.unread), (1, 3, 2));
+        assert!(page.has_more);
+        let rest = message_page(messages.clone(), 2, Some(10), true);
+        assert_eq!(rest.messages.len(), 1);
+        assert!(!rest.has_more);
+        // Older pages SEQTA hasn't been asked for yet
+        assert!(message_page(messages.clone(), 2, Some(10), false).has_more);
+        let all = message_page(messages, 0, None, true);
+        assert_eq!((all.messages.len(), all.has_more), (3, false));
+        assert_eq!(
+            message_page(Vec::new(), 5, Some(10), true).messages.len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_merged_listing_is_newest_first() {
+        let mut sent = LabelListing::new("sent", Some("Sent"));
+        sent.messages = vec![
+            message(3, "Re: Excursion", "Me", "2025-03-10 09:00"),
+            message(1, "Excursion", "Me", "2025-03-01 09:00"),
+        ];
+        let mut outbox = LabelListing::new("outbox", Some("Sent"));
+        outbox.messages = vec![message(2, "Draft", "Me", "2025-03-05 09:00")];
+        outbox.next_offset = None;
+        let listing = FolderListing {
+            labels: vec![sent, outbox],
+        };
+
+        let ids: Vec<i64> = listing.messages().iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+        assert!(!listing.is_complete());
+        assert!(FolderListing::complete(vec![]).is_complete());
+    }
+
+    #[test]
+    fn test_score_message_ranks_subject_over_body() {
+        let in_subject = message(1, "Excursion forms", "Ms. Smith", "2025-03-01 09:00");
+        let in_body = message(2, "Reminder", "Mr. Jones", "2025-03-02 09:00");
+
+        let subject_hit = score_message(&in_subject, "", "excursion").unwrap();
+        let body_hit = score_message(&in_body, "Bring your excursion form", "excursion").unwrap();
+        assert!(subject_hit.score > body_hit.score);
+        assert_eq!(body_hit.matches[0].field, "body");
+        assert_eq!(body_hit.matches[0].snippet, "Bring your excursion form");
+        assert!(score_message(&in_body, "Nothing relevant", "excursion").is_none());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let msg = message(1, "Excursion", "Ms. Smith", "2025-03-10 14:30");
+        let filters =
+            |sender: Option<&str>, from: Option<&str>, to: Option<&str>| MessageSearchFilters {
+                sender: sender.map(String::from),
+                date_from: from.map(String::from),
+                date_to: to.map(String::from),
+            };
+
+        assert!(filters(Some("smith"), None, None).matches(&msg));
+        assert!(!filters(Some("jones"), None, None).matches(&msg));
+        assert!(filters(None, Some("2025-03-10"), Some("2025-03-10")).matches(&msg));
+        assert!(!filters(None, Some("2025-03-11"), None).matches(&msg));
+        assert!(!filters(None, None, Some("2025-03-09T23:59:59")).matches(&msg));
+    }
+
+    #[test]
+    fn test_send_message_body() {
+        let recipients = [
+            Recipient { id: 7, staff: true },
+            Recipient {
+                id: 12,
+                staff: false,
+            },
+        ];
+        let body = send_message_body(&recipients, "Excursion", "<p>Hi</p>", true, &[99]);
+        assert_eq!(body["participants"][0], json!({ "staff": true, "id": 7 }));
+        assert_eq!(
+            body["participants"][1],
+            json!({ "student": true, "id": 12 })
+        );
+        assert_eq!(body["blind"], true);
+        assert_eq!(body["files"], json!([99]));
+
+        assert_eq!(
+            SendMessageError::from(netgrab::NetError::Offline),
+            SendMessageError::Network("Offline mode is enabled".to_string())
+        );
+        assert!(matches!(
+            SendMessageError::from(netgrab::NetError::HttpStatus(500)),
+            SendMessageError::Rejected(_)
+        ));
+    }
+
+    #[test]
+    fn test_failed_star_rolls_back_optimistic_state() {
+        let queue_item = |queue_id: i64, id: i64, starred: bool, created_at: i64| QueueItem {
+            id: Some(queue_id),
+            item_type: PENDING_STAR_QUEUE_TYPE.to_string(),
+            payload: json!({ "id": id, "starred": starred }),
+            created_at,
+        };
+        let now = 1_700_000_000;
+        let mut queue = vec![
+            queue_item(1, 10, true, now - 20),
+            // Toggled again: only the latest change counts
+            queue_item(2, 10, false, now - 10),
+            queue_item(3, 11, true, now - 5),
+            // Left over from a request that never finished
+            queue_item(4, 12, true, now - PENDING_STAR_MAX_AGE_SECS - 1),
+            QueueItem {
+                id: Some(5),
+                item_type: "settings_patch".to_string(),
+                payload: json!({ "id": 13, "starred": true }),
+                created_at: now,
+            },
+        ];
+        let pending = pending_stars_from_queue(&queue, now);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[&10].queue_id, 2);
+        assert!(!pending[&10].starred);
+
+        // Starring shows straight away
+        let inbox = vec![
+            message(11, "Excursion", "Ms Smith", "2024-05-01 09:00:00"),
+            message(12, "Reminder", "Mr Jones", "2024-05-02 09:00:00"),
+        ];
+        let recorded = HashMap::from([(11, pending[&11])]);
+        let shown = overlay_pending_stars(inbox.clone(), "inbox", &recorded);
+        assert!(shown[0].starred);
+        assert!(!shown[1].starred);
+
+        // SEQTA rejects it: the change is dropped and reported
+        let results = vec![MessageOperationResult {
+            id: 11,
+            success: false,
+            error: Some("HTTP 500".to_string()),
+        }];
+        let (settled, rollbacks) = settle_pending_stars(&recorded, &pending, &results);
+        assert_eq!(settled, vec![3]);
+        assert_eq!(rollbacks.len(), 1);
+        assert_eq!(rollbacks[0].id, 11);
+        assert!(rollbacks[0].starred);
+        assert_eq!(rollbacks[0].error.as_deref(), Some("HTTP 500"));
+        queue.retain(|item| !item.id.is_some_and(|id| settled.contains(&id)));
+        let pending = pending_stars_from_queue(&queue, now);
+        assert!(!pending.contains_key(&11));
+        let shown = overlay_pending_stars(inbox.clone(), "inbox", &pending);
+        assert!(!shown[0].starred);
+
+        // A change replaced by a later toggle is left for that toggle to settle
+        let replaced = pending_stars_from_queue(&queue[..1], now);
+        assert_eq!(replaced[&10].queue_id, 1);
+        let results = vec![MessageOperationResult {
+            id: 10,
+            success: false,
+            error: None,
+        }];
+        let (settled, rollbacks) = settle_pending_stars(&replaced, &pending, &results);
+        assert!(settled.is_empty());
+        assert!(rollbacks.is_empty());
+
+        // Messages being unstarred drop out of the starred folder
+        let mut starred = message(10, "Camp", "Ms Smith", "2024-05-03 09:00:00");
+        starred.starred = true;
+        assert!(overlay_pending_stars(vec![starred], "starred", &pending).is_empty());
+    }
 }
@@ -1,3 +1,4 @@
+use crate::data_dir;
 use crate::logger;
 use crate::profiles;
 use crate::session;
@@ -8,18 +9,7 @@ use std::path::PathBuf;
 
 /// Get the old data directory (before profiles)
 fn old_data_dir() -> PathBuf {
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir
-    }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir = dirs_next::data_dir().expect("Unable to determine data dir");
-        dir.push("DesQTA");
-        dir
-    }
+    data_dir::app_data_dir().expect("Unable to determine data dir")
 }
 
 /// Migrate existing files to profile-based structure
@@ -4,8 +4,13 @@ use reqwest::{self, RequestBuilder};
 use rss::Channel;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 use std::{fs, io::Cursor, io::Read, sync::OnceLock, time::Duration};
+use tokio::io::AsyncWriteExt;
 use url::form_urlencoded;
 use url::Url;
 use xmltree::{Element, XMLNode};
@@ -13,17 +18,146 @@ use xmltree::{Element, XMLNode};
 use base64::{engine::general_purpose, Engine as _};
 // opens a file using the default program:
 
+use crate::database;
 use crate::logger;
 use crate::session;
 
 static GLOBAL_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
 
+/// Cached `dev_force_offline_mode`: 0 = not read from settings yet, 1 = online, 2 = offline.
+/// Kept in an atomic so every request can check it without touching the settings file.
+static OFFLINE_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Whether offline mode is forced. Reads the setting once, then relies on `set_offline_mode`
+/// being called whenever settings are saved.
+pub fn is_offline_mode() -> bool {
+    match OFFLINE_MODE.load(Ordering::Relaxed) {
+        0 => {
+            let offline = crate::settings::Settings::load().dev_force_offline_mode;
+            // Don't clobber a value set while we were loading
+            let _ = OFFLINE_MODE.compare_exchange(
+                0,
+                if offline { 2 } else { 1 },
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            OFFLINE_MODE.load(Ordering::Relaxed) == 2
+        }
+        state => state == 2,
+    }
+}
+
+pub fn set_offline_mode(offline: bool) {
+    OFFLINE_MODE.store(if offline { 2 } else { 1 }, Ordering::Relaxed);
+}
+
+/// Fail fast with `NetError::Offline` when offline mode is on, before any network access.
+pub fn ensure_online() -> Result<(), NetError> {
+    if is_offline_mode() {
+        Err(NetError::Offline)
+    } else {
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum RequestMethod {
     GET,
     POST,
 }
 
+/// Why a SEQTA request failed. Internal callers can match on this; Tauri commands turn it into
+/// a string (via `Display`) so the frontend sees the same messages as before.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetError {
+    /// Connection, DNS, TLS or transfer failure
+    Network(String),
+    Timeout(String),
+    /// No usable session, or SEQTA rejected it and re-authentication didn't fix it
+    Unauthorized(String),
+    /// The session was cleared while the request ran, usually by a login in progress. Unlike
+    /// `Unauthorized` this doesn't mean the session expired.
+    SessionCleared,
+    /// Non-success HTTP status
+    HttpStatus(u16),
+    /// The response body couldn't be read or decoded
+    Parse(String),
+    /// Offline mode is on, so no request was made
+    Offline,
+}
+
+impl NetError {
+    /// Prefix the message with `context`, keeping the error kind.
+    pub fn with_context(self, context: &str) -> Self {
+        match self {
+            NetError::Network(m) => NetError::Network(format!("{}: {}", context, m)),
+            NetError::Timeout(m) => NetError::Timeout(format!("{}: {}", context, m)),
+            NetError::Unauthorized(m) => NetError::Unauthorized(format!("{}: {}", context, m)),
+            NetError::Parse(m) => NetError::Parse(format!("{}: {}", context, m)),
+            other => other,
+        }
+    }
+
+    /// Transient failures worth retrying (common on school WiFi).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NetError::Timeout(_) => true,
+            NetError::Network(m) => {
+                let m = m.to_lowercase();
+                m.contains("connection")
+                    || m.contains("network")
+                    || m.contains("dns")
+                    || m.contains("tls")
+                    || m.contains("certificate")
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, NetError::Unauthorized(_))
+    }
+}
+
+impl std::fmt::Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetError::Network(m)
+            | NetError::Timeout(m)
+            | NetError::Unauthorized(m)
+            | NetError::Parse(m) => write!(f, "{}", m),
+            NetError::HttpStatus(code) => write!(f, "Request failed with HTTP status {}", code),
+            NetError::SessionCleared => write!(
+                f,
+                "Session was cleared. This may happen during login. Please try again after login completes."
+            ),
+            NetError::Offline => write!(f, "Offline mode is enabled"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<reqwest::Error> for NetError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            NetError::Timeout(e.to_string())
+        } else if e.is_decode() {
+            NetError::Parse(e.to_string())
+        } else if let Some(status) = e.status() {
+            NetError::HttpStatus(status.as_u16())
+        } else {
+            NetError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<NetError> for String {
+    fn from(e: NetError) -> Self {
+        e.to_string()
+    }
+}
+
 /// Create an HTTP client builder with school network-friendly configuration:
 /// - Timeouts to prevent hanging requests
 /// - SSL certificate validation that handles MITM proxies
@@ -75,6 +209,90 @@ pub fn create_client() -> &'static reqwest::Client {
     })
 }
 
+/// Most custom headers a session may carry.
+const MAX_CUSTOM_HEADERS: usize = 20;
+/// Headers the app manages itself, which custom headers can't replace.
+const RESERVED_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "host",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+];
+
+/// Check custom headers from the user: valid HTTP names and values, none the app manages
+/// itself, no duplicates differing only in case. Returns them trimmed.
+pub fn validate_custom_headers(
+    headers: HashMap<String, String>,
+) -> Result<HashMap<String, String>, String> {
+    if headers.len() > MAX_CUSTOM_HEADERS {
+        return Err(format!(
+            "Too many custom headers (at most {})",
+            MAX_CUSTOM_HEADERS
+        ));
+    }
+    let mut validated = HashMap::new();
+    let mut seen = std::collections::HashSet::new();
+    for (name, value) in headers {
+        let name = name.trim().to_string();
+        let value = value.trim().to_string();
+        let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("'{}' isn't a valid header name", name))?;
+        if RESERVED_HEADERS.contains(&header_name.as_str()) {
+            return Err(format!("The {} header is managed by DesQTA", name));
+        }
+        reqwest::header::HeaderValue::from_str(&value)
+            .map_err(|_| format!("The value of {} isn't a valid header value", name))?;
+        if !seen.insert(header_name) {
+            return Err(format!("The {} header is set more than once", name));
+        }
+        validated.insert(name, value);
+    }
+    Ok(validated)
+}
+
+/// Add the session's custom headers to `headers`, replacing any of the same name.
+/// Invalid entries (only possible in a hand-edited session) are skipped.
+fn insert_custom_headers(
+    headers: &mut reqwest::header::HeaderMap,
+    custom: &HashMap<String, String>,
+) {
+    for (name, value) in custom {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+/// The custom headers kept for `base_url`, for clients built outside `fetch_api_data` such as
+/// the login requests.
+pub fn custom_header_map(base_url: &str) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    insert_custom_headers(
+        &mut headers,
+        &session::Session::custom_headers_for(base_url),
+    );
+    headers
+}
+
+/// Headers for one request: the caller's, with the session's custom headers taking precedence.
+/// Set with `RequestBuilder::headers`, which replaces rather than appends.
+fn request_headers(
+    per_call: Option<&HashMap<String, String>>,
+    custom: &HashMap<String, String>,
+) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(per_call) = per_call {
+        insert_custom_headers(&mut headers, per_call);
+    }
+    insert_custom_headers(&mut headers, custom);
+    headers
+}
+
 async fn append_default_headers(req: RequestBuilder) -> RequestBuilder {
     let mut session = session::Session::load();
     let mut headers = reqwest::header::HeaderMap::new();
@@ -159,12 +377,14 @@ async fn append_default_headers(req: RequestBuilder) -> RequestBuilder {
         }
     }
 
+    insert_custom_headers(&mut headers, &session.custom_headers);
     req.headers(headers)
 }
 
 /// Re-authenticate inline without app handle (for use in netgrab)
 async fn reauthenticate_inline(
     base_url: &str,
+    seqta_prefix: Option<&str>,
     username: &str,
     password: &str,
 ) -> Result<String, String> {
@@ -177,7 +397,7 @@ async fn reauthenticate_inline(
         format!("https://{}", base_url)
     };
 
-    let login_url = format!("{}/seqta/student/login", http_url);
+    let login_url = session::seqta_url(&http_url, seqta_prefix, "/seqta/student/login");
 
     // Create HTTP client with cookie store enabled and school network-friendly config
     let client = create_client_builder()
@@ -230,7 +450,7 @@ async fn reauthenticate_inline(
         .ok_or("Could not get JSESSIONID from response headers")?;
 
     // Validate session with a heartbeat request
-    let heartbeat_url = format!("{}/seqta/student/heartbeat", http_url);
+    let heartbeat_url = session::seqta_url(&http_url, seqta_prefix, "/seqta/student/heartbeat");
     let heartbeat_body = json!({ "heartbeat": true });
 
     let heartbeat_response = client
@@ -249,6 +469,136 @@ async fn reauthenticate_inline(
     Ok(jsessionid)
 }
 
+/// Charset named in a Content-Type header, e.g. `text/html; charset="ISO-8859-1"`.
+fn content_type_charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Decode a response body to UTF-8 using the Content-Type charset, defaulting to UTF-8 when
+/// none is given or it isn't recognised. A byte-order mark wins over the header. Bytes that
+/// don't decode become U+FFFD with a logged warning rather than failing the request.
+fn decode_body(bytes: &[u8], content_type: Option<&str>, url: &str) -> String {
+    let encoding = content_type
+        .and_then(content_type_charset)
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, used, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        if let Some(logger) = logger::get_logger() {
+            let _ = logger.log(
+                logger::LogLevel::WARN,
+                "netgrab",
+                "decode_body",
+                &format!("Response from {} is not valid {}", url, used.name()),
+                json!({ "url": url, "encoding": used.name(), "content_type": content_type }),
+            );
+        }
+    }
+    text.into_owned()
+}
+
+/// Response body as UTF-8 text. Content-Encoding (gzip, deflate, br) is already undone by
+/// the client; this handles the charset.
+async fn read_response_text(resp: reqwest::Response) -> Result<String, NetError> {
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let url = resp.url().to_string();
+    let bytes = resp.bytes().await.map_err(NetError::from)?;
+    Ok(decode_body(&bytes, content_type.as_deref(), &url))
+}
+
+/// Outcome of a conditional GET.
+#[derive(Debug, PartialEq)]
+enum ConditionalResponse {
+    /// HTTP 304: the cached copy is still current
+    NotModified,
+    Fresh(database::CachedResponse),
+    /// An error status; the body is passed on but never cached
+    Failed(u16, String),
+}
+
+fn header_text(resp: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    resp.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Send `request` with `If-None-Match` / `If-Modified-Since` taken from `cached`. Servers that
+/// ignore them answer 200 as usual, which is treated like any other fresh response.
+async fn send_conditional(
+    mut request: RequestBuilder,
+    cached: Option<&database::CachedResponse>,
+) -> Result<ConditionalResponse, NetError> {
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let resp = request.send().await.map_err(NetError::from)?;
+    let status = resp.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(ConditionalResponse::NotModified);
+    }
+    if !status.is_success() {
+        return Ok(ConditionalResponse::Failed(
+            status.as_u16(),
+            read_response_text(resp).await?,
+        ));
+    }
+    let etag = header_text(&resp, reqwest::header::ETAG);
+    let last_modified = header_text(&resp, reqwest::header::LAST_MODIFIED);
+    Ok(ConditionalResponse::Fresh(database::CachedResponse {
+        body: read_response_text(resp).await?,
+        etag,
+        last_modified,
+        expired: false,
+    }))
+}
+
+/// GET through the database cache. A fresh entry is returned without a request; a stale one
+/// is revalidated with its ETag / Last-Modified, and a 304 reuses the cached body and renews
+/// its TTL. Returns the status, the body and whether the body came from the cache.
+async fn cached_get(
+    request: RequestBuilder,
+    cache_key: &str,
+    ttl_minutes: i64,
+) -> Result<(u16, String, bool), NetError> {
+    // Without a database (not initialised yet) this is a plain GET
+    let cached = database::cache_get_response(cache_key).ok().flatten();
+    if let Some(cached) = cached.as_ref().filter(|cached| !cached.expired) {
+        database::DB_CACHE_STATS.hit();
+        return Ok((200, cached.body.clone(), true));
+    }
+
+    match (send_conditional(request, cached.as_ref()).await?, cached) {
+        (ConditionalResponse::NotModified, Some(cached)) => {
+            database::DB_CACHE_STATS.hit();
+            let _ = database::cache_refresh_ttl(cache_key, Some(ttl_minutes));
+            Ok((200, cached.body, true))
+        }
+        (ConditionalResponse::NotModified, None) => Ok((304, String::new(), false)),
+        (ConditionalResponse::Fresh(response), _) => {
+            database::DB_CACHE_STATS.miss();
+            let _ = database::cache_set_response(cache_key, &response, Some(ttl_minutes));
+            Ok((200, response.body, false))
+        }
+        (ConditionalResponse::Failed(status, body), _) => Ok((status, body, false)),
+    }
+}
+
 #[tauri::command]
 pub async fn fetch_api_data(
     url: &str,
@@ -260,6 +610,27 @@ pub async fn fetch_api_data(
     return_url: bool,
     parse_html: Option<bool>,
 ) -> Result<String, String> {
+    request_api_data(
+        url, method, headers, body, parameters, is_image, return_url, parse_html,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Typed-error version of `fetch_api_data` for Rust callers that need to tell
+/// auth failures, timeouts and HTTP errors apart.
+pub async fn request_api_data(
+    url: &str,
+    method: RequestMethod,
+    headers: Option<HashMap<String, String>>,
+    body: Option<Value>,
+    parameters: Option<HashMap<String, String>>,
+    is_image: bool,
+    return_url: bool,
+    parse_html: Option<bool>,
+) -> Result<String, NetError> {
+    ensure_online()?;
+
     // Log function entry
     if let Some(logger) = logger::get_logger() {
         let _ = logger.log(
@@ -282,13 +653,15 @@ pub async fn fetch_api_data(
     
     // Validate session has required data
     if session.jsessionid.is_empty() && session.base_url.is_empty() {
-        return Err("No active session found. Please log in again.".to_string());
+        return Err(NetError::Unauthorized(
+            "No active session found. Please log in again.".to_string(),
+        ));
     }
 
     let full_url = if url.starts_with("http") {
         url.to_string()
     } else {
-        format!("{}{}", session.base_url.parse::<String>().unwrap(), url)
+        session.seqta_url(url)
     };
 
     // Clone headers and parameters for potential retry
@@ -298,7 +671,7 @@ pub async fn fetch_api_data(
 
     // Retry logic for transient network failures (common on school WiFi)
     let max_retries = 3;
-    let mut last_error: Option<String> = None;
+    let mut last_error: Option<NetError> = None;
     
     for attempt in 0..=max_retries {
         // Reload session at start of each attempt to ensure we have the latest session state
@@ -308,7 +681,9 @@ pub async fn fetch_api_data(
         
         // Validate session is still valid after reload
         if session.jsessionid.is_empty() && session.base_url.is_empty() {
-            return Err("Session expired or cleared. Please log in again.".to_string());
+            return Err(NetError::Unauthorized(
+                "Session expired or cleared. Please log in again.".to_string(),
+            ));
         }
         
         // Build request for this attempt
@@ -318,12 +693,8 @@ pub async fn fetch_api_data(
         };
         
         request_to_send = append_default_headers(request_to_send).await;
-        
-        if let Some(headers) = &headers {
-            for (key, value) in headers {
-                request_to_send = request_to_send.header(key, value);
-            }
-        }
+        request_to_send =
+            request_to_send.headers(request_headers(headers.as_ref(), &session.custom_headers));
         
         if let Some(params) = &parameters {
             request_to_send = request_to_send.query(params);
@@ -388,7 +759,7 @@ pub async fn fetch_api_data(
             // SEQTA APIs can return HTTP 200 with {"status":"401"} in the body
             if !is_image && !return_url {
                 // Read the response text to check for auth failures
-                let response_text = resp.text().await.map_err(|e| e.to_string())?;
+                let response_text = read_response_text(resp).await?;
                 
                 // Try to parse as JSON and check for status: "401"
                 let mut is_body_auth_failure = false;
@@ -425,7 +796,14 @@ pub async fn fetch_api_data(
                     let base_url = session.base_url.clone();
                     
                     // Perform re-authentication directly
-                    match reauthenticate_inline(&base_url, &username, &password).await {
+                    match reauthenticate_inline(
+                        &base_url,
+                        session.seqta_prefix.as_deref(),
+                        &username,
+                        &password,
+                    )
+                    .await
+                    {
                         Ok(new_jsessionid) => {
                             // Update session with new JSESSIONID
                             let mut updated_session = session::Session::load();
@@ -451,12 +829,11 @@ pub async fn fetch_api_data(
                             
                             retry_request = append_default_headers(retry_request).await;
                             
-                            // Add custom headers if provided
-                            if let Some(headers) = &headers_clone {
-                                for (key, value) in headers {
-                                    retry_request = retry_request.header(key, value);
-                                }
-                            }
+                            // Add the caller's headers, with the session's custom ones on top
+                            retry_request = retry_request.headers(request_headers(
+                                headers_clone.as_ref(),
+                                &retry_session.custom_headers,
+                            ));
                             
                             // Add query parameters if provided
                             if let Some(params) = &parameters_clone {
@@ -479,20 +856,27 @@ pub async fn fetch_api_data(
                                 Ok(retry_resp) => {
                                     let retry_status = retry_resp.status();
                                     if retry_status.is_success() {
-                                        let retry_text = retry_resp.text().await.map_err(|e| e.to_string())?;
+                                        let retry_text = read_response_text(retry_resp).await?;
                                         return Ok(retry_text);
                                     } else {
-                                        return Err(format!("Request failed after re-authentication: {}", retry_status));
+                                        return Err(NetError::Network(format!(
+                                            "Request failed after re-authentication: {}",
+                                            retry_status
+                                        )));
                                     }
                                 }
                                 Err(e) => {
-                                    return Err(format!("Request failed after re-authentication: {}", e));
+                                    return Err(NetError::from(e)
+                                        .with_context("Request failed after re-authentication"));
                                 }
                             }
                         }
                         Err(e) => {
                             // Re-authentication failed
-                            return Err(format!("Re-authentication failed: {}", e));
+                            return Err(NetError::Unauthorized(format!(
+                                "Re-authentication failed: {}",
+                                e
+                            )));
                         }
                     }
                 } else if is_body_auth_failure || is_http_auth_failure {
@@ -504,7 +888,7 @@ pub async fn fetch_api_data(
                     // If reloaded session is empty, this likely means session was cleared (e.g., during login)
                     // Don't retry with empty session - return a clear error
                     if reloaded_session.jsessionid.is_empty() || reloaded_session.base_url.is_empty() {
-                        return Err("Session was cleared. This may happen during login. Please try again after login completes.".to_string());
+                        return Err(NetError::SessionCleared);
                     }
                     
                     if reloaded_session.jsessionid != session.jsessionid {
@@ -524,7 +908,10 @@ pub async fn fetch_api_data(
                     }
 
                     // No stored credentials and session reload didn't help (same session or empty)
-                    return Err(format!("Authentication failed: {}", response_text));
+                    return Err(NetError::Unauthorized(format!(
+                        "Authentication failed: {}",
+                        response_text
+                    )));
                 }
                 
                 // Return the response text (no auth failure detected)
@@ -553,20 +940,30 @@ pub async fn fetch_api_data(
                 let password = session.stored_password.clone().unwrap();
                 let base_url = session.base_url.clone();
                 
-                match reauthenticate_inline(&base_url, &username, &password).await {
+                match reauthenticate_inline(
+                    &base_url,
+                    session.seqta_prefix.as_deref(),
+                    &username,
+                    &password,
+                )
+                .await
+                {
                     Ok(_) => {
                         // Retry logic would go here for image/URL requests if needed
-                        return Err(format!("AUTH_REQUIRED: Session expired, re-authentication completed. Please retry request."));
+                        return Err(NetError::Unauthorized("AUTH_REQUIRED: Session expired, re-authentication completed. Please retry request.".to_string()));
                     }
                     Err(e) => {
-                        return Err(format!("Re-authentication failed: {}", e));
+                        return Err(NetError::Unauthorized(format!(
+                            "Re-authentication failed: {}",
+                            e
+                        )));
                     }
                 }
             }
 
             let result = if is_image == true {
                 // Get the bytes (await and ? to bubble up errors)
-                let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+                let bytes = resp.bytes().await.map_err(NetError::from)?;
                 // Encode to base64
                 let base64_str = general_purpose::STANDARD.encode(&bytes);
                 Ok(base64_str)
@@ -575,8 +972,7 @@ pub async fn fetch_api_data(
                 Ok(url)
             } else {
                 // This should not be reached due to the check above, but keeping for safety
-                let text = resp.text().await.map_err(|e| e.to_string())?;
-                Ok(text)
+                read_response_text(resp).await
             };
 
             // Log successful response
@@ -597,18 +993,11 @@ pub async fn fetch_api_data(
                 return result;
             }
             Err(e) => {
-                last_error = Some(e.to_string());
-                
+                let error = NetError::from(e);
+
                 // Check if this is a retryable error (network/timeout issues)
-                let is_retryable = last_error.as_ref().map(|err_str| {
-                    let err_lower = err_str.to_lowercase();
-                    err_lower.contains("timeout") 
-                        || err_lower.contains("connection")
-                        || err_lower.contains("network")
-                        || err_lower.contains("dns")
-                        || err_lower.contains("tls")
-                        || err_lower.contains("certificate")
-                }).unwrap_or(false);
+                let is_retryable = error.is_retryable();
+                last_error = Some(error);
                 
                 // If this is the last attempt or error is not retryable, return error
                 if attempt >= max_retries || !is_retryable {
@@ -627,7 +1016,7 @@ pub async fn fetch_api_data(
                             }),
                         );
                     }
-                    return Err(format!("HTTP request failed: {}", last_error.as_ref().unwrap()));
+                    return Err(last_error.unwrap().with_context("HTTP request failed"));
                 }
                 
                 // Exponential backoff: wait before retrying (1s, 2s, 4s)
@@ -655,7 +1044,9 @@ pub async fn fetch_api_data(
     }
     
     // This should never be reached, but handle it just in case
-    Err(format!("HTTP request failed: {}", last_error.unwrap_or_else(|| "Unknown error".to_string())))
+    Err(last_error
+        .unwrap_or_else(|| NetError::Network("Unknown error".to_string()))
+        .with_context("HTTP request failed"))
 }
 
 #[tauri::command]
@@ -705,23 +1096,220 @@ pub async fn get_seqta_file(file_type: &str, uuid: &str) -> Result<String, Strin
     .await
 }
 
+/// Fetch a SEQTA file into memory and return it base64-encoded. Meant for small files the UI
+/// shows inline (photos, thumbnails); use `download_seqta_file` for documents.
+#[tauri::command]
+pub async fn get_seqta_file_base64(file_type: &str, uuid: &str) -> Result<String, String> {
+    let mut params = HashMap::new();
+    params.insert(String::from("type"), String::from(file_type));
+    params.insert(String::from("file"), String::from(uuid));
+    fetch_api_data(
+        "/seqta/student/load/file",
+        RequestMethod::GET,
+        None,
+        None,
+        Some(params),
+        true,
+        false,
+        None,
+    )
+    .await
+}
+
+/// Cancellation flags for in-flight downloads, keyed by download ID.
+static DOWNLOAD_CANCEL_FLAGS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+/// Client for file downloads: same network settings as `create_client`, but without the
+/// 30 second total timeout, which large files on school WiFi can easily exceed. The per-read
+/// timeout still catches stalled transfers.
+static DOWNLOAD_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn download_client() -> Result<&'static reqwest::Client, String> {
+    if let Some(client) = DOWNLOAD_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = create_client_builder()
+        .timeout(Duration::from_secs(60 * 60))
+        .user_agent("Mozilla/5.0 (DesQTA)")
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    Ok(DOWNLOAD_CLIENT.get_or_init(|| client))
+}
+
+/// Payload of the `seqta-file-progress` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDownloadProgress {
+    pub download_id: String,
+    pub received: u64,
+    /// From Content-Length; None when the server didn't send one (indeterminate progress)
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+fn register_download(download_id: &str) -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut flags) = DOWNLOAD_CANCEL_FLAGS.lock() {
+        flags
+            .get_or_insert_with(HashMap::new)
+            .insert(download_id.to_string(), flag.clone());
+    }
+    flag
+}
+
+fn unregister_download(download_id: &str) {
+    if let Ok(mut flags) = DOWNLOAD_CANCEL_FLAGS.lock() {
+        if let Some(flags) = flags.as_mut() {
+            flags.remove(download_id);
+        }
+    }
+}
+
+/// Write a response body to `dest` chunk by chunk, calling `on_progress` after each chunk.
+/// Data goes to `dest.part` first and is only renamed into place once complete, so a
+/// cancelled or failed download never leaves a truncated file behind. Returns bytes written.
+async fn stream_response_to_file<F>(
+    mut resp: reqwest::Response,
+    dest: &Path,
+    cancelled: &AtomicBool,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let total = resp.content_length();
+    let part_path = PathBuf::from(format!("{}.part", dest.display()));
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&part_path)
+            .await
+            .map_err(|e| format!("Failed to create file: {}", e))?;
+        let mut received: u64 = 0;
+        on_progress(received, total);
+
+        loop {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err("Download cancelled".to_string());
+            }
+            let Some(chunk) = resp
+                .chunk()
+                .await
+                .map_err(|e| format!("Download interrupted: {}", e))?
+            else {
+                break;
+            };
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write file: {}", e))?;
+            received += chunk.len() as u64;
+            on_progress(received, total);
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        Ok(received)
+    }
+    .await;
+
+    match result {
+        Ok(received) => {
+            fs::rename(&part_path, dest).map_err(|e| format!("Failed to save file: {}", e))?;
+            Ok(received)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&part_path);
+            Err(e)
+        }
+    }
+}
+
+/// Stream a SEQTA file to `dest_path`, emitting `seqta-file-progress` events as it arrives.
+/// `download_id` identifies the download in progress events and for `cancel_seqta_download`;
+/// one is generated when omitted. Returns the saved path.
+#[tauri::command]
+pub async fn download_seqta_file(
+    app: tauri::AppHandle,
+    file_type: String,
+    uuid: String,
+    dest_path: String,
+    download_id: Option<String>,
+) -> Result<String, String> {
+    use tauri::Emitter;
+
+    ensure_online()?;
+    let session = session::Session::load();
+    if session.base_url.is_empty() {
+        return Err("No active session found. Please log in again.".to_string());
+    }
+    let download_id = download_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let dest = PathBuf::from(&dest_path);
+
+    let request = download_client()?
+        .get(session.seqta_url("/seqta/student/load/file"))
+        .query(&[("type", file_type.as_str()), ("file", uuid.as_str())]);
+    let resp = append_default_headers(request)
+        .await
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("Download failed with status {}", status));
+    }
+    if let Some(size) = resp.content_length() {
+        crate::disk_space::ensure_free_space(&dest, size, "download")?;
+    }
+
+    let cancelled = register_download(&download_id);
+    let result = stream_response_to_file(resp, &dest, &cancelled, |received, total| {
+        let _ = app.emit(
+            "seqta-file-progress",
+            FileDownloadProgress {
+                download_id: download_id.clone(),
+                received,
+                total,
+                done: false,
+            },
+        );
+    })
+    .await;
+    unregister_download(&download_id);
+
+    let received = result?;
+    let _ = app.emit(
+        "seqta-file-progress",
+        FileDownloadProgress {
+            download_id,
+            received,
+            total: Some(received),
+            done: true,
+        },
+    );
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Abort an in-flight `download_seqta_file`. Returns false if no such download is running.
+#[tauri::command]
+pub fn cancel_seqta_download(download_id: String) -> bool {
+    DOWNLOAD_CANCEL_FLAGS
+        .lock()
+        .ok()
+        .and_then(|flags| flags.as_ref()?.get(&download_id).cloned())
+        .map(|flag| flag.store(true, Ordering::SeqCst))
+        .is_some()
+}
+
 /// Helper function to get file size limit from seqtaConfig.json
 fn get_file_size_limit_from_config() -> Option<u64> {
-    use dirs_next;
-    use std::path::PathBuf;
-
     // Get the config file path
-    let config_path = if cfg!(target_os = "android") {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir.push("seqtaConfig.json");
-        dir
-    } else {
-        let mut dir = dirs_next::data_dir().expect("Unable to determine data dir");
-        dir.push("DesQTA");
-        dir.push("seqtaConfig.json");
-        dir
-    };
+    let config_path = crate::data_dir::app_data_dir()
+        .ok()?
+        .join("seqtaConfig.json");
 
     // Read and parse the config file
     if let Ok(mut file) = fs::File::open(&config_path) {
@@ -749,6 +1337,7 @@ fn get_file_size_limit_from_config() -> Option<u64> {
 
 #[tauri::command]
 pub async fn upload_seqta_file(file_name: String, file_path: String) -> Result<String, String> {
+    ensure_online()?;
     let client = create_client();
     let session = session::Session::load();
 
@@ -770,10 +1359,7 @@ pub async fn upload_seqta_file(file_name: String, file_path: String) -> Result<S
     // Read the file content
     let file_content = fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
-    let url = format!(
-        "{}/seqta/student/file/upload/xhr2",
-        session.base_url.parse::<String>().unwrap()
-    );
+    let url = session.seqta_url("/seqta/student/file/upload/xhr2");
     let mut request = client.post(&url);
     request = append_default_headers(request).await;
 
@@ -793,43 +1379,30 @@ pub async fn upload_seqta_file(file_name: String, file_path: String) -> Result<S
     }
 }
 
-/// Upload a file and link it to an assessment in one backend operation.
-/// This avoids frontend/seqtaFetch issues when the user navigates away during upload.
-#[tauri::command]
-pub async fn upload_and_link_assessment_file(
-    file_name: String,
-    file_path: String,
-    assessment_id: i32,
-    metaclass_id: i32,
-) -> Result<String, String> {
-    // Step 1: Upload the file
-    let upload_response = upload_seqta_file(file_name.clone(), file_path).await?;
-    let upload_result: Value = serde_json::from_str(&upload_response)
+/// An id from a SEQTA response value: `id`, `fileId`, or the value itself, as a number or
+/// numeric string.
+pub fn extract_seqta_id(v: &Value) -> Option<i64> {
+    let as_id = |id: &Value| {
+        id.as_i64()
+            .or_else(|| id.as_u64().map(|u| u as i64))
+            .or_else(|| id.as_str().and_then(|s| s.parse().ok()))
+    };
+    v.get("id")
+        .or_else(|| v.get("fileId"))
+        .map_or_else(|| as_id(v), as_id)
+}
+
+/// The file id from an `upload_seqta_file` response, failing if SEQTA rejected the upload.
+pub fn uploaded_file_id(upload_response: &str) -> Result<i64, String> {
+    let upload_result: Value = serde_json::from_str(upload_response)
         .map_err(|e| format!("Failed to parse upload response: {}", e))?;
 
     let status = upload_result.get("status").and_then(|s| s.as_str());
     let payload = upload_result.get("payload");
 
-    fn extract_id(v: &Value) -> Option<i64> {
-        if let Some(id) = v.get("id") {
-            return id.as_i64()
-                .or_else(|| id.as_u64().map(|u| u as i64))
-                .or_else(|| id.as_str().and_then(|s| s.parse().ok()));
-        }
-        if let Some(id) = v.get("fileId") {
-            return id.as_i64()
-                .or_else(|| id.as_u64().map(|u| u as i64))
-                .or_else(|| id.as_str().and_then(|s| s.parse().ok()));
-        }
-        // payload might be the id directly (e.g. payload: 12345)
-        v.as_i64()
-            .or_else(|| v.as_u64().map(|u| u as i64))
-            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
-    }
-
     let file_id: i64 = payload
-        .and_then(|p| extract_id(p))
-        .or_else(|| extract_id(&upload_result))
+        .and_then(extract_seqta_id)
+        .or_else(|| extract_seqta_id(&upload_result))
         .ok_or_else(|| {
             format!(
                 "Upload response missing file id. Response: {}",
@@ -837,14 +1410,29 @@ pub async fn upload_and_link_assessment_file(
             )
         })?;
 
-    let ok_status = status == Some("200")
-        || upload_result.get("status").and_then(|s| s.as_i64()) == Some(200);
+    let ok_status =
+        status == Some("200") || upload_result.get("status").and_then(|s| s.as_i64()) == Some(200);
     if !ok_status {
         return Err(format!(
             "Upload failed. Response: {}",
             &upload_response[..upload_response.len().min(200)]
         ));
     }
+    Ok(file_id)
+}
+
+/// Upload a file and link it to an assessment in one backend operation.
+/// This avoids frontend/seqtaFetch issues when the user navigates away during upload.
+#[tauri::command]
+pub async fn upload_and_link_assessment_file(
+    file_name: String,
+    file_path: String,
+    assessment_id: i32,
+    metaclass_id: i32,
+) -> Result<String, String> {
+    // Step 1: Upload the file
+    let upload_response = upload_seqta_file(file_name.clone(), file_path).await?;
+    let file_id = uploaded_file_id(&upload_response)?;
 
     // Step 2: Link the file to the assessment (all in backend - no frontend dependency)
     let link_body = json!({
@@ -886,6 +1474,7 @@ pub async fn upload_and_link_assessment_file(
 
 #[tauri::command]
 pub async fn get_rss_feed(feed: &str) -> Result<Value, String> {
+    ensure_online()?;
     let client = Client::builder()
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")
         .build()
@@ -1098,14 +1687,107 @@ pub async fn post_api_data(
     .await
 }
 
-/// Clear the session data with API call and remove the session file
+/// Map the status in SEQTA's response envelope to an error. SEQTA JSON endpoints answer
+/// with HTTP 200 and put the real outcome in the body:
+///
+/// ```json
+/// { "status": "200", "payload": ... }
+/// ```
+///
+/// A numeric `status` of 400 or above is an error (401/403 mean the session was rejected).
+/// Anything else, including a missing or non-numeric status, is left to the caller.
+fn check_seqta_envelope(response: &Value) -> Result<(), NetError> {
+    let code = match response.get("status") {
+        Some(Value::String(s)) => s.parse::<u16>().ok(),
+        Some(Value::Number(n)) => n.as_u64().and_then(|n| u16::try_from(n).ok()),
+        _ => None,
+    };
+    match code {
+        Some(401) | Some(403) => Err(NetError::Unauthorized(
+            "SEQTA rejected the session. Please log in again.".to_string(),
+        )),
+        Some(code) if code >= 400 => Err(NetError::HttpStatus(code)),
+        _ => Ok(()),
+    }
+}
+
+/// Typed-error version of `seqta_request` for Rust callers.
+pub async fn request_seqta_json(
+    endpoint: &str,
+    method: RequestMethod,
+    body: Option<Value>,
+) -> Result<Value, NetError> {
+    let headers = HashMap::from([(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    )]);
+    let response = request_api_data(
+        endpoint,
+        method,
+        Some(headers),
+        body,
+        None,
+        false,
+        false,
+        None,
+    )
+    .await?;
+    let value: Value = serde_json::from_str(&response).map_err(|e| {
+        NetError::Parse(format!("Failed to parse response from {}: {}", endpoint, e))
+    })?;
+    check_seqta_envelope(&value)?;
+    Ok(value)
+}
+
+/// Call a SEQTA JSON endpoint with the current session and return the parsed response.
+/// Sends the JSON content type SEQTA expects and turns an error status in the response
+/// envelope (see `check_seqta_envelope`) into an error. The data itself is usually under
+/// `payload`; the whole response is returned so callers can read other fields too.
+/// Use `fetch_api_data` for images, HTML, query parameters or custom headers.
+#[tauri::command]
+pub async fn seqta_request(
+    endpoint: String,
+    method: RequestMethod,
+    body: Option<Value>,
+) -> Result<Value, String> {
+    request_seqta_json(&endpoint, method, body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Cache key for a proxied GET. The request headers are part of it, since the same URL can
+/// answer differently per token or `Accept` header; they're hashed so tokens aren't stored in
+/// the cache table. Names are case-insensitive and their order doesn't matter.
+fn proxy_cache_key(url: &str, headers: &HashMap<String, String>) -> String {
+    if headers.is_empty() {
+        return format!("http:{}", url);
+    }
+    let sorted: std::collections::BTreeMap<String, &str> = headers
+        .iter()
+        .map(|(key, value)| (key.to_ascii_lowercase(), value.as_str()))
+        .collect();
+    let mut hasher = Sha256::new();
+    for (key, value) in sorted {
+        hasher.update(key.as_bytes());
+        hasher.update(b":");
+        hasher.update(value.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("http:{}#{}", url, hex::encode(hasher.finalize()))
+}
+
+/// Send a request to a non-SEQTA URL (cloud services) from the backend. With
+/// `cache_ttl_minutes`, GET responses are cached and revalidated with ETag / Last-Modified;
+/// `fromCache` in the result says whether the body was served from the cache.
 #[tauri::command]
 pub async fn proxy_request(
     url: &str,
     method: String,
     headers: Option<HashMap<String, String>>,
     body: Option<Value>,
+    cache_ttl_minutes: Option<i64>,
 ) -> Result<Value, String> {
+    ensure_online()?;
     let client = create_client();
     
     let mut request = match method.as_str() {
@@ -1117,20 +1799,32 @@ pub async fn proxy_request(
         _ => return Err(format!("Unsupported method: {}", method)),
     };
 
-    if let Some(headers) = headers {
-        for (key, value) in headers {
-            request = request.header(&key, value);
-        }
+    let headers = headers.unwrap_or_default();
+    for (key, value) in &headers {
+        request = request.header(key, value);
     }
 
     if let Some(body) = body {
         request = request.json(&body);
     }
 
+    if let (Some(ttl), "GET") = (cache_ttl_minutes, method.as_str()) {
+        let cache_key = proxy_cache_key(url, &headers);
+        let (status, text, from_cache) = cached_get(request, &cache_key, ttl).await?;
+        let status = reqwest::StatusCode::from_u16(status).unwrap_or(reqwest::StatusCode::OK);
+        let json_body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        return Ok(json!({
+            "status": status.as_u16(),
+            "statusText": status.canonical_reason().unwrap_or(""),
+            "data": json_body,
+            "fromCache": from_cache
+        }));
+    }
+
     match request.send().await {
         Ok(resp) => {
             let status = resp.status();
-            let text = resp.text().await.map_err(|e| e.to_string())?;
+            let text = read_response_text(resp).await?;
             
             // Try to parse as JSON, fallback to string if fails
             let json_body = serde_json::from_str(&text).unwrap_or(Value::String(text));
@@ -1145,11 +1839,391 @@ pub async fn proxy_request(
     }
 }
 
+/// Extra headers sent with every SEQTA request for the current session.
+#[tauri::command]
+pub fn get_custom_headers() -> HashMap<String, String> {
+    session::Session::load().custom_headers
+}
+
+/// Replace the current session's custom headers, e.g. a tenant header a school's proxy
+/// requires. They override the app's defaults (such as Accept or Content-Type) but can't
+/// replace the session's own Cookie or Authorization headers.
+#[tauri::command]
+pub fn set_custom_headers(headers: HashMap<String, String>) -> Result<(), String> {
+    let headers = validate_custom_headers(headers)?;
+    let mut session = session::Session::load();
+    if session.base_url.is_empty() {
+        return Err("No active session found. Please log in first.".to_string());
+    }
+    session.custom_headers = headers;
+    session.save().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn clear_session() -> Result<(), String> {
     // Send logout request first
     let _ = get_api_data("/saml2?logout", HashMap::new(), None).await;
 
-    // Then clear the session file
-    session::Session::clear_file().map_err(|e| e.to_string())
+    // Then clear the session file and who it belonged to
+    let previous = session::Session::load();
+    crate::student_info::clear_cache();
+    session::Session::clear_file().map_err(|e| e.to_string())?;
+
+    // A school's proxy still needs its headers when logging in there again
+    if !previous.custom_headers.is_empty() {
+        let kept = session::Session {
+            base_url: previous.base_url,
+            custom_headers: previous.custom_headers,
+            ..Default::default()
+        };
+        kept.save().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Serve one chunked HTTP response (no Content-Length) and return its URL.
+    async fn serve_chunked(chunks: &'static [&'static str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let mut response =
+                String::from("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n");
+            for chunk in chunks {
+                response.push_str(&format!("{:x}\r\n{}\r\n", chunk.len(), chunk));
+            }
+            response.push_str("0\r\n\r\n");
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}/file", addr)
+    }
+
+    /// Serve one response with the given extra headers and raw body bytes, returning its URL.
+    async fn serve_bytes(headers: &'static str, body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\n\r\n",
+                headers,
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+        format!("http://{}/api", addr)
+    }
+
+    /// Serve one response whose body is the request head as received. Returns the URL.
+    async fn serve_echo() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+                n
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..n]).await.unwrap();
+        });
+        format!("http://{}/api", addr)
+    }
+
+    /// Serve `requests` responses: 304 when the request carries `If-None-Match: "v1"`,
+    /// otherwise 200 with that ETag. Returns the URL.
+    async fn serve_etag(requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for _ in 0..requests {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nConnection: close\r\n\
+                     Content-Length: 5\r\n\r\nhello"
+                        .to_string()
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}/files", addr)
+    }
+
+    #[test]
+    fn test_validate_custom_headers() {
+        let headers = validate_custom_headers(HashMap::from([(
+            " X-Tenant ".to_string(),
+            " school-42 ".to_string(),
+        )]))
+        .unwrap();
+        assert_eq!(
+            headers.get("X-Tenant").map(String::as_str),
+            Some("school-42")
+        );
+
+        let invalid = |name: &str, value: &str| {
+            validate_custom_headers(HashMap::from([(name.to_string(), value.to_string())])).is_err()
+        };
+        assert!(invalid("X Tenant", "a"));
+        assert!(invalid("", "a"));
+        assert!(invalid("X-Tenant", "line\nbreak"));
+        assert!(invalid("Cookie", "JSESSIONID=abc"));
+        assert!(invalid("authorization", "Bearer abc"));
+        assert!(validate_custom_headers(HashMap::from([
+            ("X-Tenant".to_string(), "a".to_string()),
+            ("x-tenant".to_string(), "b".to_string()),
+        ]))
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_headers_sent_with_request() {
+        let url = serve_echo().await;
+        let client = create_client_builder().build().unwrap();
+        let per_call =
+            HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        let custom = validate_custom_headers(HashMap::from([
+            ("X-Tenant".to_string(), "school-42".to_string()),
+            (
+                "content-type".to_string(),
+                "application/vnd.seqta+json".to_string(),
+            ),
+        ]))
+        .unwrap();
+
+        let echoed = client
+            .post(&url)
+            .header("X-Request", "1")
+            .headers(request_headers(Some(&per_call), &custom))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+            .to_lowercase();
+        assert!(echoed.contains("\r\nx-tenant: school-42\r\n"));
+        assert!(echoed.contains("\r\nx-request: 1\r\n"));
+        assert!(echoed.contains("\r\ncontent-type: application/vnd.seqta+json\r\n"));
+        assert_eq!(echoed.matches("content-type:").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_not_modified() {
+        let url = serve_etag(2).await;
+        let client = create_client_builder().build().unwrap();
+
+        let cached = match send_conditional(client.get(&url), None).await.unwrap() {
+            ConditionalResponse::Fresh(response) => response,
+            other => panic!("expected a fresh response, got {:?}", other),
+        };
+        assert_eq!(cached.body, "hello");
+        assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+
+        let stale = database::CachedResponse {
+            expired: true,
+            ..cached
+        };
+        assert_eq!(
+            send_conditional(client.get(&url), Some(&stale))
+                .await
+                .unwrap(),
+            ConditionalResponse::NotModified
+        );
+
+        // A server that ignores the validators just answers 200 again
+        let url = serve_bytes("Connection: close\r\n", b"fresh".to_vec()).await;
+        match send_conditional(client.get(&url), Some(&stale))
+            .await
+            .unwrap()
+        {
+            ConditionalResponse::Fresh(response) => {
+                assert_eq!(response.body, "fresh");
+                assert_eq!(response.etag, None);
+            }
+            other => panic!("expected a fresh response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_net_error_messages_and_kinds() {
+        let err = NetError::Network("connection refused".to_string())
+            .with_context("HTTP request failed");
+        assert_eq!(err.to_string(), "HTTP request failed: connection refused");
+        assert!(err.is_retryable());
+
+        let err = NetError::Unauthorized("Authentication failed: {}".to_string());
+        assert!(err.is_unauthorized());
+        assert!(!err.is_retryable());
+
+        assert_eq!(NetError::HttpStatus(503).with_context("ignored"), NetError::HttpStatus(503));
+        let as_string: String = NetError::Offline.into();
+        assert_eq!(as_string, "Offline mode is enabled");
+
+        // A login clearing the session mid-request isn't an expiry
+        assert!(!NetError::SessionCleared.is_unauthorized());
+        assert!(NetError::SessionCleared
+            .to_string()
+            .starts_with("Session was cleared."));
+    }
+
+    #[test]
+    fn test_check_seqta_envelope() {
+        assert!(check_seqta_envelope(&json!({ "status": "200", "payload": [] })).is_ok());
+        assert!(check_seqta_envelope(&json!({ "payload": {} })).is_ok());
+        assert!(check_seqta_envelope(&json!({ "status": "failed" })).is_ok());
+        assert!(check_seqta_envelope(&json!({ "status": "401" }))
+            .unwrap_err()
+            .is_unauthorized());
+        assert!(check_seqta_envelope(&json!({ "status": 403 }))
+            .unwrap_err()
+            .is_unauthorized());
+        assert_eq!(
+            check_seqta_envelope(&json!({ "status": "500" })),
+            Err(NetError::HttpStatus(500))
+        );
+    }
+
+    #[test]
+    fn test_decode_body_charsets() {
+        assert_eq!(
+            content_type_charset("text/html; charset=\"ISO-8859-1\""),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(content_type_charset("application/json"), None);
+
+        let latin1 = b"caf\xe9";
+        let decode = |bytes: &[u8], content_type| decode_body(bytes, Some(content_type), "");
+        assert_eq!(decode(latin1, "text/plain; Charset=latin1"), "café");
+        assert_eq!(decode("café".as_bytes(), "application/json"), "café");
+        assert_eq!(
+            decode(b"\xef\xbb\xbfok", "text/plain; charset=latin1"),
+            "ok"
+        );
+        // Undecodable bytes fall back to replacement characters instead of an error
+        assert_eq!(decode_body(latin1, None, ""), "caf\u{fffd}");
+    }
+
+    #[tokio::test]
+    async fn test_gzipped_latin1_response() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"payload\":\"Ren\xe9e\"}").unwrap();
+        let url = serve_bytes(
+            "Content-Type: application/json; charset=ISO-8859-1\r\nContent-Encoding: gzip\r\n",
+            encoder.finish().unwrap(),
+        )
+        .await;
+
+        let client = create_client_builder().build().unwrap();
+        let resp = client.get(&url).send().await.unwrap();
+        assert_eq!(
+            read_response_text(resp).await.unwrap(),
+            "{\"payload\":\"Renée\"}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_chunked_response_to_file() {
+        let url = serve_chunked(&["hello ", "chunked ", "world"]).await;
+        let resp = reqwest::Client::new().get(&url).send().await.unwrap();
+        let dest = std::env::temp_dir()
+            .join(format!("desqta-download-{}", uuid::Uuid::new_v4()))
+            .join("file.txt");
+
+        let mut progress = Vec::new();
+        let cancelled = AtomicBool::new(false);
+        let written = stream_response_to_file(resp, &dest, &cancelled, |received, total| {
+            progress.push((received, total));
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(written, 19);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "hello chunked world");
+        assert_eq!(progress.first(), Some(&(0, None)));
+        assert_eq!(progress.last(), Some(&(19, None)));
+        assert!(!PathBuf::from(format!("{}.part", dest.display())).exists());
+        let _ = fs::remove_dir_all(dest.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_download_leaves_no_file() {
+        let url = serve_chunked(&["partial"]).await;
+        let resp = reqwest::Client::new().get(&url).send().await.unwrap();
+        let dest = std::env::temp_dir().join(format!("desqta-cancel-{}.txt", uuid::Uuid::new_v4()));
+
+        let cancelled = AtomicBool::new(true);
+        let result = stream_response_to_file(resp, &dest, &cancelled, |_, _| {}).await;
+
+        assert_eq!(result, Err("Download cancelled".to_string()));
+        assert!(!dest.exists());
+        assert!(!PathBuf::from(format!("{}.part", dest.display())).exists());
+    }
+
+    #[test]
+    fn test_proxy_cache_key_includes_headers() {
+        let url = "https://example.com/api";
+        let headers = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            proxy_cache_key(url, &HashMap::new()),
+            "http:https://example.com/api"
+        );
+        let alice = proxy_cache_key(url, &headers(&[("Authorization", "Bearer a")]));
+        let bob = proxy_cache_key(url, &headers(&[("Authorization", "Bearer b")]));
+        assert_ne!(alice, bob);
+        assert!(!alice.contains("Bearer"));
+        assert_eq!(
+            alice,
+            proxy_cache_key(url, &headers(&[("authorization", "Bearer a")]))
+        );
+        assert_eq!(
+            proxy_cache_key(url, &headers(&[("Accept", "a"), ("X-Id", "1")])),
+            proxy_cache_key(url, &headers(&[("X-Id", "1"), ("Accept", "a")]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_offline_mode_makes_no_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/seqta/student/load/prefs", listener.local_addr().unwrap());
+
+        set_offline_mode(true);
+        let result =
+            request_api_data(&url, RequestMethod::GET, None, None, None, false, false, None).await;
+        let proxied = proxy_request(&url, "GET".to_string(), None, None, None).await;
+        set_offline_mode(false);
+
+        assert_eq!(result, Err(NetError::Offline));
+        assert_eq!(proxied, Err("Offline mode is enabled".to_string()));
+        let accepted =
+            tokio::time::timeout(Duration::from_millis(200), listener.accept()).await;
+        assert!(accepted.is_err(), "a connection was made while offline");
+    }
 }
@@ -6,6 +6,8 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::netgrab;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Article {
     pub title: Option<String>,
@@ -38,6 +40,23 @@ fn cache_key(from: &str, domains: &str) -> String {
     format!("from={}|domains={}", from, domains)
 }
 
+/// Number of cached news responses.
+pub fn cache_len() -> usize {
+    NEWS_CACHE.lock().map(|cache| cache.len()).unwrap_or(0)
+}
+
+/// Drop all cached news responses. Returns the number of entries removed.
+pub fn clear_cache() -> usize {
+    NEWS_CACHE
+        .lock()
+        .map(|mut cache| {
+            let cleared = cache.len();
+            cache.clear();
+            cleared
+        })
+        .unwrap_or(0)
+}
+
 const CACHE_TTL: Duration = Duration::from_secs(60); // 1 minute TTL; adjust as desired
 const MAX_RATE_LIMIT_CACHEBUST_RETRIES: usize = 2; // mimic TS behavior but cap attempts
 
@@ -51,6 +70,15 @@ pub async fn get_news_australia(from: String, domains: String) -> Result<NewsApi
         }
     }
 
+    // Offline: an expired entry beats nothing
+    if netgrab::is_offline_mode() {
+        return NEWS_CACHE
+            .lock()
+            .ok()
+            .and_then(|m| m.get(&key).map(|c| c.data.clone()))
+            .ok_or_else(|| netgrab::NetError::Offline.to_string());
+    }
+
     let api_key = "17c0da766ba347c89d094449504e3080";
     let base_url = format!(
         "https://newsapi.org/v2/everything?domains={}&from={}&apiKey={}",
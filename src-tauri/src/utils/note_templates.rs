@@ -0,0 +1,278 @@
+//! Reusable note layouts (lecture notes, lab reports...). Saved templates are stored one JSON
+//! file each under `<data dir>/note_templates/`; a few built-in ones ship with the app and
+//! can't be changed. Placeholders like `{{date}}` or `{{subject}}` in a template are filled
+//! in when a note is created from it.
+
+use chrono::Utc;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+use super::data_dir;
+use super::notes_filesystem::{self, Note};
+use super::sanitization;
+use super::timezone;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteTemplate {
+    pub name: String,
+    pub content_html: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Ships with the app, can't be overwritten or deleted
+    #[serde(default)]
+    pub built_in: bool,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// (name, content, tags) of the templates that ship with the app.
+const BUILT_IN_TEMPLATES: &[(&str, &str, &[&str])] = &[
+    (
+        "Lecture notes",
+        "<h1>{{subject}}</h1>\
+         <p><strong>Date:</strong> {{date}}</p>\
+         <h2>Key points</h2><ul><li><p></p></li></ul>\
+         <h2>Questions</h2><ul><li><p></p></li></ul>\
+         <h2>Summary</h2><p></p>",
+        &["lecture"],
+    ),
+    (
+        "Lab report",
+        "<h1>{{subject}} lab report</h1>\
+         <p><strong>Date:</strong> {{date}}</p>\
+         <h2>Aim</h2><p></p>\
+         <h2>Hypothesis</h2><p></p>\
+         <h2>Materials</h2><ul><li><p></p></li></ul>\
+         <h2>Method</h2><ol><li><p></p></li></ol>\
+         <h2>Results</h2><p></p>\
+         <h2>Discussion</h2><p></p>\
+         <h2>Conclusion</h2><p></p>",
+        &["lab-report"],
+    ),
+];
+
+fn built_in_templates() -> Vec<NoteTemplate> {
+    BUILT_IN_TEMPLATES
+        .iter()
+        .map(|(name, content, tags)| NoteTemplate {
+            name: name.to_string(),
+            content_html: content.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            built_in: true,
+            created_at: None,
+            updated_at: None,
+        })
+        .collect()
+}
+
+fn is_built_in(name: &str) -> bool {
+    BUILT_IN_TEMPLATES
+        .iter()
+        .any(|(built_in, _, _)| built_in.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Location: `<data dir>/note_templates/`
+fn templates_dir() -> Result<PathBuf, String> {
+    Ok(data_dir::app_data_dir()?.join("note_templates"))
+}
+
+/// File a template is saved in. Names are looked up ignoring case, so the file name is
+/// lowercased to keep "Revision" and "revision" in one file on case-sensitive filesystems.
+fn template_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!(
+        "{}.json",
+        sanitization::sanitize_path_component(&name.trim().to_lowercase())
+    ))
+}
+
+/// Saved templates in `dir`, sorted by name. Files that don't parse are skipped.
+fn load_templates(dir: &Path) -> Vec<NoteTemplate> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<NoteTemplate> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .filter_map(|json| serde_json::from_str::<NoteTemplate>(&json).ok())
+        .map(|template| NoteTemplate {
+            built_in: false,
+            ..template
+        })
+        .collect();
+    templates.sort_by_key(|t| t.name.to_lowercase());
+    templates
+}
+
+fn find_template(dir: &Path, name: &str) -> Option<NoteTemplate> {
+    let name = name.trim();
+    built_in_templates()
+        .into_iter()
+        .chain(load_templates(dir))
+        .find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_]+)\s*\}\}").unwrap())
+}
+
+/// Replace `{{name}}` placeholders with HTML-escaped `variables`. Placeholders without a
+/// value are left as they are so the student can see what still needs filling in.
+fn fill_placeholders(template: &str, variables: &HashMap<String, String>) -> String {
+    placeholder_regex()
+        .replace_all(template, |caps: &Captures| match variables.get(&caps[1]) {
+            Some(value) => sanitization::escape_html(value),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// `variables` plus the ones every template can use: `date` (today at the school).
+fn template_variables(variables: Option<HashMap<String, String>>) -> HashMap<String, String> {
+    let mut all = HashMap::from([(
+        "date".to_string(),
+        timezone::today().format("%-d %B %Y").to_string(),
+    )]);
+    all.extend(
+        variables
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, value)| !value.trim().is_empty()),
+    );
+    all
+}
+
+/// Built-in templates followed by the saved ones.
+#[tauri::command]
+pub fn list_note_templates() -> Result<Vec<NoteTemplate>, String> {
+    let mut templates = built_in_templates();
+    templates.extend(load_templates(&templates_dir()?));
+    Ok(templates)
+}
+
+/// Save a template, replacing any saved template with the same name.
+#[tauri::command]
+pub fn save_note_template(
+    name: String,
+    content_html: String,
+    tags: Vec<String>,
+) -> Result<NoteTemplate, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    if is_built_in(&name) {
+        return Err(format!("\"{}\" is a built-in template", name));
+    }
+    let dir = templates_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create templates directory: {}", e))?;
+    let path = template_path(&dir, &name);
+
+    let now = Utc::now().to_rfc3339();
+    let created_at = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<NoteTemplate>(&json).ok())
+        .and_then(|existing| existing.created_at);
+    let template = NoteTemplate {
+        name,
+        content_html,
+        tags: tags
+            .into_iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        built_in: false,
+        created_at: created_at.or_else(|| Some(now.clone())),
+        updated_at: Some(now),
+    };
+    let json = serde_json::to_string_pretty(&template)
+        .map_err(|e| format!("Failed to serialize template: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write template: {}", e))?;
+    Ok(template)
+}
+
+/// Delete a saved template. Returns false if there was none with that name.
+#[tauri::command]
+pub fn delete_note_template(name: String) -> Result<bool, String> {
+    if is_built_in(&name) {
+        return Err(format!("\"{}\" is a built-in template", name.trim()));
+    }
+    let path = template_path(&templates_dir()?, name.trim());
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete template: {}", e))?;
+    Ok(true)
+}
+
+/// Create a note in `folder` (the default folder when `None`) from the template called
+/// `name`, filling its placeholders from `variables`. A `title` variable sets the note's
+/// title, otherwise it's the template name and date.
+#[tauri::command]
+pub fn create_note_from_template(
+    app: AppHandle,
+    name: String,
+    folder: Option<String>,
+    variables: Option<HashMap<String, String>>,
+) -> Result<Note, String> {
+    let template = find_template(&templates_dir()?, &name)
+        .ok_or_else(|| format!("Template not found: {}", name.trim()))?;
+    let variables = template_variables(variables);
+    let title = match variables.get("title") {
+        Some(title) => title.trim().to_string(),
+        None => format!("{} - {}", template.name, variables["date"]),
+    };
+    notes_filesystem::create_note(
+        &app,
+        folder,
+        &title,
+        fill_placeholders(&template.content_html, &variables),
+        template.tags,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        let variables = HashMap::from([
+            ("date".to_string(), "1 May 2024".to_string()),
+            ("subject".to_string(), "Physics & Chemistry".to_string()),
+        ]);
+        assert_eq!(
+            fill_placeholders(
+                "<h1>{{subject}}</h1><p>{{ date }}</p><p>{{topic}}</p>",
+                &variables
+            ),
+            "<h1>Physics &amp; Chemistry</h1><p>1 May 2024</p><p>{{topic}}</p>"
+        );
+        // Not placeholders
+        assert_eq!(
+            fill_placeholders("{{}} {date} {{sub ject}}", &variables),
+            "{{}} {date} {{sub ject}}"
+        );
+
+        let lecture = find_template(Path::new("/nonexistent"), "lecture NOTES").unwrap();
+        assert!(lecture.built_in);
+        let date_only = HashMap::from([("date".to_string(), "1 May 2024".to_string())]);
+        let filled = fill_placeholders(&lecture.content_html, &date_only);
+        assert!(filled.contains("{{subject}}"));
+        assert!(!filled.contains("{{date}}"));
+        assert!(is_built_in(" Lab report "));
+        assert_eq!(
+            template_path(Path::new("/templates"), "Revision"),
+            template_path(Path::new("/templates"), "revision ")
+        );
+    }
+}
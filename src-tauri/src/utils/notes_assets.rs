@@ -0,0 +1,340 @@
+//! Files attached to notes from elsewhere on disk, either copied next to the note's images or
+//! linked in place so large media isn't duplicated.
+//!
+//! Note content only ever holds a `desqta-asset://<note id>/<asset id>` reference. The path
+//! behind it lives in the note's `linked_assets.json` and can only be added through
+//! `link_external_file`, so a path typed or pasted into a note can never be resolved. Nothing
+//! inside DesQTA's data folder other than the notes themselves can be attached or resolved.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::data_dir;
+use super::notes_filesystem;
+use super::sanitization;
+
+const REFERENCE_PREFIX: &str = "desqta-asset://";
+const REGISTRY_FILE: &str = "linked_assets.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetLinkMode {
+    /// Copied into the note's directory; survives the original being moved or deleted
+    Copy,
+    /// Used from where it is; breaks if the original is moved or deleted
+    Link,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetReference {
+    pub id: String,
+    pub note_id: String,
+    /// What goes into the note content
+    pub reference: String,
+    pub mode: AssetLinkMode,
+    pub file_name: String,
+    /// Absolute path for links, file name inside the note's directory for copies
+    pub path: String,
+    pub size: u64,
+    pub linked_at: String,
+    /// The file was missing the last time the reference was resolved
+    #[serde(default)]
+    pub broken: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedAsset {
+    pub asset: AssetReference,
+    /// Current absolute path, `None` when the file is gone
+    pub path: Option<String>,
+}
+
+fn registry_path(note_dir: &Path) -> PathBuf {
+    note_dir.join(REGISTRY_FILE)
+}
+
+fn load_registry(note_dir: &Path) -> Result<Vec<AssetReference>, String> {
+    let path = registry_path(note_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read linked assets: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse linked assets: {}", e))
+}
+
+fn save_registry(note_dir: &Path, assets: &[AssetReference]) -> Result<(), String> {
+    fs::create_dir_all(note_dir)
+        .map_err(|e| format!("Failed to create note assets directory: {}", e))?;
+    let json = serde_json::to_string_pretty(assets)
+        .map_err(|e| format!("Failed to serialize linked assets: {}", e))?;
+    fs::write(registry_path(note_dir), json)
+        .map_err(|e| format!("Failed to save linked assets: {}", e))
+}
+
+/// Split a `desqta-asset://<note id>/<asset id>` reference.
+fn parse_reference(reference: &str) -> Result<(&str, &str), String> {
+    reference
+        .trim()
+        .strip_prefix(REFERENCE_PREFIX)
+        .and_then(|rest| rest.split_once('/'))
+        .filter(|(note_id, asset_id)| !note_id.is_empty() && !asset_id.is_empty())
+        .ok_or_else(|| format!("Not a note asset reference: {}", reference))
+}
+
+/// Whether `path` (already canonical) is inside `dir`.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    dir.canonicalize()
+        .map_or(false, |dir| path.starts_with(dir))
+}
+
+/// Whether `path` (already canonical) is in the app's private data (sessions, settings and
+/// other profiles live there). The notes folder inside it is fine.
+fn is_private(path: &Path, notes_dir: &Path, protected_dir: &Path) -> bool {
+    is_within(path, protected_dir) && !is_within(path, notes_dir)
+}
+
+/// Check a file the user picked: it must exist, be a regular file and not be private app
+/// data.
+fn validate_source(
+    source_path: &str,
+    notes_dir: &Path,
+    protected_dir: &Path,
+) -> Result<PathBuf, String> {
+    let source = Path::new(source_path.trim());
+    if !source.is_absolute() {
+        return Err("Pick a file to attach".to_string());
+    }
+    let source = source
+        .canonicalize()
+        .map_err(|_| format!("File not found: {}", source_path))?;
+    if !source.is_file() {
+        return Err("Only files can be attached to a note".to_string());
+    }
+    if is_private(&source, notes_dir, protected_dir) {
+        return Err("Files inside DesQTA's data folder can't be attached".to_string());
+    }
+    Ok(source)
+}
+
+fn current_path(note_dir: &Path, asset: &AssetReference) -> PathBuf {
+    match asset.mode {
+        AssetLinkMode::Copy => note_dir.join(&asset.path),
+        AssetLinkMode::Link => PathBuf::from(&asset.path),
+    }
+}
+
+/// Whether an asset's file is still there. A link that now resolves into private app data
+/// (e.g. an edited registry or a swapped-in symlink) counts as missing.
+fn asset_available(
+    note_dir: &Path,
+    notes_dir: &Path,
+    protected_dir: &Path,
+    asset: &AssetReference,
+) -> bool {
+    let path = current_path(note_dir, asset);
+    match asset.mode {
+        AssetLinkMode::Copy => path.is_file(),
+        AssetLinkMode::Link => path.canonicalize().map_or(false, |path| {
+            path.is_file() && !is_private(&path, notes_dir, protected_dir)
+        }),
+    }
+}
+
+/// Re-check every asset of a note, persisting any change to `broken`.
+fn refresh_assets(
+    note_dir: &Path,
+    notes_dir: &Path,
+    protected_dir: &Path,
+) -> Result<Vec<AssetReference>, String> {
+    let mut assets = load_registry(note_dir)?;
+    let mut changed = false;
+    for asset in &mut assets {
+        let broken = !asset_available(note_dir, notes_dir, protected_dir, asset);
+        changed |= asset.broken != broken;
+        asset.broken = broken;
+    }
+    if changed {
+        save_registry(note_dir, &assets)?;
+    }
+    Ok(assets)
+}
+
+fn link_file(
+    note_dir: &Path,
+    note_id: &str,
+    source_path: &str,
+    mode: AssetLinkMode,
+    notes_dir: &Path,
+    protected_dir: &Path,
+) -> Result<AssetReference, String> {
+    let source = validate_source(source_path, notes_dir, protected_dir)?;
+    let size = fs::metadata(&source)
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
+    let file_name = source
+        .file_name()
+        .map(|name| sanitization::sanitize_filename(&name.to_string_lossy()))
+        .unwrap_or_else(|| "attachment".to_string());
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let path = match mode {
+        AssetLinkMode::Copy => {
+            fs::create_dir_all(note_dir)
+                .map_err(|e| format!("Failed to create note assets directory: {}", e))?;
+            let stored_name = format!("{}_{}", id, file_name);
+            fs::copy(&source, note_dir.join(&stored_name))
+                .map_err(|e| format!("Failed to copy file: {}", e))?;
+            stored_name
+        }
+        AssetLinkMode::Link => source.to_string_lossy().to_string(),
+    };
+
+    let asset = AssetReference {
+        reference: format!("{}{}/{}", REFERENCE_PREFIX, note_id, id),
+        id,
+        note_id: note_id.to_string(),
+        mode,
+        file_name,
+        path,
+        size,
+        linked_at: chrono::Utc::now().to_rfc3339(),
+        broken: false,
+    };
+    let mut assets = load_registry(note_dir)?;
+    assets.push(asset.clone());
+    save_registry(note_dir, &assets)?;
+    Ok(asset)
+}
+
+fn resolve(
+    note_dir: &Path,
+    notes_dir: &Path,
+    protected_dir: &Path,
+    asset_id: &str,
+) -> Result<ResolvedAsset, String> {
+    let asset = refresh_assets(note_dir, notes_dir, protected_dir)?
+        .into_iter()
+        .find(|asset| asset.id == asset_id)
+        .ok_or_else(|| "This attachment isn't linked to the note".to_string())?;
+    let path =
+        (!asset.broken).then(|| current_path(note_dir, &asset).to_string_lossy().to_string());
+    Ok(ResolvedAsset { asset, path })
+}
+
+fn note_assets_dir(app: &AppHandle, note_id: &str) -> Result<PathBuf, String> {
+    if note_id.trim().is_empty() {
+        return Err("Note ID is required".to_string());
+    }
+    Ok(notes_filesystem::get_notes_images_dir(app)?
+        .join(sanitization::sanitize_path_component(note_id)))
+}
+
+/// Attach a file the user picked in a file dialog to a note, either copying it into the
+/// note's directory or linking it in place. Returns the reference to put in the note content.
+#[tauri::command]
+pub fn link_external_file(
+    app: AppHandle,
+    note_id: String,
+    source_path: String,
+    mode: AssetLinkMode,
+) -> Result<AssetReference, String> {
+    let note_dir = note_assets_dir(&app, &note_id)?;
+    link_file(
+        &note_dir,
+        &note_id,
+        &source_path,
+        mode,
+        &notes_filesystem::notes_root(&app)?,
+        &data_dir::app_data_dir()?,
+    )
+}
+
+/// Current path of a `desqta-asset://` reference. A linked file that has been moved or deleted
+/// comes back flagged `broken` with no path.
+#[tauri::command]
+pub fn resolve_note_asset(app: AppHandle, reference: String) -> Result<ResolvedAsset, String> {
+    let (note_id, asset_id) = parse_reference(&reference)?;
+    resolve(
+        &note_assets_dir(&app, note_id)?,
+        &notes_filesystem::notes_root(&app)?,
+        &data_dir::app_data_dir()?,
+        asset_id,
+    )
+}
+
+/// Attachments of a note whose file can no longer be found.
+#[tauri::command]
+pub fn find_broken_asset_links(
+    app: AppHandle,
+    note_id: String,
+) -> Result<Vec<AssetReference>, String> {
+    let assets = refresh_assets(
+        &note_assets_dir(&app, &note_id)?,
+        &notes_filesystem::notes_root(&app)?,
+        &data_dir::app_data_dir()?,
+    )?;
+    Ok(assets.into_iter().filter(|asset| asset.broken).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linked_assets_resolve_and_break() {
+        let root = std::env::temp_dir().join(format!("desqta-assets-{}", uuid::Uuid::new_v4()));
+        let data = root.join("data");
+        let notes = data.join("notes");
+        let note_dir = data.join("note_contents").join("note-1");
+        let media = root.join("media");
+        fs::create_dir_all(&media).unwrap();
+        fs::create_dir_all(&notes).unwrap();
+        fs::create_dir_all(&note_dir).unwrap();
+        let video = media.join("lab.mp4");
+        fs::write(&video, b"video").unwrap();
+        let video_path = video.to_string_lossy().to_string();
+        let link = |path: &str, mode| link_file(&note_dir, "note-1", path, mode, &notes, &data);
+        let resolve_asset = |asset_id: &str| resolve(&note_dir, &notes, &data, asset_id);
+
+        let linked = link(&video_path, AssetLinkMode::Link).unwrap();
+        let copied = link(&video_path, AssetLinkMode::Copy).unwrap();
+        assert_eq!(linked.size, 5);
+        assert_eq!(
+            parse_reference(&linked.reference).unwrap(),
+            ("note-1", linked.id.as_str())
+        );
+        assert!(resolve_asset(&linked.id).unwrap().path.is_some());
+
+        // Moving the original breaks the link but not the copy
+        fs::rename(&video, media.join("moved.mp4")).unwrap();
+        let resolved = resolve_asset(&linked.id).unwrap();
+        assert!(resolved.asset.broken);
+        assert!(resolved.path.is_none());
+        assert!(resolve_asset(&copied.id).unwrap().path.is_some());
+        let broken: Vec<String> = refresh_assets(&note_dir, &notes, &data)
+            .unwrap()
+            .into_iter()
+            .filter(|asset| asset.broken)
+            .map(|asset| asset.id)
+            .collect();
+        assert_eq!(broken, vec![linked.id.clone()]);
+
+        // The notes folder can be linked from, the rest of the data directory can't
+        let sketch = notes.join("sketch.png");
+        fs::write(&sketch, b"png").unwrap();
+        assert!(link(&sketch.to_string_lossy(), AssetLinkMode::Link).is_ok());
+        let secret = data.join("session.json");
+        fs::write(&secret, b"{}").unwrap();
+        let secret = secret.to_string_lossy().to_string();
+        assert!(link(&secret, AssetLinkMode::Link).is_err());
+        assert!(link(&secret, AssetLinkMode::Copy).is_err());
+        assert!(link("lab.mp4", AssetLinkMode::Link).is_err());
+        assert!(resolve_asset("not-linked").is_err());
+        assert!(parse_reference("/etc/passwd").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,249 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::logger;
+use crate::notes_filesystem::{self, Note};
+use crate::settings::Settings;
+
+/// Payload of the `autosave-complete` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutosaveComplete {
+    pub note_id: String,
+    /// False when the note matched what was already on disk and nothing was written
+    pub saved: bool,
+    pub saved_at: Option<String>,
+}
+
+/// Edits waiting out their debounce interval.
+struct AutosaveQueue {
+    generation: AtomicU64,
+    /// Note ID -> (generation of the latest edit, latest edit)
+    pending: Mutex<HashMap<String, (u64, Note)>>,
+}
+
+impl AutosaveQueue {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replace any queued edit for the note with this one. Returns the edit's generation.
+    fn queue(&self, note: Note) -> u64 {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(note.id.clone(), (generation, note));
+        generation
+    }
+
+    /// Take the queued edit for a note, unless a newer edit has replaced it since.
+    fn take_if_current(&self, note_id: &str, generation: u64) -> Option<Note> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(note_id) {
+            Some((queued, _)) if *queued == generation => pending.remove(note_id).map(|(_, n)| n),
+            _ => None,
+        }
+    }
+
+    fn take_all(&self) -> Vec<Note> {
+        self.pending
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, (_, note))| note)
+            .collect()
+    }
+}
+
+/// Write the note with `write` if its content differs from the copy on disk, loaded with
+/// `load_saved`. Comparing against the disk rather than the last autosave catches saves made
+/// any other way (the editor, an import, a restore). Returns when it was written.
+fn write_if_changed(
+    mut note: Note,
+    load_saved: impl FnOnce(&str) -> Option<Note>,
+    write: impl FnOnce(Note) -> Result<(), String>,
+) -> Result<Option<String>, String> {
+    if load_saved(&note.id).is_some_and(|saved| content_hash(&saved) == content_hash(&note)) {
+        return Ok(None);
+    }
+    let saved_at = chrono::Utc::now().to_rfc3339();
+    note.metadata.last_auto_save = Some(saved_at.clone());
+    write(note)?;
+    Ok(Some(saved_at))
+}
+
+fn queue() -> &'static AutosaveQueue {
+    static QUEUE: OnceLock<AutosaveQueue> = OnceLock::new();
+    QUEUE.get_or_init(AutosaveQueue::new)
+}
+
+/// Hash of the parts of a note the user edits; timestamps and metadata are left out so
+/// re-saving an unchanged note doesn't count as a change.
+fn content_hash(note: &Note) -> String {
+    let content = serde_json::json!({
+        "title": note.title,
+        "content": note.content,
+        "folder_path": note.folder_path,
+        "tags": note.tags,
+        "seqta_references": note.seqta_references,
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(content.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Persist a note taken off the queue and emit `autosave-complete` for it.
+fn persist(app: &AppHandle, note: Note) -> Result<bool, String> {
+    let note_id = note.id.clone();
+    let saved_at = write_if_changed(
+        note,
+        |id| notes_filesystem::get_note_filesystem(app.clone(), id.to_string()).ok()?,
+        |note| notes_filesystem::save_note_filesystem(app.clone(), note),
+    )?;
+    let saved = saved_at.is_some();
+    let _ = app.emit(
+        "autosave-complete",
+        AutosaveComplete {
+            note_id,
+            saved,
+            saved_at,
+        },
+    );
+    Ok(saved)
+}
+
+/// Queue an edit and write it once the note has gone `interval` without another edit.
+fn schedule<F>(queue: &'static AutosaveQueue, note: Note, interval: Duration, on_due: F)
+where
+    F: FnOnce(Note) + Send + 'static,
+{
+    let note_id = note.id.clone();
+    let generation = queue.queue(note);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(interval).await;
+        if let Some(note) = queue.take_if_current(&note_id, generation) {
+            on_due(note);
+        }
+    });
+}
+
+/// Save a note after `notes_autosave_interval_ms` without further edits to it. Each call
+/// replaces the previous pending edit of the same note, and the write is skipped when the
+/// content matches what's on disk. `autosave-complete` is emitted once the note is handled.
+#[tauri::command]
+pub fn autosave_note(app: AppHandle, note: Note) -> Result<(), String> {
+    if note.id.trim().is_empty() {
+        return Err("Note ID is required".to_string());
+    }
+    let interval = Duration::from_millis(Settings::load().notes_autosave_interval_ms);
+    schedule(queue(), note, interval, move |note| {
+        let note_id = note.id.clone();
+        if let Err(e) = persist(&app, note) {
+            if let Some(logger) = logger::get_logger() {
+                let _ = logger.log(
+                    logger::LogLevel::ERROR,
+                    "notes_autosave",
+                    "autosave_note",
+                    &format!("Autosave failed: {}", e),
+                    serde_json::json!({ "note_id": note_id }),
+                );
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Write every pending autosave now, without waiting for its interval. Called on shutdown so
+/// no edits are lost. Returns how many notes were written.
+#[tauri::command]
+pub fn flush_pending_autosaves(app: AppHandle) -> Result<usize, String> {
+    let mut written = 0;
+    let mut errors = Vec::new();
+    for note in queue().take_all() {
+        let note_id = note.id.clone();
+        match persist(&app, note) {
+            Ok(true) => written += 1,
+            Ok(false) => {}
+            Err(e) => errors.push(format!("{}: {}", note_id, e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(written)
+    } else {
+        Err(format!("Failed to save notes: {}", errors.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notes_filesystem::NoteMetadata;
+    use std::sync::Arc;
+
+    fn note(content: &str) -> Note {
+        Note {
+            id: "note-1".to_string(),
+            title: "Chemistry".to_string(),
+            content: content.to_string(),
+            folder_path: vec![],
+            tags: vec![],
+            seqta_references: vec![],
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            last_accessed: "2024-01-01T00:00:00Z".to_string(),
+            metadata: NoteMetadata {
+                word_count: 0,
+                character_count: 0,
+                reading_time: 0,
+                last_auto_save: None,
+                version: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_edits_within_interval_write_once() {
+        let queue: &'static AutosaveQueue = Box::leak(Box::new(AutosaveQueue::new()));
+        let writes: Arc<Mutex<Vec<Note>>> = Arc::default();
+        let interval = Duration::from_millis(50);
+        let edit = |content: &str| {
+            let writes = writes.clone();
+            schedule(queue, note(content), interval, move |note| {
+                let saved = writes.lock().unwrap().last().cloned();
+                write_if_changed(
+                    note,
+                    |_| saved,
+                    |note| {
+                        writes.lock().unwrap().push(note);
+                        Ok(())
+                    },
+                )
+                .unwrap();
+            });
+        };
+
+        edit("<p>H</p>");
+        edit("<p>H2</p>");
+        edit("<p>H2O</p>");
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        {
+            let writes = writes.lock().unwrap();
+            assert_eq!(writes.len(), 1);
+            assert_eq!(writes[0].content, "<p>H2O</p>");
+            assert!(writes[0].metadata.last_auto_save.is_some());
+        }
+
+        // Saving the same content again doesn't write
+        edit("<p>H2O</p>");
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(writes.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,165 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use crate::logger;
+use crate::notes_filesystem;
+use crate::settings::Settings;
+
+/// Bumped whenever the backup scheduler is started or stopped; a running loop exits as soon
+/// as it sees a generation other than its own, so at most one scheduler is ever active.
+static BACKUP_SCHEDULER_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Lets the app finish starting before an overdue backup runs.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// Payload of the `backup-created` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupCreated {
+    pub path: String,
+    /// "scheduled" or "shutdown"
+    pub reason: String,
+    /// Old backups deleted to stay within `notes_max_backups`
+    pub pruned: usize,
+}
+
+/// How long to wait before the first backup: whatever is left of the interval since the last
+/// one, so short sessions still get backed up once the interval has passed.
+fn first_backup_delay(
+    last_backup: Option<SystemTime>,
+    now: SystemTime,
+    interval: Duration,
+) -> Duration {
+    let elapsed = last_backup
+        .and_then(|last| now.duration_since(last).ok())
+        .unwrap_or(interval);
+    interval.saturating_sub(elapsed)
+}
+
+/// Back up the notes if they changed since the last backup, prune old backups and emit
+/// `backup-created`. Returns the event payload when a backup was written.
+fn run_backup(app: &AppHandle, reason: &str) -> Result<Option<BackupCreated>, String> {
+    let settings = Settings::load();
+    let Some(path) = notes_filesystem::create_notes_backup(app, true)? else {
+        return Ok(None);
+    };
+    let pruned = notes_filesystem::prune_notes_backups(settings.notes_max_backups as usize)?;
+
+    let created = BackupCreated {
+        path: path.to_string_lossy().to_string(),
+        reason: reason.to_string(),
+        pruned,
+    };
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "notes_backup",
+            "run_backup",
+            "Created automatic notes backup",
+            serde_json::json!({ "reason": reason, "pruned": pruned }),
+        );
+    }
+    let _ = app.emit("backup-created", created.clone());
+    Ok(Some(created))
+}
+
+/// Start (or restart) the backup scheduler using the interval from settings.
+pub fn start(app: AppHandle) {
+    let generation = BACKUP_SCHEDULER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let interval_hours = Settings::load().notes_backup_interval_hours.max(1);
+
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_secs(interval_hours * 60 * 60);
+        let mut delay = first_backup_delay(
+            notes_filesystem::last_notes_backup_time(),
+            SystemTime::now(),
+            interval,
+        )
+        .max(STARTUP_DELAY);
+        loop {
+            tokio::time::sleep(delay).await;
+            if BACKUP_SCHEDULER_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            match run_backup(&app, "scheduled") {
+                Ok(Some(created)) => println!("[BACKUP] Notes backed up to {}", created.path),
+                Ok(None) => println!("[BACKUP] Notes unchanged, skipping backup"),
+                Err(e) => eprintln!("[BACKUP] Automatic backup failed: {}", e),
+            }
+            delay = interval;
+        }
+    });
+}
+
+/// Stop the backup scheduler, if one is running.
+pub fn stop() {
+    BACKUP_SCHEDULER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Start the scheduler at launch if automatic backups are enabled.
+pub fn start_if_enabled(app: AppHandle) {
+    if Settings::load().notes_backup_enabled {
+        start(app);
+    }
+}
+
+/// Final backup as the app exits, when automatic backups are enabled and the notes changed.
+pub fn backup_on_exit(app: &AppHandle) {
+    if !Settings::load().notes_backup_enabled {
+        return;
+    }
+    if let Err(e) = run_backup(app, "shutdown") {
+        eprintln!("[BACKUP] Backup on exit failed: {}", e);
+    }
+}
+
+/// Turn automatic notes backups on or off (when `enabled` is given), set the interval between
+/// them and persist both. Restarts the scheduler so the new interval applies straight away.
+#[tauri::command]
+pub fn set_backup_schedule(
+    app: AppHandle,
+    interval_hours: u64,
+    enabled: Option<bool>,
+) -> Result<(), String> {
+    if interval_hours == 0 {
+        return Err("Backup interval must be at least one hour".to_string());
+    }
+    let mut settings = Settings::load();
+    settings.notes_backup_interval_hours = interval_hours;
+    if let Some(enabled) = enabled {
+        settings.notes_backup_enabled = enabled;
+    }
+    settings.save().map_err(|e| e.to_string())?;
+
+    if settings.notes_backup_enabled {
+        start(app);
+    } else {
+        stop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_backup_delay() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let now = SystemTime::now();
+        assert_eq!(first_backup_delay(None, now, day), Duration::ZERO);
+        assert_eq!(
+            first_backup_delay(Some(now - Duration::from_secs(6 * 60 * 60)), now, day),
+            Duration::from_secs(18 * 60 * 60)
+        );
+        assert_eq!(
+            first_backup_delay(Some(now - 2 * day), now, day),
+            Duration::ZERO
+        );
+        // A backup timestamp in the future (clock change) doesn't postpone backups
+        assert_eq!(
+            first_backup_delay(Some(now + day), now, day),
+            Duration::ZERO
+        );
+    }
+}
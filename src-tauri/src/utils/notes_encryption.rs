@@ -0,0 +1,618 @@
+//! Opt-in encryption of note content at rest. The key is derived from a passphrase with
+//! Argon2id and only held in memory while the notes are unlocked; a sentinel file beside the
+//! notes folder records that encryption is on, along with the salt and a check value used to
+//! recognise the right passphrase.
+
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use zeroize::Zeroizing;
+
+use crate::logger;
+use crate::notes_filesystem;
+
+/// Prefix of encrypted note content: `desqta-enc:v1:<key id>:<base64 nonce + ciphertext>`.
+const ENCRYPTED_PREFIX: &str = "desqta-enc:v1:";
+/// Lives in the profile folder rather than `notes/` so restoring a backup can't remove it.
+const SENTINEL_FILE: &str = "notes_encryption.json";
+const SALT_LEN: usize = 16;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+const CHECK_PLAINTEXT: &[u8] = b"desqta-notes";
+const CHECK_AAD: &[u8] = b"passphrase-check";
+/// A previous key sealed inside the sentinel is bound to this
+const PREVIOUS_KEY_AAD: &[u8] = b"previous-key";
+/// Sealed backups are bound to this in place of a note ID
+const BACKUP_AAD: &str = "notes-backup";
+pub const LOCKED_MESSAGE: &str = "Your notes are encrypted. Unlock them with your passphrase.";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Sentinel {
+    version: u32,
+    kdf: String,
+    /// Base64 Argon2 salt
+    salt: String,
+    /// `CHECK_PLAINTEXT` sealed with the key, to tell a wrong passphrase from a right one
+    check: String,
+    /// Set while a passphrase change is being applied: notes and backups not yet re-encrypted
+    /// still need the old key, so it's kept here sealed with the new one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous_key: Option<PreviousKey>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PreviousKey {
+    id: String,
+    /// Base64 nonce + key bytes sealed with the current key
+    key: String,
+}
+
+struct NotesKey {
+    /// Short fingerprint of the salt, stored with each note to spot a stale key
+    id: String,
+    bytes: Zeroizing<[u8; 32]>,
+    key: LessSafeKey,
+}
+
+/// Encryption state of the active profile's notes.
+struct EncryptionState {
+    notes_dir: Option<PathBuf>,
+    enabled: bool,
+    key: Option<NotesKey>,
+    /// The key before an unfinished passphrase change
+    previous_key: Option<NotesKey>,
+}
+
+static STATE: Mutex<EncryptionState> = Mutex::new(EncryptionState {
+    notes_dir: None,
+    enabled: false,
+    key: None,
+    previous_key: None,
+});
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotesEncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+fn sentinel_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.parent().unwrap_or(notes_dir).join(SENTINEL_FILE)
+}
+
+fn read_sentinel(notes_dir: &Path) -> Result<Option<Sentinel>, String> {
+    let path = sentinel_path(notes_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read notes encryption settings: {}", e))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Notes encryption settings are damaged: {}", e))
+}
+
+fn write_sentinel(notes_dir: &Path, sentinel: &Sentinel) -> Result<(), String> {
+    let path = sentinel_path(notes_dir);
+    let json = serde_json::to_string_pretty(sentinel)
+        .map_err(|e| format!("Failed to serialize notes encryption settings: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)
+        .map_err(|e| format!("Failed to write notes encryption settings: {}", e))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to save notes encryption settings: {}", e))
+}
+
+fn key_from_bytes(id: String, bytes: Zeroizing<[u8; 32]>) -> Result<NotesKey, String> {
+    let key = UnboundKey::new(&AES_256_GCM, &bytes[..])
+        .map_err(|_| "Failed to create encryption key".to_string())?;
+    Ok(NotesKey {
+        id,
+        bytes,
+        key: LessSafeKey::new(key),
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<NotesKey, String> {
+    let mut key_bytes = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key_bytes[..])
+        .map_err(|e| format!("Failed to derive encryption key: {}", e))?;
+    key_from_bytes(hex::encode(&Sha256::digest(salt)[..4]), key_bytes)
+}
+
+fn seal(key: &NotesKey, plaintext: &[u8], aad: &[u8]) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "Failed to generate random bytes".to_string())?;
+    let mut data = plaintext.to_vec();
+    key.key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut data,
+        )
+        .map_err(|_| "Failed to encrypt note".to_string())?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&data);
+    Ok(general_purpose::STANDARD.encode(payload))
+}
+
+fn open(key: &NotesKey, sealed: &str, aad: &[u8]) -> Option<Vec<u8>> {
+    let mut payload = general_purpose::STANDARD.decode(sealed).ok()?;
+    if payload.len() <= NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = payload.split_at_mut(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let plain = key
+        .key
+        .open_in_place(nonce, Aad::from(aad), ciphertext)
+        .ok()?;
+    Some(plain.to_vec())
+}
+
+/// The key an unfinished passphrase change replaced, opened with the current `key`.
+fn previous_key(sentinel: &Sentinel, key: &NotesKey) -> Result<Option<NotesKey>, String> {
+    let Some(previous) = &sentinel.previous_key else {
+        return Ok(None);
+    };
+    let bytes = open(key, &previous.key, PREVIOUS_KEY_AAD)
+        .map(Zeroizing::new)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| "Notes encryption settings are damaged (previous key)".to_string())?;
+    key_from_bytes(previous.id.clone(), Zeroizing::new(bytes)).map(Some)
+}
+
+/// Derive the key for `passphrase` and check it against the sentinel.
+fn unlock_key(sentinel: &Sentinel, passphrase: &str) -> Result<NotesKey, String> {
+    let salt = general_purpose::STANDARD
+        .decode(&sentinel.salt)
+        .map_err(|_| "Notes encryption settings are damaged (invalid salt)".to_string())?;
+    let key = derive_key(passphrase, &salt)?;
+    match open(&key, &sentinel.check, CHECK_AAD) {
+        Some(check) if check == CHECK_PLAINTEXT => Ok(key),
+        _ => Err("Wrong passphrase. Your notes are still locked.".to_string()),
+    }
+}
+
+/// A fresh salt and key for `passphrase`, with the sentinel that records them.
+fn new_key(passphrase: &str) -> Result<(Sentinel, NotesKey), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_CHARS
+        ));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| "Failed to generate random bytes".to_string())?;
+    let key = derive_key(passphrase, &salt)?;
+    let sentinel = Sentinel {
+        version: 1,
+        kdf: "argon2id".to_string(),
+        salt: general_purpose::STANDARD.encode(salt),
+        check: seal(&key, CHECK_PLAINTEXT, CHECK_AAD)?,
+        previous_key: None,
+    };
+    Ok((sentinel, key))
+}
+
+fn state() -> std::sync::MutexGuard<'static, EncryptionState> {
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Point the state at `notes_dir`, forgetting the key if the active profile changed.
+pub fn sync(notes_dir: &Path) {
+    let enabled = sentinel_path(notes_dir).exists();
+    let mut state = state();
+    if state.notes_dir.as_deref() != Some(notes_dir) {
+        state.notes_dir = Some(notes_dir.to_path_buf());
+        state.key = None;
+        state.previous_key = None;
+    }
+    state.enabled = enabled;
+}
+
+/// Fail with `LOCKED_MESSAGE` while encryption is on and the passphrase hasn't been entered.
+pub fn ensure_unlocked() -> Result<(), String> {
+    let state = state();
+    if state.enabled && state.key.is_none() {
+        return Err(LOCKED_MESSAGE.to_string());
+    }
+    Ok(())
+}
+
+/// Content as it should be written to `path`: encrypted when the file is in the active notes
+/// folder and encryption is on, unchanged otherwise.
+pub fn encrypt_content(path: &Path, note_id: &str, content: &str) -> Result<String, String> {
+    let state = state();
+    let applies = state.enabled
+        && state
+            .notes_dir
+            .as_deref()
+            .map_or(false, |dir| path.starts_with(dir));
+    if !applies {
+        return Ok(content.to_string());
+    }
+    let key = state
+        .key
+        .as_ref()
+        .ok_or_else(|| LOCKED_MESSAGE.to_string())?;
+    seal_content(key, note_id, content)
+}
+
+fn seal_content(key: &NotesKey, note_id: &str, content: &str) -> Result<String, String> {
+    Ok(format!(
+        "{}{}:{}",
+        ENCRYPTED_PREFIX,
+        key.id,
+        seal(key, content.as_bytes(), note_id.as_bytes())?
+    ))
+}
+
+/// Content as stored on disk back to plain text. Notes saved before encryption was turned on
+/// are passed through and get encrypted the next time they're saved. Until a passphrase
+/// change is finished, notes still under the old key are opened with it.
+pub fn decrypt_content(note_id: &str, content: &str) -> Result<String, String> {
+    let Some(rest) = content.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Ok(content.to_string());
+    };
+    let state = state();
+    if state.key.is_none() {
+        return Err(LOCKED_MESSAGE.to_string());
+    }
+    let (key_id, sealed) = rest
+        .split_once(':')
+        .ok_or_else(|| "Encrypted note is damaged".to_string())?;
+    let key = state
+        .key
+        .iter()
+        .chain(state.previous_key.iter())
+        .find(|key| key.id == key_id)
+        .ok_or_else(|| "This note was encrypted with a different passphrase".to_string())?;
+    let plain = open(key, sealed, note_id.as_bytes())
+        .ok_or_else(|| "Failed to decrypt note (it may be damaged)".to_string())?;
+    String::from_utf8(plain).map_err(|_| "Encrypted note is damaged".to_string())
+}
+
+/// A notes backup as it should be written: the whole file encrypted while encryption is on,
+/// since it holds every note's content.
+pub fn encrypt_backup(json: &str) -> Result<String, String> {
+    let state = state();
+    if !state.enabled {
+        return Ok(json.to_string());
+    }
+    let key = state
+        .key
+        .as_ref()
+        .ok_or_else(|| LOCKED_MESSAGE.to_string())?;
+    seal_content(key, BACKUP_AAD, json)
+}
+
+/// A notes backup read from disk back to JSON. Plain backups are passed through.
+pub fn decrypt_backup(contents: &str) -> Result<String, String> {
+    decrypt_content(BACKUP_AAD, contents)
+}
+
+/// Rewrite every backup of the notes in `notes_dir` to match the current state: sealed with
+/// the current key while encryption is on, plain once it's off. Backups sealed under a
+/// passphrase from before the last change can't be opened and are left as they are.
+fn reseal_backups(notes_dir: &Path) -> Result<usize, String> {
+    let mut resealed = 0;
+    for path in notes_filesystem::notes_backups_for(notes_dir) {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(json) = decrypt_backup(&contents) else {
+            continue;
+        };
+        let updated = encrypt_backup(&json)?;
+        if updated == contents {
+            continue;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, updated)
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| format!("Failed to update notes backup: {}", e))?;
+        resealed += 1;
+    }
+    Ok(resealed)
+}
+
+/// Re-encrypt every note and backup still under the previous key, then forget it.
+fn finish_passphrase_change(notes_dir: &Path, mut sentinel: Sentinel) -> Result<usize, String> {
+    let rewritten = notes_filesystem::rewrite_all_notes(notes_dir, || Ok(()))?;
+    reseal_backups(notes_dir)?;
+    sentinel.previous_key = None;
+    write_sentinel(notes_dir, &sentinel)?;
+    state().previous_key = None;
+    Ok(rewritten)
+}
+
+fn enable(notes_dir: &Path, passphrase: &str) -> Result<usize, String> {
+    sync(notes_dir);
+    if read_sentinel(notes_dir)?.is_some() {
+        return Err(
+            "Notes are already encrypted. Use change passphrase to pick a new one.".to_string(),
+        );
+    }
+    let (sentinel, key) = new_key(passphrase)?;
+    let rewritten = notes_filesystem::rewrite_all_notes(notes_dir, || {
+        write_sentinel(notes_dir, &sentinel)?;
+        let mut state = state();
+        state.enabled = true;
+        state.key = Some(key);
+        Ok(())
+    })?;
+    reseal_backups(notes_dir)?;
+    Ok(rewritten)
+}
+
+/// Check `passphrase` and keep its key. A passphrase change that was interrupted is finished
+/// here; if that fails again the notes still open, with both keys held.
+fn unlock(notes_dir: &Path, passphrase: &str) -> Result<(), String> {
+    sync(notes_dir);
+    let sentinel = read_sentinel(notes_dir)?.ok_or_else(|| "Notes aren't encrypted".to_string())?;
+    let key = unlock_key(&sentinel, passphrase)?;
+    let previous = previous_key(&sentinel, &key)?;
+    let pending = previous.is_some();
+    {
+        let mut state = state();
+        state.key = Some(key);
+        state.previous_key = previous;
+    }
+    if pending {
+        if let Err(e) = finish_passphrase_change(notes_dir, sentinel) {
+            eprintln!(
+                "[NotesEncryption] Failed to finish passphrase change: {}",
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+fn change_passphrase(
+    notes_dir: &Path,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<usize, String> {
+    unlock(notes_dir, old_passphrase)?;
+    let (mut sentinel, key) = new_key(new_passphrase)?;
+    let previous = state()
+        .key
+        .take()
+        .ok_or_else(|| LOCKED_MESSAGE.to_string())?;
+    sentinel.previous_key = Some(PreviousKey {
+        id: previous.id.clone(),
+        key: seal(&key, &previous.bytes[..], PREVIOUS_KEY_AAD)?,
+    });
+    // The new sentinel carries the old key, so whatever point the rewrite stops at, the new
+    // passphrase opens every note
+    if let Err(e) = write_sentinel(notes_dir, &sentinel) {
+        state().key = Some(previous);
+        return Err(e);
+    }
+    {
+        let mut state = state();
+        state.key = Some(key);
+        state.previous_key = Some(previous);
+    }
+    finish_passphrase_change(notes_dir, sentinel)
+}
+
+fn disable(notes_dir: &Path, passphrase: &str) -> Result<usize, String> {
+    unlock(notes_dir, passphrase)?;
+    // The sentinel goes last so an interrupted rewrite still asks for the passphrase
+    let rewritten = notes_filesystem::rewrite_all_notes(notes_dir, || {
+        state().enabled = false;
+        Ok(())
+    })?;
+    reseal_backups(notes_dir)?;
+    fs::remove_file(sentinel_path(notes_dir))
+        .map_err(|e| format!("Failed to remove notes encryption settings: {}", e))?;
+    state().key = None;
+    Ok(rewritten)
+}
+
+fn log_change(function: &str, message: &str, notes: usize) {
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::INFO,
+            "notes_encryption",
+            function,
+            message,
+            serde_json::json!({ "notes": notes }),
+        );
+    }
+}
+
+/// Whether the active profile's notes are encrypted and, if so, unlocked.
+#[tauri::command]
+pub fn get_notes_encryption_status(app: AppHandle) -> Result<NotesEncryptionStatus, String> {
+    let notes_dir = notes_filesystem::notes_root(&app)?;
+    sync(&notes_dir);
+    let state = state();
+    Ok(NotesEncryptionStatus {
+        enabled: state.enabled,
+        unlocked: state.key.is_some(),
+    })
+}
+
+/// Turn on encryption for the active profile's notes and encrypt every existing note. Only
+/// note content and backups are encrypted: titles stay readable as file names, and images are
+/// not covered. There's no recovery if the passphrase is forgotten. Returns the number of
+/// notes rewritten.
+#[tauri::command]
+pub fn set_notes_encryption(app: AppHandle, passphrase: String) -> Result<usize, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    let notes_dir = notes_filesystem::notes_root(&app)?;
+    let rewritten = enable(&notes_dir, &passphrase)?;
+    log_change(
+        "set_notes_encryption",
+        "Notes encryption enabled",
+        rewritten,
+    );
+    Ok(rewritten)
+}
+
+/// Unlock encrypted notes for the rest of the session.
+#[tauri::command]
+pub fn unlock_notes(app: AppHandle, passphrase: String) -> Result<(), String> {
+    let passphrase = Zeroizing::new(passphrase);
+    let notes_dir = notes_filesystem::notes_root(&app)?;
+    unlock(&notes_dir, &passphrase)
+}
+
+/// Forget the key; encrypted notes can't be read or saved until they're unlocked again.
+#[tauri::command]
+pub fn lock_notes() {
+    state().key = None;
+}
+
+/// Re-encrypt every note and backup under a new passphrase. Returns the number of notes
+/// rewritten.
+#[tauri::command]
+pub fn change_notes_passphrase(
+    app: AppHandle,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<usize, String> {
+    let old_passphrase = Zeroizing::new(old_passphrase);
+    let new_passphrase = Zeroizing::new(new_passphrase);
+    let notes_dir = notes_filesystem::notes_root(&app)?;
+    let rewritten = change_passphrase(&notes_dir, &old_passphrase, &new_passphrase)?;
+    log_change(
+        "change_notes_passphrase",
+        "Notes passphrase changed",
+        rewritten,
+    );
+    Ok(rewritten)
+}
+
+/// Decrypt every note and backup and turn encryption off. Returns the number of notes
+/// rewritten.
+#[tauri::command]
+pub fn disable_notes_encryption(app: AppHandle, passphrase: String) -> Result<usize, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    let notes_dir = notes_filesystem::notes_root(&app)?;
+    let rewritten = disable(&notes_dir, &passphrase)?;
+    log_change(
+        "disable_notes_encryption",
+        "Notes encryption disabled",
+        rewritten,
+    );
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_encryption_lifecycle() {
+        let root = std::env::temp_dir().join(format!("desqta-notes-enc-{}", uuid::Uuid::new_v4()));
+        let notes_dir = root.join("notes");
+        fs::create_dir_all(&notes_dir).unwrap();
+        let note_path = notes_dir.join("Physics.json");
+        let note = serde_json::json!({
+            "id": "n1", "title": "Physics", "content": "<p>secret formula</p>",
+            "folder_path": [], "tags": [], "seqta_references": [],
+            "created_at": "2026-01-01T00:00:00Z", "updated_at": "2026-01-01T00:00:00Z",
+            "last_accessed": "2026-01-01T00:00:00Z",
+            "metadata": { "word_count": 2, "character_count": 14, "reading_time": 1, "version": 1 }
+        });
+        fs::write(&note_path, note.to_string()).unwrap();
+        let raw = || fs::read_to_string(&note_path).unwrap();
+        let sealed_with = |id: &str| raw().contains(&format!("{}{}:", ENCRYPTED_PREFIX, id));
+        let backups = root.join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        let backup_path = backups.join("notes_filesystem_backup_20260101_000000.json");
+        fs::write(&backup_path, r#"{"notes":["secret formula"]}"#).unwrap();
+        let backup = || fs::read_to_string(&backup_path).unwrap();
+
+        assert!(enable(&notes_dir, "short").is_err());
+        assert_eq!(enable(&notes_dir, "correct horse").unwrap(), 1);
+        assert!(!raw().contains("secret formula"));
+        assert!(raw().contains(ENCRYPTED_PREFIX));
+        assert!(enable(&notes_dir, "correct horse").is_err());
+        // Backups from before encryption was turned on are sealed too
+        assert!(!backup().contains("secret formula"));
+
+        lock_notes();
+        assert_eq!(ensure_unlocked().unwrap_err(), LOCKED_MESSAGE);
+        assert!(unlock(&notes_dir, "wrong horse")
+            .unwrap_err()
+            .contains("Wrong passphrase"));
+        unlock(&notes_dir, "correct horse").unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&raw()).unwrap();
+        let content = stored["content"].as_str().unwrap();
+        assert_eq!(
+            decrypt_content("n1", content).unwrap(),
+            "<p>secret formula</p>"
+        );
+        // Content is bound to its note
+        assert!(decrypt_content("n2", content).is_err());
+
+        assert!(change_passphrase(&notes_dir, "wrong horse", "battery staple").is_err());
+        assert_eq!(
+            change_passphrase(&notes_dir, "correct horse", "battery staple").unwrap(),
+            1
+        );
+        assert!(unlock(&notes_dir, "correct horse").is_err());
+        unlock(&notes_dir, "battery staple").unwrap();
+        assert!(decrypt_backup(&backup())
+            .unwrap()
+            .contains("secret formula"));
+
+        // A change interrupted before any note was rewritten: the new passphrase still opens
+        // everything, and unlocking finishes the change
+        let (mut pending, key) = new_key("tuning fork").unwrap();
+        let old = state().key.take().unwrap();
+        pending.previous_key = Some(PreviousKey {
+            id: old.id.clone(),
+            key: seal(&key, &old.bytes[..], PREVIOUS_KEY_AAD).unwrap(),
+        });
+        write_sentinel(&notes_dir, &pending).unwrap();
+        assert!(sealed_with(&old.id));
+        unlock(&notes_dir, "tuning fork").unwrap();
+        assert!(sealed_with(&key.id));
+        assert!(read_sentinel(&notes_dir)
+            .unwrap()
+            .unwrap()
+            .previous_key
+            .is_none());
+        assert!(decrypt_backup(&backup())
+            .unwrap()
+            .contains("secret formula"));
+
+        // Backups hold every note, so they're sealed too
+        let sealed = encrypt_backup(r#"{"notes":["secret formula"]}"#).unwrap();
+        assert!(!sealed.contains("secret formula"));
+        assert_eq!(
+            decrypt_backup(&sealed).unwrap(),
+            r#"{"notes":["secret formula"]}"#
+        );
+
+        assert_eq!(disable(&notes_dir, "tuning fork").unwrap(), 1);
+        assert!(raw().contains("secret formula"));
+        assert!(backup().contains("secret formula"));
+        assert!(!sentinel_path(&notes_dir).exists());
+        ensure_unlocked().unwrap();
+        assert_eq!(encrypt_backup("{}").unwrap(), "{}");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,6 +1,8 @@
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -8,7 +10,16 @@ use tauri::AppHandle;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
+use super::data_dir;
+use super::notes_encryption;
+use super::notes_images;
 use super::profiles;
+use super::sanitization;
+use super::settings;
+use super::text_search::{
+    create_snippet, decode_html_entities, highlight_match, html_to_text, strip_html_tags,
+    SearchMatch,
+};
 
 // Define types directly here (moved from notes.rs)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +78,9 @@ pub struct FileSystemFolder {
     pub path: String, // Relative path from notes root
     pub color: Option<String>,
     pub icon: Option<String>,
+    /// Name chosen by the user, shown instead of `name` (the directory name)
+    #[serde(default)]
+    pub display_name: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -80,75 +94,90 @@ pub struct FileTreeItem {
     pub size: Option<u64>,
     pub modified: String,
     pub children: Option<Vec<FileTreeItem>>,
+    /// Number of folders and notes directly inside a folder whose `children` weren't loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<usize>,
+    // Folder metadata from `.folder-meta.json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// Hidden file inside a notes folder holding its color, icon and display name. Folders
+/// without one simply use the defaults.
+const FOLDER_META_FILE: &str = ".folder-meta.json";
+const DEFAULT_FOLDER_ICON: &str = "📁";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FolderMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
 }
 
 /// Get the notes directory path for the current profile
-fn get_notes_directory(_app: &AppHandle) -> Result<PathBuf, String> {
+fn get_notes_directory(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = notes_root(app)?;
+    notes_encryption::sync(&dir);
+    notes_encryption::ensure_unlocked()?;
+    Ok(dir)
+}
+
+/// The active profile's notes folder, whether or not its notes are locked.
+pub fn notes_root(_app: &AppHandle) -> Result<PathBuf, String> {
     // Get the current profile
     let profile = profiles::ProfileManager::get_current_profile()
         .ok_or_else(|| "No active profile. Please log in first.".to_string())?;
-    
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir.push("profiles");
-        dir.push(&profile.id);
-        dir.push("notes");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {}", e))?;
-        }
-        Ok(dir)
-    }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir =
-            dirs_next::data_dir().ok_or_else(|| "Unable to determine data dir".to_string())?;
-        dir.push("DesQTA");
-        dir.push("profiles");
-        dir.push(&profile.id);
-        dir.push("notes");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {}", e))?;
-        }
-        Ok(dir)
+
+    let mut dir = data_dir::app_data_dir()?;
+    dir.push("profiles");
+    dir.push(&profile.id);
+    dir.push("notes");
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {}", e))?;
     }
+    Ok(dir)
 }
 
-/// Generate a safe filename from a title
-fn sanitize_filename(title: &str) -> String {
-    let mut filename = title
-        .chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-            c if c.is_control() => '_',
-            c => c,
-        })
-        .collect::<String>();
-
-    // Limit length and ensure it's not empty
-    if filename.is_empty() {
-        filename = "Untitled".to_string();
-    }
+/// Whether `path` is a note file, i.e. a `.json` file other than a folder's metadata.
+fn is_note_file(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "json")
+        && path
+            .file_name()
+            .map_or(false, |name| name != FOLDER_META_FILE)
+}
 
-    if filename.len() > 100 {
-        filename.truncate(100);
+/// Generate a safe filename (without extension) from a title
+fn sanitize_filename(title: &str) -> String {
+    if title.trim().is_empty() {
+        return "Untitled".to_string();
     }
-
-    filename
+    sanitization::sanitize_path_component(title)
 }
 
 /// Convert filesystem note to Note struct for compatibility
+/// The note's `folder_path` is derived from where its file lives rather than stored, so
+/// moving or renaming folders can't leave it stale.
 fn filesystem_note_to_note(fs_note: FileSystemNote, relative_path: &str) -> Note {
-    let folder_path = Path::new(relative_path)
+    // Split on path components, not '/', so Windows separators are handled too
+    let folder_parts: Vec<String> = Path::new(relative_path)
         .parent()
-        .map(|p| p.to_string_lossy().to_string())
+        .map(|p| {
+            p.components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
         .unwrap_or_default();
-
-    let folder_parts: Vec<String> = if folder_path.is_empty() {
+    let folder_parts = if folder_parts.is_empty() {
         vec!["default".to_string()]
     } else {
-        folder_path.split('/').map(|s| s.to_string()).collect()
+        folder_parts
     };
 
     Note {
@@ -185,14 +214,16 @@ fn note_to_filesystem_note(note: Note) -> FileSystemNote {
 #[tauri::command]
 pub fn load_notes_filesystem(app: AppHandle) -> Result<Vec<Note>, String> {
     let notes_dir = get_notes_directory(&app)?;
+    load_notes_from_dir(&notes_dir)
+}
+
+fn load_notes_from_dir(notes_dir: &Path) -> Result<Vec<Note>, String> {
     let mut notes = Vec::new();
 
     for entry in WalkDir::new(&notes_dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension().map_or(false, |ext| ext == "json")
-        })
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
     {
         let relative_path = entry
             .path()
@@ -218,8 +249,9 @@ pub fn load_notes_filesystem(app: AppHandle) -> Result<Vec<Note>, String> {
 }
 
 #[tauri::command]
-pub fn save_note_filesystem(app: AppHandle, note: Note) -> Result<(), String> {
+pub fn save_note_filesystem(app: AppHandle, mut note: Note) -> Result<(), String> {
     let notes_dir = get_notes_directory(&app)?;
+    recompute_note_metadata(&mut note);
     let fs_note = note_to_filesystem_note(note.clone());
 
     // Check if a note with this ID already exists but with a different title
@@ -227,9 +259,7 @@ pub fn save_note_filesystem(app: AppHandle, note: Note) -> Result<(), String> {
     for entry in WalkDir::new(&notes_dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension().map_or(false, |ext| ext == "json")
-        })
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
     {
         if let Ok(existing_fs_note) = load_note_file(entry.path()) {
             if existing_fs_note.id == note.id {
@@ -290,9 +320,7 @@ pub fn delete_note_filesystem(app: AppHandle, note_id: String) -> Result<(), Str
     for entry in WalkDir::new(&notes_dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension().map_or(false, |ext| ext == "json")
-        })
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
     {
         if let Ok(fs_note) = load_note_file(entry.path()) {
             if fs_note.id == note_id {
@@ -306,25 +334,29 @@ pub fn delete_note_filesystem(app: AppHandle, note_id: String) -> Result<(), Str
     Err("Note not found".to_string())
 }
 
+/// Create a folder, optionally with a color and icon (the default icon otherwise).
 #[tauri::command]
 pub fn create_folder_filesystem(
     app: AppHandle,
     name: String,
     parent_path: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
 ) -> Result<FileSystemFolder, String> {
     let notes_dir = get_notes_directory(&app)?;
-
-    let folder_path = if let Some(parent) = parent_path {
-        notes_dir.join(&parent).join(&name)
-    } else {
-        notes_dir.join(&name)
-    };
-
-    if folder_path.exists() {
-        return Err("Folder already exists".to_string());
+    if name.trim().is_empty() || name.contains(['/', '\\']) || name == ".." || name == "." {
+        return Err("Invalid folder name".to_string());
     }
+    let meta = folder_metadata(color, icon, None)?;
+    let parent = resolve_notes_folder(&notes_dir, parent_path.as_deref().unwrap_or(""))?;
+    check_folder_chain(&notes_dir, &parent)?;
+    if let Some(conflict) = folder_name_conflict(&parent, &name) {
+        return Err(conflict);
+    }
+    let folder_path = parent.join(&name);
 
     fs::create_dir_all(&folder_path).map_err(|e| format!("Failed to create folder: {}", e))?;
+    write_folder_metadata(&folder_path, &meta)?;
 
     let relative_path = folder_path
         .strip_prefix(&notes_dir)
@@ -336,8 +368,9 @@ pub fn create_folder_filesystem(
         id: Uuid::new_v4().to_string(),
         name: name.clone(),
         path: relative_path,
-        color: None,
-        icon: Some("📁".to_string()),
+        color: meta.color,
+        icon: meta.icon.or_else(|| Some(DEFAULT_FOLDER_ICON.to_string())),
+        display_name: None,
         created_at: Utc::now().to_rfc3339(),
         updated_at: Utc::now().to_rfc3339(),
     };
@@ -354,22 +387,146 @@ pub fn delete_folder_filesystem(app: AppHandle, folder_path: String) -> Result<(
         return Err("Folder does not exist".to_string());
     }
 
-    // Check if folder is empty
+    // Check if folder is empty (its metadata file doesn't count)
     let is_empty = full_path
         .read_dir()
         .map_err(|e| format!("Failed to read folder: {}", e))?
-        .next()
-        .is_none();
+        .flatten()
+        .all(|entry| entry.file_name() == FOLDER_META_FILE);
 
     if !is_empty {
         return Err("Folder is not empty. Please move or delete all contents first.".to_string());
     }
 
-    fs::remove_dir(&full_path).map_err(|e| format!("Failed to delete folder: {}", e))?;
+    remove_empty_folder(&full_path).map_err(|e| format!("Failed to delete folder: {}", e))?;
+
+    Ok(())
+}
+
+/// Resolve a folder path relative to the notes root, rejecting anything that could point
+/// outside it. An empty path is the root itself.
+fn resolve_notes_folder(notes_dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative.trim_matches(|c| c == '/' || c == '\\'));
+    if !relative
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err("Invalid folder path".to_string());
+    }
+    Ok(notes_dir.join(relative))
+}
+
+/// Why a folder called `name` can't be created in `parent`, if it can't. A folder next to a
+/// note file of the same name would show up as two identically named entries in the tree.
+fn folder_name_conflict(parent: &Path, name: &str) -> Option<String> {
+    let path = parent.join(name);
+    if path.is_dir() {
+        return Some(format!("A folder named '{}' already exists", name));
+    }
+    if path.exists() {
+        return Some(format!("A file named '{}' already exists", name));
+    }
+    if parent.join(format!("{}.json", name)).is_file() {
+        return Some(format!(
+            "A note named '{}' already exists in that folder",
+            name
+        ));
+    }
+    None
+}
 
+/// Check every folder between `notes_dir` and `folder` is either missing or a real folder
+/// that can be created without colliding with a note.
+fn check_folder_chain(notes_dir: &Path, folder: &Path) -> Result<(), String> {
+    let relative = folder
+        .strip_prefix(notes_dir)
+        .map_err(|_| "Invalid folder path".to_string())?;
+    let mut current = notes_dir.to_path_buf();
+    for component in relative.components() {
+        let name = component.as_os_str().to_string_lossy();
+        let next = current.join(component);
+        if !next.is_dir() {
+            if let Some(conflict) = folder_name_conflict(&current, &name) {
+                return Err(conflict);
+            }
+        }
+        current = next;
+    }
     Ok(())
 }
 
+/// `parent/name`, or `parent/name (2)`, `parent/name (3)`... if that's taken by a folder
+/// or a note.
+fn unique_folder_destination(parent: &Path, name: &str) -> PathBuf {
+    if folder_name_conflict(parent, name).is_none() {
+        return parent.join(name);
+    }
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| folder_name_conflict(parent, candidate).is_none())
+        .map(|candidate| parent.join(candidate))
+        .unwrap()
+}
+
+/// Move the folder at `old_path` (with everything inside it) into `new_parent_path`, renaming
+/// it on a name collision. Returns the folder's new path relative to `notes_dir`.
+fn move_notes_folder(
+    notes_dir: &Path,
+    old_path: &str,
+    new_parent_path: &str,
+) -> Result<String, String> {
+    let source = resolve_notes_folder(notes_dir, old_path)?;
+    let parent = resolve_notes_folder(notes_dir, new_parent_path)?;
+    if source == notes_dir {
+        return Err("Cannot move the notes root".to_string());
+    }
+    if !source.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    if parent == source {
+        return Err("Cannot move a folder into itself".to_string());
+    }
+    if parent.starts_with(&source) {
+        return Err("Cannot move a folder into one of its own subfolders".to_string());
+    }
+    check_folder_chain(notes_dir, &parent)?;
+    if source.parent() == Some(parent.as_path()) {
+        // Already there; nothing to do
+        return Ok(old_path.trim_matches('/').to_string());
+    }
+
+    fs::create_dir_all(&parent)
+        .map_err(|e| format!("Failed to create destination folder: {}", e))?;
+    let name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid folder path")?;
+    let destination = unique_folder_destination(&parent, name);
+
+    fs::rename(&source, &destination).map_err(|e| format!("Failed to move folder: {}", e))?;
+
+    let relative = destination
+        .strip_prefix(notes_dir)
+        .map_err(|e| format!("Failed to get relative path: {}", e))?;
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Move a folder and all notes and subfolders in it under `new_parent_path` (the notes root
+/// when empty or omitted). Returns the folder's new relative path.
+#[tauri::command]
+pub fn move_folder_filesystem(
+    app: AppHandle,
+    old_path: String,
+    new_parent_path: Option<String>,
+) -> Result<String, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    move_notes_folder(&notes_dir, &old_path, new_parent_path.as_deref().unwrap_or(""))
+}
+
 #[tauri::command]
 pub fn rename_folder_filesystem(
     app: AppHandle,
@@ -377,16 +534,23 @@ pub fn rename_folder_filesystem(
     new_name: String,
 ) -> Result<(), String> {
     let notes_dir = get_notes_directory(&app)?;
-    let old_full_path = notes_dir.join(&old_path);
+    let old_full_path = resolve_notes_folder(&notes_dir, &old_path)?;
+    if old_full_path == notes_dir {
+        return Err("Invalid folder path".to_string());
+    }
+    if new_name.trim().is_empty() || new_name.contains(['/', '\\']) || new_name == ".." {
+        return Err("Invalid folder name".to_string());
+    }
 
-    let new_full_path = old_full_path
-        .parent()
-        .ok_or("Invalid folder path")?
-        .join(&new_name);
+    let parent = old_full_path.parent().ok_or("Invalid folder path")?;
+    let new_full_path = parent.join(&new_name);
 
-    if new_full_path.exists() {
+    if new_full_path.is_dir() {
         return Err("A folder with that name already exists".to_string());
     }
+    if let Some(conflict) = folder_name_conflict(parent, &new_name) {
+        return Err(conflict);
+    }
 
     fs::rename(&old_full_path, &new_full_path)
         .map_err(|e| format!("Failed to rename folder: {}", e))?;
@@ -394,12 +558,116 @@ pub fn rename_folder_filesystem(
     Ok(())
 }
 
+/// Set a folder's color, icon and display name, stored in its `.folder-meta.json`. Omitted
+/// values keep what the folder has; blank ones reset that field to the default. Returns the
+/// updated folder.
+#[tauri::command]
+pub fn set_folder_metadata(
+    app: AppHandle,
+    path: String,
+    color: Option<String>,
+    icon: Option<String>,
+    display_name: Option<String>,
+) -> Result<FileSystemFolder, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let folder = resolve_notes_folder(&notes_dir, &path)?;
+    if folder == notes_dir || !folder.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let meta = update_folder_metadata(&folder, color, icon, display_name)?;
+
+    let name = folder
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let modified = fs::metadata(&folder)
+        .and_then(|m| m.modified())
+        .map(|m| DateTime::<Utc>::from(m).to_rfc3339())
+        .unwrap_or_else(|_| Utc::now().to_rfc3339());
+    Ok(FileSystemFolder {
+        id: name.clone(),
+        name,
+        path: relative_to(&notes_dir, &folder),
+        color: meta.color,
+        icon: meta.icon.or_else(|| Some(DEFAULT_FOLDER_ICON.to_string())),
+        display_name: meta.display_name,
+        created_at: modified.clone(),
+        updated_at: modified,
+    })
+}
+
 #[tauri::command]
 pub fn get_file_tree(app: AppHandle) -> Result<Vec<FileTreeItem>, String> {
     let notes_dir = get_notes_directory(&app)?;
     build_file_tree(&notes_dir, &notes_dir)
 }
 
+/// The folders and notes directly inside `path` (relative to the notes root, the root when
+/// empty), for loading the tree one folder at a time. Folders come without `children` but
+/// with a `child_count`. With `modified_after` (RFC 3339) only entries changed since then
+/// are returned; a folder counts as changed when something was added, removed or renamed
+/// directly inside it.
+#[tauri::command]
+pub fn get_folder_children(
+    app: AppHandle,
+    path: Option<String>,
+    modified_after: Option<String>,
+) -> Result<Vec<FileTreeItem>, String> {
+    let modified_after = match modified_after.filter(|v| !v.trim().is_empty()) {
+        Some(value) => Some(
+            parse_note_timestamp(&value)
+                .ok_or_else(|| format!("Invalid modified_after timestamp: {}", value))?,
+        ),
+        None => None,
+    };
+    let notes_dir = get_notes_directory(&app)?;
+    let folder = resolve_notes_folder(&notes_dir, path.as_deref().unwrap_or(""))?;
+    if !folder.is_dir() {
+        return Err(format!("Folder not found: {}", path.unwrap_or_default()));
+    }
+    folder_children(&folder, &notes_dir, modified_after)
+}
+
+/// Where the note at `current` would live after moving to `folder_path`. Rejects folder
+/// paths that run through a note file or would create a folder sharing a note's name, and
+/// destinations already taken by a folder or by a different note.
+fn note_move_destination(
+    notes_dir: &Path,
+    current: &Path,
+    folder_path: &[String],
+    title: &str,
+) -> Result<PathBuf, String> {
+    let relative: Vec<&str> = folder_path
+        .iter()
+        .map(|f| f.as_str())
+        .filter(|f| *f != "default")
+        .collect();
+    if relative.iter().any(|f| f.contains(['/', '\\'])) {
+        return Err("Invalid folder path".to_string());
+    }
+    let folder = resolve_notes_folder(notes_dir, &relative.join("/"))?;
+    check_folder_chain(notes_dir, &folder)?;
+
+    let stem = sanitize_filename(title);
+    let destination = folder.join(format!("{}.json", stem));
+    if destination == current {
+        return Ok(destination);
+    }
+    if destination.is_dir() || folder.join(&stem).is_dir() {
+        return Err(format!(
+            "A folder named '{}' already exists in that folder",
+            stem
+        ));
+    }
+    if destination.exists() {
+        return Err(format!(
+            "A note named '{}' already exists in that folder",
+            stem
+        ));
+    }
+    Ok(destination)
+}
+
 #[tauri::command]
 pub fn move_note_filesystem(
     app: AppHandle,
@@ -407,51 +675,23 @@ pub fn move_note_filesystem(
     new_folder_path: Vec<String>,
 ) -> Result<(), String> {
     let notes_dir = get_notes_directory(&app)?;
+    let (current, mut fs_note) = find_note_file(&notes_dir, &note_id).ok_or("Note not found")?;
 
-    // Find the current note file
-    for entry in WalkDir::new(&notes_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension().map_or(false, |ext| ext == "json")
-        })
-    {
-        if let Ok(mut fs_note) = load_note_file(entry.path()) {
-            if fs_note.id == note_id {
-                // Delete old file
-                fs::remove_file(entry.path())
-                    .map_err(|e| format!("Failed to delete old note file: {}", e))?;
-
-                // Create new folder structure
-                let new_folder = if new_folder_path.is_empty() || new_folder_path[0] == "default" {
-                    notes_dir.clone()
-                } else {
-                    let mut path = notes_dir.clone();
-                    for folder in &new_folder_path {
-                        if folder != "default" {
-                            path.push(folder);
-                        }
-                    }
-                    if !path.exists() {
-                        fs::create_dir_all(&path)
-                            .map_err(|e| format!("Failed to create folder structure: {}", e))?;
-                    }
-                    path
-                };
-
-                // Save to new location
-                let filename = format!("{}.json", sanitize_filename(&fs_note.title));
-                let new_file_path = new_folder.join(&filename);
-
-                fs_note.updated_at = Utc::now().to_rfc3339();
-                save_note_file(&new_file_path, &fs_note)?;
+    let destination =
+        note_move_destination(&notes_dir, &current, &new_folder_path, &fs_note.title)?;
+    if let Some(folder) = destination.parent() {
+        fs::create_dir_all(folder)
+            .map_err(|e| format!("Failed to create folder structure: {}", e))?;
+    }
 
-                return Ok(());
-            }
-        }
+    // Write the new copy before removing the old one so a failed move can't lose the note
+    fs_note.updated_at = Utc::now().to_rfc3339();
+    save_note_file(&destination, &fs_note)?;
+    if destination != current {
+        fs::remove_file(&current).map_err(|e| format!("Failed to delete old note file: {}", e))?;
     }
 
-    Err("Note not found".to_string())
+    Ok(())
 }
 
 // Helper functions
@@ -462,28 +702,166 @@ fn load_note_file(path: &Path) -> Result<FileSystemNote, String> {
     file.read_to_string(&mut contents)
         .map_err(|e| format!("Failed to read note file: {}", e))?;
 
-    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse note JSON: {}", e))
+    let mut note: FileSystemNote =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse note JSON: {}", e))?;
+    note.content = notes_encryption::decrypt_content(&note.id, &note.content)?;
+    Ok(note)
 }
 
 fn save_note_file(path: &Path, note: &FileSystemNote) -> Result<(), String> {
-    let json = serde_json::to_string_pretty(note)
+    let stored = FileSystemNote {
+        content: notes_encryption::encrypt_content(path, &note.id, &note.content)?,
+        ..note.clone()
+    };
+    let json = serde_json::to_string_pretty(&stored)
         .map_err(|e| format!("Failed to serialize note: {}", e))?;
 
-    let mut file = File::create(path).map_err(|e| format!("Failed to create note file: {}", e))?;
+    // Write beside the note and rename over it so an interrupted write can't truncate it
+    let tmp_path = note_sidecar_path(path, "tmp");
+    let mut file =
+        File::create(&tmp_path).map_err(|e| format!("Failed to create note file: {}", e))?;
     file.write_all(json.as_bytes())
         .map_err(|e| format!("Failed to write note file: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("Failed to write note file: {}", e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to save note file: {}", e))?;
 
     Ok(())
 }
 
-fn build_file_tree(dir: &Path, root: &Path) -> Result<Vec<FileTreeItem>, String> {
-    let mut items = Vec::new();
+/// Read every note, run `switch_key` and save them all again, so they're stored under the
+/// new encryption settings. Nothing is written unless every note could be read. Returns the
+/// number of notes rewritten.
+pub fn rewrite_all_notes<F>(notes_dir: &Path, switch_key: F) -> Result<usize, String>
+where
+    F: FnOnce() -> Result<(), String>,
+{
+    let notes = WalkDir::new(notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+        .map(|entry| {
+            load_note_file(entry.path())
+                .map(|note| (entry.into_path(), note))
+                .map_err(|e| format!("Couldn't read every note, nothing was changed: {}", e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    switch_key()?;
+    for (path, note) in &notes {
+        save_note_file(path, note)?;
+    }
+    Ok(notes.len())
+}
+
+/// Trim a folder metadata value, treating blank as unset. Rejects control characters and
+/// values longer than `max_chars`.
+fn clean_folder_meta_value(
+    value: Option<String>,
+    field: &str,
+    max_chars: usize,
+) -> Result<Option<String>, String> {
+    let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > max_chars || value.chars().any(char::is_control) {
+        return Err(format!("Invalid folder {}", field));
+    }
+    Ok(Some(value))
+}
+
+fn folder_metadata(
+    color: Option<String>,
+    icon: Option<String>,
+    display_name: Option<String>,
+) -> Result<FolderMetadata, String> {
+    Ok(FolderMetadata {
+        color: clean_folder_meta_value(color, "color", 32)?,
+        icon: clean_folder_meta_value(icon, "icon", 16)?,
+        display_name: clean_folder_meta_value(display_name, "name", 100)?,
+    })
+}
+
+/// Metadata of the folder at `dir`; defaults when it has none or the file is unreadable.
+fn read_folder_metadata(dir: &Path) -> FolderMetadata {
+    fs::read_to_string(dir.join(FOLDER_META_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
 
-    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+/// Apply the given values to the folder's stored metadata and save it. `None` keeps the
+/// stored value, a blank one resets it.
+fn update_folder_metadata(
+    dir: &Path,
+    color: Option<String>,
+    icon: Option<String>,
+    display_name: Option<String>,
+) -> Result<FolderMetadata, String> {
+    let stored = read_folder_metadata(dir);
+    let meta = folder_metadata(
+        color.or(stored.color),
+        icon.or(stored.icon),
+        display_name.or(stored.display_name),
+    )?;
+    write_folder_metadata(dir, &meta)?;
+    Ok(meta)
+}
+
+/// Save the folder's metadata, or remove the file when everything is back to the defaults.
+fn write_folder_metadata(dir: &Path, meta: &FolderMetadata) -> Result<(), String> {
+    let path = dir.join(FOLDER_META_FILE);
+    if *meta == FolderMetadata::default() {
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove folder metadata: {}", e))?;
+        }
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(meta)
+        .map_err(|e| format!("Failed to serialize folder metadata: {}", e))?;
+    let tmp_path = note_sidecar_path(&path, "tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write folder metadata: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to save folder metadata: {}", e))
+}
+
+/// Remove a folder holding nothing but (possibly) its metadata file.
+fn remove_empty_folder(dir: &Path) -> std::io::Result<()> {
+    match fs::remove_file(dir.join(FOLDER_META_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    fs::remove_dir(dir)
+}
 
-    for entry in entries {
+/// `Title.json` -> `Title.json.<suffix>`
+fn note_sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// An entry of a notes folder, with its modification time for filtering.
+struct FolderEntry {
+    path: PathBuf,
+    modified: DateTime<Utc>,
+    item: FileTreeItem,
+}
+
+/// The folders and notes directly inside `dir`, unsorted and with no `children` filled in.
+fn read_folder_entries(dir: &Path, root: &Path) -> Result<Vec<FolderEntry>, String> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))? {
         let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
         let path = entry.path();
+        let is_dir = path.is_dir();
+        if !is_dir && !is_note_file(&path) {
+            continue;
+        }
         let name = path
             .file_name()
             .ok_or("Invalid filename")?
@@ -500,43 +878,102 @@ fn build_file_tree(dir: &Path, root: &Path) -> Result<Vec<FileTreeItem>, String>
             .metadata()
             .map_err(|e| format!("Failed to get file metadata: {}", e))?;
 
-        let modified = metadata
-            .modified()
-            .map_err(|e| format!("Failed to get modified time: {}", e))?;
-
-        let modified_str = DateTime::<Utc>::from(modified).to_rfc3339();
+        let modified = DateTime::<Utc>::from(
+            metadata
+                .modified()
+                .map_err(|e| format!("Failed to get modified time: {}", e))?,
+        );
 
-        if path.is_dir() {
-            let children = build_file_tree(&path, root)?;
-            items.push(FileTreeItem {
+        let item = if is_dir {
+            let meta = read_folder_metadata(&path);
+            FileTreeItem {
                 id: Uuid::new_v4().to_string(),
                 name,
                 path: relative_path,
                 item_type: "folder".to_string(),
                 size: None,
-                modified: modified_str,
-                children: Some(children),
-            });
-        } else if path.extension().map_or(false, |ext| ext == "json") {
-            items.push(FileTreeItem {
+                modified: modified.to_rfc3339(),
+                children: None,
+                child_count: None,
+                color: meta.color,
+                icon: meta.icon,
+                display_name: meta.display_name,
+            }
+        } else {
+            FileTreeItem {
                 id: Uuid::new_v4().to_string(),
                 name: name.trim_end_matches(".json").to_string(),
                 path: relative_path,
                 item_type: "file".to_string(),
                 size: Some(metadata.len()),
-                modified: modified_str,
+                modified: modified.to_rfc3339(),
                 children: None,
-            });
-        }
+                child_count: None,
+                color: None,
+                icon: None,
+                display_name: None,
+            }
+        };
+        entries.push(FolderEntry {
+            path,
+            modified,
+            item,
+        });
     }
 
-    // Sort: folders first, then files, both alphabetically
+    Ok(entries)
+}
+
+/// Sort: folders first, then files, both alphabetically
+fn sort_file_tree(items: &mut [FileTreeItem]) {
     items.sort_by(|a, b| match (a.item_type.as_str(), b.item_type.as_str()) {
         ("folder", "file") => std::cmp::Ordering::Less,
         ("file", "folder") => std::cmp::Ordering::Greater,
         _ => a.name.cmp(&b.name),
     });
+}
+
+fn build_file_tree(dir: &Path, root: &Path) -> Result<Vec<FileTreeItem>, String> {
+    let mut items = Vec::new();
+    for mut entry in read_folder_entries(dir, root)? {
+        if entry.item.item_type == "folder" {
+            entry.item.children = Some(build_file_tree(&entry.path, root)?);
+        }
+        items.push(entry.item);
+    }
+    sort_file_tree(&mut items);
+    Ok(items)
+}
 
+/// Number of folders and notes directly inside `dir`.
+fn count_folder_children(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir() || is_note_file(p))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+fn folder_children(
+    dir: &Path,
+    root: &Path,
+    modified_after: Option<DateTime<Utc>>,
+) -> Result<Vec<FileTreeItem>, String> {
+    let mut items: Vec<FileTreeItem> = read_folder_entries(dir, root)?
+        .into_iter()
+        .filter(|entry| modified_after.map_or(true, |after| entry.modified > after))
+        .map(|mut entry| {
+            if entry.item.item_type == "folder" {
+                entry.item.child_count = Some(count_folder_children(&entry.path));
+            }
+            entry.item
+        })
+        .collect();
+    sort_file_tree(&mut items);
     Ok(items)
 }
 
@@ -549,13 +986,6 @@ pub struct SearchResult {
     pub matches: Vec<SearchMatch>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchMatch {
-    pub field: String, // "title", "content", "tags", "seqta_references"
-    pub snippet: String,
-    pub position: usize,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchFilters {
     pub folder_ids: Option<Vec<String>>,
@@ -585,6 +1015,86 @@ pub fn search_notes_filesystem(app: AppHandle, query: String) -> Result<Vec<Note
     Ok(matching_notes)
 }
 
+/// Relevance score of `note` for a lowercased query, with where each term matched.
+/// Snippet offsets come from lowercased copies, so they rely on the char-safe helpers.
+fn score_note(note: &Note, query_lower: &str, search_terms: &[&str]) -> (f32, Vec<SearchMatch>) {
+    let mut score = 0.0f32;
+    let mut matches = Vec::new();
+
+    // Search in title (highest weight)
+    let title_lower = note.title.to_lowercase();
+    for term in search_terms {
+        if title_lower.contains(term) {
+            score += 10.0;
+            if let Some(pos) = title_lower.find(term) {
+                matches.push(SearchMatch {
+                    field: "title".to_string(),
+                    snippet: highlight_match(&note.title, term, pos),
+                    position: pos,
+                });
+            }
+        }
+    }
+
+    // Search in tags (high weight)
+    for tag in &note.tags {
+        let tag_lower = tag.to_lowercase();
+        for term in search_terms {
+            if tag_lower.contains(term) {
+                score += 5.0;
+                matches.push(SearchMatch {
+                    field: "tags".to_string(),
+                    snippet: tag.clone(),
+                    position: 0,
+                });
+            }
+        }
+    }
+
+    // Search in content (medium weight)
+    let content_text = html_to_text(&note.content);
+    let content_lower = content_text.to_lowercase();
+    for term in search_terms {
+        if content_lower.contains(term) {
+            score += 2.0;
+            if let Some(pos) = content_lower.find(term) {
+                matches.push(SearchMatch {
+                    field: "content".to_string(),
+                    snippet: create_snippet(&content_text, term, pos),
+                    position: pos,
+                });
+            }
+        }
+    }
+
+    // Search in SEQTA references (low weight)
+    for seqta_ref in &note.seqta_references {
+        let display_name_lower = seqta_ref.display_name.to_lowercase();
+        for term in search_terms {
+            if display_name_lower.contains(term) {
+                score += 1.0;
+                matches.push(SearchMatch {
+                    field: "seqta_references".to_string(),
+                    snippet: seqta_ref.display_name.clone(),
+                    position: 0,
+                });
+            }
+        }
+    }
+
+    // Boost score for exact matches
+    if title_lower == query_lower {
+        score += 20.0;
+    }
+
+    // Boost score for matches at the beginning
+    if title_lower.starts_with(query_lower) {
+        score += 5.0;
+    }
+
+    (score, matches)
+}
+
 #[tauri::command]
 pub fn search_notes_advanced_filesystem(
     app: AppHandle,
@@ -652,79 +1162,7 @@ pub fn search_notes_advanced_filesystem(
             }
         }
 
-        let mut score = 0.0f32;
-        let mut matches = Vec::new();
-
-        // Search in title (highest weight)
-        let title_lower = note.title.to_lowercase();
-        for term in &search_terms {
-            if title_lower.contains(term) {
-                score += 10.0;
-                if let Some(pos) = title_lower.find(term) {
-                    matches.push(SearchMatch {
-                        field: "title".to_string(),
-                        snippet: highlight_match(&note.title, term, pos),
-                        position: pos,
-                    });
-                }
-            }
-        }
-
-        // Search in tags (high weight)
-        for tag in &note.tags {
-            let tag_lower = tag.to_lowercase();
-            for term in &search_terms {
-                if tag_lower.contains(term) {
-                    score += 5.0;
-                    matches.push(SearchMatch {
-                        field: "tags".to_string(),
-                        snippet: tag.clone(),
-                        position: 0,
-                    });
-                }
-            }
-        }
-
-        // Search in content (medium weight)
-        let content_text = strip_html_tags(&note.content);
-        let content_lower = content_text.to_lowercase();
-        for term in &search_terms {
-            if content_lower.contains(term) {
-                score += 2.0;
-                if let Some(pos) = content_lower.find(term) {
-                    matches.push(SearchMatch {
-                        field: "content".to_string(),
-                        snippet: create_snippet(&content_text, term, pos),
-                        position: pos,
-                    });
-                }
-            }
-        }
-
-        // Search in SEQTA references (low weight)
-        for seqta_ref in &note.seqta_references {
-            let display_name_lower = seqta_ref.display_name.to_lowercase();
-            for term in &search_terms {
-                if display_name_lower.contains(term) {
-                    score += 1.0;
-                    matches.push(SearchMatch {
-                        field: "seqta_references".to_string(),
-                        snippet: seqta_ref.display_name.clone(),
-                        position: 0,
-                    });
-                }
-            }
-        }
-
-        // Boost score for exact matches
-        if title_lower == query_lower {
-            score += 20.0;
-        }
-
-        // Boost score for matches at the beginning
-        if title_lower.starts_with(&query_lower) {
-            score += 5.0;
-        }
+        let (score, matches) = score_note(&note, &query_lower, &search_terms);
 
         // Only include notes with matches
         if score > 0.0 {
@@ -747,85 +1185,638 @@ pub fn search_notes_advanced_filesystem(
     Ok(results)
 }
 
-fn highlight_match(text: &str, term: &str, position: usize) -> String {
-    let start = position.saturating_sub(20);
-    let end = (position + term.len() + 20).min(text.len());
-    let snippet = &text[start..end];
+// Note metadata
 
-    if start > 0 {
-        format!("...{}", snippet)
-    } else if end < text.len() {
-        format!("{}...", snippet)
-    } else {
-        snippet.to_string()
+/// Reading speed used for `reading_time`, matching the editor's status bar.
+const WORDS_PER_MINUTE: u32 = 200;
+
+/// Recompute word count, character count and reading time from the note's HTML content.
+/// Mention tags count as their visible label. Bumps `version` when anything changed, so
+/// stale figures sent by a client are corrected without double-counting a normal save.
+/// Returns whether the metadata changed.
+pub(crate) fn recompute_note_metadata(note: &mut Note) -> bool {
+    let word_count = html_to_text(&note.content).split_whitespace().count() as u32;
+    let character_count = decode_html_entities(&strip_html_tags(&note.content))
+        .trim()
+        .chars()
+        .count() as u32;
+    let reading_time = word_count.div_ceil(WORDS_PER_MINUTE);
+
+    let metadata = &mut note.metadata;
+    let changed = metadata.word_count != word_count
+        || metadata.character_count != character_count
+        || metadata.reading_time != reading_time;
+    if changed {
+        metadata.word_count = word_count;
+        metadata.character_count = character_count;
+        metadata.reading_time = reading_time;
+        metadata.version += 1;
     }
+    changed
 }
 
-fn create_snippet(text: &str, term: &str, position: usize) -> String {
-    let start = position.saturating_sub(50);
-    let end = (position + term.len() + 50).min(text.len());
-    let snippet = &text[start..end];
+/// Rewrite the metadata of every note whose stored counts don't match its content.
+/// Returns the number of notes fixed.
+#[tauri::command]
+pub fn recompute_all_notes_metadata(app: AppHandle) -> Result<usize, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let mut fixed = 0;
 
-    let prefix = if start > 0 { "..." } else { "" };
-    let suffix = if end < text.len() { "..." } else { "" };
+    for entry in WalkDir::new(&notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+    {
+        let Ok(fs_note) = load_note_file(entry.path()) else {
+            continue;
+        };
+        let mut note = filesystem_note_to_note(fs_note, "");
+        if recompute_note_metadata(&mut note) {
+            save_note_file(entry.path(), &note_to_filesystem_note(note))?;
+            fixed += 1;
+        }
+    }
 
-    format!("{}{}{}", prefix, snippet, suffix)
+    Ok(fixed)
 }
 
-fn strip_html_tags(html: &str) -> String {
-    // Simple HTML tag removal
-    let mut result = String::new();
-    let mut in_tag = false;
+// Quick capture
 
-    for ch in html.chars() {
-        match ch {
-            '<' => in_tag = true,
-            '>' => in_tag = false,
-            _ if !in_tag => result.push(ch),
-            _ => {} // Skip characters inside tags
-        }
-    }
+/// Titles derived from captured text keep at most this many words.
+const QUICK_CAPTURE_TITLE_WORDS: usize = 8;
 
-    result
+/// First non-blank line of the captured text, cut down to `QUICK_CAPTURE_TITLE_WORDS` words.
+fn quick_capture_title(text: &str) -> String {
+    let first_line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    let words: Vec<&str> = first_line.split_whitespace().collect();
+    if words.len() > QUICK_CAPTURE_TITLE_WORDS {
+        format!("{}…", words[..QUICK_CAPTURE_TITLE_WORDS].join(" "))
+    } else {
+        words.join(" ")
+    }
 }
 
-// Image handling functions
+/// One escaped paragraph per non-blank line.
+fn quick_capture_html(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("<p>{}</p>", sanitization::escape_html(line)))
+        .collect()
+}
 
-fn get_notes_images_dir(_app: &AppHandle) -> Result<PathBuf, String> {
-    // Get the current profile
-    let profile = profiles::ProfileManager::get_current_profile()
-        .ok_or_else(|| "No active profile. Please log in first.".to_string())?;
-    
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        dir.push("profiles");
-        dir.push(&profile.id);
-        dir.push("note_contents");
-        if !dir.exists() {
-            fs::create_dir_all(&dir)
-                .map_err(|e| format!("Failed to create note_contents directory: {}", e))?;
-        }
-        Ok(dir)
+/// `title`, or `title (2)`, `title (3)`... if a note or folder in `folder` already uses the
+/// file name.
+fn unique_note_title(folder: &Path, title: &str) -> String {
+    let free =
+        |candidate: &str| folder_name_conflict(folder, &sanitize_filename(candidate)).is_none();
+    if free(title) {
+        return title.to_string();
     }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir =
-            dirs_next::data_dir().ok_or_else(|| "Unable to determine data dir".to_string())?;
-        dir.push("DesQTA");
-        dir.push("profiles");
-        dir.push(&profile.id);
-        if !dir.exists() {
-            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    (2..)
+        .map(|n| format!("{} ({})", title, n))
+        .find(|candidate| free(candidate))
+        .unwrap()
+}
+
+/// Create a note in `folder` (relative to the notes root), or in the configured
+/// `default_folder` when none is given. The title gets a number appended if it's taken.
+pub(crate) fn create_note(
+    app: &AppHandle,
+    folder: Option<String>,
+    title: &str,
+    content: String,
+    tags: Vec<String>,
+) -> Result<Note, String> {
+    let notes_dir = get_notes_directory(app)?;
+    let folder = folder.unwrap_or_else(|| settings::Settings::load().default_folder);
+    let folder_dir = resolve_notes_folder(&notes_dir, &folder)?;
+    check_folder_chain(&notes_dir, &folder_dir)?;
+    fs::create_dir_all(&folder_dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    let folder_path: Vec<String> = folder_dir
+        .strip_prefix(&notes_dir)
+        .map_err(|e| format!("Failed to get relative path: {}", e))?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let now = Utc::now().to_rfc3339();
+    let mut note = Note {
+        id: Uuid::new_v4().to_string(),
+        title: unique_note_title(&folder_dir, title),
+        content,
+        folder_path: if folder_path.is_empty() {
+            vec!["default".to_string()]
+        } else {
+            folder_path
+        },
+        tags,
+        seqta_references: Vec::new(),
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        last_accessed: now,
+        metadata: NoteMetadata {
+            word_count: 0,
+            character_count: 0,
+            reading_time: 0,
+            last_auto_save: None,
+            // Bumped to 1 by the recompute below unless the note is empty
+            version: 0,
+        },
+    };
+    recompute_note_metadata(&mut note);
+
+    let file_path = folder_dir.join(format!("{}.json", sanitize_filename(&note.title)));
+    save_note_file(&file_path, &note_to_filesystem_note(note.clone()))?;
+    Ok(note)
+}
+
+/// Create a note from plain text in `folder` (relative to the notes root), or in the
+/// configured `default_folder` when none is given. Returns the new note's id.
+#[tauri::command]
+pub fn quick_capture_note(
+    app: AppHandle,
+    text: String,
+    folder: Option<String>,
+) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to capture".to_string());
+    }
+    let note = create_note(
+        &app,
+        folder,
+        &quick_capture_title(&text),
+        quick_capture_html(&text),
+        Vec::new(),
+    )?;
+    Ok(note.id)
+}
+
+// Integrity checks
+
+/// Copies a corrupt note may be recoverable from: the sidecar left by an unfinished atomic
+/// write.
+const NOTE_RECOVERY_SUFFIXES: [&str; 1] = ["tmp"];
+
+/// A note file that could not be parsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorruptNoteFile {
+    /// Path relative to the notes directory
+    pub path: String,
+    pub error: String,
+    pub size: u64,
+    /// Sidecar files that contain a parseable copy of the note
+    pub recovery_candidates: Vec<String>,
+}
+
+fn recovery_candidates(path: &Path) -> Vec<PathBuf> {
+    NOTE_RECOVERY_SUFFIXES
+        .iter()
+        .map(|suffix| note_sidecar_path(path, suffix))
+        .filter(|candidate| candidate.is_file() && load_note_file(candidate).is_ok())
+        .collect()
+}
+
+/// Restore a corrupt note from its first usable sidecar. The damaged file is kept as
+/// `.json.corrupt` so nothing is lost if the recovered copy turns out to be older.
+fn repair_note_file(path: &Path) -> Result<FileSystemNote, String> {
+    let source = recovery_candidates(path)
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No recoverable copy of this note was found".to_string())?;
+    let note = load_note_file(&source)?;
+
+    if path.exists() {
+        fs::rename(path, note_sidecar_path(path, "corrupt"))
+            .map_err(|e| format!("Failed to set aside corrupt note: {}", e))?;
+    }
+    save_note_file(path, &note)?;
+    if source.extension().map_or(false, |ext| ext == "tmp") && source.exists() {
+        let _ = fs::remove_file(&source);
+    }
+
+    Ok(note)
+}
+
+/// List note files that fail to parse (and are therefore skipped by `load_notes_filesystem`).
+#[tauri::command]
+pub fn scan_note_integrity(app: AppHandle) -> Result<Vec<CorruptNoteFile>, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let mut corrupt = Vec::new();
+
+    for entry in WalkDir::new(&notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+    {
+        let error = match load_note_file(entry.path()) {
+            Ok(_) => continue,
+            Err(e) => e,
+        };
+        let relative = |p: &Path| {
+            p.strip_prefix(&notes_dir)
+                .map(|r| r.to_string_lossy().to_string())
+                .unwrap_or_else(|_| p.to_string_lossy().to_string())
+        };
+        corrupt.push(CorruptNoteFile {
+            path: relative(entry.path()),
+            error,
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            recovery_candidates: recovery_candidates(entry.path())
+                .iter()
+                .map(|c| relative(c))
+                .collect(),
+        });
+    }
+
+    Ok(corrupt)
+}
+
+/// Restore a corrupt note (path relative to the notes directory, as returned by
+/// `scan_note_integrity`) from its `.json.tmp` copy.
+#[tauri::command]
+pub fn repair_note_from_history(app: AppHandle, path: String) -> Result<Note, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let relative = Path::new(&path);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err("Invalid note path".to_string());
+    }
+
+    let note = repair_note_file(&notes_dir.join(relative))?;
+    Ok(filesystem_note_to_note(note, &path))
+}
+
+// Folder structure checks
+
+/// A note file that still carries a `folder_path` from an older format which disagrees with
+/// the folder it's actually in. The location wins when notes are loaded; the stale field
+/// only confuses exports and older builds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderPathMismatch {
+    /// Path relative to the notes directory
+    pub path: String,
+    pub stored: Vec<String>,
+    pub actual: Vec<String>,
+}
+
+/// Several note files sharing one id. Saves and deletes only ever touch the first one found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateNoteId {
+    pub id: String,
+    pub paths: Vec<String>,
+}
+
+/// Structural problems in the notes directory, as found by `verify_notes_integrity`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotesIntegrityReport {
+    /// Folders with nothing in them but other empty folders, deepest first
+    pub empty_folders: Vec<String>,
+    /// Image directories in `note_contents` whose note no longer exists
+    pub orphaned_images: Vec<String>,
+    pub path_mismatches: Vec<FolderPathMismatch>,
+    pub duplicate_ids: Vec<DuplicateNoteId>,
+}
+
+/// Which problems `repair_notes_integrity` may fix. Everything is off unless asked for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotesRepairOptions {
+    #[serde(default)]
+    pub prune_empty_folders: bool,
+    #[serde(default)]
+    pub remove_orphaned_images: bool,
+    #[serde(default)]
+    pub fix_folder_paths: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotesRepairSummary {
+    pub folders_removed: u32,
+    pub image_dirs_removed: u32,
+    pub paths_fixed: u32,
+}
+
+fn relative_to(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Folders under `notes_dir` holding no files at any depth (a folder's metadata file aside),
+/// children before their parents so they can be removed in order.
+fn find_empty_folders(notes_dir: &Path) -> Vec<PathBuf> {
+    let mut empty: Vec<PathBuf> = Vec::new();
+    for entry in WalkDir::new(notes_dir)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+    {
+        let only_empty_folders = fs::read_dir(entry.path()).map_or(false, |entries| {
+            entries.flatten().all(|child| {
+                child.file_name() == FOLDER_META_FILE
+                    || empty.iter().any(|dir| *dir == child.path())
+            })
+        });
+        if only_empty_folders {
+            empty.push(entry.into_path());
         }
-        dir.push("note_contents");
-        if !dir.exists() {
-            fs::create_dir_all(&dir)
-                .map_err(|e| format!("Failed to create note_contents directory: {}", e))?;
+    }
+    empty
+}
+
+/// `folder_path` as saved inside the note file by older versions, if present.
+fn stored_folder_path(path: &Path) -> Option<Vec<String>> {
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    raw.get("folder_path")?.as_array().map(|parts| {
+        parts
+            .iter()
+            .filter_map(|p| p.as_str().map(str::to_string))
+            .collect()
+    })
+}
+
+/// Compare folder paths ignoring the `default` placeholder used for the root.
+fn same_folder_path(a: &[String], b: &[String]) -> bool {
+    let real = |parts: &[String]| -> Vec<String> {
+        parts.iter().filter(|p| *p != "default").cloned().collect()
+    };
+    real(a) == real(b)
+}
+
+fn check_notes_structure(notes_dir: &Path, images_dir: &Path) -> NotesIntegrityReport {
+    let mut report = NotesIntegrityReport {
+        empty_folders: find_empty_folders(notes_dir)
+            .iter()
+            .map(|dir| relative_to(notes_dir, dir))
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut paths_by_id: Vec<(String, Vec<String>)> = Vec::new();
+    for entry in WalkDir::new(notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+    {
+        // Unparseable files are reported by `scan_note_integrity`
+        let Ok(fs_note) = load_note_file(entry.path()) else {
+            continue;
+        };
+        let relative = relative_to(notes_dir, entry.path());
+        let actual = filesystem_note_to_note(fs_note.clone(), &relative).folder_path;
+        if let Some(stored) = stored_folder_path(entry.path()) {
+            if !same_folder_path(&stored, &actual) {
+                report.path_mismatches.push(FolderPathMismatch {
+                    path: relative.clone(),
+                    stored,
+                    actual,
+                });
+            }
+        }
+        match paths_by_id.iter_mut().find(|(id, _)| *id == fs_note.id) {
+            Some((_, paths)) => paths.push(relative),
+            None => paths_by_id.push((fs_note.id, vec![relative])),
         }
-        Ok(dir)
     }
+
+    let note_ids: HashSet<String> = paths_by_id.iter().map(|(id, _)| id.clone()).collect();
+    report.orphaned_images = orphaned_image_dirs(images_dir, &note_ids)
+        .iter()
+        .map(|dir| relative_to(images_dir, dir))
+        .collect();
+    report.duplicate_ids = paths_by_id
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(id, paths)| DuplicateNoteId { id, paths })
+        .collect();
+
+    report
+}
+
+fn repair_notes_structure(
+    notes_dir: &Path,
+    images_dir: &Path,
+    options: &NotesRepairOptions,
+) -> Result<NotesRepairSummary, String> {
+    let report = check_notes_structure(notes_dir, images_dir);
+    let mut summary = NotesRepairSummary::default();
+
+    if options.fix_folder_paths {
+        for mismatch in &report.path_mismatches {
+            let path = notes_dir.join(&mismatch.path);
+            // Re-saving drops the stale field; the location already decides the folder
+            save_note_file(&path, &load_note_file(&path)?)?;
+            summary.paths_fixed += 1;
+        }
+    }
+
+    if options.prune_empty_folders {
+        for folder in &report.empty_folders {
+            remove_empty_folder(&notes_dir.join(folder))
+                .map_err(|e| format!("Failed to remove empty folder {}: {}", folder, e))?;
+            summary.folders_removed += 1;
+        }
+    }
+
+    if options.remove_orphaned_images {
+        let note_ids = load_notes_from_dir(notes_dir)?
+            .into_iter()
+            .map(|note| note.id)
+            .collect();
+        summary.image_dirs_removed = remove_orphaned_image_dirs(images_dir, &note_ids);
+    }
+
+    Ok(summary)
+}
+
+/// Look for empty folders, image directories left behind by deleted notes, notes with a
+/// stale stored `folder_path`, and note ids used by more than one file. Nothing is changed.
+#[tauri::command]
+pub fn verify_notes_integrity(app: AppHandle) -> Result<NotesIntegrityReport, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let images_dir = get_notes_images_dir(&app)?;
+    Ok(check_notes_structure(&notes_dir, &images_dir))
+}
+
+/// Fix the problems `verify_notes_integrity` reports, limited to the categories enabled in
+/// `options`. Duplicate ids are only reported, since choosing which copy to keep is up to the
+/// user.
+#[tauri::command]
+pub fn repair_notes_integrity(
+    app: AppHandle,
+    options: NotesRepairOptions,
+) -> Result<NotesRepairSummary, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let images_dir = get_notes_images_dir(&app)?;
+    repair_notes_structure(&notes_dir, &images_dir, &options)
+}
+
+// Tag management
+
+/// Tags are compared case-insensitively; the first spelling seen is kept for display.
+fn tag_key(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Count tag usage across notes, grouping tags that differ only by case.
+fn collect_tag_counts(notes: &[Note]) -> Vec<(String, u32)> {
+    let mut counts: Vec<(String, u32)> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+    for note in notes {
+        let mut seen = std::collections::HashSet::new();
+        for tag in &note.tags {
+            let key = tag_key(tag);
+            if key.is_empty() || !seen.insert(key.clone()) {
+                continue;
+            }
+            match index.get(&key) {
+                Some(&i) => counts[i].1 += 1,
+                None => {
+                    index.insert(key, counts.len());
+                    counts.push((tag.trim().to_string(), 1));
+                }
+            }
+        }
+    }
+
+    counts.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+    });
+    counts
+}
+
+/// Pick the spelling a rename should end up with. If another tag already matches `new`
+/// case-insensitively it is merged into (keeping its spelling); otherwise `new` is used as given.
+fn resolve_rename_target(notes: &[Note], old: &str, new: &str) -> String {
+    let old_key = tag_key(old);
+    let new_key = tag_key(new);
+    if old_key == new_key {
+        return new.trim().to_string();
+    }
+    notes
+        .iter()
+        .flat_map(|n| n.tags.iter())
+        .find(|t| tag_key(t) == new_key)
+        .map(|t| t.trim().to_string())
+        .unwrap_or_else(|| new.trim().to_string())
+}
+
+/// Replace `old` with `target` in a note's tags, dropping duplicates created by the merge.
+/// Returns true if the tags changed.
+fn rename_tag_in_note(tags: &mut Vec<String>, old: &str, target: &str) -> bool {
+    let old_key = tag_key(old);
+    let target_key = tag_key(target);
+    if !tags.iter().any(|t| tag_key(t) == old_key) {
+        return false;
+    }
+
+    let mut renamed = Vec::with_capacity(tags.len());
+    let mut seen = std::collections::HashSet::new();
+    for tag in tags.iter() {
+        let key = tag_key(tag);
+        let (value, key) = if key == old_key || key == target_key {
+            (target.to_string(), target_key.clone())
+        } else {
+            (tag.clone(), key)
+        };
+        if seen.insert(key) {
+            renamed.push(value);
+        }
+    }
+
+    let changed = *tags != renamed;
+    *tags = renamed;
+    changed
+}
+
+/// Apply `update` to the tags of every note on disk, re-saving the ones that changed.
+/// Returns the number of notes updated.
+fn update_note_tags<F>(app: &AppHandle, mut update: F) -> Result<u32, String>
+where
+    F: FnMut(&mut Vec<String>) -> bool,
+{
+    let notes_dir = get_notes_directory(app)?;
+    let mut updated = 0;
+
+    for entry in WalkDir::new(&notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+    {
+        let mut fs_note = match load_note_file(entry.path()) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!("Failed to load note {}: {}", entry.path().display(), e);
+                continue;
+            }
+        };
+        if update(&mut fs_note.tags) {
+            fs_note.updated_at = Utc::now().to_rfc3339();
+            save_note_file(entry.path(), &fs_note)?;
+            updated += 1;
+        }
+    }
+
+    Ok(updated)
+}
+
+/// All tags in use with the number of notes carrying each, most used first.
+#[tauri::command]
+pub fn list_all_tags(app: AppHandle) -> Result<Vec<(String, u32)>, String> {
+    let notes = load_notes_filesystem(app)?;
+    Ok(collect_tag_counts(&notes))
+}
+
+/// Rename a tag on every note. If `new_tag` matches an existing tag (ignoring case) the two
+/// are merged. Returns the number of notes updated.
+#[tauri::command]
+pub fn rename_tag(app: AppHandle, old_tag: String, new_tag: String) -> Result<u32, String> {
+    if tag_key(&new_tag).is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+    let notes = load_notes_filesystem(app.clone())?;
+    let target = resolve_rename_target(&notes, &old_tag, &new_tag);
+    update_note_tags(&app, |tags| rename_tag_in_note(tags, &old_tag, &target))
+}
+
+/// Remove a tag (ignoring case) from every note. Returns the number of notes updated.
+#[tauri::command]
+pub fn delete_tag(app: AppHandle, tag: String) -> Result<u32, String> {
+    let key = tag_key(&tag);
+    update_note_tags(&app, |tags| {
+        let before = tags.len();
+        tags.retain(|t| tag_key(t) != key);
+        tags.len() != before
+    })
+}
+
+// Image handling functions
+
+pub(crate) fn get_notes_images_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    // Get the current profile
+    let profile = profiles::ProfileManager::get_current_profile()
+        .ok_or_else(|| "No active profile. Please log in first.".to_string())?;
+
+    let mut dir = data_dir::app_data_dir()?;
+    dir.push("profiles");
+    dir.push(&profile.id);
+    dir.push("note_contents");
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create note_contents directory: {}", e))?;
+    }
+    Ok(dir)
 }
 
 #[tauri::command]
@@ -851,7 +1842,7 @@ pub fn save_image_from_base64_filesystem(
     let images_dir = get_notes_images_dir(&app)?;
 
     // Create note-specific directory
-    let note_images_dir = images_dir.join(&note_id);
+    let note_images_dir = images_dir.join(sanitization::sanitize_path_component(&note_id));
     if !note_images_dir.exists() {
         fs::create_dir_all(&note_images_dir)
             .map_err(|e| format!("Failed to create note images directory: {}", e))?;
@@ -859,24 +1850,30 @@ pub fn save_image_from_base64_filesystem(
 
     // Generate unique filename
     let timestamp = chrono::Utc::now().timestamp_millis();
-    let file_extension = filename.split('.').last().unwrap_or("png");
-    let unique_filename = format!(
-        "{}_{}.{}",
+    let filename = sanitization::sanitize_filename(&filename);
+    let file_extension = filename.rsplit('.').next().unwrap_or("png");
+    let stem = format!(
+        "{}_{}",
         timestamp,
-        filename.replace(".", "_"),
-        file_extension
+        sanitization::sanitize_path_component(&filename.replace('.', "_"))
     );
 
-    let image_path = note_images_dir.join(&unique_filename);
-
-    // Write image to file
-    let mut file =
-        File::create(&image_path).map_err(|e| format!("Failed to create image file: {}", e))?;
-    file.write_all(&image_bytes)
-        .map_err(|e| format!("Failed to write image data: {}", e))?;
+    // Write image to file, as WebP if enabled and smaller
+    let encoding = notes_images::ImageEncoding::from_settings(&settings::Settings::load());
+    let unique_filename = notes_images::store_note_image(
+        &note_images_dir,
+        &stem,
+        file_extension,
+        &image_bytes,
+        encoding,
+    )?;
 
     // Return relative path for storage in note content
-    let relative_path = format!("note_contents/{}/{}", note_id, unique_filename);
+    let relative_path = format!(
+        "note_contents/{}/{}",
+        sanitization::sanitize_path_component(&note_id),
+        unique_filename
+    );
     Ok(relative_path)
 }
 
@@ -887,13 +1884,7 @@ pub fn get_image_path_filesystem(_app: AppHandle, relative_path: String) -> Resu
         .ok_or_else(|| "No active profile. Please log in first.".to_string())?;
     
     // Get the base directory for the current profile
-    #[cfg(target_os = "android")]
-    let mut base_dir = PathBuf::from("/data/data/com.desqta.app/files");
-    
-    #[cfg(not(target_os = "android"))]
-    let mut base_dir = dirs_next::data_dir().ok_or_else(|| "Unable to determine data dir".to_string())?;
-    
-    base_dir.push("DesQTA");
+    let mut base_dir = data_dir::app_data_dir()?;
     base_dir.push("profiles");
     base_dir.push(&profile.id);
 
@@ -915,16 +1906,7 @@ pub fn get_image_as_base64_filesystem(
     relative_path: String,
 ) -> Result<String, String> {
     // Get the base notes directory
-    #[cfg(target_os = "android")]
-    let base_dir = PathBuf::from("/data/data/com.desqta.app/files");
-
-    #[cfg(not(target_os = "android"))]
-    let base_dir = {
-        let mut dir =
-            dirs_next::data_dir().ok_or_else(|| "Unable to determine data dir".to_string())?;
-        dir.push("DesQTA");
-        dir
-    };
+    let base_dir = data_dir::app_data_dir()?;
 
     let full_path = base_dir.join(&relative_path);
 
@@ -962,7 +1944,7 @@ pub fn get_image_as_base64_filesystem(
 #[tauri::command]
 pub fn delete_note_images_filesystem(app: AppHandle, note_id: String) -> Result<(), String> {
     let images_dir = get_notes_images_dir(&app)?;
-    let note_images_dir = images_dir.join(&note_id);
+    let note_images_dir = images_dir.join(sanitization::sanitize_path_component(&note_id));
 
     if note_images_dir.exists() {
         fs::remove_dir_all(&note_images_dir)
@@ -972,6 +1954,40 @@ pub fn delete_note_images_filesystem(app: AppHandle, note_id: String) -> Result<
     Ok(())
 }
 
+/// Entries in `images_dir` that don't belong to any of `note_ids`.
+fn orphaned_image_dirs(images_dir: &Path, note_ids: &HashSet<String>) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(images_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .map_or(false, |name| !note_ids.contains(name))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Delete the image directories of notes that no longer exist. Returns how many were removed.
+fn remove_orphaned_image_dirs(images_dir: &Path, note_ids: &HashSet<String>) -> u32 {
+    let mut deleted_count = 0;
+    for path in orphaned_image_dirs(images_dir, note_ids) {
+        if let Err(e) = fs::remove_dir_all(&path) {
+            eprintln!(
+                "Failed to delete unused image directory {}: {}",
+                path.display(),
+                e
+            );
+        } else {
+            deleted_count += 1;
+        }
+    }
+    deleted_count
+}
+
 #[tauri::command]
 pub fn cleanup_unused_images_filesystem(app: AppHandle) -> Result<u32, String> {
     let notes = load_notes_filesystem(app.clone())?;
@@ -981,32 +1997,10 @@ pub fn cleanup_unused_images_filesystem(app: AppHandle) -> Result<u32, String> {
         return Ok(0);
     }
 
-    let mut deleted_count = 0;
-
     // Get all note IDs that still exist
-    let existing_note_ids: std::collections::HashSet<String> =
-        notes.iter().map(|n| n.id.clone()).collect();
-
-    // Iterate through image directories
-    if let Ok(entries) = fs::read_dir(&images_dir) {
-        for entry in entries.flatten() {
-            if let Some(dir_name) = entry.file_name().to_str() {
-                // If this directory doesn't correspond to an existing note, delete it
-                if !existing_note_ids.contains(dir_name) {
-                    if let Err(e) = fs::remove_dir_all(entry.path()) {
-                        eprintln!(
-                            "Failed to delete unused image directory {}: {}",
-                            dir_name, e
-                        );
-                    } else {
-                        deleted_count += 1;
-                    }
-                }
-            }
-        }
-    }
+    let existing_note_ids: HashSet<String> = notes.iter().map(|n| n.id.clone()).collect();
 
-    Ok(deleted_count)
+    Ok(remove_orphaned_image_dirs(&images_dir, &existing_note_ids))
 }
 
 // Backup and utility functions
@@ -1045,26 +2039,183 @@ fn count_folders(items: &[FileTreeItem]) -> usize {
     count
 }
 
+/// A note as it appears in the insights report.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteInsightEntry {
+    pub id: String,
+    pub title: String,
+    pub folder_path: Vec<String>,
+    pub word_count: u32,
+    pub reference_count: u32,
+    pub last_accessed: Option<String>,
+    /// Whole days since the note was last opened; None if the timestamp couldn't be parsed
+    pub days_since_accessed: Option<i64>,
+}
+
+/// Overview of a profile's notes for the notes dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotesInsights {
+    pub total_notes: usize,
+    pub total_words: u32,
+    pub stale_days: u32,
+    /// Longest notes by word count, largest first
+    pub longest_notes: Vec<NoteInsightEntry>,
+    /// Notes not opened within `stale_days`, least recently accessed first
+    pub stale_notes: Vec<NoteInsightEntry>,
+    /// Notes whose access time is missing or malformed
+    pub unknown_access_count: usize,
+    /// Notes with the most SEQTA references, most first
+    pub most_referenced_notes: Vec<NoteInsightEntry>,
+    /// Tag usage across all notes, most used first
+    pub tag_frequency: Vec<(String, u32)>,
+}
+
+fn parse_note_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn insight_entry(note: &Note, now: DateTime<Utc>) -> NoteInsightEntry {
+    let accessed = parse_note_timestamp(&note.last_accessed);
+    NoteInsightEntry {
+        id: note.id.clone(),
+        title: note.title.clone(),
+        folder_path: note.folder_path.clone(),
+        word_count: note.metadata.word_count,
+        reference_count: note.seqta_references.len() as u32,
+        last_accessed: accessed.map(|dt| dt.to_rfc3339()),
+        days_since_accessed: accessed.map(|dt| (now - dt).num_days().max(0)),
+    }
+}
+
+fn compute_notes_insights(
+    notes: &[Note],
+    now: DateTime<Utc>,
+    stale_days: u32,
+    limit: usize,
+) -> NotesInsights {
+    let entries: Vec<NoteInsightEntry> = notes.iter().map(|n| insight_entry(n, now)).collect();
+
+    let mut longest_notes: Vec<NoteInsightEntry> = entries
+        .iter()
+        .filter(|e| e.word_count > 0)
+        .cloned()
+        .collect();
+    longest_notes.sort_by(|a, b| b.word_count.cmp(&a.word_count).then(a.title.cmp(&b.title)));
+    longest_notes.truncate(limit);
+
+    let mut stale_notes: Vec<NoteInsightEntry> = entries
+        .iter()
+        .filter(|e| {
+            e.days_since_accessed
+                .map_or(false, |days| days >= stale_days as i64)
+        })
+        .cloned()
+        .collect();
+    stale_notes.sort_by(|a, b| b.days_since_accessed.cmp(&a.days_since_accessed));
+    stale_notes.truncate(limit);
+
+    let mut most_referenced_notes: Vec<NoteInsightEntry> = entries
+        .iter()
+        .filter(|e| e.reference_count > 0)
+        .cloned()
+        .collect();
+    most_referenced_notes.sort_by(|a, b| {
+        b.reference_count
+            .cmp(&a.reference_count)
+            .then(a.title.cmp(&b.title))
+    });
+    most_referenced_notes.truncate(limit);
+
+    NotesInsights {
+        total_notes: notes.len(),
+        total_words: notes.iter().map(|n| n.metadata.word_count).sum(),
+        stale_days,
+        longest_notes,
+        stale_notes,
+        unknown_access_count: entries
+            .iter()
+            .filter(|e| e.days_since_accessed.is_none())
+            .count(),
+        most_referenced_notes,
+        tag_frequency: collect_tag_counts(notes),
+    }
+}
+
+/// Longest, stale and most-referenced notes plus tag frequencies. `stale_days` defaults to 30
+/// and each list is capped at `limit` (default 10) entries.
 #[tauri::command]
-pub fn backup_notes_filesystem(app: AppHandle) -> Result<String, String> {
+pub fn get_notes_insights(
+    app: AppHandle,
+    stale_days: Option<u32>,
+    limit: Option<usize>,
+) -> Result<NotesInsights, String> {
+    let notes = load_notes_filesystem(app)?;
+    Ok(compute_notes_insights(
+        &notes,
+        Utc::now(),
+        stale_days.unwrap_or(30),
+        limit.unwrap_or(10),
+    ))
+}
+
+const NOTES_BACKUP_PREFIX: &str = "notes_filesystem_backup_";
+/// Backups written by the scheduler. Only these are pruned.
+const NOTES_AUTO_BACKUP_PREFIX: &str = "notes_filesystem_autobackup_";
+/// Hash of the notes in the most recent backup, so unchanged notes aren't backed up again.
+const LAST_BACKUP_HASH_FILE: &str = "last_notes_backup.sha256";
+
+/// Location: `$DATA_DIR/DesQTA/profiles/{profile_id}/backups`
+fn notes_backup_dir() -> PathBuf {
+    let mut dir = profiles::get_profile_dir(
+        &profiles::ProfileManager::get_current_profile()
+            .map(|p| p.id)
+            .unwrap_or_else(|| "default".to_string()),
+    );
+    dir.push("backups");
+    dir
+}
+
+/// Hash of the backed-up content (notes and tree), independent of note load order.
+fn notes_backup_hash(notes: &[Note], file_tree: &[FileTreeItem]) -> Result<String, String> {
+    let mut sorted: Vec<&Note> = notes.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+    let content = serde_json::to_vec(&serde_json::json!({
+        "notes": sorted,
+        "file_tree": file_tree,
+    }))
+    .map_err(|e| format!("Failed to serialize notes: {}", e))?;
+    let mut hasher = Sha256::new();
+    hasher.update(&content);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Write a backup of every note and the folder tree. An `automatic` backup is skipped
+/// (returning `None`) when the notes match the last backup, and is subject to pruning.
+pub fn create_notes_backup(app: &AppHandle, automatic: bool) -> Result<Option<PathBuf>, String> {
     let notes = load_notes_filesystem(app.clone())?;
-    let file_tree = get_file_tree(app)?;
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let file_tree = get_file_tree(app.clone())?;
+    let backup_dir = notes_backup_dir();
+    let hash = notes_backup_hash(&notes, &file_tree)?;
+    let hash_file = backup_dir.join(LAST_BACKUP_HASH_FILE);
+    if automatic && fs::read_to_string(&hash_file).ok().as_deref() == Some(hash.as_str()) {
+        return Ok(None);
+    }
 
-    #[cfg(target_os = "android")]
-    let backup_dir = PathBuf::from("/data/data/com.desqta.app/files/DesQTA/backups");
-    #[cfg(not(target_os = "android"))]
-    let backup_dir = dirs_next::data_dir()
-        .ok_or_else(|| "Unable to determine data dir".to_string())?
-        .join("DesQTA")
-        .join("backups");
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
 
     if !backup_dir.exists() {
         fs::create_dir_all(&backup_dir)
             .map_err(|e| format!("Failed to create backup dir: {}", e))?;
     }
 
-    let backup_file = backup_dir.join(format!("notes_filesystem_backup_{}.json", timestamp));
+    let prefix = if automatic {
+        NOTES_AUTO_BACKUP_PREFIX
+    } else {
+        NOTES_BACKUP_PREFIX
+    };
+    let backup_file = backup_dir.join(format!("{}{}.json", prefix, timestamp));
 
     // Create backup structure
     let backup_data = serde_json::json!({
@@ -1077,12 +2228,95 @@ pub fn backup_notes_filesystem(app: AppHandle) -> Result<String, String> {
 
     let json = serde_json::to_string_pretty(&backup_data)
         .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    let json = notes_encryption::encrypt_backup(&json)?;
+    crate::disk_space::ensure_free_space(&backup_dir, json.len() as u64, "notes backup")?;
 
     let mut file =
         File::create(&backup_file).map_err(|e| format!("Failed to create backup file: {}", e))?;
     file.write_all(json.as_bytes())
         .map_err(|e| format!("Failed to write backup file: {}", e))?;
+    let _ = fs::write(&hash_file, &hash);
+
+    Ok(Some(backup_file))
+}
+
+/// Write back the color, icon and display name of every folder in a backed-up tree,
+/// recreating folders that carry metadata.
+fn restore_folder_metadata(notes_dir: &Path, items: &[FileTreeItem]) {
+    for item in items.iter().filter(|item| item.item_type == "folder") {
+        let meta = FolderMetadata {
+            color: item.color.clone(),
+            icon: item.icon.clone(),
+            display_name: item.display_name.clone(),
+        };
+        if meta != FolderMetadata::default() {
+            if let Ok(dir) = resolve_notes_folder(notes_dir, &item.path) {
+                if fs::create_dir_all(&dir).is_ok() {
+                    let _ = write_folder_metadata(&dir, &meta);
+                }
+            }
+        }
+        if let Some(children) = &item.children {
+            restore_folder_metadata(notes_dir, children);
+        }
+    }
+}
+
+/// Notes backups in `dir` whose name starts with `prefix`, oldest first (the timestamped
+/// names sort chronologically).
+fn list_notes_backups(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .map_or(false, |n| n.starts_with(prefix) && n.ends_with(".json"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort();
+    backups
+}
+
+/// Every notes backup, manual and automatic, of the profile whose notes are in `notes_dir`.
+pub(crate) fn notes_backups_for(notes_dir: &Path) -> Vec<PathBuf> {
+    let backup_dir = notes_dir.parent().unwrap_or(notes_dir).join("backups");
+    [NOTES_BACKUP_PREFIX, NOTES_AUTO_BACKUP_PREFIX]
+        .iter()
+        .flat_map(|prefix| list_notes_backups(&backup_dir, prefix))
+        .collect()
+}
 
+/// When the newest automatic notes backup was written, if there is one.
+pub fn last_notes_backup_time() -> Option<std::time::SystemTime> {
+    let newest = list_notes_backups(&notes_backup_dir(), NOTES_AUTO_BACKUP_PREFIX).pop()?;
+    fs::metadata(newest).and_then(|m| m.modified()).ok()
+}
+
+fn prune_backups_in(dir: &Path, max_backups: usize) -> Result<usize, String> {
+    let backups = list_notes_backups(dir, NOTES_AUTO_BACKUP_PREFIX);
+    let excess = backups.len().saturating_sub(max_backups.max(1));
+    for path in &backups[..excess] {
+        fs::remove_file(path).map_err(|e| format!("Failed to remove old backup: {}", e))?;
+    }
+    Ok(excess)
+}
+
+/// Delete the oldest automatic notes backups of the active profile until at most
+/// `max_backups` remain. Manual backups are never removed, and at least one automatic backup
+/// is always kept. Returns how many were removed.
+pub fn prune_notes_backups(max_backups: usize) -> Result<usize, String> {
+    prune_backups_in(&notes_backup_dir(), max_backups)
+}
+
+#[tauri::command]
+pub fn backup_notes_filesystem(app: AppHandle) -> Result<String, String> {
+    let backup_file =
+        create_notes_backup(&app, false)?.ok_or_else(|| "Failed to create backup".to_string())?;
     Ok(backup_file.to_string_lossy().to_string())
 }
 
@@ -1101,6 +2335,9 @@ pub fn restore_notes_from_backup_filesystem(
     let mut contents = String::new();
     file.read_to_string(&mut contents)
         .map_err(|e| format!("Failed to read backup file: {}", e))?;
+    // Loads the encryption state, so an encrypted backup can be opened
+    let notes_dir = get_notes_directory(&app)?;
+    let contents = notes_encryption::decrypt_backup(&contents)?;
 
     let backup_data: serde_json::Value = serde_json::from_str(&contents)
         .map_err(|e| format!("Failed to parse backup JSON: {}", e))?;
@@ -1116,8 +2353,7 @@ pub fn restore_notes_from_backup_filesystem(
         )
         .map_err(|e| format!("Failed to parse notes from backup: {}", e))?;
 
-        // Get notes directory and clear it
-        let notes_dir = get_notes_directory(&app)?;
+        // Clear the notes directory
         if notes_dir.exists() {
             fs::remove_dir_all(&notes_dir)
                 .map_err(|e| format!("Failed to clear notes directory: {}", e))?;
@@ -1131,6 +2367,14 @@ pub fn restore_notes_from_backup_filesystem(
         for note in notes {
             save_note_filesystem(app.clone(), note)?;
         }
+
+        // Older backups have no folder metadata in their tree, which leaves the defaults
+        if let Some(Ok(tree)) = backup_data
+            .get("file_tree")
+            .map(|tree| serde_json::from_value::<Vec<FileTreeItem>>(tree.clone()))
+        {
+            restore_folder_metadata(&notes_dir, &tree);
+        }
     } else {
         return Err(
             "This backup file is not compatible with the filesystem storage system".to_string(),
@@ -1140,6 +2384,54 @@ pub fn restore_notes_from_backup_filesystem(
     Ok(())
 }
 
+/// Locate the file holding `note_id`.
+fn find_note_file(notes_dir: &Path, note_id: &str) -> Option<(PathBuf, FileSystemNote)> {
+    WalkDir::new(notes_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_note_file(e.path()))
+        .find_map(|entry| {
+            let fs_note = load_note_file(entry.path()).ok()?;
+            (fs_note.id == note_id).then(|| (entry.path().to_path_buf(), fs_note))
+        })
+}
+
+/// Rewrite only `last_accessed` in place. The file keeps its name and location, and
+/// `updated_at` is left alone, so opening a note never counts as editing it.
+fn touch_note_file(path: &Path, mut fs_note: FileSystemNote, accessed_at: &str) -> Result<(), String> {
+    fs_note.last_accessed = accessed_at.to_string();
+    save_note_file(path, &fs_note)
+}
+
+/// Notes ordered by `last_accessed`, most recent first. Notes that were never opened (or have
+/// an unparseable timestamp) are left out.
+fn sort_recent_notes(notes: Vec<Note>, limit: usize) -> Vec<Note> {
+    let mut recent: Vec<(DateTime<Utc>, Note)> = notes
+        .into_iter()
+        .filter_map(|note| Some((parse_note_timestamp(&note.last_accessed)?, note)))
+        .collect();
+    recent.sort_by(|a, b| b.0.cmp(&a.0));
+    recent.into_iter().take(limit).map(|(_, note)| note).collect()
+}
+
+/// Record that a note was opened. Returns the new `last_accessed` timestamp.
+#[tauri::command]
+pub fn touch_note_accessed(app: AppHandle, note_id: String) -> Result<String, String> {
+    let notes_dir = get_notes_directory(&app)?;
+    let (path, fs_note) =
+        find_note_file(&notes_dir, &note_id).ok_or_else(|| "Note not found".to_string())?;
+    let accessed_at = Utc::now().to_rfc3339();
+    touch_note_file(&path, fs_note, &accessed_at)?;
+    Ok(accessed_at)
+}
+
+/// Most recently opened notes, newest first (default 10).
+#[tauri::command]
+pub fn get_recent_notes(app: AppHandle, limit: Option<usize>) -> Result<Vec<Note>, String> {
+    let notes = load_notes_filesystem(app)?;
+    Ok(sort_recent_notes(notes, limit.unwrap_or(10)))
+}
+
 // Get a specific note by ID
 #[tauri::command]
 pub fn get_note_filesystem(app: AppHandle, note_id: String) -> Result<Option<Note>, String> {
@@ -1161,8 +2453,12 @@ pub fn load_folders_filesystem(app: AppHandle) -> Result<Vec<FileSystemFolder>,
                     id: item.name.clone(), // Use folder name as ID
                     name: item.name.clone(),
                     path: item.path.clone(),
-                    color: None,
-                    icon: Some("📁".to_string()),
+                    color: item.color.clone(),
+                    icon: item
+                        .icon
+                        .clone()
+                        .or_else(|| Some(DEFAULT_FOLDER_ICON.to_string())),
+                    display_name: item.display_name.clone(),
                     created_at: item.modified.clone(),
                     updated_at: item.modified.clone(),
                 });
@@ -1177,3 +2473,594 @@ pub fn load_folders_filesystem(app: AppHandle) -> Result<Vec<FileSystemFolder>,
     extract_folders(&file_tree, &mut folders);
     Ok(folders)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_with_tags(tags: &[&str]) -> Note {
+        Note {
+            id: Uuid::new_v4().to_string(),
+            title: "Test".to_string(),
+            content: String::new(),
+            folder_path: vec!["default".to_string()],
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            seqta_references: vec![],
+            created_at: String::new(),
+            updated_at: String::new(),
+            last_accessed: String::new(),
+            metadata: NoteMetadata {
+                word_count: 0,
+                character_count: 0,
+                reading_time: 0,
+                last_auto_save: None,
+                version: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_rename_tag_merges_into_existing() {
+        let mut notes = vec![
+            note_with_tags(&["maths", "exam"]),
+            note_with_tags(&["Mathematics"]),
+            note_with_tags(&["Maths", "mathematics"]),
+        ];
+        let target = resolve_rename_target(&notes, "maths", "MATHEMATICS");
+        assert_eq!(target, "Mathematics");
+
+        for note in notes.iter_mut() {
+            rename_tag_in_note(&mut note.tags, "maths", &target);
+        }
+        assert_eq!(notes[0].tags, vec!["Mathematics", "exam"]);
+        assert_eq!(notes[1].tags, vec!["Mathematics"]);
+        assert_eq!(notes[2].tags, vec!["Mathematics"]);
+
+        let counts = collect_tag_counts(&notes);
+        assert_eq!(counts[0], ("Mathematics".to_string(), 3));
+        assert_eq!(counts[1], ("exam".to_string(), 1));
+    }
+
+    #[test]
+    fn test_repair_note_from_tmp_copy() {
+        let dir = std::env::temp_dir().join(format!("desqta-notes-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Physics.json");
+
+        let note = note_to_filesystem_note(note_with_tags(&["physics"]));
+        fs::write(&path, "{\"id\": \"trunc").unwrap();
+        assert!(repair_note_file(&path).is_err());
+
+        let json = serde_json::to_string(&note).unwrap();
+        fs::write(note_sidecar_path(&path, "tmp"), json).unwrap();
+        let repaired = repair_note_file(&path).unwrap();
+
+        assert_eq!(repaired.id, note.id);
+        assert_eq!(load_note_file(&path).unwrap().tags, vec!["physics"]);
+        assert!(note_sidecar_path(&path, "corrupt").exists());
+        assert!(!note_sidecar_path(&path, "tmp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_verify_and_repair_notes_structure() {
+        let root = std::env::temp_dir().join(format!("desqta-integrity-{}", Uuid::new_v4()));
+        let (notes_dir, images_dir) = (root.join("notes"), root.join("note_contents"));
+        for dir in ["Maths", "Science", "Copy", "Empty/Nested"] {
+            fs::create_dir_all(notes_dir.join(dir)).unwrap();
+        }
+        let kept = note_with_tags(&[]);
+        let kept_fs = note_to_filesystem_note(kept.clone());
+        save_note_file(&notes_dir.join("Maths/Algebra.json"), &kept_fs).unwrap();
+        save_note_file(&notes_dir.join("Copy/Algebra.json"), &kept_fs).unwrap();
+        let mut stale = serde_json::to_value(note_to_filesystem_note(note_with_tags(&[]))).unwrap();
+        stale["folder_path"] = serde_json::json!(["History"]);
+        fs::write(notes_dir.join("Science/Cells.json"), stale.to_string()).unwrap();
+        for dir in [kept.id.as_str(), "deleted-note"] {
+            fs::create_dir_all(images_dir.join(dir)).unwrap();
+            fs::write(images_dir.join(dir).join("image.png"), b"png").unwrap();
+        }
+
+        let report = check_notes_structure(&notes_dir, &images_dir);
+        assert_eq!(report.empty_folders, vec!["Empty/Nested", "Empty"]);
+        assert_eq!(report.orphaned_images, vec!["deleted-note"]);
+        assert_eq!(report.path_mismatches.len(), 1);
+        assert_eq!(report.path_mismatches[0].stored, vec!["History"]);
+        assert_eq!(report.path_mismatches[0].actual, vec!["Science"]);
+        assert_eq!(report.duplicate_ids.len(), 1);
+        assert_eq!(report.duplicate_ids[0].paths.len(), 2);
+
+        // Nothing is touched unless asked for
+        let summary =
+            repair_notes_structure(&notes_dir, &images_dir, &NotesRepairOptions::default())
+                .unwrap();
+        assert_eq!(summary.folders_removed + summary.image_dirs_removed, 0);
+        assert!(notes_dir.join("Empty/Nested").exists());
+
+        let only_paths = NotesRepairOptions {
+            fix_folder_paths: true,
+            ..Default::default()
+        };
+        let summary = repair_notes_structure(&notes_dir, &images_dir, &only_paths).unwrap();
+        assert_eq!(summary.paths_fixed, 1);
+        assert!(stored_folder_path(&notes_dir.join("Science/Cells.json")).is_none());
+        assert!(images_dir.join("deleted-note").exists());
+
+        let only_folders = NotesRepairOptions {
+            prune_empty_folders: true,
+            ..Default::default()
+        };
+        let summary = repair_notes_structure(&notes_dir, &images_dir, &only_folders).unwrap();
+        assert_eq!(summary.folders_removed, 2);
+        assert!(!notes_dir.join("Empty").exists());
+        assert!(notes_dir.join("Maths/Algebra.json").exists());
+
+        let only_images = NotesRepairOptions {
+            remove_orphaned_images: true,
+            ..Default::default()
+        };
+        let summary = repair_notes_structure(&notes_dir, &images_dir, &only_images).unwrap();
+        assert_eq!(summary.image_dirs_removed, 1);
+        assert!(images_dir.join(&kept.id).join("image.png").exists());
+
+        let report = check_notes_structure(&notes_dir, &images_dir);
+        assert!(report.empty_folders.is_empty() && report.orphaned_images.is_empty());
+        assert!(report.path_mismatches.is_empty());
+        assert_eq!(report.duplicate_ids.len(), 1);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_rename_tag_case_only_change() {
+        let mut note = note_with_tags(&["english", "essay"]);
+        let target = resolve_rename_target(&[note.clone()], "english", "English");
+        assert!(rename_tag_in_note(&mut note.tags, "english", &target));
+        assert_eq!(note.tags, vec!["English", "essay"]);
+        assert!(!rename_tag_in_note(&mut note.tags, "history", "History"));
+    }
+
+    #[test]
+    fn test_search_multibyte_content() {
+        let mut note = note_with_tags(&["café"]);
+        note.title = "Café — 😀 notes".to_string();
+        note.content = format!(
+            "<p>{}Met the café owner — 😀 great coffee{}</p>",
+            "Ünïcödé — ".repeat(8),
+            " 😀 é —".repeat(12)
+        );
+
+        for query in ["café", "—", "😀", "owner", "coffee é"] {
+            let query_lower = query.to_lowercase();
+            let terms: Vec<&str> = query_lower.split_whitespace().collect();
+            let (score, matches) = score_note(&note, &query_lower, &terms);
+            assert!(score > 0.0, "no match for {:?}", query);
+            for m in matches.iter().filter(|m| m.field != "tags") {
+                let snippet = m.snippet.to_lowercase();
+                assert!(terms.iter().any(|t| snippet.contains(t)), "{}", snippet);
+            }
+        }
+
+        let (_, matches) = score_note(&note, "owner", &["owner"]);
+        assert!(matches[0].snippet.starts_with("...") && matches[0].snippet.ends_with("..."));
+    }
+
+    #[test]
+    fn test_notes_insights_reports() {
+        let now = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let reference = SeqtaReference {
+            ref_type: "subject".to_string(),
+            id: "1".to_string(),
+            display_name: "Maths".to_string(),
+            cached_data: None,
+            last_synced: None,
+        };
+
+        let mut old = note_with_tags(&["maths"]);
+        old.title = "Old".to_string();
+        old.last_accessed = "2024-04-01T12:00:00Z".to_string();
+        old.metadata.word_count = 50;
+
+        let mut recent = note_with_tags(&["Maths", "exam"]);
+        recent.title = "Recent".to_string();
+        recent.last_accessed = "2024-05-30T12:00:00Z".to_string();
+        recent.metadata.word_count = 500;
+        recent.seqta_references = vec![reference.clone(), reference];
+
+        let mut broken = note_with_tags(&[]);
+        broken.last_accessed = "not a date".to_string();
+
+        let insights = compute_notes_insights(&[old, recent, broken], now, 30, 10);
+        assert_eq!(insights.total_notes, 3);
+        assert_eq!(insights.total_words, 550);
+        assert_eq!(insights.longest_notes[0].title, "Recent");
+        assert_eq!(insights.stale_notes.len(), 1);
+        assert_eq!(insights.stale_notes[0].title, "Old");
+        assert_eq!(insights.stale_notes[0].days_since_accessed, Some(61));
+        assert_eq!(insights.unknown_access_count, 1);
+        assert_eq!(insights.most_referenced_notes.len(), 1);
+        assert_eq!(insights.most_referenced_notes[0].reference_count, 2);
+        assert_eq!(insights.tag_frequency[0], ("maths".to_string(), 2));
+    }
+
+    #[test]
+    fn test_touch_note_moves_it_to_top_of_recent() {
+        let dir = std::env::temp_dir().join(format!("desqta-notes-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut first = note_with_tags(&[]);
+        first.title = "First".to_string();
+        first.content = "<p>first</p>".to_string();
+        first.updated_at = "2024-01-01T00:00:00+00:00".to_string();
+        first.last_accessed = "2024-05-01T00:00:00+00:00".to_string();
+        let mut second = note_with_tags(&[]);
+        second.title = "Second".to_string();
+        second.last_accessed = "2024-05-02T00:00:00+00:00".to_string();
+        for note in [&first, &second] {
+            let path = dir.join(format!("{}.json", note.title));
+            save_note_file(&path, &note_to_filesystem_note(note.clone())).unwrap();
+        }
+
+        let (path, fs_note) = find_note_file(&dir, &first.id).unwrap();
+        touch_note_file(&path, fs_note, "2024-05-03T00:00:00+00:00").unwrap();
+
+        let touched = load_note_file(&path).unwrap();
+        assert_eq!(touched.content, first.content);
+        assert_eq!(touched.updated_at, first.updated_at);
+        assert_eq!(touched.last_accessed, "2024-05-03T00:00:00+00:00");
+
+        let notes: Vec<Note> = [&path, &dir.join("Second.json")]
+            .iter()
+            .map(|p| filesystem_note_to_note(load_note_file(p).unwrap(), "x.json"))
+            .collect();
+        let recent = sort_recent_notes(notes, 10);
+        assert_eq!(recent[0].id, first.id);
+        assert_eq!(recent[1].id, second.id);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recompute_metadata_with_entities_and_mentions() {
+        let mut note = note_with_tags(&[]);
+        note.content = concat!(
+            "<p>Fish&nbsp;&amp;&nbsp;chips</p><p>see ",
+            r#"<span data-type="mention" data-id="42" data-mention-type="assessment">@Essay Draft</span>"#,
+            "</p>"
+        )
+        .to_string();
+
+        assert!(recompute_note_metadata(&mut note));
+        // "Fish", "&", "chips", "see", "@Essay", "Draft"
+        assert_eq!(note.metadata.word_count, 6);
+        assert_eq!(
+            note.metadata.character_count,
+            "Fish\u{a0}&\u{a0}chipssee @Essay Draft".chars().count() as u32
+        );
+        assert_eq!(note.metadata.reading_time, 1);
+        assert_eq!(note.metadata.version, 2);
+
+        // Already accurate: nothing changes and the version stays put
+        assert!(!recompute_note_metadata(&mut note));
+        assert_eq!(note.metadata.version, 2);
+
+        assert_eq!(decode_html_entities("&#39;a&#x41;&bogus; &"), "'aA&bogus; &");
+    }
+
+    #[test]
+    fn test_move_folder_with_nested_contents() {
+        let notes_dir =
+            std::env::temp_dir().join(format!("desqta-move-folder-{}", Uuid::new_v4()));
+        let write_note = |folder: &str, title: &str| {
+            let mut note = note_with_tags(&[]);
+            note.title = title.to_string();
+            let dir = notes_dir.join(folder);
+            fs::create_dir_all(&dir).unwrap();
+            save_note_file(
+                &dir.join(format!("{}.json", title)),
+                &note_to_filesystem_note(note),
+            )
+            .unwrap();
+        };
+        write_note("School", "Overview");
+        write_note("School/Maths", "Algebra");
+        write_note("School/Maths/Exams", "Final");
+        // Destination already has a "School" folder
+        write_note("Archive/School", "Existing");
+
+        let new_path = move_notes_folder(&notes_dir, "School", "Archive").unwrap();
+        assert_eq!(new_path, "Archive/School (2)");
+        assert!(!notes_dir.join("School").exists());
+
+        let notes = load_notes_from_dir(&notes_dir).unwrap();
+        let folder_of = |title: &str| {
+            notes
+                .iter()
+                .find(|n| n.title == title)
+                .unwrap()
+                .folder_path
+                .join("/")
+        };
+        assert_eq!(folder_of("Overview"), "Archive/School (2)");
+        assert_eq!(folder_of("Algebra"), "Archive/School (2)/Maths");
+        assert_eq!(folder_of("Final"), "Archive/School (2)/Maths/Exams");
+        assert_eq!(folder_of("Existing"), "Archive/School");
+
+        assert!(move_notes_folder(&notes_dir, "Archive", "Archive/School").is_err());
+        assert!(move_notes_folder(&notes_dir, "../outside", "").is_err());
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+
+    #[test]
+    fn test_move_folder_into_itself_or_descendant() {
+        let notes_dir =
+            std::env::temp_dir().join(format!("desqta-move-self-{}", Uuid::new_v4()));
+        fs::create_dir_all(notes_dir.join("School/Maths/Exams")).unwrap();
+
+        assert_eq!(
+            move_notes_folder(&notes_dir, "School", "School").unwrap_err(),
+            "Cannot move a folder into itself"
+        );
+        assert_eq!(
+            move_notes_folder(&notes_dir, "School", "School/Maths/Exams").unwrap_err(),
+            "Cannot move a folder into one of its own subfolders"
+        );
+        // A sibling that merely shares a name prefix is fine
+        fs::create_dir_all(notes_dir.join("School Archive")).unwrap();
+        assert_eq!(
+            move_notes_folder(&notes_dir, "School/Maths", "School Archive").unwrap(),
+            "School Archive/Maths"
+        );
+        assert!(notes_dir.join("School").is_dir());
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+
+    #[test]
+    fn test_note_move_rejects_folder_and_note_collisions() {
+        let notes_dir =
+            std::env::temp_dir().join(format!("desqta-move-note-{}", Uuid::new_v4()));
+        fs::create_dir_all(notes_dir.join("School/Maths")).unwrap();
+        fs::write(notes_dir.join("Maths.json"), "{}").unwrap();
+        fs::write(notes_dir.join("School/Algebra.json"), "{}").unwrap();
+        let current = notes_dir.join("Algebra.json");
+        let folder = |path: &str| -> Vec<String> { path.split('/').map(String::from).collect() };
+
+        assert_eq!(
+            note_move_destination(&notes_dir, &current, &folder("Maths"), "Algebra").unwrap_err(),
+            "A note named 'Maths' already exists in that folder"
+        );
+        assert_eq!(
+            note_move_destination(&notes_dir, &current, &folder("School"), "Maths").unwrap_err(),
+            "A folder named 'Maths' already exists in that folder"
+        );
+        assert_eq!(
+            note_move_destination(&notes_dir, &current, &folder("School"), "Algebra").unwrap_err(),
+            "A note named 'Algebra' already exists in that folder"
+        );
+        assert!(note_move_destination(&notes_dir, &current, &folder("../x"), "Algebra").is_err());
+        assert_eq!(
+            note_move_destination(&notes_dir, &current, &folder("School/Maths"), "Algebra")
+                .unwrap(),
+            notes_dir.join("School/Maths/Algebra.json")
+        );
+        assert_eq!(
+            folder_name_conflict(&notes_dir, "Maths").unwrap(),
+            "A note named 'Maths' already exists in that folder"
+        );
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+
+    #[test]
+    fn test_quick_capture_title_and_html() {
+        assert_eq!(quick_capture_title("\n  Buy milk \nand eggs"), "Buy milk");
+        assert_eq!(
+            quick_capture_title("one two three four five six seven eight nine ten"),
+            "one two three four five six seven eight…"
+        );
+        assert_eq!(
+            quick_capture_html("a < b\n\n  indented & more"),
+            "<p>a &lt; b</p><p>  indented &amp; more</p>"
+        );
+    }
+
+    #[test]
+    fn test_unique_note_title() {
+        let notes_dir = std::env::temp_dir().join(format!("desqta-capture-{}", Uuid::new_v4()));
+        fs::create_dir_all(notes_dir.join("Ideas")).unwrap();
+        fs::write(notes_dir.join("Todo.json"), "{}").unwrap();
+        fs::write(notes_dir.join("Todo (2).json"), "{}").unwrap();
+
+        assert_eq!(unique_note_title(&notes_dir, "Todo"), "Todo (3)");
+        assert_eq!(unique_note_title(&notes_dir, "Ideas"), "Ideas (2)");
+        assert_eq!(unique_note_title(&notes_dir, "Fresh"), "Fresh");
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+
+    #[test]
+    fn test_notes_backup_hash_and_prune() {
+        let a = note_with_tags(&["maths"]);
+        let b = note_with_tags(&[]);
+        let hash = notes_backup_hash(&[a.clone(), b.clone()], &[]).unwrap();
+        assert_eq!(
+            hash,
+            notes_backup_hash(&[b.clone(), a.clone()], &[]).unwrap()
+        );
+        let mut edited = a.clone();
+        edited.content = "<p>changed</p>".to_string();
+        assert_ne!(hash, notes_backup_hash(&[edited, b], &[]).unwrap());
+
+        let dir = std::env::temp_dir().join(format!("desqta-backups-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for stamp in ["20250103_090000", "20250101_090000", "20250102_090000"] {
+            let name = format!("{}{}.json", NOTES_AUTO_BACKUP_PREFIX, stamp);
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        let manual = dir.join(format!("{}20241201_090000.json", NOTES_BACKUP_PREFIX));
+        fs::write(&manual, "{}").unwrap();
+        fs::write(dir.join(LAST_BACKUP_HASH_FILE), &hash).unwrap();
+
+        assert_eq!(prune_backups_in(&dir, 2).unwrap(), 1);
+        let remaining: Vec<String> = list_notes_backups(&dir, NOTES_AUTO_BACKUP_PREFIX)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            remaining,
+            vec![
+                "notes_filesystem_autobackup_20250102_090000.json",
+                "notes_filesystem_autobackup_20250103_090000.json"
+            ]
+        );
+        assert_eq!(prune_backups_in(&dir, 0).unwrap(), 1);
+        assert!(dir.join(LAST_BACKUP_HASH_FILE).exists());
+        // Manual backups are never pruned
+        assert!(manual.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_folder_metadata_hidden_from_notes() {
+        let notes_dir = std::env::temp_dir().join(format!("desqta-folder-meta-{}", Uuid::new_v4()));
+        let maths = notes_dir.join("Maths");
+        let empty = notes_dir.join("Empty");
+        fs::create_dir_all(&maths).unwrap();
+        fs::create_dir_all(&empty).unwrap();
+        let mut note = note_with_tags(&[]);
+        note.title = "Algebra".to_string();
+        save_note_file(&maths.join("Algebra.json"), &note_to_filesystem_note(note)).unwrap();
+
+        // No meta file yet: defaults
+        assert_eq!(read_folder_metadata(&maths), FolderMetadata::default());
+
+        let meta = folder_metadata(
+            Some(" #ff8800 ".to_string()),
+            Some("📐".to_string()),
+            Some("Mathematics".to_string()),
+        )
+        .unwrap();
+        write_folder_metadata(&maths, &meta).unwrap();
+        let bin = folder_metadata(None, Some("🗑".to_string()), None).unwrap();
+        write_folder_metadata(&empty, &bin).unwrap();
+        assert_eq!(
+            read_folder_metadata(&maths).color.as_deref(),
+            Some("#ff8800")
+        );
+
+        // Omitted fields keep their stored values, blank ones reset
+        let renamed =
+            update_folder_metadata(&maths, Some(String::new()), None, Some("Maths".to_string()))
+                .unwrap();
+        assert_eq!(renamed.color, None);
+        assert_eq!(renamed.icon.as_deref(), Some("📐"));
+        assert_eq!(read_folder_metadata(&maths), renamed);
+        let meta = update_folder_metadata(
+            &maths,
+            Some("#ff8800".to_string()),
+            None,
+            Some("Mathematics".to_string()),
+        )
+        .unwrap();
+        assert_eq!(read_folder_metadata(&maths), meta);
+
+        let tree = build_file_tree(&notes_dir, &notes_dir).unwrap();
+        let folder = tree.iter().find(|item| item.name == "Maths").unwrap();
+        assert_eq!(folder.icon.as_deref(), Some("📐"));
+        assert_eq!(folder.display_name.as_deref(), Some("Mathematics"));
+        let children = folder.children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "Algebra");
+
+        let notes = load_notes_from_dir(&notes_dir).unwrap();
+        assert_eq!(notes.len(), 1);
+        // A folder holding only its metadata still counts as empty
+        assert_eq!(find_empty_folders(&notes_dir), vec![empty.clone()]);
+        remove_empty_folder(&empty).unwrap();
+        assert!(!empty.exists());
+
+        // Back to the defaults removes the file
+        write_folder_metadata(&maths, &FolderMetadata::default()).unwrap();
+        assert!(!maths.join(FOLDER_META_FILE).exists());
+        assert!(folder_metadata(Some("x".repeat(40)), None, None).is_err());
+        assert!(folder_metadata(None, None, Some("a\nb".to_string())).is_err());
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+
+    #[test]
+    fn test_lazy_folder_children_match_full_tree() {
+        let notes_dir = std::env::temp_dir().join(format!("desqta-lazy-tree-{}", Uuid::new_v4()));
+        // 4 levels of 4 folders, each holding 3 notes: 340 folders, 1020 notes
+        fn populate(dir: &Path, depth: usize) {
+            for n in 0..3 {
+                fs::write(dir.join(format!("Note {}.json", n)), "{}").unwrap();
+            }
+            if depth == 0 {
+                return;
+            }
+            for f in 0..4 {
+                let child = dir.join(format!("Folder {}", f));
+                fs::create_dir_all(&child).unwrap();
+                populate(&child, depth - 1);
+            }
+        }
+        populate(&notes_dir, 4);
+        fs::write(notes_dir.join(FOLDER_META_FILE), "{}").unwrap();
+
+        let full = build_file_tree(&notes_dir, &notes_dir).unwrap();
+        let root = folder_children(&notes_dir, &notes_dir, None).unwrap();
+
+        // Expanding every folder one at a time gives back the full tree
+        fn expand(items: Vec<FileTreeItem>, root: &Path) -> Vec<FileTreeItem> {
+            items
+                .into_iter()
+                .map(|mut item| {
+                    if item.item_type == "folder" {
+                        assert!(item.children.is_none());
+                        let children = folder_children(&root.join(&item.path), root, None).unwrap();
+                        assert_eq!(item.child_count, Some(children.len()));
+                        item.children = Some(expand(children, root));
+                        item.child_count = None;
+                    }
+                    item
+                })
+                .collect()
+        }
+        // Every entry depth first, so order and nesting both have to match
+        fn shape(items: &[FileTreeItem]) -> Vec<(String, String)> {
+            items
+                .iter()
+                .flat_map(|item| {
+                    let mut entries = vec![(item.item_type.clone(), item.path.clone())];
+                    entries.extend(shape(item.children.as_deref().unwrap_or_default()));
+                    entries
+                })
+                .collect()
+        }
+        let expanded = expand(root, &notes_dir);
+        assert_eq!(shape(&full).len(), 340 + 1020);
+        assert_eq!(shape(&expanded), shape(&full));
+        assert_eq!(full.len(), 7);
+        assert_eq!(full[0].item_type, "folder");
+        assert_eq!(full[4].name, "Note 0");
+
+        // Only entries changed after the cutoff
+        let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_577_836_800);
+        fs::File::options()
+            .write(true)
+            .open(notes_dir.join("Note 1.json"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let cutoff = parse_note_timestamp("2021-01-01T00:00:00Z");
+        let recent = folder_children(&notes_dir, &notes_dir, cutoff).unwrap();
+        assert_eq!(recent.len(), 6);
+        assert!(!recent.iter().any(|item| item.name == "Note 1"));
+
+        let _ = fs::remove_dir_all(&notes_dir);
+    }
+}
@@ -0,0 +1,300 @@
+//! Re-encoding of images pasted into notes. Screenshots arrive as PNG and are far larger than
+//! they need to be, so opaque ones are stored as lossy WebP when `notes_images_webp` is on.
+//! Images with transparency are left alone since WebP at photo quality smears their edges.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::notes_filesystem;
+use super::sanitization;
+use super::settings::Settings;
+
+const ORIGINALS_DIR: &str = "originals";
+/// Largest width or height libwebp accepts.
+const WEBP_MAX_DIMENSION: u32 = 16383;
+/// Formats worth re-encoding. GIFs may be animated and SVGs aren't raster.
+const REENCODABLE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageEncoding {
+    /// WebP quality, 0-100
+    pub quality: f32,
+    /// Keep the file that was replaced under `originals/`
+    pub keep_originals: bool,
+}
+
+impl ImageEncoding {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            quality: settings.notes_images_webp_quality.min(100) as f32,
+            keep_originals: settings.notes_images_keep_originals,
+        }
+    }
+
+    /// Encoding for newly pasted images, `None` when re-encoding is turned off.
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        settings.notes_images_webp.then(|| Self::new(settings))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReencodeSummary {
+    pub converted: usize,
+    /// Images kept as they were: transparent, undecodable or not smaller as WebP
+    pub skipped: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Lossy WebP version of an opaque PNG or JPEG. `None` when the image should be stored as it
+/// is: it has transparency, can't be decoded, or wouldn't get any smaller.
+pub fn to_webp(bytes: &[u8], quality: f32) -> Option<Vec<u8>> {
+    let image = image::load_from_memory(bytes).ok()?;
+    if image.width() > WEBP_MAX_DIMENSION || image.height() > WEBP_MAX_DIMENSION {
+        return None;
+    }
+    if image.color().has_alpha() && image.to_rgba8().pixels().any(|p| p.0[3] < u8::MAX) {
+        return None;
+    }
+    let rgb = image.to_rgb8();
+    let encoded = webp::Encoder::from_rgb(&rgb, rgb.width(), rgb.height())
+        .encode_simple(false, quality)
+        .ok()?;
+    (encoded.len() < bytes.len()).then(|| encoded.to_vec())
+}
+
+fn keep_original(note_dir: &Path, file_name: &str, bytes: &[u8]) -> Result<(), String> {
+    let dir = note_dir.join(ORIGINALS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create originals directory: {}", e))?;
+    fs::write(dir.join(file_name), bytes)
+        .map_err(|e| format!("Failed to keep original image: {}", e))
+}
+
+/// Write a pasted image to `note_dir` as `<stem>.<extension>`, or as `<stem>.webp` when
+/// `encoding` is set and WebP comes out smaller. Returns the stored file name.
+pub(crate) fn store_note_image(
+    note_dir: &Path,
+    stem: &str,
+    extension: &str,
+    bytes: &[u8],
+    encoding: Option<ImageEncoding>,
+) -> Result<String, String> {
+    let original_name = format!("{}.{}", stem, extension);
+    let webp = encoding
+        .filter(|_| REENCODABLE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .and_then(|encoding| Some((encoding, to_webp(bytes, encoding.quality)?)));
+    let (file_name, stored) = match &webp {
+        Some((encoding, webp)) => {
+            if encoding.keep_originals {
+                keep_original(note_dir, &original_name, bytes)?;
+            }
+            (format!("{}.webp", stem), webp.as_slice())
+        }
+        None => (original_name, bytes),
+    };
+    fs::write(note_dir.join(&file_name), stored)
+        .map_err(|e| format!("Failed to write image data: {}", e))?;
+    Ok(file_name)
+}
+
+/// Result of re-encoding a note's images: the new files are written, the old ones are only
+/// removed by `retire` once the updated content has been saved.
+struct Reencoded {
+    content: String,
+    summary: ReencodeSummary,
+    /// (replaced file, WebP written in its place)
+    replaced: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Reencoded {
+    fn retire(&self, note_dir: &Path, keep_originals: bool) -> Result<(), String> {
+        for (old, _) in &self.replaced {
+            if keep_originals {
+                let dir = note_dir.join(ORIGINALS_DIR);
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create originals directory: {}", e))?;
+                if let Some(name) = old.file_name() {
+                    fs::rename(old, dir.join(name))
+                        .map_err(|e| format!("Failed to keep original image: {}", e))?;
+                }
+            } else {
+                let _ = fs::remove_file(old);
+            }
+        }
+        Ok(())
+    }
+
+    fn roll_back(&self) {
+        for (_, new) in &self.replaced {
+            let _ = fs::remove_file(new);
+        }
+    }
+}
+
+/// Write WebP versions of the images in `note_dir` that `content` references as
+/// `<relative_dir>/<file>`, and point `content` at them. Files the note doesn't reference
+/// (linked assets, kept originals) are left alone.
+fn reencode_images(
+    note_dir: &Path,
+    relative_dir: &str,
+    content: &str,
+    quality: f32,
+) -> Result<Reencoded, String> {
+    let mut reencoded = Reencoded {
+        content: content.to_string(),
+        summary: ReencodeSummary::default(),
+        replaced: Vec::new(),
+    };
+    let entries = match fs::read_dir(note_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(reencoded),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let (Some(stem), Some(extension)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+        if !REENCODABLE_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+            continue;
+        }
+        let old_reference = format!("{}/{}.{}", relative_dir, stem, extension);
+        if !reencoded.content.contains(&old_reference) {
+            continue;
+        }
+        let webp_path = note_dir.join(format!("{}.webp", stem));
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read image file: {}", e))?;
+        let webp = match to_webp(&bytes, quality) {
+            Some(webp) if !webp_path.exists() => webp,
+            _ => {
+                reencoded.summary.skipped += 1;
+                continue;
+            }
+        };
+        if let Err(e) = fs::write(&webp_path, &webp) {
+            reencoded.roll_back();
+            return Err(format!("Failed to write image data: {}", e));
+        }
+        reencoded.content = reencoded
+            .content
+            .replace(&old_reference, &format!("{}/{}.webp", relative_dir, stem));
+        reencoded.summary.converted += 1;
+        reencoded.summary.bytes_before += bytes.len() as u64;
+        reencoded.summary.bytes_after += webp.len() as u64;
+        reencoded.replaced.push((path, webp_path));
+    }
+    Ok(reencoded)
+}
+
+/// Shrink the images already stored for a note by re-encoding them as WebP, regardless of
+/// `notes_images_webp`, and update the note to point at the new files. The replaced files
+/// are moved to `originals/` when `notes_images_keep_originals` is on, deleted otherwise.
+#[tauri::command]
+pub fn reencode_existing_note_images(
+    app: AppHandle,
+    note_id: String,
+) -> Result<ReencodeSummary, String> {
+    let mut note = notes_filesystem::get_note_filesystem(app.clone(), note_id.clone())?
+        .ok_or_else(|| format!("Note not found: {}", note_id))?;
+    let encoding = ImageEncoding::new(&Settings::load());
+    let dir_name = sanitization::sanitize_path_component(&note_id);
+    let note_dir = notes_filesystem::get_notes_images_dir(&app)?.join(&dir_name);
+
+    let reencoded = reencode_images(
+        &note_dir,
+        &format!("note_contents/{}", dir_name),
+        &note.content,
+        encoding.quality,
+    )?;
+    if reencoded.replaced.is_empty() {
+        return Ok(reencoded.summary);
+    }
+
+    note.content = reencoded.content.clone();
+    if let Err(e) = notes_filesystem::save_note_filesystem(app, note) {
+        reencoded.roll_back();
+        return Err(e);
+    }
+    reencoded.retire(&note_dir, encoding.keep_originals)?;
+    Ok(reencoded.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    /// A screenshot-sized PNG with enough texture that PNG compresses it poorly.
+    fn large_png() -> Vec<u8> {
+        let image = RgbImage::from_fn(800, 600, |x, y| {
+            let noise = ((x * 7919 + y * 104729) ^ (x * y)) % 48;
+            Rgb([
+                ((x / 4 + noise) % 256) as u8,
+                ((y / 3 + noise) % 256) as u8,
+                ((x + y) / 6 % 256) as u8,
+            ])
+        });
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_reencode_shrinks_large_png() {
+        let note_dir =
+            std::env::temp_dir().join(format!("desqta-note-images-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&note_dir).unwrap();
+        let png = large_png();
+        fs::write(note_dir.join("1700000000000_paste_png.png"), &png).unwrap();
+        fs::write(note_dir.join("unreferenced.png"), &png).unwrap();
+        let content = r#"<p><img src="note_contents/note-1/1700000000000_paste_png.png"></p>"#;
+
+        let reencoded = reencode_images(&note_dir, "note_contents/note-1", content, 80.0).unwrap();
+        assert_eq!(reencoded.summary.converted, 1);
+        assert_eq!(reencoded.summary.bytes_before, png.len() as u64);
+        let stored = fs::read(note_dir.join("1700000000000_paste_png.webp")).unwrap();
+        assert_eq!(reencoded.summary.bytes_after, stored.len() as u64);
+        assert!(stored.len() < png.len() / 2);
+        assert_eq!(
+            reencoded.content,
+            r#"<p><img src="note_contents/note-1/1700000000000_paste_png.webp"></p>"#
+        );
+
+        reencoded.retire(&note_dir, true).unwrap();
+        assert!(!note_dir.join("1700000000000_paste_png.png").exists());
+        assert!(note_dir
+            .join(ORIGINALS_DIR)
+            .join("1700000000000_paste_png.png")
+            .exists());
+        assert!(note_dir.join("unreferenced.png").exists());
+
+        // Transparent images stay PNG
+        let mut transparent = Vec::new();
+        RgbaImage::from_pixel(64, 64, Rgba([255, 0, 0, 0]))
+            .write_to(&mut Cursor::new(&mut transparent), ImageFormat::Png)
+            .unwrap();
+        assert!(to_webp(&transparent, 80.0).is_none());
+        let encoding = ImageEncoding {
+            quality: 80.0,
+            keep_originals: false,
+        };
+        let name =
+            store_note_image(&note_dir, "icon", "png", &transparent, Some(encoding)).unwrap();
+        assert_eq!(name, "icon.png");
+
+        let _ = fs::remove_dir_all(&note_dir);
+    }
+}
@@ -0,0 +1,366 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDate, Utc};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use crate::notes_filesystem::{self, Note, NoteMetadata};
+use crate::text_search;
+
+/// Outcome of importing a single Markdown file.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarkdownImportResult {
+    pub path: String,
+    pub success: bool,
+    pub note_id: Option<String>,
+    pub title: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Fields we understand from a YAML front-matter block.
+#[derive(Debug, Default, PartialEq)]
+struct FrontMatter {
+    title: Option<String>,
+    tags: Vec<String>,
+    created: Option<String>,
+    updated: Option<String>,
+}
+
+/// Split a leading `---` front-matter block from the body. Only flat `key: value` pairs and
+/// string lists (`[a, b]` or `- a` lines) are supported, which covers what note apps export.
+fn split_front_matter(markdown: &str) -> (FrontMatter, &str) {
+    let mut front = FrontMatter::default();
+    let trimmed = markdown.trim_start_matches('\u{feff}');
+    let Some(rest) = trimmed
+        .strip_prefix("---\n")
+        .or_else(|| trimmed.strip_prefix("---\r\n"))
+    else {
+        return (front, trimmed);
+    };
+
+    let mut offset = 0;
+    let mut end = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" || line.trim_end() == "..." {
+            end = Some((offset, offset + line.len()));
+            break;
+        }
+        offset += line.len();
+    }
+    let Some((yaml_end, body_start)) = end else {
+        return (front, trimmed);
+    };
+
+    let mut current_list: Option<String> = None;
+    for line in rest[..yaml_end].lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            if current_list.as_deref() == Some("tags") {
+                front.tags.push(unquote(item));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        current_list = if value.is_empty() { Some(key.clone()) } else { None };
+
+        match key.as_str() {
+            "title" if !value.is_empty() => front.title = Some(unquote(value)),
+            "tags" | "keywords" if !value.is_empty() => {
+                let list = value.trim_start_matches('[').trim_end_matches(']');
+                front.tags.extend(
+                    list.split(',')
+                        .map(unquote)
+                        .filter(|t| !t.is_empty()),
+                );
+            }
+            "tags" | "keywords" => current_list = Some("tags".to_string()),
+            "created" | "date" | "created_at" => front.created = parse_date(value),
+            "updated" | "modified" | "updated_at" => front.updated = parse_date(value),
+            _ => {}
+        }
+    }
+
+    (front, &rest[body_start..])
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .trim()
+        .to_string()
+}
+
+/// Accept RFC 3339 timestamps or plain `YYYY-MM-DD` dates.
+fn parse_date(value: &str) -> Option<String> {
+    let value = unquote(value);
+    if let Ok(dt) = DateTime::parse_from_rfc3339(&value) {
+        return Some(dt.with_timezone(&Utc).to_rfc3339());
+    }
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().to_rfc3339())
+}
+
+/// Text of the first heading in the document, if any.
+fn first_heading(markdown: &str) -> Option<String> {
+    let mut in_heading = false;
+    let mut text = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => in_heading = true,
+            Event::End(_) if in_heading => {
+                let text = text.trim().to_string();
+                return if text.is_empty() { None } else { Some(text) };
+            }
+            Event::Text(t) | Event::Code(t) if in_heading => text.push_str(&t),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether a link target would run script when clicked in the note.
+fn is_script_url(url: &str) -> bool {
+    let scheme = url.trim_start().to_ascii_lowercase();
+    scheme.starts_with("javascript:") || scheme.starts_with("vbscript:")
+}
+
+/// Render Markdown to HTML, passing every image URL through `map_image` so local images
+/// can be swapped for their copy in the note_contents store. Notes are shown as HTML, so raw
+/// HTML in the Markdown is escaped into text and script links are dropped.
+fn markdown_to_html<F>(markdown: &str, mut map_image: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            let dest_url = map_image(&dest_url).map(Into::into).unwrap_or(dest_url);
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            })
+        }
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if is_script_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        other => other,
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    output
+}
+
+fn is_relative_path(url: &str) -> bool {
+    !(url.is_empty()
+        || url.starts_with('/')
+        || url.starts_with('#')
+        || url.starts_with("data:")
+        || url.contains("://"))
+}
+
+fn compute_metadata(html: &str) -> NoteMetadata {
+    let text = text_search::html_to_text(html);
+    let word_count = text.split_whitespace().count() as u32;
+    NoteMetadata {
+        word_count,
+        character_count: text.chars().count() as u32,
+        reading_time: (word_count + 199) / 200,
+        last_auto_save: None,
+        version: 1,
+    }
+}
+
+fn import_markdown_file(
+    app: &AppHandle,
+    path: &Path,
+    folder_path: &[String],
+) -> Result<Note, String> {
+    let markdown =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (front, body) = split_front_matter(&markdown);
+    let note_id = Uuid::new_v4().to_string();
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let content = markdown_to_html(body, |url| {
+        if !is_relative_path(url) {
+            return None;
+        }
+        let decoded = urlencoding::decode(url)
+            .map(|u| u.into_owned())
+            .unwrap_or_else(|_| url.to_string());
+        let image_path = base_dir.join(&decoded);
+        let bytes = match fs::read(&image_path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("Failed to read image {}: {}", image_path.display(), e);
+                return None;
+            }
+        };
+        let filename = image_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image.png".to_string());
+        notes_filesystem::save_image_from_base64_filesystem(
+            app.clone(),
+            note_id.clone(),
+            general_purpose::STANDARD.encode(&bytes),
+            filename,
+        )
+        .map_err(|e| eprintln!("Failed to import image {}: {}", image_path.display(), e))
+        .ok()
+    });
+
+    let title = front
+        .title
+        .clone()
+        .or_else(|| first_heading(body))
+        .or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    let file_modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|t| DateTime::<Utc>::from(t).to_rfc3339());
+    let now = Utc::now().to_rfc3339();
+    let created_at = front
+        .created
+        .clone()
+        .or_else(|| file_modified.clone())
+        .unwrap_or_else(|| now.clone());
+    let updated_at = front
+        .updated
+        .clone()
+        .or(file_modified)
+        .unwrap_or_else(|| created_at.clone());
+
+    let mut tags = Vec::new();
+    for tag in front.tags {
+        if !tags.iter().any(|t: &String| t.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag);
+        }
+    }
+
+    let note = Note {
+        id: note_id,
+        metadata: compute_metadata(&content),
+        title,
+        content,
+        folder_path: folder_path.to_vec(),
+        tags,
+        seqta_references: vec![],
+        created_at,
+        updated_at,
+        last_accessed: now,
+    };
+
+    notes_filesystem::save_note_filesystem(app.clone(), note.clone())?;
+    Ok(note)
+}
+
+/// Import Markdown files as notes into `target_folder` (root when omitted). Each file is
+/// imported independently; failures are reported per file rather than aborting the batch.
+#[tauri::command]
+pub fn import_notes_from_markdown(
+    app: AppHandle,
+    paths: Vec<String>,
+    target_folder: Option<Vec<String>>,
+) -> Result<Vec<MarkdownImportResult>, String> {
+    let folder_path = target_folder
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| vec!["default".to_string()]);
+
+    let results = paths
+        .into_iter()
+        .map(|path| match import_markdown_file(&app, Path::new(&path), &folder_path) {
+            Ok(note) => MarkdownImportResult {
+                path,
+                success: true,
+                note_id: Some(note.id),
+                title: Some(note.title),
+                error: None,
+            },
+            Err(e) => {
+                eprintln!("Failed to import {}: {}", path, e);
+                MarkdownImportResult {
+                    path,
+                    success: false,
+                    note_id: None,
+                    title: None,
+                    error: Some(e),
+                }
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_tags_and_dates() {
+        let md = "---\ntitle: \"Week 3\"\ntags:\n  - physics\n  - 'exam prep'\ndate: 2024-03-01\n---\n# Heading\n\nBody";
+        let (front, body) = split_front_matter(md);
+        assert_eq!(front.title.as_deref(), Some("Week 3"));
+        assert_eq!(front.tags, vec!["physics", "exam prep"]);
+        assert_eq!(front.created.as_deref(), Some("2024-03-01T00:00:00+00:00"));
+        assert!(body.starts_with("# Heading"));
+
+        let (front, _) = split_front_matter("---\ntags: [a, \"b\"]\n---\ntext");
+        assert_eq!(front.tags, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_markdown_conversion_rewrites_relative_images() {
+        let md = "# Title\n\n- one\n- two\n\n![pic](img/a.png) ![web](https://x.test/b.png)";
+        let html = markdown_to_html(md, |url| {
+            is_relative_path(url).then(|| format!("note_contents/n/{}", url))
+        });
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<li>one</li>"));
+        assert!(html.contains("src=\"note_contents/n/img/a.png\""));
+        assert!(html.contains("src=\"https://x.test/b.png\""));
+        assert_eq!(first_heading(md).as_deref(), Some("Title"));
+
+        let md = "<script>alert(1)</script>\n\nHi <img src=x onerror=alert(1)> \
+                  [click](javascript:alert(1))";
+        let html = markdown_to_html(md, |_| None);
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("javascript:"));
+    }
+}
@@ -0,0 +1,613 @@
+//! PDF export of a single note. The note's sanitized HTML is reduced to simple blocks
+//! (headings, paragraphs, lists, code, quotes, images) and laid out with genpdf on A4 pages,
+//! with the note title at the top and the export date at the bottom of every page.
+//!
+//! genpdf needs TrueType fonts, so DejaVu Sans is bundled into the app for the text. Code is
+//! set in a monospace font from the system when there is one.
+
+use genpdf::elements::{self, LinearLayout, Paragraph};
+use genpdf::fonts::{Font, FontData, FontFamily};
+use genpdf::render::Area;
+use genpdf::style::Style;
+use genpdf::{Alignment, Context, Element as _, Margins, PageDecorator, Position, Scale};
+use scraper::{ElementRef, Html, Node};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Component, Path};
+use tauri::AppHandle;
+
+use super::html_parser;
+use super::notes_filesystem;
+use super::timezone;
+
+const PAGE_MARGIN_MM: f64 = 15.0;
+/// A4 width less the margins
+const CONTENT_WIDTH_MM: f64 = 180.0;
+/// Tallest an image may be drawn, leaving room for the header and footer
+const MAX_IMAGE_HEIGHT_MM: f64 = 200.0;
+/// genpdf draws images at 300 DPI unless scaled
+const GENPDF_IMAGE_DPI: f64 = 300.0;
+/// Images are drawn at roughly their on-screen size
+const SCREEN_DPI: f64 = 96.0;
+const BODY_FONT_SIZE: u8 = 11;
+const CODE_FONT_SIZE: u8 = 9;
+const HEADER_FONT_SIZE: u8 = 8;
+
+/// (regular, bold, italic, bold italic) font files. Only the regular one has to exist;
+/// missing styles fall back to it.
+type FontFiles = [&'static str; 4];
+
+/// DejaVu Sans (see `fonts/LICENSE-DejaVu.txt`), in the same order as `FontFiles`
+const SANS_FONT_DATA: [&[u8]; 4] = [
+    include_bytes!("../../fonts/DejaVuSans.ttf"),
+    include_bytes!("../../fonts/DejaVuSans-Bold.ttf"),
+    include_bytes!("../../fonts/DejaVuSans-Oblique.ttf"),
+    include_bytes!("../../fonts/DejaVuSans-BoldOblique.ttf"),
+];
+
+const MONO_FONTS: &[FontFiles] = &[
+    [
+        "C:\\Windows\\Fonts\\cour.ttf",
+        "C:\\Windows\\Fonts\\courbd.ttf",
+        "C:\\Windows\\Fonts\\couri.ttf",
+        "C:\\Windows\\Fonts\\courbi.ttf",
+    ],
+    [
+        "/System/Library/Fonts/Supplemental/Courier New.ttf",
+        "/System/Library/Fonts/Supplemental/Courier New Bold.ttf",
+        "/System/Library/Fonts/Supplemental/Courier New Italic.ttf",
+        "/System/Library/Fonts/Supplemental/Courier New Bold Italic.ttf",
+    ],
+    [
+        "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationMono-Bold.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationMono-Italic.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationMono-BoldItalic.ttf",
+    ],
+    [
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono-Bold.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono-Oblique.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono-BoldOblique.ttf",
+    ],
+];
+
+fn bundled_sans() -> Result<FontFamily<FontData>, String> {
+    let [regular, bold, italic, bold_italic] = SANS_FONT_DATA.map(|data| {
+        FontData::new(data.to_vec(), None).map_err(|e| format!("Failed to load font: {}", e))
+    });
+    Ok(FontFamily {
+        regular: regular?,
+        bold: bold?,
+        italic: italic?,
+        bold_italic: bold_italic?,
+    })
+}
+
+fn load_font_family(candidates: &[FontFiles]) -> Option<FontFamily<FontData>> {
+    candidates
+        .iter()
+        .find_map(|[regular, bold, italic, bold_italic]| {
+            let regular = FontData::load(regular, None).ok()?;
+            let or_regular =
+                |path: &str| FontData::load(path, None).unwrap_or_else(|_| regular.clone());
+            Some(FontFamily {
+                bold: or_regular(bold),
+                italic: or_regular(italic),
+                bold_italic: or_regular(bold_italic),
+                regular: regular.clone(),
+            })
+        })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct SpanStyle {
+    bold: bool,
+    italic: bool,
+    code: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    style: SpanStyle,
+}
+
+/// A piece of a note laid out on its own lines.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(u8, Vec<Span>),
+    Paragraph(Vec<Span>),
+    List {
+        ordered: bool,
+        start: usize,
+        items: Vec<Vec<Block>>,
+    },
+    Code(String),
+    Quote(Vec<Block>),
+    Image {
+        src: String,
+        alt: String,
+    },
+}
+
+/// Collects blocks while walking the HTML, along with the inline text of the block being
+/// built.
+#[derive(Default)]
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+}
+
+impl BlockBuilder {
+    /// Inline text gathered so far, trimmed, or `None` when it's only whitespace.
+    fn take_spans(&mut self) -> Option<Vec<Span>> {
+        let mut spans = std::mem::take(&mut self.spans);
+        if let Some(first) = spans.first_mut() {
+            first.text = first.text.trim_start().to_string();
+        }
+        if let Some(last) = spans.last_mut() {
+            last.text = last.text.trim_end().to_string();
+        }
+        spans.retain(|span| !span.text.is_empty());
+        (!spans.is_empty()).then_some(spans)
+    }
+
+    fn flush(&mut self) {
+        if let Some(spans) = self.take_spans() {
+            self.blocks.push(Block::Paragraph(spans));
+        }
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.flush();
+        self.blocks
+    }
+
+    /// Add text with its whitespace collapsed like a browser would.
+    fn push_text(&mut self, text: &str, style: SpanStyle) {
+        let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut collapsed = String::new();
+        if text.starts_with(char::is_whitespace) {
+            collapsed.push(' ');
+        }
+        collapsed.push_str(&words);
+        if !words.is_empty() && text.ends_with(char::is_whitespace) {
+            collapsed.push(' ');
+        }
+        if !collapsed.is_empty() {
+            self.spans.push(Span {
+                text: collapsed,
+                style,
+            });
+        }
+    }
+
+    fn walk_children(&mut self, element: ElementRef, style: SpanStyle) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.push_text(text, style),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.walk_element(child, style);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn walk_element(&mut self, element: ElementRef, style: SpanStyle) {
+        let name = element.value().name();
+        match name {
+            "strong" | "b" => self.walk_children(
+                element,
+                SpanStyle {
+                    bold: true,
+                    ..style
+                },
+            ),
+            "em" | "i" => self.walk_children(
+                element,
+                SpanStyle {
+                    italic: true,
+                    ..style
+                },
+            ),
+            "code" => self.walk_children(
+                element,
+                SpanStyle {
+                    code: true,
+                    ..style
+                },
+            ),
+            "br" => self.flush(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.walk_children(element, style);
+                if let Some(spans) = self.take_spans() {
+                    let level = name[1..].parse().unwrap_or(1);
+                    self.blocks.push(Block::Heading(level, spans));
+                }
+            }
+            "ul" | "ol" => {
+                self.flush();
+                let items: Vec<Vec<Block>> = element
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|child| child.value().name() == "li")
+                    .map(|li| {
+                        let mut item = BlockBuilder::default();
+                        item.walk_children(li, style);
+                        item.finish()
+                    })
+                    .collect();
+                if !items.is_empty() {
+                    self.blocks.push(Block::List {
+                        ordered: name == "ol",
+                        start: element
+                            .value()
+                            .attr("start")
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(1),
+                        items,
+                    });
+                }
+            }
+            "pre" => {
+                self.flush();
+                let code: String = element.text().collect();
+                let code = code.trim_end_matches('\n');
+                if !code.trim().is_empty() {
+                    self.blocks.push(Block::Code(code.to_string()));
+                }
+            }
+            "blockquote" => {
+                self.flush();
+                let mut quote = BlockBuilder::default();
+                quote.walk_children(element, style);
+                let blocks = quote.finish();
+                if !blocks.is_empty() {
+                    self.blocks.push(Block::Quote(blocks));
+                }
+            }
+            "img" => {
+                self.flush();
+                if let Some(src) = element.value().attr("src") {
+                    self.blocks.push(Block::Image {
+                        src: src.to_string(),
+                        alt: element.value().attr("alt").unwrap_or("").to_string(),
+                    });
+                }
+            }
+            "tr" => {
+                self.flush();
+                let cells: Vec<String> = element
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .map(|cell| {
+                        let text: String = cell.text().collect();
+                        text.split_whitespace().collect::<Vec<_>>().join(" ")
+                    })
+                    .collect();
+                self.push_text(&cells.join(" | "), style);
+                self.flush();
+            }
+            "p" | "div" | "table" | "thead" | "tbody" | "li" => {
+                self.flush();
+                self.walk_children(element, style);
+                self.flush();
+            }
+            _ => self.walk_children(element, style),
+        }
+    }
+}
+
+/// Lay out sanitized note HTML as blocks.
+fn html_to_blocks(html: &str) -> Vec<Block> {
+    let fragment = Html::parse_fragment(html);
+    let mut builder = BlockBuilder::default();
+    builder.walk_children(fragment.root_element(), SpanStyle::default());
+    builder.finish()
+}
+
+/// Draws the note title above and the export date and page number below each page.
+struct NotePageDecorator {
+    title: String,
+    footer: String,
+    page: usize,
+}
+
+impl PageDecorator for NotePageDecorator {
+    fn decorate_page<'a>(
+        &mut self,
+        context: &Context,
+        mut area: Area<'a>,
+        style: Style,
+    ) -> Result<Area<'a>, genpdf::error::Error> {
+        self.page += 1;
+        area.add_margins(PAGE_MARGIN_MM);
+        let small = style.with_font_size(HEADER_FONT_SIZE);
+        let line = small.line_height(&context.font_cache);
+
+        area.print_str(
+            &context.font_cache,
+            Position::new(0.0, 0.0),
+            small.bold(),
+            &self.title,
+        )?;
+        let footer_y = area.size().height - line;
+        area.print_str(
+            &context.font_cache,
+            Position::new(0.0, footer_y),
+            small,
+            format!("{} · Page {}", self.footer, self.page),
+        )?;
+
+        area.add_offset(Position::new(0.0, line * 2.0));
+        let height = area.size().height - line * 3.0;
+        area.set_height(height);
+        Ok(area)
+    }
+}
+
+/// Fonts and paths needed while turning blocks into genpdf elements.
+struct RenderContext<'a> {
+    mono: Option<FontFamily<Font>>,
+    /// Directory image paths like `note_contents/<note>/<file>` are relative to
+    profile_dir: Option<&'a Path>,
+}
+
+impl RenderContext<'_> {
+    fn span_style(&self, span: &Span, base: Style) -> Style {
+        let mut style = base;
+        if span.style.bold {
+            style = style.bold();
+        }
+        if span.style.italic {
+            style = style.italic();
+        }
+        if span.style.code {
+            if let Some(mono) = self.mono {
+                style = style.with_font_family(mono);
+            }
+        }
+        style
+    }
+
+    fn paragraph(&self, spans: &[Span], base: Style) -> Paragraph {
+        let mut paragraph = Paragraph::default();
+        for span in spans {
+            paragraph.push_styled(span.text.clone(), self.span_style(span, base));
+        }
+        paragraph
+    }
+
+    /// The image as a PNG without its alpha channel, which genpdf can't embed, and its size in
+    /// pixels. genpdf decodes it again with its own (older) version of `image`.
+    fn load_image(&self, src: &str) -> Option<(Vec<u8>, u32, u32)> {
+        let relative = Path::new(src);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        let bytes = fs::read(self.profile_dir?.join(relative)).ok()?;
+        let rgb = image::load_from_memory(&bytes).ok()?.to_rgb8();
+        let mut png = Vec::new();
+        rgb.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .ok()?;
+        Some((png, rgb.width(), rgb.height()))
+    }
+
+    fn push_image(&self, layout: &mut LinearLayout, src: &str, alt: &str, base: Style) {
+        let image = self.load_image(src).and_then(|(png, width, height)| {
+            let image = elements::Image::from_reader(Cursor::new(png)).ok()?;
+            let natural_mm = |px: u32| px as f64 * 25.4 / GENPDF_IMAGE_DPI;
+            let scale = (GENPDF_IMAGE_DPI / SCREEN_DPI)
+                .min(CONTENT_WIDTH_MM / natural_mm(width))
+                .min(MAX_IMAGE_HEIGHT_MM / natural_mm(height));
+            Some(
+                image
+                    .with_alignment(Alignment::Center)
+                    .with_scale(Scale::new(scale, scale)),
+            )
+        });
+        match image {
+            Some(image) => layout.push(image.padded(Margins::vh(2, 0))),
+            // Remote, missing or undecodable images
+            None => {
+                let label = if alt.trim().is_empty() { src } else { alt };
+                layout.push(
+                    Paragraph::new(format!("[Image: {}]", label))
+                        .styled(base.italic())
+                        .padded(Margins::vh(1, 0)),
+                );
+            }
+        }
+    }
+
+    fn push_blocks(&self, layout: &mut LinearLayout, blocks: &[Block], base: Style) {
+        for block in blocks {
+            match block {
+                Block::Heading(level, spans) => {
+                    let size = match level {
+                        1 => 18,
+                        2 => 15,
+                        3 => 13,
+                        _ => 12,
+                    };
+                    let style = base.bold().with_font_size(size);
+                    layout.push(
+                        self.paragraph(spans, style)
+                            .padded(Margins::trbl(3, 0, 1, 0)),
+                    );
+                }
+                Block::Paragraph(spans) => {
+                    layout.push(self.paragraph(spans, base).padded(Margins::vh(1, 0)));
+                }
+                Block::List {
+                    ordered,
+                    start,
+                    items,
+                } => {
+                    let mut rendered = Vec::new();
+                    for item in items {
+                        let mut item_layout = LinearLayout::vertical();
+                        self.push_blocks(&mut item_layout, item, base);
+                        rendered.push(item_layout);
+                    }
+                    if *ordered {
+                        let mut list = elements::OrderedList::with_start(*start);
+                        rendered.into_iter().for_each(|item| list.push(item));
+                        layout.push(list.padded(Margins::vh(1, 0)));
+                    } else {
+                        let mut list = elements::UnorderedList::new();
+                        rendered.into_iter().for_each(|item| list.push(item));
+                        layout.push(list.padded(Margins::vh(1, 0)));
+                    }
+                }
+                Block::Code(code) => {
+                    let mut style = base.with_font_size(CODE_FONT_SIZE);
+                    if let Some(mono) = self.mono {
+                        style = style.with_font_family(mono);
+                    }
+                    let mut lines = LinearLayout::vertical();
+                    for line in code.lines() {
+                        // Keep indentation: genpdf collapses ordinary spaces when wrapping
+                        let indent = line.len() - line.trim_start_matches(' ').len();
+                        let text = format!("{}{}", "\u{a0}".repeat(indent), &line[indent..]);
+                        lines.push(Paragraph::new(text).styled(style));
+                    }
+                    layout.push(
+                        lines
+                            .padded(Margins::all(2))
+                            .framed()
+                            .padded(Margins::vh(2, 0)),
+                    );
+                }
+                Block::Quote(blocks) => {
+                    let mut quote = LinearLayout::vertical();
+                    self.push_blocks(&mut quote, blocks, base.italic());
+                    layout.push(quote.padded(Margins::trbl(1, 0, 1, 8)));
+                }
+                Block::Image { src, alt } => self.push_image(layout, src, alt, base),
+            }
+        }
+    }
+}
+
+/// Render a note to PDF bytes. `profile_dir` resolves the note's image paths; images that
+/// can't be found are replaced by their alt text.
+fn render_note_pdf(
+    title: &str,
+    html: &str,
+    profile_dir: Option<&Path>,
+    exported_on: &str,
+) -> Result<Vec<u8>, String> {
+    let mut doc = genpdf::Document::new(bundled_sans()?);
+    let mono = load_font_family(MONO_FONTS).map(|family| doc.add_font_family(family));
+    doc.set_title(title);
+    doc.set_paper_size(genpdf::PaperSize::A4);
+    doc.set_font_size(BODY_FONT_SIZE);
+    doc.set_line_spacing(1.25);
+    doc.set_page_decorator(NotePageDecorator {
+        title: title.to_string(),
+        footer: format!("Exported from DesQTA on {}", exported_on),
+        page: 0,
+    });
+
+    let context = RenderContext { mono, profile_dir };
+    let mut body = LinearLayout::vertical();
+    body.push(
+        Paragraph::new(title)
+            .styled(Style::new().bold().with_font_size(22))
+            .padded(Margins::trbl(0, 0, 4, 0)),
+    );
+    let sanitized = html_parser::sanitize_html(html, None)?;
+    context.push_blocks(&mut body, &html_to_blocks(&sanitized), Style::new());
+    doc.push(body);
+
+    let mut pdf = Vec::new();
+    doc.render(&mut pdf)
+        .map_err(|e| format!("Failed to render PDF: {}", e))?;
+    Ok(pdf)
+}
+
+/// Export a note as a PDF at `dest_path`. Returns the path written.
+#[tauri::command]
+pub fn export_note_pdf(
+    app: AppHandle,
+    note_id: String,
+    dest_path: String,
+) -> Result<String, String> {
+    let note = notes_filesystem::get_note_filesystem(app.clone(), note_id.clone())?
+        .ok_or_else(|| format!("Note not found: {}", note_id))?;
+    // Image paths in notes are relative to the profile directory
+    let images_dir = notes_filesystem::get_notes_images_dir(&app)?;
+    let exported_on = timezone::today().format("%-d %B %Y").to_string();
+    let title = if note.title.trim().is_empty() {
+        "Untitled"
+    } else {
+        note.title.trim()
+    };
+    let pdf = render_note_pdf(title, &note.content, images_dir.parent(), &exported_on)?;
+
+    let dest = Path::new(&dest_path);
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::write(dest, pdf).map_err(|e| format!("Failed to write PDF: {}", e))?;
+    Ok(dest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTE: &str = "<h2>Photosynthesis</h2>\
+        <p>Plants turn <strong>light</strong> into <em>chemical</em> energy.</p>\
+        <ul><li><p>Chlorophyll</p></li><li>Stomata<ol><li>Open</li><li>Closed</li></ol></li></ul>\
+        <pre><code>fn main() {\n    println!(\"hi\");\n}\n</code></pre>\
+        <p>Line one<br>Line two</p>\
+        <img src=\"note_contents/n1/missing.png\" alt=\"Leaf diagram\">";
+
+    #[test]
+    fn test_note_html_to_blocks() {
+        let blocks = html_to_blocks(&html_parser::sanitize_html(NOTE, None).unwrap());
+        assert!(
+            matches!(&blocks[0], Block::Heading(2, spans) if spans[0].text == "Photosynthesis")
+        );
+        let Block::Paragraph(spans) = &blocks[1] else {
+            panic!("expected a paragraph, got {:?}", blocks[1]);
+        };
+        assert_eq!(spans[1].text, "light");
+        assert!(spans[1].style.bold);
+        let Block::List { ordered, items, .. } = &blocks[2] else {
+            panic!("expected a list, got {:?}", blocks[2]);
+        };
+        assert!(!ordered);
+        assert_eq!(items.len(), 2);
+        assert!(
+            matches!(&items[1][1], Block::List { ordered: true, items, .. } if items.len() == 2)
+        );
+        assert_eq!(
+            blocks[3],
+            Block::Code("fn main() {\n    println!(\"hi\");\n}".to_string())
+        );
+        assert!(matches!(&blocks[5], Block::Paragraph(spans) if spans[0].text == "Line two"));
+        assert!(matches!(&blocks[6], Block::Image { alt, .. } if alt == "Leaf diagram"));
+    }
+
+    #[test]
+    fn test_render_note_pdf() {
+        let pdf = render_note_pdf("Biology", NOTE, None, "1 May 2024").unwrap();
+        let dest = std::env::temp_dir().join(format!("desqta-note-{}.pdf", uuid::Uuid::new_v4()));
+        fs::write(&dest, &pdf).unwrap();
+        let written = fs::read(&dest).unwrap();
+        let _ = fs::remove_file(&dest);
+        assert!(written.len() > 1000);
+        assert!(written.starts_with(b"%PDF"));
+    }
+}
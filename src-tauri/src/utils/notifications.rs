@@ -0,0 +1,308 @@
+//! App notifications. Everything the app shows goes through `notify`, which drops everything
+//! while `reminders_enabled` (the master switch) is off, drops categories the student has
+//! turned off, and holds notifications back during quiet hours (in the school's timezone),
+//! showing them once quiet hours are over.
+
+use chrono::{NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::plugin::PermissionState;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::color_scheme::parse_switch_time;
+use crate::settings::{NotificationPrefs, QuietHours, Settings};
+use crate::timezone;
+
+/// How often queued notifications are checked once quiet hours may have ended.
+const QUEUE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationCategory {
+    Assessments,
+    Messages,
+    Notices,
+    Reminders,
+}
+
+/// What `notify` did with a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOutcome {
+    Shown,
+    /// Held until quiet hours end
+    Queued,
+    /// The category is turned off
+    Suppressed,
+}
+
+struct QueuedNotification {
+    category: NotificationCategory,
+    title: String,
+    body: String,
+    sound: Option<String>,
+}
+
+/// Notifications held during quiet hours. Not persisted, so they're lost if the app quits first.
+static QUEUE: Mutex<Vec<QueuedNotification>> = Mutex::new(Vec::new());
+/// Whether the loop delivering `QUEUE` is running.
+static QUEUE_WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Whether `category` is shown at all. Nothing is while `reminders_enabled` is off.
+fn category_enabled(settings: &Settings, category: NotificationCategory) -> bool {
+    let prefs = &settings.notification_prefs;
+    settings.reminders_enabled
+        && match category {
+            NotificationCategory::Assessments => prefs.assessments,
+            NotificationCategory::Messages => prefs.messages,
+            NotificationCategory::Notices => prefs.notices,
+            NotificationCategory::Reminders => prefs.reminders,
+        }
+}
+
+/// Wall-clock time at the school, which quiet hours are written in.
+fn school_time_now() -> NaiveTime {
+    Utc::now()
+        .with_timezone(&timezone::school_timezone())
+        .time()
+}
+
+/// Whether `now` falls in quiet hours. Malformed or equal start and end times mean no quiet
+/// hours.
+fn in_quiet_hours(quiet: &QuietHours, now: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (
+        parse_switch_time(&quiet.start),
+        parse_switch_time(&quiet.end),
+    ) else {
+        return false;
+    };
+    if start <= end {
+        now >= start && now < end
+    } else {
+        // Spans midnight
+        now >= start || now < end
+    }
+}
+
+/// What should happen to a `category` notification at school time `now`.
+fn decide(settings: &Settings, category: NotificationCategory, now: NaiveTime) -> NotifyOutcome {
+    if !category_enabled(settings, category) {
+        NotifyOutcome::Suppressed
+    } else if settings
+        .notification_prefs
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|quiet| in_quiet_hours(quiet, now))
+    {
+        NotifyOutcome::Queued
+    } else {
+        NotifyOutcome::Shown
+    }
+}
+
+fn show(app: &AppHandle, title: &str, body: &str, sound: Option<&str>) -> Result<(), String> {
+    let notification = app.notification();
+    if notification.permission_state().map_err(|e| e.to_string())? != PermissionState::Granted
+        && notification
+            .request_permission()
+            .map_err(|e| e.to_string())?
+            != PermissionState::Granted
+    {
+        return Err("Notification permission not granted".to_string());
+    }
+    let mut builder = notification.builder().title(title).body(body);
+    if let Some(sound) = sound {
+        builder = builder.sound(sound);
+    }
+    builder.show().map_err(|e| e.to_string())
+}
+
+/// Show queued notifications if quiet hours are over. Preferences are checked again, so a
+/// category turned off in the meantime is dropped. Returns false while still quiet.
+fn deliver_queued(app: &AppHandle) -> bool {
+    let settings = Settings::load();
+    let now = school_time_now();
+    let queued = {
+        let mut queue = QUEUE.lock().unwrap();
+        if queue.is_empty() {
+            return true;
+        }
+        let quiet = &settings.notification_prefs.quiet_hours;
+        if quiet
+            .as_ref()
+            .is_some_and(|quiet| in_quiet_hours(quiet, now))
+        {
+            return false;
+        }
+        std::mem::take(&mut *queue)
+    };
+    for notification in queued {
+        if decide(&settings, notification.category, now) == NotifyOutcome::Shown {
+            if let Err(e) = show(
+                app,
+                &notification.title,
+                &notification.body,
+                notification.sound.as_deref(),
+            ) {
+                eprintln!("[Notifications] Failed to show queued notification: {}", e);
+            }
+        }
+    }
+    true
+}
+
+/// Start the loop that delivers queued notifications, unless it's already running. It stops
+/// once the queue has been delivered.
+fn start_queue_worker(app: &AppHandle) {
+    if QUEUE_WORKER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(QUEUE_CHECK_INTERVAL).await;
+            if deliver_queued(&app) {
+                QUEUE_WORKER_RUNNING.store(false, Ordering::SeqCst);
+                // Something may have been queued after the last check
+                if QUEUE.lock().unwrap().is_empty()
+                    || QUEUE_WORKER_RUNNING.swap(true, Ordering::SeqCst)
+                {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Show a notification, unless its category is turned off, or queue it until quiet hours end.
+pub fn notify(
+    app: &AppHandle,
+    category: NotificationCategory,
+    title: &str,
+    body: &str,
+) -> Result<NotifyOutcome, String> {
+    notify_with_sound(app, category, title, body, None)
+}
+
+/// `notify`, playing `sound` (a platform sound name, or "default") when it's shown.
+pub fn notify_with_sound(
+    app: &AppHandle,
+    category: NotificationCategory,
+    title: &str,
+    body: &str,
+    sound: Option<&str>,
+) -> Result<NotifyOutcome, String> {
+    let outcome = decide(&Settings::load(), category, school_time_now());
+    match outcome {
+        NotifyOutcome::Shown => show(app, title, body, sound)?,
+        NotifyOutcome::Queued => {
+            QUEUE.lock().unwrap().push(QueuedNotification {
+                category,
+                title: title.to_string(),
+                body: body.to_string(),
+                sound: sound.map(str::to_string),
+            });
+            start_queue_worker(app);
+        }
+        NotifyOutcome::Suppressed => {}
+    }
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub fn get_notification_prefs() -> NotificationPrefs {
+    Settings::load().notification_prefs
+}
+
+/// Save notification preferences for the current profile. Quiet hours need 24-hour `HH:MM`
+/// times. Anything queued is shown straight away if quiet hours no longer apply.
+#[tauri::command]
+pub fn set_notification_prefs(
+    app: AppHandle,
+    prefs: NotificationPrefs,
+) -> Result<NotificationPrefs, String> {
+    if let Some(quiet) = &prefs.quiet_hours {
+        for time in [&quiet.start, &quiet.end] {
+            if parse_switch_time(time).is_none() {
+                return Err(format!(
+                    "Invalid quiet hours time \"{}\", expected HH:MM",
+                    time
+                ));
+            }
+        }
+    }
+    let mut settings = Settings::load();
+    settings.notification_prefs = prefs;
+    settings.save().map_err(|e| e.to_string())?;
+    deliver_queued(&app);
+    Ok(settings.notification_prefs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours_across_midnight() {
+        let mut settings = Settings::default();
+        settings.reminders_enabled = true;
+        settings.notification_prefs.quiet_hours = Some(QuietHours {
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        });
+        let decide_at = |settings: &Settings, hour, minute| {
+            decide(settings, NotificationCategory::Messages, at(hour, minute))
+        };
+
+        assert_eq!(decide_at(&settings, 21, 59), NotifyOutcome::Shown);
+        assert_eq!(decide_at(&settings, 22, 0), NotifyOutcome::Queued);
+        assert_eq!(decide_at(&settings, 23, 59), NotifyOutcome::Queued);
+        assert_eq!(decide_at(&settings, 0, 0), NotifyOutcome::Queued);
+        assert_eq!(decide_at(&settings, 6, 59), NotifyOutcome::Queued);
+        assert_eq!(decide_at(&settings, 7, 0), NotifyOutcome::Shown);
+        assert_eq!(decide_at(&settings, 12, 0), NotifyOutcome::Shown);
+
+        // Turned off categories are dropped rather than queued
+        settings.notification_prefs.messages = false;
+        assert_eq!(decide_at(&settings, 23, 0), NotifyOutcome::Suppressed);
+        assert_eq!(
+            decide(&settings, NotificationCategory::Notices, at(23, 0)),
+            NotifyOutcome::Queued
+        );
+        // `reminders_enabled` is the master switch for every category
+        settings.reminders_enabled = false;
+        assert_eq!(
+            decide(&settings, NotificationCategory::Reminders, at(12, 0)),
+            NotifyOutcome::Suppressed
+        );
+        assert_eq!(
+            decide(&settings, NotificationCategory::Notices, at(12, 0)),
+            NotifyOutcome::Suppressed
+        );
+        assert_eq!(
+            decide(&settings, NotificationCategory::Notices, at(23, 0)),
+            NotifyOutcome::Suppressed
+        );
+
+        // Same-day quiet hours, and ones that can't be parsed
+        let daytime = QuietHours {
+            start: "09:00".to_string(),
+            end: "15:00".to_string(),
+        };
+        assert!(in_quiet_hours(&daytime, at(9, 0)));
+        assert!(!in_quiet_hours(&daytime, at(15, 0)));
+        assert!(!in_quiet_hours(&daytime, at(23, 0)));
+        let broken = QuietHours {
+            start: "10pm".to_string(),
+            end: "07:00".to_string(),
+        };
+        assert!(!in_quiet_hours(&broken, at(23, 0)));
+    }
+}
@@ -86,6 +86,9 @@ pub struct TestResults {
     pub summary: TestSummary,
     pub timestamp: String,
     pub version: String,
+    /// OS the run was recorded on; only comparable runs share a platform.
+    #[serde(default)]
+    pub platform: Option<String>,
     /// Optional dev/Tauri in-app metrics + synthetic benchmark attachment (frontend-defined shape).
     #[serde(rename = "megaPerf")]
     pub mega_perf: Option<serde_json::Value>,
@@ -129,6 +132,7 @@ pub fn save_performance_test_results(
     let mut results_with_metadata = results;
     results_with_metadata.timestamp = timestamp.clone();
     results_with_metadata.version = env!("CARGO_PKG_VERSION").to_string();
+    results_with_metadata.platform = Some(std::env::consts::OS.to_string());
 
     // Serialize and save to file
     let json_content = serde_json::to_string_pretty(&results_with_metadata)
@@ -246,3 +250,259 @@ pub fn clear_all_performance_tests(app: AppHandle) -> Result<u32, String> {
 
     Ok(deleted_count)
 }
+
+/// Change in one metric between two runs. All tracked metrics are lower-is-better.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: f64,
+    pub candidate: f64,
+    pub delta: f64,
+    /// `None` when the baseline is zero
+    #[serde(rename = "percentChange")]
+    pub percent_change: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PerformanceComparison {
+    #[serde(rename = "baselineId")]
+    pub baseline_id: String,
+    #[serde(rename = "candidateId")]
+    pub candidate_id: String,
+    #[serde(rename = "baselineVersion")]
+    pub baseline_version: String,
+    #[serde(rename = "candidateVersion")]
+    pub candidate_version: String,
+    #[serde(rename = "baselinePlatform")]
+    pub baseline_platform: Option<String>,
+    #[serde(rename = "candidatePlatform")]
+    pub candidate_platform: Option<String>,
+    pub metrics: Vec<MetricDelta>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegressionReport {
+    #[serde(rename = "candidateId")]
+    pub candidate_id: String,
+    /// Most recent earlier run used for comparison; `None` if there was nothing to compare against
+    #[serde(rename = "baselineId")]
+    pub baseline_id: Option<String>,
+    #[serde(rename = "thresholdPct")]
+    pub threshold_pct: f64,
+    pub regressions: Vec<MetricDelta>,
+    #[serde(rename = "hasRegression")]
+    pub has_regression: bool,
+}
+
+fn metric_delta(metric: String, baseline: f64, candidate: f64) -> MetricDelta {
+    let delta = candidate - baseline;
+    let percent_change = if baseline != 0.0 {
+        Some(delta / baseline * 100.0)
+    } else {
+        None
+    };
+    MetricDelta {
+        metric,
+        baseline,
+        candidate,
+        delta,
+        percent_change,
+    }
+}
+
+/// Per-metric deltas for summary figures and every page present in both runs.
+fn compare_results(baseline: &TestResults, candidate: &TestResults) -> Vec<MetricDelta> {
+    let (b, c) = (&baseline.summary, &candidate.summary);
+    let mut metrics = vec![
+        metric_delta(
+            "totalDuration".to_string(),
+            baseline.total_duration as f64,
+            candidate.total_duration as f64,
+        ),
+        metric_delta("averageLoadTime".to_string(), b.average_load_time, c.average_load_time),
+        metric_delta("totalErrors".to_string(), b.total_errors as f64, c.total_errors as f64),
+        metric_delta("totalWarnings".to_string(), b.total_warnings as f64, c.total_warnings as f64),
+    ];
+
+    let optional = [
+        ("averageCpuUsage", b.average_cpu_usage, c.average_cpu_usage),
+        ("peakCpuUsage", b.peak_cpu_usage, c.peak_cpu_usage),
+        ("averageMemoryUsage", b.average_memory_usage, c.average_memory_usage),
+        ("peakMemoryUsage", b.peak_memory_usage, c.peak_memory_usage),
+    ];
+    for (name, base, cand) in optional {
+        if let (Some(base), Some(cand)) = (base, cand) {
+            metrics.push(metric_delta(name.to_string(), base as f64, cand as f64));
+        }
+    }
+
+    for page in &candidate.pages {
+        if let Some(base_page) = baseline.pages.iter().find(|p| p.path == page.path) {
+            metrics.push(metric_delta(
+                format!("page:{}.loadTime", page.path),
+                base_page.load_time,
+                page.load_time,
+            ));
+        }
+    }
+
+    metrics
+}
+
+/// Metrics that got worse by more than `threshold_pct` percent.
+fn find_regressions(metrics: Vec<MetricDelta>, threshold_pct: f64) -> Vec<MetricDelta> {
+    metrics
+        .into_iter()
+        .filter(|m| match m.percent_change {
+            Some(pct) => pct > threshold_pct,
+            // From zero to non-zero (e.g. new errors) is always a regression
+            None => m.candidate > m.baseline,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn compare_performance_results(
+    app: AppHandle,
+    baseline_id: String,
+    candidate_id: String,
+) -> Result<PerformanceComparison, String> {
+    let baseline = load_performance_test_result(app.clone(), baseline_id.clone())?;
+    let candidate = load_performance_test_result(app, candidate_id.clone())?;
+
+    Ok(PerformanceComparison {
+        metrics: compare_results(&baseline, &candidate),
+        baseline_id,
+        candidate_id,
+        baseline_version: baseline.version,
+        candidate_version: candidate.version,
+        baseline_platform: baseline.platform,
+        candidate_platform: candidate.platform,
+    })
+}
+
+/// Compare a run against the most recent earlier run on the same platform and flag metrics
+/// that worsened by more than `threshold_pct` percent.
+#[tauri::command]
+pub fn detect_performance_regression(
+    app: AppHandle,
+    candidate_id: String,
+    threshold_pct: f64,
+) -> Result<RegressionReport, String> {
+    let candidate = load_performance_test_result(app.clone(), candidate_id.clone())?;
+
+    // Filenames embed the timestamp, so most-recent-first order is filename order
+    let mut baseline = None;
+    for id in get_performance_test_results(app.clone())? {
+        if id >= candidate_id {
+            continue;
+        }
+        if let Ok(result) = load_performance_test_result(app.clone(), id.clone()) {
+            if candidate.platform.is_none() || result.platform == candidate.platform {
+                baseline = Some((id, result));
+                break;
+            }
+        }
+    }
+
+    let (baseline_id, regressions) = match baseline {
+        Some((id, result)) => (
+            Some(id),
+            find_regressions(compare_results(&result, &candidate), threshold_pct),
+        ),
+        None => (None, Vec::new()),
+    };
+
+    Ok(RegressionReport {
+        candidate_id,
+        baseline_id,
+        threshold_pct,
+        has_regression: !regressions.is_empty(),
+        regressions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(path: &str, load_time: f64) -> PerformanceMetrics {
+        PerformanceMetrics {
+            page_name: path.to_string(),
+            path: path.to_string(),
+            load_time,
+            dom_content_loaded: load_time / 2.0,
+            first_paint: None,
+            first_contentful_paint: None,
+            largest_contentful_paint: None,
+            cumulative_layout_shift: None,
+            first_input_delay: None,
+            memory_usage: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            network_requests: 0,
+            resource_load_times: Vec::new(),
+            system_metrics: None,
+        }
+    }
+
+    fn fixture(
+        average_load_time: f64,
+        total_errors: u32,
+        pages: Vec<PerformanceMetrics>,
+    ) -> TestResults {
+        TestResults {
+            start_time: 0,
+            end_time: 1000,
+            total_duration: 1000,
+            pages,
+            overall_errors: Vec::new(),
+            system_metrics_history: None,
+            summary: TestSummary {
+                average_load_time,
+                slowest_page: PageSummary { name: "/".to_string(), time: average_load_time },
+                fastest_page: PageSummary { name: "/".to_string(), time: average_load_time },
+                total_errors,
+                total_warnings: 0,
+                average_cpu_usage: Some(10.0),
+                peak_cpu_usage: None,
+                average_memory_usage: None,
+                peak_memory_usage: None,
+            },
+            timestamp: String::new(),
+            version: "1.0.0".to_string(),
+            platform: Some("linux".to_string()),
+            mega_perf: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_results_deltas() {
+        let baseline = fixture(200.0, 0, vec![page("/courses", 100.0)]);
+        let candidate = fixture(250.0, 0, vec![page("/courses", 90.0), page("/new", 50.0)]);
+        let metrics = compare_results(&baseline, &candidate);
+
+        let avg = metrics.iter().find(|m| m.metric == "averageLoadTime").unwrap();
+        assert_eq!(avg.delta, 50.0);
+        assert_eq!(avg.percent_change, Some(25.0));
+
+        let courses = metrics.iter().find(|m| m.metric == "page:/courses.loadTime").unwrap();
+        assert_eq!(courses.percent_change, Some(-10.0));
+        assert!(metrics.iter().all(|m| m.metric != "page:/new.loadTime"));
+        // CPU is only compared when both runs recorded it
+        assert!(metrics.iter().any(|m| m.metric == "averageCpuUsage"));
+        assert!(metrics.iter().all(|m| m.metric != "peakCpuUsage"));
+    }
+
+    #[test]
+    fn test_find_regressions_threshold() {
+        let baseline = fixture(200.0, 0, vec![page("/courses", 100.0)]);
+        let candidate = fixture(230.0, 2, vec![page("/courses", 105.0)]);
+        let regressions = find_regressions(compare_results(&baseline, &candidate), 10.0);
+        let names: Vec<&str> = regressions.iter().map(|m| m.metric.as_str()).collect();
+
+        assert!(names.contains(&"averageLoadTime")); // +15%
+        assert!(names.contains(&"totalErrors")); // 0 -> 2
+        assert!(!names.contains(&"page:/courses.loadTime")); // +5%
+    }
+}
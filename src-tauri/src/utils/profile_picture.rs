@@ -4,9 +4,9 @@ use std::path::PathBuf;
 
 /// Get the profile picture directory path
 fn get_profile_picture_dir() -> Result<PathBuf, String> {
-    let app_data_dir = dirs_next::data_dir().ok_or("Failed to get app data directory")?;
+    let app_data_dir = crate::data_dir::app_data_dir()?;
 
-    let profile_dir = app_data_dir.join("DesQTA").join("profile");
+    let profile_dir = app_data_dir.join("profile");
 
     // Create directory if it doesn't exist
     if !profile_dir.exists() {
@@ -1,3 +1,4 @@
+use crate::data_dir;
 use crate::logger;
 use ring::digest;
 use serde::{Deserialize, Serialize};
@@ -23,29 +24,86 @@ pub struct ProfilesMetadata {
     pub migration_completed: bool,
 }
 
-/// Get the base data directory (DesQTA root).
-/// Public for app-level storage (e.g. reserved DesQTA client ID).
-pub fn get_base_data_dir() -> PathBuf {
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).expect("Unable to create data dir");
+/// Longest display name accepted for a profile.
+const MAX_PROFILE_NAME_LENGTH: usize = 100;
+
+fn validate_profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_PROFILE_NAME_LENGTH {
+        return Err(format!(
+            "Profile name cannot be longer than {} characters",
+            MAX_PROFILE_NAME_LENGTH
+        ));
+    }
+    Ok(name.to_string())
+}
+
+impl ProfilesMetadata {
+    /// Add a profile that isn't tied to a SEQTA account yet (it gets one on first login).
+    fn add_named_profile(&mut self, name: &str, now: i64) -> Result<Profile, String> {
+        let profile = Profile {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            base_url: String::new(),
+            user_id: 0,
+            display_name: Some(validate_profile_name(name)?),
+            created_at: now,
+        };
+        self.profiles.push(profile.clone());
+        if self.current_profile_id.is_none() {
+            self.current_profile_id = Some(profile.id.clone());
         }
-        dir
+        Ok(profile)
     }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir = dirs_next::data_dir().expect("Unable to determine data dir");
-        dir.push("DesQTA");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).expect("Unable to create data dir");
+
+    fn rename_profile(&mut self, profile_id: &str, name: &str) -> Result<Profile, String> {
+        let name = validate_profile_name(name)?;
+        let profile = self
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == profile_id)
+            .ok_or_else(|| format!("Profile {} not found", profile_id))?;
+        profile.display_name = Some(name);
+        Ok(profile.clone())
+    }
+
+    fn select_profile(&mut self, profile_id: &str) -> Result<(), String> {
+        if !self.profiles.iter().any(|p| p.id == profile_id) {
+            return Err(format!("Profile {} not found", profile_id));
         }
-        dir
+        self.current_profile_id = Some(profile_id.to_string());
+        Ok(())
+    }
+
+    /// Remove a profile. If it was the active one, the first remaining profile becomes
+    /// active (or none, if it was the last). Returns true when the active profile changed.
+    fn remove_profile(&mut self, profile_id: &str) -> Result<bool, String> {
+        let before = self.profiles.len();
+        self.profiles.retain(|p| p.id != profile_id);
+        if self.profiles.len() == before {
+            return Err(format!("Profile {} not found", profile_id));
+        }
+
+        if self.current_profile_id.as_deref() == Some(profile_id) {
+            self.current_profile_id = self.profiles.first().map(|p| p.id.clone());
+            return Ok(true);
+        }
+        Ok(false)
     }
 }
 
+/// Get the base data directory (DesQTA root).
+/// Public for app-level storage (e.g. reserved DesQTA client ID).
+pub fn get_base_data_dir() -> PathBuf {
+    let dir = data_dir::app_data_dir().expect("Unable to determine data dir");
+    if !dir.exists() {
+        fs::create_dir_all(&dir).expect("Unable to create data dir");
+    }
+    dir
+}
+
 /// Get the profiles.json file path
 fn profiles_metadata_file() -> PathBuf {
     let mut dir = get_base_data_dir();
@@ -183,13 +241,7 @@ impl ProfileManager {
     /// Set the current active profile
     pub fn set_current_profile(profile_id: String) -> Result<(), String> {
         let mut metadata = load_profiles_metadata();
-        
-        // Verify profile exists
-        if !metadata.profiles.iter().any(|p| p.id == profile_id) {
-            return Err(format!("Profile {} not found", profile_id));
-        }
-        
-        metadata.current_profile_id = Some(profile_id.clone());
+        metadata.select_profile(&profile_id)?;
         save_profiles_metadata(&metadata).map_err(|e| format!("Failed to save profiles metadata: {}", e))?;
         
         if let Some(logger) = logger::get_logger() {
@@ -211,24 +263,36 @@ impl ProfileManager {
         metadata.profiles
     }
     
-    /// Delete a profile (and its directory)
-    pub fn delete_profile(profile_id: String) -> Result<(), String> {
+    /// Create a profile with just a display name; it's bound to an account on first login.
+    pub fn create_profile(name: &str) -> Result<Profile, String> {
         let mut metadata = load_profiles_metadata();
-        
-        // Remove from list
-        metadata.profiles.retain(|p| p.id != profile_id);
-        
-        // If this was the current profile, clear it
-        if metadata.current_profile_id.as_ref() == Some(&profile_id) {
-            metadata.current_profile_id = None;
-        }
+        let profile = metadata.add_named_profile(name, chrono::Utc::now().timestamp())?;
+        save_profiles_metadata(&metadata).map_err(|e| format!("Failed to save profiles metadata: {}", e))?;
+        // Creates the profile directory
+        let _ = get_profile_dir(&profile.id);
+        Ok(profile)
+    }
+
+    /// Change a profile's display name.
+    pub fn rename_profile(profile_id: &str, name: &str) -> Result<Profile, String> {
+        let mut metadata = load_profiles_metadata();
+        let profile = metadata.rename_profile(profile_id, name)?;
+        save_profiles_metadata(&metadata).map_err(|e| format!("Failed to save profiles metadata: {}", e))?;
+        Ok(profile)
+    }
+
+    /// Delete a profile, optionally with its directory. Deleting the active profile falls
+    /// back to the first remaining one; returns true when the active profile changed.
+    pub fn delete_profile(profile_id: String, delete_data: bool) -> Result<bool, String> {
+        let mut metadata = load_profiles_metadata();
+        let active_changed = metadata.remove_profile(&profile_id)?;
         
         // Save metadata
         save_profiles_metadata(&metadata).map_err(|e| format!("Failed to save profiles metadata: {}", e))?;
         
-        // Delete profile directory
-        let profile_dir = get_profile_dir(&profile_id);
-        if profile_dir.exists() {
+        // Delete profile directory (without get_profile_dir, which would recreate it)
+        let profile_dir = profiles_dir().join(&profile_id);
+        if delete_data && profile_dir.exists() {
             fs::remove_dir_all(&profile_dir).map_err(|e| format!("Failed to delete profile directory: {}", e))?;
         }
         
@@ -238,11 +302,11 @@ impl ProfileManager {
                 "profiles",
                 "delete_profile",
                 "Deleted profile",
-                serde_json::json!({"profile_id": profile_id}),
+                serde_json::json!({"profile_id": profile_id, "delete_data": delete_data}),
             );
         }
         
-        Ok(())
+        Ok(active_changed)
     }
     
     /// Check if migration has been completed
@@ -271,15 +335,50 @@ pub fn list_profiles() -> Vec<Profile> {
 }
 
 #[tauri::command]
-pub fn switch_profile(profile_id: String, app: tauri::AppHandle) -> Result<(), String> {
-    ProfileManager::set_current_profile(profile_id.clone())?;
+pub fn create_profile(name: String) -> Result<Profile, String> {
+    ProfileManager::create_profile(&name)
+}
+
+#[tauri::command]
+pub fn rename_profile(profile_id: String, name: String) -> Result<Profile, String> {
+    ProfileManager::rename_profile(&profile_id, &name)
+}
 
+/// Drop everything held in memory for the previous profile and pick up the settings cached
+/// in memory from the current one.
+fn reset_profile_state(app: &tauri::AppHandle) {
     // Invalidate session cache so API calls use the new profile's session (not stale in-memory cache)
     crate::session::Session::invalidate_cache();
+    crate::caches::clear_memory_caches();
+    let settings = crate::settings::Settings::load();
+    crate::netgrab::set_offline_mode(settings.dev_force_offline_mode);
+    crate::timezone::set_timezone_override(settings.timezone.as_deref());
+    #[cfg(desktop)]
+    crate::global_shortcuts::register_saved(app);
+    // The inbox checks belong to the previous profile's session and settings
+    crate::message_poller::stop();
+    crate::message_poller::start_if_enabled(app.clone());
+}
+
+/// Drop everything held in memory for the previous profile, reopen the database and tell the
+/// UI to reload.
+fn activate_current_profile(app: &tauri::AppHandle) -> Result<(), String> {
+    use tauri::Emitter;
+
+    reset_profile_state(app);
 
     // Reinitialize database for new profile
-    crate::database::reinit_database(&app)
+    crate::database::reinit_database(app)
         .map_err(|e| format!("Failed to reinitialize database: {}", e))?;
+
+    let _ = app.emit("profile-changed", ProfileManager::get_current_profile());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn switch_profile(profile_id: String, app: tauri::AppHandle) -> Result<(), String> {
+    ProfileManager::set_current_profile(profile_id.clone())?;
+    activate_current_profile(&app)?;
     
     if let Some(logger) = logger::get_logger() {
         let _ = logger.log(
@@ -294,8 +393,110 @@ pub fn switch_profile(profile_id: String, app: tauri::AppHandle) -> Result<(), S
     Ok(())
 }
 
+/// Delete a profile. Its data directory is removed too unless `delete_data` is false.
 #[tauri::command]
-pub fn delete_profile(profile_id: String) -> Result<(), String> {
-    ProfileManager::delete_profile(profile_id)
+pub fn delete_profile(
+    profile_id: String,
+    delete_data: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let delete_data = delete_data.unwrap_or(true);
+    // The open database lives in the active profile's directory, so close it before that
+    // directory is removed
+    let closes_database =
+        delete_data && ProfileManager::get_current_profile().is_some_and(|p| p.id == profile_id);
+    if closes_database {
+        crate::database::close_database();
+    }
+
+    let active_changed = match ProfileManager::delete_profile(profile_id, delete_data) {
+        Ok(active_changed) => active_changed,
+        Err(e) => {
+            if closes_database {
+                let _ = crate::database::reinit_database(&app);
+            }
+            return Err(e);
+        }
+    };
+    if active_changed {
+        if ProfileManager::get_current_profile().is_some() {
+            activate_current_profile(&app)?;
+        } else {
+            use tauri::Emitter;
+            reset_profile_state(&app);
+            // Start over with an empty database rather than leaving it closed
+            crate::database::reinit_database(&app)
+                .map_err(|e| format!("Failed to reinitialize database: {}", e))?;
+            let _ = app.emit("profile-changed", None::<Profile>);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_with(ids: &[&str]) -> ProfilesMetadata {
+        ProfilesMetadata {
+            profiles: ids
+                .iter()
+                .map(|id| Profile {
+                    id: id.to_string(),
+                    base_url: String::new(),
+                    user_id: 0,
+                    display_name: None,
+                    created_at: 0,
+                })
+                .collect(),
+            current_profile_id: ids.first().map(|id| id.to_string()),
+            migration_completed: true,
+        }
+    }
+
+    #[test]
+    fn test_create_profile_becomes_current_only_when_none() {
+        let mut metadata = ProfilesMetadata::default();
+        let first = metadata.add_named_profile("  School  ", 1).unwrap();
+        assert_eq!(first.display_name.as_deref(), Some("School"));
+        assert_eq!(metadata.current_profile_id.as_deref(), Some(first.id.as_str()));
+
+        let second = metadata.add_named_profile("Home", 2).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(metadata.current_profile_id.as_deref(), Some(first.id.as_str()));
+        assert!(metadata.add_named_profile("   ", 3).is_err());
+        assert_eq!(metadata.profiles.len(), 2);
+    }
+
+    #[test]
+    fn test_switch_and_rename_require_existing_profile() {
+        let mut metadata = metadata_with(&["a", "b"]);
+        metadata.select_profile("b").unwrap();
+        assert_eq!(metadata.current_profile_id.as_deref(), Some("b"));
+        assert!(metadata.select_profile("missing").is_err());
+        assert_eq!(metadata.current_profile_id.as_deref(), Some("b"));
+
+        assert!(metadata.rename_profile("missing", "x").is_err());
+        assert_eq!(
+            metadata.rename_profile("a", "Renamed").unwrap().display_name.as_deref(),
+            Some("Renamed")
+        );
+    }
+
+    #[test]
+    fn test_delete_active_profile_falls_back() {
+        let mut metadata = metadata_with(&["a", "b", "c"]);
+        metadata.select_profile("b").unwrap();
+
+        assert!(!metadata.remove_profile("c").unwrap());
+        assert_eq!(metadata.current_profile_id.as_deref(), Some("b"));
+
+        assert!(metadata.remove_profile("b").unwrap());
+        assert_eq!(metadata.current_profile_id.as_deref(), Some("a"));
+
+        assert!(metadata.remove_profile("a").unwrap());
+        assert_eq!(metadata.current_profile_id, None);
+        assert!(metadata.remove_profile("a").is_err());
+    }
 }
 
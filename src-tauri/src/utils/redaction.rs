@@ -0,0 +1,212 @@
+//! Masks personal details in logs and support reports while `dev_sensitive_info_hider` is on.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::{profiles, settings, student_info};
+
+/// Replacement for masked values.
+pub const REDACTED: &str = "[redacted]";
+
+/// JSON keys masked when `redacted_fields` isn't set. Compared ignoring case, `_` and `-`, so
+/// `studentId` also covers `student_id`.
+pub const DEFAULT_REDACTED_FIELDS: &[&str] = &[
+    "email",
+    "userName",
+    "userDesc",
+    "displayName",
+    "firstName",
+    "lastName",
+    "surname",
+    "prefName",
+    "preferredName",
+    "sender",
+    "student",
+    "studentId",
+    "studentNumber",
+    "userCode",
+    "personUuid",
+    "storedUsername",
+];
+
+static EMAIL_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)+").unwrap());
+
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Masks sensitive keys and free-text details. Built from the settings and what's known about
+/// the signed-in student, so their name is caught even outside a named field.
+pub struct Redactor {
+    fields: HashSet<String>,
+    /// Exact strings to mask wherever they appear (names, usernames)
+    literals: Vec<String>,
+    home_dir: Option<String>,
+}
+
+impl Redactor {
+    pub fn new(fields: &[String], literals: Vec<String>) -> Self {
+        let mut literals: Vec<String> = literals
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| s.chars().count() >= 3)
+            .collect();
+        // Longest first, so a full name is masked before a username inside it
+        literals.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        Self {
+            fields: fields.iter().map(|f| normalize_key(f)).collect(),
+            literals,
+            home_dir: dirs_next::home_dir().map(|dir| dir.to_string_lossy().to_string()),
+        }
+    }
+
+    /// Field list from the settings, plus the current profile's and student's names.
+    pub fn from_settings() -> Self {
+        let fields = settings::Settings::load()
+            .redacted_fields
+            .unwrap_or_else(|| {
+                DEFAULT_REDACTED_FIELDS
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect()
+            });
+
+        let mut literals = Vec::new();
+        if let Some(info) = student_info::cached_student_info() {
+            literals.push(info.name);
+            literals.extend(info.user_name);
+        }
+        if let Some(profile) = profiles::ProfileManager::get_current_profile() {
+            literals.extend(profile.display_name);
+        }
+        Self::new(&fields, literals)
+    }
+
+    /// Mask email addresses, known names and the home directory (which usually holds the
+    /// account name) in free text.
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut result = EMAIL_PATTERN.replace_all(text, "[email]").into_owned();
+        for literal in &self.literals {
+            result = result.replace(literal.as_str(), REDACTED);
+        }
+        if let Some(home) = self.home_dir.as_deref().filter(|h| h.len() > 1) {
+            result = result.replace(home, "~");
+        }
+        result
+    }
+
+    /// Mask every value under a sensitive key, and the free-text details in all other strings.
+    pub fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.fields.contains(&normalize_key(key)) {
+                        if !field.is_null() {
+                            *field = Value::String(REDACTED.to_string());
+                        }
+                    } else {
+                        self.redact_value(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::String(s) => *s = self.redact_text(s),
+            _ => {}
+        }
+    }
+
+    /// Redact one `logger` line: `[..] message | {metadata}`. The metadata is masked by key,
+    /// the rest as free text.
+    fn redact_log_line(&self, line: &str) -> String {
+        let metadata = line.match_indices(" | ").find_map(|(index, _)| {
+            serde_json::from_str::<Value>(&line[index + 3..])
+                .ok()
+                .map(|value| (index, value))
+        });
+        match metadata {
+            Some((index, mut value)) => {
+                self.redact_value(&mut value);
+                format!("{} | {}", self.redact_text(&line[..index]), value)
+            }
+            None => self.redact_text(line),
+        }
+    }
+
+    /// Redact a whole log file, line by line.
+    pub fn redact_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.redact_log_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Mask names, emails and student numbers in `value` using the configured field list.
+pub fn redact_sensitive(value: &mut Value) {
+    Redactor::from_settings().redact_value(value);
+}
+
+/// Whether logs and reports should be redacted.
+pub fn is_enabled() -> bool {
+    settings::Settings::load().dev_sensitive_info_hider
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn redactor() -> Redactor {
+        let fields: Vec<String> = DEFAULT_REDACTED_FIELDS
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        Redactor::new(&fields, vec!["Jane Smith".to_string(), "js".to_string()])
+    }
+
+    #[test]
+    fn test_redact_value() {
+        let mut value = json!({
+            "payload": {
+                "id": 12,
+                "user_name": "jsmith",
+                "studentId": 1234,
+                "displayName": null,
+                "subjects": [{ "title": "Maths", "note": "Mail jane@school.edu.au" }],
+                "summary": "Jane Smith's timetable"
+            }
+        });
+        redactor().redact_value(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "payload": {
+                    "id": 12,
+                    "user_name": REDACTED,
+                    "studentId": REDACTED,
+                    "displayName": null,
+                    "subjects": [{ "title": "Maths", "note": "Mail [email]" }],
+                    "summary": "[redacted]'s timetable"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_redact_log_keeps_format() {
+        let log = "[2025-01-01 09:00:00.000] [INFO] [auth::login] [:0] [ThreadId(1)] \
+                   Logged in as jsmith@school.edu.au | {\"email\":\"a@b.co\",\"count\":2}\n\
+                   plain line from jane.smith@example.org";
+        let redacted = redactor().redact_log(log);
+        assert!(!EMAIL_PATTERN.is_match(&redacted));
+        assert!(redacted.contains("Logged in as [email] | "));
+        assert!(redacted.contains("\"count\":2"));
+        assert!(redacted.ends_with("plain line from [email]"));
+    }
+}
@@ -1,20 +1,22 @@
 use once_cell::sync::Lazy;
 /// Input sanitization utilities for the Rust backend
 use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
 
 /// Maximum allowed length for search queries
 const MAX_SEARCH_QUERY_LENGTH: usize = 500;
 
-/// Maximum allowed length for filenames
-#[allow(dead_code)]
+/// Maximum allowed length for filenames, in bytes
 const MAX_FILENAME_LENGTH: usize = 255;
 
+/// Maximum length for a folder name or file stem, in bytes
+const MAX_PATH_COMPONENT_LENGTH: usize = 100;
+
 /// Regex patterns for sanitization
 static HTML_TAG_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
 static JAVASCRIPT_PROTOCOL: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)javascript:").unwrap());
 static EVENT_HANDLER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)on\w+\s*=").unwrap());
-#[allow(dead_code)]
-static PATH_TRAVERSAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.\./|\.\.\").unwrap());
+static PATH_TRAVERSAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.\.[/\\]").unwrap());
 
 /// Sanitize search query input
 pub fn sanitize_search_query(query: &str) -> String {
@@ -72,46 +74,88 @@ pub fn sanitize_text(text: &str) -> String {
     sanitized
 }
 
-/// Sanitize filename
-#[allow(dead_code)]
-pub fn sanitize_filename(filename: &str) -> String {
-    if filename.is_empty() {
-        return "unnamed".to_string();
-    }
+/// Device names Windows reserves regardless of extension (`nul.txt` is still NUL)
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
 
-    let mut sanitized = filename.to_string();
+/// Longest byte prefix of `s` that fits in `max_bytes` without splitting a character
+fn truncate_to_bytes(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
 
-    // Remove path traversal attempts
-    sanitized = PATH_TRAVERSAL.replace_all(&sanitized, "").to_string();
+/// Shared cleanup for file and folder names: NFC-normalize, drop traversal sequences,
+/// separators, forbidden and control characters, and trim dots/spaces Windows won't keep.
+fn clean_path_component(name: &str) -> String {
+    let normalized: String = name.nfc().collect();
+    let without_traversal = PATH_TRAVERSAL.replace_all(&normalized, "");
 
-    // Remove dangerous characters (Windows forbidden chars + common dangerous ones)
-    let forbidden_chars = ['<', '>', ':', '"', '|', '?', '*', '\0'];
-    for ch in forbidden_chars {
-        sanitized = sanitized.replace(ch, "");
-    }
+    let forbidden_chars = ['<', '>', ':', '"', '|', '?', '*', '/', '\\'];
+    let cleaned: String = without_traversal
+        .chars()
+        .filter(|c| !c.is_control() && !forbidden_chars.contains(c))
+        .collect();
 
-    // Remove control characters (ASCII 0-31)
-    sanitized = sanitized.chars().filter(|&c| c as u32 >= 32).collect();
+    cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace()).to_string()
+}
 
-    // Remove leading dots
-    sanitized = sanitized.trim_start_matches('.').to_string();
+/// Prefix Windows device names so they can be created as ordinary files
+fn escape_reserved_name(name: String) -> String {
+    let stem = name.split('.').next().unwrap_or("").trim_end();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
 
-    // Limit length
-    if sanitized.len() > MAX_FILENAME_LENGTH {
-        // Try to preserve file extension
-        if let Some(pos) = sanitized.rfind('.') {
-            let ext = &sanitized[pos..];
-            if ext.len() < 10 {
-                let name_max_len = MAX_FILENAME_LENGTH - ext.len();
-                sanitized = format!("{}{}", &sanitized[..name_max_len], ext);
-            } else {
-                sanitized.truncate(MAX_FILENAME_LENGTH);
+/// Sanitize a filename so it is safe to create on Windows, macOS and Linux.
+/// The length limit counts bytes and keeps a short extension intact.
+pub fn sanitize_filename(filename: &str) -> String {
+    let sanitized = escape_reserved_name(clean_path_component(filename));
+
+    let sanitized = if sanitized.len() > MAX_FILENAME_LENGTH {
+        match sanitized.rfind('.') {
+            Some(pos) if sanitized.len() - pos < 10 && pos > 0 => {
+                let ext = &sanitized[pos..];
+                let stem = truncate_to_bytes(&sanitized[..pos], MAX_FILENAME_LENGTH - ext.len());
+                format!("{}{}", stem.trim_end(), ext)
             }
-        } else {
-            sanitized.truncate(MAX_FILENAME_LENGTH);
+            _ => truncate_to_bytes(&sanitized, MAX_FILENAME_LENGTH)
+                .trim_end()
+                .to_string(),
         }
+    } else {
+        sanitized
+    };
+
+    if sanitized.is_empty() {
+        return "unnamed".to_string();
     }
 
+    sanitized
+}
+
+/// Sanitize a single folder name or file stem. Capped at `MAX_PATH_COMPONENT_LENGTH` bytes
+/// so callers can append an extension (e.g. a note title becoming `<title>.json`).
+pub fn sanitize_path_component(name: &str) -> String {
+    let cleaned = clean_path_component(name);
+    let truncated = truncate_to_bytes(&cleaned, MAX_PATH_COMPONENT_LENGTH)
+        .trim_end_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string();
+    let sanitized = escape_reserved_name(truncated);
+
     if sanitized.is_empty() {
         return "unnamed".to_string();
     }
@@ -220,6 +264,31 @@ mod tests {
         assert_eq!(sanitize_filename(""), "unnamed");
     }
 
+    #[test]
+    fn test_sanitize_filename_reserved_names() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_filename("Com1 .log"), "_Com1 .log");
+        assert_eq!(sanitize_filename("CONSOLE.txt"), "CONSOLE.txt");
+        assert_eq!(sanitize_path_component("lpt9"), "_lpt9");
+    }
+
+    #[test]
+    fn test_sanitize_filename_all_illegal_characters() {
+        assert_eq!(sanitize_filename("<>:\"/\\|?*"), "unnamed");
+        assert_eq!(sanitize_path_component(" ... "), "unnamed");
+        assert_eq!(sanitize_path_component("name. "), "name");
+    }
+
+    #[test]
+    fn test_sanitize_filename_truncates_on_char_boundary() {
+        let long = format!("{}.pdf", "é".repeat(200));
+        let sanitized = sanitize_filename(&long);
+        assert!(sanitized.len() <= MAX_FILENAME_LENGTH);
+        assert!(sanitized.ends_with(".pdf"));
+        assert!(sanitize_path_component(&"日".repeat(50)).len() <= MAX_PATH_COMPONENT_LENGTH);
+    }
+
     #[test]
     fn test_validate_url() {
         assert!(validate_url("https://example.com").is_ok());
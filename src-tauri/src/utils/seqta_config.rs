@@ -1,8 +1,13 @@
-use serde_json::Value;
-use std::{fs, io::Read, path::PathBuf};
-use tauri::command;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs, io::Read, path::PathBuf, sync::Mutex};
+use tauri::{command, Emitter};
+use crate::netgrab;
 use crate::profiles;
 
+/// Config fetched from SEQTA that differs from the stored one, held until the user acknowledges it.
+static PENDING_CONFIG: Mutex<Option<Value>> = Mutex::new(None);
+
 /// Returns the path to seqtaConfig.json in the profile directory.
 fn config_file() -> PathBuf {
     let mut dir = profiles::get_profile_dir(
@@ -45,3 +50,184 @@ pub fn is_seqta_config_different(new_config: Value) -> bool {
         None => true,
     }
 }
+
+/// One setting whose value changed between the stored and fetched config.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ChangedConfigKey {
+    pub key: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Structured difference between two SEQTA configs, keyed by setting name
+/// (e.g. `coneqt-s.page.goals`). Sections are the key minus its last segment.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct SeqtaConfigDiff {
+    pub sections: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedConfigKey>,
+    /// True when the new config was saved straight away (auto-accept setting)
+    pub accepted: bool,
+}
+
+impl SeqtaConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn config_section(key: &str) -> String {
+    match key.rsplit_once('.') {
+        Some((section, _)) => section.to_string(),
+        None => key.to_string(),
+    }
+}
+
+fn config_entries(config: &Value) -> HashMap<&str, &Value> {
+    config
+        .get("payload")
+        .and_then(|p| p.as_object())
+        .map(|obj| obj.iter().map(|(k, v)| (k.as_str(), v)).collect())
+        .unwrap_or_default()
+}
+
+/// Compare the `payload` maps of two `/seqta/student/load/settings` responses.
+fn diff_configs(old: &Value, new: &Value) -> SeqtaConfigDiff {
+    let old_entries = config_entries(old);
+    let new_entries = config_entries(new);
+    let mut diff = SeqtaConfigDiff::default();
+
+    for (key, new_value) in &new_entries {
+        match old_entries.get(key) {
+            None => diff.added.push(key.to_string()),
+            Some(old_value) if old_value != new_value => diff.changed.push(ChangedConfigKey {
+                key: key.to_string(),
+                old: (*old_value).clone(),
+                new: (*new_value).clone(),
+            }),
+            _ => {}
+        }
+    }
+    for key in old_entries.keys() {
+        if !new_entries.contains_key(key) {
+            diff.removed.push(key.to_string());
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut sections: Vec<String> = diff
+        .added
+        .iter()
+        .chain(diff.removed.iter())
+        .chain(diff.changed.iter().map(|c| &c.key))
+        .map(|k| config_section(k))
+        .collect();
+    sections.sort();
+    sections.dedup();
+    diff.sections = sections;
+
+    diff
+}
+
+async fn fetch_seqta_config() -> Result<Value, String> {
+    let headers = HashMap::from([(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    )]);
+    let response = netgrab::fetch_api_data(
+        "/seqta/student/load/settings",
+        netgrab::RequestMethod::POST,
+        Some(headers),
+        Some(json!({})),
+        None,
+        false,
+        false,
+        None,
+    )
+    .await?;
+    serde_json::from_str(&response).map_err(|e| format!("Failed to parse SEQTA config: {}", e))
+}
+
+/// Fetch the SEQTA config and compare it with the stored copy. Emits `seqta-config-changed`
+/// with the diff when they differ. The new config is saved immediately only if
+/// `auto_accept_seqta_config_changes` is on; otherwise it waits for
+/// `acknowledge_seqta_config_changes`. The first fetch for a profile is saved silently.
+#[command]
+pub async fn check_seqta_config_changes(
+    app: tauri::AppHandle,
+) -> Result<Option<SeqtaConfigDiff>, String> {
+    let fetched = fetch_seqta_config().await?;
+
+    let stored = match load_seqta_config() {
+        Some(stored) => stored,
+        None => {
+            save_seqta_config(fetched)?;
+            return Ok(None);
+        }
+    };
+
+    let mut diff = diff_configs(&stored, &fetched);
+    if diff.is_empty() {
+        return Ok(None);
+    }
+
+    if crate::settings::Settings::load().auto_accept_seqta_config_changes {
+        save_seqta_config(fetched)?;
+        diff.accepted = true;
+    } else if let Ok(mut pending) = PENDING_CONFIG.lock() {
+        *pending = Some(fetched);
+    }
+
+    let _ = app.emit("seqta-config-changed", &diff);
+    Ok(Some(diff))
+}
+
+/// Save the config held back by the last `check_seqta_config_changes`.
+/// Returns false if nothing was pending.
+#[command]
+pub fn acknowledge_seqta_config_changes() -> Result<bool, String> {
+    let pending = PENDING_CONFIG
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    match pending {
+        Some(config) => save_seqta_config(config).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_configs_reports_sections() {
+        let old = json!({ "payload": {
+            "coneqt-s.page.goals": { "value": "enabled" },
+            "attendance.defaults.time_from": { "value": "08:00" },
+            "coneqt-s.page.forums": { "value": "enabled" }
+        }});
+        let new = json!({ "payload": {
+            "coneqt-s.page.goals": { "value": "disabled" },
+            "attendance.defaults.time_from": { "value": "08:00" },
+            "coneqt-s.page.folios": { "value": "enabled" }
+        }});
+
+        let diff = diff_configs(&old, &new);
+        assert_eq!(diff.added, vec!["coneqt-s.page.folios"]);
+        assert_eq!(diff.removed, vec!["coneqt-s.page.forums"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].key, "coneqt-s.page.goals");
+        assert_eq!(diff.sections, vec!["coneqt-s.page"]);
+    }
+
+    #[test]
+    fn test_diff_configs_identical() {
+        let config = json!({ "payload": { "a.b": { "value": "1" } } });
+        assert!(diff_configs(&config, &config).is_empty());
+    }
+}
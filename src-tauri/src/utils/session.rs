@@ -7,6 +7,7 @@ use ring::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs,
     io::{self, Read, Write},
     path::PathBuf,
@@ -152,9 +153,44 @@ pub struct Session {
     /// Stored credentials for direct login (encrypted)
     pub stored_username: Option<String>,
     pub stored_password: Option<String>,
+    /// Extra headers sent with every SEQTA request, for schools behind proxies that need them
+    #[serde(default)]
+    pub custom_headers: HashMap<String, String>,
+    /// Path SEQTA is served under when it isn't `/seqta`, e.g. `/portal/seqta` or `""`
+    #[serde(default)]
+    pub seqta_prefix: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Path SEQTA is served under on most instances. Endpoint paths throughout the app are
+/// written with it (`/seqta/student/...`) and rewritten for sessions with another prefix.
+pub const DEFAULT_SEQTA_PREFIX: &str = "/seqta";
+
+/// `prefix` with a leading slash and no trailing one; `""` when SEQTA is at the root.
+pub fn normalize_seqta_prefix(prefix: &str) -> String {
+    let trimmed = prefix.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+/// Full URL of the SEQTA endpoint `path` at `base_url`. A leading `/seqta` in `path` is
+/// replaced by `prefix` (`None` keeps the default); other paths are appended as they are.
+pub fn seqta_url(base_url: &str, prefix: Option<&str>, path: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let rest = path
+        .strip_prefix(DEFAULT_SEQTA_PREFIX)
+        .filter(|rest| rest.is_empty() || rest.starts_with(['/', '?']));
+    match (prefix, rest) {
+        (Some(prefix), Some(rest)) => {
+            format!("{}{}{}", base_url, normalize_seqta_prefix(prefix), rest)
+        }
+        _ => format!("{}{}", base_url, path),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Cookie {
     pub name: String,
     pub value: String,
@@ -324,6 +360,25 @@ impl Session {
             additional_cookies: Vec::new(),
             stored_username: None,
             stored_password: None,
+            custom_headers: HashMap::new(),
+            seqta_prefix: None,
+        }
+    }
+
+    /// Full URL of the SEQTA endpoint `path` (written as `/seqta/student/...`) for this
+    /// session, respecting its prefix.
+    pub fn seqta_url(&self, path: &str) -> String {
+        seqta_url(&self.base_url, self.seqta_prefix.as_deref(), path)
+    }
+
+    /// Custom headers of the saved session if it's for the same SEQTA instance, so logging in
+    /// again keeps them; empty for any other instance.
+    pub fn custom_headers_for(base_url: &str) -> HashMap<String, String> {
+        let current = Self::load();
+        if current.base_url.trim_end_matches('/') == base_url.trim_end_matches('/') {
+            current.custom_headers
+        } else {
+            HashMap::new()
         }
     }
 
@@ -457,4 +512,42 @@ impl Session {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seqta_url_with_custom_prefix() {
+        let base = "https://learn.example.edu.au/";
+        assert_eq!(
+            seqta_url(base, None, "/seqta/student/load/subjects?"),
+            "https://learn.example.edu.au/seqta/student/load/subjects?"
+        );
+        assert_eq!(
+            seqta_url(base, Some("portal/seqta/"), "/seqta/student/load/subjects?"),
+            "https://learn.example.edu.au/portal/seqta/student/load/subjects?"
+        );
+        // SEQTA at the root of the base URL
+        assert_eq!(
+            seqta_url(base, Some(""), "/seqta/student/heartbeat"),
+            "https://learn.example.edu.au/student/heartbeat"
+        );
+        // Only a whole `/seqta` segment is replaced
+        assert_eq!(
+            seqta_url(base, Some("/portal"), "/seqtaextra/file"),
+            "https://learn.example.edu.au/seqtaextra/file"
+        );
+
+        let session = Session {
+            base_url: "https://school.example.com/learning".to_string(),
+            seqta_prefix: Some("/sq".to_string()),
+            ..Session::default()
+        };
+        assert_eq!(
+            session.seqta_url("/seqta/student/login"),
+            "https://school.example.com/learning/sq/student/login"
+        );
+    }
+}
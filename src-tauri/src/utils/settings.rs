@@ -6,6 +6,7 @@ use std::{
     fs,
     io::{self, Read},
     path::PathBuf,
+    sync::Mutex,
 };
 
 #[path = "session.rs"]
@@ -81,6 +82,18 @@ pub struct CloudToken {
 pub struct CloudState {
     #[serde(default)]
     pub previously_signed_into_cloud: bool,
+    /// `settings_hash` of the settings last uploaded, with its visibility and time
+    #[serde(default)]
+    pub last_upload_hash: Option<String>,
+    #[serde(default)]
+    pub last_upload_public: bool,
+    #[serde(default)]
+    pub last_upload_at: Option<String>,
+    /// `settings_hash` of the settings last downloaded, with the time
+    #[serde(default)]
+    pub last_download_hash: Option<String>,
+    #[serde(default)]
+    pub last_download_at: Option<String>,
 }
 
 impl CloudState {
@@ -96,6 +109,15 @@ impl CloudState {
         }
         CloudState::default()
     }
+    /// Forget what was last uploaded and downloaded. Called when the cloud account changes, since
+    /// the new account has none of it.
+    fn forget_sync(&mut self) {
+        self.last_upload_hash = None;
+        self.last_upload_public = false;
+        self.last_upload_at = None;
+        self.last_download_hash = None;
+        self.last_download_at = None;
+    }
     pub fn save(&self) -> io::Result<()> {
         let path = cloud_state_file();
         if let Some(parent) = path.parent() {
@@ -272,12 +294,79 @@ pub struct Settings {
     pub downloaded_theme_ids: Option<Vec<String>>, // Array of theme UUIDs from cloud store
     #[serde(default)]
     pub downloaded_theme_metadata: Option<serde_json::Value>, // Map of theme UUID -> { version, checksum, updated_at }
-    /// Interface zoom level (0.5 to 2.0, default 1.0)
+    /// Interface zoom level (0.5 to 3.0, default 1.0)
     #[serde(default)]
     pub zoom_level: Option<f64>,
     /// When true, closing the window hides to system tray. When false, closing fully quits the app.
     #[serde(default = "default_minimize_to_tray")]
     pub minimize_to_tray: bool,
+    /// How long the browser login window waits for a SEQTA session before giving up (seconds).
+    #[serde(default = "default_login_timeout_secs")]
+    pub login_timeout_secs: u64,
+    /// Save SEQTA config changes without waiting for the user to acknowledge them.
+    #[serde(default)]
+    pub auto_accept_seqta_config_changes: bool,
+    /// Periodically send SEQTA heartbeats while the app is open so the session doesn't idle out.
+    #[serde(default)]
+    pub session_keepalive_enabled: bool,
+    /// Minutes between keep-alive heartbeats.
+    #[serde(default = "default_session_keepalive_interval_mins")]
+    pub session_keepalive_interval_mins: u64,
+    /// Check the inbox in the background and notify about new messages.
+    #[serde(default)]
+    pub message_polling_enabled: bool,
+    /// Minutes between background inbox checks.
+    #[serde(default = "default_message_polling_interval_mins")]
+    pub message_polling_interval_mins: u64,
+    /// Pins the color scheme to "light" or "dark", ignoring the theme's autoSwitchTime.
+    #[serde(default)]
+    pub color_scheme_override: Option<String>,
+    /// When launched at login, stay in the tray instead of showing the window.
+    #[serde(default)]
+    pub autostart_minimized: bool,
+    /// When launched at login, wait this many seconds before showing the window.
+    #[serde(default)]
+    pub autostart_delay_secs: u32,
+    /// How long before each assessment's due date reminders fire (seconds, one per reminder).
+    #[serde(default = "default_reminder_lead_times_secs")]
+    pub reminder_lead_times_secs: Vec<u64>,
+    /// Notes folder (relative to the notes root) that quick captures are saved to; empty is the root.
+    #[serde(default)]
+    pub default_folder: String,
+    /// JSON keys masked in logs and reports while `dev_sensitive_info_hider` is on. Unset uses
+    /// `redaction::DEFAULT_REDACTED_FIELDS`.
+    #[serde(default)]
+    pub redacted_fields: Option<Vec<String>>,
+    /// Back up notes automatically every `notes_backup_interval_hours` and on exit.
+    #[serde(default)]
+    pub notes_backup_enabled: bool,
+    #[serde(default = "default_notes_backup_interval_hours")]
+    pub notes_backup_interval_hours: u64,
+    /// Oldest notes backups beyond this many are deleted after an automatic backup.
+    #[serde(default = "default_notes_max_backups")]
+    pub notes_max_backups: u32,
+    /// Quiet period after the last edit before `autosave_note` writes a note to disk.
+    #[serde(default = "default_notes_autosave_interval_ms")]
+    pub notes_autosave_interval_ms: u64,
+    /// Re-encode opaque images pasted into notes as lossy WebP. Images with transparency stay PNG.
+    #[serde(default)]
+    pub notes_images_webp: bool,
+    /// WebP quality (0-100) used when `notes_images_webp` is on.
+    #[serde(default = "default_notes_images_webp_quality")]
+    pub notes_images_webp_quality: u8,
+    /// Keep the original file under `originals/` when a note image is re-encoded.
+    #[serde(default)]
+    pub notes_images_keep_originals: bool,
+    /// System-wide hotkeys for quick actions, registered on startup (desktop only).
+    #[serde(default)]
+    pub global_shortcuts: Vec<GlobalShortcutBinding>,
+    /// Notification categories to show and quiet hours. Nothing is shown while
+    /// `reminders_enabled` is off.
+    #[serde(default)]
+    pub notification_prefs: NotificationPrefs,
+    /// IANA timezone of the school (e.g. "Australia/Perth"). Unset uses the system timezone.
+    #[serde(default)]
+    pub timezone: Option<String>,
     /// Last settings revision returned by accounts.betterseqta.org (`sync-init` / `POST /api/settings`).
     #[serde(default)]
     pub cloud_settings_server_revision: i64,
@@ -290,6 +379,38 @@ fn default_minimize_to_tray() -> bool {
     true
 }
 
+fn default_login_timeout_secs() -> u64 {
+    1920
+}
+
+fn default_session_keepalive_interval_mins() -> u64 {
+    10
+}
+
+fn default_message_polling_interval_mins() -> u64 {
+    5
+}
+
+fn default_notes_backup_interval_hours() -> u64 {
+    24
+}
+
+fn default_notes_max_backups() -> u32 {
+    10
+}
+
+fn default_notes_autosave_interval_ms() -> u64 {
+    2000
+}
+
+fn default_notes_images_webp_quality() -> u8 {
+    80
+}
+
+fn default_reminder_lead_times_secs() -> Vec<u64> {
+    vec![24 * 60 * 60, 60 * 60]
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -338,6 +459,28 @@ impl Default for Settings {
             downloaded_theme_metadata: None,
             zoom_level: None,
             minimize_to_tray: true,
+            login_timeout_secs: default_login_timeout_secs(),
+            auto_accept_seqta_config_changes: false,
+            session_keepalive_enabled: false,
+            session_keepalive_interval_mins: default_session_keepalive_interval_mins(),
+            message_polling_enabled: false,
+            message_polling_interval_mins: default_message_polling_interval_mins(),
+            color_scheme_override: None,
+            autostart_minimized: false,
+            autostart_delay_secs: 0,
+            reminder_lead_times_secs: default_reminder_lead_times_secs(),
+            default_folder: String::new(),
+            redacted_fields: None,
+            notes_backup_enabled: false,
+            notes_backup_interval_hours: default_notes_backup_interval_hours(),
+            notes_max_backups: default_notes_max_backups(),
+            notes_autosave_interval_ms: default_notes_autosave_interval_ms(),
+            notes_images_webp: false,
+            notes_images_webp_quality: default_notes_images_webp_quality(),
+            notes_images_keep_originals: false,
+            global_shortcuts: Vec::new(),
+            notification_prefs: NotificationPrefs::default(),
+            timezone: None,
             cloud_settings_server_revision: 0,
             cloud_settings_server_updated_at: None,
         }
@@ -351,6 +494,114 @@ pub struct Shortcut {
     pub url: String,
 }
 
+/// Parse a shortcut URL, allowing only http(s). A bare host such as `example.com` or
+/// `localhost:3000` gets `https://` added.
+fn normalize_shortcut_url(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("URL is empty".to_string());
+    }
+    let with_https =
+        || url::Url::parse(&format!("https://{}", url)).map_err(|e| format!("invalid URL ({})", e));
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        // "localhost:3000" parses with "localhost" as its scheme
+        Ok(parsed)
+            if !url.contains("://")
+                && url[parsed.scheme().len() + 1..].starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            with_https()?
+        }
+        Ok(parsed) => {
+            return Err(format!(
+                "\"{}:\" links aren't allowed, only http and https",
+                parsed.scheme()
+            ))
+        }
+        Err(url::ParseError::RelativeUrlWithoutBase) => with_https()?,
+        Err(e) => return Err(format!("invalid URL ({})", e)),
+    };
+    if parsed.host_str().unwrap_or("").is_empty() {
+        return Err("URL has no host".to_string());
+    }
+    Ok(parsed.to_string())
+}
+
+/// URLs of invalid shortcuts already logged, so one left in the settings file is reported
+/// once rather than on every load.
+static LOGGED_INVALID_SHORTCUTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Normalize every shortcut URL and drop shortcuts repeating an earlier URL. A shortcut whose
+/// URL isn't a valid http(s) link is dropped (and logged) on its own, so it can't fail the
+/// whole settings load or save.
+pub fn normalize_shortcuts(shortcuts: Vec<Shortcut>) -> Vec<Shortcut> {
+    let mut seen = std::collections::HashSet::new();
+    shortcuts
+        .into_iter()
+        .filter_map(|shortcut| match normalize_shortcut_url(&shortcut.url) {
+            Ok(url) => seen
+                .insert(url.clone())
+                .then(|| Shortcut { url, ..shortcut }),
+            Err(e) => {
+                let mut logged = LOGGED_INVALID_SHORTCUTS
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                if !logged.contains(&shortcut.url) {
+                    logged.push(shortcut.url.clone());
+                    if let Some(logger) = logger::get_logger() {
+                        let _ = logger.log(
+                            logger::LogLevel::WARN,
+                            "settings",
+                            "normalize_shortcuts",
+                            &format!("Dropping invalid shortcut \"{}\": {}", shortcut.name, e),
+                            serde_json::json!({ "url": shortcut.url }),
+                        );
+                    }
+                }
+                None
+            }
+        })
+        .collect()
+}
+
+/// An accelerator such as `CmdOrCtrl+Shift+K` bound to a quick action.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GlobalShortcutBinding {
+    pub action_id: String,
+    pub accelerator: String,
+}
+
+/// Which kinds of notification are shown, and when they're held back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct NotificationPrefs {
+    pub assessments: bool,
+    pub messages: bool,
+    pub notices: bool,
+    pub reminders: bool,
+    /// Notifications during these hours are queued and shown once they end
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self {
+            assessments: true,
+            messages: true,
+            notices: true,
+            reminders: true,
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Local `HH:MM` times. `start` after `end` spans midnight, e.g. 22:00 to 07:00.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Feed {
     pub url: String,
@@ -516,7 +767,7 @@ impl Settings {
                 });
             }
         }
-        default_settings.shortcuts = shortcuts;
+        default_settings.shortcuts = normalize_shortcuts(shortcuts);
 
         // Merge feeds
         let feeds_json = get_array(&existing_json, "feeds");
@@ -664,6 +915,88 @@ impl Settings {
             "minimize_to_tray",
             default_settings.minimize_to_tray,
         );
+        default_settings.login_timeout_secs = existing_json
+            .get("login_timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_settings.login_timeout_secs);
+        default_settings.auto_accept_seqta_config_changes = get_bool(
+            &existing_json,
+            "auto_accept_seqta_config_changes",
+            default_settings.auto_accept_seqta_config_changes,
+        );
+        default_settings.session_keepalive_enabled = get_bool(
+            &existing_json,
+            "session_keepalive_enabled",
+            default_settings.session_keepalive_enabled,
+        );
+        default_settings.session_keepalive_interval_mins = existing_json
+            .get("session_keepalive_interval_mins")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_settings.session_keepalive_interval_mins);
+        default_settings.message_polling_enabled = get_bool(
+            &existing_json,
+            "message_polling_enabled",
+            default_settings.message_polling_enabled,
+        );
+        default_settings.message_polling_interval_mins = existing_json
+            .get("message_polling_interval_mins")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_settings.message_polling_interval_mins);
+        default_settings.color_scheme_override =
+            get_opt_string(&existing_json, "color_scheme_override");
+        default_settings.autostart_minimized = get_bool(
+            &existing_json,
+            "autostart_minimized",
+            default_settings.autostart_minimized,
+        );
+        default_settings.autostart_delay_secs = existing_json
+            .get("autostart_delay_secs")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32)
+            .unwrap_or(default_settings.autostart_delay_secs);
+        if let Some(lead_times) = existing_json
+            .get("reminder_lead_times_secs")
+            .and_then(|v| v.as_array())
+        {
+            default_settings.reminder_lead_times_secs =
+                lead_times.iter().filter_map(|v| v.as_u64()).collect();
+        }
+        default_settings.default_folder = get_string(&existing_json, "default_folder", "");
+        default_settings.timezone = get_opt_string(&existing_json, "timezone");
+        default_settings.redacted_fields = get_opt_string_array(&existing_json, "redacted_fields");
+        default_settings.notes_backup_enabled = get_bool(
+            &existing_json,
+            "notes_backup_enabled",
+            default_settings.notes_backup_enabled,
+        );
+        default_settings.notes_backup_interval_hours = existing_json
+            .get("notes_backup_interval_hours")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_settings.notes_backup_interval_hours);
+        default_settings.notes_max_backups = existing_json
+            .get("notes_max_backups")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32)
+            .unwrap_or(default_settings.notes_max_backups);
+        default_settings.notes_autosave_interval_ms = existing_json
+            .get("notes_autosave_interval_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default_settings.notes_autosave_interval_ms);
+        default_settings.notes_images_webp = get_bool(
+            &existing_json,
+            "notes_images_webp",
+            default_settings.notes_images_webp,
+        );
+        default_settings.notes_images_webp_quality = existing_json
+            .get("notes_images_webp_quality")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(100) as u8)
+            .unwrap_or(default_settings.notes_images_webp_quality);
+        default_settings.notes_images_keep_originals = get_bool(
+            &existing_json,
+            "notes_images_keep_originals",
+            default_settings.notes_images_keep_originals,
+        );
         default_settings.cloud_settings_server_revision = existing_json
             .get("cloud_settings_server_revision")
             .and_then(|v| v.as_i64())
@@ -742,6 +1075,21 @@ impl Settings {
         // Merge downloaded theme IDs
         default_settings.downloaded_theme_ids = get_opt_string_array(&existing_json, "downloaded_theme_ids");
 
+        // Merge global shortcuts, dropping malformed entries
+        if let Some(bindings) = existing_json.get("global_shortcuts").and_then(|v| v.as_array()) {
+            default_settings.global_shortcuts = bindings
+                .iter()
+                .filter_map(|b| serde_json::from_value(b.clone()).ok())
+                .collect();
+        }
+
+        if let Some(prefs) = existing_json
+            .get("notification_prefs")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            default_settings.notification_prefs = prefs;
+        }
+
         // Merge downloaded theme metadata (preserve as JSON value)
         if let Some(metadata_json) = existing_json.get("downloaded_theme_metadata") {
             default_settings.downloaded_theme_metadata = Some(metadata_json.clone());
@@ -753,7 +1101,10 @@ impl Settings {
     /// Persist to disk.
     pub fn save(&self) -> io::Result<()> {
         let path = settings_file();
-        fs::write(path, serde_json::to_string(self).unwrap())
+        fs::write(path, serde_json::to_string(self).unwrap())?;
+        crate::netgrab::set_offline_mode(self.dev_force_offline_mode);
+        crate::timezone::set_timezone_override(self.timezone.as_deref());
+        Ok(())
     }
 
     /// Convert to JSON string for cloud sync
@@ -796,7 +1147,7 @@ pub fn get_settings() -> Settings {
 }
 
 #[tauri::command]
-pub fn save_settings(new_settings: Settings) -> Result<(), String> {
+pub fn save_settings(mut new_settings: Settings) -> Result<(), String> {
     if let Some(logger) = logger::get_logger() {
         let _ = logger.log(
             logger::LogLevel::INFO,
@@ -806,6 +1157,7 @@ pub fn save_settings(new_settings: Settings) -> Result<(), String> {
             serde_json::json!({}),
         );
     }
+    new_settings.shortcuts = normalize_shortcuts(new_settings.shortcuts);
 
     match new_settings.save() {
         Ok(_) => {
@@ -835,22 +1187,36 @@ pub fn save_settings(new_settings: Settings) -> Result<(), String> {
     }
 }
 
+/// Settings as sent to the frontend. A Gemini key still kept in the file is moved to the
+/// keychain first, and the key is never included.
+fn frontend_settings() -> Settings {
+    let mut settings = Settings::load();
+    if crate::ai::migrate_legacy_gemini_key(&mut settings) {
+        if let Err(e) = settings.save() {
+            println!("[Settings] Failed to clear migrated Gemini key: {}", e);
+        }
+    }
+    settings.gemini_api_key = None;
+    settings
+}
+
 #[tauri::command]
 pub fn get_settings_json() -> Result<String, String> {
-    let settings = Settings::load();
-    settings.to_json()
+    frontend_settings().to_json()
 }
 
 #[tauri::command]
 pub fn save_settings_from_json(json: String) -> Result<(), String> {
-    let settings = Settings::from_json(&json)?;
+    let mut settings = Settings::from_json(&json)?;
+    settings.shortcuts = normalize_shortcuts(settings.shortcuts);
+    crate::ai::migrate_legacy_gemini_key(&mut settings);
     settings.save().map_err(|e| e.to_string())
 }
 
 /// Return a subset of settings keys to reduce round-trips from the frontend.
 #[tauri::command]
 pub fn get_settings_subset(keys: Vec<String>) -> Result<serde_json::Value, String> {
-    let settings = Settings::load();
+    let settings = frontend_settings();
     let full = serde_json::to_value(settings).map_err(|e| e.to_string())?;
     let mut result = serde_json::Map::new();
     for k in keys {
@@ -861,11 +1227,65 @@ pub fn get_settings_subset(keys: Vec<String>) -> Result<serde_json::Value, Strin
     Ok(serde_json::Value::Object(result))
 }
 
+/// Settings never included in a `settings-changed` event, not even by name.
+const SECRET_SETTINGS: &[&str] = &["gemini_api_key", "cerebras_api_key"];
+
+/// Settings whose new value is sent along in `settings-changed`, so listeners can apply
+/// them without reloading everything.
+const EVENT_VALUE_SETTINGS: &[&str] = &[
+    "theme",
+    "current_theme",
+    "accent_color",
+    "color_scheme_override",
+    "language",
+    "zoom_level",
+    "auto_collapse_sidebar",
+    "auto_expand_sidebar_hover",
+    "menu_order",
+    "disabled_sidebar_pages",
+    "sidebar_favorites",
+];
+
+/// Payload of the `settings-changed` event.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SettingsChanged {
+    /// Top-level keys whose value differs from before the save, sorted
+    pub keys: Vec<String>,
+    /// New values of the changed keys listed in `EVENT_VALUE_SETTINGS`
+    pub values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Keys that differ between two serialized `Settings`, minus `SECRET_SETTINGS`.
+fn settings_diff(old: &serde_json::Value, new: &serde_json::Value) -> SettingsChanged {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|k| !SECRET_SETTINGS.contains(&k.as_str()))
+        .filter(|k| old.get(*k) != new.get(*k))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    let values = keys
+        .iter()
+        .filter(|k| EVENT_VALUE_SETTINGS.contains(&k.as_str()))
+        .filter_map(|k| Some((k.clone(), new.get(k)?.clone())))
+        .collect();
+    SettingsChanged { keys, values }
+}
+
 /// Merge partial settings into current settings and save (coalesces get+save into one call).
+/// Emits `settings-changed` to every window with the keys that actually changed.
 #[tauri::command]
-pub fn save_settings_merge(patch: serde_json::Value) -> Result<(), String> {
+pub fn save_settings_merge(app: tauri::AppHandle, patch: serde_json::Value) -> Result<(), String> {
+    use tauri::Emitter;
+
     let current = Settings::load();
     let mut current_val = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    let previous_val = current_val.clone();
 
     // Shallow merge top-level keys from patch into current
     if let (Some(obj_curr), Some(obj_patch)) = (current_val.as_object_mut(), patch.as_object()) {
@@ -878,8 +1298,18 @@ pub fn save_settings_merge(patch: serde_json::Value) -> Result<(), String> {
         }
     }
 
-    let merged: Settings = serde_json::from_value(current_val).map_err(|e| e.to_string())?;
-    merged.save().map_err(|e| e.to_string())
+    let mut merged: Settings = serde_json::from_value(current_val).map_err(|e| e.to_string())?;
+    merged.shortcuts = normalize_shortcuts(merged.shortcuts);
+    // A Gemini key in the patch goes to the keychain rather than the settings file
+    crate::ai::migrate_legacy_gemini_key(&mut merged);
+    merged.save().map_err(|e| e.to_string())?;
+
+    let merged_val = serde_json::to_value(&merged).map_err(|e| e.to_string())?;
+    let changed = settings_diff(&previous_val, &merged_val);
+    if !changed.keys.is_empty() {
+        let _ = app.emit("settings-changed", changed);
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -928,6 +1358,7 @@ pub async fn save_cloud_token(
         })?
     };
     let mut cloud_token = CloudToken::load();
+    let same_account = cloud_token.user.as_ref().map(|u| u.id.as_str()) == Some(user.id.as_str());
     cloud_token.token = Some(token);
     cloud_token.refresh_token = refresh_token;
     cloud_token.user = Some(user.clone());
@@ -936,6 +1367,9 @@ pub async fn save_cloud_token(
     // Mark that user has signed into cloud (for sign-out detection)
     let mut state = CloudState::load();
     state.previously_signed_into_cloud = true;
+    if !same_account {
+        state.forget_sync();
+    }
     let _ = state.save();
     Ok(user)
 }
@@ -956,6 +1390,7 @@ pub fn clear_cloud_token() -> Result<(), String> {
     // Clear previously-signed flag on explicit logout
     let mut state = CloudState::load();
     state.previously_signed_into_cloud = false;
+    state.forget_sync();
     state.save().map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -1093,24 +1528,144 @@ pub fn set_cloud_base_url(new_base_url: String) -> Result<(), String> {
     tok.save().map_err(|e| e.to_string())
 }
 
+/// Turn a failed cloud API response into an error message, preferring the API's own
+/// `statusCode`/`statusMessage` body when it sent one.
+fn cloud_api_error(context: &str, status: reqwest::StatusCode, error_text: &str) -> String {
+    if let Ok(api_error) = serde_json::from_str::<APIError>(error_text) {
+        return format!(
+            "API Error {}: {}",
+            api_error.statusCode, api_error.statusMessage
+        );
+    }
+    format!("{} failed: {} - {}", context, status, error_text)
+}
+
+/// Find the settings file in the user's cloud storage.
+async fn find_cloud_settings_file(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+) -> Result<CloudFile, String> {
+    let response = client
+        .get(&format!("{}/files/list", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .query(&[("search", "desqta-settings.json"), ("limit", "10")])
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(cloud_api_error("List files", status, &error_text));
+    }
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let file_list: FileListResponse = serde_json::from_str(&response_text).map_err(|e| {
+        format!(
+            "Failed to parse response: {} - Raw response: {}",
+            e, response_text
+        )
+    })?;
+    file_list
+        .files
+        .into_iter()
+        .find(|file| file.filename == "desqta-settings.json")
+        .ok_or_else(|| "No settings file found in cloud".to_string())
+}
+
+/// SHA-256 of the settings' JSON. Objects are hashed with sorted keys, so equal settings
+/// always hash the same.
+fn settings_hash(settings: &Settings) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    let canonical = serde_json::to_value(settings)
+        .map_err(|e| e.to_string())?
+        .to_string();
+    Ok(hex::encode(Sha256::digest(canonical.as_bytes())))
+}
+
+/// Whether the cloud already has settings hashing to `hash`, uploaded with `public`.
+fn upload_unchanged(state: &CloudState, hash: &str, public: bool) -> bool {
+    state.last_upload_hash.as_deref() == Some(hash) && state.last_upload_public == public
+}
+
+fn record_upload(state: &mut CloudState, hash: String, public: bool, at: String) {
+    state.last_upload_hash = Some(hash);
+    state.last_upload_public = public;
+    state.last_upload_at = Some(at);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudUploadStatus {
+    Uploaded,
+    /// Skipped, the cloud copy already matches
+    Unchanged,
+}
+
+/// Cloud sync bookkeeping, for showing when settings were last synced and whether local
+/// changes are waiting to be uploaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudSyncState {
+    pub current_hash: String,
+    pub last_upload_hash: Option<String>,
+    pub last_upload_at: Option<String>,
+    pub last_download_hash: Option<String>,
+    pub last_download_at: Option<String>,
+    /// The local settings differ from the last upload
+    pub pending_upload: bool,
+}
+
+#[tauri::command]
+pub fn get_cloud_sync_state() -> Result<CloudSyncState, String> {
+    let state = CloudState::load();
+    let current_hash = settings_hash(&Settings::load())?;
+    Ok(CloudSyncState {
+        pending_upload: state.last_upload_hash.as_deref() != Some(current_hash.as_str()),
+        current_hash,
+        last_upload_hash: state.last_upload_hash,
+        last_upload_at: state.last_upload_at,
+        last_download_hash: state.last_download_hash,
+        last_download_at: state.last_download_at,
+    })
+}
+
+/// Upload the current settings file. It's private unless `public` is true, so shortcut and
+/// feed lists aren't world-readable by default. Nothing is sent when the settings and
+/// visibility match the last upload, unless `force` is set.
 #[tauri::command]
-pub async fn upload_settings_to_cloud() -> Result<(), String> {
+pub async fn upload_settings_to_cloud(
+    public: Option<bool>,
+    force: Option<bool>,
+) -> Result<CloudUploadStatus, String> {
     let cloud_token = CloudToken::load();
     let token = cloud_token
         .token
         .clone()
         .ok_or("No cloud token found. Please authenticate first.")?;
-    let base_url = get_base_api_url();
+    let public = public.unwrap_or(false);
     let settings = Settings::load();
+    let hash = settings_hash(&settings)?;
+    let mut state = CloudState::load();
+    if !force.unwrap_or(false) && upload_unchanged(&state, &hash, public) {
+        return Ok(CloudUploadStatus::Unchanged);
+    }
+    let base_url = get_base_api_url();
     let settings_json = settings.to_json()?;
     let client = reqwest::Client::new();
-    let form = reqwest::multipart::Form::new().part(
-        "file",
-        reqwest::multipart::Part::text(settings_json)
-            .file_name("desqta-settings.json")
-            .mime_str("application/json")
-            .unwrap(),
-    );
+    let form = reqwest::multipart::Form::new()
+        .part(
+            "file",
+            reqwest::multipart::Part::text(settings_json)
+                .file_name("desqta-settings.json")
+                .mime_str("application/json")
+                .unwrap(),
+        )
+        .text("isPublic", public.to_string());
     let response = client
         .post(&format!("{}/files/upload", base_url))
         .header("Authorization", format!("Bearer {}", token))
@@ -1126,7 +1681,9 @@ pub async fn upload_settings_to_cloud() -> Result<(), String> {
             .unwrap_or_else(|_| "Unknown error".to_string());
         return Err(format!("Upload failed: {} - {}", status, error_text));
     }
-    Ok(())
+    record_upload(&mut state, hash, public, chrono::Utc::now().to_rfc3339());
+    state.save().map_err(|e| e.to_string())?;
+    Ok(CloudUploadStatus::Uploaded)
 }
 
 #[tauri::command]
@@ -1138,42 +1695,7 @@ pub async fn download_settings_from_cloud() -> Result<Settings, String> {
         .ok_or("No cloud token found. Please authenticate first.")?;
     let base_url = get_base_api_url();
     let client = reqwest::Client::new();
-    let response = client
-        .get(&format!("{}/files/list", base_url))
-        .header("Authorization", format!("Bearer {}", token))
-        .query(&[("search", "desqta-settings.json"), ("limit", "10")])
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-    let status = response.status();
-    if !status.is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        if let Ok(api_error) = serde_json::from_str::<APIError>(&error_text) {
-            return Err(format!(
-                "API Error {}: {}",
-                api_error.statusCode, api_error.statusMessage
-            ));
-        }
-        return Err(format!("List files failed: {} - {}", status, error_text));
-    }
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-    let file_list: FileListResponse = serde_json::from_str(&response_text).map_err(|e| {
-        format!(
-            "Failed to parse response: {} - Raw response: {}",
-            e, response_text
-        )
-    })?;
-    let settings_file = file_list
-        .files
-        .iter()
-        .find(|file| file.filename == "desqta-settings.json")
-        .ok_or("No settings file found in cloud")?;
+    let settings_file = find_cloud_settings_file(&client, &base_url, &token).await?;
     let download_url = if settings_file.is_public {
         format!("{}/files/public/{}", base_url, settings_file.stored_name)
     } else {
@@ -1211,7 +1733,54 @@ pub async fn download_settings_from_cloud() -> Result<Settings, String> {
         .text()
         .await
         .map_err(|e| format!("Failed to read response: {}", e))?;
-    Settings::from_json(&settings_text)
+    let settings = Settings::from_json(&settings_text)?;
+
+    let mut state = CloudState::load();
+    state.last_download_hash = Some(settings_hash(&settings)?);
+    state.last_download_at = Some(chrono::Utc::now().to_rfc3339());
+    state.save().map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Make the settings file already in the cloud public or private.
+#[tauri::command]
+pub async fn set_cloud_settings_visibility(public: bool) -> Result<(), String> {
+    let cloud_token = CloudToken::load();
+    let token = cloud_token
+        .token
+        .clone()
+        .ok_or("No cloud token found. Please authenticate first.")?;
+    let base_url = get_base_api_url();
+    let client = reqwest::Client::new();
+    let settings_file = find_cloud_settings_file(&client, &base_url, &token).await?;
+    if settings_file.is_public == public {
+        return Ok(());
+    }
+    let response = client
+        .patch(&format!("{}/files/{}", base_url, settings_file.id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "isPublic": public }))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        let message = cloud_api_error("Visibility change", status, &error_text);
+        if status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+            || status == reqwest::StatusCode::NOT_IMPLEMENTED
+        {
+            return Err(format!(
+                "Cloud server doesn't support changing file visibility ({})",
+                message
+            ));
+        }
+        return Err(message);
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -1256,3 +1825,101 @@ pub async fn check_cloud_settings() -> Result<bool, String> {
     })?;
     Ok(!file_list.files.is_empty())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut(name: &str, url: &str) -> Shortcut {
+        Shortcut {
+            name: name.to_string(),
+            icon: "link".to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_identical_settings_upload_is_skipped() {
+        let mut settings = Settings::default();
+        let hash = settings_hash(&settings).unwrap();
+        assert_eq!(hash, settings_hash(&settings.clone()).unwrap());
+
+        let mut state = CloudState::default();
+        assert!(!upload_unchanged(&state, &hash, false));
+        let uploaded_at = "2024-05-01T00:00:00Z".to_string();
+        record_upload(&mut state, hash.clone(), false, uploaded_at);
+        // Second upload of the same settings: skipped
+        assert!(upload_unchanged(&state, &hash, false));
+        // Changing visibility still needs an upload
+        assert!(!upload_unchanged(&state, &hash, true));
+
+        settings.theme = "light".to_string();
+        let changed = settings_hash(&settings).unwrap();
+        assert_ne!(changed, hash);
+        assert!(!upload_unchanged(&state, &changed, false));
+
+        // Another account has none of it
+        state.forget_sync();
+        assert!(!upload_unchanged(&state, &hash, false));
+    }
+
+    #[test]
+    fn test_normalize_shortcuts() {
+        let normalized = normalize_shortcuts(vec![
+            shortcut("Example", "example.com"),
+            shortcut("Example again", "https://example.com/"),
+            shortcut("Local", "localhost:3000/app"),
+            shortcut("Docs", " http://docs.example.com/guide "),
+        ]);
+        let urls: Vec<&str> = normalized.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/",
+                "https://localhost:3000/app",
+                "http://docs.example.com/guide"
+            ]
+        );
+        assert_eq!(normalized[0].name, "Example");
+
+        // An invalid shortcut is dropped on its own instead of failing the rest
+        let kept = normalize_shortcuts(vec![
+            shortcut("Fine", "example.com"),
+            shortcut("Sneaky", "javascript:alert(1)"),
+            shortcut("File", "file:///etc/passwd"),
+            shortcut("Empty", "  "),
+            shortcut("Fine again", "https://example.com"),
+        ]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].name, "Fine");
+        assert_eq!(kept[0].url, "https://example.com/");
+        assert!(normalize_shortcut_url("javascript:alert(1)")
+            .unwrap_err()
+            .contains("javascript"));
+    }
+
+    #[test]
+    fn test_settings_diff_reports_only_changed_keys() {
+        let old = Settings::default();
+        let mut new = old.clone();
+        new.theme = "light".to_string();
+        new.weather_city = "Perth".to_string();
+        new.gemini_api_key = Some("secret-key".to_string());
+        // Set to the value it already had
+        new.language = old.language.clone();
+
+        let changed = settings_diff(
+            &serde_json::to_value(&old).unwrap(),
+            &serde_json::to_value(&new).unwrap(),
+        );
+        assert_eq!(changed.keys, vec!["theme", "weather_city"]);
+        assert_eq!(changed.values.len(), 1);
+        assert_eq!(changed.values["theme"], "light");
+        let payload = serde_json::to_string(&changed).unwrap();
+        assert!(!payload.contains("gemini_api_key"));
+        assert!(!payload.contains("secret-key"));
+
+        let unchanged = serde_json::to_value(&old).unwrap();
+        assert!(settings_diff(&unchanged, &unchanged).keys.is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Mutex, OnceLock};
+
+use crate::caches::CacheCounters;
+use crate::logger;
+use crate::netgrab::{self, RequestMethod};
+
+/// The signed-in student as SEQTA describes them. Schools choose what their instance exposes,
+/// so everything but the id and name may be missing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudentInfo {
+    /// SEQTA's student id, the `student` field in most student endpoints
+    pub id: i64,
+    pub name: String,
+    pub user_name: Option<String>,
+    pub email: Option<String>,
+    pub school: Option<String>,
+    pub year: Option<String>,
+    pub house: Option<String>,
+    pub house_colour: Option<String>,
+}
+
+impl StudentInfo {
+    /// Copy with the identifying fields replaced, for `dev_sensitive_info_hider`.
+    fn redacted(&self) -> Self {
+        Self {
+            id: self.id,
+            name: "Demo Student".to_string(),
+            user_name: self.user_name.as_ref().map(|_| "student".to_string()),
+            email: self
+                .email
+                .as_ref()
+                .map(|_| "student@example.com".to_string()),
+            school: self.school.as_ref().map(|_| "Demo School".to_string()),
+            year: self.year.clone(),
+            house: self.house.clone(),
+            house_colour: self.house_colour.clone(),
+        }
+    }
+}
+
+/// Student for the current session. Lives until logout, a profile switch or the next login.
+static STUDENT_INFO_CACHE: OnceLock<Mutex<Option<StudentInfo>>> = OnceLock::new();
+pub static STUDENT_INFO_CACHE_STATS: CacheCounters = CacheCounters::new();
+
+fn student_info_cache() -> &'static Mutex<Option<StudentInfo>> {
+    STUDENT_INFO_CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// Forget the cached student so the next lookup asks SEQTA again. Returns entries removed.
+pub fn clear_cache() -> usize {
+    student_info_cache()
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.take())
+        .map_or(0, |_| 1)
+}
+
+/// The cached student, if this session has looked them up. Never contacts SEQTA.
+pub fn cached_student_info() -> Option<StudentInfo> {
+    student_info_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.clone())
+}
+
+/// First of `keys` present in `payload` as non-blank text or a number.
+fn field_text(payload: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match payload.get(*key)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// Read the student from the `/seqta/student/login` payload. Field names differ between
+/// SEQTA versions, so each one is looked up under the spellings seen in the wild.
+fn parse_student_info(payload: &Value) -> Result<StudentInfo, String> {
+    let id = payload
+        .get("id")
+        .and_then(|id| id.as_i64().or_else(|| id.as_str()?.parse().ok()))
+        .ok_or_else(|| "SEQTA didn't return a student id".to_string())?;
+    let user_name = field_text(payload, &["userName"]);
+    let name = field_text(payload, &["displayName", "userDesc"])
+        .or_else(|| user_name.clone())
+        .unwrap_or_else(|| format!("Student {}", id));
+
+    Ok(StudentInfo {
+        id,
+        name,
+        user_name,
+        email: field_text(payload, &["email"]),
+        school: field_text(payload, &["school", "schoolName", "school_name"]),
+        year: field_text(payload, &["year", "yearLevel", "year_level"]),
+        house: field_text(payload, &["house", "houseName", "house_name"]),
+        house_colour: field_text(payload, &["houseColour", "house_colour", "houseColor"]),
+    })
+}
+
+/// The current student, from the cache or fetched with the saved session.
+pub async fn load_student_info() -> Result<StudentInfo, String> {
+    if let Some(info) = cached_student_info() {
+        STUDENT_INFO_CACHE_STATS.hit();
+        return Ok(info);
+    }
+    STUDENT_INFO_CACHE_STATS.miss();
+
+    let response =
+        netgrab::request_seqta_json("/seqta/student/login", RequestMethod::POST, Some(json!({})))
+            .await?;
+    let info = parse_student_info(response.get("payload").unwrap_or(&Value::Null))?;
+
+    if let Some(logger) = logger::get_logger() {
+        let _ = logger.log(
+            logger::LogLevel::DEBUG,
+            "student_info",
+            "load_student_info",
+            "Loaded student profile",
+            json!({
+                "has_school": info.school.is_some(),
+                "has_year": info.year.is_some(),
+                "has_house": info.house.is_some()
+            }),
+        );
+    }
+
+    if let Ok(mut cache) = student_info_cache().lock() {
+        *cache = Some(info.clone());
+    }
+    Ok(info)
+}
+
+/// SEQTA id of the signed-in student, for endpoints that take a `student` field.
+pub async fn current_student_id() -> Result<i64, String> {
+    load_student_info().await.map(|info| info.id)
+}
+
+/// Name, school, year level and house of the signed-in student. Fetched once per session.
+/// With `dev_sensitive_info_hider` on, the name, username, email and school are replaced.
+#[tauri::command]
+pub async fn get_current_student_info() -> Result<StudentInfo, String> {
+    let info = load_student_info().await?;
+    if crate::settings::Settings::load().dev_sensitive_info_hider {
+        return Ok(info.redacted());
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_student_info() {
+        let info = parse_student_info(&json!({
+            "id": 1234,
+            "userName": "jsmith",
+            "userDesc": "Jane Smith",
+            "email": "jsmith@school.edu.au",
+            "yearLevel": 10,
+            "house": "Banksia",
+            "houseColour": "#2e7d32"
+        }))
+        .unwrap();
+        assert_eq!(info.id, 1234);
+        assert_eq!(info.name, "Jane Smith");
+        assert_eq!(info.year.as_deref(), Some("10"));
+        assert_eq!(info.house.as_deref(), Some("Banksia"));
+        assert_eq!(info.school, None);
+
+        let redacted = info.redacted();
+        assert_eq!(redacted.name, "Demo Student");
+        assert_eq!(redacted.email.as_deref(), Some("student@example.com"));
+        assert_eq!(redacted.school, None);
+        assert_eq!(redacted.house, info.house);
+    }
+
+    #[test]
+    fn test_parse_student_info_minimal() {
+        let info =
+            parse_student_info(&json!({ "id": "77", "userName": "s77", "house": "" })).unwrap();
+        assert_eq!(info.id, 77);
+        assert_eq!(info.name, "s77");
+        assert_eq!(info.house, None);
+
+        assert!(parse_student_info(&json!({ "userName": "nobody" })).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! One color per subject, shared by the timetable, assessments and notes. A subject gets the
+//! color the student picked in DesQTA, else the one set in SEQTA, else one picked from a fixed
+//! palette by hashing its code, so it never changes between sessions.
+
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::assessments;
+use crate::courses;
+use crate::database;
+use crate::profiles;
+
+const SEQTA_COLORS_CACHE_KEY: &str = "subject_colors_seqta";
+const SEQTA_COLORS_CACHE_TTL_MINUTES: i64 = 24 * 60;
+
+/// Colors handed out to subjects without one of their own
+const PALETTE: [&str; 12] = [
+    "#ef4444", "#f97316", "#f59e0b", "#84cc16", "#22c55e", "#14b8a6", "#06b6d4", "#3b82f6",
+    "#6366f1", "#8b5cf6", "#d946ef", "#ec4899",
+];
+
+/// Location: `$DATA_DIR/DesQTA/profiles/{profile_id}/subject_colors.json`
+fn overrides_file() -> PathBuf {
+    let mut dir = profiles::get_profile_dir(
+        &profiles::ProfileManager::get_current_profile()
+            .map(|p| p.id)
+            .unwrap_or_else(|| "default".to_string()),
+    );
+    dir.push("subject_colors.json");
+    dir
+}
+
+/// Colors set with `set_subject_color`, by subject code.
+fn load_overrides() -> HashMap<String, String> {
+    fs::read_to_string(overrides_file())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_overrides(overrides: &HashMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(overrides).map_err(|e| e.to_string())?;
+    fs::write(overrides_file(), json).map_err(|e| format!("Failed to save subject colors: {}", e))
+}
+
+/// `color` as lowercase `#rrggbb`. Accepts `#rgb` and `#rrggbb`, with or without the `#`.
+fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => Some(format!("#{}", hex.to_ascii_lowercase())),
+        3 => Some(
+            hex.chars()
+                .flat_map(|c| [c, c])
+                .fold("#".to_string(), |mut out, c| {
+                    out.push(c.to_ascii_lowercase());
+                    out
+                }),
+        ),
+        _ => None,
+    }
+}
+
+/// The palette color for `code`. Uses SHA-256 rather than `Hash` so it's the same in every
+/// build and on every platform.
+fn hashed_color(code: &str) -> &'static str {
+    let digest = Sha256::digest(code.trim().as_bytes());
+    let n = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    PALETTE[n as usize % PALETTE.len()]
+}
+
+/// A color for each of `codes` and every subject with a SEQTA color or an override: the
+/// override first, then SEQTA's color when it's a valid one, then the hashed palette color.
+fn assign_colors<'a>(
+    codes: impl IntoIterator<Item = &'a str>,
+    seqta: &HashMap<String, String>,
+    overrides: &HashMap<String, String>,
+) -> BTreeMap<String, String> {
+    codes
+        .into_iter()
+        .chain(seqta.keys().map(String::as_str))
+        .chain(overrides.keys().map(String::as_str))
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            let color = overrides
+                .get(code)
+                .or_else(|| seqta.get(code))
+                .and_then(|color| normalize_color(color))
+                .unwrap_or_else(|| hashed_color(code).to_string());
+            (code.to_string(), color)
+        })
+        .collect()
+}
+
+/// The same colors `get_subject_colors` returns, for each of `codes` and every subject with a
+/// SEQTA color or an override.
+pub(crate) async fn colors_for(codes: Vec<&str>) -> BTreeMap<String, String> {
+    assign_colors(codes, &seqta_colors().await, &load_overrides())
+}
+
+/// SEQTA's subject colors, cached for a day. Empty if they can't be fetched.
+async fn seqta_colors() -> HashMap<String, String> {
+    let cached = database::db_cache_get(SEQTA_COLORS_CACHE_KEY.to_string())
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_value::<HashMap<String, String>>(value).ok());
+    if let Some(colors) = cached {
+        return colors;
+    }
+
+    match assessments::fetch_lesson_colours().await {
+        Ok(prefs) => {
+            let colors = assessments::colours_by_code(&prefs);
+            if let Ok(value) = serde_json::to_value(&colors) {
+                let _ = database::db_cache_set(
+                    SEQTA_COLORS_CACHE_KEY.to_string(),
+                    value,
+                    Some(SEQTA_COLORS_CACHE_TTL_MINUTES),
+                );
+            }
+            colors
+        }
+        Err(e) => {
+            println!("[SubjectColors] Failed to load SEQTA colors: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Color of every subject the student has taken, by subject code (`#rrggbb`).
+#[tauri::command]
+pub async fn get_subject_colors() -> Result<BTreeMap<String, String>, String> {
+    let (folders, seqta) = tokio::join!(courses::get_courses_subjects(), seqta_colors());
+    // Offline, subjects with a SEQTA color or an override still get theirs
+    let codes: Vec<String> = folders
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|folder| folder.subjects)
+        .map(|subject| subject.code)
+        .collect();
+    Ok(assign_colors(
+        codes.iter().map(String::as_str),
+        &seqta,
+        &load_overrides(),
+    ))
+}
+
+/// Set the color for subject `code` (`#rgb` or `#rrggbb`), or go back to the default with
+/// `None`. Returns the color the subject now has.
+#[tauri::command]
+pub async fn set_subject_color(code: String, color: Option<String>) -> Result<String, String> {
+    let code = code.trim().to_string();
+    if code.is_empty() {
+        return Err("Subject code cannot be empty".to_string());
+    }
+    let mut overrides = load_overrides();
+    match color {
+        Some(color) => {
+            let normalized = normalize_color(&color)
+                .ok_or_else(|| format!("Invalid color \"{}\", expected #rrggbb", color))?;
+            overrides.insert(code.clone(), normalized);
+        }
+        None => {
+            overrides.remove(&code);
+        }
+    }
+    save_overrides(&overrides)?;
+
+    let seqta = seqta_colors().await;
+    Ok(assign_colors([code.as_str()], &seqta, &overrides)
+        .remove(&code)
+        .unwrap_or_else(|| hashed_color(&code).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_colors_are_stable() {
+        let codes = ["10MAT", "10ENG", "10SCI", "10HIS"];
+        let first = assign_colors(codes, &HashMap::new(), &HashMap::new());
+        let second = assign_colors(
+            codes.iter().rev().copied(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        assert_eq!(first, second);
+        assert_eq!(first["10MAT"], hashed_color("10MAT"));
+        assert!(first.values().all(|c| PALETTE.contains(&c.as_str())));
+
+        // SEQTA's color beats the hash, the student's own beats both
+        let seqta = HashMap::from([
+            ("10MAT".to_string(), "#FF0000".to_string()),
+            ("10ENG".to_string(), "not a color".to_string()),
+            ("11BIO".to_string(), "0f0".to_string()),
+        ]);
+        let overrides = HashMap::from([("10SCI".to_string(), "#123abc".to_string())]);
+        let colors = assign_colors(codes, &seqta, &overrides);
+        assert_eq!(colors["10MAT"], "#ff0000");
+        assert_eq!(colors["10ENG"], first["10ENG"]);
+        assert_eq!(colors["10SCI"], "#123abc");
+        assert_eq!(colors["11BIO"], "#00ff00");
+        assert_eq!(colors.len(), 5);
+
+        assert_eq!(normalize_color(" #AbC "), Some("#aabbcc".to_string()));
+        assert_eq!(normalize_color("zzzzzz"), None);
+        assert_eq!(normalize_color("#12345"), None);
+    }
+}
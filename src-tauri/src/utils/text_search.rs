@@ -0,0 +1,229 @@
+//! Plain-text helpers shared by the notes and message searches.
+
+use serde::{Deserialize, Serialize};
+
+/// Where a search term was found in a result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub field: String, // e.g. "title", "content", "tags", "subject", "sender"
+    pub snippet: String,
+    pub position: usize,
+}
+
+/// Largest char boundary at or before `index`.
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Smallest char boundary at or after `index`.
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// `text[start..end]` widened to char boundaries, so positions found in a lowercased copy
+/// can't split a multi-byte character.
+fn char_safe_range(text: &str, start: usize, end: usize) -> (usize, usize) {
+    (
+        floor_char_boundary(text, start),
+        ceil_char_boundary(text, end),
+    )
+}
+
+/// Short context (20 bytes either side) around a match in a title-like field.
+pub fn highlight_match(text: &str, term: &str, position: usize) -> String {
+    let (start, end) = char_safe_range(
+        text,
+        position.saturating_sub(20),
+        position + term.len() + 20,
+    );
+    let snippet = &text[start..end];
+
+    if start > 0 {
+        format!("...{}", snippet)
+    } else if end < text.len() {
+        format!("{}...", snippet)
+    } else {
+        snippet.to_string()
+    }
+}
+
+/// Context (50 bytes either side) around a match in body text, with ellipses where cut.
+pub fn create_snippet(text: &str, term: &str, position: usize) -> String {
+    let (start, end) = char_safe_range(
+        text,
+        position.saturating_sub(50),
+        position + term.len() + 50,
+    );
+    let snippet = &text[start..end];
+
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < text.len() { "..." } else { "" };
+
+    format!("{}{}{}", prefix, snippet, suffix)
+}
+
+pub fn strip_html_tags(html: &str) -> String {
+    // Simple HTML tag removal
+    let mut result = String::new();
+    let mut in_tag = false;
+
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => result.push(ch),
+            _ => {} // Skip characters inside tags
+        }
+    }
+
+    result
+}
+
+/// Decode the HTML entities an editor produces: the common named ones and numeric references.
+/// Unknown entities are left as written.
+pub fn decode_html_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..=end];
+                let ch = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                        .and_then(char::from_u32),
+                };
+                ch.map(|ch| (ch, end + 2))
+            });
+        match decoded {
+            Some((ch, len)) => {
+                result.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Elements that separate words even when the HTML has no whitespace between them
+/// (`<p>one</p><p>two</p>`).
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "li",
+    "ul",
+    "ol",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "tr",
+    "td",
+    "th",
+    "hr",
+];
+
+/// Readable plain text of an HTML fragment: tags removed with a space wherever a block element
+/// starts or ends, entities decoded and runs of whitespace collapsed to a single space.
+pub fn html_to_text(html: &str) -> String {
+    let mut result = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let name: String = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            result.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    decode_html_entities(&result)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippets_respect_char_boundaries() {
+        let text = "Ünïcödé ".repeat(10) + "needle" + &" ëñd".repeat(20);
+        let position = text.find("needle").unwrap();
+        // Offsets that land inside multi-byte characters must not panic
+        for offset in 0..4 {
+            let snippet = create_snippet(&text, "needle", position + offset);
+            assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+            highlight_match(&text, "needle", position + offset);
+        }
+        assert_eq!(highlight_match("short title", "title", 6), "short title");
+    }
+
+    #[test]
+    fn test_snippets_around_emoji() {
+        let text = format!("{}🎉café🎉{}", "🎉".repeat(30), "é".repeat(40));
+        let position = text.find("café").unwrap();
+        // Every byte offset near the match, including ones inside the emoji, must be safe
+        for position in position.saturating_sub(6)..position + 8 {
+            let snippet = create_snippet(&text, "café", position);
+            assert!(snippet.contains("café"));
+            assert!(highlight_match(&text, "café", position).contains("café"));
+        }
+        assert_eq!(create_snippet("🎉café", "café", 4), "🎉café");
+        assert_eq!(highlight_match("é🎉", "🎉", 2), "é🎉");
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text("<p>Hello&nbsp;there</p><p>Fish &amp; chips</p>\n"),
+            "Hello there Fish & chips"
+        );
+        assert_eq!(
+            html_to_text("<ul><li>one</li><li>two</li></ul><b>bo</b>ld &#x1F389;"),
+            "one two bold 🎉"
+        );
+        assert_eq!(html_to_text("  <br/>  "), "");
+    }
+}
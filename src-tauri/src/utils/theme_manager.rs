@@ -6,6 +6,148 @@ use tauri::{AppHandle, Manager};
 use zip::ZipArchive;
 use sha2::{Sha256, Digest};
 use hex;
+use std::collections::HashSet;
+
+use crate::logger;
+
+/// Variables referenced by the generated stylesheets, with the fallback injected into `:root`
+/// when a theme doesn't define them. An empty fallback means it's derived in
+/// `variable_fallback`.
+const CONSUMED_VARIABLES: &[(&str, &str)] = &[
+    ("--accent-color", ""),
+    ("--accent-hover", "color-mix(in srgb, var(--accent-color) 85%, black)"),
+    ("--surface-color", "rgba(128, 128, 128, 0.08)"),
+    ("--border-color", "rgba(128, 128, 128, 0.25)"),
+    ("--animation-duration", "200ms"),
+    ("--animation-easing", "ease-in-out"),
+    ("--animation-scale", "1.02"),
+];
+
+fn variable_fallback(name: &str, theme_data: &ThemeManifest) -> String {
+    match name {
+        // Most themes only set `primaryColor`, which is meant as the accent
+        "--accent-color" => ["--primary-color", "primaryColor"]
+            .iter()
+            .filter_map(|key| theme_data.custom_properties.get(*key))
+            .chain([&theme_data.settings.default_accent_color])
+            .map(|value| value.trim())
+            .find(|value| !value.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(default_accent_color_value),
+        _ => CONSUMED_VARIABLES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, fallback)| fallback.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// `var(--name, fallback)`, so the rule still resolves if the variable is missing at runtime.
+fn css_var(name: &str, theme_data: &ThemeManifest) -> String {
+    format!("var({}, {})", name, variable_fallback(name, theme_data))
+}
+
+/// Consumed variables the theme doesn't define in its custom properties or either color
+/// scheme (scheme keys are written without the leading `--`).
+fn missing_consumed_variables(theme_data: &ThemeManifest) -> Vec<&'static str> {
+    CONSUMED_VARIABLES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| {
+            let bare = name.trim_start_matches("--");
+            !theme_data.custom_properties.contains_key(*name)
+                && !theme_data.color_schemes.light.contains_key(bare)
+                && !theme_data.color_schemes.dark.contains_key(bare)
+        })
+        .collect()
+}
+
+/// How generated stylesheets are written: readable for editing, or minified for packaged themes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CssOutputStyle {
+    #[default]
+    Pretty,
+    Minified,
+}
+
+/// Properties sorted by name so generated CSS is identical between runs.
+fn sorted_properties(
+    properties: &std::collections::HashMap<String, String>,
+) -> Vec<(&String, &String)> {
+    let mut sorted: Vec<_> = properties.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+/// Whether whitespace after the end of `css` so far can be dropped.
+fn ends_with_punctuation(css: &str) -> bool {
+    matches!(
+        css.chars().last(),
+        None | Some('{' | '}' | ';' | ':' | ',' | '>')
+    )
+}
+
+/// Strip comments and redundant whitespace from generated CSS. Quoted strings (font names,
+/// `content` values) are copied as-is.
+fn minify_css(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+
+    while let Some(ch) = chars.next() {
+        if let Some(q) = quote {
+            out.push(ch);
+            if ch == '\\' {
+                if let Some(escaped) = chars.next() {
+                    out.push(escaped);
+                }
+            } else if ch == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match ch {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                pending_space = true;
+            }
+            c if c.is_whitespace() => pending_space = true,
+            '{' | '}' | ';' | ':' | ',' | '>' => {
+                // `.card :hover` (any hovered descendant) isn't `.card:hover`
+                if ch == ':' && pending_space && !ends_with_punctuation(&out) {
+                    out.push(' ');
+                }
+                // The last declaration in a block doesn't need its semicolon
+                if ch == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(ch);
+                pending_space = false;
+            }
+            _ => {
+                if pending_space && !ends_with_punctuation(&out) {
+                    out.push(' ');
+                }
+                pending_space = false;
+                if ch == '"' || ch == '\'' {
+                    quote = Some(ch);
+                }
+                out.push(ch);
+            }
+        }
+    }
+    out
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -183,6 +325,251 @@ pub struct ThemeResponsive {
     pub adaptive_spacing: bool,
 }
 
+/// Key colors of one color scheme, resolved to concrete values where the theme allows it.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemePreviewColors {
+    pub accent: String,
+    pub accent_hover: String,
+    pub background: String,
+    pub surface: String,
+    pub border: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemePreviewScheme {
+    /// Effective CSS variables: global custom properties overlaid with the scheme's own
+    pub variables: std::collections::BTreeMap<String, String>,
+    pub colors: ThemePreviewColors,
+}
+
+/// Everything the theme grid needs to draw a swatch without loading the theme's stylesheets.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemePreviewData {
+    pub name: String,
+    pub display_name: String,
+    pub default_mode: String,
+    pub font_stack: String,
+    pub monospace_font_stack: String,
+    pub thumbnail: Option<String>,
+    pub light: ThemePreviewScheme,
+    pub dark: ThemePreviewScheme,
+}
+
+/// `--kebab-case` form of a manifest key. Custom themes write `--accent-color`, color schemes
+/// `accent-color`, and the bundled themes `accentColor`.
+fn normalize_variable_name(key: &str) -> String {
+    if key.starts_with("--") {
+        return key.to_string();
+    }
+    let mut name = String::from("--");
+    for ch in key.chars() {
+        if ch.is_ascii_uppercase() {
+            name.push('-');
+            name.push(ch.to_ascii_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// Follow `var(--name)` / `var(--name, fallback)` when it makes up the whole value, so the
+/// preview gets a color it can paint. Anything more complex is returned unchanged.
+fn resolve_variable(value: &str, variables: &std::collections::BTreeMap<String, String>) -> String {
+    let mut current = value.trim().to_string();
+    // Bounded so self-referencing variables can't loop forever
+    for _ in 0..8 {
+        let Some(inner) = current
+            .strip_prefix("var(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        else {
+            break;
+        };
+        let (name, fallback) = match inner.split_once(',') {
+            Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+            None => (inner.trim(), None),
+        };
+        current = match (variables.get(name), fallback) {
+            (Some(next), _) => next.trim().to_string(),
+            (None, Some(fallback)) => fallback.to_string(),
+            (None, None) => break,
+        };
+    }
+    current
+}
+
+fn preview_scheme(
+    theme_data: &ThemeManifest,
+    scheme: &std::collections::HashMap<String, String>,
+    dark: bool,
+) -> ThemePreviewScheme {
+    let mut variables: std::collections::BTreeMap<String, String> = theme_data
+        .custom_properties
+        .iter()
+        .chain(scheme.iter())
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| (normalize_variable_name(key), value.trim().to_string()))
+        .collect();
+
+    // Same fallbacks the generated global.css injects, so the swatch matches the applied theme
+    for (name, _) in CONSUMED_VARIABLES {
+        if !variables.contains_key(*name) {
+            variables.insert(name.to_string(), variable_fallback(name, theme_data));
+        }
+    }
+
+    let lookup = |name: &str, default: &str| {
+        variables
+            .get(name)
+            .map(|value| resolve_variable(value, &variables))
+            .unwrap_or_else(|| default.to_string())
+    };
+    // Both always set by the fallbacks above
+    let accent = lookup("--accent-color", "");
+    let accent_hover = lookup("--accent-hover", "").replace("var(--accent-color)", &accent);
+    let (background, text) = if dark {
+        ("#111827", "#f9fafb")
+    } else {
+        ("#ffffff", "#111827")
+    };
+
+    ThemePreviewScheme {
+        colors: ThemePreviewColors {
+            accent,
+            accent_hover,
+            background: lookup("--background-color", background),
+            surface: lookup("--surface-color", ""),
+            border: lookup("--border-color", ""),
+            text: lookup("--text-color", text),
+        },
+        variables,
+    }
+}
+
+/// Font list for CSS `font-family`, skipping fonts the manifest leaves blank.
+fn font_stack(fonts: &[&str], fallback: &str) -> String {
+    let mut stack: Vec<&str> = fonts
+        .iter()
+        .map(|font| font.trim())
+        .filter(|font| !font.is_empty())
+        .collect();
+    stack.push(fallback);
+    stack.join(", ")
+}
+
+fn build_theme_preview(theme_data: &ThemeManifest) -> ThemePreviewData {
+    let thumbnail = theme_data.preview.thumbnail.trim();
+    ThemePreviewData {
+        name: theme_data.name.clone(),
+        display_name: if theme_data.display_name.is_empty() {
+            theme_data.name.clone()
+        } else {
+            theme_data.display_name.clone()
+        },
+        default_mode: theme_data.settings.default_theme.clone(),
+        font_stack: font_stack(
+            &[&theme_data.fonts.primary, &theme_data.fonts.secondary],
+            &default_display_font(),
+        ),
+        monospace_font_stack: font_stack(&[&theme_data.fonts.monospace], "monospace"),
+        thumbnail: (!thumbnail.is_empty()).then(|| thumbnail.to_string()),
+        light: preview_scheme(theme_data, &theme_data.color_schemes.light, false),
+        dark: preview_scheme(theme_data, &theme_data.color_schemes.dark, true),
+    }
+}
+
+/// Largest theme download accepted from a URL
+const MAX_THEME_DOWNLOAD_BYTES: u64 = 10 * 1024 * 1024;
+/// Largest total size a theme bundle may unpack to, so a zip bomb can't fill the disk
+const MAX_THEME_BUNDLE_UNPACKED_BYTES: u64 = 50 * 1024 * 1024;
+const THEME_DOWNLOAD_TIMEOUT_SECS: u64 = 30;
+
+/// Theme names become directory names, so they must stay a single path component.
+fn is_safe_theme_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', ':'])
+        && name == name.trim_end_matches(['.', ' '])
+}
+
+/// Parse a zip bundle into its manifest and the remaining files (paths relative to the theme
+/// directory). The manifest may sit at the root or inside a single top-level folder. Entries
+/// that would escape the theme directory reject the whole bundle.
+fn read_theme_bundle(bytes: &[u8]) -> Result<(ThemeManifest, Vec<(PathBuf, Vec<u8>)>)> {
+    use std::io::Read;
+
+    let mut archive = ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| anyhow!("Failed to open ZIP archive: {}", e))?;
+
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let mut unpacked: u64 = 0;
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| anyhow!("Failed to read file {} from ZIP: {}", i, e))?;
+        let Some(path) = file.enclosed_name().map(|p| p.to_path_buf()) else {
+            return Err(anyhow!(
+                "Theme bundle contains an unsafe path: {}",
+                file.name()
+            ));
+        };
+        if file.is_dir() {
+            continue;
+        }
+
+        unpacked += file.size();
+        if unpacked > MAX_THEME_BUNDLE_UNPACKED_BYTES {
+            return Err(anyhow!("Theme bundle is too large"));
+        }
+        let mut content = Vec::with_capacity(file.size() as usize);
+        // The declared size can lie, so cap the actual read too
+        (&mut file)
+            .take(MAX_THEME_BUNDLE_UNPACKED_BYTES + 1)
+            .read_to_end(&mut content)
+            .map_err(|e| anyhow!("Failed to read {:?} from ZIP: {}", path, e))?;
+        if content.len() as u64 > file.size() {
+            return Err(anyhow!(
+                "Theme bundle entry {:?} is larger than declared",
+                path
+            ));
+        }
+        files.push((path, content));
+    }
+
+    let manifest_index = files
+        .iter()
+        .position(|(path, _)| path.as_path() == std::path::Path::new("theme-manifest.json"))
+        .or_else(|| {
+            files.iter().position(|(path, _)| {
+                path.components().count() == 2 && path.ends_with("theme-manifest.json")
+            })
+        })
+        .ok_or_else(|| anyhow!("Theme bundle has no theme-manifest.json"))?;
+    let (manifest_path, manifest_content) = files.remove(manifest_index);
+    let theme_data: ThemeManifest = serde_json::from_slice(&manifest_content)
+        .map_err(|e| anyhow!("Failed to parse theme manifest: {}", e))?;
+
+    // Strip the top-level folder the manifest was found in; files outside it are dropped
+    let root = manifest_path
+        .parent()
+        .unwrap_or(std::path::Path::new(""))
+        .to_path_buf();
+    let assets = files
+        .into_iter()
+        .filter_map(|(path, content)| {
+            path.strip_prefix(&root)
+                .ok()
+                .map(|relative| (relative.to_path_buf(), content))
+        })
+        .collect();
+
+    Ok((theme_data, assets))
+}
+
 pub struct ThemeManager {
     app_handle: AppHandle,
 }
@@ -442,7 +829,7 @@ impl ThemeManager {
         println!("[ThemeManager] Wrote manifest: {:?}", manifest_path);
 
         // Generate CSS files based on theme data
-        self.generate_theme_css(&theme_dir, theme_data)?;
+        self.generate_theme_css(&theme_dir, theme_data, CssOutputStyle::Pretty)?;
 
         Ok(())
     }
@@ -459,41 +846,52 @@ impl ThemeManager {
         Ok(())
     }
 
-    fn generate_theme_css(&self, theme_dir: &PathBuf, theme_data: &ThemeManifest) -> Result<()> {
+    /// Regenerate a theme's stylesheets from its saved manifest, e.g. after editing the
+    /// manifest by hand.
+    pub fn regenerate_theme_css(&self, theme_name: &str, style: CssOutputStyle) -> Result<()> {
+        let theme_dir = self.get_themes_directory()?.join(theme_name);
+        let theme_data = self.load_theme_manifest(theme_name)?;
+        self.validate_theme(&theme_data)?;
+        self.generate_theme_css(&theme_dir, &theme_data, style)
+    }
+
+    fn generate_theme_css(
+        &self,
+        theme_dir: &PathBuf,
+        theme_data: &ThemeManifest,
+        style: CssOutputStyle,
+    ) -> Result<()> {
         let styles_dir = theme_dir.join("styles");
         fs::create_dir_all(&styles_dir)
             .map_err(|e| anyhow!("Failed to create styles directory: {}", e))?;
 
-        // Generate global.css
-        let global_css = self.generate_global_css(theme_data);
-        fs::write(styles_dir.join("global.css"), global_css)
-            .map_err(|e| anyhow!("Failed to write global.css: {}", e))?;
-
-        // Generate light.css
-        let light_css = self.generate_light_css(theme_data);
-        fs::write(styles_dir.join("light.css"), light_css)
-            .map_err(|e| anyhow!("Failed to write light.css: {}", e))?;
-
-        // Generate dark.css
-        let dark_css = self.generate_dark_css(theme_data);
-        fs::write(styles_dir.join("dark.css"), dark_css)
-            .map_err(|e| anyhow!("Failed to write dark.css: {}", e))?;
-
-        // Generate components.css
-        let components_css = self.generate_components_css(theme_data);
-        fs::write(styles_dir.join("components.css"), components_css)
-            .map_err(|e| anyhow!("Failed to write components.css: {}", e))?;
+        let files = [
+            ("global.css", Self::generate_global_css(theme_data)),
+            ("light.css", Self::generate_light_css(theme_data)),
+            ("dark.css", Self::generate_dark_css(theme_data)),
+            ("components.css", Self::generate_components_css(theme_data)),
+        ];
+        for (file_name, css) in files {
+            let css = match style {
+                CssOutputStyle::Pretty => css,
+                CssOutputStyle::Minified => minify_css(&css),
+            };
+            fs::write(styles_dir.join(file_name), css)
+                .map_err(|e| anyhow!("Failed to write {}: {}", file_name, e))?;
+        }
 
         Ok(())
     }
 
-    fn generate_global_css(&self, theme_data: &ThemeManifest) -> String {
+    fn generate_global_css(theme_data: &ThemeManifest) -> String {
         let mut css = String::new();
 
         // Add custom properties
         css.push_str(":root {\n");
-        for (key, value) in &theme_data.custom_properties {
+        let mut declared: HashSet<String> = HashSet::new();
+        for (key, value) in sorted_properties(&theme_data.custom_properties) {
             css.push_str(&format!("  {}: {};\n", key, value));
+            declared.insert(key.clone());
         }
 
         // Add font properties
@@ -516,20 +914,31 @@ impl ThemeManager {
             ));
         }
 
-        // Add animation properties
+        // Add animation properties (empty manifest values would produce invalid declarations)
         if theme_data.features.animations {
-            css.push_str(&format!(
-                "  --animation-duration: {};\n",
-                theme_data.animations.duration
-            ));
-            css.push_str(&format!(
-                "  --animation-easing: {};\n",
-                theme_data.animations.easing
-            ));
-            css.push_str(&format!(
-                "  --animation-scale: {};\n",
-                theme_data.animations.scale
-            ));
+            let animations = [
+                ("--animation-duration", &theme_data.animations.duration),
+                ("--animation-easing", &theme_data.animations.easing),
+                ("--animation-scale", &theme_data.animations.scale),
+            ];
+            for (name, value) in animations {
+                if !value.trim().is_empty() {
+                    css.push_str(&format!("  {}: {};\n", name, value));
+                    declared.insert(name.to_string());
+                }
+            }
+        }
+
+        // Fallbacks for variables the generated stylesheets consume but the manifest omits.
+        // light.css/dark.css are loaded afterwards, so scheme values still take precedence.
+        for (name, _) in CONSUMED_VARIABLES {
+            if !declared.contains(*name) {
+                css.push_str(&format!(
+                    "  {}: {};\n",
+                    name,
+                    variable_fallback(name, theme_data)
+                ));
+            }
         }
 
         css.push_str("}\n\n");
@@ -556,12 +965,12 @@ impl ThemeManager {
         css
     }
 
-    fn generate_light_css(&self, theme_data: &ThemeManifest) -> String {
+    fn generate_light_css(theme_data: &ThemeManifest) -> String {
         let mut css = String::new();
 
         if !theme_data.color_schemes.light.is_empty() {
             css.push_str(":root {\n");
-            for (key, value) in &theme_data.color_schemes.light {
+            for (key, value) in sorted_properties(&theme_data.color_schemes.light) {
                 css.push_str(&format!("  --{}: {};\n", key, value));
             }
             css.push_str("}\n\n");
@@ -570,12 +979,12 @@ impl ThemeManager {
         css
     }
 
-    fn generate_dark_css(&self, theme_data: &ThemeManifest) -> String {
+    fn generate_dark_css(theme_data: &ThemeManifest) -> String {
         let mut css = String::new();
 
         if !theme_data.color_schemes.dark.is_empty() {
             css.push_str(".dark {\n");
-            for (key, value) in &theme_data.color_schemes.dark {
+            for (key, value) in sorted_properties(&theme_data.color_schemes.dark) {
                 css.push_str(&format!("  --{}: {};\n", key, value));
             }
             css.push_str("}\n\n");
@@ -584,7 +993,7 @@ impl ThemeManager {
         css
     }
 
-    fn generate_components_css(&self, theme_data: &ThemeManifest) -> String {
+    fn generate_components_css(theme_data: &ThemeManifest) -> String {
         let mut css = String::new();
 
         // Generate component-specific styles based on theme features
@@ -592,7 +1001,7 @@ impl ThemeManager {
 
         // Button styles
         css.push_str(".btn {\n");
-        css.push_str("  background: var(--accent-color);\n");
+        css.push_str(&format!("  background: {};\n", css_var("--accent-color", theme_data)));
         css.push_str("  color: white;\n");
         css.push_str("  border: none;\n");
         css.push_str("  border-radius: 8px;\n");
@@ -605,7 +1014,7 @@ impl ThemeManager {
         css.push_str("}\n\n");
 
         css.push_str(".btn:hover {\n");
-        css.push_str("  background: var(--accent-hover);\n");
+        css.push_str(&format!("  background: {};\n", css_var("--accent-hover", theme_data)));
 
         if theme_data.features.animations {
             css.push_str("  transform: scale(var(--animation-scale));\n");
@@ -615,8 +1024,11 @@ impl ThemeManager {
 
         // Card styles
         css.push_str(".card {\n");
-        css.push_str("  background: var(--surface-color);\n");
-        css.push_str("  border: 1px solid var(--border-color);\n");
+        css.push_str(&format!("  background: {};\n", css_var("--surface-color", theme_data)));
+        css.push_str(&format!(
+            "  border: 1px solid {};\n",
+            css_var("--border-color", theme_data)
+        ));
         css.push_str("  border-radius: 12px;\n");
         css.push_str("  padding: 16px;\n");
 
@@ -646,6 +1058,39 @@ impl ThemeManager {
         Ok(theme_data.name)
     }
 
+    /// Install a downloaded theme, either a bare manifest or a zip bundle. Everything is parsed
+    /// and validated in memory first, so a rejected payload never touches the themes directory.
+    pub fn import_theme_from_bytes(&self, bytes: &[u8]) -> Result<String> {
+        let (theme_data, assets) = if bytes.starts_with(b"PK\x03\x04") {
+            read_theme_bundle(bytes)?
+        } else {
+            let theme_data: ThemeManifest = serde_json::from_slice(bytes)
+                .map_err(|e| anyhow!("Failed to parse theme manifest: {}", e))?;
+            (theme_data, Vec::new())
+        };
+
+        self.validate_theme(&theme_data)?;
+        if !is_safe_theme_name(&theme_data.name) {
+            return Err(anyhow!("Invalid theme name: {}", theme_data.name));
+        }
+
+        self.save_custom_theme(&theme_data.name, &theme_data)?;
+
+        // Bundled files go in after the generated CSS so a bundle's own stylesheets win
+        let theme_dir = self.get_themes_directory()?.join(&theme_data.name);
+        for (relative_path, content) in assets {
+            let outpath = theme_dir.join(&relative_path);
+            if let Some(parent) = outpath.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| anyhow!("Failed to create directory {:?}: {}", parent, e))?;
+            }
+            fs::write(&outpath, content)
+                .map_err(|e| anyhow!("Failed to write {:?}: {}", outpath, e))?;
+        }
+
+        Ok(theme_data.name)
+    }
+
     fn validate_theme(&self, theme_data: &ThemeManifest) -> Result<()> {
         if theme_data.name.is_empty() {
             return Err(anyhow!("Theme name cannot be empty"));
@@ -666,6 +1111,23 @@ impl ThemeManager {
             }
         }
 
+        let missing = missing_consumed_variables(theme_data);
+        if !missing.is_empty() {
+            if let Some(logger) = logger::get_logger() {
+                let _ = logger.log(
+                    logger::LogLevel::WARN,
+                    "theme_manager",
+                    "validate_theme",
+                    &format!(
+                        "Theme '{}' does not define {}; built-in fallbacks will be used",
+                        theme_data.name,
+                        missing.join(", ")
+                    ),
+                    serde_json::json!({ "theme": theme_data.name, "missing": missing }),
+                );
+            }
+        }
+
         Ok(())
     }
 }
@@ -724,6 +1186,19 @@ pub async fn load_theme_manifest(
         .map_err(|e| e.to_string())
 }
 
+/// Effective colors and fonts of a theme for the theme grid's swatch preview.
+#[tauri::command]
+pub async fn render_theme_preview(
+    app: AppHandle,
+    theme_name: String,
+) -> Result<ThemePreviewData, String> {
+    let theme_manager = ThemeManager::new(app);
+    theme_manager
+        .load_theme_manifest(&theme_name)
+        .map(|theme_data| build_theme_preview(&theme_data))
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn save_custom_theme(
     app: AppHandle,
@@ -736,6 +1211,19 @@ pub async fn save_custom_theme(
         .map_err(|e| e.to_string())
 }
 
+/// Re-emit a theme's CSS from its manifest. `style` is "pretty" (default) or "minified".
+#[tauri::command]
+pub async fn regenerate_theme_css(
+    app: AppHandle,
+    theme_name: String,
+    style: Option<CssOutputStyle>,
+) -> Result<(), String> {
+    let theme_manager = ThemeManager::new(app);
+    theme_manager
+        .regenerate_theme_css(&theme_name, style.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn delete_custom_theme(app: AppHandle, theme_name: String) -> Result<(), String> {
     let theme_manager = ThemeManager::new(app);
@@ -752,6 +1240,49 @@ pub async fn import_theme_from_file(app: AppHandle, file_path: String) -> Result
         .map_err(|e| e.to_string())
 }
 
+/// Download a theme manifest or zip bundle over https and install it. Returns the theme name.
+#[tauri::command]
+pub async fn import_theme_from_url(app: AppHandle, url: String) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "https" {
+        return Err("Themes can only be imported over https".to_string());
+    }
+
+    use crate::netgrab;
+    netgrab::ensure_online()?;
+    let mut response = netgrab::create_client()
+        .get(parsed)
+        .timeout(std::time::Duration::from_secs(THEME_DOWNLOAD_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download theme: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP error: {}", response.status()));
+    }
+    if response.content_length().unwrap_or(0) > MAX_THEME_DOWNLOAD_BYTES {
+        return Err("Theme download is too large".to_string());
+    }
+
+    // Content-Length can be missing or wrong, so the limit is enforced while reading too
+    let mut bytes: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Download interrupted: {}", e))?
+    {
+        if bytes.len() as u64 + chunk.len() as u64 > MAX_THEME_DOWNLOAD_BYTES {
+            return Err("Theme download is too large".to_string());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let theme_manager = ThemeManager::new(app);
+    theme_manager
+        .import_theme_from_bytes(&bytes)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_themes_directory_path(app: AppHandle) -> Result<String, String> {
     let theme_manager = ThemeManager::new(app);
@@ -1217,6 +1748,28 @@ pub async fn get_cached_image_url(
     Ok(Some(format!("file:///{}", path_str)))
 }
 
+/// Delete every cached theme store image. Returns the number of files removed.
+pub fn clear_theme_image_cache(app: AppHandle) -> Result<usize, String> {
+    let themes_dir = ThemeManager::new(app)
+        .get_themes_directory()
+        .map_err(|e| format!("Failed to get themes directory: {}", e))?;
+    let images_dir = themes_dir.join("images");
+    if !images_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in fs::read_dir(&images_dir)
+        .map_err(|e| format!("Failed to read images directory: {}", e))?
+        .flatten()
+    {
+        if entry.path().is_file() && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
 #[tauri::command]
 pub async fn invalidate_theme_image_cache(
     app: AppHandle,
@@ -1255,3 +1808,273 @@ pub async fn invalidate_theme_image_cache(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal_manifest() -> ThemeManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": "minimal",
+            "description": "",
+            "version": "1.0.0",
+            "author": "",
+            "preview": {},
+            "settings": {},
+            "customProperties": {
+                "--background-color": "#000",
+                "--text-color": "#fff"
+            },
+            "features": { "animations": true, "glassmorphism": true },
+            "fonts": { "primary": "sans-serif", "secondary": "sans-serif", "monospace": "monospace" }
+        }))
+        .unwrap()
+    }
+
+    /// Names of every `var(--name...)` reference in `css`.
+    fn referenced_variables(css: &str) -> Vec<String> {
+        css.split("var(")
+            .skip(1)
+            .map(|rest| {
+                rest.chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_minimal_manifest_resolves_consumed_variables() {
+        let theme = minimal_manifest();
+        let global = ThemeManager::generate_global_css(&theme);
+        let css = [
+            global.clone(),
+            ThemeManager::generate_light_css(&theme),
+            ThemeManager::generate_dark_css(&theme),
+            ThemeManager::generate_components_css(&theme),
+        ]
+        .concat();
+
+        let referenced = referenced_variables(&css);
+        assert!(referenced.contains(&"--accent-hover".to_string()));
+        for name in referenced {
+            assert!(
+                global.contains(&format!("  {}: ", name)),
+                "{} is used but never declared",
+                name
+            );
+        }
+        assert!(global.contains("  --accent-color: #3b82f6;"));
+        assert!(!global.contains(": ;"));
+        assert!(ThemeManager::generate_components_css(&theme)
+            .contains("var(--border-color, rgba(128, 128, 128, 0.25))"));
+    }
+
+    #[test]
+    fn test_missing_consumed_variables_checks_schemes() {
+        let mut theme = minimal_manifest();
+        theme
+            .custom_properties
+            .insert("--accent-color".to_string(), "#f00".to_string());
+        theme
+            .color_schemes
+            .dark
+            .insert("surface-color".to_string(), "#111".to_string());
+
+        let missing = missing_consumed_variables(&theme);
+        assert!(!missing.contains(&"--accent-color"));
+        assert!(!missing.contains(&"--surface-color"));
+        assert!(missing.contains(&"--border-color"));
+    }
+
+    fn manifest_with_many_properties() -> ThemeManifest {
+        let properties: serde_json::Map<String, serde_json::Value> = (0..32)
+            .map(|i| (format!("--prop-{:02}", i), serde_json::json!(format!("#{:06x}", i))))
+            .chain([
+                ("--background-color".to_string(), serde_json::json!("#000")),
+                ("--text-color".to_string(), serde_json::json!("#fff")),
+                ("--accent-color".to_string(), serde_json::json!("#3b82f6")),
+            ])
+            .collect();
+        let mut theme = minimal_manifest();
+        theme.custom_properties = serde_json::from_value(properties.into()).unwrap();
+        theme.color_schemes.light = (0..16)
+            .map(|i| (format!("light-{}", i), format!("#{:06x}", i)))
+            .collect();
+        theme
+    }
+
+    #[test]
+    fn test_generated_css_is_deterministic() {
+        // Separate HashMaps get separate hash seeds, so iteration order differs between them
+        let first = manifest_with_many_properties();
+        let second = manifest_with_many_properties();
+        assert_eq!(
+            ThemeManager::generate_global_css(&first),
+            ThemeManager::generate_global_css(&second)
+        );
+        assert_eq!(
+            ThemeManager::generate_light_css(&first),
+            ThemeManager::generate_light_css(&second)
+        );
+    }
+
+    #[test]
+    fn test_theme_preview_merges_schemes() {
+        let mut theme = minimal_manifest();
+        theme
+            .custom_properties
+            .insert("--accent-color".to_string(), "#ff0000".to_string());
+        theme.color_schemes.dark.insert(
+            "surface-color".to_string(),
+            "var(--background-color)".to_string(),
+        );
+        theme
+            .color_schemes
+            .light
+            .insert("text-color".to_string(), "#111".to_string());
+
+        let preview = build_theme_preview(&theme);
+        assert_eq!(preview.display_name, "minimal");
+        assert_eq!(preview.default_mode, "dark");
+        assert_eq!(preview.light.colors.text, "#111");
+        assert_eq!(preview.dark.colors.text, "#fff");
+        assert_eq!(preview.dark.colors.surface, "#000");
+        assert_eq!(preview.light.colors.surface, "rgba(128, 128, 128, 0.08)");
+        assert_eq!(
+            preview.dark.colors.accent_hover,
+            "color-mix(in srgb, #ff0000 85%, black)"
+        );
+        assert_eq!(
+            preview.font_stack,
+            "sans-serif, sans-serif, system-ui, -apple-system, sans-serif"
+        );
+    }
+
+    #[test]
+    fn test_theme_preview_fills_defaults() {
+        let mut theme = minimal_manifest();
+        theme.custom_properties = [("primaryColor".to_string(), "#3399ff".to_string())]
+            .into_iter()
+            .collect();
+        theme.fonts.primary = String::new();
+        theme.fonts.secondary = " ".to_string();
+
+        let preview = build_theme_preview(&theme);
+        // primaryColor stands in for the missing accent, as it does in global.css
+        assert_eq!(preview.light.colors.accent, "#3399ff");
+        assert!(ThemeManager::generate_global_css(&theme).contains("  --accent-color: #3399ff;"));
+        assert_eq!(preview.light.colors.background, "#ffffff");
+        assert_eq!(preview.dark.colors.background, "#111827");
+        assert_eq!(
+            preview.light.variables.get("--primary-color"),
+            Some(&"#3399ff".to_string())
+        );
+        assert_eq!(preview.font_stack, "system-ui, -apple-system, sans-serif");
+        assert_eq!(preview.thumbnail, None);
+    }
+
+    #[test]
+    fn test_resolve_variable() {
+        let variables: std::collections::BTreeMap<String, String> = [
+            ("--a".to_string(), "var(--b)".to_string()),
+            ("--b".to_string(), "#123".to_string()),
+            ("--loop".to_string(), "var(--loop)".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(resolve_variable("var(--a)", &variables), "#123");
+        assert_eq!(resolve_variable("var(--missing, #fff)", &variables), "#fff");
+        assert_eq!(
+            resolve_variable("var(--missing)", &variables),
+            "var(--missing)"
+        );
+        assert_eq!(resolve_variable("var(--loop)", &variables), "var(--loop)");
+    }
+
+    fn bundle(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn manifest_json() -> Vec<u8> {
+        let mut theme = minimal_manifest();
+        theme
+            .custom_properties
+            .insert("--accent-color".to_string(), "#f00".to_string());
+        serde_json::to_vec(&theme).unwrap()
+    }
+
+    #[test]
+    fn test_read_theme_bundle_strips_root_folder() {
+        let manifest = manifest_json();
+        let bytes = bundle(&[
+            ("minimal/theme-manifest.json", &manifest),
+            ("minimal/styles/global.css", b":root {}"),
+            ("minimal/preview.png", b"png"),
+            ("README.md", b"outside the theme folder"),
+        ]);
+
+        let (theme, assets) = read_theme_bundle(&bytes).unwrap();
+        assert_eq!(theme.name, "minimal");
+        let paths: Vec<PathBuf> = assets.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("styles/global.css"),
+                PathBuf::from("preview.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_theme_bundle_rejects_unsafe_paths() {
+        let manifest = manifest_json();
+        let bytes = bundle(&[
+            ("theme-manifest.json", &manifest),
+            ("../../evil.sh", b"echo pwned"),
+        ]);
+        assert!(read_theme_bundle(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_read_theme_bundle_requires_manifest() {
+        let bytes = bundle(&[("styles/global.css", b":root {}")]);
+        assert!(read_theme_bundle(&bytes).is_err());
+
+        let bytes = bundle(&[("a/b/theme-manifest.json", &manifest_json())]);
+        assert!(read_theme_bundle(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_is_safe_theme_name() {
+        assert!(is_safe_theme_name("Ocean Breeze"));
+        assert!(!is_safe_theme_name(""));
+        assert!(!is_safe_theme_name(".."));
+        assert!(!is_safe_theme_name(".temp"));
+        assert!(!is_safe_theme_name("../themes"));
+        assert!(!is_safe_theme_name("a\\b"));
+        assert!(!is_safe_theme_name("C:"));
+        assert!(!is_safe_theme_name("trailing."));
+    }
+
+    #[test]
+    fn test_minify_css() {
+        let css = "/* Component */\n.btn:hover {\n  font: 12px \"Open  Sans\", serif;\n  border: 1px solid var(--border, #fff);\n  width: calc(100% - 2px);\n}\n\n";
+        assert_eq!(
+            minify_css(css),
+            ".btn:hover{font:12px \"Open  Sans\",serif;border:1px solid var(--border,#fff);width:calc(100% - 2px)}"
+        );
+        assert_eq!(
+            minify_css(".card :hover,\n.list > :first-child { color: red; }"),
+            ".card :hover,.list>:first-child{color:red}"
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! iCalendar export of the SEQTA timetable, for importing into phone and desktop calendars.
+//!
+//! SEQTA gives lesson times in the school's local time without an offset. They are converted
+//! to UTC with the school's timezone (see `timezone`), so calendars in any timezone show
+//! lessons at the right moment.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+
+use crate::seqta_mentions;
+use crate::timezone;
+
+const PRODID: &str = "-//BetterSEQTA//DesQTA Timetable//EN";
+/// Longest content line in octets, excluding the CRLF (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableIcsSummary {
+    pub path: String,
+    pub events: usize,
+    /// Entries without a usable date
+    pub skipped: usize,
+}
+
+/// A timetable entry ready to become a VEVENT.
+#[derive(Debug, Clone, PartialEq)]
+struct CalendarLesson {
+    uid: String,
+    date: NaiveDate,
+    /// Start and end time, `None` for entries written as all-day events
+    times: Option<(NaiveTime, NaiveTime)>,
+    summary: String,
+    location: String,
+    description: String,
+}
+
+/// Parse SEQTA's "HH:MM" or "HH:MM:SS" lesson times.
+pub(crate) fn parse_lesson_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .ok()
+}
+
+/// UID that stays the same across exports so calendars update a lesson instead of adding it
+/// again: SEQTA's lesson ID when there is one, otherwise a hash of the class and date.
+fn lesson_uid(lesson: &Value, date: NaiveDate) -> String {
+    let key = match lesson["id"].as_i64() {
+        Some(id) => format!("{}-{}", id, date),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(
+                format!(
+                    "{}|{}|{}|{}|{}",
+                    date,
+                    lesson["from"].as_str().unwrap_or(""),
+                    lesson["code"].as_str().unwrap_or(""),
+                    lesson["programmeID"],
+                    lesson["metaID"]
+                )
+                .as_bytes(),
+            );
+            hex::encode(&hasher.finalize()[..12])
+        }
+    };
+    format!("seqta-lesson-{}@desqta", key)
+}
+
+/// Turn a timetable item into a calendar entry. Items without a date are dropped; items
+/// without a sensible start and end become all-day events.
+fn calendar_lesson(lesson: &Value, subject: &str) -> Option<CalendarLesson> {
+    let date = lesson["date"]
+        .as_str()
+        .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())?;
+    let times = lesson["from"]
+        .as_str()
+        .and_then(parse_lesson_time)
+        .zip(lesson["until"].as_str().and_then(parse_lesson_time))
+        .filter(|(start, end)| end > start);
+    let summary = Some(subject.trim())
+        .filter(|s| !s.is_empty())
+        .or_else(|| lesson["description"].as_str().map(str::trim))
+        .filter(|s| !s.is_empty())
+        .unwrap_or("Lesson");
+    let text = |value: &Value| value.as_str().unwrap_or("").trim().to_string();
+    let mut teacher = text(&lesson["staff"]);
+    if teacher.is_empty() {
+        teacher = text(&lesson["teacher"]);
+    }
+    Some(CalendarLesson {
+        uid: lesson_uid(lesson, date),
+        date,
+        times,
+        summary: summary.to_string(),
+        location: text(&lesson["room"]),
+        description: teacher,
+    })
+}
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11).
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded onto continuation lines so none exceeds 75 octets.
+fn push_line(out: &mut String, line: &str) {
+    let mut limit = MAX_LINE_OCTETS;
+    let mut used = 0;
+    for c in line.chars() {
+        if used + c.len_utf8() > limit {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            limit = MAX_LINE_OCTETS - 1;
+            used = 0;
+        }
+        out.push(c);
+        used += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Build the iCalendar document for `lessons`, with lesson times interpreted in `tz`.
+fn build_calendar<Tz: TimeZone>(lessons: &[CalendarLesson], tz: &Tz, now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{}", PRODID));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(&mut out, "X-WR-CALNAME:SEQTA Timetable");
+
+    let stamp = format_utc(now);
+    for lesson in lessons {
+        let span = lesson.times.and_then(|(start, end)| {
+            Some((
+                timezone::local_to_utc(tz, lesson.date.and_time(start))?,
+                timezone::local_to_utc(tz, lesson.date.and_time(end))?,
+            ))
+        });
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}", lesson.uid));
+        push_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        match span {
+            Some((start, end)) => {
+                push_line(&mut out, &format!("DTSTART:{}", format_utc(start)));
+                push_line(&mut out, &format!("DTEND:{}", format_utc(end)));
+            }
+            None => {
+                let next_day = lesson.date + Duration::days(1);
+                push_line(
+                    &mut out,
+                    &format!("DTSTART;VALUE=DATE:{}", lesson.date.format("%Y%m%d")),
+                );
+                push_line(
+                    &mut out,
+                    &format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")),
+                );
+                push_line(&mut out, "TRANSP:TRANSPARENT");
+            }
+        }
+        push_line(
+            &mut out,
+            &format!("SUMMARY:{}", escape_text(&lesson.summary)),
+        );
+        if !lesson.location.is_empty() {
+            push_line(
+                &mut out,
+                &format!("LOCATION:{}", escape_text(&lesson.location)),
+            );
+        }
+        if !lesson.description.is_empty() {
+            push_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(&lesson.description)),
+            );
+        }
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// Write the timetable between `from` and `until` (`YYYY-MM-DD`, inclusive) to an `.ics` file
+/// at `dest_path`. Lesson UIDs are stable, so importing a newer export updates the lessons
+/// already in the calendar.
+#[tauri::command]
+pub async fn export_timetable_ics(
+    from: String,
+    until: String,
+    dest_path: String,
+) -> Result<TimetableIcsSummary, String> {
+    let (from, until) = seqta_mentions::parse_timetable_window(Some(&from), Some(&until))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "A timetable range is required".to_string())?;
+    let items = seqta_mentions::fetch_timetable_range(from, until)
+        .await
+        .map_err(|e| e.to_string())?;
+    let subjects = seqta_mentions::lesson_subject_titles(&items).await;
+
+    let mut lessons: Vec<CalendarLesson> = items
+        .iter()
+        .zip(&subjects)
+        .filter_map(|(item, subject)| calendar_lesson(item, subject))
+        .collect();
+    let skipped = items.len() - lessons.len();
+    lessons.sort_by(|a, b| (a.date, a.times).cmp(&(b.date, b.times)));
+    // The same lesson can come back from two overlapping chunks
+    let mut seen = std::collections::HashSet::new();
+    lessons.retain(|l| seen.insert(l.uid.clone()));
+
+    let calendar = build_calendar(&lessons, &timezone::school_timezone(), Utc::now());
+    fs::write(&dest_path, calendar).map_err(|e| format!("Failed to write calendar: {}", e))?;
+    Ok(TimetableIcsSummary {
+        path: dest_path,
+        events: lessons.len(),
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use serde_json::json;
+
+    /// Unfold and split an iCalendar document into (name with parameters, value) pairs,
+    /// checking the line rules along the way.
+    fn parse_ics(ics: &str) -> Vec<(String, String)> {
+        assert!(ics.ends_with("\r\n"));
+        let mut lines: Vec<String> = Vec::new();
+        for raw in ics.trim_end_matches("\r\n").split("\r\n") {
+            assert!(raw.len() <= MAX_LINE_OCTETS, "line too long: {}", raw);
+            assert!(!raw.contains('\n'));
+            match raw.strip_prefix(' ') {
+                Some(rest) => lines.last_mut().unwrap().push_str(rest),
+                None => lines.push(raw.to_string()),
+            }
+        }
+        lines
+            .into_iter()
+            .map(|line| {
+                let (name, value) = line.split_once(':').expect("property without a value");
+                (name.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_timetable_ics_parses() {
+        let items = vec![
+            json!({"id": 501, "date": "2024-05-01", "from": "08:45:00", "until": "09:35:00",
+                "code": "10MAT", "room": "B12", "staff": "Ms Smith"}),
+            json!({"date": "2024-05-01", "from": "13:00", "until": "13:00",
+                "description": "Sports carnival"}),
+            json!({"from": "09:00", "until": "10:00", "code": "10ENG"}),
+        ];
+        let subjects = [
+            "Mathematics, Year 10; Advanced".to_string(),
+            String::new(),
+            "English".to_string(),
+        ];
+        let lessons: Vec<CalendarLesson> = items
+            .iter()
+            .zip(&subjects)
+            .filter_map(|(item, subject)| calendar_lesson(item, subject))
+            .collect();
+        assert_eq!(lessons.len(), 2);
+
+        let perth = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 4, 30, 12, 0, 0).unwrap();
+        let ics = build_calendar(&lessons, &perth, now);
+        let props = parse_ics(&ics);
+
+        let names: Vec<&str> = props.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names.first(), Some(&"BEGIN"));
+        assert_eq!(names.iter().filter(|n| **n == "BEGIN").count(), 3);
+        assert_eq!(names.iter().filter(|n| **n == "END").count(), 3);
+        let get = |name: &str| -> Vec<&str> {
+            props
+                .iter()
+                .filter(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+                .collect()
+        };
+        assert_eq!(get("UID")[0], "seqta-lesson-501-2024-05-01@desqta");
+        assert_eq!(get("DTSTART"), vec!["20240501T004500Z"]);
+        assert_eq!(get("DTEND"), vec!["20240501T013500Z"]);
+        assert_eq!(
+            get("SUMMARY"),
+            vec!["Mathematics\\, Year 10\\; Advanced", "Sports carnival"]
+        );
+        assert_eq!(get("LOCATION"), vec!["B12"]);
+        assert_eq!(get("DESCRIPTION"), vec!["Ms Smith"]);
+        // Zero-length entry becomes an all-day event
+        assert_eq!(get("DTSTART;VALUE=DATE"), vec!["20240501"]);
+        assert_eq!(get("DTEND;VALUE=DATE"), vec!["20240502"]);
+
+        // Re-exporting gives the same UIDs
+        let again = calendar_lesson(&items[1], &subjects[1]).unwrap();
+        assert_eq!(again.uid, lessons[1].uid);
+
+        // Long values are folded without splitting characters
+        let mut long = lessons[0].clone();
+        long.description = "Ms Smith — ".repeat(20);
+        let folded = build_calendar(&[long.clone()], &perth, now);
+        let description = parse_ics(&folded)
+            .into_iter()
+            .find(|(n, _)| n == "DESCRIPTION")
+            .unwrap()
+            .1;
+        assert_eq!(description, long.description);
+    }
+}
@@ -0,0 +1,156 @@
+//! The school's timezone. SEQTA works in the school's local time, so "today", the date ranges
+//! sent to SEQTA and due dates written without an offset are all taken in this zone. It is the
+//! system timezone unless `Settings::timezone` names another one.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::sync::RwLock;
+
+use crate::settings::Settings;
+
+/// The zone `school_timezone` hands out. Filled from the `timezone` setting on first use and
+/// replaced by `set_timezone_override` whenever the setting may have changed.
+static TIMEZONE: RwLock<Option<Tz>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimezoneInfo {
+    /// Timezone in use
+    pub timezone: String,
+    pub system: String,
+    /// The timezone comes from settings rather than the system
+    pub overridden: bool,
+}
+
+/// The operating system's timezone, or UTC when it can't be determined.
+pub fn system_timezone() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+fn parse_timezone(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+/// The timezone for a `timezone` setting. Unset, empty or unknown names use the system's.
+fn resolve(setting: Option<&str>) -> Tz {
+    setting
+        .and_then(parse_timezone)
+        .unwrap_or_else(system_timezone)
+}
+
+/// The school's timezone, from the `timezone` setting or the system's when none is set.
+pub fn school_timezone() -> Tz {
+    if let Some(tz) = *TIMEZONE.read().unwrap() {
+        return tz;
+    }
+    let tz = resolve(Settings::load().timezone.as_deref());
+    // Don't clobber a value set while we were loading
+    *TIMEZONE.write().unwrap().get_or_insert(tz)
+}
+
+/// Switch to the zone named by a `timezone` setting, or the system's for `None`. Called when
+/// settings are saved and when another profile's settings become active.
+pub fn set_timezone_override(setting: Option<&str>) {
+    *TIMEZONE.write().unwrap() = Some(resolve(setting));
+}
+
+/// The date at `now` in `tz`.
+pub fn today_in(tz: &Tz, now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(tz).date_naive()
+}
+
+/// Today's date at the school.
+pub fn today() -> NaiveDate {
+    today_in(&school_timezone(), Utc::now())
+}
+
+/// Wall-clock time in `tz` as UTC. Times skipped by a daylight saving change are moved
+/// forward an hour, like the clocks were.
+pub fn local_to_utc<Z: TimeZone>(tz: &Z, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn timezone_info(settings: &Settings) -> TimezoneInfo {
+    let overridden = settings
+        .timezone
+        .as_deref()
+        .and_then(parse_timezone)
+        .is_some();
+    TimezoneInfo {
+        timezone: resolve(settings.timezone.as_deref()).name().to_string(),
+        system: system_timezone().name().to_string(),
+        overridden,
+    }
+}
+
+#[tauri::command]
+pub fn get_timezone_info() -> TimezoneInfo {
+    timezone_info(&Settings::load())
+}
+
+/// IANA names of every timezone that can be picked, e.g. "Australia/Perth".
+#[tauri::command]
+pub fn list_timezones() -> Vec<&'static str> {
+    chrono_tz::TZ_VARIANTS.iter().map(|tz| tz.name()).collect()
+}
+
+/// Use `timezone` (an IANA name) instead of the system timezone, or go back to the system's
+/// with `None`.
+#[tauri::command]
+pub fn set_timezone(timezone: Option<String>) -> Result<TimezoneInfo, String> {
+    let timezone = timezone
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if let Some(name) = &timezone {
+        if parse_timezone(name).is_none() {
+            return Err(format!("Unknown timezone: {}", name));
+        }
+    }
+    let mut settings = Settings::load();
+    settings.timezone = timezone;
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(timezone_info(&settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_today_follows_school_timezone() {
+        let sydney = resolve(Some("Australia/Sydney"));
+        assert_eq!(sydney, Tz::Australia__Sydney);
+        assert_eq!(resolve(Some("Mars/Olympus_Mons")), system_timezone());
+        assert_eq!(resolve(Some("  ")), system_timezone());
+
+        // 8pm UTC on 1 May is already the morning of 2 May in Sydney
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 20, 0, 0).unwrap();
+        assert_eq!(
+            today_in(&Tz::UTC, now),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+        assert_eq!(
+            today_in(&sydney, now),
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+        );
+
+        // 2:30am on the first Sunday of October doesn't exist in Sydney
+        let gap = NaiveDate::from_ymd_opt(2024, 10, 6)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(
+            local_to_utc(&sydney, gap),
+            Some(Utc.with_ymd_and_hms(2024, 10, 5, 16, 30, 0).unwrap())
+        );
+    }
+}
@@ -1,8 +1,17 @@
+use chrono::{NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use tauri::AppHandle;
+use uuid::Uuid;
+
+use crate::notes_filesystem;
+use crate::sanitization;
+use crate::timezone;
+
+/// Longest title a todo made from a note selection gets; the full text goes in the description.
+const MAX_SELECTION_TITLE_CHARS: usize = 80;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subtask {
@@ -22,6 +31,8 @@ pub struct TodoItem {
     #[serde(default)]
     pub related_assessment: Option<String>,
     #[serde(default)]
+    pub related_note: Option<String>, // id of the note the todo was made from
+    #[serde(default)]
     pub due_date: Option<String>, // ISO date (YYYY-MM-DD)
     #[serde(default)]
     pub due_time: Option<String>, // 24h time (HH:MM)
@@ -39,31 +50,14 @@ pub struct TodoItem {
     pub updated_at: Option<String>, // ISO timestamp
 }
 
-/// Location strategy mirrors settings.rs:
-/// - Android: /data/data/com.desqta.app/files/DesQTA/todolist.json
-/// - Other platforms: <OS data dir>/DesQTA/todolist.json
+/// Location: `<data dir>/todolist.json`
 fn todos_file_path(_app: &AppHandle) -> Result<PathBuf, String> {
-    #[cfg(target_os = "android")]
-    {
-        let mut dir = PathBuf::from("/data/data/com.desqta.app/files");
-        dir.push("DesQTA");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
-        }
-        dir.push("todolist.json");
-        Ok(dir)
-    }
-    #[cfg(not(target_os = "android"))]
-    {
-        let mut dir =
-            dirs_next::data_dir().ok_or_else(|| "Unable to determine data dir".to_string())?;
-        dir.push("DesQTA");
-        if !dir.exists() {
-            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
-        }
-        dir.push("todolist.json");
-        Ok(dir)
+    let mut dir = crate::data_dir::app_data_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
     }
+    dir.push("todolist.json");
+    Ok(dir)
 }
 
 fn ensure_parent_dir(path: &PathBuf) -> Result<(), String> {
@@ -102,3 +96,216 @@ pub fn save_todos(app: AppHandle, todos: Vec<TodoItem>) -> Result<(), String> {
         .map_err(|e| format!("Failed to write file: {}", e))?;
     Ok(())
 }
+
+/// `due` as (date, time): `YYYY-MM-DD`, optionally followed by ` HH:MM` or `THH:MM`.
+fn parse_due(due: &str) -> Result<(String, Option<String>), String> {
+    let invalid = || format!("Invalid due date \"{}\", expected YYYY-MM-DD [HH:MM]", due);
+    let due = due.trim();
+    let (date, time) = match due.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (due, None),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+    let time = time
+        .map(|time| NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid()))
+        .transpose()?;
+    Ok((
+        date.format("%Y-%m-%d").to_string(),
+        time.map(|time| time.format("%H:%M").to_string()),
+    ))
+}
+
+/// A todo for the text selected in note `note_id`. The first line becomes the title (cut
+/// short if it's long); the whole selection is kept as the description when it's more than that.
+fn todo_from_selection(text: &str, due: Option<&str>, note_id: &str) -> Result<TodoItem, String> {
+    let text = text.trim();
+    let first_line = text.lines().next().unwrap_or_default().trim();
+    if first_line.is_empty() {
+        return Err("Select some text to turn into a todo".to_string());
+    }
+    let title = if first_line.chars().count() > MAX_SELECTION_TITLE_CHARS {
+        let cut: String = first_line.chars().take(MAX_SELECTION_TITLE_CHARS).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        first_line.to_string()
+    };
+    let (due_date, due_time) = match due.filter(|due| !due.trim().is_empty()) {
+        Some(due) => {
+            let (date, time) = parse_due(due)?;
+            (Some(date), time)
+        }
+        None => (None, None),
+    };
+
+    let now = Utc::now().to_rfc3339();
+    Ok(TodoItem {
+        id: Uuid::new_v4().to_string(),
+        description: (title != text).then(|| text.to_string()),
+        title,
+        related_subject: None,
+        related_assessment: None,
+        related_note: Some(note_id.to_string()),
+        due_date,
+        due_time,
+        tags: None,
+        subtasks: None,
+        completed: false,
+        priority: None,
+        created_at: Some(now.clone()),
+        updated_at: Some(now),
+    })
+}
+
+fn todos_for_note(todos: Vec<TodoItem>, note_id: &str) -> Vec<TodoItem> {
+    todos
+        .into_iter()
+        .filter(|todo| todo.related_note.as_deref() == Some(note_id))
+        .collect()
+}
+
+/// Line appended to a note when a todo made from it is completed.
+fn completion_annotation(title: &str, date: NaiveDate) -> String {
+    format!(
+        "<p>✓ Done: {} ({})</p>",
+        sanitization::escape_html(title),
+        date.format("%-d %B %Y")
+    )
+}
+
+/// Append the completion line for `title` to a note's content, unless the note already has
+/// one (the todo was completed, reopened and completed again). Returns whether it was added.
+fn annotate_completion(content: &mut String, title: &str, date: NaiveDate) -> bool {
+    let marker = format!("<p>✓ Done: {} (", sanitization::escape_html(title));
+    if content.contains(&marker) {
+        return false;
+    }
+    content.push_str(&completion_annotation(title, date));
+    true
+}
+
+/// Turn text selected in a note into a todo linked back to the note. `due` is `YYYY-MM-DD`,
+/// optionally with a ` HH:MM` time.
+#[tauri::command]
+pub fn create_todo_from_note_selection(
+    app: AppHandle,
+    text: String,
+    due: Option<String>,
+    note_id: String,
+) -> Result<TodoItem, String> {
+    if notes_filesystem::get_note_filesystem(app.clone(), note_id.clone())?.is_none() {
+        return Err("Note not found".to_string());
+    }
+    let todo = todo_from_selection(&text, due.as_deref(), &note_id)?;
+    let mut todos = load_todos(app.clone())?;
+    todos.insert(0, todo.clone());
+    save_todos(app, todos)?;
+    Ok(todo)
+}
+
+/// Todos made from the note `note_id`, in list order.
+#[tauri::command]
+pub fn get_todos_for_note(app: AppHandle, note_id: String) -> Result<Vec<TodoItem>, String> {
+    Ok(todos_for_note(load_todos(app)?, &note_id))
+}
+
+/// Mark a todo done. With `annotate_note`, a todo made from a note also adds a "Done" line to
+/// the end of that note, unless it already has one; a note that has since been deleted is
+/// skipped. A todo that's already done is returned unchanged.
+#[tauri::command]
+pub fn complete_todo(
+    app: AppHandle,
+    todo_id: String,
+    annotate_note: Option<bool>,
+) -> Result<TodoItem, String> {
+    let mut todos = load_todos(app.clone())?;
+    let todo = todos
+        .iter_mut()
+        .find(|todo| todo.id == todo_id)
+        .ok_or_else(|| "Todo not found".to_string())?;
+    if todo.completed {
+        return Ok(todo.clone());
+    }
+    todo.completed = true;
+    todo.updated_at = Some(Utc::now().to_rfc3339());
+    let todo = todo.clone();
+    save_todos(app.clone(), todos)?;
+
+    if annotate_note.unwrap_or(false) {
+        if let Some(note_id) = &todo.related_note {
+            if let Some(mut note) =
+                notes_filesystem::get_note_filesystem(app.clone(), note_id.clone())?
+            {
+                if annotate_completion(&mut note.content, &todo.title, timezone::today()) {
+                    note.updated_at = Utc::now().to_rfc3339();
+                    notes_filesystem::save_note_filesystem(app, note)?;
+                }
+            }
+        }
+    }
+    Ok(todo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_selection_to_todo_round_trip() {
+        let todo = todo_from_selection(
+            "  Email Ms Smith about the lab\nAsk whether the report can be handed in late ",
+            Some("2024-05-20 15:30"),
+            "note-1",
+        )
+        .unwrap();
+        assert_eq!(todo.title, "Email Ms Smith about the lab");
+        assert_eq!(
+            todo.description.as_deref(),
+            Some("Email Ms Smith about the lab\nAsk whether the report can be handed in late")
+        );
+        assert_eq!(todo.due_date.as_deref(), Some("2024-05-20"));
+        assert_eq!(todo.due_time.as_deref(), Some("15:30"));
+
+        // Saved and loaded again, the todo still leads back to its note
+        let other = todo_from_selection("Revise chapter 3", None, "note-2").unwrap();
+        assert!(other.description.is_none() && other.due_date.is_none());
+        let json = serde_json::to_string(&vec![todo.clone(), other]).unwrap();
+        let loaded: Vec<TodoItem> = serde_json::from_str(&json).unwrap();
+        let linked = todos_for_note(loaded, "note-1");
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].id, todo.id);
+        assert_eq!(linked[0].related_note.as_deref(), Some("note-1"));
+
+        // Todos saved before notes could be linked still load
+        let old: TodoItem =
+            serde_json::from_str(r#"{"id": "a", "title": "Old", "completed": false}"#).unwrap();
+        assert!(old.related_note.is_none());
+
+        let long = "x".repeat(200);
+        let todo = todo_from_selection(&long, Some("2024-05-20"), "note-1").unwrap();
+        assert_eq!(todo.title.chars().count(), MAX_SELECTION_TITLE_CHARS + 1);
+        assert_eq!(todo.description.as_deref(), Some(long.as_str()));
+        assert!(todo.due_time.is_none());
+        assert!(todo_from_selection("   ", None, "note-1").is_err());
+        assert!(todo_from_selection("Task", Some("20/05/2024"), "note-1").is_err());
+
+        assert_eq!(
+            completion_annotation(
+                "Read <Macbeth>",
+                NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()
+            ),
+            "<p>✓ Done: Read &lt;Macbeth&gt; (20 May 2024)</p>"
+        );
+
+        // Completing again (after reopening) doesn't add a second line
+        let mut content = "<p>Notes</p>".to_string();
+        let may = NaiveDate::from_ymd_opt(2024, 5, 20).unwrap();
+        assert!(annotate_completion(&mut content, "Read <Macbeth>", may));
+        assert!(!annotate_completion(
+            &mut content,
+            "Read <Macbeth>",
+            may.succ_opt().unwrap()
+        ));
+        assert_eq!(content.matches("✓ Done").count(), 1);
+        assert!(annotate_completion(&mut content, "Read Hamlet", may));
+    }
+}
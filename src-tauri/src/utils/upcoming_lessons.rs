@@ -0,0 +1,224 @@
+//! The next few lessons across all of the student's classes, for the "what's next" widget.
+//! Lessons are read from the timetable for the coming fortnight, so after the last lesson of
+//! the day (or over a weekend) the list simply carries on with the next school day.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::database;
+use crate::seqta_mentions;
+use crate::timetable_ics::parse_lesson_time;
+use crate::timezone;
+
+/// Days of timetable, from today, searched for upcoming lessons
+const LOOKAHEAD_DAYS: i64 = 14;
+/// Most lessons returned by one call
+const MAX_UPCOMING_LESSONS: usize = 50;
+const UPCOMING_LESSONS_CACHE_KEY: &str = "upcoming_lessons";
+const UPCOMING_LESSONS_CACHE_TTL_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpcomingLesson {
+    pub subject: String,
+    pub code: String,
+    pub room: String,
+    pub teacher: String,
+    /// `YYYY-MM-DD`, school time
+    pub date: String,
+    /// `HH:MM`, school time
+    pub start: String,
+    pub end: String,
+    /// RFC 3339 start with the school's UTC offset
+    pub starts_at: String,
+}
+
+impl UpcomingLesson {
+    fn start_local(&self) -> Option<NaiveDateTime> {
+        let date = NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()?;
+        let start = NaiveTime::parse_from_str(&self.start, "%H:%M").ok()?;
+        Some(date.and_time(start))
+    }
+}
+
+/// Timetable `items` (with their subject titles) as lessons in start order. Entries without
+/// a date or start time are dropped, as are repeats from overlapping timetable chunks.
+fn timetable_lessons<Tz: TimeZone>(
+    items: &[Value],
+    subjects: &[String],
+    tz: &Tz,
+) -> Vec<UpcomingLesson>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let text = |value: &Value| value.as_str().unwrap_or("").trim().to_string();
+    let mut lessons: Vec<(NaiveDateTime, UpcomingLesson)> = items
+        .iter()
+        .zip(subjects)
+        .filter_map(|(item, subject)| {
+            let date = item["date"]
+                .as_str()
+                .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())?;
+            let start = date.and_time(item["from"].as_str().and_then(parse_lesson_time)?);
+            let end = item["until"]
+                .as_str()
+                .and_then(parse_lesson_time)
+                .unwrap_or(start.time());
+            let starts_at = timezone::local_to_utc(tz, start)?
+                .with_timezone(tz)
+                .to_rfc3339();
+            let code = text(&item["code"]);
+            let mut teacher = text(&item["staff"]);
+            if teacher.is_empty() {
+                teacher = text(&item["teacher"]);
+            }
+            let subject = match subject.trim() {
+                "" => code.clone(),
+                subject => subject.to_string(),
+            };
+            Some((
+                start,
+                UpcomingLesson {
+                    subject,
+                    code,
+                    room: text(&item["room"]),
+                    teacher,
+                    date: date.format("%Y-%m-%d").to_string(),
+                    start: start.format("%H:%M").to_string(),
+                    end: end.format("%H:%M").to_string(),
+                    starts_at,
+                },
+            ))
+        })
+        .collect();
+    lessons.sort_by(|a, b| (a.0, &a.1.subject).cmp(&(b.0, &b.1.subject)));
+    let mut seen = HashSet::new();
+    lessons.retain(|(_, l)| seen.insert((l.date.clone(), l.start.clone(), l.code.clone())));
+    lessons.into_iter().map(|(_, lesson)| lesson).collect()
+}
+
+/// The first `count` of `lessons` (in start order) that haven't started by `now`.
+fn next_lessons(
+    lessons: Vec<UpcomingLesson>,
+    now: NaiveDateTime,
+    count: usize,
+) -> Vec<UpcomingLesson> {
+    lessons
+        .into_iter()
+        .filter(|lesson| lesson.start_local().is_some_and(|start| start > now))
+        .take(count)
+        .collect()
+}
+
+/// Lessons from today over the next fortnight, cached for a few minutes.
+async fn lessons_ahead(today: NaiveDate) -> Result<Vec<UpcomingLesson>, String> {
+    let cached = database::db_cache_get(UPCOMING_LESSONS_CACHE_KEY.to_string())
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_value::<(String, Vec<UpcomingLesson>)>(value).ok());
+    let today_str = today.format("%Y-%m-%d").to_string();
+    if let Some((from, lessons)) = cached {
+        // A cache filled yesterday doesn't reach as far ahead
+        if from == today_str {
+            return Ok(lessons);
+        }
+    }
+
+    let items =
+        seqta_mentions::fetch_timetable_range(today, today + Duration::days(LOOKAHEAD_DAYS))
+            .await
+            .map_err(|e| e.to_string())?;
+    let subjects = seqta_mentions::lesson_subject_titles(&items).await;
+    let lessons = timetable_lessons(&items, &subjects, &timezone::school_timezone());
+    if let Ok(value) = serde_json::to_value((&today_str, &lessons)) {
+        let _ = database::db_cache_set(
+            UPCOMING_LESSONS_CACHE_KEY.to_string(),
+            value,
+            Some(UPCOMING_LESSONS_CACHE_TTL_MINUTES),
+        );
+    }
+    Ok(lessons)
+}
+
+/// The next `count` lessons (at most 50) across all classes, soonest first, with times in
+/// the school's timezone. Lessons already under way aren't included.
+#[tauri::command]
+pub async fn get_upcoming_lessons(count: usize) -> Result<Vec<UpcomingLesson>, String> {
+    let now = Utc::now()
+        .with_timezone(&timezone::school_timezone())
+        .naive_local();
+    let lessons = lessons_ahead(now.date()).await?;
+    Ok(next_lessons(lessons, now, count.min(MAX_UPCOMING_LESSONS)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use serde_json::json;
+
+    #[test]
+    fn test_upcoming_lessons_in_order() {
+        // Out of order and across classes, as the timetable chunks come back
+        let items = vec![
+            json!({"date": "2024-05-07", "from": "08:50:00", "until": "09:45:00",
+                "code": "10MAT", "room": "B12", "staff": "Ms Smith"}),
+            json!({"date": "2024-05-06", "from": "14:00:00", "until": "14:55:00",
+                "code": "10ENG", "room": "A3", "teacher": "Mr Jones"}),
+            json!({"date": "2024-05-06", "from": "09:45:00", "until": "10:40:00",
+                "code": "10SCI", "room": "Lab 2"}),
+            json!({"date": "2024-05-07", "from": "08:50", "until": "09:45",
+                "code": "10MAT", "room": "B12", "staff": "Ms Smith"}),
+            json!({"date": "2024-05-07", "from": "09:45:00", "until": "10:40:00",
+                "code": "10HIS"}),
+            json!({"from": "11:00:00", "until": "12:00:00", "code": "10ART"}),
+        ];
+        let subjects: Vec<String> = [
+            "Mathematics",
+            "English",
+            "Science",
+            "Mathematics",
+            "",
+            "Art",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let perth = FixedOffset::east_opt(8 * 3600).unwrap();
+        let lessons = timetable_lessons(&items, &subjects, &perth);
+        // The undated lesson and the repeat from an overlapping chunk are gone
+        assert_eq!(lessons.len(), 4);
+
+        let at = |date: &str, time: &str| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+        };
+        let next = next_lessons(lessons.clone(), at("2024-05-06", "09:00"), 3);
+        let order: Vec<(&str, &str)> = next
+            .iter()
+            .map(|l| (l.date.as_str(), l.start.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("2024-05-06", "09:45"),
+                ("2024-05-06", "14:00"),
+                ("2024-05-07", "08:50"),
+            ]
+        );
+        assert_eq!(next[0].subject, "Science");
+        assert_eq!(next[1].teacher, "Mr Jones");
+        assert_eq!(next[2].starts_at, "2024-05-07T08:50:00+08:00");
+
+        // Once the day's lessons are over, tomorrow's come next
+        let next = next_lessons(lessons.clone(), at("2024-05-06", "15:30"), 5);
+        assert_eq!(next.len(), 2);
+        assert_eq!(next[0].subject, "Mathematics");
+        // No subject title falls back to the code
+        assert_eq!(next[1].subject, "10HIS");
+
+        assert!(next_lessons(lessons, at("2024-05-07", "12:00"), 5).is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::netgrab;
+
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const REVERSE_GEOCODING_URL: &str = "https://api.bigdatacloud.net/data/reverse-geocode-client";
+
+/// How long a fetched forecast is served before hitting the provider again.
+const WEATHER_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Cached forecasts keyed by location (see `cache_key`).
+static WEATHER_CACHE: Mutex<Option<HashMap<String, (Instant, WeatherData)>>> = Mutex::new(None);
+
+/// Place names for coordinates, keyed like the forecast cache. Places don't move, so entries
+/// never expire; only successful lookups are stored.
+static PLACE_CACHE: Mutex<Option<HashMap<String, PlaceName>>> = Mutex::new(None);
+
+/// Separate from netgrab's SEQTA client so session headers/cookies never go to third parties.
+static WEATHER_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastDay {
+    pub date: String,
+    pub temp_max: f64,
+    pub temp_min: f64,
+    pub weathercode: u32,
+}
+
+/// Current conditions plus a daily forecast. Field names match the frontend `WeatherData` type.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WeatherData {
+    pub temperature: f64,
+    pub weathercode: u32,
+    pub condition: String,
+    pub icon: String,
+    pub location: String,
+    pub country: String,
+    pub forecast: Vec<ForecastDay>,
+    /// RFC 3339 time the data was fetched from the provider
+    pub fetched_at: String,
+    /// True when the provider was unreachable and an expired cache entry was returned
+    #[serde(default)]
+    pub stale: bool,
+}
+
+/// Display label for a pair of coordinates.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlaceName {
+    pub city: String,
+    pub country: String,
+    /// True when the lookup failed and `city` holds the coordinates instead
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+fn weather_client() -> Result<&'static reqwest::Client, String> {
+    if let Some(client) = WEATHER_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = netgrab::create_client_builder()
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    Ok(WEATHER_CLIENT.get_or_init(|| client))
+}
+
+/// Human-readable condition and icon for a WMO weather code.
+fn describe_weathercode(code: u32) -> (&'static str, &'static str) {
+    match code {
+        0 => ("Clear", "☀️"),
+        1 | 2 => ("Partly cloudy", "⛅"),
+        3 => ("Overcast", "☁️"),
+        45..=48 => ("Fog", "🌫️"),
+        51..=57 => ("Drizzle", "🌧️"),
+        61..=67 => ("Rain", "🌧️"),
+        71..=77 => ("Snow", "🌨️"),
+        80..=82 => ("Rain showers", "⛈️"),
+        85 | 86 => ("Snow showers", "🌨️"),
+        95..=99 => ("Thunderstorm", "⛈️"),
+        _ => ("Cloudy", "☁️"),
+    }
+}
+
+fn cache_key(city: &str, country: &str, coords: Option<(f64, f64)>) -> String {
+    match coords {
+        // ~1km precision is plenty for a forecast and keeps GPS jitter from missing the cache
+        Some((lat, lon)) => format!("coords:{:.2},{:.2}", lat, lon),
+        None => format!(
+            "city:{},{}",
+            city.trim().to_lowercase(),
+            country.trim().to_lowercase()
+        ),
+    }
+}
+
+async fn get_json(url: &str, query: &[(&str, String)]) -> Result<Value, String> {
+    let response = weather_client()?
+        .get(url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| format!("Weather request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Weather request failed with status {}", response.status()));
+    }
+    response
+        .json::<Value>()
+        .await
+        .map_err(|e| format!("Failed to parse weather response: {}", e))
+}
+
+/// Resolve a city to (latitude, longitude, name, country).
+async fn geocode_city(city: &str, country: &str) -> Result<(f64, f64, String, String), String> {
+    let json = get_json(
+        GEOCODING_URL,
+        &[
+            ("name", city.to_string()),
+            ("countryCode", country.to_string()),
+            ("count", "10".to_string()),
+            ("language", "en".to_string()),
+            ("format", "json".to_string()),
+        ],
+    )
+    .await?;
+
+    let first = json
+        .get("results")
+        .and_then(|r| r.as_array())
+        .and_then(|r| r.first())
+        .ok_or_else(|| format!("Location not found: {}", city))?;
+
+    let lat = first.get("latitude").and_then(|v| v.as_f64());
+    let lon = first.get("longitude").and_then(|v| v.as_f64());
+    let (Some(lat), Some(lon)) = (lat, lon) else {
+        return Err(format!("Location not found: {}", city));
+    };
+    let name = first
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(city)
+        .to_string();
+    let country = first
+        .get("country")
+        .and_then(|v| v.as_str())
+        .unwrap_or(country)
+        .to_string();
+    Ok((lat, lon, name, country))
+}
+
+fn validate_coords(lat: f64, lon: f64) -> Result<(), String> {
+    if !lat.is_finite() || !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("Latitude must be between -90 and 90, got {}", lat));
+    }
+    if !lon.is_finite() || !(-180.0..=180.0).contains(&lon) {
+        return Err(format!(
+            "Longitude must be between -180 and 180, got {}",
+            lon
+        ));
+    }
+    Ok(())
+}
+
+/// Label used when the coordinates can't be named.
+fn coords_place_name(lat: f64, lon: f64) -> PlaceName {
+    PlaceName {
+        city: format!("{:.2}, {:.2}", lat, lon),
+        country: String::new(),
+        fallback: true,
+    }
+}
+
+/// City and country from a reverse-geocoding response. Falls back from the city to the
+/// locality and then the state, since rural points often have no city.
+fn parse_place_name(json: &Value) -> Option<PlaceName> {
+    let field = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+    let city = field("city")
+        .or_else(|| field("locality"))
+        .or_else(|| field("principalSubdivision"))?;
+    Some(PlaceName {
+        city: city.to_string(),
+        country: field("countryName").unwrap_or_default().to_string(),
+        fallback: false,
+    })
+}
+
+async fn lookup_place_name(lat: f64, lon: f64) -> Result<PlaceName, String> {
+    netgrab::ensure_online()?;
+    let json = get_json(
+        REVERSE_GEOCODING_URL,
+        &[
+            ("latitude", lat.to_string()),
+            ("longitude", lon.to_string()),
+            ("localityLanguage", "en".to_string()),
+        ],
+    )
+    .await?;
+    parse_place_name(&json).ok_or_else(|| "No place found for coordinates".to_string())
+}
+
+/// Name the place at `lat`/`lon`, from the cache when possible. Never fails for valid
+/// coordinates: if the lookup fails the coordinates themselves are returned as the label.
+async fn place_name(lat: f64, lon: f64) -> PlaceName {
+    let key = cache_key("", "", Some((lat, lon)));
+    let cached = PLACE_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref().and_then(|c| c.get(&key).cloned()));
+    if let Some(place) = cached {
+        return place;
+    }
+
+    match lookup_place_name(lat, lon).await {
+        Ok(place) => {
+            if let Ok(mut cache) = PLACE_CACHE.lock() {
+                cache
+                    .get_or_insert_with(HashMap::new)
+                    .insert(key, place.clone());
+            }
+            place
+        }
+        Err(e) => {
+            println!("[DesQTA] Reverse geocoding failed: {}", e);
+            coords_place_name(lat, lon)
+        }
+    }
+}
+
+/// City and country for a pair of coordinates, so the weather widget can label the user's
+/// location. Falls back to the coordinates as the label if the lookup fails.
+#[tauri::command]
+pub async fn reverse_geocode(lat: f64, lon: f64) -> Result<PlaceName, String> {
+    validate_coords(lat, lon)?;
+    Ok(place_name(lat, lon).await)
+}
+
+fn parse_forecast(json: &Value) -> Vec<ForecastDay> {
+    let daily = &json["daily"];
+    let Some(dates) = daily["time"].as_array() else {
+        return Vec::new();
+    };
+    dates
+        .iter()
+        .enumerate()
+        .map(|(i, date)| ForecastDay {
+            date: date.as_str().unwrap_or_default().to_string(),
+            temp_max: daily["temperature_2m_max"][i].as_f64().unwrap_or(0.0),
+            temp_min: daily["temperature_2m_min"][i].as_f64().unwrap_or(0.0),
+            weathercode: daily["weathercode"][i].as_u64().unwrap_or(0) as u32,
+        })
+        .collect()
+}
+
+async fn fetch_forecast(
+    lat: f64,
+    lon: f64,
+    location: String,
+    country: String,
+) -> Result<WeatherData, String> {
+    let json = get_json(
+        FORECAST_URL,
+        &[
+            ("latitude", lat.to_string()),
+            ("longitude", lon.to_string()),
+            ("current_weather", "true".to_string()),
+            (
+                "daily",
+                "temperature_2m_max,temperature_2m_min,weathercode".to_string(),
+            ),
+            ("timezone", "auto".to_string()),
+        ],
+    )
+    .await?;
+
+    let current = &json["current_weather"];
+    let temperature = current["temperature"]
+        .as_f64()
+        .ok_or_else(|| "Weather response missing current temperature".to_string())?;
+    let weathercode = current["weathercode"].as_u64().unwrap_or(0) as u32;
+    let (condition, icon) = describe_weathercode(weathercode);
+
+    Ok(WeatherData {
+        temperature,
+        weathercode,
+        condition: condition.to_string(),
+        icon: icon.to_string(),
+        location,
+        country,
+        forecast: parse_forecast(&json),
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+        stale: false,
+    })
+}
+
+/// Look up `key` in the cache. Returns the entry and whether it is still fresh.
+fn cached_weather(key: &str) -> Option<(WeatherData, bool)> {
+    let cache = WEATHER_CACHE.lock().ok()?;
+    let (fetched, data) = cache.as_ref()?.get(key)?;
+    Some((data.clone(), fetched.elapsed() < WEATHER_CACHE_TTL))
+}
+
+fn store_weather(key: String, data: &WeatherData) {
+    if let Ok(mut cache) = WEATHER_CACHE.lock() {
+        cache
+            .get_or_insert_with(HashMap::new)
+            .insert(key, (Instant::now(), data.clone()));
+    }
+}
+
+/// Number of cached forecasts and place names.
+pub fn cache_len() -> usize {
+    let forecasts = WEATHER_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref().map(|cache| cache.len()))
+        .unwrap_or(0);
+    let places = PLACE_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref().map(|cache| cache.len()))
+        .unwrap_or(0);
+    forecasts + places
+}
+
+/// Drop all cached forecasts and place names. Returns the number of entries removed.
+pub fn clear_cache() -> usize {
+    let forecasts = WEATHER_CACHE
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.take())
+        .map_or(0, |cache| cache.len());
+    let places = PLACE_CACHE
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.take())
+        .map_or(0, |cache| cache.len());
+    forecasts + places
+}
+
+/// Fetch current weather and a daily forecast from Open-Meteo.
+///
+/// Coordinates (sent when `force_use_location` is on) take precedence, with `city`/`country`
+/// then only used as the display name (reverse geocoded when not given); otherwise
+/// `city`/`country` are geocoded. Results are
+/// cached for 15 minutes per location, and an expired entry is returned with `stale: true`
+/// if the provider can't be reached or offline mode is on.
+#[tauri::command]
+pub async fn get_weather(
+    city: Option<String>,
+    country: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+) -> Result<WeatherData, String> {
+    let city = city.unwrap_or_default();
+    let country = country.unwrap_or_default();
+    let coords = latitude.zip(longitude);
+    if coords.is_none() && city.trim().is_empty() {
+        return Err("No city or coordinates provided".to_string());
+    }
+    if let Some((lat, lon)) = coords {
+        validate_coords(lat, lon)?;
+    }
+
+    let key = cache_key(&city, &country, coords);
+    let cached = cached_weather(&key);
+    if let Some((data, true)) = &cached {
+        return Ok(data.clone());
+    }
+
+    let result = if netgrab::is_offline_mode() {
+        Err(netgrab::NetError::Offline.to_string())
+    } else {
+        match coords {
+            Some((lat, lon)) if city.trim().is_empty() => {
+                let place = place_name(lat, lon).await;
+                fetch_forecast(lat, lon, place.city, place.country).await
+            }
+            Some((lat, lon)) => fetch_forecast(lat, lon, city.clone(), country.clone()).await,
+            None => match geocode_city(city.trim(), country.trim()).await {
+                Ok((lat, lon, name, country)) => fetch_forecast(lat, lon, name, country).await,
+                Err(e) => Err(e),
+            },
+        }
+    };
+
+    match result {
+        Ok(data) => {
+            store_weather(key, &data);
+            Ok(data)
+        }
+        Err(e) => match cached {
+            Some((mut data, _)) => {
+                println!("[DesQTA] Weather fetch failed, serving cached data: {}", e);
+                data.stale = true;
+                Ok(data)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_normalises_location() {
+        assert_eq!(
+            cache_key(" Perth ", "AU", None),
+            cache_key("perth", "au", None)
+        );
+        assert_eq!(
+            cache_key("", "", Some((-31.95224, 115.8614))),
+            "coords:-31.95,115.86"
+        );
+    }
+
+    #[test]
+    fn test_validate_coords() {
+        assert!(validate_coords(-31.95, 115.86).is_ok());
+        assert!(validate_coords(90.0, -180.0).is_ok());
+        assert!(validate_coords(90.5, 0.0).is_err());
+        assert!(validate_coords(0.0, 181.0).is_err());
+        assert!(validate_coords(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_parse_place_name() {
+        let json = serde_json::json!({
+            "city": "",
+            "locality": "Kings Park",
+            "principalSubdivision": "Western Australia",
+            "countryName": "Australia"
+        });
+        assert_eq!(
+            parse_place_name(&json),
+            Some(PlaceName {
+                city: "Kings Park".to_string(),
+                country: "Australia".to_string(),
+                fallback: false,
+            })
+        );
+        assert_eq!(
+            parse_place_name(&serde_json::json!({ "countryName": "" })),
+            None
+        );
+
+        let fallback = coords_place_name(-31.95224, 115.8614);
+        assert_eq!(fallback.city, "-31.95, 115.86");
+        assert!(fallback.fallback);
+    }
+
+    #[test]
+    fn test_parse_forecast() {
+        let json = serde_json::json!({
+            "daily": {
+                "time": ["2024-05-01", "2024-05-02"],
+                "temperature_2m_max": [22.5, 19.0],
+                "temperature_2m_min": [12.0, 10.5],
+                "weathercode": [0, 61]
+            }
+        });
+        let forecast = parse_forecast(&json);
+        assert_eq!(forecast.len(), 2);
+        assert_eq!(forecast[1].weathercode, 61);
+        assert_eq!(forecast[1].temp_min, 10.5);
+        assert_eq!(describe_weathercode(61).0, "Rain");
+    }
+}
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tauri::{PhysicalPosition, PhysicalSize, Runtime, WebviewWindow};
+
+use crate::profiles;
+
+/// Delay after the last move/resize before the geometry is written to disk.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Minimum visible strip (physical px) of the window's top edge that must land on a monitor.
+const MIN_VISIBLE_WIDTH: i64 = 100;
+const MIN_VISIBLE_HEIGHT: i64 = 40;
+
+/// Bumped on every move/resize; a pending save only runs if it is still the latest.
+static SAVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Main window geometry in physical pixels.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// Location: `$DATA_DIR/DesQTA/window_state.json` (shared across profiles)
+fn window_state_file() -> PathBuf {
+    let mut dir = profiles::get_base_data_dir();
+    dir.push("window_state.json");
+    dir
+}
+
+impl WindowState {
+    /// Load the saved geometry, if any.
+    pub fn load() -> Option<Self> {
+        let contents = fs::read_to_string(window_state_file()).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Persist to disk.
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(window_state_file(), json).map_err(|e| e.to_string())
+    }
+
+    /// True if enough of the window's top edge lies on one of `monitors` (x, y, width, height)
+    /// for the user to grab it.
+    fn is_visible_on(&self, monitors: &[(i32, i32, u32, u32)]) -> bool {
+        if self.width == 0 || self.height == 0 {
+            return false;
+        }
+        let (left, top) = (self.x as i64, self.y as i64);
+        let right = left + self.width as i64;
+        let title_bottom = top + MIN_VISIBLE_HEIGHT.min(self.height as i64);
+
+        monitors.iter().any(|&(mx, my, mw, mh)| {
+            let (m_left, m_top) = (mx as i64, my as i64);
+            let (m_right, m_bottom) = (m_left + mw as i64, m_top + mh as i64);
+            let overlap_w = right.min(m_right) - left.max(m_left);
+            let overlap_h = title_bottom.min(m_bottom) - top.max(m_top);
+            overlap_w >= MIN_VISIBLE_WIDTH.min(self.width as i64) && overlap_h > 0
+        })
+    }
+}
+
+/// Read the window's current geometry. While maximized the last normal geometry is kept
+/// so un-maximizing after a restart returns to where the user had it.
+fn capture<R: Runtime>(window: &WebviewWindow<R>) -> Option<WindowState> {
+    // Avoid is_maximized() on macOS (plugins-workspace#1918)
+    #[cfg(target_os = "macos")]
+    let maximized = false;
+    #[cfg(not(target_os = "macos"))]
+    let maximized = window.is_maximized().unwrap_or(false);
+
+    if window.is_minimized().unwrap_or(false) || window.is_fullscreen().unwrap_or(false) {
+        return None;
+    }
+
+    if maximized {
+        let mut state = WindowState::load()?;
+        state.maximized = true;
+        return Some(state);
+    }
+
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: false,
+    })
+}
+
+/// Apply the saved geometry to `window`. Returns false (leaving the window untouched) when
+/// nothing is saved or the saved position is no longer on any connected monitor.
+pub fn restore<R: Runtime>(window: &WebviewWindow<R>) -> bool {
+    let Some(state) = WindowState::load() else {
+        return false;
+    };
+
+    let monitors: Vec<(i32, i32, u32, u32)> = window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let pos = m.position();
+            let size = m.size();
+            (pos.x, pos.y, size.width, size.height)
+        })
+        .collect();
+
+    if !state.is_visible_on(&monitors) {
+        println!("[DesQTA] Saved window position is off-screen, using defaults");
+        return false;
+    }
+
+    let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+    let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+    if state.maximized {
+        let _ = window.maximize();
+    }
+    true
+}
+
+/// Save the window geometry once moves/resizes have settled for `SAVE_DEBOUNCE`.
+pub fn schedule_save<R: Runtime>(window: &WebviewWindow<R>) {
+    let generation = SAVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+        if SAVE_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Some(state) = capture(&window) {
+            if let Err(e) = state.save() {
+                eprintln!("[DesQTA] Failed to save window state: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: i32, y: i32) -> WindowState {
+        WindowState {
+            x,
+            y,
+            width: 900,
+            height: 700,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn test_visible_on_primary_monitor() {
+        let monitors = [(0, 0, 1920, 1080)];
+        assert!(state(100, 100).is_visible_on(&monitors));
+        assert!(state(1800, 50).is_visible_on(&monitors));
+    }
+
+    #[test]
+    fn test_rejects_disconnected_display() {
+        let monitors = [(0, 0, 1920, 1080)];
+        assert!(!state(2500, 100).is_visible_on(&monitors));
+        assert!(!state(100, -800).is_visible_on(&monitors));
+        assert!(!state(100, 100).is_visible_on(&[]));
+    }
+
+    #[test]
+    fn test_secondary_monitor_with_negative_origin() {
+        let monitors = [(0, 0, 1920, 1080), (-1280, 0, 1280, 1024)];
+        assert!(state(-1000, 200).is_visible_on(&monitors));
+    }
+}
@@ -660,8 +660,8 @@ export const seqtaMentionSuggestion = {
                 categoryFilter || undefined,
               );
 
-          // Check if aborted after request completes
-          if (abortController.signal.aborted) {
+          // Check if aborted (or superseded by a newer search) after request completes
+          if (abortController.signal.aborted || results === null) {
             resolve([]);
             return;
           }
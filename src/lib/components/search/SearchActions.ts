@@ -41,21 +41,15 @@ export async function handleAction(item: SearchItem) {
     case 'action-close':
       await invoke('quit').catch(console.warn);
       break;
-    case 'action-zoom-in': {
-      const level = zoomIn();
-      invoke('save_settings_merge', { patch: { zoom_level: level } }).catch(console.warn);
+    case 'action-zoom-in':
+      await zoomIn().catch(console.warn);
       break;
-    }
-    case 'action-zoom-out': {
-      const level = zoomOut();
-      invoke('save_settings_merge', { patch: { zoom_level: level } }).catch(console.warn);
+    case 'action-zoom-out':
+      await zoomOut().catch(console.warn);
       break;
-    }
-    case 'action-zoom-reset': {
-      zoomReset();
-      invoke('save_settings_merge', { patch: { zoom_level: 1 } }).catch(console.warn);
+    case 'action-zoom-reset':
+      await zoomReset().catch(console.warn);
       break;
-    }
     case 'action-sidebar-toggle':
       // This should be handled by the parent component
       break;
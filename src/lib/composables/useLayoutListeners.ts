@@ -5,18 +5,20 @@ import { logger } from '../../utils/logger';
 export interface LayoutListenersOptions {
   appWindow: ReturnType<typeof Window.getCurrent>;
   onFullscreenChange: (isFullscreen: boolean) => void;
+  /** A system-wide hotkey fired; the backend has already brought the window up. */
+  onGlobalShortcut: (actionId: string) => void;
 }
 
 const isMacOS = import.meta.env.TAURI_ENV_PLATFORM === 'darwin' || import.meta.env.TAURI_ENV_PLATFORM === 'macos';
 
 /**
- * Set up layout event listeners (reload, fullscreen, zoom).
+ * Set up layout event listeners (reload, fullscreen, global shortcuts).
  * On macOS, we avoid calling isMaximized() in resize/move handlers - it causes
  * infinite resize loop and 100% CPU hang (tauri-apps/plugins-workspace#1918).
  * Returns cleanup function to call on destroy.
  */
 export async function useLayoutListeners(options: LayoutListenersOptions): Promise<() => void> {
-  const { appWindow, onFullscreenChange } = options;
+  const { appWindow, onFullscreenChange, onGlobalShortcut } = options;
 
   const unlistenReload = await listen<string>('reload', () => {
     logger.info('layout', 'reload_listener', 'Received reload event');
@@ -28,11 +30,9 @@ export async function useLayoutListeners(options: LayoutListenersOptions): Promi
     logger.debug('layout', 'fullscreen_listener', `Window state changed: ${event.payload}`);
   });
 
-  const unlistenZoom = await listen<string>('zoom', async (event) => {
-    const { zoomIn, zoomOut, zoomReset } = await import('$lib/utils/zoom');
-    if (event.payload === 'in') zoomIn();
-    else if (event.payload === 'out') zoomOut();
-    else if (event.payload === 'reset') zoomReset();
+  const unlistenGlobalShortcut = await listen<{ action_id: string }>('global-shortcut', (event) => {
+    logger.debug('layout', 'global_shortcut_listener', `Shortcut fired: ${event.payload.action_id}`);
+    onGlobalShortcut(event.payload.action_id);
   });
 
   const checkFullscreenState = async () => {
@@ -68,7 +68,7 @@ export async function useLayoutListeners(options: LayoutListenersOptions): Promi
     logger.debug('layout', 'onDestroy', 'Cleaning up layout listeners');
     unlistenReload();
     unlistenFullscreenChanged();
-    unlistenZoom();
+    unlistenGlobalShortcut();
     unlistenResized?.();
     unlistenMoved?.();
   };
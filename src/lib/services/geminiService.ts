@@ -34,8 +34,6 @@ export interface LessonSummary {
   steps: string[];
 }
 
-const GEMINI_API_URL =
-  'https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash-lite:generateContent';
 const CEREBRAS_API_URL = 'https://api.cerebras.ai/v1/chat/completions';
 /** See https://inference-docs.cerebras.ai/models/overview — old ids (e.g. llama-3.3-70b) return 404. */
 const CEREBRAS_MODEL = 'gpt-oss-120b';
@@ -52,23 +50,38 @@ export class GeminiService {
     }
   }
 
-  static async getApiKey(provider?: AIProvider): Promise<string | null> {
+  /** Whether `provider` has a key set. The Gemini key stays in the backend's keychain. */
+  static async hasApiKey(provider: AIProvider): Promise<boolean> {
+    if (provider === 'cerebras') {
+      return !!(await this.getCerebrasApiKey());
+    }
+    try {
+      return await invoke<boolean>('has_gemini_api_key');
+    } catch {
+      return false;
+    }
+  }
+
+  static async getCerebrasApiKey(): Promise<string | null> {
     try {
-      const currentProvider = provider || (await this.getProvider());
-      const keys = currentProvider === 'cerebras' ? ['cerebras_api_key'] : ['gemini_api_key'];
-      const subset = await invoke<any>('get_settings_subset', { keys });
-      return currentProvider === 'cerebras'
-        ? subset?.cerebras_api_key || null
-        : subset?.gemini_api_key || null;
+      const subset = await invoke<any>('get_settings_subset', { keys: ['cerebras_api_key'] });
+      return subset?.cerebras_api_key || null;
     } catch {
       return null;
     }
   }
 
+  /** Gemini answer text, requested by the backend so the key never reaches the webview. */
+  private static async geminiGenerate(prompt: string, maxOutputTokens: number): Promise<string> {
+    return invoke<string>('ai_generate', {
+      prompt,
+      options: { temperature: 0.3, max_output_tokens: maxOutputTokens },
+    });
+  }
+
   static async predictGrades(assessments: AssessmentData[]): Promise<GradePrediction[]> {
     const provider = await this.getProvider();
-    const apiKey = await this.getApiKey(provider);
-    if (!apiKey) {
+    if (!(await this.hasApiKey(provider))) {
       const providerName = provider === 'cerebras' ? 'Cerebras' : 'Gemini';
       throw new Error(`No ${providerName} API key set. Please add your API key in Settings.`);
     }
@@ -108,7 +121,7 @@ export class GeminiService {
 
         const prompt = this.buildPredictionPrompt(subject, assessmentData);
 
-        const prediction = await this.callAIAPI(prompt, apiKey, provider);
+        const prediction = await this.callAIAPI(prompt, provider);
         if (prediction) {
           predictions.push(prediction);
         }
@@ -155,56 +168,18 @@ Be realistic and consider that the prediction should be based on demonstrated pe
 
   private static async callAIAPI(
     prompt: string,
-    apiKey: string,
     provider: AIProvider,
   ): Promise<GradePrediction | null> {
     if (provider === 'cerebras') {
-      return this.callCerebrasAPI(prompt, apiKey);
+      return this.callCerebrasAPI(prompt);
     } else {
-      return this.callGeminiAPI(prompt, apiKey);
+      return this.callGeminiAPI(prompt);
     }
   }
 
-  private static async callGeminiAPI(
-    prompt: string,
-    apiKey: string,
-  ): Promise<GradePrediction | null> {
+  private static async callGeminiAPI(prompt: string): Promise<GradePrediction | null> {
     try {
-      const response = await fetch(`${GEMINI_API_URL}?key=${apiKey}`, {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-        },
-        body: JSON.stringify({
-          contents: [
-            {
-              parts: [
-                {
-                  text: prompt,
-                },
-              ],
-            },
-          ],
-          generationConfig: {
-            temperature: 0.3,
-            topK: 40,
-            topP: 0.95,
-            maxOutputTokens: 1024,
-          },
-        }),
-      });
-
-      if (!response.ok) {
-        throw new Error(`Gemini API error: ${response.status}`);
-      }
-
-      const data = await response.json();
-
-      if (!data.candidates || !data.candidates[0] || !data.candidates[0].content) {
-        throw new Error('Invalid response from Gemini API');
-      }
-
-      const responseText = data.candidates[0].content.parts[0].text;
+      const responseText = await this.geminiGenerate(prompt, 1024);
 
       // Try to extract JSON from the response
       const jsonMatch = responseText.match(/\{[\s\S]*\}/);
@@ -231,11 +206,9 @@ Be realistic and consider that the prediction should be based on demonstrated pe
     }
   }
 
-  private static async callCerebrasAPI(
-    prompt: string,
-    apiKey: string,
-  ): Promise<GradePrediction | null> {
+  private static async callCerebrasAPI(prompt: string): Promise<GradePrediction | null> {
     try {
+      const apiKey = await this.getCerebrasApiKey();
       const response = await fetch(CEREBRAS_API_URL, {
         method: 'POST',
         headers: {
@@ -301,8 +274,7 @@ Be realistic and consider that the prediction should be based on demonstrated pe
     attachments: { name: string }[];
   }): Promise<LessonSummary | null> {
     const provider = await this.getProvider();
-    const apiKey = await this.getApiKey(provider);
-    if (!apiKey) {
+    if (!(await this.hasApiKey(provider))) {
       const providerName = provider === 'cerebras' ? 'Cerebras' : 'Gemini';
       throw new Error(`No ${providerName} API key set. Please add your API key in Settings.`);
     }
@@ -335,6 +307,7 @@ Respond ONLY in this JSON format (no markdown, no code blocks):
 }`;
     try {
       if (provider === 'cerebras') {
+        const apiKey = await this.getCerebrasApiKey();
         const response = await fetch(CEREBRAS_API_URL, {
           method: 'POST',
           headers: {
@@ -362,24 +335,7 @@ Respond ONLY in this JSON format (no markdown, no code blocks):
           throw new Error('Invalid summary format');
         return summaryObj as LessonSummary;
       } else {
-        const response = await fetch(`${GEMINI_API_URL}?key=${apiKey}`, {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/json' },
-          body: JSON.stringify({
-            contents: [{ parts: [{ text: prompt }] }],
-            generationConfig: {
-              temperature: 0.3,
-              topK: 40,
-              topP: 0.95,
-              maxOutputTokens: 1024,
-            },
-          }),
-        });
-        if (!response.ok) throw new Error(`Gemini API error: ${response.status}`);
-        const data = await response.json();
-        if (!data.candidates || !data.candidates[0] || !data.candidates[0].content)
-          throw new Error('Invalid response from Gemini API');
-        const responseText = data.candidates[0].content.parts[0].text;
+        const responseText = await this.geminiGenerate(prompt, 1024);
         const jsonMatch = responseText.match(/\{[\s\S]*\}/);
         if (!jsonMatch) throw new Error('No JSON found in response');
         const summaryObj = JSON.parse(jsonMatch[0]);
@@ -404,14 +360,14 @@ Respond ONLY in this JSON format (no markdown, no code blocks):
     options?: { maxTokens?: number },
   ): Promise<T | null> {
     const provider = await this.getProvider();
-    const apiKey = await this.getApiKey(provider);
-    if (!apiKey) {
+    if (!(await this.hasApiKey(provider))) {
       const providerName = provider === 'cerebras' ? 'Cerebras' : 'Gemini';
       throw new Error(`No ${providerName} API key set. Please add your API key in Settings.`);
     }
     const maxTokens = options?.maxTokens ?? 2048;
     try {
       if (provider === 'cerebras') {
+        const apiKey = await this.getCerebrasApiKey();
         const response = await fetch(CEREBRAS_API_URL, {
           method: 'POST',
           headers: {
@@ -436,24 +392,7 @@ Respond ONLY in this JSON format (no markdown, no code blocks):
         if (!jsonMatch) throw new Error('No JSON found in response');
         return JSON.parse(jsonMatch[0]) as T;
       } else {
-        const response = await fetch(`${GEMINI_API_URL}?key=${apiKey}`, {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/json' },
-          body: JSON.stringify({
-            contents: [{ parts: [{ text: prompt }] }],
-            generationConfig: {
-              temperature: 0.3,
-              topK: 40,
-              topP: 0.95,
-              maxOutputTokens: maxTokens,
-            },
-          }),
-        });
-        if (!response.ok) throw new Error(`Gemini API error: ${response.status}`);
-        const data = await response.json();
-        if (!data.candidates?.[0]?.content?.parts?.[0]?.text)
-          throw new Error('Invalid response from Gemini API');
-        const text = data.candidates[0].content.parts[0].text;
+        const text = await this.geminiGenerate(prompt, maxTokens);
         const jsonMatch = text.match(/\{[\s\S]*\}/);
         if (!jsonMatch) throw new Error('No JSON found in response');
         return JSON.parse(jsonMatch[0]) as T;
@@ -11,7 +11,16 @@ import { logger } from '../../utils/logger';
 import type { Assessment } from '../types';
 import { invokeGetProcessedAssessments } from './processedAssessmentsInvoke';
 
-export type NotificationType = 'reminder_3days' | 'reminder_1day' | 'due_date' | 'overdue';
+/**
+ * `reminder_1day` is no longer scheduled (the backend's lead-time reminders cover it) but may
+ * still be stored. `lead_<seconds>s` reminders are scheduled by the backend.
+ */
+export type NotificationType =
+  | 'reminder_3days'
+  | 'reminder_1day'
+  | 'due_date'
+  | 'overdue'
+  | `lead_${number}s`;
 
 interface Notification {
   id: number;
@@ -71,8 +80,8 @@ class NotificationService {
     const now = nowSeconds;
 
     // Calculate reminder times
+    // Shorter reminders come from the backend's configurable lead times
     const reminder3Days = dueTimestamp - 3 * 24 * 60 * 60; // 3 days before
-    const reminder1Day = dueTimestamp - 1 * 24 * 60 * 60; // 1 day before
     const dueDateTimestamp = dueTimestamp;
 
     // Check if overdue (only within last 7 days - ignore assessments overdue by more than 7 days)
@@ -86,10 +95,6 @@ class NotificationService {
       await this.scheduleNotification(assessment.id, 'reminder_3days', reminder3Days);
     }
 
-    if (reminder1Day > now) {
-      await this.scheduleNotification(assessment.id, 'reminder_1day', reminder1Day);
-    }
-
     if (dueDateTimestamp > now) {
       await this.scheduleNotification(assessment.id, 'due_date', dueDateTimestamp);
     }
@@ -184,6 +189,12 @@ class NotificationService {
         const notification = dueNotifications[i];
         const assessment = assessmentsMap.get(notification.assessment_id);
 
+        // Reminders from before lead times existed would repeat the backend's own 1-day reminder
+        if (notification.notification_type === 'reminder_1day') {
+          await invoke('db_notification_mark_sent', { notificationId: notification.id });
+          continue;
+        }
+
         // Skip overdue notifications for assessments due more than 7 days ago
         if (notification.notification_type === 'overdue') {
           if (!assessment) {
@@ -213,6 +224,7 @@ class NotificationService {
           await notify({
             title,
             body: `Assessment #${notification.assessment_id}`,
+            category: 'assessments',
           });
 
           // Mark as sent to avoid retrying
@@ -248,6 +260,7 @@ class NotificationService {
       await notify({
         title,
         body,
+        category: 'assessments',
         sound: notification.notification_type === 'overdue' ? 'default' : undefined,
       });
 
@@ -273,6 +286,10 @@ class NotificationService {
    * Get notification title based on type
    */
   private getNotificationTitle(type: NotificationType): string {
+    const lead = this.leadSeconds(type);
+    if (lead !== null) {
+      return `Assessment Due in ${this.formatLead(lead)}`;
+    }
     switch (type) {
       case 'reminder_3days':
         return 'Assessment Reminder';
@@ -294,6 +311,10 @@ class NotificationService {
     const title = assessment.title || 'Untitled Assessment';
     const subject = assessment.subject || 'Unknown Subject';
 
+    const lead = this.leadSeconds(type);
+    if (lead !== null) {
+      return `${title} (${subject}) is due in ${this.formatLead(lead).toLowerCase()}!`;
+    }
     switch (type) {
       case 'reminder_3days':
         return `${title} (${subject}) is due in 3 days!`;
@@ -308,6 +329,33 @@ class NotificationService {
     }
   }
 
+  /**
+   * Lead time of a backend reminder type such as `lead_3600s`, or null for other types
+   */
+  private leadSeconds(type: NotificationType): number | null {
+    const match = /^lead_(\d+)s$/.exec(type);
+    return match ? Number(match[1]) : null;
+  }
+
+  /**
+   * A lead time in its largest whole unit, e.g. "1 Day" or "30 Minutes"
+   */
+  private formatLead(seconds: number): string {
+    const units: [number, string][] = [
+      [24 * 60 * 60, 'Day'],
+      [60 * 60, 'Hour'],
+      [60, 'Minute'],
+    ];
+    for (const [size, name] of units) {
+      if (seconds >= size && seconds % size === 0) {
+        const count = seconds / size;
+        return `${count} ${name}${count === 1 ? '' : 's'}`;
+      }
+    }
+    const minutes = Math.max(1, Math.round(seconds / 60));
+    return `${minutes} Minute${minutes === 1 ? '' : 's'}`;
+  }
+
   /**
    * Start the background notification checker
    */
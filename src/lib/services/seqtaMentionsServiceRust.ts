@@ -25,20 +25,49 @@ export interface SeqtaMentionItem {
   lastUpdated?: string;
 }
 
+/**
+ * Mention search response. Sources that failed (e.g. 'notices') are listed in
+ * failedSources and missing from items, so the UI can warn about partial results.
+ * `cancelled` is set when a newer search on the same channel replaced this one.
+ */
+export interface SeqtaMentionSearchResults {
+  items: SeqtaMentionItem[];
+  failedSources: string[];
+  cancelled: boolean;
+}
+
+/**
+ * Searches only cancel earlier ones on the same channel, so e.g. the detail modal's lookups
+ * don't interrupt the editor's mention picker.
+ */
+export type MentionSearchChannel = 'editor' | 'detail';
+
+function searchItems(results: SeqtaMentionSearchResults): SeqtaMentionItem[] | null {
+  if (results.cancelled) {
+    return null;
+  }
+  if (results.failedSources.length > 0) {
+    console.warn('Some SEQTA mention sources failed:', results.failedSources.join(', '));
+  }
+  return results.items;
+}
+
 /**
  * Search for SEQTA elements that can be mentioned
- * Calls Rust backend via Tauri
+ * Calls Rust backend via Tauri. Returns null when a newer search on `channel` replaced it.
  */
 export async function searchMentions(
   query: string = '',
   categoryFilter?: string,
-): Promise<SeqtaMentionItem[]> {
+  channel: MentionSearchChannel = 'editor',
+): Promise<SeqtaMentionItem[] | null> {
   try {
-    const results = await invoke<SeqtaMentionItem[]>('search_seqta_mentions', {
+    const results = await invoke<SeqtaMentionSearchResults>('search_seqta_mentions', {
       query,
       categoryFilter: categoryFilter || null,
+      channel,
     });
-    return results;
+    return searchItems(results);
   } catch (error) {
     console.error('Error searching SEQTA mentions:', error);
     return [];
@@ -47,20 +76,22 @@ export async function searchMentions(
 
 /**
  * Search mentions with context (note content for better suggestions)
- * Calls Rust backend via Tauri
+ * Calls Rust backend via Tauri. Returns null when a newer search on `channel` replaced it.
  */
 export async function searchMentionsWithContext(
   query: string = '',
   noteContent: string = '',
   categoryFilter?: string,
-): Promise<SeqtaMentionItem[]> {
+  channel: MentionSearchChannel = 'editor',
+): Promise<SeqtaMentionItem[] | null> {
   try {
-    const results = await invoke<SeqtaMentionItem[]>('search_seqta_mentions_with_context', {
+    const results = await invoke<SeqtaMentionSearchResults>('search_seqta_mentions_with_context', {
       query,
       noteContent,
       categoryFilter: categoryFilter || null,
+      channel,
     });
-    return results;
+    return searchItems(results);
   } catch (error) {
     console.error('Error searching SEQTA mentions with context:', error);
     return [];
@@ -89,6 +120,34 @@ export async function updateMentionData(
   }
 }
 
+export interface MentionUpdateRequest {
+  mention_id: string;
+  mention_type: string;
+  meta?: any;
+}
+
+export interface MentionUpdateResult {
+  item: SeqtaMentionItem | null;
+  error: string | null;
+}
+
+/**
+ * Refresh several mentions in one call (resolved concurrently in Rust)
+ * Returns results keyed by mention id; failures are reported per mention
+ */
+export async function updateMentionsBatch(
+  requests: MentionUpdateRequest[],
+): Promise<Record<string, MentionUpdateResult>> {
+  try {
+    return await invoke<Record<string, MentionUpdateResult>>('update_mentions_batch', {
+      requests: requests.map((r) => ({ ...r, meta: r.meta || null })),
+    });
+  } catch (error) {
+    console.error('Error updating mentions batch:', error);
+    return {};
+  }
+}
+
 /**
  * Get weekly schedule for a class
  * Calls Rust backend via Tauri
@@ -143,24 +202,26 @@ export async function fetchLessonContent(
  */
 export class SeqtaMentionsServiceRust {
   /**
-   * Search for SEQTA elements that can be mentioned
+   * Search for SEQTA elements that can be mentioned; null if superseded on `channel`
    */
   static async searchMentions(
     query: string = '',
     categoryFilter?: string,
-  ): Promise<SeqtaMentionItem[]> {
-    return searchMentions(query, categoryFilter);
+    channel: MentionSearchChannel = 'editor',
+  ): Promise<SeqtaMentionItem[] | null> {
+    return searchMentions(query, categoryFilter, channel);
   }
 
   /**
-   * Search mentions with context
+   * Search mentions with context; null if superseded on `channel`
    */
   static async searchMentionsWithContext(
     query: string = '',
     noteContent: string = '',
     categoryFilter?: string,
-  ): Promise<SeqtaMentionItem[]> {
-    return searchMentionsWithContext(query, noteContent, categoryFilter);
+    channel: MentionSearchChannel = 'editor',
+  ): Promise<SeqtaMentionItem[] | null> {
+    return searchMentionsWithContext(query, noteContent, categoryFilter, channel);
   }
 
   /**
@@ -174,6 +235,15 @@ export class SeqtaMentionsServiceRust {
     return updateMentionData(mentionId, mentionType, meta);
   }
 
+  /**
+   * Refresh several mentions at once
+   */
+  static async updateMentionsBatch(
+    requests: MentionUpdateRequest[],
+  ): Promise<Record<string, MentionUpdateResult>> {
+    return updateMentionsBatch(requests);
+  }
+
   /**
    * Get weekly schedule for a class
    */
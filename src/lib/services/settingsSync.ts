@@ -22,7 +22,6 @@ export const CLOUD_SYNC_SUBSET_KEYS = [
   'current_theme',
   'disable_school_picture',
   'enhanced_animations',
-  'gemini_api_key',
   'ai_integrations_enabled',
   'grade_analyser_enabled',
   'lesson_summary_analyser_enabled',
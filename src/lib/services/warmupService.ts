@@ -398,7 +398,7 @@ async function prefetchAnalyticsSync(): Promise<void> {
     }
 
     // Only sync if no data exists and we have a valid session
-    await invoke<string>('sync_analytics_data');
+    await invoke('sync_analytics_data');
     logger.info('warmup', 'prefetchAnalyticsSync', 'Analytics data synced successfully');
 
     // Show success toast notification
@@ -9,31 +9,12 @@ export interface ForecastDay {
   weathercode: number;
 }
 
-function parseDailyForecast(json: {
-  daily?: { time?: string[]; temperature_2m_max?: number[]; temperature_2m_min?: number[]; weathercode?: number[] };
-}): ForecastDay[] {
-  const daily = json.daily;
-  if (!daily?.time?.length) return [];
-
-  const result: ForecastDay[] = [];
-  const maxes = daily.temperature_2m_max || [];
-  const mins = daily.temperature_2m_min || [];
-  const codes = daily.weathercode || [];
-
-  for (let i = 0; i < daily.time.length; i++) {
-    result.push({
-      date: daily.time[i],
-      tempMax: maxes[i] ?? 0,
-      tempMin: mins[i] ?? 0,
-      weathercode: codes[i] ?? 0,
-    });
-  }
-  return result;
-}
-
 export interface WeatherData {
   temperature: number;
   weathercode: number;
+  condition?: string;
+  icon?: string;
+  stale?: boolean;
   location: string;
   country: string;
   forecast?: ForecastDay[];
@@ -94,18 +75,12 @@ export const weatherService = {
         throw new Error('IP geolocation failed and no fallback location provided');
       }
 
-      const weatherRes = await fetch(
-        `https://api.open-meteo.com/v1/forecast?latitude=${latitude}&longitude=${longitude}&current_weather=true&daily=temperature_2m_max,temperature_2m_min,weathercode&timezone=auto`,
-      );
-      const weatherJson = await weatherRes.json();
-
-      const forecast = parseDailyForecast(weatherJson);
-      const weatherData: WeatherData = {
-        ...weatherJson.current_weather,
-        location: name,
+      const weatherData = await invoke<WeatherData>('get_weather', {
+        city: name,
         country,
-        forecast,
-      };
+        latitude,
+        longitude,
+      });
 
       cache.set('weather', weatherData, 15 * 60 * 1000);
       return weatherData;
@@ -126,27 +101,10 @@ export const weatherService = {
     }
 
     try {
-      const geoRes = await fetch(
-        `https://geocoding-api.open-meteo.com/v1/search?name=${encodeURIComponent(weatherCity)}&countryCode=${encodeURIComponent(weatherCountry)}&count=10&language=en&format=json`,
-      );
-      const geoJson = await geoRes.json();
-      if (!geoJson.results || !geoJson.results.length) {
-        throw new Error('Location not found');
-      }
-
-      const { latitude, longitude, name, country } = geoJson.results[0];
-      const weatherRes = await fetch(
-        `https://api.open-meteo.com/v1/forecast?latitude=${latitude}&longitude=${longitude}&current_weather=true&daily=temperature_2m_max,temperature_2m_min,weathercode&timezone=auto`,
-      );
-      const weatherJson = await weatherRes.json();
-
-      const forecast = parseDailyForecast(weatherJson);
-      const weatherData: WeatherData = {
-        ...weatherJson.current_weather,
-        location: name,
-        country,
-        forecast,
-      };
+      const weatherData = await invoke<WeatherData>('get_weather', {
+        city: weatherCity,
+        country: weatherCountry,
+      });
 
       cache.set('weather', weatherData, 15 * 60 * 1000);
       return weatherData;
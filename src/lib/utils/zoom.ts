@@ -1,48 +1,42 @@
-const ZOOM_MIN = 0.5;
-const ZOOM_MAX = 2;
-const ZOOM_STEP = 0.1;
-const ZOOM_STORAGE_KEY = 'desqta-zoom';
-
-export function getZoom(): number {
-  if (typeof document === 'undefined') return 1;
-  const stored = localStorage.getItem(ZOOM_STORAGE_KEY);
-  return stored ? parseFloat(stored) : 1;
+import { invoke } from '@tauri-apps/api/core';
+
+// The backend saves the level as `zoom_level` and applies it as every window's webview zoom
+// (0.5 to 3.0). Mobile webviews have no native zoom, so there the level is applied as CSS
+// zoom instead.
+
+const isNativeMobile =
+  import.meta.env.TAURI_ENV_PLATFORM === 'android' || import.meta.env.TAURI_ENV_PLATFORM === 'ios';
+
+function applyCssZoom(level: number): number {
+  if (isNativeMobile && typeof document !== 'undefined') {
+    document.documentElement.style.zoom = String(level);
+  }
+  return level;
 }
 
-export function setZoom(zoom: number): number {
-  if (typeof document === 'undefined') return 1;
-  const clamped = Math.min(ZOOM_MAX, Math.max(ZOOM_MIN, zoom));
-  document.documentElement.style.zoom = String(clamped);
-  localStorage.setItem(ZOOM_STORAGE_KEY, String(clamped));
-  return clamped;
+export function getZoom(): Promise<number> {
+  return invoke<number>('get_zoom');
 }
 
-export function zoomIn(): number {
-  return setZoom(getZoom() + ZOOM_STEP);
+export async function setZoom(level: number): Promise<number> {
+  return applyCssZoom(await invoke<number>('set_zoom', { level }));
 }
 
-export function zoomOut(): number {
-  return setZoom(getZoom() - ZOOM_STEP);
+export async function zoomIn(): Promise<number> {
+  return applyCssZoom(await invoke<number>('zoom_in'));
 }
 
-export function zoomReset(): number {
-  return setZoom(1);
+export async function zoomOut(): Promise<number> {
+  return applyCssZoom(await invoke<number>('zoom_out'));
 }
 
-export function restoreZoom(): void {
-  const stored = localStorage.getItem(ZOOM_STORAGE_KEY);
-  if (stored) {
-    const zoom = parseFloat(stored);
-    if (zoom >= ZOOM_MIN && zoom <= ZOOM_MAX) {
-      document.documentElement.style.zoom = stored;
-    }
-  }
+export async function zoomReset(): Promise<number> {
+  return applyCssZoom(await invoke<number>('zoom_reset'));
 }
 
-/** Restore zoom from settings (called by layout after loading from backend) */
-export function restoreZoomFromLevel(level: number): void {
-  if (typeof document === 'undefined') return;
-  const clamped = Math.min(ZOOM_MAX, Math.max(ZOOM_MIN, level));
-  document.documentElement.style.zoom = String(clamped);
-  localStorage.setItem(ZOOM_STORAGE_KEY, String(clamped));
+/** Apply the saved level on mobile, where the backend can't. Called by the layout on startup. */
+export async function restoreZoom(): Promise<void> {
+  if (isNativeMobile) {
+    applyCssZoom(await getZoom());
+  }
 }
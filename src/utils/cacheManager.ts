@@ -38,13 +38,20 @@ export class CacheManager {
   }
 
   /**
-   * Clear SQLite cache (replaces IndexedDB cache clearing)
+   * Clear backend caches: SQLite cache table, in-memory API caches and cached images
+   * (replaces IndexedDB cache clearing)
    */
   static async clearIndexedDBCaches(): Promise<void> {
     try {
-      // Clear SQLite cache via Rust backend
-      await invoke('db_cache_clear');
-      console.log('Cleared SQLite cache');
+      const summary = await invoke<{
+        caches: { name: string; entries: number }[];
+        total_entries: number;
+        errors: string[];
+      }>('clear_all_caches');
+      console.log(`Cleared ${summary.total_entries} backend cache entries`, summary.caches);
+      if (summary.errors.length > 0) {
+        console.warn('Some caches could not be cleared:', summary.errors);
+      }
 
       // Also cleanup expired entries
       await invoke('db_cache_cleanup_expired');
@@ -1,51 +1,55 @@
-import {
-  isPermissionGranted,
-  requestPermission,
-  sendNotification,
-  type Options as NotificationOptions,
-} from '@tauri-apps/plugin-notification';
-
-export interface UniversalNotificationOptions extends NotificationOptions {
-  /**
-   * Optionally override the notification channel (for Android).
-   */
-  channelId?: string;
+import { invoke } from '@tauri-apps/api/core';
+
+export type NotificationCategory = 'assessments' | 'messages' | 'notices' | 'reminders';
+
+/** What the backend did with a notification */
+export type NotifyOutcome = 'shown' | 'queued' | 'suppressed';
+
+export interface AppNotificationOptions {
+  title: string;
+  body: string;
+  /** Defaults to 'reminders' */
+  category?: NotificationCategory;
+  /** Sound to play, e.g. 'default'. Silent when unset */
+  sound?: string;
+}
+
+export interface QuietHours {
+  /** 24-hour HH:MM, local time */
+  start: string;
+  end: string;
+}
+
+export interface NotificationPrefs {
+  assessments: boolean;
+  messages: boolean;
+  notices: boolean;
+  reminders: boolean;
+  quiet_hours: QuietHours | null;
 }
 
 /**
- * Sends a native notification using Tauri, with customizable options and sensible defaults.
- * Automatically requests permission if needed.
+ * Sends a native notification through the backend, which skips categories the user turned
+ * off (reminders also need reminders enabled) and holds notifications during quiet hours.
  */
-export async function notify(options: Partial<UniversalNotificationOptions> = {}) {
-  // Sensible defaults
-  const defaultOptions: NotificationOptions = {
-    title: 'Notification',
-    body: 'You have a new notification.',
-    icon: undefined, // You can set a default icon path here if you want
-    sound: undefined,
-    silent: false,
-    // ...add more defaults as needed
-  };
-
-  // Merge user options with defaults
-  const merged: NotificationOptions = { ...defaultOptions, ...options };
-
-  // Check and request permission if needed
-  let permissionGranted = await isPermissionGranted();
-  if (!permissionGranted) {
-    const permission = await requestPermission();
-    permissionGranted = permission === 'granted';
+export async function notify(options: AppNotificationOptions): Promise<NotifyOutcome | null> {
+  try {
+    return await invoke<NotifyOutcome>('show_notification', {
+      title: options.title,
+      body: options.body,
+      category: options.category ?? 'reminders',
+      sound: options.sound ?? null,
+    });
+  } catch (error) {
+    console.warn('Failed to show notification:', error);
+    return null;
   }
+}
 
-  if (permissionGranted) {
-    // Send the notification (with channel if provided)
-    if (options.channelId) {
-      sendNotification({ ...merged, channelId: options.channelId });
-    } else {
-      sendNotification(merged);
-    }
-  } else {
-    // Optionally handle denied permission
-    console.warn('Notification permission not granted.');
-  }
+export function getNotificationPrefs(): Promise<NotificationPrefs> {
+  return invoke<NotificationPrefs>('get_notification_prefs');
+}
+
+export function setNotificationPrefs(prefs: NotificationPrefs): Promise<NotificationPrefs> {
+  return invoke<NotificationPrefs>('set_notification_prefs', { prefs });
 }